@@ -0,0 +1,13 @@
+CREATE INDEX account_license_code ON account (license_code);
+CREATE TABLE account (
+    user_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    user_key BLOB NOT NULL,
+    license_code TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE codo_account (
+    codo_id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL UNIQUE
+);
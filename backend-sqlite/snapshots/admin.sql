@@ -0,0 +1,12 @@
+CREATE TABLE ban (
+    user_id INTEGER PRIMARY KEY,
+    reason TEXT NOT NULL,
+    banned_at INTEGER NOT NULL
+);
+CREATE TABLE platform_ban (
+    platform INTEGER NOT NULL,
+    platform_user_id INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    banned_at INTEGER NOT NULL,
+    PRIMARY KEY (platform, platform_user_id)
+);
@@ -0,0 +1,15 @@
+CREATE INDEX challenge_failure_user ON challenge_failure (user_id);
+CREATE TABLE challenge_failure (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    challenge_id INTEGER NOT NULL,
+    failure TEXT NOT NULL,
+    failed_at INTEGER NOT NULL
+);
+CREATE TABLE flagged_user (
+    user_id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    failure_count INTEGER NOT NULL,
+    flagged_at INTEGER NOT NULL
+);
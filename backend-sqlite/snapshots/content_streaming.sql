@@ -0,0 +1,26 @@
+CREATE UNIQUE INDEX user_stream_title_owner_id_slot_unq ON user_stream (
+	title,
+	owner_id,
+	slot
+);
+CREATE TABLE user_info (
+    user_id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
+CREATE TABLE user_stream (
+    id INTEGER PRIMARY KEY,
+    filename TEXT NOT NULL,
+    title INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    metadata BLOB,
+    category INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    data BLOB
+);
+CREATE TABLE user_stream_tag (
+    stream_id INTEGER NOT NULL REFERENCES user_stream(id) ON DELETE CASCADE,
+    primary_tag INTEGER NOT NULL,
+    secondary_tag INTEGER NOT NULL
+);
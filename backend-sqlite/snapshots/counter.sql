@@ -0,0 +1,22 @@
+CREATE INDEX counter_snapshot_counter ON counter_snapshot (title, counter_id);
+CREATE TABLE counter (
+    title INTEGER NOT NULL,
+    counter_id INTEGER NOT NULL,
+    value INTEGER NOT NULL,
+    PRIMARY KEY (title, counter_id)
+);
+CREATE TABLE counter_snapshot (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    counter_id INTEGER NOT NULL,
+    window TEXT NOT NULL,
+    period_start INTEGER NOT NULL,
+    period_end INTEGER NOT NULL,
+    value INTEGER NOT NULL
+);
+CREATE TABLE counter_window (
+    title INTEGER NOT NULL,
+    window TEXT NOT NULL,
+    period_start INTEGER NOT NULL,
+    PRIMARY KEY (title, window)
+);
@@ -0,0 +1,10 @@
+CREATE INDEX event_title_user ON event (title, user_id);
+CREATE TABLE event (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    category INTEGER NOT NULL,
+    is_binary INTEGER NOT NULL,
+    payload BLOB NOT NULL
+);
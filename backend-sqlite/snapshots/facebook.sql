@@ -0,0 +1,8 @@
+CREATE TABLE facebook_account (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    access_token TEXT NOT NULL,
+    name TEXT NOT NULL,
+    linked_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id)
+);
@@ -0,0 +1,17 @@
+CREATE INDEX identity_account ON identity (account_id);
+CREATE TABLE identity (
+    platform INTEGER NOT NULL,
+    platform_user_id INTEGER NOT NULL,
+    account_id INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (platform, platform_user_id)
+);
+CREATE TABLE identity_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    platform INTEGER NOT NULL,
+    platform_user_id INTEGER NOT NULL,
+    previous_account_id INTEGER,
+    account_id INTEGER NOT NULL,
+    reason TEXT NOT NULL
+);
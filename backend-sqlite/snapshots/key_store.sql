@@ -0,0 +1,6 @@
+CREATE TABLE backend_key (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    aes_key BLOB NOT NULL,
+    aes_iv BLOB NOT NULL,
+    valid_until INTEGER NOT NULL
+);
@@ -0,0 +1,33 @@
+CREATE INDEX subdivision_league ON subdivision (title, league_id, season_id);
+CREATE INDEX team_member_user ON team_member (user_id);
+CREATE INDEX team_subdivision_subdivision ON team_subdivision (subdivision_id);
+CREATE TABLE subdivision (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    league_id INTEGER NOT NULL,
+    season_id INTEGER NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE TABLE team (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    member_key TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_active_at INTEGER NOT NULL,
+    UNIQUE (title, member_key)
+);
+CREATE TABLE team_member (
+    team_id INTEGER NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL,
+    username TEXT NOT NULL,
+    PRIMARY KEY (team_id, user_id)
+);
+CREATE TABLE team_subdivision (
+    team_id INTEGER NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    league_id INTEGER NOT NULL,
+    season_id INTEGER NOT NULL,
+    subdivision_id INTEGER NOT NULL REFERENCES subdivision(id),
+    placed_at INTEGER NOT NULL,
+    PRIMARY KEY (team_id, league_id, season_id)
+);
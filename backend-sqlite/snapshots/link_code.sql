@@ -0,0 +1,15 @@
+CREATE TABLE link_code (
+    code TEXT PRIMARY KEY,
+    title INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    owner_name TEXT NOT NULL,
+    expires_at INTEGER NOT NULL,
+    redeemed_by INTEGER
+);
+CREATE TABLE linked_user (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    linked_user_id INTEGER NOT NULL,
+    linked_username TEXT NOT NULL,
+    PRIMARY KEY (title, user_id, linked_user_id)
+);
@@ -0,0 +1,5 @@
+CREATE TABLE user_locale (
+    user_id INTEGER PRIMARY KEY,
+    locale TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
@@ -0,0 +1,14 @@
+CREATE INDEX mail_title_recipient_id_idx ON mail (
+    title,
+    recipient_id
+);
+CREATE TABLE mail (
+    id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    sender_id INTEGER NOT NULL,
+    sender_name TEXT NOT NULL,
+    recipient_id INTEGER NOT NULL,
+    sent_at INTEGER NOT NULL,
+    read INTEGER NOT NULL DEFAULT 0,
+    body BLOB NOT NULL
+);
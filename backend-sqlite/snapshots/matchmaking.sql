@@ -0,0 +1,8 @@
+CREATE TABLE performance_value (
+    title INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    performance_value INTEGER NOT NULL,
+    submitted_by INTEGER NOT NULL,
+    submitted_at INTEGER NOT NULL,
+    PRIMARY KEY (title, entity_id)
+);
@@ -0,0 +1,19 @@
+CREATE INDEX instant_message_title_recipient_id_idx ON instant_message (
+    title,
+    recipient_id
+);
+CREATE TABLE blocked_user (
+    title INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    blocked_user_id INTEGER NOT NULL,
+    PRIMARY KEY (title, owner_id, blocked_user_id)
+);
+CREATE TABLE instant_message (
+    id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    sender_id INTEGER NOT NULL,
+    sender_name TEXT NOT NULL,
+    recipient_id INTEGER NOT NULL,
+    sent_at INTEGER NOT NULL,
+    payload BLOB NOT NULL
+);
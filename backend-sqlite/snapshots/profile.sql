@@ -0,0 +1,9 @@
+CREATE TABLE user_profile (
+    id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    profile_type INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
@@ -0,0 +1,10 @@
+CREATE TABLE user_file (
+    id INTEGER PRIMARY KEY,
+    filename TEXT NOT NULL,
+    title INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    visibility INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
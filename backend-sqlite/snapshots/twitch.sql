@@ -0,0 +1,7 @@
+CREATE TABLE twitch_account (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    token TEXT NOT NULL,
+    linked_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id)
+);
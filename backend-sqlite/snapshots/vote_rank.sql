@@ -0,0 +1,10 @@
+CREATE INDEX vote_entity ON vote (title, entity_id, category);
+CREATE TABLE vote (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    category INTEGER NOT NULL,
+    rating INTEGER NOT NULL,
+    voted_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id, entity_id, category)
+);
@@ -0,0 +1,6 @@
+CREATE TABLE youtube_account (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    registered_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id)
+);
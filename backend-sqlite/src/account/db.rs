@@ -0,0 +1,42 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static ACCOUNT_DB: RefCell<Connection> = RefCell::new(open_db(&ACCOUNT_SCHEMA));
+}
+
+const ACCOUNT_CHANGELOG_0: &str = "
+CREATE TABLE account (
+    user_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    user_key BLOB NOT NULL,
+    license_code TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX account_license_code ON account (license_code);
+";
+
+const ACCOUNT_CHANGELOG_1: &str = "
+CREATE TABLE codo_account (
+    codo_id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL UNIQUE
+);
+";
+
+const ACCOUNT_SCHEMA: DbSchema = DbSchema {
+    name: "account",
+    changelogs: &[ACCOUNT_CHANGELOG_0, ACCOUNT_CHANGELOG_1],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&ACCOUNT_SCHEMA);
+    }
+}
@@ -0,0 +1,351 @@
+mod db;
+
+use crate::account::db::ACCOUNT_DB;
+use crate::identity::link_identity;
+use bitdemon::auth::account::{AccountStore, AccountStoreError, BdAccount, UserKey};
+use bitdemon::auth::identity::PlatformIdentity;
+use bitdemon::crypto::constant_time_eq;
+use chrono::Utc;
+use log::info;
+use rand::Rng;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior};
+
+/// Persists bitdemon accounts.
+/// User keys are stored as sent by the client, since they are needed to encrypt
+/// the tickets of the account later on.
+pub struct DwAccountStore {}
+
+impl AccountStore for DwAccountStore {
+    fn create_account(
+        &self,
+        username: &str,
+        user_key: &UserKey,
+        license_code: &str,
+    ) -> Result<u64, AccountStoreError> {
+        ACCOUNT_DB.with_borrow(|db| {
+            let now = Utc::now().timestamp();
+            let inserted = db
+                .execute(
+                    "INSERT INTO account (username, user_key, license_code, created_at, updated_at)
+                     VALUES (?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+                    (username, user_key.as_slice(), license_code, now, now),
+                )
+                .expect("insertion to be successful");
+
+            if inserted == 0 {
+                return Err(AccountStoreError::UsernameExistsError);
+            }
+
+            Ok(db.last_insert_rowid() as u64)
+        })
+    }
+
+    fn change_user_key(
+        &self,
+        username: &str,
+        user_key: &UserKey,
+        new_user_key: &UserKey,
+    ) -> Result<(), AccountStoreError> {
+        ACCOUNT_DB.with_borrow(|db| {
+            let (user_id, current_user_key, _) = query_account(db, username)?;
+            if !constant_time_eq(&current_user_key, user_key) {
+                return Err(AccountStoreError::IncorrectUserKeyError);
+            }
+
+            update_user_key(db, user_id, new_user_key);
+            info!("Changed user key of account user_id={user_id}");
+
+            Ok(())
+        })
+    }
+
+    fn reset_account(
+        &self,
+        username: &str,
+        license_code: &str,
+        new_user_key: &UserKey,
+    ) -> Result<(), AccountStoreError> {
+        ACCOUNT_DB.with_borrow(|db| {
+            let (user_id, _, account_license_code) = query_account(db, username)?;
+            if account_license_code.is_empty()
+                || !constant_time_eq(account_license_code.as_bytes(), license_code.as_bytes())
+            {
+                return Err(AccountStoreError::IncorrectLicenseCodeError);
+            }
+
+            update_user_key(db, user_id, new_user_key);
+            info!("Reset account user_id={user_id}");
+
+            Ok(())
+        })
+    }
+
+    fn delete_account(&self, username: &str, user_key: &UserKey) -> Result<(), AccountStoreError> {
+        ACCOUNT_DB.with_borrow(|db| {
+            let (user_id, current_user_key, _) = query_account(db, username)?;
+            if !constant_time_eq(&current_user_key, user_key) {
+                return Err(AccountStoreError::IncorrectUserKeyError);
+            }
+
+            db.execute("DELETE FROM account WHERE user_id = ?1", (user_id,))
+                .expect("deletion to be successful");
+            db.execute("DELETE FROM codo_account WHERE user_id = ?1", (user_id,))
+                .expect("deletion to be successful");
+            info!("Deleted account user_id={user_id}");
+
+            Ok(())
+        })
+    }
+
+    fn migrate_account(
+        &self,
+        username: &str,
+        user_key: &UserKey,
+        identity: &PlatformIdentity,
+    ) -> Result<(), AccountStoreError> {
+        let user_id = ACCOUNT_DB.with_borrow(|db| {
+            let (user_id, current_user_key, _) = query_account(db, username)?;
+            if !constant_time_eq(&current_user_key, user_key) {
+                return Err(AccountStoreError::IncorrectUserKeyError);
+            }
+
+            let mut invalid_user_key: UserKey = [0; 24];
+            rand::rng().fill_bytes(&mut invalid_user_key);
+            update_user_key(db, user_id, &invalid_user_key);
+
+            Ok(user_id)
+        })?;
+
+        link_identity(identity, user_id, "migrated");
+        info!(
+            "Migrated account user_id={user_id} to {:?} user {}",
+            identity.platform, identity.platform_user_id
+        );
+
+        Ok(())
+    }
+
+    fn find_account(&self, username: &str) -> Result<BdAccount, AccountStoreError> {
+        ACCOUNT_DB.with_borrow(|db| {
+            db.query_row(
+                "SELECT a.user_id, a.username, a.user_key FROM account a WHERE a.username = ?1",
+                (username,),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Vec<u8>>(2)?)),
+            )
+            .optional()
+            .expect("query to be successful")
+            .and_then(|(user_id, username, user_key)| {
+                Some(BdAccount {
+                    user_id,
+                    username,
+                    user_key: user_key.try_into().ok()?,
+                })
+            })
+            .ok_or(AccountStoreError::AccountNotFoundError)
+        })
+    }
+
+    fn resolve_codo_account(
+        &self,
+        codo_id: u64,
+        username: &str,
+    ) -> Result<BdAccount, AccountStoreError> {
+        ACCOUNT_DB.with_borrow_mut(|db| {
+            // Takes the write lock right away, so that concurrent first logins of an id
+            // do not both create an account
+            let transaction = db
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .expect("transaction to be started");
+
+            let maybe_account = transaction
+                .query_row(
+                    "SELECT a.user_id, a.username, a.user_key FROM codo_account c
+                     JOIN account a ON a.user_id = c.user_id
+                     WHERE c.codo_id = ?1",
+                    (codo_id,),
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Vec<u8>>(2)?)),
+                )
+                .optional()
+                .expect("query to be successful");
+
+            if let Some((user_id, username, user_key)) = maybe_account {
+                return Ok(BdAccount {
+                    user_id,
+                    username,
+                    user_key: user_key
+                        .try_into()
+                        .map_err(|_| AccountStoreError::AccountNotFoundError)?,
+                });
+            }
+
+            let mut user_key: UserKey = [0; 24];
+            rand::rng().fill_bytes(&mut user_key);
+
+            let now = Utc::now().timestamp();
+            let (user_id, username) = (1u32..)
+                .find_map(|attempt| {
+                    // The name may already be taken by an account of another id or a regular account
+                    let candidate = if attempt == 1 {
+                        String::from(username)
+                    } else {
+                        format!("{username}_{attempt}")
+                    };
+                    let inserted = transaction
+                        .execute(
+                            "INSERT INTO account (username, user_key, license_code, created_at, updated_at)
+                             VALUES (?, ?, '', ?, ?) ON CONFLICT DO NOTHING",
+                            (&candidate, user_key.as_slice(), now, now),
+                        )
+                        .expect("insertion to be successful");
+
+                    (inserted > 0).then(|| (transaction.last_insert_rowid() as u64, candidate))
+                })
+                .expect("a free username to be found");
+
+            transaction
+                .execute(
+                    "INSERT INTO codo_account (codo_id, user_id) VALUES (?1, ?2)
+                     ON CONFLICT (codo_id) DO UPDATE SET user_id = excluded.user_id",
+                    (codo_id, user_id),
+                )
+                .expect("insertion to be successful");
+            transaction.commit().expect("transaction to be committed");
+            info!("Created account user_id={user_id} username={username} for codo_id={codo_id}");
+
+            Ok(BdAccount {
+                user_id,
+                username,
+                user_key,
+            })
+        })
+    }
+}
+
+impl Default for DwAccountStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwAccountStore {
+    pub fn new() -> DwAccountStore {
+        DwAccountStore {}
+    }
+}
+
+fn query_account(
+    db: &Connection,
+    username: &str,
+) -> Result<(u64, Vec<u8>, String), AccountStoreError> {
+    db.query_row(
+        "SELECT a.user_id, a.user_key, a.license_code FROM account a WHERE a.username = ?1",
+        (username,),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+    .optional()
+    .expect("query to be successful")
+    .ok_or(AccountStoreError::AccountNotFoundError)
+}
+
+fn update_user_key(db: &Connection, user_id: u64, user_key: &UserKey) {
+    db.execute(
+        "UPDATE account SET user_key = ?1, updated_at = ?2 WHERE user_id = ?3",
+        (user_key.as_slice(), Utc::now().timestamp(), user_id),
+    )
+    .expect("update to be successful");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::DwAccountResolver;
+    use bitdemon::auth::identity::{AccountResolver, IdentityPlatform};
+
+    const KEY: UserKey = [1; 24];
+    const OTHER_KEY: UserKey = [2; 24];
+
+    #[test]
+    fn usernames_are_unique_regardless_of_case() {
+        let store = DwAccountStore::new();
+
+        let user_id = store.create_account("Player", &KEY, "").unwrap();
+        assert_eq!(
+            store.create_account("player", &OTHER_KEY, ""),
+            Err(AccountStoreError::UsernameExistsError)
+        );
+        assert_ne!(store.create_account("Player2", &KEY, "").unwrap(), user_id);
+
+        let account = store.find_account("PLAYER").unwrap();
+        assert_eq!(account.user_id, user_id);
+        assert_eq!(account.username, "Player");
+    }
+
+    #[test]
+    fn migrated_accounts_are_resolved_from_identity() {
+        let store = DwAccountStore::new();
+        let user_id = store.create_account("Player", &KEY, "").unwrap();
+        let identity = PlatformIdentity {
+            platform: IdentityPlatform::Steam,
+            platform_user_id: 76561197960265728,
+        };
+
+        assert_eq!(
+            store.migrate_account("Player", &OTHER_KEY, &identity),
+            Err(AccountStoreError::IncorrectUserKeyError)
+        );
+        store.migrate_account("Player", &KEY, &identity).unwrap();
+
+        assert_eq!(
+            DwAccountResolver::new().resolve_account(&identity).unwrap(),
+            user_id
+        );
+        assert_ne!(store.find_account("Player").unwrap().user_key, KEY);
+    }
+
+    #[test]
+    fn codo_ids_keep_their_account() {
+        let store = DwAccountStore::new();
+
+        let account = store.resolve_codo_account(1, "Player").unwrap();
+        assert_eq!(store.resolve_codo_account(1, "Renamed").unwrap(), account);
+        let other = store.resolve_codo_account(2, "player").unwrap();
+        assert_ne!(other.user_id, account.user_id);
+        assert_eq!(other.username, "player_2");
+        assert_eq!(store.resolve_codo_account(2, "player").unwrap(), other);
+
+        store.delete_account("Player", &account.user_key).unwrap();
+        assert_ne!(
+            store.resolve_codo_account(1, "Player").unwrap().user_id,
+            account.user_id
+        );
+    }
+
+    #[test]
+    fn user_key_can_be_changed_with_current_key_or_license_code() {
+        let store = DwAccountStore::new();
+        store.create_account("Player", &KEY, "LICENSE").unwrap();
+
+        assert_eq!(
+            store.change_user_key("Player", &OTHER_KEY, &OTHER_KEY),
+            Err(AccountStoreError::IncorrectUserKeyError)
+        );
+        store.change_user_key("Player", &KEY, &OTHER_KEY).unwrap();
+
+        assert_eq!(
+            store.reset_account("Player", "WRONG", &KEY),
+            Err(AccountStoreError::IncorrectLicenseCodeError)
+        );
+        store.reset_account("Player", "LICENSE", &KEY).unwrap();
+
+        assert_eq!(
+            store.delete_account("Player", &OTHER_KEY),
+            Err(AccountStoreError::IncorrectUserKeyError)
+        );
+        assert_eq!(store.find_account("player").unwrap().user_key, KEY);
+        store.delete_account("Player", &KEY).unwrap();
+        assert_eq!(
+            store.delete_account("Player", &KEY),
+            Err(AccountStoreError::AccountNotFoundError)
+        );
+    }
+}
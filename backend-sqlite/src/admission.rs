@@ -0,0 +1,148 @@
+use crate::ban;
+use crate::config::{AdmissionConfig, LocalizationConfig};
+use crate::localization::{message_key, MessageCatalog};
+use bitdemon::auth::authentication::SessionAuthentication;
+use bitdemon::lobby::admission::{AdmissionRejection, SessionAdmissionPolicy};
+use bitdemon::networking::bd_session::{BdSession, SessionId};
+use bitdemon::networking::session_manager::SessionManager;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Admits sessions according to the ban list and the configured restrictions.
+pub struct DwSessionAdmissionPolicy {
+    config: AdmissionConfig,
+    /// The user of each authenticated session.
+    sessions: Arc<Mutex<HashMap<SessionId, u64>>>,
+    messages: MessageCatalog,
+}
+
+impl SessionAdmissionPolicy for DwSessionAdmissionPolicy {
+    fn admit(
+        &self,
+        _session: &BdSession,
+        authentication: &SessionAuthentication,
+    ) -> Result<(), AdmissionRejection> {
+        let user_id = authentication.user_id;
+
+        if ban::is_banned(user_id) {
+            return Err(AdmissionRejection::UserBannedError);
+        }
+
+        if self.config.maintenance {
+            return Err(AdmissionRejection::MaintenanceError);
+        }
+
+        if !self
+            .config
+            .is_allowlisted(authentication.title, authentication.user_id)
+        {
+            return Err(AdmissionRejection::NotAllowlistedError);
+        }
+
+        let sessions = self.sessions.lock().unwrap();
+        let session_limit_reached = self
+            .config
+            .max_sessions
+            .is_some_and(|max_sessions| sessions.len() >= max_sessions);
+        let user_session_limit_reached =
+            self.config
+                .max_sessions_per_user
+                .is_some_and(|max_sessions_per_user| {
+                    sessions.values().filter(|other| **other == user_id).count()
+                        >= max_sessions_per_user
+                });
+        if session_limit_reached || user_session_limit_reached {
+            return Err(AdmissionRejection::SessionLimitReachedError);
+        }
+
+        Ok(())
+    }
+
+    fn rejection_message(
+        &self,
+        rejection: AdmissionRejection,
+        authentication: &SessionAuthentication,
+    ) -> Option<String> {
+        let key = match rejection {
+            AdmissionRejection::UserBannedError => message_key::BANNED,
+            AdmissionRejection::MaintenanceError => message_key::MAINTENANCE,
+            AdmissionRejection::NotAllowlistedError => message_key::NOT_ALLOWLISTED,
+            AdmissionRejection::SessionLimitReachedError => message_key::SESSION_LIMIT_REACHED,
+        };
+
+        Some(self.messages.message_for_user(authentication.user_id, key))
+    }
+}
+
+impl DwSessionAdmissionPolicy {
+    /// Creates a policy that counts the authenticated sessions of the session manager.
+    pub fn new(
+        config: AdmissionConfig,
+        session_manager: &SessionManager,
+    ) -> DwSessionAdmissionPolicy {
+        let sessions = Arc::new(Mutex::new(HashMap::new()));
+
+        let authenticated_sessions = sessions.clone();
+        session_manager.on_session_authenticated(move |session| {
+            let user_id = session.authentication().unwrap().user_id;
+            authenticated_sessions
+                .lock()
+                .unwrap()
+                .insert(session.id, user_id);
+        });
+
+        let unregistered_sessions = sessions.clone();
+        session_manager.on_session_unregistered(move |session| {
+            unregistered_sessions.lock().unwrap().remove(&session.id);
+        });
+
+        DwSessionAdmissionPolicy {
+            config,
+            sessions,
+            messages: MessageCatalog::new(LocalizationConfig::default()),
+        }
+    }
+
+    /// Explains rejections with the translated messages.
+    pub fn with_localization(mut self, localization: LocalizationConfig) -> Self {
+        self.messages = MessageCatalog::new(localization);
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use bitdemon::domain::title::Title;
+    use num_traits::ToPrimitive;
+
+    #[test]
+    fn sessions_are_limited_per_user() {
+        let session_manager = SessionManager::new();
+        let policy = DwSessionAdmissionPolicy::new(
+            AdmissionConfig {
+                allowlists: HashMap::from([(Title::T6Pc.to_u32().unwrap(), vec![1])]),
+                max_sessions_per_user: Some(1),
+                ..AdmissionConfig::default()
+            },
+            &session_manager,
+        );
+        let session = authenticated_session(1, Title::T6Pc);
+        let authentication = session.authentication().unwrap();
+
+        assert_eq!(policy.admit(&session, authentication), Ok(()));
+        session_manager.authenticate_session(&session);
+        assert_eq!(
+            policy.admit(&session, authentication),
+            Err(AdmissionRejection::SessionLimitReachedError)
+        );
+
+        let other = authenticated_session(2, Title::T6Pc);
+        assert_eq!(
+            policy.admit(&other, other.authentication().unwrap()),
+            Err(AdmissionRejection::NotAllowlistedError)
+        );
+    }
+}
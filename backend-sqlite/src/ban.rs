@@ -0,0 +1,228 @@
+use crate::db::{open_db, DbSchema};
+use bitdemon::auth::ban::BanStore;
+use bitdemon::auth::identity::PlatformIdentity;
+use chrono::Utc;
+use log::info;
+use num_traits::ToPrimitive;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::cell::RefCell;
+
+thread_local! {
+    static ADMIN_DB: RefCell<Connection> = RefCell::new(open_db(&ADMIN_SCHEMA));
+}
+
+const ADMIN_CHANGELOG_0: &str = "
+CREATE TABLE ban (
+    user_id INTEGER PRIMARY KEY,
+    reason TEXT NOT NULL,
+    banned_at INTEGER NOT NULL
+);
+";
+
+const ADMIN_CHANGELOG_1: &str = "
+CREATE TABLE platform_ban (
+    platform INTEGER NOT NULL,
+    platform_user_id INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    banned_at INTEGER NOT NULL,
+    PRIMARY KEY (platform, platform_user_id)
+);
+";
+
+#[derive(Serialize)]
+pub struct Ban {
+    pub user_id: u64,
+    pub reason: String,
+    pub banned_at: i64,
+}
+
+/// A ban of the account of a user on an external platform.
+#[derive(Serialize)]
+pub struct PlatformBan {
+    pub platform: u8,
+    pub platform_user_id: u64,
+    pub reason: String,
+    pub banned_at: i64,
+}
+
+pub fn ban_user(user_id: u64, reason: &str) {
+    info!("Banning user {user_id}");
+
+    ADMIN_DB.with_borrow(|db| {
+        db.execute(
+            "INSERT OR REPLACE INTO ban (user_id, reason, banned_at) VALUES (?, ?, ?)",
+            (user_id, reason, Utc::now().timestamp()),
+        )
+        .expect("insertion to be successful");
+    });
+}
+
+/// Returns whether the user was banned before.
+pub fn unban_user(user_id: u64) -> bool {
+    info!("Unbanning user {user_id}");
+
+    ADMIN_DB.with_borrow(|db| {
+        db.execute("DELETE FROM ban WHERE user_id = ?1", (user_id,))
+            .expect("deletion to be successful")
+            > 0
+    })
+}
+
+pub fn is_banned(user_id: u64) -> bool {
+    ADMIN_DB.with_borrow(|db| {
+        db.query_row(
+            "SELECT COUNT(*) FROM ban WHERE user_id = ?1",
+            (user_id,),
+            |row| row.get::<usize, u64>(0),
+        )
+        .expect("query to be successful")
+            > 0
+    })
+}
+
+pub fn list_bans() -> Vec<Ban> {
+    ADMIN_DB.with_borrow(|db| {
+        db.prepare("SELECT b.user_id, b.reason, b.banned_at FROM ban b ORDER BY b.banned_at")
+            .expect("preparation to be successful")
+            .query_map((), |row| {
+                Ok(Ban {
+                    user_id: row.get(0)?,
+                    reason: row.get(1)?,
+                    banned_at: row.get(2)?,
+                })
+            })
+            .expect("query to be successful")
+            .filter_map(|ban| ban.ok())
+            .collect()
+    })
+}
+
+/// Bans the account of a user on an external platform,
+/// regardless of which account it belongs to.
+pub fn ban_platform_user(identity: &PlatformIdentity, reason: &str) {
+    info!("Banning platform user {identity:?}");
+
+    ADMIN_DB.with_borrow(|db| {
+        db.execute(
+            "INSERT OR REPLACE INTO platform_ban (platform, platform_user_id, reason, banned_at)
+             VALUES (?, ?, ?, ?)",
+            (
+                identity.platform.to_u8().unwrap(),
+                identity.platform_user_id,
+                reason,
+                Utc::now().timestamp(),
+            ),
+        )
+        .expect("insertion to be successful");
+    });
+}
+
+/// Returns whether the platform user was banned before.
+pub fn unban_platform_user(identity: &PlatformIdentity) -> bool {
+    info!("Unbanning platform user {identity:?}");
+
+    ADMIN_DB.with_borrow(|db| {
+        db.execute(
+            "DELETE FROM platform_ban WHERE platform = ?1 AND platform_user_id = ?2",
+            (
+                identity.platform.to_u8().unwrap(),
+                identity.platform_user_id,
+            ),
+        )
+        .expect("deletion to be successful")
+            > 0
+    })
+}
+
+pub fn is_platform_user_banned(identity: &PlatformIdentity) -> bool {
+    ADMIN_DB.with_borrow(|db| {
+        db.query_row(
+            "SELECT COUNT(*) FROM platform_ban WHERE platform = ?1 AND platform_user_id = ?2",
+            (
+                identity.platform.to_u8().unwrap(),
+                identity.platform_user_id,
+            ),
+            |row| row.get::<usize, u64>(0),
+        )
+        .expect("query to be successful")
+            > 0
+    })
+}
+
+pub fn list_platform_bans() -> Vec<PlatformBan> {
+    ADMIN_DB.with_borrow(|db| {
+        db.prepare(
+            "SELECT p.platform, p.platform_user_id, p.reason, p.banned_at FROM platform_ban p
+             ORDER BY p.banned_at",
+        )
+        .expect("preparation to be successful")
+        .query_map((), |row| {
+            Ok(PlatformBan {
+                platform: row.get(0)?,
+                platform_user_id: row.get(1)?,
+                reason: row.get(2)?,
+                banned_at: row.get(3)?,
+            })
+        })
+        .expect("query to be successful")
+        .filter_map(|ban| ban.ok())
+        .collect()
+    })
+}
+
+/// Refuses banned users and platform users when authenticating.
+#[derive(Default)]
+pub struct DwBanStore {}
+
+impl BanStore for DwBanStore {
+    fn is_user_banned(&self, user_id: u64) -> bool {
+        is_banned(user_id)
+    }
+
+    fn is_platform_user_banned(&self, identity: &PlatformIdentity) -> bool {
+        is_platform_user_banned(identity)
+    }
+}
+
+impl DwBanStore {
+    pub fn new() -> DwBanStore {
+        DwBanStore {}
+    }
+}
+
+const ADMIN_SCHEMA: DbSchema = DbSchema {
+    name: "admin",
+    changelogs: &[ADMIN_CHANGELOG_0, ADMIN_CHANGELOG_1],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+    use bitdemon::auth::identity::IdentityPlatform;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&ADMIN_SCHEMA);
+    }
+
+    #[test]
+    fn platform_user_is_banned_regardless_of_account() {
+        let identity = PlatformIdentity {
+            platform: IdentityPlatform::Steam,
+            platform_user_id: 76561197960287930,
+        };
+        let ban_store = DwBanStore::new();
+        assert!(!ban_store.is_banned(1, Some(&identity)));
+
+        ban_platform_user(&identity, "cheating");
+        assert!(ban_store.is_banned(1, Some(&identity)));
+        assert!(!ban_store.is_banned(1, None));
+        assert_eq!(list_platform_bans().len(), 1);
+
+        assert!(unban_platform_user(&identity));
+        assert!(!unban_platform_user(&identity));
+        assert!(!ban_store.is_banned(1, Some(&identity)));
+    }
+}
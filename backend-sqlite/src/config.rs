@@ -0,0 +1,366 @@
+use crate::db::DEFAULT_DB_DIR;
+use bitdemon::domain::title::Title;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const DEFAULT_PUBLISHER_STORAGE_DIR: &str = "storage/publisher";
+const DEFAULT_PUBLISHER_STREAM_DIR: &str = "stream/publisher";
+const DEFAULT_CONTENT_SERVER_HOSTNAME: &str = "localhost";
+const DEFAULT_CONTENT_SERVER_PORT: u16 = 3076;
+const DEFAULT_MAX_USER_FILE_SIZE: usize = 50_000; // 50KB
+const DEFAULT_MATCHMAKING_SESSION_TTL_SECS: u64 = 600;
+const TENANTS_DIR: &str = "tenants";
+
+/// Determines where the backend keeps its data and how clients can reach it.
+pub struct BackendConfig {
+    /// The directory containing the SQLite databases.
+    pub db_dir: PathBuf,
+    /// The directory containing publisher files of the storage service, one subdirectory per title.
+    pub publisher_storage_dir: PathBuf,
+    /// The directory containing publisher streams of the content streaming service,
+    /// one subdirectory per title.
+    pub publisher_stream_dir: PathBuf,
+    /// The hostname under which the content streaming http server can be reached.
+    pub content_server_hostname: String,
+    /// The port under which the content streaming http server can be reached.
+    pub content_server_port: u16,
+    /// The url twitter posts of users are forwarded to as json.
+    /// Posts are dropped when not set.
+    pub twitter_webhook_url: Option<String>,
+    /// The maximum sizes of files users can upload.
+    pub user_file_size_limits: UserFileSizeLimits,
+    /// Which sessions may authenticate on the lobby server.
+    pub admission: AdmissionConfig,
+    /// Translations of messages shown to players.
+    pub localization: LocalizationConfig,
+    /// The season and subdivision size of leagues.
+    pub league: LeagueConfig,
+    /// Per-title settings of the title utilities.
+    pub title_utilities: TitleUtilitiesConfig,
+    /// The challenges clients have to answer and when users are flagged for failing them.
+    pub anti_cheat: AntiCheatConfig,
+    /// The windows after which counters are reset.
+    pub counter: CounterConfig,
+    /// The queries clients search for matchmaking sessions with.
+    pub matchmaking: MatchmakingConfig,
+    /// How Steam tickets of clients are validated.
+    pub steam: SteamConfig,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        BackendConfig {
+            db_dir: PathBuf::from(DEFAULT_DB_DIR),
+            publisher_storage_dir: PathBuf::from(DEFAULT_PUBLISHER_STORAGE_DIR),
+            publisher_stream_dir: PathBuf::from(DEFAULT_PUBLISHER_STREAM_DIR),
+            content_server_hostname: DEFAULT_CONTENT_SERVER_HOSTNAME.to_string(),
+            content_server_port: DEFAULT_CONTENT_SERVER_PORT,
+            twitter_webhook_url: None,
+            user_file_size_limits: UserFileSizeLimits::default(),
+            admission: AdmissionConfig::default(),
+            localization: LocalizationConfig::default(),
+            league: LeagueConfig::default(),
+            title_utilities: TitleUtilitiesConfig::default(),
+            anti_cheat: AntiCheatConfig::default(),
+            counter: CounterConfig::default(),
+            matchmaking: MatchmakingConfig::default(),
+            steam: SteamConfig::default(),
+        }
+    }
+}
+
+impl BackendConfig {
+    /// Moves all persisted data into a directory of its own for the tenant,
+    /// so that several communities can be served from the same working directory
+    /// without seeing each other's users, files or databases.
+    pub fn with_tenant(mut self, tenant: &str) -> Self {
+        let tenant_dir = PathBuf::from(TENANTS_DIR).join(tenant);
+        self.db_dir = tenant_dir.join(&self.db_dir);
+        self.publisher_storage_dir = tenant_dir.join(&self.publisher_storage_dir);
+        self.publisher_stream_dir = tenant_dir.join(&self.publisher_stream_dir);
+
+        self
+    }
+}
+
+/// The maximum sizes in bytes of files users can upload, per service.
+/// Titles can override the defaults individually.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserFileSizeLimits {
+    /// The limit for user files of the storage service.
+    pub storage: usize,
+    /// The limit for user streams of the content streaming service.
+    pub streaming: usize,
+    /// Overrides keyed by title number.
+    pub titles: HashMap<u32, TitleUserFileSizeLimits>,
+}
+
+/// Overrides of [`UserFileSizeLimits`] for a single title.
+/// Limits that are not set fall back to the defaults.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleUserFileSizeLimits {
+    pub storage: Option<usize>,
+    pub streaming: Option<usize>,
+}
+
+impl Default for UserFileSizeLimits {
+    fn default() -> Self {
+        UserFileSizeLimits {
+            storage: DEFAULT_MAX_USER_FILE_SIZE,
+            streaming: DEFAULT_MAX_USER_FILE_SIZE,
+            titles: HashMap::new(),
+        }
+    }
+}
+
+impl UserFileSizeLimits {
+    /// The maximum size of a user file of the storage service for the specified title.
+    pub fn storage_limit(&self, title: Title) -> usize {
+        self.title_limits(title)
+            .and_then(|limits| limits.storage)
+            .unwrap_or(self.storage)
+    }
+
+    /// The maximum size of a user stream of the content streaming service for the specified title.
+    pub fn streaming_limit(&self, title: Title) -> usize {
+        self.title_limits(title)
+            .and_then(|limits| limits.streaming)
+            .unwrap_or(self.streaming)
+    }
+
+    /// The largest user stream any title may upload.
+    pub fn max_streaming_limit(&self) -> usize {
+        self.titles
+            .values()
+            .filter_map(|limits| limits.streaming)
+            .fold(self.streaming, usize::max)
+    }
+
+    fn title_limits(&self, title: Title) -> Option<&TitleUserFileSizeLimits> {
+        self.titles.get(&title.to_u32().unwrap())
+    }
+}
+
+/// Restrictions on which sessions may authenticate on the lobby server.
+/// Banned users are always rejected.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdmissionConfig {
+    /// Rejects all sessions while enabled.
+    pub maintenance: bool,
+    /// The users admitted to a title, keyed by title number.
+    /// Titles without an allowlist admit everyone.
+    pub allowlists: HashMap<u32, Vec<u64>>,
+    /// The maximum amount of authenticated sessions. Unlimited when not set.
+    pub max_sessions: Option<usize>,
+    /// The maximum amount of authenticated sessions of a single user. Unlimited when not set.
+    pub max_sessions_per_user: Option<usize>,
+}
+
+impl AdmissionConfig {
+    /// Whether the user may use the specified title.
+    pub fn is_allowlisted(&self, title: Title, user_id: u64) -> bool {
+        self.allowlists
+            .get(&title.to_u32().unwrap())
+            .is_none_or(|allowlist| allowlist.contains(&user_id))
+    }
+}
+
+/// Translations of messages shown to players, e.g. when they cannot connect.
+/// Messages that are not translated are shown in English.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalizationConfig {
+    /// Messages by key, keyed by locale (`de_AT`) or language (`de`).
+    pub messages: HashMap<String, HashMap<String, String>>,
+}
+
+/// How teams are grouped into the subdivisions of leagues.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeagueConfig {
+    /// The current season. Teams are placed into new subdivisions when it changes.
+    pub season_id: u64,
+    /// The maximum amount of teams in a subdivision.
+    pub subdivision_size: u32,
+}
+
+impl Default for LeagueConfig {
+    fn default() -> Self {
+        LeagueConfig {
+            season_id: 1,
+            subdivision_size: 100,
+        }
+    }
+}
+
+/// The anti cheat challenges of each title.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AntiCheatConfig {
+    /// Challenges keyed by title number.
+    pub titles: HashMap<u32, Vec<ChallengeDefinition>>,
+    /// How many challenges are issued to each session.
+    pub challenges_per_session: usize,
+    /// How many failed challenges it takes for a user to be flagged.
+    pub flag_threshold: u32,
+}
+
+/// A challenge with the answer of an unmodified client, both encoded as base64.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChallengeDefinition {
+    pub parameter: String,
+    pub answer: String,
+}
+
+impl Default for AntiCheatConfig {
+    fn default() -> Self {
+        AntiCheatConfig {
+            titles: HashMap::new(),
+            challenges_per_session: 1,
+            flag_threshold: 3,
+        }
+    }
+}
+
+impl AntiCheatConfig {
+    /// The challenges of the specified title if it has any.
+    pub fn challenges(&self, title: Title) -> Option<&[ChallengeDefinition]> {
+        self.titles
+            .get(&title.to_u32().unwrap())
+            .map(|challenges| challenges.as_slice())
+    }
+}
+
+/// The windows counters of each title accumulate over before being reset.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CounterConfig {
+    /// Windows keyed by title number and then counter id.
+    /// Counters without a window are never reset.
+    pub titles: HashMap<u32, HashMap<u32, CounterWindow>>,
+}
+
+/// The period a counter accumulates over, in UTC.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CounterWindow {
+    /// Reset every day at midnight.
+    Daily,
+    /// Reset every monday at midnight.
+    Weekly,
+    /// Never reset.
+    #[default]
+    AllTime,
+}
+
+impl CounterConfig {
+    /// The windows of the counters of the specified title.
+    pub fn windows(&self, title: Title) -> Option<&HashMap<u32, CounterWindow>> {
+        self.titles.get(&title.to_u32().unwrap())
+    }
+}
+
+/// The credentials for validating Steam tickets with the Steam Web API.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SteamConfig {
+    /// The publisher key of the Steam Web API.
+    /// Steam tickets are accepted without validation when not set.
+    pub web_api_key: Option<String>,
+    /// The Steam app id keyed by title number.
+    /// Titles without app id cannot authenticate with Steam while validation is enabled.
+    pub app_ids: HashMap<u32, u32>,
+}
+
+/// The matchmaking queries of each title and how long sessions live without their host.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchmakingConfig {
+    /// Seconds after which sessions are deleted when their host did not update them.
+    pub session_ttl_secs: u64,
+    /// Settings keyed by title number.
+    pub titles: HashMap<u32, MatchmakingTitleConfig>,
+}
+
+impl Default for MatchmakingConfig {
+    fn default() -> Self {
+        MatchmakingConfig {
+            session_ttl_secs: DEFAULT_MATCHMAKING_SESSION_TTL_SECS,
+            titles: HashMap::new(),
+        }
+    }
+}
+
+/// The matchmaking queries of a single title.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchmakingTitleConfig {
+    /// The filters of each query keyed by query id.
+    /// Without any queries, every search finds all sessions of the title.
+    pub queries: HashMap<u32, Vec<MatchmakingFilterConfig>>,
+}
+
+/// A condition sessions have to fulfill to be found by a query.
+/// Attributes of the session and parameters of the search are referenced by their index.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MatchmakingFilterConfig {
+    /// The game type of the session equals the parameter.
+    GameType { parameter: usize },
+    /// The attribute equals the parameter.
+    Equals { attribute: usize, parameter: usize },
+    /// The attribute lies between the two parameters, both inclusive.
+    Range {
+        attribute: usize,
+        min_parameter: usize,
+        max_parameter: usize,
+    },
+}
+
+/// The settings of the title utilities of each title.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleUtilitiesConfig {
+    /// Settings keyed by title number.
+    pub titles: HashMap<u32, TitleUtilitiesTitleConfig>,
+}
+
+/// The settings of the title utilities of a single title.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleUtilitiesTitleConfig {
+    /// Words that verified strings must not contain, matched case-insensitively anywhere in the string.
+    pub blocked_words: Vec<String>,
+    /// Seconds that are added to the server time reported to clients,
+    /// e.g. to test time-limited events.
+    pub server_time_offset_secs: i64,
+}
+
+impl TitleUtilitiesConfig {
+    /// The settings of the specified title if it has any.
+    pub fn title_config(&self, title: Title) -> Option<&TitleUtilitiesTitleConfig> {
+        self.titles.get(&title.to_u32().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_overrides_fall_back_to_defaults() {
+        let limits: UserFileSizeLimits = serde_json::from_str(
+            r#"{ "streaming": 100000, "titles": { "18397": { "storage": 250000 } } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(limits.storage_limit(Title::T6Pc), 250_000);
+        assert_eq!(limits.streaming_limit(Title::T6Pc), 100_000);
+        assert_eq!(limits.storage_limit(Title::Iw5), DEFAULT_MAX_USER_FILE_SIZE);
+        assert_eq!(limits.max_streaming_limit(), 100_000);
+    }
+}
@@ -0,0 +1,179 @@
+use log::info;
+use rusqlite::Connection;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+pub(crate) const DEFAULT_DB_DIR: &str = "db";
+/// How long a connection waits for other threads to finish writing before giving up.
+const DB_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+static DB_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+static IN_MEMORY_DBS: AtomicBool = AtomicBool::new(false);
+
+/// Describes a database that is created and upgraded by applying changelogs in order.
+pub struct DbSchema {
+    /// The name of the database, which is also used for its file name.
+    pub name: &'static str,
+    /// Sql batches that create and upgrade the database.
+    /// Which changelogs were already applied is tracked using the user version of the database,
+    /// so existing changelogs must never be changed or reordered.
+    pub changelogs: &'static [&'static str],
+}
+
+/// Changes the directory databases are kept in, which is `db` by default.
+///
+/// Only affects databases that are opened afterward.
+/// Databases are opened lazily once per thread,
+/// so this should be called before any service is used.
+pub fn set_db_dir(db_dir: impl Into<PathBuf>) {
+    *DB_DIR.write().unwrap() = Some(db_dir.into());
+}
+
+/// Keeps all databases that are opened afterward in memory instead of files.
+///
+/// Meant for tests of crates using this backend,
+/// which get a fresh database for every thread like the tests of this crate.
+pub fn use_in_memory_dbs() {
+    IN_MEMORY_DBS.store(true, Ordering::Relaxed);
+}
+
+/// The directory databases are kept in.
+pub(crate) fn db_dir() -> PathBuf {
+    DB_DIR
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DB_DIR))
+}
+
+/// Opens the database of the schema and applies all changelogs that are missing.
+///
+/// Tests get a fresh in-memory database instead of the file.
+/// Since databases are usually kept in thread locals and every test runs on its own thread,
+/// each test starts out with empty databases.
+///
+/// Every thread has a connection of its own, so files are opened in WAL mode
+/// and writers wait for each other instead of failing with `SQLITE_BUSY`.
+pub fn open_db(schema: &DbSchema) -> Connection {
+    let conn = if cfg!(test) || IN_MEMORY_DBS.load(Ordering::Relaxed) {
+        Connection::open_in_memory().expect("expected in-memory db to be able to open")
+    } else {
+        let db_dir = db_dir();
+        create_dir_all(&db_dir).expect("to be able to create dir");
+
+        let conn = Connection::open(db_dir.join(format!("{}.db", schema.name)))
+            .expect("expected db connection to be able to open");
+        conn.busy_timeout(DB_BUSY_TIMEOUT)
+            .expect("busy timeout to be able to be set");
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            .expect("journal mode to be able to be set");
+
+        conn
+    };
+
+    conn.execute("PRAGMA foreign_keys = ON", ())
+        .expect("foreign keys to be able to be set");
+
+    rusqlite::vtab::array::load_module(&conn).expect("array extension to be loadable");
+
+    migrate(&conn, schema);
+
+    conn
+}
+
+fn migrate(conn: &Connection, schema: &DbSchema) {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", (), |row| row.get(0))
+        .expect("Version to be available");
+
+    for (index, changelog) in schema.changelogs.iter().enumerate().skip(version) {
+        conn.execute_batch(changelog)
+            .expect("Initialization to succeed");
+
+        conn.execute(format!("PRAGMA user_version = {}", index + 1).as_str(), ())
+            .expect("Setting pragma to succeed");
+
+        info!("Migrated {} db to version {}", schema.name, index + 1);
+    }
+}
+
+#[cfg(test)]
+pub mod test_util {
+    use crate::db::{open_db, DbSchema};
+    use bitdemon::auth::authentication::SessionAuthentication;
+    use bitdemon::domain::title::Title;
+    use bitdemon::networking::bd_session::BdSession;
+    use std::fs::{create_dir_all, read_to_string, write};
+    use std::net::{TcpListener, TcpStream};
+    use std::path::PathBuf;
+
+    /// Creates a session that is authenticated as the specified user.
+    /// The session is connected to a local socket that nobody reads from.
+    pub fn authenticated_session(user_id: u64, title: Title) -> BdSession {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("to be able to bind");
+        let stream =
+            TcpStream::connect(listener.local_addr().unwrap()).expect("to be able to connect");
+
+        let mut session = BdSession::new(stream);
+        session.set_authentication(SessionAuthentication {
+            user_id,
+            username: format!("user{user_id}"),
+            session_key: [0u8; 24].into(),
+            title,
+            clock_skew: None,
+            anonymous: false,
+        });
+
+        session
+    }
+
+    /// Retrieves the sql of all tables and indices of a database, sorted by name.
+    pub fn schema_of(schema: &DbSchema) -> String {
+        let conn = open_db(schema);
+        let mut statement = conn
+            .prepare(
+                "SELECT m.sql FROM sqlite_master m
+                 WHERE m.sql IS NOT NULL AND m.name NOT LIKE 'sqlite_%'
+                 ORDER BY m.type, m.name",
+            )
+            .expect("preparation to be successful");
+
+        let statements: Vec<String> = statement
+            .query_map((), |row| row.get(0))
+            .expect("query to be successful")
+            .filter_map(|sql| sql.ok())
+            .collect();
+
+        statements.join(";\n") + ";\n"
+    }
+
+    /// Compares the schema that the changelogs produce with the snapshot that is checked in.
+    /// Set `UPDATE_SNAPSHOTS=1` to write the current schema as snapshot instead.
+    pub fn assert_schema_snapshot(schema: &DbSchema) {
+        let snapshot_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("snapshots");
+        let snapshot_path = snapshot_dir.join(format!("{}.sql", schema.name));
+        let actual = schema_of(schema);
+
+        if std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|value| value == "1") {
+            create_dir_all(&snapshot_dir).expect("to be able to create dir");
+            write(&snapshot_path, actual).expect("to be able to write snapshot");
+            return;
+        }
+
+        let expected = read_to_string(&snapshot_path).unwrap_or_else(|_| {
+            panic!(
+                "Missing schema snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it",
+                snapshot_path.display()
+            )
+        });
+        assert_eq!(
+            expected,
+            actual,
+            "Schema of {} db differs from snapshot, run with UPDATE_SNAPSHOTS=1 if this is intended",
+            schema.name
+        );
+    }
+}
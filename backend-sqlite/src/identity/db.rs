@@ -0,0 +1,43 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static IDENTITY_DB: RefCell<Connection> = RefCell::new(open_db(&IDENTITY_SCHEMA));
+}
+
+const IDENTITY_CHANGELOG_0: &str = "
+CREATE TABLE identity (
+    platform INTEGER NOT NULL,
+    platform_user_id INTEGER NOT NULL,
+    account_id INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (platform, platform_user_id)
+);
+CREATE INDEX identity_account ON identity (account_id);
+CREATE TABLE identity_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    platform INTEGER NOT NULL,
+    platform_user_id INTEGER NOT NULL,
+    previous_account_id INTEGER,
+    account_id INTEGER NOT NULL,
+    reason TEXT NOT NULL
+);
+";
+
+const IDENTITY_SCHEMA: DbSchema = DbSchema {
+    name: "identity",
+    changelogs: &[IDENTITY_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&IDENTITY_SCHEMA);
+    }
+}
@@ -0,0 +1,275 @@
+mod db;
+
+use crate::identity::db::IDENTITY_DB;
+use bitdemon::auth::identity::{AccountResolver, PlatformIdentity};
+use chrono::Utc;
+use log::info;
+use num_traits::ToPrimitive;
+use rusqlite::{OptionalExtension, Transaction};
+use std::error::Error;
+
+/// Reasons why two accounts cannot be merged.
+#[derive(Debug)]
+pub enum IdentityMergeError {
+    /// Both accounts are the same.
+    AlreadyMerged,
+    /// Both accounts have an identity on the same platform.
+    Conflict,
+}
+
+/// Keeps track of which account each platform identity belongs to.
+/// Identities authenticating for the first time get an account with the id of the platform user.
+pub struct DwAccountResolver {}
+
+impl AccountResolver for DwAccountResolver {
+    fn resolve_account(&self, identity: &PlatformIdentity) -> Result<u64, Box<dyn Error>> {
+        let platform_num = identity.platform.to_u8().unwrap();
+
+        let account_id = IDENTITY_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            let maybe_account_id: Option<u64> = transaction
+                .query_row(
+                    "SELECT i.account_id FROM identity i
+                     WHERE i.platform = ?1 AND i.platform_user_id = ?2",
+                    (platform_num, identity.platform_user_id),
+                    |row| row.get(0),
+                )
+                .optional()
+                .expect("query to be successful");
+
+            if let Some(account_id) = maybe_account_id {
+                return account_id;
+            }
+
+            let account_id = identity.platform_user_id;
+            transaction
+                .execute(
+                    "INSERT INTO identity (platform, platform_user_id, account_id, created_at)
+                     VALUES (?, ?, ?, ?)",
+                    (
+                        platform_num,
+                        identity.platform_user_id,
+                        account_id,
+                        Utc::now().timestamp(),
+                    ),
+                )
+                .expect("insertion to be successful");
+            audit(
+                &transaction,
+                platform_num,
+                identity.platform_user_id,
+                None,
+                account_id,
+                "created",
+            );
+
+            transaction.commit().expect("commit to be successful");
+
+            account_id
+        });
+
+        Ok(account_id)
+    }
+}
+
+impl Default for DwAccountResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwAccountResolver {
+    pub fn new() -> DwAccountResolver {
+        DwAccountResolver {}
+    }
+}
+
+/// Moves all identities of the source account to the target account.
+/// Every moved identity is recorded in the audit log together with the reason.
+pub fn merge_accounts(
+    source_account_id: u64,
+    target_account_id: u64,
+    reason: &str,
+) -> Result<(), IdentityMergeError> {
+    if source_account_id == target_account_id {
+        return Err(IdentityMergeError::AlreadyMerged);
+    }
+
+    IDENTITY_DB.with_borrow_mut(|db| {
+        let transaction = db.transaction().expect("transaction to be started");
+
+        let conflicts: u64 = transaction
+            .query_row(
+                "SELECT COUNT(*) FROM identity s
+                 JOIN identity t ON t.platform = s.platform
+                 WHERE s.account_id = ?1 AND t.account_id = ?2",
+                (source_account_id, target_account_id),
+                |row| row.get(0),
+            )
+            .expect("query to be successful");
+        if conflicts > 0 {
+            return Err(IdentityMergeError::Conflict);
+        }
+
+        let moved_identities: Vec<(u8, u64)> = transaction
+            .prepare(
+                "SELECT i.platform, i.platform_user_id FROM identity i WHERE i.account_id = ?1",
+            )
+            .expect("preparation to be successful")
+            .query_map((source_account_id,), |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query to be successful")
+            .filter_map(|identity| identity.ok())
+            .collect();
+
+        transaction
+            .execute(
+                "UPDATE identity SET account_id = ?1 WHERE account_id = ?2",
+                (target_account_id, source_account_id),
+            )
+            .expect("update to be successful");
+
+        for (platform_num, platform_user_id) in moved_identities {
+            audit(
+                &transaction,
+                platform_num,
+                platform_user_id,
+                Some(source_account_id),
+                target_account_id,
+                reason,
+            );
+        }
+
+        transaction.commit().expect("commit to be successful");
+
+        info!("Merged account {source_account_id} into {target_account_id} ({reason})");
+
+        Ok(())
+    })
+}
+
+/// Lets the identity authenticate as the specified account from now on,
+/// moving it away from any account it belonged to before.
+/// The change is recorded in the audit log together with the reason.
+pub fn link_identity(identity: &PlatformIdentity, account_id: u64, reason: &str) {
+    let platform_num = identity.platform.to_u8().unwrap();
+
+    IDENTITY_DB.with_borrow_mut(|db| {
+        let transaction = db.transaction().expect("transaction to be started");
+
+        let previous_account_id: Option<u64> = transaction
+            .query_row(
+                "SELECT i.account_id FROM identity i
+                 WHERE i.platform = ?1 AND i.platform_user_id = ?2",
+                (platform_num, identity.platform_user_id),
+                |row| row.get(0),
+            )
+            .optional()
+            .expect("query to be successful");
+
+        transaction
+            .execute(
+                "INSERT INTO identity (platform, platform_user_id, account_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (platform, platform_user_id) DO UPDATE SET account_id = ?3",
+                (
+                    platform_num,
+                    identity.platform_user_id,
+                    account_id,
+                    Utc::now().timestamp(),
+                ),
+            )
+            .expect("insertion to be successful");
+        audit(
+            &transaction,
+            platform_num,
+            identity.platform_user_id,
+            previous_account_id,
+            account_id,
+            reason,
+        );
+
+        transaction.commit().expect("commit to be successful");
+    });
+}
+
+fn audit(
+    transaction: &Transaction,
+    platform_num: u8,
+    platform_user_id: u64,
+    previous_account_id: Option<u64>,
+    account_id: u64,
+    reason: &str,
+) {
+    transaction
+        .execute(
+            "INSERT INTO identity_audit (timestamp, platform, platform_user_id, previous_account_id, account_id, reason)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            (
+                Utc::now().timestamp(),
+                platform_num,
+                platform_user_id,
+                previous_account_id,
+                account_id,
+                reason,
+            ),
+        )
+        .expect("insertion to be successful");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitdemon::auth::identity::IdentityPlatform;
+
+    fn steam(platform_user_id: u64) -> PlatformIdentity {
+        PlatformIdentity {
+            platform: IdentityPlatform::Steam,
+            platform_user_id,
+        }
+    }
+
+    #[test]
+    fn new_identities_get_own_account() {
+        let resolver = DwAccountResolver::new();
+
+        assert_eq!(resolver.resolve_account(&steam(1)).unwrap(), 1);
+        assert_eq!(resolver.resolve_account(&steam(2)).unwrap(), 2);
+        assert_eq!(resolver.resolve_account(&steam(1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn merging_accounts_of_same_platform_conflicts() {
+        let resolver = DwAccountResolver::new();
+        resolver.resolve_account(&steam(1)).unwrap();
+        resolver.resolve_account(&steam(2)).unwrap();
+
+        assert!(matches!(
+            merge_accounts(2, 1, "test"),
+            Err(IdentityMergeError::Conflict)
+        ));
+        assert!(matches!(
+            merge_accounts(1, 1, "test"),
+            Err(IdentityMergeError::AlreadyMerged)
+        ));
+        assert_eq!(resolver.resolve_account(&steam(2)).unwrap(), 2);
+    }
+
+    #[test]
+    fn merged_identities_resolve_to_target_account() {
+        let resolver = DwAccountResolver::new();
+        resolver.resolve_account(&steam(1)).unwrap();
+
+        merge_accounts(5, 1, "test").unwrap();
+        assert_eq!(resolver.resolve_account(&steam(1)).unwrap(), 1);
+
+        merge_accounts(1, 5, "test").unwrap();
+        assert_eq!(resolver.resolve_account(&steam(1)).unwrap(), 5);
+
+        let audit_entries: u64 = IDENTITY_DB.with_borrow(|db| {
+            db.query_row("SELECT COUNT(*) FROM identity_audit", (), |row| row.get(0))
+                .unwrap()
+        });
+        assert_eq!(audit_entries, 2);
+    }
+}
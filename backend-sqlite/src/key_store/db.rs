@@ -0,0 +1,32 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static KEY_STORE_DB: RefCell<Connection> = RefCell::new(open_db(&KEY_STORE_SCHEMA));
+}
+
+const KEY_STORE_CHANGELOG_0: &str = "
+CREATE TABLE backend_key (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    aes_key BLOB NOT NULL,
+    aes_iv BLOB NOT NULL,
+    valid_until INTEGER NOT NULL
+);
+";
+
+const KEY_STORE_SCHEMA: DbSchema = DbSchema {
+    name: "key_store",
+    changelogs: &[KEY_STORE_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&KEY_STORE_SCHEMA);
+    }
+}
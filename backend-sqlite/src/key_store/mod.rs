@@ -0,0 +1,168 @@
+mod db;
+
+use crate::key_store::db::KEY_STORE_DB;
+use bitdemon::auth::key_store::{AesIv, AesKey, BackendPrivateKey, BackendPrivateKeyStorage};
+use chrono::Utc;
+use log::info;
+use rand::Rng;
+use rusqlite::{Connection, OptionalExtension, Row};
+
+/// How long each key lives.
+const KEY_LIFESPAN: i64 = 15 * 60;
+
+/// How long before the end of its life a key is no longer used for new proofs.
+const KEY_TIMEOUT: i64 = 14 * 60;
+
+/// Keeps the keys that auth proofs are encrypted with in a database,
+/// so that clients stay authenticated when the server restarts.
+/// Anyone with access to the database can forge auth proofs, so it must be kept private.
+pub struct DwKeyStore {}
+
+impl BackendPrivateKeyStorage for DwKeyStore {
+    fn get_current_key(&self) -> BackendPrivateKey {
+        let now = Utc::now().timestamp();
+
+        KEY_STORE_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            let current_key = transaction
+                .query_row(
+                    "SELECT k.aes_key, k.aes_iv, k.valid_until FROM backend_key k
+                     WHERE k.valid_until >= ?1
+                     ORDER BY k.id DESC LIMIT 1",
+                    (now + KEY_TIMEOUT,),
+                    read_key,
+                )
+                .optional()
+                .expect("query to be successful")
+                .flatten();
+
+            if let Some(current_key) = current_key {
+                return current_key;
+            }
+
+            info!("Current key reached end of lifetime, creating a new one");
+
+            let next_key = insert_next_key(&transaction, now);
+            transaction.commit().expect("commit to be successful");
+
+            next_key
+        })
+    }
+
+    fn get_valid_keys(&self) -> Vec<BackendPrivateKey> {
+        let now = Utc::now().timestamp();
+
+        KEY_STORE_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT k.aes_key, k.aes_iv, k.valid_until FROM backend_key k
+                 WHERE k.valid_until >= ?1
+                 ORDER BY k.id DESC",
+            )
+            .expect("preparation to be successful")
+            .query_map((now,), read_key)
+            .expect("query to be successful")
+            .filter_map(|key| key.ok().flatten())
+            .collect()
+        })
+    }
+
+    fn rotate_key(&self) -> BackendPrivateKey {
+        let now = Utc::now().timestamp();
+
+        info!("Rotating current key");
+
+        KEY_STORE_DB.with_borrow(|db| insert_next_key(db, now))
+    }
+
+    fn evict_expired_keys(&self) -> usize {
+        let now = Utc::now().timestamp();
+
+        KEY_STORE_DB.with_borrow(|db| {
+            db.execute("DELETE FROM backend_key WHERE valid_until < ?1", (now,))
+                .expect("deletion to be successful")
+        })
+    }
+}
+
+impl Default for DwKeyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwKeyStore {
+    pub fn new() -> DwKeyStore {
+        DwKeyStore {}
+    }
+}
+
+fn insert_next_key(db: &Connection, now: i64) -> BackendPrivateKey {
+    let mut aes_key: AesKey = [0u8; 32];
+    let mut aes_iv: AesIv = [0u8; 16];
+    rand::rng().fill_bytes(&mut aes_key);
+    rand::rng().fill_bytes(&mut aes_iv);
+    let valid_until = now + KEY_LIFESPAN;
+
+    db.execute(
+        "INSERT INTO backend_key (aes_key, aes_iv, valid_until) VALUES (?, ?, ?)",
+        (aes_key.as_slice(), aes_iv.as_slice(), valid_until),
+    )
+    .expect("insertion to be successful");
+
+    BackendPrivateKey::new(aes_key, aes_iv, valid_until)
+}
+
+/// Reads a key from a row, skipping keys with material of the wrong size.
+fn read_key(row: &Row) -> rusqlite::Result<Option<BackendPrivateKey>> {
+    let aes_key: Vec<u8> = row.get(0)?;
+    let aes_iv: Vec<u8> = row.get(1)?;
+    let valid_until: i64 = row.get(2)?;
+
+    Ok(aes_key
+        .try_into()
+        .ok()
+        .zip(aes_iv.try_into().ok())
+        .map(|(aes_key, aes_iv)| BackendPrivateKey::new(aes_key, aes_iv, valid_until)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_key_is_reused_and_valid() {
+        let key_store = DwKeyStore::new();
+
+        let mut buf = [1u8; 32];
+        key_store.get_current_key().encrypt_data(&mut buf).unwrap();
+        key_store.get_current_key().decrypt_data(&mut buf).unwrap();
+        assert_eq!(buf, [1u8; 32]);
+
+        assert_eq!(key_store.get_valid_keys().len(), 1);
+    }
+
+    #[test]
+    fn rotated_key_becomes_current_and_expired_keys_are_evicted() {
+        let key_store = DwKeyStore::new();
+        let first_key = key_store.get_current_key();
+
+        let rotated_key = key_store.rotate_key();
+        let mut buf = [1u8; 32];
+        rotated_key.encrypt_data(&mut buf).unwrap();
+        key_store.get_current_key().decrypt_data(&mut buf).unwrap();
+        assert_eq!(buf, [1u8; 32]);
+        assert_eq!(key_store.get_valid_keys().len(), 2);
+
+        KEY_STORE_DB.with_borrow(|db| {
+            db.execute(
+                "UPDATE backend_key SET valid_until = 1 WHERE valid_until = ?1 AND id = 1",
+                (first_key.valid_until(),),
+            )
+            .unwrap();
+        });
+        assert_eq!(key_store.evict_expired_keys(), 1);
+        assert_eq!(key_store.evict_expired_keys(), 0);
+        assert_eq!(key_store.get_valid_keys().len(), 1);
+    }
+}
@@ -0,0 +1,14 @@
+//! Implementations of the bitdemon lobby services that persist their data in SQLite databases
+//! and the local file system.
+
+pub mod account;
+pub mod admission;
+pub mod ban;
+pub mod config;
+pub mod db;
+pub mod identity;
+pub mod key_store;
+pub mod lobby;
+pub mod localization;
+pub mod metrics;
+pub mod steam;
@@ -0,0 +1,43 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static ANTI_CHEAT_DB: RefCell<Connection> = RefCell::new(open_db(&ANTI_CHEAT_SCHEMA));
+}
+
+const ANTI_CHEAT_CHANGELOG_0: &str = "
+CREATE TABLE challenge_failure (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    challenge_id INTEGER NOT NULL,
+    failure TEXT NOT NULL,
+    failed_at INTEGER NOT NULL
+);
+
+CREATE INDEX challenge_failure_user ON challenge_failure (user_id);
+
+CREATE TABLE flagged_user (
+    user_id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    failure_count INTEGER NOT NULL,
+    flagged_at INTEGER NOT NULL
+);
+";
+
+const ANTI_CHEAT_SCHEMA: DbSchema = DbSchema {
+    name: "anti_cheat",
+    changelogs: &[ANTI_CHEAT_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&ANTI_CHEAT_SCHEMA);
+    }
+}
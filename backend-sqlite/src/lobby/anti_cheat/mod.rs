@@ -0,0 +1,35 @@
+mod db;
+mod service;
+
+pub use service::DwAntiCheatService;
+
+use crate::config::BackendConfig;
+use bitdemon::lobby::anti_cheat::AntiCheatHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use bitdemon::networking::session_manager::SessionManager;
+use log::warn;
+use std::sync::Arc;
+
+/// Creates the anti cheat handler and challenges every session once it authenticated.
+pub fn create_anti_cheat_handler(
+    config: &BackendConfig,
+    session_manager: Arc<SessionManager>,
+) -> Arc<ThreadSafeLobbyHandler> {
+    let handler = Arc::new(AntiCheatHandler::new(Arc::new(DwAntiCheatService::new(
+        config.anti_cheat.clone(),
+    ))));
+
+    let authenticated_handler = handler.clone();
+    session_manager.on_session_authenticated(move |session| {
+        if let Err(e) = authenticated_handler.issue_challenges(session) {
+            warn!("Failed to issue anti cheat challenges: {e}");
+        }
+    });
+
+    let unregistered_handler = handler.clone();
+    session_manager.on_session_unregistered(move |session| {
+        unregistered_handler.expire_challenges(session);
+    });
+
+    handler
+}
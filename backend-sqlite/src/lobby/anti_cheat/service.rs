@@ -0,0 +1,165 @@
+use crate::config::AntiCheatConfig;
+use crate::lobby::anti_cheat::db::ANTI_CHEAT_DB;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use bitdemon::lobby::anti_cheat::{AntiCheatService, Challenge, ChallengeFailure, IssuedChallenge};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::warn;
+use num_traits::ToPrimitive;
+use rand::seq::IndexedRandom;
+use rand::RngExt;
+
+/// Challenges clients with the configured challenges of their title
+/// and flags users once they failed too many of them.
+pub struct DwAntiCheatService {
+    config: AntiCheatConfig,
+}
+
+impl AntiCheatService for DwAntiCheatService {
+    fn create_challenges(&self, session: &BdSession) -> Vec<IssuedChallenge> {
+        let Some(definitions) = self
+            .config
+            .challenges(session.authentication().unwrap().title)
+        else {
+            return Vec::new();
+        };
+
+        let mut rng = rand::rng();
+        definitions
+            .sample(&mut rng, self.config.challenges_per_session)
+            .filter_map(|definition| {
+                let parameter = BASE64_STANDARD.decode(&definition.parameter);
+                let expected_answer = BASE64_STANDARD.decode(&definition.answer);
+                if parameter.is_err() || expected_answer.is_err() {
+                    warn!("Skipping anti cheat challenge that is not valid base64");
+                    return None;
+                }
+
+                Some(IssuedChallenge {
+                    challenge: Challenge {
+                        challenge_id: rng.random(),
+                        parameter: parameter.unwrap(),
+                    },
+                    expected_answer: expected_answer.unwrap(),
+                })
+            })
+            .collect()
+    }
+
+    fn record_failure(&self, session: &BdSession, challenge_id: u32, failure: ChallengeFailure) {
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let user_id = authentication.user_id;
+        let now = Utc::now().timestamp();
+
+        ANTI_CHEAT_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            transaction
+                .execute(
+                    "INSERT INTO challenge_failure (title, user_id, challenge_id, failure, failed_at)
+                     VALUES (?, ?, ?, ?, ?)",
+                    (title_num, user_id, challenge_id, format!("{failure:?}"), now),
+                )
+                .expect("insertion to be successful");
+
+            let failure_count: u32 = transaction
+                .query_row(
+                    "SELECT COUNT(*) FROM challenge_failure f WHERE f.user_id = ?1",
+                    (user_id,),
+                    |row| row.get(0),
+                )
+                .expect("query to be successful");
+
+            if failure_count >= self.config.flag_threshold {
+                transaction
+                    .execute(
+                        "INSERT INTO flagged_user (user_id, title, failure_count, flagged_at)
+                         VALUES (?1, ?2, ?3, ?4)
+                         ON CONFLICT (user_id) DO UPDATE SET failure_count = ?3",
+                        (user_id, title_num, failure_count, now),
+                    )
+                    .expect("insertion to be successful");
+
+                if failure_count == self.config.flag_threshold {
+                    warn!(
+                        "Flagged user {user_id} ({}) after {failure_count} failed anti cheat challenges",
+                        authentication.username
+                    );
+                }
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+    }
+}
+
+impl DwAntiCheatService {
+    pub fn new(config: AntiCheatConfig) -> DwAntiCheatService {
+        DwAntiCheatService { config }
+    }
+
+    /// Whether the user failed so many challenges that they were flagged.
+    pub fn is_flagged(&self, user_id: u64) -> bool {
+        ANTI_CHEAT_DB.with_borrow(|db| {
+            db.query_row(
+                "SELECT COUNT(*) FROM flagged_user u WHERE u.user_id = ?1",
+                (user_id,),
+                |row| row.get::<_, u32>(0),
+            )
+            .expect("query to be successful")
+                > 0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChallengeDefinition;
+    use crate::db::test_util::authenticated_session;
+    use bitdemon::domain::title::Title;
+
+    fn service() -> DwAntiCheatService {
+        let mut config = AntiCheatConfig {
+            flag_threshold: 2,
+            ..AntiCheatConfig::default()
+        };
+        config.titles.insert(
+            Title::T6Pc.to_u32().unwrap(),
+            vec![ChallengeDefinition {
+                parameter: BASE64_STANDARD.encode([1, 2]),
+                answer: BASE64_STANDARD.encode([3, 4]),
+            }],
+        );
+
+        DwAntiCheatService::new(config)
+    }
+
+    #[test]
+    fn creates_challenges_of_title() {
+        let service = service();
+
+        let challenges = service.create_challenges(&authenticated_session(1, Title::T6Pc));
+        assert_eq!(challenges.len(), 1);
+        assert_eq!(challenges[0].challenge.parameter, vec![1, 2]);
+        assert_eq!(challenges[0].expected_answer, vec![3, 4]);
+        assert!(service
+            .create_challenges(&authenticated_session(1, Title::Iw5))
+            .is_empty());
+    }
+
+    #[test]
+    fn flags_user_after_too_many_failures() {
+        let service = service();
+        let session = authenticated_session(1, Title::T6Pc);
+
+        service.record_failure(&session, 1, ChallengeFailure::WrongAnswer);
+        assert!(!service.is_flagged(1));
+
+        service.record_failure(&session, 2, ChallengeFailure::Unanswered);
+        assert!(service.is_flagged(1));
+        assert!(!service.is_flagged(2));
+    }
+}
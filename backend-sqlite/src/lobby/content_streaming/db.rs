@@ -1,16 +1,15 @@
-﻿use bitdemon::domain::title::Title;
+﻿use crate::db::{open_db, DbSchema};
+use bitdemon::domain::title::Title;
 use bitdemon::lobby::content_streaming::{CategoryId, StreamSlot, StreamTag};
 use chrono::Utc;
-use log::info;
 use num_traits::ToPrimitive;
 use rusqlite::types::Value;
 use rusqlite::{Connection, DropBehavior, Row};
 use std::cell::RefCell;
-use std::fs::create_dir_all;
 use std::rc::Rc;
 
 thread_local! {
-    pub static CONTENT_STREAMING_DB: RefCell<Connection> = RefCell::new(initialized_db());
+    pub static CONTENT_STREAMING_DB: RefCell<Connection> = RefCell::new(open_db(&CONTENT_STREAMING_SCHEMA));
 }
 
 const CONTENT_STREAMING_CHANGELOG_0: &str = "
@@ -42,32 +41,10 @@ CREATE UNIQUE INDEX user_stream_title_owner_id_slot_unq ON user_stream (
 );
 ";
 
-fn initialized_db() -> Connection {
-    create_dir_all("db").expect("to be able to create dir");
-
-    let conn = Connection::open("db/content_streaming.db")
-        .expect("expected db connection to be able to open");
-
-    conn.execute("PRAGMA foreign_keys = ON", ())
-        .expect("foreign keys to be able to be set");
-
-    rusqlite::vtab::array::load_module(&conn).expect("array extension to be loadable");
-
-    let version: u64 = conn
-        .query_row("PRAGMA user_version", (), |row| row.get(0))
-        .expect("Version to be available");
-    if version < 1 {
-        conn.execute_batch(CONTENT_STREAMING_CHANGELOG_0)
-            .expect("Initialization to succeed");
-
-        conn.execute("PRAGMA user_version = 1", ())
-            .expect("Setting pragma to succeed");
-
-        info!("Initialized content streaming db");
-    }
-
-    conn
-}
+const CONTENT_STREAMING_SCHEMA: DbSchema = DbSchema {
+    name: "content_streaming",
+    changelogs: &[CONTENT_STREAMING_CHANGELOG_0],
+};
 
 pub struct PersistedStreamInfo {
     pub id: u64,
@@ -523,3 +500,14 @@ fn map_tag(row: &Row) -> rusqlite::Result<StreamTag> {
         secondary: row.get(1)?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&CONTENT_STREAMING_SCHEMA);
+    }
+}
@@ -0,0 +1,6 @@
+﻿mod db;
+mod publisher_file;
+mod user_file;
+
+pub use publisher_file::DwPublisherContentStreamingService;
+pub use user_file::{DwUserContentStreamingService, UserFileClaimOperation, UserFileClaims};
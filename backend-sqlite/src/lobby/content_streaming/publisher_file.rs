@@ -1,4 +1,4 @@
-﻿use crate::config::DwServerConfig;
+﻿use crate::config::BackendConfig;
 use bitdemon::domain::result_slice::ResultSlice;
 use bitdemon::domain::title::Title;
 use bitdemon::lobby::content_streaming::{
@@ -12,12 +12,14 @@ use std::collections::HashMap;
 use std::fs;
 use std::fs::DirEntry;
 use std::ops::Sub;
+use std::path::PathBuf;
 use std::sync::{RwLock, RwLockReadGuard};
 use std::time::UNIX_EPOCH;
 
 pub struct DwPublisherContentStreamingService {
     content_server_hostname: String,
     content_server_port: u16,
+    publisher_stream_dir: PathBuf,
     publisher_streams: RwLock<HashMap<Title, PublisherStreamState>>,
 }
 
@@ -115,12 +117,13 @@ impl PublisherContentStreamingService for DwPublisherContentStreamingService {
 }
 
 impl DwPublisherContentStreamingService {
-    pub fn new(config: &DwServerConfig) -> DwPublisherContentStreamingService {
+    pub fn new(config: &BackendConfig) -> DwPublisherContentStreamingService {
         let state_map = HashMap::new();
 
         DwPublisherContentStreamingService {
-            content_server_hostname: config.hostname().to_string(),
-            content_server_port: config.content_port(),
+            content_server_hostname: config.content_server_hostname.clone(),
+            content_server_port: config.content_server_port,
+            publisher_stream_dir: config.publisher_stream_dir.clone(),
             publisher_streams: RwLock::new(state_map),
         }
     }
@@ -136,6 +139,16 @@ impl DwPublisherContentStreamingService {
             .cloned()
     }
 
+    /// The path of the file containing the data of a publisher stream.
+    pub fn stream_file_path(&self, title: Title, filename: &str) -> PathBuf {
+        self.title_dir(title).join(filename)
+    }
+
+    fn title_dir(&self, title: Title) -> PathBuf {
+        self.publisher_stream_dir
+            .join(title.to_u32().unwrap().to_string())
+    }
+
     fn read_publisher_streams(
         &self,
         title: Title,
@@ -202,8 +215,7 @@ impl PublisherStreamState {
     }
 
     fn refresh(&mut self, service: &DwPublisherContentStreamingService) {
-        let dir_name = format!("stream/publisher/{}", self.title.to_u32().unwrap());
-        if let Ok(dir) = fs::read_dir(dir_name) {
+        if let Ok(dir) = fs::read_dir(service.title_dir(self.title)) {
             dir.filter_map(|entry| entry.ok())
                 .for_each(|entry| self.handle_entry(service, entry));
         }
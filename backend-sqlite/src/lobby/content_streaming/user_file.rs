@@ -1,9 +1,10 @@
-﻿use crate::config::DwServerConfig;
+﻿use crate::config::{BackendConfig, UserFileSizeLimits};
 use crate::lobby::content_streaming::db::{
     create_empty_stream, delete_db_stream, get_slot_count_for_upload, get_stream_data,
     get_stream_id_for_slot, get_streams_by_ids, get_streams_by_owners, record_user_name,
     set_stream_data, set_stream_metadata, PersistedStreamInfo,
 };
+use crate::localization::remember_user_locale;
 use bitdemon::domain::result_slice::ResultSlice;
 use bitdemon::domain::title::Title;
 use bitdemon::lobby::content_streaming::{
@@ -44,13 +45,13 @@ pub struct UserFileClaims {
 pub struct DwUserContentStreamingService {
     content_server_hostname: String,
     content_server_port: u16,
+    file_size_limits: UserFileSizeLimits,
     encoding_key: EncodingKey,
     pub decoding_key: DecodingKey,
 }
 
 const CLAIM_LIFETIME_IN_SECONDS: i64 = 5 * 60; // 5min
 const MAX_FILENAME_LENGTH: usize = 260;
-const MAX_USER_FILE_SIZE: usize = 50_000; // 50KB
 const MAX_METADATA_SIZE: usize = 50_000; // 50KB
 const MAX_SLOT_COUNT: usize = 128;
 
@@ -117,7 +118,11 @@ impl UserContentStreamingService for DwUserContentStreamingService {
     ) -> Result<StreamUrl, ContentStreamingServiceError> {
         info!("Requesting stream upload request={request_data:?}");
 
-        if request_data.file_size as usize > MAX_USER_FILE_SIZE {
+        let authentication = session
+            .authentication()
+            .expect("session to be authentication checked");
+
+        if request_data.file_size as usize > self.max_stream_size(authentication.title) {
             return Err(ContentStreamingServiceError::StorageSpaceExceeded);
         }
 
@@ -125,10 +130,6 @@ impl UserContentStreamingService for DwUserContentStreamingService {
             return Err(ContentStreamingServiceError::StorageSpaceExceeded);
         }
 
-        let authentication = session
-            .authentication()
-            .expect("session to be authentication checked");
-
         let slot_count_for_upload = get_slot_count_for_upload(
             authentication.title,
             authentication.user_id,
@@ -150,6 +151,7 @@ impl UserContentStreamingService for DwUserContentStreamingService {
         );
 
         record_user_name(authentication.user_id, authentication.username.as_str());
+        remember_user_locale(authentication.user_id, &request_data.client_locale);
 
         Ok(self.build_stream_url(
             authentication.user_id,
@@ -209,7 +211,7 @@ impl UserContentStreamingService for DwUserContentStreamingService {
 }
 
 impl DwUserContentStreamingService {
-    pub fn new(config: &DwServerConfig) -> DwUserContentStreamingService {
+    pub fn new(config: &BackendConfig) -> DwUserContentStreamingService {
         let mut random = [0u8; 128];
         rand::rng().fill_bytes(&mut random);
 
@@ -217,13 +219,24 @@ impl DwUserContentStreamingService {
         let decoding_key = DecodingKey::from_secret(&random);
 
         DwUserContentStreamingService {
-            content_server_hostname: config.hostname().to_string(),
-            content_server_port: config.content_port(),
+            content_server_hostname: config.content_server_hostname.clone(),
+            content_server_port: config.content_server_port,
+            file_size_limits: config.user_file_size_limits.clone(),
             encoding_key,
             decoding_key,
         }
     }
 
+    /// The maximum size of a user stream of the specified title.
+    pub fn max_stream_size(&self, title: Title) -> usize {
+        self.file_size_limits.streaming_limit(title)
+    }
+
+    /// The largest user stream any title may upload.
+    pub fn max_stream_size_of_any_title(&self) -> usize {
+        self.file_size_limits.max_streaming_limit()
+    }
+
     pub fn stream_by_id(&self, title: Title, stream_id: u64) -> Option<Vec<u8>> {
         get_stream_data(title, stream_id)
     }
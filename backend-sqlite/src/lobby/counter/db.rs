@@ -0,0 +1,51 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static COUNTER_DB: RefCell<Connection> = RefCell::new(open_db(&COUNTER_SCHEMA));
+}
+
+const COUNTER_CHANGELOG_0: &str = "
+CREATE TABLE counter (
+    title INTEGER NOT NULL,
+    counter_id INTEGER NOT NULL,
+    value INTEGER NOT NULL,
+    PRIMARY KEY (title, counter_id)
+);
+
+CREATE TABLE counter_window (
+    title INTEGER NOT NULL,
+    window TEXT NOT NULL,
+    period_start INTEGER NOT NULL,
+    PRIMARY KEY (title, window)
+);
+
+CREATE TABLE counter_snapshot (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    counter_id INTEGER NOT NULL,
+    window TEXT NOT NULL,
+    period_start INTEGER NOT NULL,
+    period_end INTEGER NOT NULL,
+    value INTEGER NOT NULL
+);
+
+CREATE INDEX counter_snapshot_counter ON counter_snapshot (title, counter_id);
+";
+
+const COUNTER_SCHEMA: DbSchema = DbSchema {
+    name: "counter",
+    changelogs: &[COUNTER_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&COUNTER_SCHEMA);
+    }
+}
@@ -1,6 +1,10 @@
-﻿mod service;
+﻿mod db;
+mod service;
+mod window;
+
+pub use service::DwCounterService;
+pub use window::roll_over_counter_windows;
 
-use crate::lobby::counter::service::DwCounterService;
 use bitdemon::lobby::counter::CounterHandler;
 use bitdemon::lobby::ThreadSafeLobbyHandler;
 use std::sync::Arc;
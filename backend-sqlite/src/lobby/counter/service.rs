@@ -0,0 +1,126 @@
+use crate::lobby::counter::db::COUNTER_DB;
+use bitdemon::lobby::counter::{CounterIncrement, CounterService, CounterValue};
+use bitdemon::networking::bd_session::BdSession;
+use log::info;
+use num_traits::ToPrimitive;
+use rusqlite::OptionalExtension;
+use std::error::Error;
+
+/// Persists the counters of each title.
+/// The values are reset by [`super::roll_over_counter_windows`] once their window elapsed.
+pub struct DwCounterService {}
+
+impl CounterService for DwCounterService {
+    fn get_counter_totals(
+        &self,
+        session: &BdSession,
+        counter_ids: Vec<u32>,
+    ) -> Result<Vec<CounterValue>, Box<dyn Error>> {
+        info!(
+            "Retrieving counter totals for {} counters",
+            counter_ids.len()
+        );
+
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+
+        COUNTER_DB.with_borrow(|db| {
+            let mut statement = db
+                .prepare_cached(
+                    "SELECT c.value FROM counter c WHERE c.title = ?1 AND c.counter_id = ?2",
+                )
+                .expect("preparation to be successful");
+
+            let mut result = Vec::with_capacity(counter_ids.len());
+            for counter_id in counter_ids {
+                let counter_value = statement
+                    .query_row((title_num, counter_id), |row| row.get(0))
+                    .optional()
+                    .expect("query to be successful")
+                    .unwrap_or(0);
+
+                result.push(CounterValue {
+                    counter_id,
+                    counter_value,
+                })
+            }
+
+            Ok(result)
+        })
+    }
+
+    fn increment_counters(
+        &self,
+        session: &BdSession,
+        increments: Vec<CounterIncrement>,
+    ) -> Result<(), Box<dyn Error>> {
+        info!(
+            "Incrementing counter totals for {} counters",
+            increments.len()
+        );
+
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+
+        COUNTER_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for increment in increments {
+                transaction
+                    .execute(
+                        "INSERT INTO counter (title, counter_id, value) VALUES (?1, ?2, ?3)
+                         ON CONFLICT (title, counter_id) DO UPDATE SET value = value + ?3",
+                        (title_num, increment.counter_id, increment.counter_increment),
+                    )
+                    .expect("upsert to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+}
+
+impl Default for DwCounterService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwCounterService {
+    pub fn new() -> DwCounterService {
+        DwCounterService {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use bitdemon::domain::title::Title;
+
+    #[test]
+    fn counters_are_separated_by_title() {
+        let service = DwCounterService::new();
+        let t6_session = authenticated_session(1, Title::T6Pc);
+        let iw5_session = authenticated_session(1, Title::Iw5);
+
+        for _ in 0..2 {
+            service
+                .increment_counters(
+                    &t6_session,
+                    vec![CounterIncrement {
+                        counter_id: 7,
+                        counter_increment: 5,
+                    }],
+                )
+                .unwrap();
+        }
+
+        let t6_totals = service.get_counter_totals(&t6_session, vec![7, 8]).unwrap();
+        assert_eq!(t6_totals[0].counter_value, 10);
+        assert_eq!(t6_totals[1].counter_value, 0);
+
+        let iw5_totals = service.get_counter_totals(&iw5_session, vec![7]).unwrap();
+        assert_eq!(iw5_totals[0].counter_value, 0);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::config::{CounterConfig, CounterWindow};
+use crate::lobby::counter::db::COUNTER_DB;
+use chrono::{DateTime, Datelike, Days, Utc};
+use log::info;
+use rusqlite::types::Value;
+use rusqlite::OptionalExtension;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Snapshots and resets all counters whose window elapsed since the last roll over.
+/// Windows that are rolled over for the first time only remember their current period.
+pub fn roll_over_counter_windows(config: &CounterConfig, now: DateTime<Utc>) {
+    for (title_num, windows) in &config.titles {
+        let mut counters_by_window: HashMap<CounterWindow, Vec<u32>> = HashMap::new();
+        for (counter_id, window) in windows {
+            counters_by_window
+                .entry(*window)
+                .or_default()
+                .push(*counter_id);
+        }
+
+        for (window, counter_ids) in counters_by_window {
+            if let Some(period_start) = period_start(window, now) {
+                roll_over_window(*title_num, window, &counter_ids, period_start);
+            }
+        }
+    }
+}
+
+/// The start of the period of the window that contains the specified time,
+/// or `None` if the window never ends.
+fn period_start(window: CounterWindow, now: DateTime<Utc>) -> Option<i64> {
+    let today = now.date_naive();
+    let start = match window {
+        CounterWindow::Daily => today,
+        CounterWindow::Weekly => today
+            .checked_sub_days(Days::new(today.weekday().num_days_from_monday() as u64))
+            .unwrap(),
+        CounterWindow::AllTime => return None,
+    };
+
+    Some(start.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+}
+
+fn roll_over_window(title_num: u32, window: CounterWindow, counter_ids: &[u32], period_start: i64) {
+    let window_name = format!("{window:?}");
+
+    COUNTER_DB.with_borrow_mut(|db| {
+        let transaction = db.transaction().expect("transaction to be started");
+
+        let previous_start: Option<i64> = transaction
+            .query_row(
+                "SELECT w.period_start FROM counter_window w WHERE w.title = ?1 AND w.window = ?2",
+                (title_num, &window_name),
+                |row| row.get(0),
+            )
+            .optional()
+            .expect("query to be successful");
+
+        if previous_start.is_some_and(|previous_start| previous_start >= period_start) {
+            return;
+        }
+
+        if let Some(previous_start) = previous_start {
+            let ids: Rc<Vec<Value>> = Rc::new(
+                counter_ids
+                    .iter()
+                    .map(|id| Value::Integer(*id as i64))
+                    .collect(),
+            );
+
+            let snapshot_count = transaction
+                .execute(
+                    "INSERT INTO counter_snapshot (title, counter_id, window, period_start, period_end, value)
+                     SELECT c.title, c.counter_id, ?2, ?3, ?4, c.value FROM counter c
+                     WHERE c.title = ?1 AND c.counter_id IN rarray(?5)",
+                    (title_num, &window_name, previous_start, period_start, ids.clone()),
+                )
+                .expect("insertion to be successful");
+
+            transaction
+                .execute(
+                    "UPDATE counter SET value = 0 WHERE title = ?1 AND counter_id IN rarray(?2)",
+                    (title_num, ids),
+                )
+                .expect("update to be successful");
+
+            info!("Rolled over {snapshot_count} {window_name} counters of title {title_num}");
+        }
+
+        transaction
+            .execute(
+                "INSERT INTO counter_window (title, window, period_start) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (title, window) DO UPDATE SET period_start = ?3",
+                (title_num, &window_name, period_start),
+            )
+            .expect("upsert to be successful");
+
+        transaction.commit().expect("commit to be successful");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use crate::lobby::counter::DwCounterService;
+    use bitdemon::domain::title::Title;
+    use bitdemon::lobby::counter::{CounterIncrement, CounterService};
+    use chrono::TimeZone;
+    use num_traits::ToPrimitive;
+
+    #[test]
+    fn weekly_periods_start_on_monday() {
+        // A wednesday afternoon
+        let now = Utc.with_ymd_and_hms(2024, 5, 15, 15, 30, 0).unwrap();
+
+        assert_eq!(
+            period_start(CounterWindow::Daily, now),
+            Some(
+                Utc.with_ymd_and_hms(2024, 5, 15, 0, 0, 0)
+                    .unwrap()
+                    .timestamp()
+            )
+        );
+        assert_eq!(
+            period_start(CounterWindow::Weekly, now),
+            Some(
+                Utc.with_ymd_and_hms(2024, 5, 13, 0, 0, 0)
+                    .unwrap()
+                    .timestamp()
+            )
+        );
+        assert_eq!(period_start(CounterWindow::AllTime, now), None);
+    }
+
+    #[test]
+    fn elapsed_windows_are_snapshot_and_reset() {
+        let title = Title::T6Pc;
+        let mut config = CounterConfig::default();
+        config.titles.insert(
+            title.to_u32().unwrap(),
+            HashMap::from([(1, CounterWindow::Daily), (2, CounterWindow::AllTime)]),
+        );
+
+        let service = DwCounterService::new();
+        let session = authenticated_session(1, title);
+        let increments = || {
+            vec![
+                CounterIncrement {
+                    counter_id: 1,
+                    counter_increment: 3,
+                },
+                CounterIncrement {
+                    counter_id: 2,
+                    counter_increment: 4,
+                },
+            ]
+        };
+
+        let day_one = Utc.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap();
+        roll_over_counter_windows(&config, day_one);
+        service.increment_counters(&session, increments()).unwrap();
+
+        roll_over_counter_windows(&config, day_one + Days::new(1));
+        service.increment_counters(&session, increments()).unwrap();
+
+        let totals = service.get_counter_totals(&session, vec![1, 2]).unwrap();
+        assert_eq!(totals[0].counter_value, 3);
+        assert_eq!(totals[1].counter_value, 8);
+
+        let snapshot: (u32, i64) = COUNTER_DB.with_borrow(|db| {
+            db.query_row(
+                "SELECT s.counter_id, s.value FROM counter_snapshot s",
+                (),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+        });
+        assert_eq!(snapshot, (1, 3));
+    }
+}
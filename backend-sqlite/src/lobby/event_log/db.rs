@@ -0,0 +1,36 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static EVENT_LOG_DB: RefCell<Connection> = RefCell::new(open_db(&EVENT_LOG_SCHEMA));
+}
+
+const EVENT_LOG_CHANGELOG_0: &str = "
+CREATE TABLE event (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    category INTEGER NOT NULL,
+    is_binary INTEGER NOT NULL,
+    payload BLOB NOT NULL
+);
+CREATE INDEX event_title_user ON event (title, user_id);
+";
+
+const EVENT_LOG_SCHEMA: DbSchema = DbSchema {
+    name: "event_log",
+    changelogs: &[EVENT_LOG_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&EVENT_LOG_SCHEMA);
+    }
+}
@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwEventLogService;
+
+use bitdemon::lobby::event_log::EventLogHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_event_log_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(EventLogHandler::new(Arc::new(DwEventLogService::new())))
+}
@@ -0,0 +1,67 @@
+use crate::lobby::event_log::db::EVENT_LOG_DB;
+use bitdemon::lobby::event_log::{Event, EventLogService, EventPayload};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::info;
+use num_traits::ToPrimitive;
+use rusqlite::types::Value;
+use std::error::Error;
+
+pub struct DwEventLogService {}
+
+impl EventLogService for DwEventLogService {
+    fn record_events(&self, session: &BdSession, events: Vec<Event>) -> Result<(), Box<dyn Error>> {
+        info!("Recording {} events", events.len());
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+
+        EVENT_LOG_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            {
+                let mut statement = transaction
+                    .prepare(
+                        "INSERT INTO event (title, user_id, timestamp, category, is_binary, payload)
+                         VALUES (?, ?, ?, ?, ?, ?)",
+                    )
+                    .expect("preparation to be successful");
+
+                for event in events {
+                    let (is_binary, payload) = match event.payload {
+                        EventPayload::Text(text) => (false, Value::Text(text)),
+                        EventPayload::Binary(data) => (true, Value::Blob(data)),
+                    };
+
+                    statement
+                        .execute((
+                            title_num,
+                            authentication.user_id,
+                            now,
+                            event.category_id,
+                            is_binary,
+                            payload,
+                        ))
+                        .expect("insertion to be successful");
+                }
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+}
+
+impl Default for DwEventLogService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwEventLogService {
+    pub fn new() -> DwEventLogService {
+        DwEventLogService {}
+    }
+}
@@ -0,0 +1,34 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static FACEBOOK_DB: RefCell<Connection> = RefCell::new(open_db(&FACEBOOK_SCHEMA));
+}
+
+const FACEBOOK_CHANGELOG_0: &str = "
+CREATE TABLE facebook_account (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    access_token TEXT NOT NULL,
+    name TEXT NOT NULL,
+    linked_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id)
+);
+";
+
+const FACEBOOK_SCHEMA: DbSchema = DbSchema {
+    name: "facebook",
+    changelogs: &[FACEBOOK_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&FACEBOOK_SCHEMA);
+    }
+}
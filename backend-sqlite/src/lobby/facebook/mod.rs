@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwFacebookService;
+
+use bitdemon::lobby::facebook::FacebookHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_facebook_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(FacebookHandler::new(Arc::new(DwFacebookService::new())))
+}
@@ -0,0 +1,137 @@
+use crate::lobby::facebook::db::FACEBOOK_DB;
+use bitdemon::lobby::facebook::{FacebookAccountInfo, FacebookService, FacebookServiceError};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::info;
+use num_traits::ToPrimitive;
+use rusqlite::OptionalExtension;
+
+/// Remembers registered Facebook accounts without talking to Facebook.
+/// Sharing content is answered as unavailable.
+pub struct DwFacebookService {}
+
+impl FacebookService for DwFacebookService {
+    fn register_account(
+        &self,
+        session: &BdSession,
+        access_token: String,
+    ) -> Result<(), FacebookServiceError> {
+        if access_token.is_empty() {
+            return Err(FacebookServiceError::AuthAttemptFailedError);
+        }
+        if Self::registered_name(session).is_some() {
+            return Err(FacebookServiceError::AccountAlreadyRegisteredError);
+        }
+
+        info!("Registering facebook account");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        FACEBOOK_DB.with_borrow(|db| {
+            db.execute(
+                "INSERT INTO facebook_account (title, user_id, access_token, name, linked_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                (
+                    title_num,
+                    authentication.user_id,
+                    access_token.as_str(),
+                    authentication.username.as_str(),
+                    Utc::now().timestamp(),
+                ),
+            )
+            .expect("insertion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn unregister_account(&self, session: &BdSession) -> Result<(), FacebookServiceError> {
+        info!("Unregistering facebook account");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        FACEBOOK_DB.with_borrow(|db| {
+            db.execute(
+                "DELETE FROM facebook_account WHERE title = ?1 AND user_id = ?2",
+                (title_num, authentication.user_id),
+            )
+            .expect("deletion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn is_registered(&self, session: &BdSession) -> Result<bool, FacebookServiceError> {
+        Ok(Self::registered_name(session).is_some())
+    }
+
+    fn get_info(&self, session: &BdSession) -> Result<FacebookAccountInfo, FacebookServiceError> {
+        let name =
+            Self::registered_name(session).ok_or(FacebookServiceError::NotRegisteredError)?;
+
+        Ok(FacebookAccountInfo { name })
+    }
+
+    fn get_registered_accounts(
+        &self,
+        session: &BdSession,
+    ) -> Result<Vec<FacebookAccountInfo>, FacebookServiceError> {
+        Ok(Self::registered_name(session)
+            .map(|name| FacebookAccountInfo { name })
+            .into_iter()
+            .collect())
+    }
+
+    fn post(&self, session: &BdSession, _message: String) -> Result<(), FacebookServiceError> {
+        Self::require_registered(session)?;
+
+        Err(FacebookServiceError::UnavailableError)
+    }
+
+    fn upload_photo(&self, session: &BdSession, _file_id: u64) -> Result<(), FacebookServiceError> {
+        Self::require_registered(session)?;
+
+        Err(FacebookServiceError::UnavailableError)
+    }
+
+    fn upload_video(&self, session: &BdSession, _file_id: u64) -> Result<(), FacebookServiceError> {
+        Self::require_registered(session)?;
+
+        Err(FacebookServiceError::UnavailableError)
+    }
+}
+
+impl Default for DwFacebookService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwFacebookService {
+    pub fn new() -> DwFacebookService {
+        DwFacebookService {}
+    }
+
+    fn require_registered(session: &BdSession) -> Result<(), FacebookServiceError> {
+        Self::registered_name(session)
+            .map(|_| ())
+            .ok_or(FacebookServiceError::NotRegisteredError)
+    }
+
+    fn registered_name(session: &BdSession) -> Option<String> {
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        FACEBOOK_DB.with_borrow(|db| {
+            db.query_row(
+                "SELECT f.name FROM facebook_account f WHERE f.title = ?1 AND f.user_id = ?2",
+                (title_num, authentication.user_id),
+                |row| row.get(0),
+            )
+            .optional()
+            .expect("query to be successful")
+        })
+    }
+}
@@ -5,8 +5,8 @@ use std::sync::Arc;
 
 mod service;
 
+pub use service::DwGroupService;
+
 pub fn create_group_handler(session_manager: Arc<SessionManager>) -> Arc<ThreadSafeLobbyHandler> {
-    Arc::new(GroupHandler::new(service::DwGroupService::new(
-        session_manager,
-    )))
+    Arc::new(GroupHandler::new(DwGroupService::new(session_manager)))
 }
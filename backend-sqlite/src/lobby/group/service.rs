@@ -1,16 +1,17 @@
 ﻿use bitdemon::lobby::group::GroupService;
-use bitdemon::networking::bd_session::{BdSession, SessionId};
+use bitdemon::networking::bd_session::BdSession;
 use bitdemon::networking::session_manager::SessionManager;
 use log::{error, info};
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, RwLock};
 
 type GroupId = u32;
 
+const SESSION_GROUPS_KEY: &str = "groups";
+
 pub struct DwGroupService {
     aggregated_group_counts: RwLock<HashMap<GroupId, u64>>,
-    session_groups: Mutex<HashMap<SessionId, Vec<GroupId>>>,
 }
 
 impl GroupService for DwGroupService {
@@ -37,19 +38,15 @@ impl GroupService for DwGroupService {
     fn set_groups(&self, session: &BdSession, groups: &[u32]) -> Result<(), Box<dyn Error>> {
         info!("Setting {} groups for session", groups.len());
 
-        let previous_groups: HashSet<GroupId>;
-        let groups_clone = groups.to_vec();
-
-        {
-            let mut session_groups = self.session_groups.lock().unwrap();
-
-            previous_groups = session_groups
-                .remove(&session.id)
-                .map(HashSet::from_iter)
-                .unwrap_or_default();
+        let previous_groups: HashSet<GroupId> = session
+            .scratch()
+            .take::<Vec<GroupId>>(SESSION_GROUPS_KEY)
+            .map(HashSet::from_iter)
+            .unwrap_or_default();
 
-            session_groups.insert(session.id, groups_clone);
-        }
+        session
+            .scratch()
+            .insert(SESSION_GROUPS_KEY, groups.to_vec());
 
         let new_groups: HashSet<GroupId> = HashSet::from_iter(
             groups
@@ -89,7 +86,6 @@ impl DwGroupService {
     pub fn new(session_manager: Arc<SessionManager>) -> Arc<DwGroupService> {
         let service = Arc::new(DwGroupService {
             aggregated_group_counts: RwLock::new(HashMap::new()),
-            session_groups: Mutex::new(HashMap::new()),
         });
 
         Self::register_session_manager_callbacks(service.clone(), session_manager);
@@ -102,18 +98,12 @@ impl DwGroupService {
         session_manager: Arc<SessionManager>,
     ) {
         session_manager.on_session_unregistered(move |session| {
-            service.remove_all_groups_for_session(session.id);
+            service.remove_all_groups_for_session(session);
         });
     }
 
-    fn remove_all_groups_for_session(&self, session_id: SessionId) {
-        let maybe_groups;
-        {
-            let mut session_groups = self.session_groups.lock().unwrap();
-            maybe_groups = session_groups.remove(&session_id);
-        }
-
-        if let Some(groups) = maybe_groups {
+    fn remove_all_groups_for_session(&self, session: &BdSession) {
+        if let Some(groups) = session.scratch().take::<Vec<GroupId>>(SESSION_GROUPS_KEY) {
             info!("Removing {} groups due to disconnect", groups.len());
             let mut aggregated_group_counts = self.aggregated_group_counts.write().unwrap();
 
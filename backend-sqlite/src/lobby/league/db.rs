@@ -0,0 +1,59 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static LEAGUE_DB: RefCell<Connection> = RefCell::new(open_db(&LEAGUE_SCHEMA));
+}
+
+const LEAGUE_CHANGELOG_0: &str = "
+CREATE TABLE team (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    member_key TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_active_at INTEGER NOT NULL,
+    UNIQUE (title, member_key)
+);
+CREATE TABLE team_member (
+    team_id INTEGER NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL,
+    username TEXT NOT NULL,
+    PRIMARY KEY (team_id, user_id)
+);
+CREATE INDEX team_member_user ON team_member (user_id);
+CREATE TABLE subdivision (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title INTEGER NOT NULL,
+    league_id INTEGER NOT NULL,
+    season_id INTEGER NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE INDEX subdivision_league ON subdivision (title, league_id, season_id);
+CREATE TABLE team_subdivision (
+    team_id INTEGER NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    league_id INTEGER NOT NULL,
+    season_id INTEGER NOT NULL,
+    subdivision_id INTEGER NOT NULL REFERENCES subdivision(id),
+    placed_at INTEGER NOT NULL,
+    PRIMARY KEY (team_id, league_id, season_id)
+);
+CREATE INDEX team_subdivision_subdivision ON team_subdivision (subdivision_id);
+";
+
+const LEAGUE_SCHEMA: DbSchema = DbSchema {
+    name: "league",
+    changelogs: &[LEAGUE_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&LEAGUE_SCHEMA);
+    }
+}
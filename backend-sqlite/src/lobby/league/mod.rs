@@ -0,0 +1,15 @@
+mod db;
+mod service;
+
+pub use service::DwLeagueService;
+
+use crate::config::BackendConfig;
+use bitdemon::lobby::league::LeagueHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_league_handler(config: &BackendConfig) -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(LeagueHandler::new(Arc::new(DwLeagueService::new(
+        config.league.clone(),
+    ))))
+}
@@ -0,0 +1,543 @@
+use crate::config::LeagueConfig;
+use crate::lobby::league::db::LEAGUE_DB;
+use bitdemon::domain::result_slice::ResultSlice;
+use bitdemon::lobby::league::{
+    LeagueService, LeagueServiceError, SubdivisionHistoryEntry, SubdivisionInfo, TeamInfo,
+    TeamMemberInfo, TeamOrderType, TeamSubdivision,
+};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::info;
+use num_traits::ToPrimitive;
+use rusqlite::types::Value;
+use rusqlite::{OptionalExtension, Transaction};
+use std::rc::Rc;
+
+const MAX_TEAM_SIZE: usize = 8;
+const MAX_TEAM_NAME_LENGTH: usize = 32;
+const MAX_RESULTS_PER_REQUEST: usize = 100;
+
+/// Keeps teams per title and places them into the subdivisions of the current season.
+pub struct DwLeagueService {
+    config: LeagueConfig,
+}
+
+impl LeagueService for DwLeagueService {
+    fn get_team_id(
+        &self,
+        session: &BdSession,
+        mut user_ids: Vec<u64>,
+    ) -> Result<u64, LeagueServiceError> {
+        user_ids.sort_unstable();
+        user_ids.dedup();
+        if user_ids.is_empty() || user_ids.len() > MAX_TEAM_SIZE {
+            return Err(LeagueServiceError::InvalidTeamSizeError);
+        }
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let member_key = user_ids
+            .iter()
+            .map(|user_id| user_id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let now = Utc::now().timestamp();
+
+        LEAGUE_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            let maybe_team_id: Option<u64> = transaction
+                .query_row(
+                    "SELECT t.id FROM team t WHERE t.title = ?1 AND t.member_key = ?2",
+                    (title_num, &member_key),
+                    |row| row.get(0),
+                )
+                .optional()
+                .expect("query to be successful");
+
+            let team_id = match maybe_team_id {
+                Some(team_id) => team_id,
+                None => {
+                    transaction
+                        .execute(
+                            "INSERT INTO team (title, member_key, name, created_at, last_active_at)
+                             VALUES (?, ?, '', ?, ?)",
+                            (title_num, &member_key, now, now),
+                        )
+                        .expect("insertion to be successful");
+                    let team_id = transaction.last_insert_rowid() as u64;
+
+                    for user_id in &user_ids {
+                        transaction
+                            .execute(
+                                "INSERT INTO team_member (team_id, user_id, username)
+                                 VALUES (?, ?, '')",
+                                (team_id, user_id),
+                            )
+                            .expect("insertion to be successful");
+                    }
+
+                    info!("Created team {team_id} for users {user_ids:?}");
+
+                    team_id
+                }
+            };
+
+            transaction
+                .execute(
+                    "UPDATE team_member SET username = ?3 WHERE team_id = ?1 AND user_id = ?2",
+                    (team_id, authentication.user_id, &authentication.username),
+                )
+                .expect("update to be successful");
+
+            transaction.commit().expect("commit to be successful");
+
+            Ok(team_id)
+        })
+    }
+
+    fn get_team_ids_for_user(
+        &self,
+        session: &BdSession,
+        user_id: u64,
+        order_type: TeamOrderType,
+        item_offset: usize,
+        item_count: usize,
+    ) -> Result<ResultSlice<u64>, LeagueServiceError> {
+        if item_count > MAX_RESULTS_PER_REQUEST {
+            return Err(LeagueServiceError::TooManyResultsRequestedError);
+        }
+
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+        let order = match order_type {
+            TeamOrderType::OrderByTeamId => "t.id",
+            TeamOrderType::OrderByRecentActivity => "t.last_active_at DESC, t.id",
+        };
+
+        LEAGUE_DB.with_borrow(|db| {
+            let total_count: usize = db
+                .query_row(
+                    "SELECT COUNT(*) FROM team t
+                     JOIN team_member m ON m.team_id = t.id
+                     WHERE t.title = ?1 AND m.user_id = ?2",
+                    (title_num, user_id),
+                    |row| row.get(0),
+                )
+                .expect("query to be successful");
+
+            let team_ids = db
+                .prepare(&format!(
+                    "SELECT t.id FROM team t
+                     JOIN team_member m ON m.team_id = t.id
+                     WHERE t.title = ?1 AND m.user_id = ?2
+                     ORDER BY {order}
+                     LIMIT ?3 OFFSET ?4"
+                ))
+                .expect("preparation to be successful")
+                .query_map((title_num, user_id, item_count, item_offset), |row| {
+                    row.get(0)
+                })
+                .expect("query to be successful")
+                .filter_map(|team_id| team_id.ok())
+                .collect();
+
+            Ok(ResultSlice::with_total_count(
+                team_ids,
+                item_offset,
+                total_count,
+            ))
+        })
+    }
+
+    fn get_team_subdivisions(
+        &self,
+        session: &BdSession,
+        team_id: u64,
+        league_ids: Vec<u64>,
+    ) -> Result<Vec<TeamSubdivision>, LeagueServiceError> {
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+
+        LEAGUE_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            if !Self::team_exists(&transaction, title_num, team_id) {
+                return Err(LeagueServiceError::InvalidTeamError);
+            }
+
+            let subdivisions = league_ids
+                .into_iter()
+                .map(|league_id| TeamSubdivision {
+                    league_id,
+                    subdivision_id: self.place_team(&transaction, title_num, team_id, league_id),
+                })
+                .collect();
+
+            transaction.commit().expect("commit to be successful");
+
+            Ok(subdivisions)
+        })
+    }
+
+    fn set_team_name(
+        &self,
+        session: &BdSession,
+        team_id: u64,
+        name: String,
+    ) -> Result<(), LeagueServiceError> {
+        if name.chars().count() > MAX_TEAM_NAME_LENGTH {
+            return Err(LeagueServiceError::TeamNameTooLongError);
+        }
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        let updated = LEAGUE_DB.with_borrow(|db| {
+            db.execute(
+                "UPDATE team SET name = ?4, last_active_at = ?5
+                 WHERE title = ?1 AND id = ?2
+                 AND EXISTS (SELECT 1 FROM team_member m WHERE m.team_id = ?2 AND m.user_id = ?3)",
+                (
+                    title_num,
+                    team_id,
+                    authentication.user_id,
+                    &name,
+                    Utc::now().timestamp(),
+                ),
+            )
+            .expect("update to be successful")
+        });
+
+        if updated == 0 {
+            return Err(LeagueServiceError::InvalidTeamError);
+        }
+
+        Ok(())
+    }
+
+    fn get_team_infos(&self, session: &BdSession, team_ids: Vec<u64>) -> Vec<TeamInfo> {
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+
+        LEAGUE_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT t.id, t.name, t.last_active_at,
+                        (SELECT GROUP_CONCAT(m.user_id) FROM team_member m WHERE m.team_id = t.id)
+                 FROM team t
+                 WHERE t.title = ?1 AND t.id IN rarray(?2)
+                 ORDER BY t.id",
+            )
+            .expect("preparation to be successful")
+            .query_map((title_num, Self::id_array(&team_ids)), |row| {
+                let member_ids: String = row.get(3)?;
+
+                Ok(TeamInfo {
+                    team_id: row.get(0)?,
+                    name: row.get(1)?,
+                    last_active_at: row.get(2)?,
+                    member_ids: member_ids
+                        .split(',')
+                        .filter_map(|user_id| user_id.parse().ok())
+                        .collect(),
+                })
+            })
+            .expect("query to be successful")
+            .filter_map(|team_info| team_info.ok())
+            .collect()
+        })
+    }
+
+    fn get_team_member_infos(
+        &self,
+        session: &BdSession,
+        team_ids: Vec<u64>,
+    ) -> Vec<TeamMemberInfo> {
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+
+        LEAGUE_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT m.team_id, m.user_id, m.username FROM team_member m
+                 JOIN team t ON t.id = m.team_id
+                 WHERE t.title = ?1 AND t.id IN rarray(?2)
+                 ORDER BY m.team_id, m.user_id",
+            )
+            .expect("preparation to be successful")
+            .query_map((title_num, Self::id_array(&team_ids)), |row| {
+                Ok(TeamMemberInfo {
+                    team_id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    username: row.get(2)?,
+                })
+            })
+            .expect("query to be successful")
+            .filter_map(|member_info| member_info.ok())
+            .collect()
+        })
+    }
+
+    fn get_team_subdivision_infos(
+        &self,
+        session: &BdSession,
+        subdivision_ids: Vec<u64>,
+    ) -> Vec<SubdivisionInfo> {
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+
+        LEAGUE_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT s.id, s.league_id, s.name,
+                        (SELECT COUNT(*) FROM team_subdivision ts WHERE ts.subdivision_id = s.id)
+                 FROM subdivision s
+                 WHERE s.title = ?1 AND s.id IN rarray(?2)
+                 ORDER BY s.id",
+            )
+            .expect("preparation to be successful")
+            .query_map((title_num, Self::id_array(&subdivision_ids)), |row| {
+                Ok(SubdivisionInfo {
+                    subdivision_id: row.get(0)?,
+                    league_id: row.get(1)?,
+                    name: row.get(2)?,
+                    team_count: row.get(3)?,
+                })
+            })
+            .expect("query to be successful")
+            .filter_map(|subdivision_info| subdivision_info.ok())
+            .collect()
+        })
+    }
+
+    fn get_team_subdivision_history(
+        &self,
+        session: &BdSession,
+        team_id: u64,
+        league_id: u64,
+        season_ids: Vec<u64>,
+    ) -> Vec<SubdivisionHistoryEntry> {
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+
+        LEAGUE_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT ts.season_id, ts.subdivision_id FROM team_subdivision ts
+                 JOIN team t ON t.id = ts.team_id
+                 WHERE t.title = ?1 AND ts.team_id = ?2 AND ts.league_id = ?3
+                 AND ts.season_id IN rarray(?4)
+                 ORDER BY ts.season_id",
+            )
+            .expect("preparation to be successful")
+            .query_map(
+                (title_num, team_id, league_id, Self::id_array(&season_ids)),
+                |row| {
+                    Ok(SubdivisionHistoryEntry {
+                        season_id: row.get(0)?,
+                        subdivision_id: row.get(1)?,
+                    })
+                },
+            )
+            .expect("query to be successful")
+            .filter_map(|entry| entry.ok())
+            .collect()
+        })
+    }
+}
+
+impl DwLeagueService {
+    pub fn new(config: LeagueConfig) -> DwLeagueService {
+        DwLeagueService { config }
+    }
+
+    fn team_exists(transaction: &Transaction, title_num: u32, team_id: u64) -> bool {
+        transaction
+            .query_row(
+                "SELECT 1 FROM team t WHERE t.title = ?1 AND t.id = ?2",
+                (title_num, team_id),
+                |_| Ok(()),
+            )
+            .optional()
+            .expect("query to be successful")
+            .is_some()
+    }
+
+    /// Retrieves the subdivision of the league the team competes in this season.
+    /// Teams that were not placed yet join the first subdivision with space left.
+    fn place_team(
+        &self,
+        transaction: &Transaction,
+        title_num: u32,
+        team_id: u64,
+        league_id: u64,
+    ) -> u64 {
+        let season_id = self.config.season_id;
+
+        let maybe_subdivision_id: Option<u64> = transaction
+            .query_row(
+                "SELECT ts.subdivision_id FROM team_subdivision ts
+                 WHERE ts.team_id = ?1 AND ts.league_id = ?2 AND ts.season_id = ?3",
+                (team_id, league_id, season_id),
+                |row| row.get(0),
+            )
+            .optional()
+            .expect("query to be successful");
+        if let Some(subdivision_id) = maybe_subdivision_id {
+            return subdivision_id;
+        }
+
+        let maybe_open_subdivision_id: Option<u64> = transaction
+            .query_row(
+                "SELECT s.id FROM subdivision s
+                 WHERE s.title = ?1 AND s.league_id = ?2 AND s.season_id = ?3
+                 AND (SELECT COUNT(*) FROM team_subdivision ts WHERE ts.subdivision_id = s.id) < ?4
+                 ORDER BY s.id
+                 LIMIT 1",
+                (
+                    title_num,
+                    league_id,
+                    season_id,
+                    self.config.subdivision_size,
+                ),
+                |row| row.get(0),
+            )
+            .optional()
+            .expect("query to be successful");
+
+        let subdivision_id = maybe_open_subdivision_id.unwrap_or_else(|| {
+            let subdivision_count: u64 = transaction
+                .query_row(
+                    "SELECT COUNT(*) FROM subdivision s
+                     WHERE s.title = ?1 AND s.league_id = ?2 AND s.season_id = ?3",
+                    (title_num, league_id, season_id),
+                    |row| row.get(0),
+                )
+                .expect("query to be successful");
+
+            transaction
+                .execute(
+                    "INSERT INTO subdivision (title, league_id, season_id, name) VALUES (?, ?, ?, ?)",
+                    (
+                        title_num,
+                        league_id,
+                        season_id,
+                        format!("Subdivision {}", subdivision_count + 1),
+                    ),
+                )
+                .expect("insertion to be successful");
+
+            transaction.last_insert_rowid() as u64
+        });
+
+        transaction
+            .execute(
+                "INSERT INTO team_subdivision (team_id, league_id, season_id, subdivision_id, placed_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                (
+                    team_id,
+                    league_id,
+                    season_id,
+                    subdivision_id,
+                    Utc::now().timestamp(),
+                ),
+            )
+            .expect("insertion to be successful");
+
+        info!("Placed team {team_id} into subdivision {subdivision_id} of league {league_id}");
+
+        subdivision_id
+    }
+
+    fn id_array(ids: &[u64]) -> Rc<Vec<Value>> {
+        Rc::new(ids.iter().map(|id| Value::Integer(*id as i64)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use bitdemon::domain::title::Title;
+
+    fn service() -> DwLeagueService {
+        DwLeagueService::new(LeagueConfig {
+            season_id: 3,
+            subdivision_size: 1,
+        })
+    }
+
+    #[test]
+    fn same_members_form_same_team() {
+        let service = service();
+        let session = authenticated_session(1, Title::T6Pc);
+
+        let team_id = service.get_team_id(&session, vec![2, 1]).unwrap();
+        assert_eq!(
+            service.get_team_id(&session, vec![1, 2, 2]).unwrap(),
+            team_id
+        );
+        assert_ne!(service.get_team_id(&session, vec![1]).unwrap(), team_id);
+        assert!(matches!(
+            service.get_team_id(&session, Vec::new()),
+            Err(LeagueServiceError::InvalidTeamSizeError)
+        ));
+
+        service
+            .set_team_name(&session, team_id, "Team".to_string())
+            .unwrap();
+        let team_infos = service.get_team_infos(&session, vec![team_id]);
+        assert_eq!(team_infos[0].name, "Team");
+        assert_eq!(team_infos[0].member_ids, vec![1, 2]);
+        let member_infos = service.get_team_member_infos(&session, vec![team_id]);
+        assert_eq!(member_infos[0].username, "user1");
+
+        let team_ids = service
+            .get_team_ids_for_user(&session, 1, TeamOrderType::OrderByTeamId, 0, 10)
+            .unwrap();
+        assert_eq!(team_ids.total_count(), 2);
+    }
+
+    #[test]
+    fn only_members_can_rename_team() {
+        let service = service();
+        let owner = authenticated_session(1, Title::T6Pc);
+        let other = authenticated_session(2, Title::T6Pc);
+        let team_id = service.get_team_id(&owner, vec![1]).unwrap();
+
+        assert!(matches!(
+            service.set_team_name(&other, team_id, "Team".to_string()),
+            Err(LeagueServiceError::InvalidTeamError)
+        ));
+        assert!(matches!(
+            service.set_team_name(&owner, team_id, "T".repeat(MAX_TEAM_NAME_LENGTH + 1)),
+            Err(LeagueServiceError::TeamNameTooLongError)
+        ));
+    }
+
+    #[test]
+    fn teams_are_placed_into_subdivisions_with_space() {
+        let service = service();
+        let session = authenticated_session(1, Title::T6Pc);
+        let first_team = service.get_team_id(&session, vec![1]).unwrap();
+        let second_team = service.get_team_id(&session, vec![2]).unwrap();
+
+        let first = service
+            .get_team_subdivisions(&session, first_team, vec![10])
+            .unwrap();
+        let second = service
+            .get_team_subdivisions(&session, second_team, vec![10])
+            .unwrap();
+        assert_ne!(first[0].subdivision_id, second[0].subdivision_id);
+        assert_eq!(
+            service
+                .get_team_subdivisions(&session, first_team, vec![10])
+                .unwrap()[0]
+                .subdivision_id,
+            first[0].subdivision_id
+        );
+
+        let infos = service.get_team_subdivision_infos(&session, vec![first[0].subdivision_id]);
+        assert_eq!(
+            (infos[0].name.as_str(), infos[0].team_count),
+            ("Subdivision 1", 1)
+        );
+        let history = service.get_team_subdivision_history(&session, first_team, 10, vec![3, 4]);
+        assert_eq!(history.len(), 1);
+        assert!(matches!(
+            service.get_team_subdivisions(&session, 999, vec![10]),
+            Err(LeagueServiceError::InvalidTeamError)
+        ));
+    }
+}
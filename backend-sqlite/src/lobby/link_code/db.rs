@@ -0,0 +1,41 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static LINK_CODE_DB: RefCell<Connection> = RefCell::new(open_db(&LINK_CODE_SCHEMA));
+}
+
+const LINK_CODE_CHANGELOG_0: &str = "
+CREATE TABLE link_code (
+    code TEXT PRIMARY KEY,
+    title INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    owner_name TEXT NOT NULL,
+    expires_at INTEGER NOT NULL,
+    redeemed_by INTEGER
+);
+CREATE TABLE linked_user (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    linked_user_id INTEGER NOT NULL,
+    linked_username TEXT NOT NULL,
+    PRIMARY KEY (title, user_id, linked_user_id)
+);
+";
+
+const LINK_CODE_SCHEMA: DbSchema = DbSchema {
+    name: "link_code",
+    changelogs: &[LINK_CODE_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&LINK_CODE_SCHEMA);
+    }
+}
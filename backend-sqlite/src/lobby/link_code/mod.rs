@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwLinkCodeService;
+
+use bitdemon::lobby::link_code::LinkCodeHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_link_code_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(LinkCodeHandler::new(Arc::new(DwLinkCodeService::new())))
+}
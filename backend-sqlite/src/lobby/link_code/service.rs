@@ -0,0 +1,403 @@
+use crate::identity::{merge_accounts, IdentityMergeError};
+use crate::lobby::link_code::db::LINK_CODE_DB;
+use bitdemon::lobby::link_code::{LinkCode, LinkCodeService, LinkCodeServiceError, LinkedUser};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::{info, warn};
+use num_traits::ToPrimitive;
+use rand::RngExt;
+use rusqlite::{OptionalExtension, Transaction};
+
+pub struct DwLinkCodeService {}
+
+const CODE_LENGTH: usize = 8;
+// Leaves out characters that are easily confused with each other
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_VALIDITY_SECONDS: i64 = 15 * 60;
+
+impl LinkCodeService for DwLinkCodeService {
+    fn generate_code(&self, session: &BdSession) -> Result<LinkCode, LinkCodeServiceError> {
+        info!("Generating link code");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+        let expires_at = now + CODE_VALIDITY_SECONDS;
+
+        let code = LINK_CODE_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            transaction
+                .execute(
+                    "DELETE FROM link_code WHERE expires_at < ?1 AND redeemed_by IS NULL",
+                    (now,),
+                )
+                .expect("deletion to be successful");
+
+            let code = loop {
+                let code = Self::random_code();
+                let inserted = transaction
+                    .execute(
+                        "INSERT OR IGNORE INTO link_code (code, title, owner_id, owner_name, expires_at)
+                         VALUES (?, ?, ?, ?, ?)",
+                        (
+                            code.as_str(),
+                            title_num,
+                            authentication.user_id,
+                            authentication.username.as_str(),
+                            expires_at,
+                        ),
+                    )
+                    .expect("insertion to be successful");
+
+                if inserted > 0 {
+                    break code;
+                }
+            };
+
+            transaction.commit().expect("commit to be successful");
+
+            code
+        });
+
+        Ok(LinkCode { code, expires_at })
+    }
+
+    fn redeem_code(
+        &self,
+        session: &BdSession,
+        code: String,
+    ) -> Result<LinkedUser, LinkCodeServiceError> {
+        info!("Redeeming link code {code}");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+        let code = code.trim().to_uppercase();
+
+        LINK_CODE_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            let (owner_id, owner_name) = Self::claim_code(
+                &transaction,
+                code.as_str(),
+                title_num,
+                authentication.user_id,
+                now,
+            )?;
+
+            let mut link_statement = transaction
+                .prepare(
+                    "INSERT OR REPLACE INTO linked_user (title, user_id, linked_user_id, linked_username)
+                     VALUES (?, ?, ?, ?)",
+                )
+                .expect("preparation to be successful");
+            link_statement
+                .execute((
+                    title_num,
+                    owner_id,
+                    authentication.user_id,
+                    authentication.username.as_str(),
+                ))
+                .expect("insertion to be successful");
+            link_statement
+                .execute((
+                    title_num,
+                    authentication.user_id,
+                    owner_id,
+                    owner_name.as_str(),
+                ))
+                .expect("insertion to be successful");
+            drop(link_statement);
+
+            transaction.commit().expect("commit to be successful");
+
+            Ok(LinkedUser {
+                user_id: owner_id,
+                username: owner_name,
+            })
+        })
+    }
+
+    fn merge_accounts(
+        &self,
+        session: &BdSession,
+        code: String,
+    ) -> Result<LinkedUser, LinkCodeServiceError> {
+        info!("Redeeming link code {code} to merge accounts");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+        let code = code.trim().to_uppercase();
+
+        LINK_CODE_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            let (owner_id, owner_name) = Self::claim_code(
+                &transaction,
+                code.as_str(),
+                title_num,
+                authentication.user_id,
+                now,
+            )?;
+
+            merge_accounts(
+                authentication.user_id,
+                owner_id,
+                format!("redeemed link code {code}").as_str(),
+            )
+            .map_err(|e| {
+                warn!("Could not merge accounts: {e:?}");
+                match e {
+                    IdentityMergeError::AlreadyMerged => {
+                        LinkCodeServiceError::AccountsAlreadyMergedError
+                    }
+                    IdentityMergeError::Conflict => LinkCodeServiceError::IdentityConflictError,
+                }
+            })?;
+
+            transaction.commit().expect("commit to be successful");
+
+            Ok(LinkedUser {
+                user_id: owner_id,
+                username: owner_name,
+            })
+        })
+    }
+
+    fn get_linked_users(
+        &self,
+        session: &BdSession,
+    ) -> Result<Vec<LinkedUser>, LinkCodeServiceError> {
+        info!("Retrieving linked users");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        let linked_users = LINK_CODE_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT l.linked_user_id, l.linked_username FROM linked_user l
+                 WHERE l.title = ?1 AND l.user_id = ?2",
+            )
+            .expect("preparation to be successful")
+            .query_map((title_num, authentication.user_id), |row| {
+                Ok(LinkedUser {
+                    user_id: row.get(0)?,
+                    username: row.get(1)?,
+                })
+            })
+            .expect("query to be successful")
+            .filter_map(|linked_user| linked_user.ok())
+            .collect()
+        });
+
+        Ok(linked_users)
+    }
+}
+
+impl Default for DwLinkCodeService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwLinkCodeService {
+    pub fn new() -> DwLinkCodeService {
+        DwLinkCodeService {}
+    }
+
+    /// Validates a code that is about to be redeemed and marks it as redeemed.
+    /// Returns id and name of the user that generated the code.
+    fn claim_code(
+        transaction: &Transaction,
+        code: &str,
+        title_num: u32,
+        user_id: u64,
+        now: i64,
+    ) -> Result<(u64, String), LinkCodeServiceError> {
+        let maybe_code: Option<(u64, String, i64, Option<u64>)> = transaction
+            .query_row(
+                "SELECT c.owner_id, c.owner_name, c.expires_at, c.redeemed_by FROM link_code c
+                 WHERE c.code = ?1 AND c.title = ?2",
+                (code, title_num),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()
+            .expect("query to be successful");
+
+        let Some((owner_id, owner_name, expires_at, redeemed_by)) = maybe_code else {
+            warn!("Tried to redeem unknown link code");
+            return Err(LinkCodeServiceError::InvalidCodeError);
+        };
+
+        if redeemed_by.is_some() {
+            warn!("Tried to redeem link code that was already redeemed");
+            return Err(LinkCodeServiceError::CodeAlreadyRedeemedError);
+        }
+
+        if expires_at < now {
+            warn!("Tried to redeem expired link code");
+            return Err(LinkCodeServiceError::CodeExpiredError);
+        }
+
+        if owner_id == user_id {
+            warn!("Tried to redeem own link code");
+            return Err(LinkCodeServiceError::SelfLinkNotAllowedError);
+        }
+
+        transaction
+            .execute(
+                "UPDATE link_code SET redeemed_by = ?1 WHERE code = ?2",
+                (user_id, code),
+            )
+            .expect("update to be successful");
+
+        Ok((owner_id, owner_name))
+    }
+
+    fn random_code() -> String {
+        let mut rng = rand::rng();
+
+        (0..CODE_LENGTH)
+            .map(|_| CODE_ALPHABET[rng.random_range(0..CODE_ALPHABET.len())] as char)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use crate::identity::DwAccountResolver;
+    use bitdemon::auth::identity::{AccountResolver, IdentityPlatform, PlatformIdentity};
+    use bitdemon::domain::title::Title;
+
+    fn identity(platform: IdentityPlatform, platform_user_id: u64) -> PlatformIdentity {
+        PlatformIdentity {
+            platform,
+            platform_user_id,
+        }
+    }
+
+    #[test]
+    fn redeeming_code_links_both_users() {
+        let service = DwLinkCodeService::new();
+        let owner = authenticated_session(1, Title::T6Pc);
+        let redeemer = authenticated_session(2, Title::T6Pc);
+
+        let code = service.generate_code(&owner).unwrap();
+        let linked_user = service.redeem_code(&redeemer, code.code.clone()).unwrap();
+
+        assert_eq!(linked_user.user_id, 1);
+        assert_eq!(service.get_linked_users(&owner).unwrap()[0].user_id, 2);
+        assert!(matches!(
+            service.redeem_code(&redeemer, code.code),
+            Err(LinkCodeServiceError::CodeAlreadyRedeemedError)
+        ));
+    }
+
+    #[test]
+    fn codes_cannot_be_redeemed_by_owner_or_for_other_titles() {
+        let service = DwLinkCodeService::new();
+        let owner = authenticated_session(1, Title::T6Pc);
+        let other_title = authenticated_session(2, Title::Iw5);
+
+        let code = service.generate_code(&owner).unwrap();
+
+        assert!(matches!(
+            service.redeem_code(&owner, code.code.clone()),
+            Err(LinkCodeServiceError::SelfLinkNotAllowedError)
+        ));
+        assert!(matches!(
+            service.redeem_code(&other_title, code.code),
+            Err(LinkCodeServiceError::InvalidCodeError)
+        ));
+    }
+
+    #[test]
+    fn merging_with_code_moves_identities_to_owner_account() {
+        let service = DwLinkCodeService::new();
+        let resolver = DwAccountResolver::new();
+        let steam = identity(IdentityPlatform::Steam, 100);
+        let wii_u = identity(IdentityPlatform::WiiU, 200);
+        let owner_id = resolver.resolve_account(&steam).unwrap();
+        let redeemer_id = resolver.resolve_account(&wii_u).unwrap();
+        let owner = authenticated_session(owner_id, Title::T6Pc);
+        let redeemer = authenticated_session(redeemer_id, Title::T6WiiU);
+
+        let code = service.generate_code(&owner).unwrap();
+        assert!(matches!(
+            service.merge_accounts(&redeemer, code.code.clone()),
+            Err(LinkCodeServiceError::InvalidCodeError)
+        ));
+
+        let redeemer = authenticated_session(redeemer_id, Title::T6Pc);
+        let merged_into = service.merge_accounts(&redeemer, code.code).unwrap();
+
+        assert_eq!(merged_into.user_id, owner_id);
+        assert_eq!(resolver.resolve_account(&wii_u).unwrap(), owner_id);
+        assert_eq!(resolver.resolve_account(&steam).unwrap(), owner_id);
+    }
+
+    #[test]
+    fn conflicting_merge_leaves_code_unredeemed() {
+        let service = DwLinkCodeService::new();
+        let resolver = DwAccountResolver::new();
+        let first = identity(IdentityPlatform::Steam, 100);
+        let second = identity(IdentityPlatform::Steam, 200);
+        let owner_id = resolver.resolve_account(&first).unwrap();
+        let redeemer_id = resolver.resolve_account(&second).unwrap();
+        let owner = authenticated_session(owner_id, Title::T6Pc);
+        let redeemer = authenticated_session(redeemer_id, Title::T6Pc);
+
+        let code = service.generate_code(&owner).unwrap();
+
+        assert!(matches!(
+            service.merge_accounts(&redeemer, code.code.clone()),
+            Err(LinkCodeServiceError::IdentityConflictError)
+        ));
+        assert_eq!(resolver.resolve_account(&second).unwrap(), redeemer_id);
+        assert_eq!(
+            service.redeem_code(&redeemer, code.code).unwrap().user_id,
+            owner_id
+        );
+    }
+
+    #[test]
+    fn expired_codes_cannot_be_redeemed() {
+        let service = DwLinkCodeService::new();
+        let owner = authenticated_session(1, Title::T6Pc);
+        let redeemer = authenticated_session(2, Title::T6Pc);
+
+        let code = service.generate_code(&owner).unwrap();
+        LINK_CODE_DB.with_borrow(|db| {
+            db.execute(
+                "UPDATE link_code SET expires_at = ?1 WHERE code = ?2",
+                (Utc::now().timestamp() - 1, code.code.as_str()),
+            )
+            .unwrap();
+        });
+
+        assert!(matches!(
+            service.redeem_code(&redeemer, code.code),
+            Err(LinkCodeServiceError::CodeExpiredError)
+        ));
+        assert!(service.get_linked_users(&redeemer).unwrap().is_empty());
+    }
+
+    #[test]
+    fn codes_are_matched_regardless_of_case_and_whitespace() {
+        let service = DwLinkCodeService::new();
+        let owner = authenticated_session(1, Title::T6Pc);
+        let redeemer = authenticated_session(2, Title::T6Pc);
+
+        let code = service.generate_code(&owner).unwrap();
+        let linked_user = service
+            .redeem_code(&redeemer, format!(" {} ", code.code.to_lowercase()))
+            .unwrap();
+
+        assert_eq!(linked_user.user_id, 1);
+        assert_eq!(linked_user.username, "user1");
+    }
+}
@@ -0,0 +1,40 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static MAIL_DB: RefCell<Connection> = RefCell::new(open_db(&MAIL_SCHEMA));
+}
+
+const MAIL_CHANGELOG_0: &str = "
+CREATE TABLE mail (
+    id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    sender_id INTEGER NOT NULL,
+    sender_name TEXT NOT NULL,
+    recipient_id INTEGER NOT NULL,
+    sent_at INTEGER NOT NULL,
+    read INTEGER NOT NULL DEFAULT 0,
+    body BLOB NOT NULL
+);
+CREATE INDEX mail_title_recipient_id_idx ON mail (
+    title,
+    recipient_id
+);
+";
+
+const MAIL_SCHEMA: DbSchema = DbSchema {
+    name: "mail",
+    changelogs: &[MAIL_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&MAIL_SCHEMA);
+    }
+}
@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwMailService;
+
+use bitdemon::lobby::mail::MailHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_mail_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(MailHandler::new(Arc::new(DwMailService::new())))
+}
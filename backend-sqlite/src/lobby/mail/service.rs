@@ -0,0 +1,222 @@
+use crate::lobby::mail::db::MAIL_DB;
+use bitdemon::domain::result_slice::ResultSlice;
+use bitdemon::lobby::mail::{MailBody, MailHeader, MailService, MailServiceError};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::{info, warn};
+use num_traits::ToPrimitive;
+use rusqlite::DropBehavior;
+
+pub struct DwMailService {}
+
+const MAX_MAIL_SIZE: usize = 8_192; // 8KiB
+const MAX_RECIPIENTS: usize = 32;
+const MAX_MAILS_PER_REQUEST: usize = 100;
+
+impl MailService for DwMailService {
+    fn send_mail(
+        &self,
+        session: &BdSession,
+        recipient_ids: &[u64],
+        body: Vec<u8>,
+    ) -> Result<(), MailServiceError> {
+        info!(
+            "Sending mail recipients={recipient_ids:?} len={}",
+            body.len()
+        );
+
+        if recipient_ids.is_empty() || recipient_ids.contains(&0) {
+            warn!("Tried to send mail to invalid recipient");
+            return Err(MailServiceError::InvalidRecipientError);
+        }
+
+        if recipient_ids.len() > MAX_RECIPIENTS {
+            warn!("Tried to send mail to too many recipients");
+            return Err(MailServiceError::TooManyRecipientsError);
+        }
+
+        if body.len() > MAX_MAIL_SIZE {
+            warn!("Tried to send mail that is too large");
+            return Err(MailServiceError::MailTooLargeError);
+        }
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+
+        MAIL_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for recipient_id in recipient_ids {
+                transaction
+                    .execute(
+                        "INSERT INTO mail
+                         (title, sender_id, sender_name, recipient_id, sent_at, body)
+                         VALUES (?, ?, ?, ?, ?, ?)",
+                        (
+                            title_num,
+                            authentication.user_id,
+                            authentication.username.as_str(),
+                            recipient_id,
+                            now,
+                            body.as_slice(),
+                        ),
+                    )
+                    .expect("insertion to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn get_mail_headers(
+        &self,
+        session: &BdSession,
+        item_offset: usize,
+        item_count: usize,
+    ) -> Result<ResultSlice<MailHeader>, MailServiceError> {
+        info!("Retrieving mail headers item_offset={item_offset} item_count={item_count}");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let item_count = item_count.min(MAX_MAILS_PER_REQUEST);
+
+        MAIL_DB.with_borrow_mut(|db| {
+            let mut transaction = db.transaction().expect("transaction to be started");
+            transaction.set_drop_behavior(DropBehavior::Commit);
+
+            let total_count: usize = transaction
+                .query_row(
+                    "SELECT COUNT(*) FROM mail m
+                     WHERE m.title = ?1 AND m.recipient_id = ?2",
+                    (title_num, authentication.user_id),
+                    |row| row.get(0),
+                )
+                .expect("query to be successful");
+
+            let headers = transaction
+                .prepare(
+                    "SELECT m.id, m.sender_id, m.sender_name, m.sent_at, LENGTH(m.body), m.read
+                     FROM mail m
+                     WHERE m.title = ?1 AND m.recipient_id = ?2
+                     ORDER BY m.sent_at DESC, m.id DESC
+                     LIMIT ?4 OFFSET ?3",
+                )
+                .expect("preparation to be successful")
+                .query_map(
+                    (title_num, authentication.user_id, item_offset, item_count),
+                    |row| {
+                        Ok(MailHeader {
+                            id: row.get(0)?,
+                            sender_id: row.get(1)?,
+                            sender_name: row.get(2)?,
+                            sent_at: row.get(3)?,
+                            body_size: row.get(4)?,
+                            read: row.get(5)?,
+                        })
+                    },
+                )
+                .expect("query to be successful")
+                .filter_map(|header| header.ok())
+                .collect();
+
+            Ok(ResultSlice::with_total_count(
+                headers,
+                item_offset,
+                total_count,
+            ))
+        })
+    }
+
+    fn get_mail_bodies(
+        &self,
+        session: &BdSession,
+        mail_ids: &[u64],
+    ) -> Result<Vec<MailBody>, MailServiceError> {
+        info!("Retrieving {} mail bodies", mail_ids.len());
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        let bodies = MAIL_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+            let mut bodies = Vec::with_capacity(mail_ids.len());
+
+            {
+                let mut select_statement = transaction
+                    .prepare(
+                        "SELECT m.body FROM mail m
+                         WHERE m.id = ?1 AND m.title = ?2 AND m.recipient_id = ?3",
+                    )
+                    .expect("preparation to be successful");
+                let mut update_statement = transaction
+                    .prepare(
+                        "UPDATE mail SET read = 1
+                         WHERE id = ?1 AND title = ?2 AND recipient_id = ?3",
+                    )
+                    .expect("preparation to be successful");
+
+                for mail_id in mail_ids {
+                    let maybe_body: Option<Vec<u8>> = select_statement
+                        .query_row((mail_id, title_num, authentication.user_id), |row| {
+                            row.get(0)
+                        })
+                        .ok();
+
+                    if let Some(body) = maybe_body {
+                        update_statement
+                            .execute((mail_id, title_num, authentication.user_id))
+                            .expect("update to be successful");
+
+                        bodies.push(MailBody { id: *mail_id, body });
+                    }
+                }
+            }
+
+            transaction.commit().expect("commit to be successful");
+
+            bodies
+        });
+
+        Ok(bodies)
+    }
+
+    fn delete_mails(&self, session: &BdSession, mail_ids: &[u64]) -> Result<(), MailServiceError> {
+        info!("Deleting {} mails", mail_ids.len());
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        MAIL_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for mail_id in mail_ids {
+                transaction
+                    .execute(
+                        "DELETE FROM mail
+                         WHERE id = ?1 AND title = ?2 AND recipient_id = ?3",
+                        (mail_id, title_num, authentication.user_id),
+                    )
+                    .expect("deletion to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+}
+
+impl Default for DwMailService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwMailService {
+    pub fn new() -> DwMailService {
+        DwMailService {}
+    }
+}
@@ -0,0 +1,34 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static MATCHMAKING_DB: RefCell<Connection> = RefCell::new(open_db(&MATCHMAKING_SCHEMA));
+}
+
+const MATCHMAKING_CHANGELOG_0: &str = "
+CREATE TABLE performance_value (
+    title INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    performance_value INTEGER NOT NULL,
+    submitted_by INTEGER NOT NULL,
+    submitted_at INTEGER NOT NULL,
+    PRIMARY KEY (title, entity_id)
+);
+";
+
+const MATCHMAKING_SCHEMA: DbSchema = DbSchema {
+    name: "matchmaking",
+    changelogs: &[MATCHMAKING_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&MATCHMAKING_SCHEMA);
+    }
+}
@@ -0,0 +1,73 @@
+mod db;
+mod service;
+
+pub use service::DwMatchmakingService;
+
+use crate::config::{BackendConfig, MatchmakingFilterConfig};
+use bitdemon::domain::title::Title;
+use bitdemon::lobby::matchmaking::{
+    InMemoryMatchmakingService, MatchmakingFilter, MatchmakingHandler, MatchmakingQuery,
+    MatchmakingService,
+};
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use bitdemon::networking::session_manager::SessionManager;
+use log::warn;
+use num_traits::FromPrimitive;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Creates the matchmaking handler which keeps sessions in memory, persists performance values,
+/// searches them with the configured queries and pushes invites to online users.
+/// Sessions are deleted as soon as the connection of their host closes.
+pub fn create_matchmaking_handler(
+    config: &BackendConfig,
+    session_manager: Arc<SessionManager>,
+) -> Arc<ThreadSafeLobbyHandler> {
+    let mut service = InMemoryMatchmakingService::new()
+        .with_session_ttl(Duration::from_secs(config.matchmaking.session_ttl_secs));
+
+    for (title_num, title_config) in &config.matchmaking.titles {
+        let Some(title) = Title::from_u32(*title_num) else {
+            warn!("Ignoring matchmaking queries of unknown title {title_num}");
+            continue;
+        };
+
+        for (query_id, filters) in &title_config.queries {
+            let filters = filters.iter().map(to_filter).collect();
+            service = service.with_query(title, *query_id, MatchmakingQuery::new(filters));
+        }
+    }
+
+    let service = Arc::new(DwMatchmakingService::new(service));
+
+    let unregistered_service = service.clone();
+    session_manager.on_session_unregistered(move |session| {
+        unregistered_service.delete_sessions_of_host(session);
+    });
+
+    Arc::new(MatchmakingHandler::new(service).with_session_manager(session_manager))
+}
+
+fn to_filter(filter: &MatchmakingFilterConfig) -> MatchmakingFilter {
+    match *filter {
+        MatchmakingFilterConfig::GameType { parameter } => {
+            MatchmakingFilter::GameType { parameter }
+        }
+        MatchmakingFilterConfig::Equals {
+            attribute,
+            parameter,
+        } => MatchmakingFilter::Equals {
+            attribute,
+            parameter,
+        },
+        MatchmakingFilterConfig::Range {
+            attribute,
+            min_parameter,
+            max_parameter,
+        } => MatchmakingFilter::Range {
+            attribute,
+            min_parameter,
+            max_parameter,
+        },
+    }
+}
@@ -0,0 +1,276 @@
+use crate::lobby::matchmaking::db::MATCHMAKING_DB;
+use bitdemon::domain::result_slice::ResultSlice;
+use bitdemon::lobby::matchmaking::{
+    EntitySession, InMemoryMatchmakingService, MatchmakingInvite, MatchmakingSearchCriteria,
+    MatchmakingService, MatchmakingServiceError, MatchmakingSession, MatchmakingSessionId,
+    MatchmakingSessionInfo, PerformanceValue,
+};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use num_traits::ToPrimitive;
+use rusqlite::types::Value;
+use std::rc::Rc;
+
+/// Keeps matchmaking sessions in memory, since they do not outlive their hosts,
+/// but persists performance values so that users keep their skill across sessions.
+pub struct DwMatchmakingService {
+    sessions: InMemoryMatchmakingService,
+}
+
+impl MatchmakingService for DwMatchmakingService {
+    fn create_session(
+        &self,
+        session: &BdSession,
+        info: MatchmakingSessionInfo,
+    ) -> Result<MatchmakingSessionId, MatchmakingServiceError> {
+        self.sessions.create_session(session, info)
+    }
+
+    fn update_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        info: MatchmakingSessionInfo,
+    ) -> Result<(), MatchmakingServiceError> {
+        self.sessions.update_session(session, session_id, info)
+    }
+
+    fn delete_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+    ) -> Result<(), MatchmakingServiceError> {
+        self.sessions.delete_session(session, session_id)
+    }
+
+    fn delete_sessions_of_host(&self, session: &BdSession) {
+        self.sessions.delete_sessions_of_host(session)
+    }
+
+    fn notify_join(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError> {
+        self.sessions.notify_join(session, session_id, user_ids)
+    }
+
+    fn notify_leave(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError> {
+        self.sessions.notify_leave(session, session_id, user_ids)
+    }
+
+    fn update_session_players(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError> {
+        self.sessions
+            .update_session_players(session, session_id, user_ids)
+    }
+
+    fn find_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+    ) -> Result<MatchmakingSession, MatchmakingServiceError> {
+        self.sessions.find_session(session, session_id)
+    }
+
+    fn find_sessions_by_entity_ids(
+        &self,
+        session: &BdSession,
+        entity_ids: Vec<u64>,
+    ) -> Result<Vec<EntitySession>, MatchmakingServiceError> {
+        self.sessions
+            .find_sessions_by_entity_ids(session, entity_ids)
+    }
+
+    fn find_sessions_from_ids(
+        &self,
+        session: &BdSession,
+        session_ids: Vec<MatchmakingSessionId>,
+    ) -> Result<Vec<MatchmakingSession>, MatchmakingServiceError> {
+        self.sessions.find_sessions_from_ids(session, session_ids)
+    }
+
+    fn invite_to_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+        attachment: Vec<u8>,
+    ) -> Result<Vec<u64>, MatchmakingServiceError> {
+        self.sessions
+            .invite_to_session(session, session_id, user_ids, attachment)
+    }
+
+    fn get_session_invites(
+        &self,
+        session: &BdSession,
+    ) -> Result<Vec<MatchmakingInvite>, MatchmakingServiceError> {
+        self.sessions.get_session_invites(session)
+    }
+
+    fn submit_performance(
+        &self,
+        session: &BdSession,
+        values: Vec<PerformanceValue>,
+    ) -> Result<(), MatchmakingServiceError> {
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+
+        MATCHMAKING_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for value in values {
+                transaction
+                    .execute(
+                        "INSERT INTO performance_value (title, entity_id, performance_value, submitted_by, submitted_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5)
+                         ON CONFLICT (title, entity_id) DO UPDATE
+                         SET performance_value = ?3, submitted_by = ?4, submitted_at = ?5",
+                        (
+                            title_num,
+                            value.entity_id,
+                            value.performance_value,
+                            authentication.user_id,
+                            now,
+                        ),
+                    )
+                    .expect("upsert to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn get_performance_values(
+        &self,
+        session: &BdSession,
+        entity_ids: Vec<u64>,
+    ) -> Result<Vec<PerformanceValue>, MatchmakingServiceError> {
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+        let ids: Rc<Vec<Value>> = Rc::new(
+            entity_ids
+                .iter()
+                .map(|id| Value::Integer(*id as i64))
+                .collect(),
+        );
+
+        let values = MATCHMAKING_DB.with_borrow(|db| {
+            db.prepare_cached(
+                "SELECT p.entity_id, p.performance_value FROM performance_value p
+                 WHERE p.title = ?1 AND p.entity_id IN rarray(?2)",
+            )
+            .expect("preparation to be successful")
+            .query_map((title_num, ids), |row| {
+                Ok(PerformanceValue {
+                    entity_id: row.get(0)?,
+                    performance_value: row.get(1)?,
+                })
+            })
+            .expect("query to be successful")
+            .map(|row| row.expect("row to be valid"))
+            .collect::<Vec<PerformanceValue>>()
+        });
+
+        // Results are returned in the order they were requested in
+        Ok(entity_ids
+            .into_iter()
+            .filter_map(|entity_id| {
+                values
+                    .iter()
+                    .find(|value| value.entity_id == entity_id)
+                    .copied()
+            })
+            .collect())
+    }
+
+    fn find_sessions(
+        &self,
+        session: &BdSession,
+        criteria: MatchmakingSearchCriteria,
+        offset: usize,
+        max_results: usize,
+    ) -> Result<ResultSlice<MatchmakingSession>, MatchmakingServiceError> {
+        self.sessions
+            .find_sessions(session, criteria, offset, max_results)
+    }
+}
+
+impl DwMatchmakingService {
+    pub fn new(sessions: InMemoryMatchmakingService) -> DwMatchmakingService {
+        DwMatchmakingService { sessions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use bitdemon::domain::title::Title;
+
+    #[test]
+    fn performance_values_are_kept_per_title() {
+        let service = DwMatchmakingService::new(InMemoryMatchmakingService::new());
+        let session = authenticated_session(1, Title::T6Pc);
+
+        service
+            .submit_performance(
+                &session,
+                vec![
+                    PerformanceValue {
+                        entity_id: 1,
+                        performance_value: 1200,
+                    },
+                    PerformanceValue {
+                        entity_id: 2,
+                        performance_value: 900,
+                    },
+                ],
+            )
+            .unwrap();
+        service
+            .submit_performance(
+                &session,
+                vec![PerformanceValue {
+                    entity_id: 1,
+                    performance_value: 1250,
+                }],
+            )
+            .unwrap();
+
+        let values = service
+            .get_performance_values(&session, vec![3, 2, 1])
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                PerformanceValue {
+                    entity_id: 2,
+                    performance_value: 900,
+                },
+                PerformanceValue {
+                    entity_id: 1,
+                    performance_value: 1250,
+                },
+            ]
+        );
+
+        let other_title_session = authenticated_session(1, Title::Iw5);
+        assert!(service
+            .get_performance_values(&other_title_session, vec![1, 2])
+            .unwrap()
+            .is_empty());
+    }
+}
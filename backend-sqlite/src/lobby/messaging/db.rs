@@ -0,0 +1,45 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static MESSAGING_DB: RefCell<Connection> = RefCell::new(open_db(&MESSAGING_SCHEMA));
+}
+
+const MESSAGING_CHANGELOG_0: &str = "
+CREATE TABLE instant_message (
+    id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    sender_id INTEGER NOT NULL,
+    sender_name TEXT NOT NULL,
+    recipient_id INTEGER NOT NULL,
+    sent_at INTEGER NOT NULL,
+    payload BLOB NOT NULL
+);
+CREATE INDEX instant_message_title_recipient_id_idx ON instant_message (
+    title,
+    recipient_id
+);
+CREATE TABLE blocked_user (
+    title INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    blocked_user_id INTEGER NOT NULL,
+    PRIMARY KEY (title, owner_id, blocked_user_id)
+);
+";
+
+const MESSAGING_SCHEMA: DbSchema = DbSchema {
+    name: "messaging",
+    changelogs: &[MESSAGING_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&MESSAGING_SCHEMA);
+    }
+}
@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwMessagingService;
+
+use bitdemon::lobby::messaging::Messaging2Handler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_messaging2_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(Messaging2Handler::new(Arc::new(DwMessagingService::new())))
+}
@@ -0,0 +1,265 @@
+use crate::lobby::messaging::db::MESSAGING_DB;
+use bitdemon::domain::result_slice::ResultSlice;
+use bitdemon::lobby::messaging::{InstantMessage, MessagingService, MessagingServiceError};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::{info, warn};
+use num_traits::ToPrimitive;
+use rusqlite::DropBehavior;
+
+pub struct DwMessagingService {}
+
+const MAX_MESSAGE_SIZE: usize = 1_024; // 1KiB
+const MAX_MESSAGES_PER_REQUEST: usize = 100;
+
+impl MessagingService for DwMessagingService {
+    fn send_message(
+        &self,
+        session: &BdSession,
+        recipient_id: u64,
+        payload: Vec<u8>,
+    ) -> Result<u64, MessagingServiceError> {
+        info!(
+            "Sending message recipient={recipient_id} len={}",
+            payload.len()
+        );
+
+        if recipient_id == 0 {
+            warn!("Tried to send message to invalid recipient");
+            return Err(MessagingServiceError::InvalidRecipientError);
+        }
+
+        if payload.len() > MAX_MESSAGE_SIZE {
+            warn!("Tried to send message that is too large");
+            return Err(MessagingServiceError::MessageTooLargeError);
+        }
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+
+        let message_id = MESSAGING_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            let blocked: bool = transaction
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM blocked_user b
+                     WHERE b.title = ?1 AND b.owner_id = ?2 AND b.blocked_user_id = ?3)",
+                    (title_num, recipient_id, authentication.user_id),
+                    |row| row.get(0),
+                )
+                .expect("query to be successful");
+
+            if blocked {
+                info!("Discarding message since recipient blocked sender");
+                return 0;
+            }
+
+            transaction
+                .execute(
+                    "INSERT INTO instant_message
+                     (title, sender_id, sender_name, recipient_id, sent_at, payload)
+                     VALUES (?, ?, ?, ?, ?, ?)",
+                    (
+                        title_num,
+                        authentication.user_id,
+                        authentication.username.as_str(),
+                        recipient_id,
+                        now,
+                        payload,
+                    ),
+                )
+                .expect("insertion to be successful");
+            let message_id = transaction.last_insert_rowid() as u64;
+
+            transaction.commit().expect("commit to be successful");
+
+            message_id
+        });
+
+        Ok(message_id)
+    }
+
+    fn get_messages(
+        &self,
+        session: &BdSession,
+        item_offset: usize,
+        item_count: usize,
+    ) -> Result<ResultSlice<InstantMessage>, MessagingServiceError> {
+        info!("Retrieving messages item_offset={item_offset} item_count={item_count}");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let item_count = item_count.min(MAX_MESSAGES_PER_REQUEST);
+
+        MESSAGING_DB.with_borrow_mut(|db| {
+            let mut transaction = db.transaction().expect("transaction to be started");
+            transaction.set_drop_behavior(DropBehavior::Commit);
+
+            let total_count: usize = transaction
+                .query_row(
+                    "SELECT COUNT(*) FROM instant_message m
+                     WHERE m.title = ?1 AND m.recipient_id = ?2",
+                    (title_num, authentication.user_id),
+                    |row| row.get(0),
+                )
+                .expect("query to be successful");
+
+            let messages = transaction
+                .prepare(
+                    "SELECT m.id, m.sender_id, m.sender_name, m.sent_at, m.payload
+                     FROM instant_message m
+                     WHERE m.title = ?1 AND m.recipient_id = ?2
+                     ORDER BY m.sent_at DESC, m.id DESC
+                     LIMIT ?4 OFFSET ?3",
+                )
+                .expect("preparation to be successful")
+                .query_map(
+                    (title_num, authentication.user_id, item_offset, item_count),
+                    |row| {
+                        Ok(InstantMessage {
+                            id: row.get(0)?,
+                            sender_id: row.get(1)?,
+                            sender_name: row.get(2)?,
+                            sent_at: row.get(3)?,
+                            payload: row.get(4)?,
+                        })
+                    },
+                )
+                .expect("query to be successful")
+                .filter_map(|message| message.ok())
+                .collect();
+
+            Ok(ResultSlice::with_total_count(
+                messages,
+                item_offset,
+                total_count,
+            ))
+        })
+    }
+
+    fn delete_messages(
+        &self,
+        session: &BdSession,
+        message_ids: &[u64],
+    ) -> Result<(), MessagingServiceError> {
+        info!("Deleting {} messages", message_ids.len());
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        MESSAGING_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for message_id in message_ids {
+                transaction
+                    .execute(
+                        "DELETE FROM instant_message
+                         WHERE id = ?1 AND title = ?2 AND recipient_id = ?3",
+                        (message_id, title_num, authentication.user_id),
+                    )
+                    .expect("deletion to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn block_users(
+        &self,
+        session: &BdSession,
+        user_ids: &[u64],
+    ) -> Result<(), MessagingServiceError> {
+        info!("Blocking {} users", user_ids.len());
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        if user_ids.contains(&authentication.user_id) {
+            warn!("Tried to block self");
+            return Err(MessagingServiceError::SelfBlockNotAllowedError);
+        }
+
+        MESSAGING_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for user_id in user_ids {
+                transaction
+                    .execute(
+                        "INSERT OR IGNORE INTO blocked_user (title, owner_id, blocked_user_id)
+                         VALUES (?, ?, ?)",
+                        (title_num, authentication.user_id, user_id),
+                    )
+                    .expect("insertion to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn unblock_users(
+        &self,
+        session: &BdSession,
+        user_ids: &[u64],
+    ) -> Result<(), MessagingServiceError> {
+        info!("Unblocking {} users", user_ids.len());
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        MESSAGING_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for user_id in user_ids {
+                transaction
+                    .execute(
+                        "DELETE FROM blocked_user
+                         WHERE title = ?1 AND owner_id = ?2 AND blocked_user_id = ?3",
+                        (title_num, authentication.user_id, user_id),
+                    )
+                    .expect("deletion to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn get_blocked_users(&self, session: &BdSession) -> Result<Vec<u64>, MessagingServiceError> {
+        info!("Retrieving blocked users");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        let blocked_users = MESSAGING_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT b.blocked_user_id FROM blocked_user b
+                 WHERE b.title = ?1 AND b.owner_id = ?2",
+            )
+            .expect("preparation to be successful")
+            .query_map((title_num, authentication.user_id), |row| row.get(0))
+            .expect("query to be successful")
+            .filter_map(|user_id| user_id.ok())
+            .collect()
+        });
+
+        Ok(blocked_users)
+    }
+}
+
+impl Default for DwMessagingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwMessagingService {
+    pub fn new() -> DwMessagingService {
+        DwMessagingService {}
+    }
+}
@@ -0,0 +1,19 @@
+pub mod anti_cheat;
+pub mod content_streaming;
+pub mod counter;
+pub mod event_log;
+pub mod facebook;
+pub mod group;
+pub mod league;
+pub mod link_code;
+pub mod mail;
+pub mod matchmaking;
+pub mod messaging;
+pub mod profile;
+pub mod rich_presence;
+pub mod storage;
+pub mod title_utilities;
+pub mod twitch;
+pub mod twitter;
+pub mod vote_rank;
+pub mod youtube;
@@ -0,0 +1,61 @@
+﻿use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static PROFILE_DB: RefCell<Connection> = RefCell::new(open_db(&PROFILE_SCHEMA));
+}
+
+const PROFILE_CHANGELOG_0: &str = "
+CREATE TABLE user_profile (
+    id INTEGER PRIMARY KEY,
+    title INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    profile_type INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+";
+
+const PROFILE_SCHEMA: DbSchema = DbSchema {
+    name: "profile",
+    changelogs: &[PROFILE_CHANGELOG_0],
+};
+
+pub enum ProfileType {
+    Public,
+    Private,
+}
+
+impl From<u8> for ProfileType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => ProfileType::Private,
+            _ => {
+                debug_assert_eq!(value, 1);
+                ProfileType::Public
+            }
+        }
+    }
+}
+
+impl From<ProfileType> for u8 {
+    fn from(value: ProfileType) -> Self {
+        match value {
+            ProfileType::Private => 0,
+            ProfileType::Public => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&PROFILE_SCHEMA);
+    }
+}
@@ -1,7 +1,8 @@
 ﻿mod db;
 mod service;
 
-use crate::lobby::profile::service::DwProfileService;
+pub use service::DwProfileService;
+
 use bitdemon::lobby::profile::ProfileHandler;
 use bitdemon::lobby::ThreadSafeLobbyHandler;
 use std::sync::Arc;
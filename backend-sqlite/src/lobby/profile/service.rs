@@ -120,6 +120,12 @@ impl ProfileService for DwProfileService {
     }
 }
 
+impl Default for DwProfileService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DwProfileService {
     pub fn new() -> DwProfileService {
         DwProfileService {}
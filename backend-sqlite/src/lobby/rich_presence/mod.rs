@@ -1,6 +1,7 @@
 ﻿mod service;
 
-use crate::lobby::rich_presence::service::DwRichPresenceService;
+pub use service::DwRichPresenceService;
+
 use bitdemon::lobby::rich_presence::RichPresenceHandler;
 use bitdemon::lobby::ThreadSafeLobbyHandler;
 use bitdemon::networking::session_manager::SessionManager;
@@ -0,0 +1,64 @@
+﻿use crate::db::{open_db, DbSchema};
+use bitdemon::domain::title::Title;
+use bitdemon::lobby::storage::FileVisibility;
+use num_traits::{FromPrimitive, ToPrimitive};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static STORAGE_DB: RefCell<Connection> = RefCell::new(open_db(&STORAGE_SCHEMA));
+}
+
+const STORAGE_CHANGELOG_0: &str = "
+CREATE TABLE user_file (
+    id INTEGER PRIMARY KEY,
+    filename TEXT NOT NULL,
+    title INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    visibility INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+";
+
+const STORAGE_SCHEMA: DbSchema = DbSchema {
+    name: "storage",
+    changelogs: &[STORAGE_CHANGELOG_0],
+};
+
+pub fn from_title(value: Title) -> u32 {
+    value.to_u32().unwrap()
+}
+
+pub fn _to_title(value: u32) -> Title {
+    Title::from_u32(value).expect("to be a valid title")
+}
+
+pub fn from_file_visibility(value: FileVisibility) -> u8 {
+    match value {
+        FileVisibility::VisiblePrivate => 0u8,
+        FileVisibility::VisiblePublic => 1u8,
+    }
+}
+
+pub fn to_file_visibility(value: u8) -> FileVisibility {
+    match value {
+        0 => FileVisibility::VisiblePrivate,
+        value => {
+            debug_assert_eq!(value, 1u8);
+            FileVisibility::VisiblePublic
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&STORAGE_SCHEMA);
+    }
+}
@@ -0,0 +1,22 @@
+﻿use crate::config::BackendConfig;
+use bitdemon::lobby::storage::StorageHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+mod db;
+mod publisher_file;
+mod user_file;
+
+pub use publisher_file::DwPublisherStorageService;
+pub use user_file::{user_storage_usage, DwUserStorageService, UserStorageUsage};
+
+pub fn create_storage_handler(config: &BackendConfig) -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(StorageHandler::new(
+        Arc::new(DwUserStorageService::new(
+            config.user_file_size_limits.clone(),
+        )),
+        Arc::new(DwPublisherStorageService::new(
+            config.publisher_storage_dir.clone(),
+        )),
+    ))
+}
@@ -7,12 +7,18 @@ use bitdemon::networking::bd_session::BdSession;
 use log::{info, warn};
 use num_traits::ToPrimitive;
 use std::fs;
-use std::fs::DirEntry;
+use std::fs::{DirEntry, File};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Component, PathBuf};
 use std::str::FromStr;
 use std::time::UNIX_EPOCH;
 
-pub struct DwPublisherStorageService {}
+pub struct DwPublisherStorageService {
+    publisher_dir: PathBuf,
+}
+
+// Larger files can only be retrieved in chunks
+const MAX_WHOLE_PUBLISHER_FILE_SIZE: u64 = 0x2000000;
 
 impl PublisherStorageService for DwPublisherStorageService {
     fn get_publisher_file_data(
@@ -22,29 +28,56 @@ impl PublisherStorageService for DwPublisherStorageService {
     ) -> Result<Vec<u8>, StorageServiceError> {
         info!("Requesting publisher file {}", filename.as_str());
 
-        let path_buf = PathBuf::from_str(&filename)
-            .map_err(|_| StorageServiceError::StorageFileNotFoundError)?;
+        let full_file_path = self.publisher_file_path(session, &filename)?;
 
-        let directory_traversal = path_buf
-            .components()
-            .any(|component| component == Component::ParentDir);
+        let file_size = fs::metadata(&full_file_path)
+            .map_err(|_| {
+                warn!("Requested publisher file could not be found",);
+                StorageServiceError::StorageFileNotFoundError
+            })?
+            .len();
 
-        if directory_traversal {
-            warn!("User attempted directory traversal!",);
-            return Err(StorageServiceError::StorageFileNotFoundError);
+        if file_size > MAX_WHOLE_PUBLISHER_FILE_SIZE {
+            warn!("Requested publisher file is too large to be sent at once (size={file_size})");
+            return Err(StorageServiceError::StorageFileTooLargeError);
         }
 
-        let full_file_path = format!(
-            "storage/publisher/{}/{filename}",
-            session.authentication().unwrap().title.to_u32().unwrap()
-        );
-
         fs::read(full_file_path).map_err(|_| {
             warn!("Requested publisher file could not be found",);
             StorageServiceError::StorageFileNotFoundError
         })
     }
 
+    fn get_publisher_file_data_range(
+        &self,
+        session: &BdSession,
+        filename: String,
+        offset: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, StorageServiceError> {
+        info!(
+            "Requesting publisher file {} offset={offset} length={length}",
+            filename.as_str()
+        );
+
+        let full_file_path = self.publisher_file_path(session, &filename)?;
+
+        let mut file = File::open(full_file_path).map_err(|_| {
+            warn!("Requested publisher file could not be found",);
+            StorageServiceError::StorageFileNotFoundError
+        })?;
+
+        let mut data = Vec::new();
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.take(length as u64).read_to_end(&mut data))
+            .map_err(|_| {
+                warn!("Requested publisher file could not be read",);
+                StorageServiceError::StorageFileNotFoundError
+            })?;
+
+        Ok(data)
+    }
+
     fn list_publisher_files(
         &self,
         session: &BdSession,
@@ -55,7 +88,7 @@ impl PublisherStorageService for DwPublisherStorageService {
         info!("Listing publisher files min_date_time={min_date_time} item_offset={item_offset} item_count={item_count}");
 
         let title = session.authentication().unwrap().title;
-        let full_dir_path = format!("storage/publisher/{}", title.to_u32().unwrap());
+        let full_dir_path = self.title_dir(title);
 
         let dir = fs::read_dir(full_dir_path);
         if dir.is_err() {
@@ -90,7 +123,7 @@ impl PublisherStorageService for DwPublisherStorageService {
         info!("Filtering publisher files min_date_time={min_date_time} item_offset={item_offset} item_count={item_count} filter={filter}");
 
         let title = session.authentication().unwrap().title;
-        let full_dir_path = format!("storage/publisher/{}", title.to_u32().unwrap());
+        let full_dir_path = self.title_dir(title);
 
         let dir = fs::read_dir(full_dir_path);
         if dir.is_err() {
@@ -125,8 +158,34 @@ impl PublisherStorageService for DwPublisherStorageService {
 }
 
 impl DwPublisherStorageService {
-    pub fn new() -> DwPublisherStorageService {
-        DwPublisherStorageService {}
+    pub fn new(publisher_dir: PathBuf) -> DwPublisherStorageService {
+        DwPublisherStorageService { publisher_dir }
+    }
+
+    fn title_dir(&self, title: Title) -> PathBuf {
+        self.publisher_dir.join(title.to_u32().unwrap().to_string())
+    }
+
+    fn publisher_file_path(
+        &self,
+        session: &BdSession,
+        filename: &str,
+    ) -> Result<PathBuf, StorageServiceError> {
+        let path_buf = PathBuf::from_str(filename)
+            .map_err(|_| StorageServiceError::StorageFileNotFoundError)?;
+
+        let directory_traversal = path_buf
+            .components()
+            .any(|component| component == Component::ParentDir);
+
+        if directory_traversal {
+            warn!("User attempted directory traversal!",);
+            return Err(StorageServiceError::StorageFileNotFoundError);
+        }
+
+        Ok(self
+            .title_dir(session.authentication().unwrap().title)
+            .join(path_buf))
     }
 
     fn map_info_info(title: Title, entry: DirEntry) -> StorageFileInfo {
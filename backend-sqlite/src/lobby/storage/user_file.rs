@@ -1,16 +1,21 @@
-﻿use crate::lobby::storage::db::{from_file_visibility, from_title, to_file_visibility, STORAGE_DB};
+﻿use crate::config::UserFileSizeLimits;
+use crate::lobby::storage::db::{from_file_visibility, from_title, to_file_visibility, STORAGE_DB};
 use bitdemon::domain::result_slice::ResultSlice;
+use bitdemon::domain::title::Title;
 use bitdemon::lobby::storage::{
     FileVisibility, StorageFileInfo, StorageServiceError, UserStorageService,
 };
 use bitdemon::networking::bd_session::BdSession;
 use chrono::Utc;
 use log::{info, warn};
+use num_traits::FromPrimitive;
+use serde::Serialize;
 
-pub struct DwUserStorageService {}
+pub struct DwUserStorageService {
+    file_size_limits: UserFileSizeLimits,
+}
 
 const MAX_FILENAME_LENGTH: usize = 260;
-const MAX_USER_FILE_SIZE: usize = 50_000; // 50KB
 
 impl UserStorageService for DwUserStorageService {
     fn get_storage_file_data_by_id(
@@ -116,12 +121,12 @@ impl UserStorageService for DwUserStorageService {
             return Err(StorageServiceError::FilenameTooLongError);
         }
 
-        if file_size > MAX_USER_FILE_SIZE {
+        let title = session.authentication().unwrap().title;
+        if file_size > self.file_size_limits.storage_limit(title) {
             warn!("Tried to upload file that is too large");
             return Err(StorageServiceError::StorageFileTooLargeError);
         }
 
-        let title = session.authentication().unwrap().title;
         let title_num = from_title(title);
         let now = Utc::now().timestamp();
         let visibility_num = from_file_visibility(visibility);
@@ -197,13 +202,13 @@ impl UserStorageService for DwUserStorageService {
             return Err(StorageServiceError::PermissionDeniedError);
         }
 
-        if file_size > MAX_USER_FILE_SIZE {
+        let title = session.authentication().unwrap().title;
+        if file_size > self.file_size_limits.storage_limit(title) {
             warn!("Tried to update file with data that is too large");
             return Err(StorageServiceError::StorageFileTooLargeError);
         }
 
         let now = Utc::now().timestamp();
-        let title = session.authentication().unwrap().title;
         let title_num = from_title(title);
 
         STORAGE_DB.with_borrow_mut(|db| {
@@ -267,7 +272,45 @@ impl UserStorageService for DwUserStorageService {
 }
 
 impl DwUserStorageService {
-    pub fn new() -> DwUserStorageService {
-        DwUserStorageService {}
+    pub fn new(file_size_limits: UserFileSizeLimits) -> DwUserStorageService {
+        DwUserStorageService { file_size_limits }
     }
 }
+
+/// The storage a user occupies for a single title.
+#[derive(Serialize)]
+pub struct UserStorageUsage {
+    pub title: u32,
+    pub file_count: u64,
+    pub total_size: u64,
+    pub max_file_size: usize,
+}
+
+pub fn user_storage_usage(
+    user_id: u64,
+    file_size_limits: &UserFileSizeLimits,
+) -> Vec<UserStorageUsage> {
+    STORAGE_DB.with_borrow(|db| {
+        db.prepare(
+            "SELECT f.title, COUNT(*), SUM(LENGTH(f.data)) FROM user_file f
+             WHERE f.owner_id = ?1 GROUP BY f.title",
+        )
+        .expect("preparation to be successful")
+        .query_map((user_id,), |row| {
+            let title = row.get(0)?;
+            let max_file_size = Title::from_u32(title)
+                .map(|title| file_size_limits.storage_limit(title))
+                .unwrap_or(file_size_limits.storage);
+
+            Ok(UserStorageUsage {
+                title,
+                file_count: row.get(1)?,
+                total_size: row.get(2)?,
+                max_file_size,
+            })
+        })
+        .expect("query to be successful")
+        .filter_map(|usage| usage.ok())
+        .collect()
+    })
+}
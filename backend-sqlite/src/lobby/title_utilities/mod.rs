@@ -0,0 +1,19 @@
+mod service;
+
+pub use service::DwTitleUtilitiesService;
+
+use crate::config::BackendConfig;
+use bitdemon::lobby::title_utilities::TitleUtilitiesHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use bitdemon::networking::session_manager::SessionManager;
+use std::sync::Arc;
+
+pub fn create_title_utilities_handler(
+    config: &BackendConfig,
+    session_manager: Arc<SessionManager>,
+) -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(TitleUtilitiesHandler::new(DwTitleUtilitiesService::new(
+        config.title_utilities.clone(),
+        session_manager,
+    )))
+}
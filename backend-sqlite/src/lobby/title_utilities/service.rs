@@ -0,0 +1,220 @@
+use crate::config::{TitleUtilitiesConfig, TitleUtilitiesTitleConfig};
+use crate::lobby::event_log::DwEventLogService;
+use bitdemon::domain::title::Title;
+use bitdemon::lobby::event_log::{Event, EventLogService, EventPayload};
+use bitdemon::lobby::title_utilities::{
+    TitleStats, TitleUtilitiesService, UserName, UserOnlineStatus,
+};
+use bitdemon::networking::bd_session::{BdSession, SessionId};
+use bitdemon::networking::session_manager::SessionManager;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::sync::{Arc, RwLock};
+
+/// Events of the legacy event api have no category.
+const LEGACY_EVENT_CATEGORY: u32 = 0;
+
+struct OnlineUser {
+    user_id: u64,
+    username: String,
+    title: Title,
+}
+
+/// Answers the title utilities with the per-title settings of the config.
+/// Users count as online while they have an authenticated lobby session,
+/// and only the names of online users are known.
+pub struct DwTitleUtilitiesService {
+    config: TitleUtilitiesConfig,
+    event_log_service: DwEventLogService,
+    online_users: RwLock<HashMap<SessionId, OnlineUser>>,
+}
+
+impl TitleUtilitiesService for DwTitleUtilitiesService {
+    fn verify_string(&self, session: &BdSession, value: String) -> bool {
+        let Some(title_config) = self.title_config(session) else {
+            return true;
+        };
+        let value = value.to_lowercase();
+
+        !title_config
+            .blocked_words
+            .iter()
+            .any(|blocked_word| value.contains(blocked_word.to_lowercase().as_str()))
+    }
+
+    fn get_title_stats(&self, session: &BdSession) -> TitleStats {
+        let title = session.authentication().unwrap().title;
+        let online_users: HashSet<u64> = self
+            .online_users
+            .read()
+            .unwrap()
+            .values()
+            .filter(|online_user| online_user.title == title)
+            .map(|online_user| online_user.user_id)
+            .collect();
+
+        TitleStats {
+            online_users: online_users.len() as u32,
+        }
+    }
+
+    fn record_event(&self, session: &BdSession, payload: EventPayload) {
+        let event = Event {
+            category_id: LEGACY_EVENT_CATEGORY,
+            payload,
+        };
+
+        if let Err(e) = self.event_log_service.record_events(session, vec![event]) {
+            warn!("Failed to record legacy event: {e}");
+        }
+    }
+
+    fn record_ip(&self, _session: &BdSession, _ip: Ipv4Addr) {
+        // The address is only logged, clients are located by the DML service
+    }
+
+    fn get_server_time(&self, session: &BdSession) -> DateTime<Utc> {
+        let offset_secs = self
+            .title_config(session)
+            .map(|title_config| title_config.server_time_offset_secs)
+            .unwrap_or_default();
+
+        Utc::now() + TimeDelta::seconds(offset_secs)
+    }
+
+    fn are_users_online(&self, session: &BdSession, user_ids: Vec<u64>) -> Vec<UserOnlineStatus> {
+        let title = session.authentication().unwrap().title;
+        let online_users = self.online_users.read().unwrap();
+
+        user_ids
+            .into_iter()
+            .map(|user_id| UserOnlineStatus {
+                user_id,
+                online: online_users.values().any(|online_user| {
+                    online_user.user_id == user_id && online_user.title == title
+                }),
+            })
+            .collect()
+    }
+
+    fn get_user_names(&self, _session: &BdSession, user_ids: Vec<u64>) -> Vec<UserName> {
+        let online_users = self.online_users.read().unwrap();
+
+        user_ids
+            .into_iter()
+            .filter_map(|user_id| {
+                online_users
+                    .values()
+                    .find(|online_user| online_user.user_id == user_id)
+                    .map(|online_user| UserName {
+                        user_id,
+                        username: online_user.username.clone(),
+                    })
+            })
+            .collect()
+    }
+}
+
+impl DwTitleUtilitiesService {
+    pub fn new(
+        config: TitleUtilitiesConfig,
+        session_manager: Arc<SessionManager>,
+    ) -> Arc<DwTitleUtilitiesService> {
+        let service = Arc::new(DwTitleUtilitiesService {
+            config,
+            event_log_service: DwEventLogService::new(),
+            online_users: RwLock::new(HashMap::new()),
+        });
+
+        Self::register_session_manager_callbacks(service.clone(), session_manager);
+
+        service
+    }
+
+    fn register_session_manager_callbacks(
+        service: Arc<Self>,
+        session_manager: Arc<SessionManager>,
+    ) {
+        let authenticated_service = service.clone();
+        session_manager.on_session_authenticated(move |session| {
+            let authentication = session.authentication().unwrap();
+            authenticated_service.online_users.write().unwrap().insert(
+                session.id,
+                OnlineUser {
+                    user_id: authentication.user_id,
+                    username: authentication.username.clone(),
+                    title: authentication.title,
+                },
+            );
+        });
+
+        session_manager.on_session_unregistered(move |session| {
+            service.online_users.write().unwrap().remove(&session.id);
+        });
+    }
+
+    fn title_config(&self, session: &BdSession) -> Option<&TitleUtilitiesTitleConfig> {
+        self.config
+            .title_config(session.authentication().unwrap().title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use num_traits::ToPrimitive;
+
+    fn service() -> (Arc<SessionManager>, Arc<DwTitleUtilitiesService>) {
+        let mut config = TitleUtilitiesConfig::default();
+        config.titles.insert(
+            Title::T6Pc.to_u32().unwrap(),
+            TitleUtilitiesTitleConfig {
+                blocked_words: vec!["Cheat".to_string()],
+                server_time_offset_secs: 3600,
+            },
+        );
+
+        let session_manager = Arc::new(SessionManager::new());
+        let service = DwTitleUtilitiesService::new(config, session_manager.clone());
+
+        (session_manager, service)
+    }
+
+    #[test]
+    fn applies_title_settings() {
+        let (_, service) = service();
+        let session = authenticated_session(1, Title::T6Pc);
+        let other_title_session = authenticated_session(1, Title::Iw5);
+
+        assert!(!service.verify_string(&session, "no cheating".to_string()));
+        assert!(service.verify_string(&other_title_session, "no cheating".to_string()));
+        assert!(service.get_server_time(&session) > Utc::now() + TimeDelta::minutes(59));
+        assert!(service.get_server_time(&other_title_session) <= Utc::now());
+    }
+
+    #[test]
+    fn tracks_online_users_per_title() {
+        let (session_manager, service) = service();
+        let mut session = authenticated_session(1, Title::T6Pc);
+        session.id = 1;
+        let mut other_title_session = authenticated_session(2, Title::Iw5);
+        other_title_session.id = 2;
+        session_manager.authenticate_session(&session);
+        session_manager.authenticate_session(&other_title_session);
+
+        let statuses = service.are_users_online(&session, vec![1, 2]);
+        assert!(statuses[0].online);
+        assert!(!statuses[1].online);
+        assert_eq!(service.get_title_stats(&session).online_users, 1);
+        assert_eq!(
+            service.get_user_names(&session, vec![2, 3])[0].username,
+            "user2"
+        );
+
+        session_manager.unregister_session(&session);
+        assert!(!service.are_users_online(&session, vec![1])[0].online);
+    }
+}
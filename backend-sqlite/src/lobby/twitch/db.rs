@@ -0,0 +1,33 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static TWITCH_DB: RefCell<Connection> = RefCell::new(open_db(&TWITCH_SCHEMA));
+}
+
+const TWITCH_CHANGELOG_0: &str = "
+CREATE TABLE twitch_account (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    token TEXT NOT NULL,
+    linked_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id)
+);
+";
+
+const TWITCH_SCHEMA: DbSchema = DbSchema {
+    name: "twitch",
+    changelogs: &[TWITCH_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&TWITCH_SCHEMA);
+    }
+}
@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwTwitchService;
+
+use bitdemon::lobby::twitch::TwitchHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_twitch_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(TwitchHandler::new(Arc::new(DwTwitchService::new())))
+}
@@ -0,0 +1,79 @@
+use crate::lobby::twitch::db::TWITCH_DB;
+use bitdemon::lobby::twitch::TwitchService;
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::info;
+use num_traits::ToPrimitive;
+use std::error::Error;
+
+pub struct DwTwitchService {}
+
+impl TwitchService for DwTwitchService {
+    fn link_account(&self, session: &BdSession, token: String) -> Result<(), Box<dyn Error>> {
+        info!("Linking twitch account");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        TWITCH_DB.with_borrow(|db| {
+            db.execute(
+                "INSERT OR REPLACE INTO twitch_account (title, user_id, token, linked_at)
+                 VALUES (?, ?, ?, ?)",
+                (
+                    title_num,
+                    authentication.user_id,
+                    token.as_str(),
+                    Utc::now().timestamp(),
+                ),
+            )
+            .expect("insertion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn unlink_account(&self, session: &BdSession) -> Result<(), Box<dyn Error>> {
+        info!("Unlinking twitch account");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        TWITCH_DB.with_borrow(|db| {
+            db.execute(
+                "DELETE FROM twitch_account WHERE title = ?1 AND user_id = ?2",
+                (title_num, authentication.user_id),
+            )
+            .expect("deletion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn is_linked(&self, session: &BdSession) -> Result<bool, Box<dyn Error>> {
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        let count: u64 = TWITCH_DB.with_borrow(|db| {
+            db.query_row(
+                "SELECT COUNT(*) FROM twitch_account WHERE title = ?1 AND user_id = ?2",
+                (title_num, authentication.user_id),
+                |row| row.get(0),
+            )
+            .expect("query to be successful")
+        });
+
+        Ok(count > 0)
+    }
+}
+
+impl Default for DwTwitchService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwTwitchService {
+    pub fn new() -> DwTwitchService {
+        DwTwitchService {}
+    }
+}
@@ -0,0 +1,33 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static TWITTER_DB: RefCell<Connection> = RefCell::new(open_db(&TWITTER_SCHEMA));
+}
+
+const TWITTER_CHANGELOG_0: &str = "
+CREATE TABLE twitter_account (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    handle TEXT NOT NULL,
+    linked_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id)
+);
+";
+
+const TWITTER_SCHEMA: DbSchema = DbSchema {
+    name: "twitter",
+    changelogs: &[TWITTER_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&TWITTER_SCHEMA);
+    }
+}
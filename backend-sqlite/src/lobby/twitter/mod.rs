@@ -0,0 +1,15 @@
+mod db;
+mod service;
+
+pub use service::DwTwitterService;
+
+use crate::config::BackendConfig;
+use bitdemon::lobby::twitter::TwitterHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_twitter_handler(config: &BackendConfig) -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(TwitterHandler::new(Arc::new(DwTwitterService::new(
+        config.twitter_webhook_url.clone(),
+    ))))
+}
@@ -0,0 +1,125 @@
+use crate::lobby::twitter::db::TWITTER_DB;
+use bitdemon::lobby::twitter::{TwitterAccountInfo, TwitterService, TwitterServiceError};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::{info, warn};
+use num_traits::ToPrimitive;
+use rusqlite::OptionalExtension;
+use serde_json::json;
+
+/// Remembers registered Twitter handles.
+/// Posts are forwarded to a webhook if one is configured and dropped otherwise.
+pub struct DwTwitterService {
+    webhook_url: Option<String>,
+}
+
+impl TwitterService for DwTwitterService {
+    fn register_account(
+        &self,
+        session: &BdSession,
+        handle: String,
+        _password: String,
+    ) -> Result<(), TwitterServiceError> {
+        let handle = handle.trim().trim_start_matches('@').to_string();
+        if handle.is_empty() {
+            return Err(TwitterServiceError::AuthAttemptFailedError);
+        }
+
+        info!("Registering twitter account {handle}");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        TWITTER_DB.with_borrow(|db| {
+            db.execute(
+                "INSERT OR REPLACE INTO twitter_account (title, user_id, handle, linked_at)
+                 VALUES (?, ?, ?, ?)",
+                (
+                    title_num,
+                    authentication.user_id,
+                    handle.as_str(),
+                    Utc::now().timestamp(),
+                ),
+            )
+            .expect("insertion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn unregister_account(&self, session: &BdSession) -> Result<(), TwitterServiceError> {
+        info!("Unregistering twitter account");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        TWITTER_DB.with_borrow(|db| {
+            db.execute(
+                "DELETE FROM twitter_account WHERE title = ?1 AND user_id = ?2",
+                (title_num, authentication.user_id),
+            )
+            .expect("deletion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn is_registered(&self, session: &BdSession) -> Result<bool, TwitterServiceError> {
+        Ok(Self::registered_handle(session).is_some())
+    }
+
+    fn post(&self, session: &BdSession, message: String) -> Result<(), TwitterServiceError> {
+        let handle =
+            Self::registered_handle(session).ok_or(TwitterServiceError::NotRegisteredError)?;
+
+        let Some(webhook_url) = self.webhook_url.as_deref() else {
+            info!("Dropping twitter post of {handle} as no webhook is configured");
+            return Ok(());
+        };
+
+        let authentication = session.authentication().unwrap();
+        let payload = json!({
+            "title": authentication.title.to_u32().unwrap(),
+            "user_id": authentication.user_id,
+            "username": authentication.username,
+            "handle": handle,
+            "message": message,
+        });
+
+        ureq::post(webhook_url)
+            .send_json(&payload)
+            .map(|_| ())
+            .map_err(|e| {
+                warn!("Failed to forward twitter post to webhook: {e}");
+                TwitterServiceError::UnavailableError
+            })
+    }
+
+    fn get_info(&self, session: &BdSession) -> Result<TwitterAccountInfo, TwitterServiceError> {
+        let handle =
+            Self::registered_handle(session).ok_or(TwitterServiceError::NotRegisteredError)?;
+
+        Ok(TwitterAccountInfo { handle })
+    }
+}
+
+impl DwTwitterService {
+    pub fn new(webhook_url: Option<String>) -> DwTwitterService {
+        DwTwitterService { webhook_url }
+    }
+
+    fn registered_handle(session: &BdSession) -> Option<String> {
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        TWITTER_DB.with_borrow(|db| {
+            db.query_row(
+                "SELECT t.handle FROM twitter_account t WHERE t.title = ?1 AND t.user_id = ?2",
+                (title_num, authentication.user_id),
+                |row| row.get(0),
+            )
+            .optional()
+            .expect("query to be successful")
+        })
+    }
+}
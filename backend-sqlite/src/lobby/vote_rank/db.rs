@@ -0,0 +1,36 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static VOTE_RANK_DB: RefCell<Connection> = RefCell::new(open_db(&VOTE_RANK_SCHEMA));
+}
+
+const VOTE_RANK_CHANGELOG_0: &str = "
+CREATE TABLE vote (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    entity_id INTEGER NOT NULL,
+    category INTEGER NOT NULL,
+    rating INTEGER NOT NULL,
+    voted_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id, entity_id, category)
+);
+CREATE INDEX vote_entity ON vote (title, entity_id, category);
+";
+
+const VOTE_RANK_SCHEMA: DbSchema = DbSchema {
+    name: "vote_rank",
+    changelogs: &[VOTE_RANK_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&VOTE_RANK_SCHEMA);
+    }
+}
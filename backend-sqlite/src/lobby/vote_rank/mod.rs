@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwVoteRankService;
+
+use bitdemon::lobby::vote_rank::VoteRankHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_vote_rank_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(VoteRankHandler::new(Arc::new(DwVoteRankService::new())))
+}
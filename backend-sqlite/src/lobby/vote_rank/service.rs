@@ -0,0 +1,225 @@
+use crate::lobby::vote_rank::db::VOTE_RANK_DB;
+use bitdemon::domain::result_slice::ResultSlice;
+use bitdemon::lobby::vote_rank::{
+    CategorizedRatingInfo, RatingAggregate, RatingInfo, Vote, VoteRankService, VoteRankServiceError,
+};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::info;
+use num_traits::{FromPrimitive, ToPrimitive};
+use rusqlite::types::Value;
+use rusqlite::DropBehavior;
+use std::rc::Rc;
+
+const MAX_RATINGS_PER_REQUEST: usize = 100;
+const MAX_HISTORY_PER_REQUEST: usize = 100;
+
+pub struct DwVoteRankService {}
+
+impl VoteRankService for DwVoteRankService {
+    fn submit_ratings(
+        &self,
+        session: &BdSession,
+        ratings: Vec<CategorizedRatingInfo>,
+    ) -> Result<(), VoteRankServiceError> {
+        if ratings.len() > MAX_RATINGS_PER_REQUEST {
+            return Err(VoteRankServiceError::TooManyRatingsError);
+        }
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let now = Utc::now().timestamp();
+
+        VOTE_RANK_DB.with_borrow_mut(|db| {
+            let transaction = db.transaction().expect("transaction to be started");
+
+            for rating in &ratings {
+                transaction
+                    .execute(
+                        "INSERT OR REPLACE INTO vote (title, user_id, entity_id, category, rating, voted_at)
+                         VALUES (?, ?, ?, ?, ?, ?)",
+                        (
+                            title_num,
+                            authentication.user_id,
+                            rating.rating_info.entity_id,
+                            rating.category,
+                            rating.rating_info.rating.to_u8().unwrap(),
+                            now,
+                        ),
+                    )
+                    .expect("insertion to be successful");
+            }
+
+            transaction.commit().expect("commit to be successful");
+        });
+
+        info!("Recorded {} votes", ratings.len());
+
+        Ok(())
+    }
+
+    fn get_vote_history(
+        &self,
+        session: &BdSession,
+        item_offset: usize,
+        item_count: usize,
+    ) -> ResultSlice<CategorizedRatingInfo> {
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+        let item_count = item_count.min(MAX_HISTORY_PER_REQUEST);
+
+        VOTE_RANK_DB.with_borrow_mut(|db| {
+            let mut transaction = db.transaction().expect("transaction to be started");
+            transaction.set_drop_behavior(DropBehavior::Commit);
+
+            let total_count: usize = transaction
+                .query_row(
+                    "SELECT COUNT(*) FROM vote v WHERE v.title = ?1 AND v.user_id = ?2",
+                    (title_num, authentication.user_id),
+                    |row| row.get(0),
+                )
+                .expect("query to be successful");
+
+            let votes = transaction
+                .prepare(
+                    "SELECT v.entity_id, v.rating, v.category FROM vote v
+                     WHERE v.title = ?1 AND v.user_id = ?2
+                     ORDER BY v.voted_at DESC, v.entity_id, v.category
+                     LIMIT ?3 OFFSET ?4",
+                )
+                .expect("preparation to be successful")
+                .query_map(
+                    (title_num, authentication.user_id, item_count, item_offset),
+                    |row| Ok((row.get(0)?, row.get::<_, u8>(1)?, row.get(2)?)),
+                )
+                .expect("query to be successful")
+                .filter_map(|vote| vote.ok())
+                .filter_map(|(entity_id, rating, category)| {
+                    Some(CategorizedRatingInfo {
+                        rating_info: RatingInfo {
+                            entity_id,
+                            rating: Vote::from_u8(rating)?,
+                        },
+                        category,
+                    })
+                })
+                .collect();
+
+            ResultSlice::with_total_count(votes, item_offset, total_count)
+        })
+    }
+
+    fn get_rating_aggregates(
+        &self,
+        session: &BdSession,
+        entity_ids: &[u64],
+    ) -> Vec<RatingAggregate> {
+        let title_num = session.authentication().unwrap().title.to_u32().unwrap();
+        let entity_ids: Rc<Vec<Value>> = Rc::new(
+            entity_ids
+                .iter()
+                .map(|entity_id| Value::Integer(*entity_id as i64))
+                .collect(),
+        );
+
+        VOTE_RANK_DB.with_borrow(|db| {
+            db.prepare(
+                "SELECT v.entity_id, v.category,
+                        SUM(v.rating = ?2), SUM(v.rating = ?3)
+                 FROM vote v
+                 WHERE v.title = ?1 AND v.entity_id IN rarray(?4)
+                 GROUP BY v.entity_id, v.category
+                 ORDER BY v.entity_id, v.category",
+            )
+            .expect("preparation to be successful")
+            .query_map(
+                (
+                    title_num,
+                    Vote::Like.to_u8().unwrap(),
+                    Vote::Dislike.to_u8().unwrap(),
+                    entity_ids,
+                ),
+                |row| {
+                    Ok(RatingAggregate {
+                        entity_id: row.get(0)?,
+                        category: row.get(1)?,
+                        likes: row.get(2)?,
+                        dislikes: row.get(3)?,
+                    })
+                },
+            )
+            .expect("query to be successful")
+            .filter_map(|aggregate| aggregate.ok())
+            .collect()
+        })
+    }
+}
+
+impl Default for DwVoteRankService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwVoteRankService {
+    pub fn new() -> DwVoteRankService {
+        DwVoteRankService {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::authenticated_session;
+    use bitdemon::domain::title::Title;
+
+    fn vote(entity_id: u64, rating: Vote, category: u16) -> CategorizedRatingInfo {
+        CategorizedRatingInfo {
+            rating_info: RatingInfo { entity_id, rating },
+            category,
+        }
+    }
+
+    #[test]
+    fn later_votes_replace_earlier_ones() {
+        let service = DwVoteRankService::new();
+        let first = authenticated_session(1, Title::T6Pc);
+        let second = authenticated_session(2, Title::T6Pc);
+
+        service
+            .submit_ratings(&first, vec![vote(10, Vote::Dislike, 0)])
+            .unwrap();
+        service
+            .submit_ratings(
+                &first,
+                vec![vote(10, Vote::Like, 0), vote(10, Vote::Like, 1)],
+            )
+            .unwrap();
+        service
+            .submit_ratings(&second, vec![vote(10, Vote::Dislike, 0)])
+            .unwrap();
+
+        let history = service.get_vote_history(&first, 0, 10);
+        assert_eq!(history.total_count(), 2);
+
+        let aggregates = service.get_rating_aggregates(&first, &[10, 11]);
+        assert_eq!(aggregates.len(), 2);
+        assert_eq!((aggregates[0].likes, aggregates[0].dislikes), (1, 1));
+        assert_eq!(aggregates[0].like_ratio(), 0.5);
+        assert_eq!((aggregates[1].category, aggregates[1].likes), (1, 1));
+    }
+
+    #[test]
+    fn rejects_too_many_ratings() {
+        let service = DwVoteRankService::new();
+        let session = authenticated_session(1, Title::T6Pc);
+        let ratings = (0..=MAX_RATINGS_PER_REQUEST as u64)
+            .map(|entity_id| vote(entity_id, Vote::Like, 0))
+            .collect();
+
+        assert!(matches!(
+            service.submit_ratings(&session, ratings),
+            Err(VoteRankServiceError::TooManyRatingsError)
+        ));
+    }
+}
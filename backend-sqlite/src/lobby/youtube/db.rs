@@ -0,0 +1,32 @@
+use crate::db::{open_db, DbSchema};
+use rusqlite::Connection;
+use std::cell::RefCell;
+
+thread_local! {
+    pub static YOUTUBE_DB: RefCell<Connection> = RefCell::new(open_db(&YOUTUBE_SCHEMA));
+}
+
+const YOUTUBE_CHANGELOG_0: &str = "
+CREATE TABLE youtube_account (
+    title INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    registered_at INTEGER NOT NULL,
+    PRIMARY KEY (title, user_id)
+);
+";
+
+const YOUTUBE_SCHEMA: DbSchema = DbSchema {
+    name: "youtube",
+    changelogs: &[YOUTUBE_CHANGELOG_0],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&YOUTUBE_SCHEMA);
+    }
+}
@@ -0,0 +1,12 @@
+mod db;
+mod service;
+
+pub use service::DwYoutubeService;
+
+use bitdemon::lobby::youtube::YoutubeHandler;
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use std::sync::Arc;
+
+pub fn create_youtube_handler() -> Arc<ThreadSafeLobbyHandler> {
+    Arc::new(YoutubeHandler::new(Arc::new(DwYoutubeService::new())))
+}
@@ -0,0 +1,98 @@
+use crate::lobby::youtube::db::YOUTUBE_DB;
+use bitdemon::lobby::youtube::{YoutubeService, YoutubeServiceError, YoutubeUpload};
+use bitdemon::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::info;
+use num_traits::ToPrimitive;
+
+/// Keeps track of registered YouTube accounts without talking to YouTube.
+/// Everything that would require an actual account is reported as disabled.
+pub struct DwYoutubeService {}
+
+impl YoutubeService for DwYoutubeService {
+    fn start_account_registration(&self, session: &BdSession) -> Result<(), YoutubeServiceError> {
+        if self.is_registered(session)? {
+            return Err(YoutubeServiceError::AccountAlreadyRegisteredError);
+        }
+
+        info!("Registering youtube account");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        YOUTUBE_DB.with_borrow(|db| {
+            db.execute(
+                "INSERT INTO youtube_account (title, user_id, registered_at) VALUES (?, ?, ?)",
+                (title_num, authentication.user_id, Utc::now().timestamp()),
+            )
+            .expect("insertion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn is_registered(&self, session: &BdSession) -> Result<bool, YoutubeServiceError> {
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        let count: u64 = YOUTUBE_DB.with_borrow(|db| {
+            db.query_row(
+                "SELECT COUNT(*) FROM youtube_account WHERE title = ?1 AND user_id = ?2",
+                (title_num, authentication.user_id),
+                |row| row.get(0),
+            )
+            .expect("query to be successful")
+        });
+
+        Ok(count > 0)
+    }
+
+    fn unregister(&self, session: &BdSession) -> Result<(), YoutubeServiceError> {
+        info!("Unregistering youtube account");
+
+        let authentication = session.authentication().unwrap();
+        let title_num = authentication.title.to_u32().unwrap();
+
+        YOUTUBE_DB.with_borrow(|db| {
+            db.execute(
+                "DELETE FROM youtube_account WHERE title = ?1 AND user_id = ?2",
+                (title_num, authentication.user_id),
+            )
+            .expect("deletion to be successful");
+        });
+
+        Ok(())
+    }
+
+    fn upload_video(
+        &self,
+        session: &BdSession,
+        _upload: YoutubeUpload,
+    ) -> Result<(), YoutubeServiceError> {
+        if !self.is_registered(session)? {
+            return Err(YoutubeServiceError::AccountNotRegisteredError);
+        }
+
+        Err(YoutubeServiceError::ServiceDisabledError)
+    }
+
+    fn get_user_token(&self, session: &BdSession) -> Result<String, YoutubeServiceError> {
+        if !self.is_registered(session)? {
+            return Err(YoutubeServiceError::AccountNotRegisteredError);
+        }
+
+        Err(YoutubeServiceError::ServiceDisabledError)
+    }
+}
+
+impl Default for DwYoutubeService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DwYoutubeService {
+    pub fn new() -> DwYoutubeService {
+        DwYoutubeService {}
+    }
+}
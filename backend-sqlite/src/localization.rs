@@ -0,0 +1,147 @@
+use crate::config::LocalizationConfig;
+use crate::db::{open_db, DbSchema};
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::cell::RefCell;
+
+thread_local! {
+    static LOCALIZATION_DB: RefCell<Connection> = RefCell::new(open_db(&LOCALIZATION_SCHEMA));
+}
+
+const LOCALIZATION_CHANGELOG_0: &str = "
+CREATE TABLE user_locale (
+    user_id INTEGER PRIMARY KEY,
+    locale TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+";
+
+const LOCALIZATION_SCHEMA: DbSchema = DbSchema {
+    name: "localization",
+    changelogs: &[LOCALIZATION_CHANGELOG_0],
+};
+
+const FALLBACK_LOCALE: &str = "en";
+
+/// Keys of the messages that can be localized.
+pub mod message_key {
+    pub const BANNED: &str = "banned";
+    pub const MAINTENANCE: &str = "maintenance";
+    pub const NOT_ALLOWLISTED: &str = "not_allowlisted";
+    pub const SESSION_LIMIT_REACHED: &str = "session_limit_reached";
+}
+
+/// Remembers the locale a user's client reported,
+/// so that messages can be localized before the client reports it again.
+pub fn remember_user_locale(user_id: u64, locale: &str) {
+    if locale.is_empty() {
+        return;
+    }
+
+    LOCALIZATION_DB.with_borrow(|db| {
+        db.execute(
+            "INSERT OR REPLACE INTO user_locale (user_id, locale, updated_at) VALUES (?, ?, ?)",
+            (user_id, locale, Utc::now().timestamp()),
+        )
+        .expect("insertion to be successful");
+    });
+}
+
+/// The locale the client of the user reported most recently.
+pub fn user_locale(user_id: u64) -> Option<String> {
+    LOCALIZATION_DB.with_borrow(|db| {
+        db.query_row(
+            "SELECT locale FROM user_locale WHERE user_id = ?",
+            (user_id,),
+            |row| row.get(0),
+        )
+        .optional()
+        .expect("query to be successful")
+    })
+}
+
+/// Looks up messages in the configured catalogs, falling back to English.
+pub struct MessageCatalog {
+    config: LocalizationConfig,
+}
+
+impl MessageCatalog {
+    pub fn new(config: LocalizationConfig) -> MessageCatalog {
+        MessageCatalog { config }
+    }
+
+    /// The message in the locale of the user.
+    /// Tries the full locale (`de_AT`), its language (`de`) and then English.
+    pub fn message_for_user(&self, user_id: u64, key: &str) -> String {
+        let locale = user_locale(user_id).unwrap_or_else(|| FALLBACK_LOCALE.to_string());
+
+        self.message(&locale, key)
+    }
+
+    pub fn message(&self, locale: &str, key: &str) -> String {
+        let language = locale.split(['_', '-']).next().unwrap_or(locale);
+
+        [locale, language, FALLBACK_LOCALE]
+            .into_iter()
+            .find_map(|candidate| {
+                self.config
+                    .messages
+                    .iter()
+                    .find(|(configured, _)| configured.eq_ignore_ascii_case(candidate))
+                    .and_then(|(_, messages)| messages.get(key))
+            })
+            .cloned()
+            .unwrap_or_else(|| Self::default_message(key).to_string())
+    }
+
+    fn default_message(key: &str) -> &'static str {
+        match key {
+            message_key::BANNED => "You are banned from this server.",
+            message_key::MAINTENANCE => "The server is under maintenance. Please try again later.",
+            message_key::NOT_ALLOWLISTED => "You are not allowed to play on this server.",
+            message_key::SESSION_LIMIT_REACHED => {
+                "The server is full or you are already connected. Please try again later."
+            }
+            _ => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::assert_schema_snapshot;
+    use std::collections::HashMap;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        assert_schema_snapshot(&LOCALIZATION_SCHEMA);
+    }
+
+    #[test]
+    fn messages_fall_back_to_language_and_english() {
+        remember_user_locale(1, "de_AT");
+        let catalog = MessageCatalog::new(LocalizationConfig {
+            messages: HashMap::from([(
+                "de".to_string(),
+                HashMap::from([(
+                    message_key::MAINTENANCE.to_string(),
+                    "Wartungsarbeiten".to_string(),
+                )]),
+            )]),
+        });
+
+        assert_eq!(
+            catalog.message_for_user(1, message_key::MAINTENANCE),
+            "Wartungsarbeiten"
+        );
+        assert_eq!(
+            catalog.message_for_user(1, message_key::BANNED),
+            "You are banned from this server."
+        );
+        assert_eq!(
+            catalog.message_for_user(2, message_key::MAINTENANCE),
+            "The server is under maintenance. Please try again later."
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::db::db_dir;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::fs::{metadata, read_dir};
+use std::path::Path;
+
+/// Columns that hold the seconds timestamp a row was recorded at, in order of preference.
+const RECORD_TIMESTAMP_COLUMNS: [&str; 2] = ["created_at", "timestamp"];
+
+/// The size and contents of a single database.
+#[derive(Clone, Serialize)]
+pub struct DbMetrics {
+    pub name: String,
+    /// The size of the database file including its write-ahead log in bytes.
+    pub file_size: u64,
+    pub tables: Vec<TableMetrics>,
+}
+
+/// The amount and age of the rows of a single table.
+#[derive(Clone, Serialize)]
+pub struct TableMetrics {
+    pub name: String,
+    pub row_count: u64,
+    /// The seconds timestamp of the oldest row, if the table records when rows were added.
+    pub oldest_record: Option<i64>,
+    /// The seconds timestamp of the newest row, if the table records when rows were added.
+    pub newest_record: Option<i64>,
+}
+
+/// Measures all databases in the database directory.
+///
+/// The databases are opened read-only in addition to the connections of the services,
+/// so this should not be called too frequently on large databases.
+pub fn collect_db_metrics() -> Vec<DbMetrics> {
+    let Ok(entries) = read_dir(db_dir()) else {
+        return Vec::new();
+    };
+
+    let mut db_metrics: Vec<DbMetrics> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "db"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().to_string();
+            let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY).ok()?;
+
+            Some(DbMetrics {
+                name,
+                file_size: file_size(&path),
+                tables: table_metrics(&conn),
+            })
+        })
+        .collect();
+    db_metrics.sort_by(|a, b| a.name.cmp(&b.name));
+
+    db_metrics
+}
+
+fn file_size(path: &Path) -> u64 {
+    let wal_path = path.with_extension("db-wal");
+
+    [path, wal_path.as_path()]
+        .iter()
+        .filter_map(|path| metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn table_metrics(conn: &Connection) -> Vec<TableMetrics> {
+    let table_names: Vec<String> = conn
+        .prepare(
+            "SELECT m.name FROM sqlite_master m
+             WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+             ORDER BY m.name",
+        )
+        .expect("preparation to be successful")
+        .query_map((), |row| row.get(0))
+        .expect("query to be successful")
+        .filter_map(|name| name.ok())
+        .collect();
+
+    table_names
+        .into_iter()
+        .map(|name| {
+            let row_count = conn
+                .query_row(&format!("SELECT COUNT(*) FROM \"{name}\""), (), |row| {
+                    row.get(0)
+                })
+                .expect("query to be successful");
+
+            let (oldest_record, newest_record) = record_timestamp_column(conn, &name)
+                .map(|column| {
+                    conn.query_row(
+                        &format!("SELECT MIN(\"{column}\"), MAX(\"{column}\") FROM \"{name}\""),
+                        (),
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .expect("query to be successful")
+                })
+                .unwrap_or((None, None));
+
+            TableMetrics {
+                name,
+                row_count,
+                oldest_record,
+                newest_record,
+            }
+        })
+        .collect()
+}
+
+fn record_timestamp_column(conn: &Connection, table_name: &str) -> Option<String> {
+    let columns: Vec<String> = conn
+        .prepare(&format!("PRAGMA table_info(\"{table_name}\")"))
+        .expect("preparation to be successful")
+        .query_map((), |row| row.get(1))
+        .expect("query to be successful")
+        .filter_map(|column| column.ok())
+        .collect();
+
+    RECORD_TIMESTAMP_COLUMNS
+        .iter()
+        .find(|preferred| columns.iter().any(|column| column == *preferred))
+        .map(|column| column.to_string())
+        .or_else(|| {
+            columns
+                .into_iter()
+                .find(|column| column.ends_with("_at") && column != "expires_at")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{open_db, DbSchema};
+
+    const TEST_SCHEMA: DbSchema = DbSchema {
+        name: "metrics_test",
+        changelogs: &["
+            CREATE TABLE entry (id INTEGER PRIMARY KEY, expires_at INTEGER, sent_at INTEGER);
+            CREATE TABLE setting (key TEXT PRIMARY KEY, value TEXT);
+        "],
+    };
+
+    #[test]
+    fn measures_rows_and_record_timestamps() {
+        let conn = open_db(&TEST_SCHEMA);
+        conn.execute_batch(
+            "INSERT INTO entry (expires_at, sent_at) VALUES (1, 300), (2, 100), (3, 200);
+             INSERT INTO setting (key, value) VALUES ('a', 'b');",
+        )
+        .unwrap();
+
+        let tables = table_metrics(&conn);
+
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].name, "entry");
+        assert_eq!(tables[0].row_count, 3);
+        assert_eq!(tables[0].oldest_record, Some(100));
+        assert_eq!(tables[0].newest_record, Some(300));
+        assert_eq!(tables[1].row_count, 1);
+        assert_eq!(tables[1].oldest_record, None);
+    }
+}
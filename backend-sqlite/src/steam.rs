@@ -0,0 +1,74 @@
+use crate::config::SteamConfig;
+use bitdemon::auth::auth_handler::steam::{SteamTicketValidation, SteamTicketValidator};
+use bitdemon::domain::title::Title;
+use log::warn;
+use num_traits::ToPrimitive;
+use serde::Deserialize;
+use std::error::Error;
+
+const AUTHENTICATE_USER_TICKET_URL: &str =
+    "https://api.steampowered.com/ISteamUserAuth/AuthenticateUserTicket/v1/";
+
+/// Validates Steam tickets with the `AuthenticateUserTicket` call of the Steam Web API.
+pub struct SteamWebApiTicketValidator {
+    web_api_key: String,
+    config: SteamConfig,
+}
+
+#[derive(Deserialize)]
+struct AuthenticateUserTicketResponse {
+    response: AuthenticateUserTicketResult,
+}
+
+#[derive(Deserialize)]
+struct AuthenticateUserTicketResult {
+    params: Option<AuthenticateUserTicketParams>,
+}
+
+#[derive(Deserialize)]
+struct AuthenticateUserTicketParams {
+    result: String,
+    steamid: String,
+}
+
+impl SteamTicketValidator for SteamWebApiTicketValidator {
+    fn validate_ticket(
+        &self,
+        title: Title,
+        ticket: &[u8],
+    ) -> Result<SteamTicketValidation, Box<dyn Error>> {
+        let title_num = title.to_u32().unwrap();
+        let Some(app_id) = self.config.app_ids.get(&title_num) else {
+            warn!("Cannot validate Steam ticket for title {title_num} without app id");
+            return Ok(SteamTicketValidation::AppIdMismatch);
+        };
+
+        let hex_ticket: String = ticket.iter().map(|b| format!("{b:02x}")).collect();
+        let response: AuthenticateUserTicketResponse = ureq::get(AUTHENTICATE_USER_TICKET_URL)
+            .query("key", &self.web_api_key)
+            .query("appid", app_id.to_string())
+            .query("ticket", hex_ticket)
+            .call()?
+            .body_mut()
+            .read_json()?;
+
+        Ok(match response.response.params {
+            Some(params) if params.result == "OK" => SteamTicketValidation::Valid {
+                steam_id: params.steamid.parse()?,
+            },
+            _ => SteamTicketValidation::Invalid,
+        })
+    }
+}
+
+impl SteamWebApiTicketValidator {
+    /// Creates a validator if a Web API key is configured.
+    pub fn from_config(config: SteamConfig) -> Option<SteamWebApiTicketValidator> {
+        let web_api_key = config.web_api_key.clone().filter(|key| !key.is_empty())?;
+
+        Some(SteamWebApiTicketValidator {
+            web_api_key,
+            config,
+        })
+    }
+}
@@ -0,0 +1,271 @@
+use bitdemon::auth::auth_handler::AuthMessageType;
+use bitdemon::crypto::{
+    calculate_hmac, decrypt_buffer_in_place, encrypt_buffer_in_place, generate_iv_from_seed,
+    generate_iv_seed,
+};
+use bitdemon::domain::title::Title;
+use bitdemon::lobby::{BdMessageType, LobbyServiceId};
+use bitdemon::messaging::bd_reader::BdReader;
+use bitdemon::messaging::bd_writer::BdWriter;
+use bitdemon::messaging::{BdErrorCode, StreamMode};
+use num_traits::ToPrimitive;
+use rand::Rng;
+use snafu::{ensure, Snafu};
+use std::error::Error;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Must match the signature of custom tickets that the server is able to parse.
+const CUSTOM_TICKET_SIGNATURE: u32 = 0xDEADBABE;
+const RESPONSE_SIGNATURE: u32 = 0xDEADBEEF;
+const AUTH_PROOF_SIZE: usize = 128;
+const ENCRYPTED_TICKET_SIZE: usize = 128;
+const TDES_BLOCK_SIZE: usize = 8;
+
+#[derive(Debug, Snafu)]
+enum MockClientError {
+    #[snafu(display("The server rejected the authentication (error_code={error_code})"))]
+    AuthenticationRejected { error_code: u32 },
+    #[snafu(display("The server answered with an unexpected message type {message_type}"))]
+    UnexpectedMessageType { message_type: u8 },
+    #[snafu(display("The server sent an encrypted message with an invalid signature"))]
+    InvalidSignature,
+    #[snafu(display("The server sent an unencrypted message when an encrypted one was expected"))]
+    UnencryptedMessage,
+    #[snafu(display("The server sent an encrypted message when an unencrypted one was expected"))]
+    EncryptedMessage,
+}
+
+/// The result of a lobby task as seen by the client.
+pub struct TaskResult {
+    pub error_code: u32,
+}
+
+impl TaskResult {
+    pub fn is_success(&self) -> bool {
+        self.error_code == BdErrorCode::NoError.to_u32().unwrap()
+    }
+}
+
+/// A minimal client that speaks just enough of the protocol to authenticate
+/// against the auth server with a custom Steam ticket and call lobby tasks.
+pub struct MockClient {
+    title: Title,
+    user_id: u64,
+    username: String,
+    session_key: [u8; 24],
+    auth_proof: Option<[u8; AUTH_PROOF_SIZE]>,
+    lobby_stream: Option<TcpStream>,
+}
+
+impl MockClient {
+    pub fn new(title: Title, user_id: u64, username: String) -> MockClient {
+        let mut session_key = [0u8; 24];
+        rand::rng().fill_bytes(&mut session_key);
+
+        MockClient {
+            title,
+            user_id,
+            username,
+            session_key,
+            auth_proof: None,
+            lobby_stream: None,
+        }
+    }
+
+    /// Authenticates at the auth server and stores the resulting opaque auth proof.
+    pub fn authenticate(&mut self, auth_address: &str) -> Result<(), Box<dyn Error>> {
+        let mut ticket = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut ticket);
+            writer.write_u32(CUSTOM_TICKET_SIGNATURE)?;
+            writer.write_u64(self.user_id)?;
+            writer.write_u32(24 + 64)?;
+            writer.write_bytes(&self.session_key)?;
+            writer.write_str(self.username.as_str())?;
+            writer.write_i64(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64)?;
+        }
+
+        let mut request = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut request);
+            writer.write_u8(AuthMessageType::SteamForMmpRequest.to_u8().unwrap())?;
+
+            writer.set_mode(StreamMode::BitMode);
+            writer.set_type_checked(true);
+            writer.write_type_checked_bit()?;
+            writer.write_u32(generate_iv_seed())?;
+            writer.write_u32(self.title.to_u32().unwrap())?;
+            writer.write_u32(ticket.len() as u32)?;
+            writer.write_bytes(ticket.as_slice())?;
+        }
+
+        let mut stream = TcpStream::connect(auth_address)?;
+        write_unencrypted(&mut stream, request.as_slice())?;
+
+        let (encrypted, response) = read_message(&mut stream)?;
+        ensure!(!encrypted, EncryptedMessageSnafu {});
+
+        let mut reader = BdReader::new(response);
+        reader.set_mode(StreamMode::BitMode);
+        reader.read_u8()?;
+        reader.read_type_checked_bit()?;
+
+        let error_code = reader.read_u32()?;
+        ensure!(
+            error_code == BdErrorCode::AuthNoError.to_u32().unwrap(),
+            AuthenticationRejectedSnafu { error_code }
+        );
+
+        let _iv_seed = reader.read_u32()?;
+        let mut encrypted_ticket = [0u8; ENCRYPTED_TICKET_SIZE];
+        reader.read_bytes(&mut encrypted_ticket)?;
+
+        let mut auth_proof = [0u8; AUTH_PROOF_SIZE];
+        reader.read_bytes(&mut auth_proof)?;
+        self.auth_proof = Some(auth_proof);
+
+        Ok(())
+    }
+
+    /// Connects to the lobby server and authenticates using the previously retrieved auth proof.
+    pub fn connect_lobby(&mut self, lobby_address: &str) -> Result<u64, Box<dyn Error>> {
+        let mut request = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut request);
+            writer.write_u8(LobbyServiceId::LobbyService.to_u8().unwrap())?;
+
+            writer.set_mode(StreamMode::BitMode);
+            writer.set_type_checked(true);
+            writer.write_type_checked_bit()?;
+            writer.write_u32(self.title.to_u32().unwrap())?;
+            writer.write_u32(generate_iv_seed())?;
+            writer.write_bytes(self.auth_proof.as_ref().unwrap())?;
+        }
+
+        let mut stream = TcpStream::connect(lobby_address)?;
+        stream.set_nodelay(true)?;
+        write_unencrypted(&mut stream, request.as_slice())?;
+        self.lobby_stream = Some(stream);
+
+        let mut reader = self.read_encrypted_reply()?;
+        let message_type = reader.read_u8()?;
+        ensure!(
+            message_type == BdMessageType::LsgServiceConnectionId.to_u8().unwrap(),
+            UnexpectedMessageTypeSnafu { message_type }
+        );
+
+        reader.set_type_checked(true);
+        reader.read_u64()
+    }
+
+    /// Calls a lobby task and waits for its reply.
+    /// The `write_args` closure is expected to write the arguments of the task.
+    pub fn call_task<T, F>(
+        &mut self,
+        service_id: LobbyServiceId,
+        task_id: T,
+        write_args: F,
+    ) -> Result<TaskResult, Box<dyn Error>>
+    where
+        T: ToPrimitive,
+        F: FnOnce(&mut BdWriter) -> Result<(), Box<dyn Error>>,
+    {
+        let mut payload = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut payload);
+            // Reserved for the hmac
+            writer.write_u32(0)?;
+            writer.write_u8(service_id.to_u8().unwrap())?;
+
+            writer.set_type_checked(true);
+            writer.write_u8(task_id.to_u8().unwrap())?;
+            write_args(&mut writer)?;
+        }
+
+        self.write_encrypted(payload)?;
+
+        let mut reader = self.read_encrypted_reply()?;
+        let message_type = reader.read_u8()?;
+        ensure!(
+            message_type == BdMessageType::LobbyServiceTaskReply.to_u8().unwrap(),
+            UnexpectedMessageTypeSnafu { message_type }
+        );
+
+        reader.set_type_checked(true);
+        let _transaction_id = reader.read_u64()?;
+        let error_code = reader.read_u32()?;
+        let _operation_id = reader.read_u8()?;
+
+        Ok(TaskResult { error_code })
+    }
+
+    fn write_encrypted(&mut self, mut payload: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        // The server validates the hmac over the padded plaintext so pad before calculating it.
+        let payload_len = payload.len();
+        payload.resize(payload_len.next_multiple_of(TDES_BLOCK_SIZE), 0);
+
+        let hmac = calculate_hmac(&payload[5..], &self.session_key);
+        payload[0..4].copy_from_slice(&hmac.to_le_bytes());
+
+        let seed = generate_iv_seed();
+        encrypt_buffer_in_place(
+            &mut payload,
+            &self.session_key,
+            &generate_iv_from_seed(seed),
+        );
+
+        let stream = self.lobby_stream.as_mut().unwrap();
+        stream.write_all(&((payload.len() + 5) as u32).to_le_bytes())?;
+        stream.write_all(&[1u8])?;
+        stream.write_all(&seed.to_le_bytes())?;
+        stream.write_all(payload.as_slice())?;
+
+        Ok(())
+    }
+
+    fn read_encrypted_reply(&mut self) -> Result<BdReader<'static>, Box<dyn Error>> {
+        let (encrypted, mut message) = read_message(self.lobby_stream.as_mut().unwrap())?;
+        ensure!(encrypted, UnencryptedMessageSnafu {});
+
+        let seed = u32::from_le_bytes(message[0..4].try_into().unwrap());
+        decrypt_buffer_in_place(
+            &mut message[4..],
+            &self.session_key,
+            &generate_iv_from_seed(seed),
+        )?;
+
+        let signature = u32::from_le_bytes(message[4..8].try_into().unwrap());
+        ensure!(signature == RESPONSE_SIGNATURE, InvalidSignatureSnafu {});
+
+        Ok(BdReader::new(Vec::from(&message[8..])))
+    }
+}
+
+fn write_unencrypted(stream: &mut TcpStream, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    stream.write_all(&((data.len() + 1) as u32).to_le_bytes())?;
+    stream.write_all(&[0u8])?;
+    stream.write_all(data)?;
+
+    Ok(())
+}
+
+fn read_message(stream: &mut TcpStream) -> Result<(bool, Vec<u8>), Box<dyn Error>> {
+    let mut header = [0u8; 4];
+
+    // Skip pings the server might send
+    let mut message_len = 0;
+    while message_len == 0 {
+        stream.read_exact(&mut header)?;
+        message_len = u32::from_le_bytes(header) as usize;
+    }
+
+    let mut message = vec![0u8; message_len];
+    stream.read_exact(message.as_mut_slice())?;
+
+    let encrypted = message[0] > 0;
+    message.remove(0);
+
+    Ok((encrypted, message))
+}
@@ -0,0 +1,115 @@
+mod client;
+mod scenario;
+mod stats;
+
+use crate::scenario::{run_player, ScenarioOptions};
+use crate::stats::LatencyRecorder;
+use log::{error, info, LevelFilter};
+use std::process::exit;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+const DEFAULT_HOST: &str = "localhost";
+const DEFAULT_AUTH_PORT: u16 = 3075;
+const DEFAULT_LOBBY_PORT: u16 = 3074;
+const DEFAULT_PLAYERS: usize = 16;
+const DEFAULT_ITERATIONS: usize = 10;
+
+const USAGE: &str = "Usage: bd-loadtest [--host <host>] [--auth-port <port>] [--lobby-port <port>] [--players <count>] [--iterations <count>]";
+
+struct LoadTestArgs {
+    host: String,
+    auth_port: u16,
+    lobby_port: u16,
+    players: usize,
+    iterations: usize,
+}
+
+fn main() {
+    env_logger::builder().filter_level(LevelFilter::Info).init();
+
+    let args = parse_args().unwrap_or_else(|message| {
+        eprintln!("{message}");
+        eprintln!("{USAGE}");
+        exit(1);
+    });
+
+    let options = Arc::new(ScenarioOptions {
+        auth_address: format!("{}:{}", args.host, args.auth_port),
+        lobby_address: format!("{}:{}", args.host, args.lobby_port),
+        iterations: args.iterations,
+    });
+    let recorder = Arc::new(LatencyRecorder::new());
+
+    info!(
+        "Simulating {} players with {} iterations each",
+        args.players, args.iterations
+    );
+
+    let start = Instant::now();
+    let player_threads: Vec<_> = (0..args.players)
+        .map(|player_index| {
+            let options = options.clone();
+            let recorder = recorder.clone();
+            thread::spawn(move || {
+                if let Err(e) = run_player(&options, player_index, &recorder) {
+                    error!("Player {player_index} aborted: {e}");
+                    return false;
+                }
+
+                true
+            })
+        })
+        .collect();
+
+    let aborted_players = player_threads
+        .into_iter()
+        .map(|handle| handle.join().unwrap_or(false))
+        .filter(|completed| !completed)
+        .count();
+
+    info!(
+        "Finished after {:.2}s ({aborted_players} players aborted)",
+        start.elapsed().as_secs_f64()
+    );
+    recorder.print_report();
+
+    if aborted_players > 0 {
+        exit(2);
+    }
+}
+
+fn parse_args() -> Result<LoadTestArgs, String> {
+    let mut args = LoadTestArgs {
+        host: String::from(DEFAULT_HOST),
+        auth_port: DEFAULT_AUTH_PORT,
+        lobby_port: DEFAULT_LOBBY_PORT,
+        players: DEFAULT_PLAYERS,
+        iterations: DEFAULT_ITERATIONS,
+    };
+
+    let mut input = std::env::args().skip(1);
+    while let Some(arg) = input.next() {
+        let value = input
+            .next()
+            .ok_or_else(|| format!("Missing value for argument {arg}"))?;
+
+        match arg.as_str() {
+            "--host" => args.host = value,
+            "--auth-port" => args.auth_port = parse_value(&arg, &value)?,
+            "--lobby-port" => args.lobby_port = parse_value(&arg, &value)?,
+            "--players" => args.players = parse_value(&arg, &value)?,
+            "--iterations" => args.iterations = parse_value(&arg, &value)?,
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+
+    Ok(args)
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for argument {arg}: {value}"))
+}
@@ -0,0 +1,99 @@
+use crate::client::{MockClient, TaskResult};
+use crate::stats::LatencyRecorder;
+use bitdemon::domain::title::Title;
+use bitdemon::lobby::rich_presence::RichPresenceTaskId;
+use bitdemon::lobby::storage::StorageTaskId;
+use bitdemon::lobby::LobbyServiceId;
+use std::error::Error;
+use std::time::Instant;
+
+const UPLOAD_FILE_SIZE: usize = 4_096;
+
+/// The actions each simulated player performs.
+/// Every action is recorded separately so that slow services can be identified.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub enum Action {
+    Login,
+    SetPresence,
+    StorageRead,
+    StorageUpload,
+}
+
+impl Action {
+    pub const ALL: [Action; 4] = [
+        Action::Login,
+        Action::SetPresence,
+        Action::StorageRead,
+        Action::StorageUpload,
+    ];
+}
+
+pub struct ScenarioOptions {
+    pub auth_address: String,
+    pub lobby_address: String,
+    pub iterations: usize,
+}
+
+/// Runs the scripted mix of actions for a single player.
+pub fn run_player(
+    options: &ScenarioOptions,
+    player_index: usize,
+    recorder: &LatencyRecorder,
+) -> Result<(), Box<dyn Error>> {
+    let user_id = 0x1100001_00000000u64 + player_index as u64;
+    let mut client = MockClient::new(Title::T6Pc, user_id, format!("loadtest{player_index}"));
+
+    let start = Instant::now();
+    client.authenticate(options.auth_address.as_str())?;
+    client.connect_lobby(options.lobby_address.as_str())?;
+    recorder.record(Action::Login, start.elapsed(), true);
+
+    let filename = format!("loadtest_{player_index}");
+    let file_data = vec![0xAAu8; UPLOAD_FILE_SIZE];
+
+    for iteration in 0..options.iterations {
+        let presence = format!("iteration {iteration}");
+        timed(recorder, Action::SetPresence, || {
+            client.call_task(
+                LobbyServiceId::RichPresence,
+                RichPresenceTaskId::SetInfo,
+                |writer| {
+                    writer.write_u64(0)?;
+                    writer.write_blob(presence.as_bytes())
+                },
+            )
+        })?;
+
+        timed(recorder, Action::StorageUpload, || {
+            client.call_task(
+                LobbyServiceId::Storage,
+                StorageTaskId::UploadFile,
+                |writer| {
+                    writer.write_str(filename.as_str())?;
+                    writer.write_bool(false)?;
+                    writer.write_blob(file_data.as_slice())
+                },
+            )
+        })?;
+
+        timed(recorder, Action::StorageRead, || {
+            client.call_task(LobbyServiceId::Storage, StorageTaskId::GetFile, |writer| {
+                writer.write_str(filename.as_str())?;
+                writer.write_u64(0)
+            })
+        })?;
+    }
+
+    Ok(())
+}
+
+fn timed<F>(recorder: &LatencyRecorder, action: Action, call: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnOnce() -> Result<TaskResult, Box<dyn Error>>,
+{
+    let start = Instant::now();
+    let result = call()?;
+    recorder.record(action, start.elapsed(), result.is_success());
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::scenario::Action;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Default)]
+struct ActionSamples {
+    latencies: Vec<Duration>,
+    failures: usize,
+}
+
+/// Collects latencies of all players.
+pub struct LatencyRecorder {
+    samples: Mutex<HashMap<Action, ActionSamples>>,
+}
+
+impl LatencyRecorder {
+    pub fn new() -> LatencyRecorder {
+        LatencyRecorder {
+            samples: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record(&self, action: Action, latency: Duration, success: bool) {
+        let mut samples = self.samples.lock().unwrap();
+        let action_samples = samples.entry(action).or_default();
+
+        action_samples.latencies.push(latency);
+        if !success {
+            action_samples.failures += 1;
+        }
+    }
+
+    pub fn print_report(&self) {
+        let mut samples = self.samples.lock().unwrap();
+
+        println!(
+            "{:<16}{:>8}{:>10}{:>10}{:>10}{:>10}{:>10}",
+            "action", "count", "failed", "p50", "p90", "p99", "max"
+        );
+
+        for action in Action::ALL {
+            let Some(action_samples) = samples.get_mut(&action) else {
+                continue;
+            };
+
+            action_samples.latencies.sort();
+            let latencies = &action_samples.latencies;
+
+            println!(
+                "{:<16}{:>8}{:>10}{:>10}{:>10}{:>10}{:>10}",
+                format!("{action:?}"),
+                latencies.len(),
+                action_samples.failures,
+                format_ms(percentile(latencies, 50)),
+                format_ms(percentile(latencies, 90)),
+                format_ms(percentile(latencies, 99)),
+                format_ms(latencies.last().copied().unwrap_or_default()),
+            );
+        }
+    }
+}
+
+fn percentile(sorted_latencies: &[Duration], percentile: usize) -> Duration {
+    if sorted_latencies.is_empty() {
+        return Duration::ZERO;
+    }
+
+    let index = (sorted_latencies.len() * percentile).div_ceil(100);
+    sorted_latencies[index.saturating_sub(1)]
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,234 @@
+//! Derive macros for `BdSerialize` and `BdDeserialize` of libbitdemon.
+//!
+//! Fields are written and read in declaration order.
+//! Their types must implement `BdSerialize` or `BdDeserialize` themselves,
+//! which libbitdemon does for primitives and strings.
+//!
+//! The encoding of a field can be changed with the `#[bd(...)]` attribute:
+//! - `blob`: Writes a `Vec<u8>` as a blob instead of an array of `u8`.
+//! - `array`: Writes a `Vec` of primitives as a typed array.
+//! - `unchecked`: Writes the field without data types, even if the buffer is type checked.
+//! - `skip`: Does not write the field and uses its default value when reading.
+//! - `with = "path"`: Uses `path::serialize` and `path::deserialize` for the field.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Member};
+
+#[derive(Default)]
+struct FieldOptions {
+    blob: bool,
+    array: bool,
+    unchecked: bool,
+    skip: bool,
+    with: Option<syn::Path>,
+}
+
+impl FieldOptions {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut options = FieldOptions::default();
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("bd")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("blob") {
+                    options.blob = true;
+                } else if meta.path.is_ident("array") {
+                    options.array = true;
+                } else if meta.path.is_ident("unchecked") {
+                    options.unchecked = true;
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else if meta.path.is_ident("with") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    options.with = Some(path.parse()?);
+                } else {
+                    return Err(meta.error("unknown bd attribute"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        let encodings = [options.blob, options.array, options.with.is_some()];
+        if encodings.iter().filter(|set| **set).count() > 1 {
+            return Err(syn::Error::new(
+                field.span(),
+                "only one of blob, array and with can be used for a field",
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
+struct StructField {
+    member: Member,
+    binding: syn::Ident,
+    options: FieldOptions,
+}
+
+fn struct_fields(input: &DeriveInput) -> syn::Result<(Vec<StructField>, bool)> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "bd derives are only supported for structs",
+        ));
+    };
+
+    let named = matches!(data.fields, Fields::Named(_));
+    let fields = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let (member, binding) = match &field.ident {
+                Some(ident) => (Member::Named(ident.clone()), ident.clone()),
+                None => (
+                    Member::Unnamed(index.into()),
+                    format_ident!("field_{index}"),
+                ),
+            };
+
+            Ok(StructField {
+                member,
+                binding,
+                options: FieldOptions::parse(field)?,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok((fields, named))
+}
+
+/// Wraps the access of a field so that it is done without data types if requested.
+fn unchecked(options: &FieldOptions, stream: &TokenStream2, access: TokenStream2) -> TokenStream2 {
+    if !options.unchecked {
+        return access;
+    }
+
+    quote! {{
+        let type_checked = #stream.type_checked();
+        #stream.set_type_checked(false);
+        let result = #access;
+        #stream.set_type_checked(type_checked);
+        result
+    }}
+}
+
+#[proc_macro_derive(BdSerialize, attributes(bd))]
+pub fn derive_bd_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_serialize(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_serialize(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let (fields, _) = struct_fields(input)?;
+    let writer = quote!(writer);
+
+    let writes = fields
+        .iter()
+        .filter(|field| !field.options.skip)
+        .map(|field| {
+            let member = &field.member;
+            let options = &field.options;
+            let access = if options.blob {
+                quote!(#writer.write_blob(&self.#member))
+            } else if options.array {
+                quote!(::bitdemon::messaging::bd_serialization::BdArraySerialize::serialize_array(
+                    &self.#member,
+                    #writer
+                ))
+            } else if let Some(with) = &options.with {
+                quote!(#with::serialize(&self.#member, #writer))
+            } else {
+                quote!(::bitdemon::messaging::bd_serialization::BdSerialize::serialize(
+                    &self.#member,
+                    #writer
+                ))
+            };
+
+            let access = unchecked(options, &writer, access);
+            quote!(#access?;)
+        });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::bitdemon::messaging::bd_serialization::BdSerialize for #name #ty_generics #where_clause {
+            fn serialize(
+                &self,
+                #writer: &mut ::bitdemon::messaging::bd_writer::BdWriter,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error>> {
+                #(#writes)*
+
+                Ok(())
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(BdDeserialize, attributes(bd))]
+pub fn derive_bd_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_deserialize(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_deserialize(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let (fields, named) = struct_fields(input)?;
+    let reader = quote!(reader);
+
+    let reads = fields.iter().map(|field| {
+        let binding = &field.binding;
+        let options = &field.options;
+        if options.skip {
+            return quote!(let #binding = ::std::default::Default::default(););
+        }
+
+        let access = if options.blob {
+            quote!(#reader.read_blob())
+        } else if options.array {
+            quote!(::bitdemon::messaging::bd_serialization::BdArrayDeserialize::deserialize_array(#reader))
+        } else if let Some(with) = &options.with {
+            quote!(#with::deserialize(#reader))
+        } else {
+            quote!(::bitdemon::messaging::bd_serialization::BdDeserialize::deserialize(#reader))
+        };
+
+        let access = unchecked(options, &reader, access);
+        quote!(let #binding = #access?;)
+    });
+
+    let bindings = fields.iter().map(|field| &field.binding);
+    let construct = if named {
+        quote!(Self { #(#bindings),* })
+    } else {
+        quote!(Self(#(#bindings),*))
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::bitdemon::messaging::bd_serialization::BdDeserialize for #name #ty_generics #where_clause {
+            fn deserialize(
+                #reader: &mut ::bitdemon::messaging::bd_reader::BdReader,
+            ) -> ::std::result::Result<Self, ::std::boxed::Box<dyn ::std::error::Error>>
+            where
+                Self: Sized,
+            {
+                #(#reads)*
+
+                Ok(#construct)
+            }
+        }
+    })
+}
@@ -0,0 +1,424 @@
+use crate::admin::session_registry::ActiveSession;
+use crate::admin::AdminState;
+use crate::config::{config_file_path, DwServerConfig};
+use crate::state_metrics::StateMetricsSnapshot;
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::Response;
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use bitdemon::auth::identity::{IdentityPlatform, PlatformIdentity};
+use bitdemon::messaging::bd_message::MessageCryptoMetrics;
+use bitdemon::networking::bd_session::SessionId;
+use bitdemon_backend_sqlite::ban::{
+    ban_platform_user, ban_user, list_bans, list_platform_bans, unban_platform_user, unban_user,
+    Ban, PlatformBan,
+};
+use bitdemon_backend_sqlite::lobby::storage::{user_storage_usage, UserStorageUsage};
+use log::info;
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::fs::read_to_string;
+
+#[derive(Deserialize)]
+struct BanRequest {
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BanResult {
+    user_id: u64,
+    kicked_sessions: usize,
+}
+
+#[derive(Serialize)]
+struct ClearedCachesResult {
+    cleared_caches: usize,
+}
+
+#[derive(Serialize)]
+struct RotatedKeyResult {
+    valid_until: i64,
+}
+
+#[derive(Serialize)]
+struct CryptoFailureCounts {
+    protocol_mismatches: u64,
+    corrupted_messages: u64,
+    wrong_keys: u64,
+}
+
+#[derive(Serialize)]
+struct CryptoMetricsResult {
+    auth: CryptoFailureCounts,
+    lobby: CryptoFailureCounts,
+}
+
+impl From<&MessageCryptoMetrics> for CryptoFailureCounts {
+    fn from(metrics: &MessageCryptoMetrics) -> Self {
+        CryptoFailureCounts {
+            protocol_mismatches: metrics.protocol_mismatches(),
+            corrupted_messages: metrics.corrupted_messages(),
+            wrong_keys: metrics.wrong_keys(),
+        }
+    }
+}
+
+pub fn create_admin_api_router(state: Arc<AdminState>) -> Router {
+    Router::new()
+        .route("/admin/sessions", get(list_sessions))
+        .route("/admin/sessions/{session_id}/kick", post(kick_session))
+        .route("/admin/bans", get(get_bans))
+        .route("/admin/bans/{user_id}", put(put_ban).delete(delete_ban))
+        .route("/admin/platform-bans", get(get_platform_bans))
+        .route(
+            "/admin/platform-bans/{platform}/{platform_user_id}",
+            put(put_platform_ban).delete(delete_platform_ban),
+        )
+        .route("/admin/config/reload", post(reload_config))
+        .route(
+            "/admin/caches/publisher/invalidate",
+            post(invalidate_publisher_caches),
+        )
+        .route("/admin/users/{user_id}/quota", get(get_quota))
+        .route("/admin/metrics/crypto", get(get_crypto_metrics))
+        .route("/admin/metrics/databases", get(get_database_metrics))
+        .route("/admin/keys/rotate", post(rotate_key))
+        .layer(from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+async fn require_token(
+    State(state): State<Arc<AdminState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let expected = format!("Bearer {}", state.token.read().unwrap());
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value == expected);
+
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(next.run(request).await)
+}
+
+async fn list_sessions(State(state): State<Arc<AdminState>>) -> Json<Vec<ActiveSession>> {
+    Json(state.sessions.list())
+}
+
+async fn kick_session(
+    State(state): State<Arc<AdminState>>,
+    Path(session_id): Path<SessionId>,
+) -> StatusCode {
+    if state.sessions.kick(session_id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn get_bans() -> Json<Vec<Ban>> {
+    Json(list_bans())
+}
+
+async fn put_ban(
+    State(state): State<Arc<AdminState>>,
+    Path(user_id): Path<u64>,
+    Json(ban_request): Json<BanRequest>,
+) -> Json<BanResult> {
+    ban_user(user_id, ban_request.reason.as_deref().unwrap_or_default());
+    let kicked_sessions = state.sessions.kick_user(user_id);
+
+    Json(BanResult {
+        user_id,
+        kicked_sessions,
+    })
+}
+
+async fn delete_ban(Path(user_id): Path<u64>) -> StatusCode {
+    if unban_user(user_id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn get_platform_bans() -> Json<Vec<PlatformBan>> {
+    Json(list_platform_bans())
+}
+
+/// Bans the account of a user on an external platform, like a Steam id.
+/// Sessions of the platform user that are already authenticated are not kicked,
+/// since the account they belong to is not known.
+async fn put_platform_ban(
+    Path((platform, platform_user_id)): Path<(u8, u64)>,
+    Json(ban_request): Json<BanRequest>,
+) -> StatusCode {
+    let Some(platform) = IdentityPlatform::from_u8(platform) else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    ban_platform_user(
+        &PlatformIdentity {
+            platform,
+            platform_user_id,
+        },
+        ban_request.reason.as_deref().unwrap_or_default(),
+    );
+
+    StatusCode::NO_CONTENT
+}
+
+async fn delete_platform_ban(Path((platform, platform_user_id)): Path<(u8, u64)>) -> StatusCode {
+    let unbanned = IdentityPlatform::from_u8(platform).is_some_and(|platform| {
+        unban_platform_user(&PlatformIdentity {
+            platform,
+            platform_user_id,
+        })
+    });
+
+    if unbanned {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Reloads the settings that can be changed at runtime.
+/// These are the admin token and the response cache duration.
+async fn reload_config(
+    State(state): State<Arc<AdminState>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let json_str = read_to_string(config_file_path())
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("Could not read config: {e}")))?;
+    let config: DwServerConfig = serde_json::from_str(json_str.as_str())
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid config: {e}")))?;
+
+    let token = config.admin_token().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Config must not disable the admin token".to_string(),
+        )
+    })?;
+    *state.token.write().unwrap() = token.to_string();
+
+    let ttl = config.response_cache_ttl().unwrap_or_default();
+    state
+        .response_caches
+        .all()
+        .for_each(|cache| cache.set_ttl(ttl));
+
+    info!("Reloaded config");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn invalidate_publisher_caches(
+    State(state): State<Arc<AdminState>>,
+) -> Json<ClearedCachesResult> {
+    let publisher_caches = &state.response_caches.publisher_files;
+    publisher_caches.iter().for_each(|cache| cache.clear());
+
+    info!("Invalidated publisher caches");
+
+    Json(ClearedCachesResult {
+        cleared_caches: publisher_caches.len(),
+    })
+}
+
+async fn get_quota(
+    State(state): State<Arc<AdminState>>,
+    Path(user_id): Path<u64>,
+) -> Json<Vec<UserStorageUsage>> {
+    Json(user_storage_usage(user_id, &state.user_file_size_limits))
+}
+
+async fn get_crypto_metrics(State(state): State<Arc<AdminState>>) -> Json<CryptoMetricsResult> {
+    Json(CryptoMetricsResult {
+        auth: state.crypto_metrics.auth.as_ref().into(),
+        lobby: state.crypto_metrics.lobby.as_ref().into(),
+    })
+}
+
+/// Replaces the key new auth tickets are encrypted with.
+/// Tickets issued with previous keys stay valid until their key expires.
+async fn rotate_key(State(state): State<Arc<AdminState>>) -> Json<RotatedKeyResult> {
+    let key_store = state.key_store.clone();
+    let rotated_key = tokio::task::spawn_blocking(move || key_store.rotate_key())
+        .await
+        .unwrap();
+
+    Json(RotatedKeyResult {
+        valid_until: rotated_key.valid_until(),
+    })
+}
+
+/// The most recent measurement of the databases.
+/// Unavailable until the first measurement completed or when measuring is disabled.
+async fn get_database_metrics(
+    State(state): State<Arc<AdminState>>,
+) -> Result<Json<StateMetricsSnapshot>, StatusCode> {
+    state
+        .state_metrics
+        .latest()
+        .map(Json)
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin::session_registry::SessionRegistry;
+    use crate::admin::SocketCryptoMetrics;
+    use axum::body::{to_bytes, Body};
+    use bitdemon::auth::key_store::InMemoryKeyStore;
+    use bitdemon::networking::session_manager::SessionManager;
+    use bitdemon_backend_sqlite::db::use_in_memory_dbs;
+    use serde_json::{json, Value};
+    use std::sync::RwLock;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "test-token";
+
+    fn admin_router() -> Router {
+        use_in_memory_dbs();
+
+        create_admin_api_router(Arc::new(AdminState {
+            token: RwLock::new(TOKEN.to_string()),
+            sessions: SessionRegistry::new(&SessionManager::new()),
+            response_caches: Default::default(),
+            user_file_size_limits: Default::default(),
+            crypto_metrics: SocketCryptoMetrics {
+                auth: Default::default(),
+                lobby: Default::default(),
+            },
+            state_metrics: Default::default(),
+            key_store: Arc::new(InMemoryKeyStore::new()),
+        }))
+    }
+
+    async fn send(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Option<Value>) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {TOKEN}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&bytes).ok())
+    }
+
+    #[tokio::test]
+    async fn requests_without_token_are_rejected() {
+        let router = admin_router();
+
+        let request = Request::builder()
+            .uri("/admin/bans")
+            .header(header::AUTHORIZATION, "Bearer wrong-token")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn banned_users_are_listed_until_unbanned() {
+        let router = admin_router();
+
+        let (status, result) = send(
+            &router,
+            "PUT",
+            "/admin/bans/42",
+            Some(json!({ "reason": "cheating" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result, Some(json!({ "user_id": 42, "kicked_sessions": 0 })));
+
+        let (_, bans) = send(&router, "GET", "/admin/bans", None).await;
+        let bans = bans.unwrap();
+        assert_eq!(bans.as_array().unwrap().len(), 1);
+        assert_eq!(bans[0]["user_id"], 42);
+        assert_eq!(bans[0]["reason"], "cheating");
+
+        let (status, _) = send(&router, "DELETE", "/admin/bans/42", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (_, bans) = send(&router, "GET", "/admin/bans", None).await;
+        assert_eq!(bans, Some(json!([])));
+    }
+
+    #[tokio::test]
+    async fn unbanning_user_that_is_not_banned_is_not_found() {
+        let router = admin_router();
+
+        let (status, _) = send(&router, "DELETE", "/admin/bans/42", None).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn platform_bans_can_be_lifted() {
+        let router = admin_router();
+
+        let (status, _) = send(
+            &router,
+            "PUT",
+            "/admin/platform-bans/1/76561197960265728",
+            Some(json!({ "reason": "cheating" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (_, bans) = send(&router, "GET", "/admin/platform-bans", None).await;
+        let bans = bans.unwrap();
+        assert_eq!(bans[0]["platform"], 1);
+        assert_eq!(bans[0]["platform_user_id"], 76561197960265728u64);
+
+        let (status, _) = send(
+            &router,
+            "DELETE",
+            "/admin/platform-bans/1/76561197960265728",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (_, bans) = send(&router, "GET", "/admin/platform-bans", None).await;
+        assert_eq!(bans, Some(json!([])));
+    }
+
+    #[tokio::test]
+    async fn platform_bans_of_unknown_platforms_are_not_found() {
+        let router = admin_router();
+
+        let (status, _) = send(
+            &router,
+            "PUT",
+            "/admin/platform-bans/200/1",
+            Some(json!({ "reason": "cheating" })),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,61 @@
+mod http;
+mod session_registry;
+
+use crate::admin::http::create_admin_api_router;
+use crate::admin::session_registry::SessionRegistry;
+use crate::config::DwServerConfig;
+use crate::lobby::ResponseCaches;
+use crate::state_metrics::StateMetrics;
+use axum::Router;
+use bitdemon::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use bitdemon::messaging::bd_message::MessageCryptoMetrics;
+use bitdemon::networking::session_manager::SessionManager;
+use bitdemon_backend_sqlite::config::UserFileSizeLimits;
+use log::info;
+use std::sync::{Arc, RwLock};
+
+pub struct AdminState {
+    token: RwLock<String>,
+    sessions: Arc<SessionRegistry>,
+    response_caches: ResponseCaches,
+    user_file_size_limits: UserFileSizeLimits,
+    crypto_metrics: SocketCryptoMetrics,
+    state_metrics: Arc<StateMetrics>,
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+}
+
+/// Counts of messages that could not be decrypted, per socket.
+pub struct SocketCryptoMetrics {
+    pub auth: Arc<MessageCryptoMetrics>,
+    pub lobby: Arc<MessageCryptoMetrics>,
+}
+
+/// Creates the router of the admin api.
+/// Returns `None` if the admin api is disabled.
+pub fn create_admin_router(
+    config: &DwServerConfig,
+    lobby_session_manager: &SessionManager,
+    response_caches: ResponseCaches,
+    crypto_metrics: SocketCryptoMetrics,
+    state_metrics: Arc<StateMetrics>,
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+) -> Option<Router> {
+    let Some(token) = config.admin_token() else {
+        info!("No admin token configured, admin api is disabled");
+        return None;
+    };
+
+    let sessions = SessionRegistry::new(lobby_session_manager);
+
+    let state = Arc::new(AdminState {
+        token: RwLock::new(token.to_string()),
+        sessions,
+        response_caches,
+        user_file_size_limits: config.user_file_size_limits(),
+        crypto_metrics,
+        state_metrics,
+        key_store,
+    });
+
+    Some(create_admin_api_router(state))
+}
@@ -0,0 +1,165 @@
+use bitdemon::networking::bd_session::{BdSession, SessionId};
+use bitdemon::networking::session_manager::SessionManager;
+use chrono::Utc;
+use log::{info, warn};
+use num_traits::ToPrimitive;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+
+#[derive(Serialize, Clone)]
+pub struct SessionUser {
+    pub user_id: u64,
+    pub username: String,
+    pub title: u32,
+    pub title_name: String,
+    pub clock_skew: Option<i32>,
+    pub anonymous: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ActiveSession {
+    pub id: SessionId,
+    pub peer_addr: String,
+    pub connected_at: i64,
+    pub user: Option<SessionUser>,
+}
+
+struct TrackedSession {
+    info: ActiveSession,
+    stream: TcpStream,
+}
+
+/// Keeps track of all connected lobby sessions so that they can be inspected and closed.
+pub struct SessionRegistry {
+    sessions: Mutex<HashMap<SessionId, TrackedSession>>,
+}
+
+impl SessionRegistry {
+    pub fn new(session_manager: &SessionManager) -> Arc<SessionRegistry> {
+        let registry = Arc::new(SessionRegistry {
+            sessions: Mutex::new(HashMap::new()),
+        });
+
+        Self::register_session_manager_callbacks(registry.clone(), session_manager);
+
+        registry
+    }
+
+    pub fn list(&self) -> Vec<ActiveSession> {
+        let mut sessions: Vec<ActiveSession> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .values()
+            .map(|session| session.info.clone())
+            .collect();
+        sessions.sort_by_key(|session| session.id);
+
+        sessions
+    }
+
+    /// Closes the connection of a session.
+    /// Returns whether the session was found.
+    pub fn kick(&self, session_id: SessionId) -> bool {
+        let sessions = self.sessions.lock().unwrap();
+
+        match sessions.get(&session_id) {
+            Some(session) => {
+                info!("Kicking session {session_id}");
+                Self::close(session);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Closes all connections of a user.
+    /// Returns the amount of closed sessions.
+    pub fn kick_user(&self, user_id: u64) -> usize {
+        let sessions = self.sessions.lock().unwrap();
+
+        sessions
+            .values()
+            .filter(|session| {
+                session
+                    .info
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| user.user_id == user_id)
+            })
+            .inspect(|session| {
+                info!("Kicking session {} of user {user_id}", session.info.id);
+                Self::close(session);
+            })
+            .count()
+    }
+
+    fn close(session: &TrackedSession) {
+        if let Err(e) = session.stream.shutdown(Shutdown::Both) {
+            warn!("Failed to close session {}: {e}", session.info.id);
+        }
+    }
+
+    fn register_session_manager_callbacks(registry: Arc<Self>, session_manager: &SessionManager) {
+        let register_registry = registry.clone();
+        session_manager.on_session_registered(move |session| {
+            register_registry.add_session(session);
+        });
+
+        let authenticate_registry = registry.clone();
+        session_manager.on_session_authenticated(move |session| {
+            authenticate_registry.update_user(session);
+        });
+
+        session_manager.on_session_unregistered(move |session| {
+            registry.sessions.lock().unwrap().remove(&session.id);
+        });
+    }
+
+    fn add_session(&self, session: &BdSession) {
+        let stream = match session.try_clone_stream() {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Could not track session {}: {e}", session.id);
+                return;
+            }
+        };
+
+        let peer_addr = session
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+
+        self.sessions.lock().unwrap().insert(
+            session.id,
+            TrackedSession {
+                info: ActiveSession {
+                    id: session.id,
+                    peer_addr,
+                    connected_at: Utc::now().timestamp(),
+                    user: None,
+                },
+                stream,
+            },
+        );
+    }
+
+    fn update_user(&self, session: &BdSession) {
+        let Some(authentication) = session.authentication() else {
+            return;
+        };
+
+        if let Some(tracked_session) = self.sessions.lock().unwrap().get_mut(&session.id) {
+            tracked_session.info.user = Some(SessionUser {
+                user_id: authentication.user_id,
+                username: authentication.username.clone(),
+                title: authentication.title.to_u32().unwrap(),
+                title_name: format!("{:?}", authentication.title),
+                clock_skew: authentication.clock_skew,
+                anonymous: authentication.anonymous,
+            });
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::config::{AuthPlatform, DwServerConfig};
+use ::log::{info, warn};
+use bitdemon::auth::auth_handler::abaccounts::AbaccountsAuthHandler;
+use bitdemon::auth::auth_handler::account::{AccountHandler, AccountOperation};
+use bitdemon::auth::auth_handler::account_for_host::AccountForHostHandler;
+use bitdemon::auth::auth_handler::account_for_mmp::AccountForMmpHandler;
+use bitdemon::auth::auth_handler::anonymous::AnonymousAuthHandler;
+use bitdemon::auth::auth_handler::codo::CodoAuthHandler;
+use bitdemon::auth::auth_handler::nintendo::{NintendoAuthHandler, NintendoAuthKind};
+use bitdemon::auth::auth_handler::steam::SteamAuthHandler;
+use bitdemon::auth::auth_handler::AuthMessageType;
+use bitdemon::auth::auth_server::AuthServer;
+use bitdemon::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use bitdemon_backend_sqlite::account::DwAccountStore;
+use bitdemon_backend_sqlite::ban::DwBanStore;
+use bitdemon_backend_sqlite::config::SteamConfig;
+use bitdemon_backend_sqlite::identity::DwAccountResolver;
+use bitdemon_backend_sqlite::steam::SteamWebApiTicketValidator;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Adds the handlers of all platforms that clients may authenticate with according to the config.
+pub fn configure_auth_server(
+    auth_server: &AuthServer,
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    config: &DwServerConfig,
+    steam_config: SteamConfig,
+) {
+    let platforms = config.auth_platforms();
+    let account_store = Arc::new(DwAccountStore::new());
+    let ban_store = Arc::new(DwBanStore::new());
+
+    if platforms.contains(&AuthPlatform::Steam) {
+        let mut steam_handler = SteamAuthHandler::new(key_store.clone())
+            .with_account_resolver(Arc::new(DwAccountResolver::new()))
+            .with_ban_store(ban_store.clone());
+        if let Some(ticket_validator) = SteamWebApiTicketValidator::from_config(steam_config) {
+            info!("Validating Steam tickets with the Steam Web API");
+            steam_handler = steam_handler.with_ticket_validator(Arc::new(ticket_validator));
+        }
+        auth_server.add_handler(AuthMessageType::SteamForMmpRequest, Arc::new(steam_handler));
+    }
+
+    if platforms.contains(&AuthPlatform::Nintendo) {
+        for kind in NintendoAuthKind::ALL {
+            auth_server.add_handler(
+                kind.request_type(),
+                Arc::new(
+                    NintendoAuthHandler::new(key_store.clone(), kind)
+                        .with_account_resolver(Arc::new(DwAccountResolver::new()))
+                        .with_ban_store(ban_store.clone()),
+                ),
+            );
+        }
+    }
+
+    if platforms.contains(&AuthPlatform::Abaccounts) {
+        let mut abaccounts_handler = AbaccountsAuthHandler::new(key_store.clone())
+            .with_account_resolver(Arc::new(DwAccountResolver::new()))
+            .with_ban_store(ban_store.clone());
+        if let Some(app_id) = config.abaccounts_app_id() {
+            abaccounts_handler = abaccounts_handler.with_app_id(app_id);
+        }
+        auth_server.add_handler(
+            AuthMessageType::AbaccountsForMmpRequest,
+            Arc::new(abaccounts_handler),
+        );
+    }
+
+    if platforms.contains(&AuthPlatform::Account) {
+        for operation in AccountOperation::ALL {
+            auth_server.add_handler(
+                operation.request_type(),
+                Arc::new(AccountHandler::new(account_store.clone(), operation)),
+            );
+        }
+        auth_server.add_handler(
+            AuthMessageType::AccountForMmpRequest,
+            Arc::new(
+                AccountForMmpHandler::new(key_store.clone(), account_store.clone())
+                    .with_ban_store(ban_store.clone()),
+            ),
+        );
+        auth_server.add_handler(
+            AuthMessageType::HostForMmpRequest,
+            Arc::new(
+                AccountForMmpHandler::for_host(key_store.clone(), account_store.clone())
+                    .with_ban_store(ban_store.clone()),
+            ),
+        );
+        auth_server.add_handler(
+            AuthMessageType::AccountForHostRequest,
+            Arc::new(
+                AccountForHostHandler::new(key_store.clone(), account_store.clone())
+                    .with_ban_store(ban_store.clone()),
+            ),
+        );
+    }
+
+    if platforms.contains(&AuthPlatform::Codo) {
+        auth_server.add_handler(
+            AuthMessageType::CodoForMmpRequest,
+            Arc::new(
+                CodoAuthHandler::new(key_store.clone(), account_store).with_ban_store(ban_store),
+            ),
+        );
+    }
+
+    if config.allow_anonymous_auth() {
+        auth_server.add_handler(
+            AuthMessageType::AnonymousForMmpRequest,
+            Arc::new(AnonymousAuthHandler::new(key_store)),
+        );
+    }
+}
+
+/// Removes expired keys from the key store in the background every interval.
+pub fn spawn_key_sweeper(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let key_store = key_store.clone();
+            match tokio::task::spawn_blocking(move || key_store.evict_expired_keys()).await {
+                Ok(0) => {}
+                Ok(evicted_count) => info!("Evicted {evicted_count} expired keys"),
+                Err(e) => warn!("Failed to evict expired keys: {e}"),
+            }
+        }
+    });
+}
@@ -1,21 +1,374 @@
-﻿use serde::{Deserialize, Serialize};
+﻿use bitdemon::messaging::string_encoding::{StringEncoding, UnknownStringEncodingError};
+use bitdemon::networking::connection_limits::ConnectionLimits;
+use bitdemon_backend_sqlite::config::{
+    AdmissionConfig, AntiCheatConfig, BackendConfig, CounterConfig, LeagueConfig,
+    LocalizationConfig, MatchmakingConfig, SteamConfig, TitleUtilitiesConfig, UserFileSizeLimits,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+
+use std::env;
+use std::path::PathBuf;
+
+const CONFIG_FILE_PATH: &str = "./config.json";
+/// Environment variable that points to a different config file,
+/// e.g. to run a server per tenant from the same working directory.
+const CONFIG_FILE_ENV: &str = "DW_SERVER_CONFIG";
+
+const DEFAULT_AUTH_PORT: u16 = 3075;
+const DEFAULT_LOBBY_PORT: u16 = 3074;
 
 const DEFAULT_CONTENT_PORT: u16 = 3076;
+const DEFAULT_ADMIN_PORT: u16 = 3077;
+const DEFAULT_STATE_METRICS_INTERVAL_SECS: u64 = 300;
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_IDLE_SESSION_TIMEOUT_SECS: u64 = 600;
+const DEFAULT_KEY_SWEEP_INTERVAL_SECS: u64 = 60;
 const DEFAULT_HOSTNAME: &str = "localhost";
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct DwServerConfig {
+    auth_port: Option<u16>,
+    lobby_port: Option<u16>,
     content_port: Option<u16>,
+    /// The port the admin api listens on locally
+    admin_port: Option<u16>,
+    /// The token admin api clients need to present.
+    /// The admin api is disabled when not set.
+    admin_token: Option<String>,
     /// The hostname under which the server can be reached
     hostname: Option<String>,
+    /// How long responses of idempotent tasks are reused.
+    /// Caching is disabled when not set.
+    response_cache_ttl_secs: Option<u64>,
+    /// The url twitter posts of users are forwarded to as json.
+    /// Posts are dropped when not set.
+    twitter_webhook_url: Option<String>,
+    /// The maximum sizes of files users can upload to storage and content streaming,
+    /// optionally overridden per title number.
+    /// Defaults to 50KB for both services when not set.
+    user_file_size_limits: Option<UserFileSizeLimits>,
+    /// Sends lobby responses without encryption for patched clients and protocol research.
+    /// Never enable this on a server that is reachable by other people.
+    insecure_plaintext_protocol: Option<bool>,
+    /// How strings of lobby messages are encoded: `utf8`, `utf8_lossy` or `windows1252`.
+    /// Old clients send names in their Windows code page, which fails as UTF-8.
+    /// Strings must be valid UTF-8 when not set.
+    string_encoding: Option<String>,
+    /// Lets clients without any account authenticate as guests.
+    /// Guests are turned away when not set.
+    allow_anonymous_auth: Option<bool>,
+    /// Keeps the keys auth tickets are encrypted with in the database,
+    /// so clients stay authenticated when the server restarts.
+    /// Keys only live in memory when not set.
+    persistent_key_store: Option<bool>,
+    /// How often expired keys are removed from the key store.
+    /// Defaults to every minute, `0` disables removing.
+    key_sweep_interval_secs: Option<u64>,
+    /// The platforms clients may authenticate with.
+    /// All platforms are accepted when not set.
+    auth_platforms: Option<Vec<AuthPlatform>>,
+    /// The app id that clients authenticating with AB accounts must present.
+    /// Any app id is accepted when not set.
+    abaccounts_app_id: Option<u32>,
+    /// Maintenance mode, per-title allowlists and session limits of the lobby server.
+    /// Only banned users are rejected when not set.
+    admission: Option<AdmissionConfig>,
+    /// Rhai scripts overriding single lobby tasks.
+    /// Requires the server to be built with the `scripting` feature.
+    task_scripts: Option<Vec<TaskScriptConfig>>,
+    /// Keeps all persisted data of this server apart from other servers in the same directory.
+    /// Defaults to a tenant named after the lobby port when that is not the default one,
+    /// otherwise data is kept in the shared directories.
+    tenant: Option<String>,
+    /// Translations of messages shown to players, keyed by locale and message key.
+    /// Messages are shown in English when not set.
+    localization: Option<LocalizationConfig>,
+    /// How often the sizes and row counts of the databases are measured.
+    /// Defaults to every 5 minutes, `0` disables measuring.
+    state_metrics_interval_secs: Option<u64>,
+    /// How long messages that are being handled may take to complete when the server shuts down,
+    /// before their connections are dropped.
+    /// Defaults to 30 seconds.
+    shutdown_timeout_secs: Option<u64>,
+    /// After how long without any message or ping sessions are closed,
+    /// so that clients which crashed do not keep their sessions forever.
+    /// Defaults to 10 minutes, `0` keeps idle sessions open.
+    idle_session_timeout_secs: Option<u64>,
+    /// How many connections a single IP address may open and how many messages each may send,
+    /// to keep single peers from exhausting the resources of the server.
+    /// Defaults to 32 connections per IP address sending 100 messages per second each.
+    connection_limits: Option<ConnectionLimitsConfig>,
+    /// The current league season and how many teams share a subdivision.
+    /// Defaults to season 1 with 100 teams per subdivision.
+    league: Option<LeagueConfig>,
+    /// The path of a MaxMind GeoIP2 or GeoLite2 city database in the mmdb format
+    /// that users are located with, e.g. to select their matchmaking region.
+    /// All users are placed at the same location when not set.
+    geoip_database: Option<String>,
+    /// The blocked words and server time offsets of the title utilities per title number.
+    /// Strings are not filtered and the actual time is reported when not set.
+    title_utilities: Option<TitleUtilitiesConfig>,
+    /// The anti cheat challenges per title number and after how many failures users are flagged.
+    /// Clients are not challenged when not set.
+    anti_cheat: Option<AntiCheatConfig>,
+    /// Whether counters are reset daily or weekly, per title number and counter id.
+    /// Counters accumulate forever when not set.
+    counter: Option<CounterConfig>,
+    /// The queries clients search for matchmaking sessions with, per title number and query id,
+    /// and after how many seconds without updates sessions expire.
+    /// Every search finds all sessions of the title and sessions expire after 10 minutes when not set.
+    matchmaking: Option<MatchmakingConfig>,
+    /// The Steam Web API key and the Steam app id per title number that Steam tickets
+    /// are validated with.
+    /// Steam tickets are accepted without validation when not set.
+    steam: Option<SteamConfig>,
+}
+
+/// Limits of the connections to the auth and lobby server.
+/// A limit of `0` disables it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionLimitsConfig {
+    /// Further connections of an IP address are refused while it has this many open.
+    /// Load tests with many players from a single machine need a higher limit.
+    pub max_connections_per_ip: usize,
+    /// Messages beyond this rate are answered with an error instead of being handled.
+    pub max_messages_per_second: u32,
+    /// How many messages may be sent at once after being quiet for a while.
+    pub message_burst: u32,
+    /// Connections are dropped once this many of their messages were throttled.
+    pub max_throttled_messages: u32,
+}
+
+impl Default for ConnectionLimitsConfig {
+    fn default() -> Self {
+        ConnectionLimitsConfig {
+            max_connections_per_ip: 32,
+            max_messages_per_second: 100,
+            message_burst: 200,
+            max_throttled_messages: 500,
+        }
+    }
+}
+
+impl ConnectionLimitsConfig {
+    pub fn to_connection_limits(&self) -> ConnectionLimits {
+        let mut limits = ConnectionLimits::new();
+        if self.max_connections_per_ip > 0 {
+            limits = limits.with_max_connections_per_ip(self.max_connections_per_ip);
+        }
+        if self.max_messages_per_second > 0 {
+            limits = limits.with_message_rate(self.max_messages_per_second, self.message_burst);
+        }
+        if self.max_throttled_messages > 0 {
+            limits = limits.with_max_throttled_messages(self.max_throttled_messages);
+        }
+
+        limits
+    }
+}
+
+/// A way of authenticating with the auth server besides anonymously.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthPlatform {
+    Steam,
+    /// Wii, WiiU and 3DS.
+    Nintendo,
+    /// AB accounts.
+    Abaccounts,
+    /// Call of Duty online ids.
+    Codo,
+    /// Bitdemon accounts with username and password, including listen server hosts.
+    Account,
+}
+
+impl AuthPlatform {
+    pub const ALL: [AuthPlatform; 5] = [
+        AuthPlatform::Steam,
+        AuthPlatform::Nintendo,
+        AuthPlatform::Abaccounts,
+        AuthPlatform::Codo,
+        AuthPlatform::Account,
+    ];
+}
+
+/// A rhai script answering a single task of a lobby service instead of its regular handler.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TaskScriptConfig {
+    /// The name of the lobby service, e.g. `TitleUtilities`.
+    pub service: String,
+    pub task_id: u8,
+    /// The path of the script file.
+    pub path: String,
+}
+
+/// The path of the config file, which can be overridden with the `DW_SERVER_CONFIG` variable.
+pub fn config_file_path() -> PathBuf {
+    env::var_os(CONFIG_FILE_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_PATH))
 }
 
 impl DwServerConfig {
+    pub fn auth_port(&self) -> u16 {
+        self.auth_port.unwrap_or(DEFAULT_AUTH_PORT)
+    }
+
+    pub fn lobby_port(&self) -> u16 {
+        self.lobby_port.unwrap_or(DEFAULT_LOBBY_PORT)
+    }
+
     pub fn content_port(&self) -> u16 {
         self.content_port.unwrap_or(DEFAULT_CONTENT_PORT)
     }
 
+    pub fn admin_port(&self) -> u16 {
+        self.admin_port.unwrap_or(DEFAULT_ADMIN_PORT)
+    }
+
+    pub fn admin_token(&self) -> Option<&str> {
+        self.admin_token
+            .as_deref()
+            .filter(|token| !token.is_empty())
+    }
+
     pub fn hostname(&self) -> &str {
         self.hostname.as_deref().unwrap_or(DEFAULT_HOSTNAME)
     }
+
+    pub fn response_cache_ttl(&self) -> Option<Duration> {
+        self.response_cache_ttl_secs
+            .filter(|ttl_secs| *ttl_secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    pub fn state_metrics_interval(&self) -> Option<Duration> {
+        Some(
+            self.state_metrics_interval_secs
+                .unwrap_or(DEFAULT_STATE_METRICS_INTERVAL_SECS),
+        )
+        .filter(|interval_secs| *interval_secs > 0)
+        .map(Duration::from_secs)
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.shutdown_timeout_secs
+                .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+        )
+    }
+
+    pub fn idle_session_timeout(&self) -> Option<Duration> {
+        Some(
+            self.idle_session_timeout_secs
+                .unwrap_or(DEFAULT_IDLE_SESSION_TIMEOUT_SECS),
+        )
+        .filter(|timeout_secs| *timeout_secs > 0)
+        .map(Duration::from_secs)
+    }
+
+    pub fn connection_limits(&self) -> ConnectionLimits {
+        self.connection_limits
+            .clone()
+            .unwrap_or_default()
+            .to_connection_limits()
+    }
+
+    pub fn geoip_database(&self) -> Option<&str> {
+        self.geoip_database
+            .as_deref()
+            .filter(|path| !path.is_empty())
+    }
+
+    pub fn twitter_webhook_url(&self) -> Option<&str> {
+        self.twitter_webhook_url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+    }
+
+    pub fn insecure_plaintext_protocol(&self) -> bool {
+        self.insecure_plaintext_protocol.unwrap_or(false)
+    }
+
+    pub fn string_encoding(&self) -> Result<StringEncoding, UnknownStringEncodingError> {
+        self.string_encoding
+            .as_deref()
+            .map(StringEncoding::from_str)
+            .unwrap_or(Ok(StringEncoding::Utf8))
+    }
+
+    pub fn allow_anonymous_auth(&self) -> bool {
+        self.allow_anonymous_auth.unwrap_or(false)
+    }
+
+    pub fn persistent_key_store(&self) -> bool {
+        self.persistent_key_store.unwrap_or(false)
+    }
+
+    pub fn key_sweep_interval(&self) -> Option<Duration> {
+        Some(
+            self.key_sweep_interval_secs
+                .unwrap_or(DEFAULT_KEY_SWEEP_INTERVAL_SECS),
+        )
+        .filter(|interval_secs| *interval_secs > 0)
+        .map(Duration::from_secs)
+    }
+
+    pub fn auth_platforms(&self) -> &[AuthPlatform] {
+        self.auth_platforms.as_deref().unwrap_or(&AuthPlatform::ALL)
+    }
+
+    pub fn abaccounts_app_id(&self) -> Option<u32> {
+        self.abaccounts_app_id
+    }
+
+    /// The tenant whose data this server serves or `None` for the shared data directories.
+    pub fn tenant(&self) -> Option<String> {
+        match self.tenant.as_deref().filter(|tenant| !tenant.is_empty()) {
+            Some(tenant) => Some(tenant.to_string()),
+            None => (self.lobby_port() != DEFAULT_LOBBY_PORT)
+                .then(|| format!("lobby-{}", self.lobby_port())),
+        }
+    }
+
+    pub fn task_scripts(&self) -> &[TaskScriptConfig] {
+        self.task_scripts.as_deref().unwrap_or_default()
+    }
+
+    pub fn user_file_size_limits(&self) -> UserFileSizeLimits {
+        self.user_file_size_limits.clone().unwrap_or_default()
+    }
+
+    /// Configuration of the services provided by the sqlite backend.
+    pub fn backend_config(&self) -> BackendConfig {
+        let backend_config = BackendConfig {
+            content_server_hostname: self.hostname().to_string(),
+            content_server_port: self.content_port(),
+            twitter_webhook_url: self.twitter_webhook_url().map(String::from),
+            user_file_size_limits: self.user_file_size_limits(),
+            admission: self.admission.clone().unwrap_or_default(),
+            localization: self.localization.clone().unwrap_or_default(),
+            league: self.league.clone().unwrap_or_default(),
+            title_utilities: self.title_utilities.clone().unwrap_or_default(),
+            anti_cheat: self.anti_cheat.clone().unwrap_or_default(),
+            counter: self.counter.clone().unwrap_or_default(),
+            matchmaking: self.matchmaking.clone().unwrap_or_default(),
+            steam: self.steam.clone().unwrap_or_default(),
+            ..BackendConfig::default()
+        };
+
+        match self.tenant() {
+            Some(tenant) => backend_config.with_tenant(&tenant),
+            None => backend_config,
+        }
+    }
+}
+
+/// Whether the tenant name can safely be used as a directory name.
+pub fn is_valid_tenant(tenant: &str) -> bool {
+    tenant
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
 }
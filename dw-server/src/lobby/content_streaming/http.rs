@@ -1,17 +1,17 @@
-use crate::lobby::content_streaming::publisher_file::DwPublisherContentStreamingService;
-use crate::lobby::content_streaming::user_file::{
-    DwUserContentStreamingService, UserFileClaimOperation, UserFileClaims,
-};
 use axum::body::{Body, Bytes};
-use axum::extract::{Path, Query, State};
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use axum::Router;
 use axum_extra::response::FileStream;
 use bitdemon::domain::title::Title;
+use bitdemon_backend_sqlite::lobby::content_streaming::{
+    DwPublisherContentStreamingService, DwUserContentStreamingService, UserFileClaimOperation,
+    UserFileClaims,
+};
 use jsonwebtoken::{decode, Validation};
-use log::info;
+use log::{info, warn};
 use num_traits::FromPrimitive;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -31,6 +31,7 @@ pub fn create_content_streaming_router(
         .route("/{title}/{stream_id}", get(retrieve_publisher_file))
         .with_state(publisher_service);
 
+    let max_stream_size = user_service.max_stream_size_of_any_title();
     let user_router: Router = Router::new()
         .route(
             "/{title}/{stream_id}",
@@ -38,6 +39,7 @@ pub fn create_content_streaming_router(
                 .put(upload_user_file)
                 .delete(delete_user_file),
         )
+        .layer(DefaultBodyLimit::max(max_stream_size))
         .with_state(user_service);
 
     Router::new()
@@ -58,13 +60,13 @@ async fn retrieve_publisher_file(
         .stream_by_id(title, stream_id)
         .ok_or_else(|| (StatusCode::NOT_FOUND, "Stream not found".to_string()))?;
 
-    let file_name = format!("stream/publisher/{title_num}/{}", stream.filename);
-    let file = File::open(file_name.as_str())
+    let file_path = publisher_service.stream_file_path(title, &stream.filename);
+    let file = File::open(&file_path)
         .await
         .map_err(|e| (StatusCode::NOT_FOUND, format!("File not found: {e}")))?;
 
     let stream = ReaderStream::new(file);
-    let file_stream_resp = FileStream::new(stream).file_name(file_name);
+    let file_stream_resp = FileStream::new(stream).file_name(file_path.to_string_lossy());
 
     Ok(file_stream_resp.into_response())
 }
@@ -111,6 +113,11 @@ async fn upload_user_file(
 
     let title = Title::from_u32(title_num).ok_or(StatusCode::BAD_REQUEST)?;
 
+    if body.len() > user_service.max_stream_size(title) {
+        warn!("Tried to upload user stream that is too large");
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
     let data = body.to_vec();
 
     if user_service.set_stream_data(title, stream_id, data) {
@@ -1,18 +1,16 @@
-﻿use crate::config::DwServerConfig;
-use crate::lobby::content_streaming::http::create_content_streaming_router;
-use crate::lobby::content_streaming::publisher_file::DwPublisherContentStreamingService;
-use crate::lobby::content_streaming::user_file::DwUserContentStreamingService;
+﻿use crate::lobby::content_streaming::http::create_content_streaming_router;
 use crate::lobby::ConfiguredEnvironment;
 use bitdemon::lobby::content_streaming::ContentStreamingHandler;
 use bitdemon::lobby::LobbyServiceId;
+use bitdemon_backend_sqlite::config::BackendConfig;
+use bitdemon_backend_sqlite::lobby::content_streaming::{
+    DwPublisherContentStreamingService, DwUserContentStreamingService,
+};
 use std::sync::Arc;
 
-mod db;
 mod http;
-mod publisher_file;
-mod user_file;
 
-pub fn create_content_streaming_handler(config: &DwServerConfig) -> ConfiguredEnvironment {
+pub fn create_content_streaming_handler(config: &BackendConfig) -> ConfiguredEnvironment {
     let user_service = Arc::new(DwUserContentStreamingService::new(config));
     let publisher_service = Arc::new(DwPublisherContentStreamingService::new(config));
 
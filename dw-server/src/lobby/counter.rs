@@ -0,0 +1,35 @@
+use bitdemon::lobby::ThreadSafeLobbyHandler;
+use bitdemon_backend_sqlite::config::BackendConfig;
+use bitdemon_backend_sqlite::lobby::counter::{create_counter_handler, roll_over_counter_windows};
+use chrono::Utc;
+use log::warn;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often counters are checked for windows that elapsed.
+const ROLL_OVER_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Creates the counter handler and resets counters in the background once their window elapsed.
+pub fn create_scheduled_counter_handler(
+    backend_config: &BackendConfig,
+) -> Arc<ThreadSafeLobbyHandler> {
+    let config = Arc::new(backend_config.counter.clone());
+    if !config.titles.is_empty() {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(ROLL_OVER_INTERVAL);
+            loop {
+                ticker.tick().await;
+
+                let config = config.clone();
+                let roll_over = tokio::task::spawn_blocking(move || {
+                    roll_over_counter_windows(&config, Utc::now())
+                });
+                if let Err(e) = roll_over.await {
+                    warn!("Failed to roll over counter windows: {e}");
+                }
+            }
+        });
+    }
+
+    create_counter_handler()
+}
@@ -0,0 +1,201 @@
+use crate::config::DwServerConfig;
+use bitdemon::lobby::dml::{
+    DmlHandler, DmlHierarchicalInfo, DmlInfo, DmlService, ThreadSafeDmlService,
+};
+use bitdemon::networking::bd_session::BdSession;
+use log::{debug, error, info};
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::sync::Arc;
+
+const RECORDED_IP_KEY: &str = "dml_recorded_ip";
+
+/// Creates the DML handler, locating users with the configured GeoIP database.
+/// All users are placed at the same location when no database is configured or it cannot be read.
+pub fn create_dml_handler(config: &DwServerConfig) -> Arc<DmlHandler> {
+    let dml_service: Arc<ThreadSafeDmlService> = match config.geoip_database() {
+        Some(path) => match GeoIpDmlService::open(path) {
+            Ok(service) => {
+                info!("Locating users with GeoIP database {path}");
+                Arc::new(service)
+            }
+            Err(e) => {
+                error!("Failed to open GeoIP database {path}: {e}");
+                Arc::new(FixedDmlService::new())
+            }
+        },
+        None => Arc::new(FixedDmlService::new()),
+    };
+
+    Arc::new(DmlHandler::new(dml_service))
+}
+
+/// Places all users at the same location.
+pub struct FixedDmlService {}
+
+impl DmlService for FixedDmlService {
+    fn record_ip(&self, _session: &BdSession, _ip: Ipv4Addr) {}
+
+    fn get_user_data(&self, _session: &BdSession) -> DmlInfo {
+        Self::fixed_info()
+    }
+
+    fn get_user_hierarchical_data(&self, _session: &BdSession) -> DmlHierarchicalInfo {
+        DmlHierarchicalInfo {
+            base: Self::fixed_info(),
+            tier0: 0,
+            tier1: 0,
+            tier2: 0,
+            tier3: 0,
+        }
+    }
+}
+
+impl Default for FixedDmlService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixedDmlService {
+    pub fn new() -> FixedDmlService {
+        FixedDmlService {}
+    }
+
+    fn fixed_info() -> DmlInfo {
+        DmlInfo {
+            country_code: String::from("US"),
+            country: String::from("United States"),
+            region: String::from("California"),
+            city: String::from("Los Angeles"),
+            latitude: 34.0453f32,
+            longitude: -118.2413f32,
+        }
+    }
+}
+
+/// Locates users with a MaxMind GeoIP2 or GeoLite2 city database.
+///
+/// Users are located by the address they are connected from.
+/// When that is not a public address, e.g. for players in the same network as the server,
+/// the address the client recorded for itself is used instead.
+/// Users that cannot be located are placed at the same location as with [`FixedDmlService`].
+pub struct GeoIpDmlService {
+    reader: Reader<Vec<u8>>,
+}
+
+impl DmlService for GeoIpDmlService {
+    fn record_ip(&self, session: &BdSession, ip: Ipv4Addr) {
+        session.scratch().insert(RECORDED_IP_KEY, ip);
+    }
+
+    fn get_user_data(&self, session: &BdSession) -> DmlInfo {
+        self.get_user_hierarchical_data(session).base
+    }
+
+    fn get_user_hierarchical_data(&self, session: &BdSession) -> DmlHierarchicalInfo {
+        let maybe_info = Self::lookup_address(session).and_then(|ip| {
+            let info = self.locate(ip);
+            if info.is_none() {
+                debug!("No location is known for {ip}");
+            }
+
+            info
+        });
+
+        maybe_info.unwrap_or_else(|| FixedDmlService::new().get_user_hierarchical_data(session))
+    }
+}
+
+impl GeoIpDmlService {
+    pub fn open(path: impl AsRef<Path>) -> Result<GeoIpDmlService, MaxMindDBError> {
+        Ok(GeoIpDmlService {
+            reader: Reader::open_readfile(path)?,
+        })
+    }
+
+    fn lookup_address(session: &BdSession) -> Option<IpAddr> {
+        let peer_ip = session
+            .peer_addr()
+            .ok()
+            .map(|addr| addr.ip().to_canonical());
+        if peer_ip.is_some_and(is_public) {
+            return peer_ip;
+        }
+
+        session
+            .scratch()
+            .get::<Ipv4Addr>(RECORDED_IP_KEY)
+            .map(IpAddr::V4)
+            .filter(|ip| is_public(*ip))
+    }
+
+    fn locate(&self, ip: IpAddr) -> Option<DmlHierarchicalInfo> {
+        let city: geoip2::City = self.reader.lookup(ip).ok()?;
+
+        let country = city.country.as_ref();
+        let subdivision = city
+            .subdivisions
+            .as_ref()
+            .and_then(|subdivisions| subdivisions.first());
+        let location = city.location.as_ref();
+
+        Some(DmlHierarchicalInfo {
+            base: DmlInfo {
+                country_code: country
+                    .and_then(|country| country.iso_code)
+                    .unwrap_or_default()
+                    .to_string(),
+                country: english_name(country.and_then(|country| country.names.as_ref())),
+                region: english_name(
+                    subdivision.and_then(|subdivision| subdivision.names.as_ref()),
+                ),
+                city: english_name(city.city.as_ref().and_then(|city| city.names.as_ref())),
+                latitude: location
+                    .and_then(|location| location.latitude)
+                    .unwrap_or_default() as f32,
+                longitude: location
+                    .and_then(|location| location.longitude)
+                    .unwrap_or_default() as f32,
+            },
+            tier0: city
+                .continent
+                .as_ref()
+                .and_then(|continent| continent.geoname_id)
+                .unwrap_or_default(),
+            tier1: country
+                .and_then(|country| country.geoname_id)
+                .unwrap_or_default(),
+            tier2: subdivision
+                .and_then(|subdivision| subdivision.geoname_id)
+                .unwrap_or_default(),
+            tier3: city
+                .city
+                .as_ref()
+                .and_then(|city| city.geoname_id)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+fn english_name(names: Option<&BTreeMap<&str, &str>>) -> String {
+    names
+        .and_then(|names| names.get("en"))
+        .map(|name| name.to_string())
+        .unwrap_or_default()
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => !(ip.is_loopback() || ip.is_unspecified()),
+    }
+}
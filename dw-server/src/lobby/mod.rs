@@ -1,66 +1,164 @@
 ﻿mod content_streaming;
 mod counter;
-mod group;
-mod profile;
-mod rich_presence;
-mod storage;
+mod dml;
 
 use crate::config::DwServerConfig;
 use crate::lobby::content_streaming::create_content_streaming_handler;
-use crate::lobby::counter::create_counter_handler;
-use crate::lobby::group::create_group_handler;
-use crate::lobby::profile::create_profile_handler;
-use crate::lobby::rich_presence::create_rich_presence_handler;
-use crate::lobby::storage::create_storage_handler;
+use crate::lobby::counter::create_scheduled_counter_handler;
+use crate::lobby::dml::create_dml_handler;
+use crate::manifest::{ManifestTaggingHandler, TitleManifests};
+#[cfg(feature = "scripting")]
+use crate::scripting::TaskScripts;
 use axum::Router;
-use bitdemon::lobby::anti_cheat::AntiCheatHandler;
 use bitdemon::lobby::bandwidth::BandwidthHandler;
-use bitdemon::lobby::dml::DmlHandler;
-use bitdemon::lobby::event_log::EventLogHandler;
+use bitdemon::lobby::dml::DmlTaskId;
 use bitdemon::lobby::key_archive::KeyArchiveHandler;
-use bitdemon::lobby::league::LeagueHandler;
-use bitdemon::lobby::title_utilities::TitleUtilitiesHandler;
-use bitdemon::lobby::twitch::TwitchHandler;
-use bitdemon::lobby::vote_rank::VoteRankHandler;
-use bitdemon::lobby::youtube::YoutubeHandler;
+use bitdemon::lobby::response_cache::{CachingLobbyHandler, ResponseCacheScope};
+use bitdemon::lobby::storage::StorageTaskId;
 use bitdemon::lobby::LobbyServiceId::{
-    Anticheat, BandwidthTest, Counter, Dml, EventLog, Group, KeyArchive, League, Profile,
-    RichPresence, Storage, TitleUtilities, Twitch, VoteRank, Youtube,
+    Anticheat, BandwidthTest, Counter, Dml, EventLog, Facebook, Group, KeyArchive, League,
+    LinkCode, Mail, Matchmaking, Messaging, Messaging2, Profile, RichPresence, Storage,
+    TitleUtilities, Twitch, Twitter, VoteRank, Youtube,
 };
 use bitdemon::lobby::{LobbyServer, LobbyServiceId, ThreadSafeLobbyHandler};
 use bitdemon::networking::session_manager::SessionManager;
+use bitdemon_backend_sqlite::lobby::anti_cheat::create_anti_cheat_handler;
+use bitdemon_backend_sqlite::lobby::event_log::create_event_log_handler;
+use bitdemon_backend_sqlite::lobby::facebook::create_facebook_handler;
+use bitdemon_backend_sqlite::lobby::group::create_group_handler;
+use bitdemon_backend_sqlite::lobby::league::create_league_handler;
+use bitdemon_backend_sqlite::lobby::link_code::create_link_code_handler;
+use bitdemon_backend_sqlite::lobby::mail::create_mail_handler;
+use bitdemon_backend_sqlite::lobby::matchmaking::create_matchmaking_handler;
+use bitdemon_backend_sqlite::lobby::messaging::create_messaging2_handler;
+use bitdemon_backend_sqlite::lobby::profile::create_profile_handler;
+use bitdemon_backend_sqlite::lobby::rich_presence::create_rich_presence_handler;
+use bitdemon_backend_sqlite::lobby::storage::create_storage_handler;
+use bitdemon_backend_sqlite::lobby::title_utilities::create_title_utilities_handler;
+use bitdemon_backend_sqlite::lobby::twitch::create_twitch_handler;
+use bitdemon_backend_sqlite::lobby::twitter::create_twitter_handler;
+use bitdemon_backend_sqlite::lobby::vote_rank::create_vote_rank_handler;
+use bitdemon_backend_sqlite::lobby::youtube::create_youtube_handler;
+#[cfg(feature = "scripting")]
+use log::error;
+#[cfg(not(feature = "scripting"))]
+use log::warn;
 use std::cell::Cell;
+#[cfg(feature = "scripting")]
+use std::process::exit;
 use std::sync::Arc;
 
 pub fn configure_lobby_server(
     lobby_server: &LobbyServer,
     session_manager: Arc<SessionManager>,
     config: &DwServerConfig,
+    response_caches: &mut ResponseCaches,
+    manifests: Arc<TitleManifests>,
 ) -> Router {
-    let mut configurer = DwServerConfigurer::new(lobby_server);
+    let backend_config = config.backend_config();
+    let mut configurer = DwServerConfigurer::new(lobby_server, manifests, config);
 
-    configurer.direct_config(Anticheat, Arc::new(AntiCheatHandler::new()));
+    configurer.direct_config(
+        Anticheat,
+        create_anti_cheat_handler(&backend_config, session_manager.clone()),
+    );
     configurer.direct_config(BandwidthTest, Arc::new(BandwidthHandler::new()));
 
-    configurer.full_config(create_content_streaming_handler(config));
+    configurer.full_config(create_content_streaming_handler(&backend_config));
 
-    configurer.direct_config(Counter, create_counter_handler());
-    configurer.direct_config(Dml, Arc::new(DmlHandler::new()));
-    configurer.direct_config(EventLog, Arc::new(EventLogHandler::new()));
+    configurer.direct_config(Counter, create_scheduled_counter_handler(&backend_config));
+    configurer.direct_config(
+        Dml,
+        cache_responses(
+            config,
+            create_dml_handler(config),
+            &mut response_caches.other,
+            |cache| {
+                cache
+                    .with_cacheable_task(DmlTaskId::GetUserData)
+                    .with_cacheable_task(DmlTaskId::GetUserHierarchicalData)
+            },
+        ),
+    );
+    configurer.direct_config(EventLog, create_event_log_handler());
+    configurer.direct_config(Facebook, create_facebook_handler());
     configurer.direct_config(Group, create_group_handler(session_manager.clone()));
     configurer.direct_config(KeyArchive, Arc::new(KeyArchiveHandler::new()));
-    configurer.direct_config(League, Arc::new(LeagueHandler::new()));
+    configurer.direct_config(League, create_league_handler(&backend_config));
+    configurer.direct_config(LinkCode, create_link_code_handler());
+    configurer.direct_config(Mail, create_mail_handler());
+    configurer.direct_config(
+        Matchmaking,
+        create_matchmaking_handler(&backend_config, session_manager.clone()),
+    );
+    let messaging_handler = create_messaging2_handler();
+    configurer.direct_config(Messaging, messaging_handler.clone());
+    configurer.direct_config(Messaging2, messaging_handler);
     configurer.direct_config(Profile, create_profile_handler());
-    configurer.direct_config(RichPresence, create_rich_presence_handler(session_manager));
-    configurer.direct_config(Storage, create_storage_handler());
-    configurer.direct_config(TitleUtilities, Arc::new(TitleUtilitiesHandler::new()));
-    configurer.direct_config(Twitch, Arc::new(TwitchHandler::new()));
-    configurer.direct_config(VoteRank, Arc::new(VoteRankHandler::new()));
-    configurer.direct_config(Youtube, Arc::new(YoutubeHandler::new()));
+    configurer.direct_config(
+        RichPresence,
+        create_rich_presence_handler(session_manager.clone()),
+    );
+    configurer.direct_config(
+        Storage,
+        cache_responses(
+            config,
+            create_storage_handler(&backend_config),
+            &mut response_caches.publisher_files,
+            |cache| {
+                cache
+                    .with_scope(ResponseCacheScope::Title)
+                    .with_cacheable_task(StorageTaskId::ListAllPublisherFiles)
+            },
+        ),
+    );
+    configurer.direct_config(
+        TitleUtilities,
+        create_title_utilities_handler(&backend_config, session_manager),
+    );
+    configurer.direct_config(Twitch, create_twitch_handler());
+    configurer.direct_config(Twitter, create_twitter_handler(&backend_config));
+    configurer.direct_config(VoteRank, create_vote_rank_handler());
+    configurer.direct_config(Youtube, create_youtube_handler());
 
     configurer.into()
 }
 
+/// Response caches of lobby services that can be managed at runtime.
+#[derive(Default)]
+pub struct ResponseCaches {
+    /// Caches of publisher file listings.
+    pub publisher_files: Vec<Arc<CachingLobbyHandler>>,
+    /// All other caches.
+    pub other: Vec<Arc<CachingLobbyHandler>>,
+}
+
+impl ResponseCaches {
+    pub fn all(&self) -> impl Iterator<Item = &Arc<CachingLobbyHandler>> {
+        self.publisher_files.iter().chain(self.other.iter())
+    }
+}
+
+fn cache_responses<F>(
+    config: &DwServerConfig,
+    handler: Arc<ThreadSafeLobbyHandler>,
+    caches: &mut Vec<Arc<CachingLobbyHandler>>,
+    configure: F,
+) -> Arc<ThreadSafeLobbyHandler>
+where
+    F: FnOnce(CachingLobbyHandler) -> CachingLobbyHandler,
+{
+    match config.response_cache_ttl() {
+        Some(ttl) => {
+            let cache = Arc::new(configure(CachingLobbyHandler::new(handler, ttl)));
+            caches.push(cache.clone());
+
+            cache
+        }
+        None => handler,
+    }
+}
+
 pub struct ConfiguredEnvironment {
     service_id: LobbyServiceId,
     handler: Arc<ThreadSafeLobbyHandler>,
@@ -101,13 +199,33 @@ impl ConfiguredEnvironment {
 struct DwServerConfigurer<'a> {
     lobby_server: &'a LobbyServer,
     pub_router: Cell<Router>,
+    manifests: Arc<TitleManifests>,
+    #[cfg(feature = "scripting")]
+    task_scripts: Arc<TaskScripts>,
 }
 
 impl<'a> DwServerConfigurer<'a> {
-    fn new(lobby_server: &'a LobbyServer) -> Self {
+    fn new(
+        lobby_server: &'a LobbyServer,
+        manifests: Arc<TitleManifests>,
+        config: &DwServerConfig,
+    ) -> Self {
+        #[cfg(not(feature = "scripting"))]
+        if !config.task_scripts().is_empty() {
+            warn!("Task scripts are configured but the server was built without scripting support");
+        }
+
         DwServerConfigurer {
             lobby_server,
             pub_router: Cell::new(Router::new()),
+            manifests,
+            #[cfg(feature = "scripting")]
+            task_scripts: Arc::new(
+                TaskScripts::load(config.task_scripts()).unwrap_or_else(|e| {
+                    error!("Failed to load task scripts: {e}");
+                    exit(1);
+                }),
+            ),
         }
     }
 
@@ -116,14 +234,33 @@ impl<'a> DwServerConfigurer<'a> {
         lobby_service_id: LobbyServiceId,
         handler: Arc<ThreadSafeLobbyHandler>,
     ) {
-        self.lobby_server.add_service(lobby_service_id, handler);
+        self.lobby_server.add_service(
+            lobby_service_id,
+            self.wrap_handler(lobby_service_id, handler),
+        );
     }
 
     fn full_config(&mut self, mut env: ConfiguredEnvironment) {
         self.pub_router
             .set(env.configure_pub_router(self.pub_router.take()));
+        env.handler = self.wrap_handler(env.service_id, env.handler);
         env.configure_lobby_server(self.lobby_server)
     }
+
+    fn wrap_handler(
+        &self,
+        lobby_service_id: LobbyServiceId,
+        handler: Arc<ThreadSafeLobbyHandler>,
+    ) -> Arc<ThreadSafeLobbyHandler> {
+        #[cfg(feature = "scripting")]
+        let handler = self.task_scripts.wrap(lobby_service_id, handler);
+
+        Arc::new(ManifestTaggingHandler::new(
+            lobby_service_id,
+            handler,
+            self.manifests.clone(),
+        ))
+    }
 }
 
 impl<'a> From<DwServerConfigurer<'a>> for Router {
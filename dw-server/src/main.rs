@@ -1,72 +1,255 @@
+mod admin;
+mod auth;
 mod config;
 mod lobby;
 mod log;
+mod manifest;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod state_metrics;
 
-use crate::config::DwServerConfig;
-use crate::lobby::configure_lobby_server;
+use crate::admin::{create_admin_router, SocketCryptoMetrics};
+use crate::auth::{configure_auth_server, spawn_key_sweeper};
+use crate::config::{config_file_path, is_valid_tenant, DwServerConfig};
+use crate::lobby::{configure_lobby_server, ResponseCaches};
 use crate::log::{initialize_log, log_session_id};
-use ::log::{error, info};
+use crate::manifest::TitleManifests;
+use crate::state_metrics::StateMetrics;
+use ::log::{error, info, warn};
 use bitdemon::auth::auth_server::AuthServer;
-use bitdemon::auth::key_store::InMemoryKeyStore;
+use bitdemon::auth::key_store::{InMemoryKeyStore, ThreadSafeBackendPrivateKeyStorage};
 use bitdemon::lobby::LobbyServer;
 use bitdemon::networking::bd_socket::BdSocket;
 use bitdemon::networking::session_manager::SessionManager;
+use bitdemon_backend_sqlite::admission::DwSessionAdmissionPolicy;
+use bitdemon_backend_sqlite::db::set_db_dir;
+use bitdemon_backend_sqlite::key_store::DwKeyStore;
 use std::process::exit;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::fs::read_to_string;
 use tokio::net::TcpListener;
 
-const AUTH_SERVER_PORT: u16 = 3075;
-const LOBBY_SERVER_PORT: u16 = 3074;
+/// Idle sessions are closed at most this long after reaching the idle timeout.
+const MAX_IDLE_SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
 
 #[tokio::main]
 async fn main() {
     initialize_log();
 
     let config = read_config().await;
+    let backend_config = config.backend_config();
+    match config.tenant() {
+        Some(tenant) if !is_valid_tenant(&tenant) => {
+            error!("Tenant {tenant} must only consist of letters, digits, '-' and '_'");
+            exit(1);
+        }
+        Some(tenant) => info!("Serving tenant {tenant}"),
+        None => {}
+    }
+    set_db_dir(&backend_config.db_dir);
+    let auth_port = config.auth_port();
+    let lobby_port = config.lobby_port();
 
     let auth_session_manager = Arc::new(SessionManager::new());
     log_session_id(auth_session_manager.as_ref(), "auth");
     let mut auth_socket =
-        match BdSocket::new_with_session_manager(AUTH_SERVER_PORT, auth_session_manager) {
+        match BdSocket::new_with_session_manager(auth_port, auth_session_manager.clone()) {
             Err(err) => {
-                panic!("Failed to open socket for auth server on port {AUTH_SERVER_PORT}: {err}")
+                panic!("Failed to open socket for auth server on port {auth_port}: {err}")
             }
             Ok(s) => s,
         };
 
     let lobby_session_manager = Arc::new(SessionManager::new());
     log_session_id(lobby_session_manager.as_ref(), "lobby");
-    let mut lobby_socket = match BdSocket::new_with_session_manager(
-        LOBBY_SERVER_PORT,
-        lobby_session_manager.clone(),
-    ) {
-        Err(err) => {
-            panic!("Failed to open socket for lobby server on port {LOBBY_SERVER_PORT}: {err}")
-        }
-        Ok(s) => s,
+    let mut lobby_socket =
+        match BdSocket::new_with_session_manager(lobby_port, lobby_session_manager.clone()) {
+            Err(err) => {
+                panic!("Failed to open socket for lobby server on port {lobby_port}: {err}")
+            }
+            Ok(s) => s,
+        };
+    let connection_limits = config.connection_limits();
+    auth_socket.set_connection_limits(connection_limits);
+    lobby_socket.set_connection_limits(connection_limits);
+    if config.insecure_plaintext_protocol() {
+        warn!("!!! INSECURE: Plaintext protocol mode is enabled !!!");
+        warn!(
+            "Lobby responses are sent without encryption and can be read by anyone on the network."
+        );
+        warn!("Only use this with patched clients for protocol research on a trusted network.");
+        lobby_socket.set_plaintext(true);
+    }
+
+    let key_store: Arc<ThreadSafeBackendPrivateKeyStorage> = if config.persistent_key_store() {
+        Arc::new(DwKeyStore::new())
+    } else {
+        Arc::new(InMemoryKeyStore::new())
     };
+    if let Some(interval) = config.key_sweep_interval() {
+        spawn_key_sweeper(key_store.clone(), interval);
+    }
+    if let Some(idle_timeout) = config.idle_session_timeout() {
+        spawn_idle_session_sweeper(
+            vec![auth_session_manager, lobby_session_manager.clone()],
+            idle_timeout,
+        );
+    }
+
+    let auth_server = Arc::new(AuthServer::new());
+    configure_auth_server(
+        &auth_server,
+        key_store.clone(),
+        &config,
+        backend_config.steam,
+    );
 
-    let key_store = Arc::new(InMemoryKeyStore::new());
+    let admission_policy =
+        DwSessionAdmissionPolicy::new(backend_config.admission, lobby_session_manager.as_ref())
+            .with_localization(backend_config.localization);
+    let string_encoding = config.string_encoding().unwrap_or_else(|e| {
+        error!("Failed to parse config: {e}");
+        exit(1);
+    });
+    let lobby_server = Arc::new(
+        LobbyServer::new(key_store.clone())
+            .with_admission_policy(Arc::new(admission_policy))
+            .with_string_encoding(string_encoding),
+    );
 
-    let auth_server = Arc::new(AuthServer::new(key_store.clone()));
-    let lobby_server = Arc::new(LobbyServer::new(key_store.clone()));
+    let manifests = Arc::new(TitleManifests::load());
+    let mut response_caches = ResponseCaches::default();
+    let lobby_router = configure_lobby_server(
+        &lobby_server,
+        lobby_session_manager.clone(),
+        &config,
+        &mut response_caches,
+        manifests.clone(),
+    );
+    manifests.log_coverage_gaps(&lobby_server);
 
-    let lobby_router = configure_lobby_server(&lobby_server, lobby_session_manager, &config);
+    let state_metrics = Arc::new(StateMetrics::default());
+    if let Some(interval) = config.state_metrics_interval() {
+        state_metrics.spawn_collector(interval);
+    }
 
-    let auth_join = auth_socket.run_async(auth_server);
-    let lobby_join = lobby_socket.run_async(lobby_server);
+    let crypto_metrics = SocketCryptoMetrics {
+        auth: auth_socket.crypto_metrics(),
+        lobby: lobby_socket.crypto_metrics(),
+    };
+    if let Some(admin_router) = create_admin_router(
+        &config,
+        &lobby_session_manager,
+        response_caches,
+        crypto_metrics,
+        state_metrics,
+        key_store,
+    ) {
+        let admin_port = config.admin_port();
+        info!("Running admin http server on port {admin_port}");
+        let admin_listener = TcpListener::bind(format!("127.0.0.1:{admin_port}"))
+            .await
+            .unwrap();
+        tokio::spawn(async move { axum::serve(admin_listener, admin_router).await });
+    }
+
+    let auth_shutdown = auth_socket.shutdown_handle();
+    let lobby_shutdown = lobby_socket.shutdown_handle();
+    let auth_join = auth_socket.run_tokio_blocking(auth_server);
+    let lobby_join = lobby_socket.run_tokio_blocking(lobby_server);
 
     let content_port = config.content_port();
     info!("Running content http server on port {content_port}");
     let listener = TcpListener::bind(format!("0.0.0.0:{content_port}"))
         .await
         .unwrap();
-    let http_promise = axum::serve(listener, lobby_router);
+    let http_promise =
+        axum::serve(listener, lobby_router).with_graceful_shutdown(shutdown_signal());
 
     http_promise.await.unwrap();
-    auth_join.join().unwrap().unwrap();
-    lobby_join.join().unwrap().unwrap();
+
+    // Players are not dropped in the middle of a request when restarting the server
+    auth_shutdown.shutdown();
+    lobby_shutdown.shutdown();
+    let shutdown_timeout = config.shutdown_timeout();
+    let drained = {
+        let auth_shutdown = auth_shutdown.clone();
+        let lobby_shutdown = lobby_shutdown.clone();
+        tokio::task::spawn_blocking(move || {
+            let started_at = Instant::now();
+            auth_shutdown.wait_timeout(shutdown_timeout)
+                && lobby_shutdown
+                    .wait_timeout(shutdown_timeout.saturating_sub(started_at.elapsed()))
+        })
+        .await
+        .unwrap()
+    };
+    if !drained {
+        warn!("Dropping connections that did not complete within {shutdown_timeout:?}");
+        auth_shutdown.close_connections();
+        lobby_shutdown.close_connections();
+    }
+
+    auth_join.await.unwrap().unwrap();
+    lobby_join.await.unwrap().unwrap();
+    info!("Shut down");
+}
+
+/// Closes sessions of clients that stopped sending anything in the background.
+fn spawn_idle_session_sweeper(session_managers: Vec<Arc<SessionManager>>, idle_timeout: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(idle_timeout.min(MAX_IDLE_SESSION_SWEEP_INTERVAL));
+        loop {
+            ticker.tick().await;
+
+            let session_managers = session_managers.clone();
+            let evicted_count = tokio::task::spawn_blocking(move || {
+                session_managers
+                    .iter()
+                    .map(|session_manager| session_manager.evict_idle_sessions(idle_timeout))
+                    .sum::<usize>()
+            })
+            .await;
+            match evicted_count {
+                Ok(0) => {}
+                Ok(evicted_count) => info!("Closed {evicted_count} idle sessions"),
+                Err(e) => warn!("Failed to close idle sessions: {e}"),
+            }
+        }
+    });
+}
+
+/// Completes when the server is asked to stop, either with Ctrl+C or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+
+    info!("Shutting down");
 }
 
 async fn read_config() -> DwServerConfig {
@@ -77,10 +260,14 @@ async fn read_config() -> DwServerConfig {
 }
 
 async fn read_config_from_file() -> Option<DwServerConfig> {
-    let json_str = read_to_string("./config.json")
+    let config_path = config_file_path();
+    let json_str = read_to_string(&config_path)
         .await
         .map_err(|_| {
-            info!("Could not read config.json, applying default configuration");
+            info!(
+                "Could not read {}, applying default configuration",
+                config_path.display()
+            );
         })
         .ok()?;
 
@@ -0,0 +1,299 @@
+use bitdemon::domain::title::Title;
+use bitdemon::lobby::anti_cheat::AntiCheatTaskId;
+use bitdemon::lobby::bandwidth::BandwidthTaskId;
+use bitdemon::lobby::content_streaming::ContentStreamingTaskId;
+use bitdemon::lobby::counter::CounterTaskId;
+use bitdemon::lobby::dml::DmlTaskId;
+use bitdemon::lobby::event_log::EventLogTaskId;
+use bitdemon::lobby::facebook::FacebookTaskId;
+use bitdemon::lobby::group::GroupTaskId;
+use bitdemon::lobby::key_archive::KeyArchiveTaskId;
+use bitdemon::lobby::league::LeagueTaskId;
+use bitdemon::lobby::link_code::LinkCodeTaskId;
+use bitdemon::lobby::mail::MailTaskId;
+use bitdemon::lobby::matchmaking::MatchmakingTaskId;
+use bitdemon::lobby::messaging::Messaging2TaskId;
+use bitdemon::lobby::profile::ProfileTaskId;
+use bitdemon::lobby::rich_presence::RichPresenceTaskId;
+use bitdemon::lobby::storage::StorageTaskId;
+use bitdemon::lobby::title_utilities::TitleUtilitiesTaskId;
+use bitdemon::lobby::twitch::TwitchTaskId;
+use bitdemon::lobby::twitter::TwitterTaskId;
+use bitdemon::lobby::vote_rank::VoteRankTaskId;
+use bitdemon::lobby::youtube::YoutubeTaskId;
+use bitdemon::lobby::{LobbyHandler, LobbyServer, LobbyServiceId, ThreadSafeLobbyHandler};
+use bitdemon::messaging::bd_message::BdMessage;
+use bitdemon::messaging::bd_reader::BdReader;
+use bitdemon::messaging::bd_response::BdResponse;
+use bitdemon::networking::bd_session::BdSession;
+use log::{info, warn};
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Which services and tasks the supported titles are known to use.
+const TITLE_MANIFESTS_JSON: &str = include_str!("../manifests/titles.json");
+
+#[derive(Deserialize)]
+struct TitleManifestsFile {
+    manifests: Vec<TitleManifestEntry>,
+}
+
+/// The services used by a group of titles sharing the same code base, e.g. all platforms of T6.
+#[derive(Deserialize)]
+struct TitleManifestEntry {
+    /// Names of [`Title`] variants.
+    titles: Vec<String>,
+    /// Task ids by names of [`LobbyServiceId`] variants.
+    /// An empty list means the title uses the service, but none of its task ids are known.
+    services: BTreeMap<String, Vec<u8>>,
+}
+
+/// Whether a task a client called is known to be used by its title.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum TaskExpectation {
+    /// The manifest of the title lists the task.
+    Expected,
+    /// The title has a manifest that does not list the task.
+    Unexpected,
+    /// There is no manifest for the title.
+    NoManifest,
+}
+
+/// The services and task ids of the supported titles, read from the embedded manifest file.
+pub struct TitleManifests {
+    titles: HashMap<Title, HashMap<LobbyServiceId, Vec<u8>>>,
+}
+
+impl TitleManifests {
+    pub fn load() -> TitleManifests {
+        let file: TitleManifestsFile = serde_json::from_str(TITLE_MANIFESTS_JSON)
+            .expect("embedded title manifests to be valid");
+
+        let mut titles = HashMap::new();
+        for entry in file.manifests {
+            let services: HashMap<LobbyServiceId, Vec<u8>> = entry
+                .services
+                .into_iter()
+                .map(|(name, task_ids)| {
+                    let service_id = parse_service_id(&name)
+                        .unwrap_or_else(|| panic!("manifest service {name} to be known"));
+                    (service_id, task_ids)
+                })
+                .collect();
+
+            for title_name in entry.titles {
+                let title = parse_title(&title_name)
+                    .unwrap_or_else(|| panic!("manifest title {title_name} to be known"));
+                titles.insert(title, services.clone());
+            }
+        }
+
+        TitleManifests { titles }
+    }
+
+    pub fn task_expectation(
+        &self,
+        title: Title,
+        service_id: LobbyServiceId,
+        task_id: u8,
+    ) -> TaskExpectation {
+        match self.titles.get(&title) {
+            Some(services) => {
+                let expected = services
+                    .get(&service_id)
+                    .is_some_and(|task_ids| task_ids.contains(&task_id));
+                if expected {
+                    TaskExpectation::Expected
+                } else {
+                    TaskExpectation::Unexpected
+                }
+            }
+            None => TaskExpectation::NoManifest,
+        }
+    }
+
+    /// Logs all services and tasks of the manifests the lobby server cannot answer.
+    pub fn log_coverage_gaps(&self, lobby_server: &LobbyServer) {
+        let mut titles: Vec<&Title> = self.titles.keys().collect();
+        titles.sort_by_key(|title| format!("{title:?}"));
+
+        for title in titles {
+            let mut services: Vec<(&LobbyServiceId, &Vec<u8>)> =
+                self.titles[title].iter().collect();
+            services.sort_by_key(|(service_id, _)| service_id.to_u8());
+            let mut missing_services = Vec::new();
+            let mut missing_tasks = Vec::new();
+
+            for (service_id, task_ids) in services {
+                if !lobby_server.has_service(*service_id) {
+                    missing_services.push(format!("{service_id:?}"));
+                    continue;
+                }
+
+                missing_tasks.extend(
+                    task_ids
+                        .iter()
+                        .filter(|task_id| !is_known_task(*service_id, **task_id))
+                        .map(|task_id| format!("{service_id:?}/{task_id}")),
+                );
+            }
+
+            if missing_services.is_empty() && missing_tasks.is_empty() {
+                info!("All services and tasks known to be used by {title:?} are available");
+                continue;
+            }
+            if !missing_services.is_empty() {
+                warn!(
+                    "Services used by {title:?} that are not available: {}",
+                    missing_services.join(", ")
+                );
+            }
+            if !missing_tasks.is_empty() {
+                warn!(
+                    "Tasks used by {title:?} that are unknown: {}",
+                    missing_tasks.join(", ")
+                );
+            }
+        }
+    }
+}
+
+/// Tags calls of tasks the wrapped handler does not know with whether the title of the client
+/// is expected to call them.
+pub struct ManifestTaggingHandler {
+    service_id: LobbyServiceId,
+    handler: Arc<ThreadSafeLobbyHandler>,
+    manifests: Arc<TitleManifests>,
+}
+
+impl ManifestTaggingHandler {
+    pub fn new(
+        service_id: LobbyServiceId,
+        handler: Arc<ThreadSafeLobbyHandler>,
+        manifests: Arc<TitleManifests>,
+    ) -> ManifestTaggingHandler {
+        ManifestTaggingHandler {
+            service_id,
+            handler,
+            manifests,
+        }
+    }
+
+    fn tag_unknown_task(&self, session: &BdSession, message: &BdMessage) {
+        let Some(authentication) = session.authentication() else {
+            return;
+        };
+        let Ok(request) = message.reader.remaining_data() else {
+            return;
+        };
+
+        let mut task_id_reader = BdReader::new(request.to_vec());
+        task_id_reader.set_type_checked(message.reader.type_checked());
+        let Ok(task_id) = task_id_reader.read_u8() else {
+            return;
+        };
+        if is_known_task(self.service_id, task_id) {
+            return;
+        }
+
+        let expectation =
+            self.manifests
+                .task_expectation(authentication.title, self.service_id, task_id);
+        warn!(
+            "Unknown task {task_id} of {:?} called by {:?} (manifest={expectation:?})",
+            self.service_id, authentication.title
+        );
+    }
+}
+
+impl LobbyHandler for ManifestTaggingHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        self.tag_unknown_task(session, &message);
+
+        self.handler.handle_message(session, message)
+    }
+
+    fn requires_authentication(&self) -> bool {
+        self.handler.requires_authentication()
+    }
+}
+
+pub fn parse_service_id(name: &str) -> Option<LobbyServiceId> {
+    (0..=u8::MAX)
+        .filter_map(LobbyServiceId::from_u8)
+        .find(|service_id| format!("{service_id:?}") == name)
+}
+
+fn parse_title(name: &str) -> Option<Title> {
+    [
+        Title::Iw5,
+        Title::T5,
+        Title::T6Xenon,
+        Title::T6Ps3,
+        Title::T6Pc,
+        Title::T6WiiU,
+    ]
+    .into_iter()
+    .find(|title| format!("{title:?}") == name)
+}
+
+/// Whether the server knows the task of the service, regardless of whether it is implemented.
+fn is_known_task(service_id: LobbyServiceId, task_id: u8) -> bool {
+    match service_id {
+        LobbyServiceId::Anticheat => AntiCheatTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::BandwidthTest => BandwidthTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::ContentStreaming => ContentStreamingTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Counter => CounterTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Dml => DmlTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::EventLog => EventLogTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Facebook => FacebookTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Group => GroupTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::KeyArchive => KeyArchiveTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::League => LeagueTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::LinkCode => LinkCodeTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Mail => MailTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Matchmaking => MatchmakingTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Messaging | LobbyServiceId::Messaging2 => {
+            Messaging2TaskId::from_u8(task_id).is_some()
+        }
+        LobbyServiceId::Profile => ProfileTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::RichPresence => RichPresenceTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Storage => StorageTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::TitleUtilities => TitleUtilitiesTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Twitch => TwitchTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Twitter => TwitterTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::VoteRank => VoteRankTaskId::from_u8(task_id).is_some(),
+        LobbyServiceId::Youtube => YoutubeTaskId::from_u8(task_id).is_some(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_manifests_are_valid() {
+        let manifests = TitleManifests::load();
+
+        assert_eq!(
+            manifests.task_expectation(Title::T6Pc, LobbyServiceId::Storage, 1),
+            TaskExpectation::Expected
+        );
+        assert_eq!(
+            manifests.task_expectation(Title::T6Pc, LobbyServiceId::Storage, 200),
+            TaskExpectation::Unexpected
+        );
+        assert_eq!(
+            manifests.task_expectation(Title::Iw5, LobbyServiceId::Storage, 1),
+            TaskExpectation::NoManifest
+        );
+    }
+}
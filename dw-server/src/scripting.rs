@@ -0,0 +1,314 @@
+use crate::config::TaskScriptConfig;
+use crate::manifest::parse_service_id;
+use bitdemon::lobby::{LobbyHandler, LobbyServiceId, TaskReply, ThreadSafeLobbyHandler};
+use bitdemon::messaging::bd_message::BdMessage;
+use bitdemon::messaging::bd_reader::BdReader;
+use bitdemon::messaging::bd_response::{BdResponse, ResponseCreator};
+use bitdemon::messaging::bd_serialization::BdSerialize;
+use bitdemon::messaging::bd_writer::BdWriter;
+use bitdemon::messaging::BdErrorCode;
+use bitdemon::networking::bd_session::BdSession;
+use log::{debug, info, warn};
+use num_traits::{FromPrimitive, ToPrimitive};
+use rhai::{Array, Blob, Dynamic, Engine, Scope, AST};
+use snafu::Snafu;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+/// Scripts are aborted after this amount of operations so that they cannot stall a session.
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+#[derive(Debug, Snafu)]
+pub enum TaskScriptError {
+    #[snafu(display("The service of a task script is unknown (service={service})"))]
+    UnknownService { service: String },
+    #[snafu(display("The task script {path} could not be compiled: {message}"))]
+    Compilation { path: String, message: String },
+    #[snafu(display("The task script of task {task_id} failed: {message}"))]
+    Evaluation { task_id: u8, message: String },
+    #[snafu(display("The task script of task {task_id} returned an unsupported value: {value}"))]
+    UnsupportedReturnValue { task_id: u8, value: String },
+}
+
+/// A value of a task result crafted by a script.
+/// Scripts create them with the `bd_*` functions, e.g. `bd_u32(5)`.
+#[derive(Clone, Debug)]
+enum ScriptValue {
+    Bool(bool),
+    U8(u8),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    Str(String),
+    Blob(Vec<u8>),
+}
+
+/// A task result crafted by a script, consisting of the values in the order they are written.
+struct ScriptResult {
+    values: Vec<ScriptValue>,
+}
+
+impl BdSerialize for ScriptResult {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        for value in &self.values {
+            match value {
+                ScriptValue::Bool(value) => writer.write_bool(*value)?,
+                ScriptValue::U8(value) => writer.write_u8(*value)?,
+                ScriptValue::I32(value) => writer.write_i32(*value)?,
+                ScriptValue::U32(value) => writer.write_u32(*value)?,
+                ScriptValue::I64(value) => writer.write_i64(*value)?,
+                ScriptValue::U64(value) => writer.write_u64(*value)?,
+                ScriptValue::F32(value) => writer.write_f32(*value)?,
+                ScriptValue::Str(value) => writer.write_str(value)?,
+                ScriptValue::Blob(value) => writer.write_blob(value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rhai scripts that operators configured to intercept single tasks of lobby services.
+///
+/// Each script is evaluated with the following variables in scope:
+/// `title`, `user_id`, `username`, `task_id` and `request`,
+/// the latter being the blob of the request following the task id.
+///
+/// The value the script evaluates to determines the response:
+/// * `()` passes the request on to the regular handler of the service
+/// * an integer answers with only that error code
+/// * an array of results, each being an array of `bd_*` values, answers with those results
+pub struct TaskScripts {
+    engine: Engine,
+    scripts: HashMap<LobbyServiceId, HashMap<u8, AST>>,
+}
+
+impl TaskScripts {
+    pub fn load(configs: &[TaskScriptConfig]) -> Result<TaskScripts, TaskScriptError> {
+        let engine = Self::create_engine();
+
+        let mut scripts: HashMap<LobbyServiceId, HashMap<u8, AST>> = HashMap::new();
+        for config in configs {
+            let service_id = parse_service_id(&config.service).ok_or_else(|| {
+                UnknownServiceSnafu {
+                    service: config.service.clone(),
+                }
+                .build()
+            })?;
+            let ast = engine
+                .compile_file(config.path.clone().into())
+                .map_err(|e| {
+                    CompilationSnafu {
+                        path: config.path.clone(),
+                        message: e.to_string(),
+                    }
+                    .build()
+                })?;
+
+            info!(
+                "Overriding task {} of {service_id:?} with script {}",
+                config.task_id, config.path
+            );
+            scripts
+                .entry(service_id)
+                .or_default()
+                .insert(config.task_id, ast);
+        }
+
+        Ok(TaskScripts { engine, scripts })
+    }
+
+    /// Wraps the handler of a service if any of its tasks are overridden by scripts.
+    pub fn wrap(
+        self: &Arc<Self>,
+        service_id: LobbyServiceId,
+        handler: Arc<ThreadSafeLobbyHandler>,
+    ) -> Arc<ThreadSafeLobbyHandler> {
+        if !self.scripts.contains_key(&service_id) {
+            return handler;
+        }
+
+        Arc::new(ScriptedLobbyHandler {
+            service_id,
+            handler,
+            scripts: self.clone(),
+        })
+    }
+
+    fn create_engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+        engine.register_type_with_name::<ScriptValue>("BdValue");
+        engine.register_fn("bd_bool", ScriptValue::Bool);
+        engine.register_fn("bd_u8", |value: i64| ScriptValue::U8(value as u8));
+        engine.register_fn("bd_i32", |value: i64| ScriptValue::I32(value as i32));
+        engine.register_fn("bd_u32", |value: i64| ScriptValue::U32(value as u32));
+        engine.register_fn("bd_i64", ScriptValue::I64);
+        engine.register_fn("bd_u64", |value: i64| ScriptValue::U64(value as u64));
+        engine.register_fn("bd_f32", |value: f64| ScriptValue::F32(value as f32));
+        engine.register_fn("bd_str", |value: &str| ScriptValue::Str(value.to_string()));
+        engine.register_fn("bd_blob", ScriptValue::Blob);
+
+        engine
+    }
+
+    fn run(
+        &self,
+        session: &BdSession,
+        ast: &AST,
+        task_id: u8,
+        request: Blob,
+    ) -> Result<Option<BdResponse>, Box<dyn Error>> {
+        let authentication = session.authentication();
+        let mut scope = Scope::new();
+        scope.push_constant(
+            "title",
+            authentication.map_or(0, |a| a.title.to_u32().unwrap() as i64),
+        );
+        scope.push_constant("user_id", authentication.map_or(0, |a| a.user_id as i64));
+        scope.push_constant(
+            "username",
+            authentication.map_or(String::new(), |a| a.username.clone()),
+        );
+        scope.push_constant("task_id", task_id as i64);
+        scope.push_constant("request", request);
+
+        let value = self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+            .map_err(|e| {
+                EvaluationSnafu {
+                    task_id,
+                    message: e.to_string(),
+                }
+                .build()
+            })?;
+
+        if value.is_unit() {
+            return Ok(None);
+        }
+        if let Ok(error_code) = value.as_int() {
+            let error_code = u32::try_from(error_code)
+                .ok()
+                .and_then(BdErrorCode::from_u32)
+                .ok_or_else(|| Self::unsupported(task_id, &value))?;
+            return Ok(Some(
+                TaskReply::with_only_error_code(error_code, task_id).to_response()?,
+            ));
+        }
+
+        let results = value
+            .clone()
+            .try_cast::<Array>()
+            .ok_or_else(|| Self::unsupported(task_id, &value))?
+            .into_iter()
+            .map(|result| {
+                let values = result
+                    .try_cast::<Array>()
+                    .ok_or_else(|| Self::unsupported(task_id, &value))?
+                    .into_iter()
+                    .map(|value| {
+                        value
+                            .try_cast::<ScriptValue>()
+                            .ok_or_else(|| Self::unsupported(task_id, &Dynamic::UNIT))
+                    })
+                    .collect::<Result<Vec<ScriptValue>, TaskScriptError>>()?;
+
+                Ok(Box::new(ScriptResult { values }) as Box<dyn BdSerialize>)
+            })
+            .collect::<Result<Vec<Box<dyn BdSerialize>>, TaskScriptError>>()?;
+
+        Ok(Some(
+            TaskReply::with_results(task_id, results).to_response()?,
+        ))
+    }
+
+    fn unsupported(task_id: u8, value: &Dynamic) -> TaskScriptError {
+        UnsupportedReturnValueSnafu {
+            task_id,
+            value: value.type_name().to_string(),
+        }
+        .build()
+    }
+}
+
+/// Answers tasks with scripts if there is one for the task and passes all others to the
+/// wrapped handler.
+struct ScriptedLobbyHandler {
+    service_id: LobbyServiceId,
+    handler: Arc<ThreadSafeLobbyHandler>,
+    scripts: Arc<TaskScripts>,
+}
+
+impl LobbyHandler for ScriptedLobbyHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let request = message.reader.remaining_data()?.to_vec();
+        let mut task_id_reader = BdReader::new(request);
+        task_id_reader.set_type_checked(message.reader.type_checked());
+        let task_id = task_id_reader.read_u8()?;
+
+        let maybe_ast = self
+            .scripts
+            .scripts
+            .get(&self.service_id)
+            .and_then(|tasks| tasks.get(&task_id));
+        if let Some(ast) = maybe_ast {
+            let parameters = task_id_reader.remaining_data()?.to_vec();
+            match self.scripts.run(session, ast, task_id, parameters) {
+                Ok(Some(response)) => {
+                    debug!(
+                        "Answered task {task_id} of {:?} with script",
+                        self.service_id
+                    );
+                    return Ok(response);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("{e}"),
+            }
+        }
+
+        self.handler.handle_message(session, message)
+    }
+
+    fn requires_authentication(&self) -> bool {
+        self.handler.requires_authentication()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+
+    fn run_script(script: &str) -> Result<Option<BdResponse>, Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let session = BdSession::new(TcpStream::connect(listener.local_addr().unwrap()).unwrap());
+        let scripts = TaskScripts {
+            engine: TaskScripts::create_engine(),
+            scripts: HashMap::new(),
+        };
+        let ast = scripts.engine.compile(script).unwrap();
+
+        scripts.run(&session, &ast, 6, Vec::new())
+    }
+
+    #[test]
+    fn script_return_values_determine_response() {
+        assert!(run_script("()").unwrap().is_none());
+        assert!(run_script("if task_id == 6 { 0 }").unwrap().is_some());
+        assert!(
+            run_script("[[bd_u32(5), bd_str(\"hello\")], [bd_bool(true)]]")
+                .unwrap()
+                .is_some()
+        );
+        assert!(run_script("\"unsupported\"").is_err());
+        assert!(run_script("loop {}").is_err());
+    }
+}
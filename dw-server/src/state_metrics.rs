@@ -0,0 +1,62 @@
+use bitdemon_backend_sqlite::metrics::{collect_db_metrics, DbMetrics};
+use chrono::Utc;
+use log::{info, warn};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The state of the persisted data at a point in time.
+#[derive(Clone, Serialize)]
+pub struct StateMetricsSnapshot {
+    /// The seconds timestamp of when the databases were measured.
+    pub collected_at: i64,
+    pub databases: Vec<DbMetrics>,
+}
+
+/// Keeps the most recent measurement of the databases,
+/// so operators can follow how the persisted data grows.
+#[derive(Default)]
+pub struct StateMetrics {
+    latest: RwLock<Option<StateMetricsSnapshot>>,
+}
+
+impl StateMetrics {
+    pub fn latest(&self) -> Option<StateMetricsSnapshot> {
+        self.latest.read().unwrap().clone()
+    }
+
+    /// Measures the databases in the background every interval.
+    pub fn spawn_collector(self: &Arc<Self>, interval: Duration) {
+        let state_metrics = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+
+                match tokio::task::spawn_blocking(collect_db_metrics).await {
+                    Ok(databases) => state_metrics.record(databases),
+                    Err(e) => warn!("Failed to collect database metrics: {e}"),
+                }
+            }
+        });
+    }
+
+    fn record(&self, databases: Vec<DbMetrics>) {
+        let total_size: u64 = databases.iter().map(|db| db.file_size).sum();
+        let total_rows: u64 = databases
+            .iter()
+            .flat_map(|db| db.tables.iter())
+            .map(|table| table.row_count)
+            .sum();
+        info!(
+            "Databases hold {total_rows} rows in {total_size} bytes across {} files",
+            databases.len()
+        );
+
+        *self.latest.write().unwrap() = Some(StateMetricsSnapshot {
+            collected_at: Utc::now().timestamp(),
+            databases,
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use serde_json::Value;
+use snafu::{ensure, Snafu};
+use std::error::Error;
+use ureq::http::StatusCode;
+use ureq::Agent;
+
+#[derive(Debug, Snafu)]
+enum AdminClientError {
+    #[snafu(display("The server rejected the admin token"))]
+    Unauthorized,
+    #[snafu(display("The requested entry does not exist"))]
+    NotFound,
+    #[snafu(display("The server answered with status {status}: {body}"))]
+    UnexpectedStatus { status: u16, body: String },
+}
+
+/// Talks to the admin api of a dw-server.
+pub struct AdminClient {
+    agent: Agent,
+    base_url: String,
+    authorization: String,
+}
+
+impl AdminClient {
+    pub fn new(base_url: &str, token: &str) -> AdminClient {
+        let agent = Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .into();
+
+        AdminClient {
+            agent,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            authorization: format!("Bearer {token}"),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Result<Option<Value>, Box<dyn Error>> {
+        let response = self
+            .agent
+            .get(self.url(path))
+            .header("Authorization", self.authorization.as_str())
+            .call()?;
+
+        Self::parse_response(response)
+    }
+
+    pub fn post(&self, path: &str) -> Result<Option<Value>, Box<dyn Error>> {
+        let response = self
+            .agent
+            .post(self.url(path))
+            .header("Authorization", self.authorization.as_str())
+            .send_empty()?;
+
+        Self::parse_response(response)
+    }
+
+    pub fn put(&self, path: &str, body: &Value) -> Result<Option<Value>, Box<dyn Error>> {
+        let response = self
+            .agent
+            .put(self.url(path))
+            .header("Authorization", self.authorization.as_str())
+            .header("Content-Type", "application/json")
+            .send(body.to_string())?;
+
+        Self::parse_response(response)
+    }
+
+    pub fn delete(&self, path: &str) -> Result<Option<Value>, Box<dyn Error>> {
+        let response = self
+            .agent
+            .delete(self.url(path))
+            .header("Authorization", self.authorization.as_str())
+            .call()?;
+
+        Self::parse_response(response)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    fn parse_response(
+        mut response: ureq::http::Response<ureq::Body>,
+    ) -> Result<Option<Value>, Box<dyn Error>> {
+        let status = response.status();
+        ensure!(status != StatusCode::UNAUTHORIZED, UnauthorizedSnafu);
+        ensure!(status != StatusCode::NOT_FOUND, NotFoundSnafu);
+
+        let body = response.body_mut().read_to_string()?;
+        ensure!(
+            status.is_success(),
+            UnexpectedStatusSnafu {
+                status: status.as_u16(),
+                body
+            }
+        );
+
+        if body.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_json::from_str(body.as_str())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Answers a single request with the given status line and body.
+    /// Returns the address to send the request to and the head of the request once it arrived.
+    fn respond_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(line.as_str());
+            }
+
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+
+            head
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn json_responses_are_parsed() {
+        let (url, server) = respond_once("200 OK", r#"[{"user_id":42}]"#);
+
+        let result = AdminClient::new(url.as_str(), "token")
+            .get("/admin/bans")
+            .unwrap();
+
+        assert_eq!(result, Some(json!([{ "user_id": 42 }])));
+
+        let head = server.join().unwrap();
+        assert!(head.starts_with("GET /admin/bans HTTP/1.1\r\n"));
+        assert!(head
+            .to_ascii_lowercase()
+            .contains("authorization: bearer token\r\n"));
+    }
+
+    #[test]
+    fn empty_responses_have_no_value() {
+        let (url, server) = respond_once("204 No Content", "");
+
+        let result = AdminClient::new(url.as_str(), "token")
+            .delete("/admin/bans/42")
+            .unwrap();
+
+        assert_eq!(result, None);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn rejected_tokens_are_reported() {
+        let (url, server) = respond_once("401 Unauthorized", "");
+
+        let error = AdminClient::new(url.as_str(), "token")
+            .get("/admin/bans")
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "The server rejected the admin token");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn unexpected_statuses_include_the_body() {
+        let (url, server) = respond_once("400 Bad Request", "Invalid config");
+
+        let error = AdminClient::new(url.as_str(), "token")
+            .post("/admin/config/reload")
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The server answered with status 400: Invalid config"
+        );
+        server.join().unwrap();
+    }
+}
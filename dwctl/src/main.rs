@@ -0,0 +1,165 @@
+mod client;
+mod output;
+
+use crate::client::AdminClient;
+use crate::output::{print_json, print_table, Column};
+use serde_json::{json, Value};
+use std::env;
+use std::error::Error;
+use std::process::exit;
+
+const DEFAULT_URL: &str = "http://127.0.0.1:3077";
+const TOKEN_ENV_VAR: &str = "DWCTL_TOKEN";
+
+const USAGE: &str = "Usage: dwctl [--url <url>] [--token <token>] [--json] <command> [<args>]
+
+Commands:
+  sessions                    List connected lobby sessions
+  kick <session-id>           Close the connection of a session
+  bans                        List banned users
+  ban <user-id> [<reason>]    Ban a user and close all of their sessions
+  unban <user-id>             Lift the ban of a user
+  reload-config               Reload runtime settings from the config of the server
+  invalidate-publisher-cache  Discard cached publisher file listings
+  quota <user-id>             Show the storage used by a user
+
+The token can also be specified with the DWCTL_TOKEN environment variable.";
+
+const SESSION_COLUMNS: [Column; 6] = [
+    Column::new("ID", "/id"),
+    Column::new("PEER", "/peer_addr"),
+    Column::new("CONNECTED AT", "/connected_at"),
+    Column::new("USER ID", "/user/user_id"),
+    Column::new("USERNAME", "/user/username"),
+    Column::new("TITLE", "/user/title_name"),
+];
+
+const BAN_COLUMNS: [Column; 3] = [
+    Column::new("USER ID", "/user_id"),
+    Column::new("BANNED AT", "/banned_at"),
+    Column::new("REASON", "/reason"),
+];
+
+const BAN_RESULT_COLUMNS: [Column; 2] = [
+    Column::new("USER ID", "/user_id"),
+    Column::new("KICKED SESSIONS", "/kicked_sessions"),
+];
+
+const CLEARED_CACHES_COLUMNS: [Column; 1] = [Column::new("CLEARED CACHES", "/cleared_caches")];
+
+const QUOTA_COLUMNS: [Column; 4] = [
+    Column::new("TITLE", "/title"),
+    Column::new("FILES", "/file_count"),
+    Column::new("TOTAL SIZE", "/total_size"),
+    Column::new("MAX FILE SIZE", "/max_file_size"),
+];
+
+struct DwCtlArgs {
+    url: String,
+    token: String,
+    json: bool,
+    command: Vec<String>,
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|message| {
+        eprintln!("{message}");
+        eprintln!("{USAGE}");
+        exit(1);
+    });
+
+    let client = AdminClient::new(args.url.as_str(), args.token.as_str());
+
+    if let Err(e) = run_command(&client, &args) {
+        eprintln!("{e}");
+        exit(2);
+    }
+}
+
+fn run_command(client: &AdminClient, args: &DwCtlArgs) -> Result<(), Box<dyn Error>> {
+    let command: Vec<&str> = args.command.iter().map(String::as_str).collect();
+
+    let (result, columns): (Option<Value>, &[Column]) = match command.as_slice() {
+        ["sessions"] => (client.get("/admin/sessions")?, &SESSION_COLUMNS),
+        ["kick", session_id] => (
+            client.post(format!("/admin/sessions/{}/kick", parse_id(session_id)?).as_str())?,
+            &[],
+        ),
+        ["bans"] => (client.get("/admin/bans")?, &BAN_COLUMNS),
+        ["ban", user_id, reason @ ..] => (
+            client.put(
+                format!("/admin/bans/{}", parse_id(user_id)?).as_str(),
+                &json!({ "reason": reason.join(" ") }),
+            )?,
+            &BAN_RESULT_COLUMNS,
+        ),
+        ["unban", user_id] => (
+            client.delete(format!("/admin/bans/{}", parse_id(user_id)?).as_str())?,
+            &[],
+        ),
+        ["reload-config"] => (client.post("/admin/config/reload")?, &[]),
+        ["invalidate-publisher-cache"] => (
+            client.post("/admin/caches/publisher/invalidate")?,
+            &CLEARED_CACHES_COLUMNS,
+        ),
+        ["quota", user_id] => (
+            client.get(format!("/admin/users/{}/quota", parse_id(user_id)?).as_str())?,
+            &QUOTA_COLUMNS,
+        ),
+        _ => return Err(format!("Unknown command: {}\n{USAGE}", command.join(" ")).into()),
+    };
+
+    match result {
+        Some(value) if args.json => print_json(&value),
+        Some(value) => print_table(&value, columns),
+        None if !args.json => println!("OK"),
+        None => {}
+    }
+
+    Ok(())
+}
+
+fn parse_id(value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("Invalid id: {value}"))
+}
+
+fn parse_args() -> Result<DwCtlArgs, String> {
+    let mut args = DwCtlArgs {
+        url: DEFAULT_URL.to_string(),
+        token: env::var(TOKEN_ENV_VAR).unwrap_or_default(),
+        json: false,
+        command: Vec::new(),
+    };
+
+    let mut input = env::args().skip(1);
+    while let Some(arg) = input.next() {
+        if !args.command.is_empty() {
+            args.command.push(arg);
+            continue;
+        }
+
+        match arg.as_str() {
+            "--url" => args.url = input.next().ok_or("Missing value for --url")?,
+            "--token" => args.token = input.next().ok_or("Missing value for --token")?,
+            "--json" => args.json = true,
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                exit(0);
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown argument: {flag}")),
+            _ => args.command.push(arg),
+        }
+    }
+
+    if args.command.is_empty() {
+        return Err("No command specified".to_string());
+    }
+
+    if args.token.is_empty() {
+        return Err(format!(
+            "No admin token specified, use --token or {TOKEN_ENV_VAR}"
+        ));
+    }
+
+    Ok(args)
+}
@@ -0,0 +1,127 @@
+use serde_json::Value;
+use std::iter::once;
+
+/// A column of a printed table.
+/// The path points to the displayed value within each row using JSON pointer syntax.
+pub struct Column {
+    pub header: &'static str,
+    pub path: &'static str,
+}
+
+impl Column {
+    pub const fn new(header: &'static str, path: &'static str) -> Column {
+        Column { header, path }
+    }
+}
+
+pub fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    );
+}
+
+/// Prints an array of objects as a table.
+/// A single object is printed as a table with one row.
+pub fn print_table(value: &Value, columns: &[Column]) {
+    format_table(value, columns)
+        .iter()
+        .for_each(|line| println!("{line}"));
+}
+
+fn format_table(value: &Value, columns: &[Column]) -> Vec<String> {
+    let rows: Vec<&Value> = match value {
+        Value::Array(rows) => rows.iter().collect(),
+        row => vec![row],
+    };
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| format_cell(row.pointer(column.path)))
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            cells
+                .iter()
+                .map(|row| row[index].len())
+                .chain([column.header.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let headers: Vec<String> = columns
+        .iter()
+        .map(|column| column.header.to_string())
+        .collect();
+
+    once(&headers)
+        .chain(&cells)
+        .map(|row| format_row(row, &widths))
+        .collect()
+}
+
+fn format_row(cells: &[String], widths: &[usize]) -> String {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect();
+
+    line.join("  ").trim_end().to_string()
+}
+
+fn format_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const COLUMNS: [Column; 3] = [
+        Column::new("USER ID", "/user_id"),
+        Column::new("NAME", "/user/name"),
+        Column::new("REASON", "/reason"),
+    ];
+
+    #[test]
+    fn columns_are_as_wide_as_their_widest_cell() {
+        let value = json!([
+            { "user_id": 1, "user": { "name": "somebody" }, "reason": "cheating" },
+            { "user_id": 123456789, "user": { "name": "x" }, "reason": null },
+        ]);
+
+        assert_eq!(
+            format_table(&value, &COLUMNS),
+            vec![
+                "USER ID    NAME      REASON",
+                "1          somebody  cheating",
+                "123456789  x         -",
+            ]
+        );
+    }
+
+    #[test]
+    fn single_object_is_formatted_as_one_row() {
+        let value = json!({ "user_id": 7, "reason": "spam" });
+
+        assert_eq!(
+            format_table(&value, &COLUMNS),
+            vec!["USER ID  NAME  REASON", "7        -     spam"]
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Adds the storage service to a lobby server, keeping user files in memory.
+//!
+//! Backends implement the service traits of `bitdemon::lobby` and hand them to the
+//! matching handler, which takes care of the protocol.
+
+use bitdemon::auth::auth_handler::steam::SteamAuthHandler;
+use bitdemon::auth::auth_handler::AuthMessageType;
+use bitdemon::auth::auth_server::AuthServer;
+use bitdemon::auth::key_store::InMemoryKeyStore;
+use bitdemon::domain::result_slice::ResultSlice;
+use bitdemon::lobby::storage::{
+    FileVisibility, PublisherStorageService, StorageFileInfo, StorageHandler, StorageServiceError,
+    UserStorageService,
+};
+use bitdemon::lobby::{LobbyServer, LobbyServiceId};
+use bitdemon::networking::bd_session::BdSession;
+use bitdemon::networking::bd_socket::BdSocket;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+const AUTH_PORT: u16 = 3075;
+const LOBBY_PORT: u16 = 3074;
+
+struct StoredFile {
+    info: StorageFileInfo,
+    data: Vec<u8>,
+}
+
+/// Keeps the files of all users until the server stops.
+#[derive(Default)]
+struct InMemoryStorageService {
+    files: Mutex<Vec<StoredFile>>,
+}
+
+impl InMemoryStorageService {
+    fn read_file(
+        &self,
+        session: &BdSession,
+        predicate: impl Fn(&StorageFileInfo) -> bool,
+    ) -> Result<Vec<u8>, StorageServiceError> {
+        let user_id = session.authentication().unwrap().user_id;
+        let files = self.files.lock().unwrap();
+        let file = files
+            .iter()
+            .find(|file| predicate(&file.info))
+            .ok_or(StorageServiceError::StorageFileNotFoundError)?;
+
+        if file.info.visibility == FileVisibility::VisiblePrivate && file.info.owner_id != user_id {
+            return Err(StorageServiceError::PermissionDeniedError);
+        }
+
+        Ok(file.data.clone())
+    }
+
+    fn list_files(
+        &self,
+        owner_id: u64,
+        min_date_time: i64,
+        item_offset: usize,
+        item_count: usize,
+        filter: Option<&str>,
+    ) -> ResultSlice<StorageFileInfo> {
+        let infos: Vec<StorageFileInfo> = self
+            .files
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|file| &file.info)
+            .filter(|info| info.owner_id == owner_id && info.modified >= min_date_time)
+            .filter(|info| filter.is_none_or(|filter| info.filename.starts_with(filter)))
+            .cloned()
+            .collect();
+        let total_count = infos.len();
+
+        ResultSlice::with_total_count(
+            infos
+                .into_iter()
+                .skip(item_offset)
+                .take(item_count)
+                .collect(),
+            item_offset,
+            total_count,
+        )
+    }
+}
+
+impl UserStorageService for InMemoryStorageService {
+    fn get_storage_file_data_by_id(
+        &self,
+        session: &BdSession,
+        owner_id: u64,
+        file_id: u64,
+    ) -> Result<Vec<u8>, StorageServiceError> {
+        self.read_file(session, |info| {
+            info.owner_id == owner_id && info.id == file_id
+        })
+    }
+
+    fn get_storage_file_data_by_name(
+        &self,
+        session: &BdSession,
+        owner_id: u64,
+        filename: String,
+    ) -> Result<Vec<u8>, StorageServiceError> {
+        self.read_file(session, |info| {
+            info.owner_id == owner_id && info.filename == filename
+        })
+    }
+
+    fn list_storage_files(
+        &self,
+        _session: &BdSession,
+        owner_id: u64,
+        min_date_time: i64,
+        item_offset: usize,
+        item_count: usize,
+    ) -> Result<ResultSlice<StorageFileInfo>, StorageServiceError> {
+        Ok(self.list_files(owner_id, min_date_time, item_offset, item_count, None))
+    }
+
+    fn filter_storage_files(
+        &self,
+        _session: &BdSession,
+        owner_id: u64,
+        min_date_time: i64,
+        item_offset: usize,
+        item_count: usize,
+        filter: String,
+    ) -> Result<ResultSlice<StorageFileInfo>, StorageServiceError> {
+        Ok(self.list_files(
+            owner_id,
+            min_date_time,
+            item_offset,
+            item_count,
+            Some(&filter),
+        ))
+    }
+
+    fn create_storage_file(
+        &self,
+        session: &BdSession,
+        owner_id: u64,
+        filename: String,
+        visibility: FileVisibility,
+        file_data: Vec<u8>,
+    ) -> Result<StorageFileInfo, StorageServiceError> {
+        let authentication = session.authentication().unwrap();
+        if authentication.user_id != owner_id {
+            return Err(StorageServiceError::PermissionDeniedError);
+        }
+
+        let mut files = self.files.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let info = StorageFileInfo {
+            id: files.len() as u64 + 1,
+            filename,
+            title: authentication.title,
+            file_size: file_data.len() as u64,
+            created: now,
+            modified: now,
+            visibility,
+            owner_id,
+        };
+        files.push(StoredFile {
+            info: info.clone(),
+            data: file_data,
+        });
+
+        Ok(info)
+    }
+
+    fn update_storage_file_data(
+        &self,
+        session: &BdSession,
+        owner_id: u64,
+        file_id: u64,
+        file_data: Vec<u8>,
+    ) -> Result<(), StorageServiceError> {
+        if session.authentication().unwrap().user_id != owner_id {
+            return Err(StorageServiceError::PermissionDeniedError);
+        }
+
+        let mut files = self.files.lock().unwrap();
+        let file = files
+            .iter_mut()
+            .find(|file| file.info.owner_id == owner_id && file.info.id == file_id)
+            .ok_or(StorageServiceError::StorageFileNotFoundError)?;
+        file.info.file_size = file_data.len() as u64;
+        file.info.modified = chrono::Utc::now().timestamp();
+        file.data = file_data;
+
+        Ok(())
+    }
+
+    fn remove_storage_file(
+        &self,
+        session: &BdSession,
+        owner_id: u64,
+        filename: String,
+    ) -> Result<(), StorageServiceError> {
+        if session.authentication().unwrap().user_id != owner_id {
+            return Err(StorageServiceError::PermissionDeniedError);
+        }
+
+        let mut files = self.files.lock().unwrap();
+        let file_count = files.len();
+        files.retain(|file| file.info.owner_id != owner_id || file.info.filename != filename);
+        if files.len() == file_count {
+            return Err(StorageServiceError::StorageFileNotFoundError);
+        }
+
+        Ok(())
+    }
+}
+
+/// A backend without any publisher files.
+struct NoPublisherFiles;
+
+impl PublisherStorageService for NoPublisherFiles {
+    fn get_publisher_file_data(
+        &self,
+        _session: &BdSession,
+        _filename: String,
+    ) -> Result<Vec<u8>, StorageServiceError> {
+        Err(StorageServiceError::StorageFileNotFoundError)
+    }
+
+    fn list_publisher_files(
+        &self,
+        _session: &BdSession,
+        _min_date_time: i64,
+        item_offset: usize,
+        _item_count: usize,
+    ) -> Result<ResultSlice<StorageFileInfo>, StorageServiceError> {
+        Ok(ResultSlice::new(Vec::new(), item_offset))
+    }
+
+    fn filter_publisher_files(
+        &self,
+        _session: &BdSession,
+        _min_date_time: i64,
+        item_offset: usize,
+        _item_count: usize,
+        _filter: String,
+    ) -> Result<ResultSlice<StorageFileInfo>, StorageServiceError> {
+        Ok(ResultSlice::new(Vec::new(), item_offset))
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let key_store = Arc::new(InMemoryKeyStore::new());
+    let auth_server = Arc::new(AuthServer::new());
+    auth_server.add_handler(
+        AuthMessageType::SteamForMmpRequest,
+        Arc::new(SteamAuthHandler::new(key_store.clone())),
+    );
+    let lobby_server = Arc::new(LobbyServer::new(key_store));
+
+    lobby_server.add_service(
+        LobbyServiceId::Storage,
+        Arc::new(StorageHandler::new(
+            Arc::new(InMemoryStorageService::default()),
+            Arc::new(NoPublisherFiles),
+        )),
+    );
+
+    let auth_join = BdSocket::new(AUTH_PORT)?.run_async(auth_server);
+    let lobby_join = BdSocket::new(LOBBY_PORT)?.run_async(lobby_server);
+
+    auth_join.join().unwrap()?;
+    lobby_join.join().unwrap()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitdemon::auth::authentication::SessionAuthentication;
+    use bitdemon::domain::title::Title;
+    use std::net::{TcpListener, TcpStream};
+
+    fn authenticated_session(user_id: u64) -> BdSession {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut session = BdSession::new(stream);
+        session.set_authentication(SessionAuthentication {
+            user_id,
+            username: format!("user{user_id}"),
+            session_key: [0; 24].into(),
+            title: Title::T6Pc,
+            clock_skew: None,
+            anonymous: false,
+        });
+
+        session
+    }
+
+    #[test]
+    fn private_files_are_only_readable_by_owner() {
+        let service = InMemoryStorageService::default();
+        let owner = authenticated_session(1);
+        let other = authenticated_session(2);
+
+        service
+            .create_storage_file(
+                &owner,
+                1,
+                "loadout".to_string(),
+                FileVisibility::VisiblePrivate,
+                vec![1, 2, 3],
+            )
+            .unwrap();
+
+        assert_eq!(
+            service
+                .get_storage_file_data_by_name(&owner, 1, "loadout".to_string())
+                .unwrap(),
+            vec![1, 2, 3]
+        );
+        assert!(matches!(
+            service.get_storage_file_data_by_name(&other, 1, "loadout".to_string()),
+            Err(StorageServiceError::PermissionDeniedError)
+        ));
+        assert_eq!(
+            service
+                .list_storage_files(&other, 1, 0, 0, 10)
+                .unwrap()
+                .total_count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Runs the auth and lobby servers with the services that need no backend.
+//!
+//! Clients can authenticate and connect to the lobby, but every service besides the
+//! lobby service itself replies that it is not available.
+//! See `custom_storage_backend.rs` for adding services.
+
+use bitdemon::auth::auth_handler::steam::SteamAuthHandler;
+use bitdemon::auth::auth_handler::AuthMessageType;
+use bitdemon::auth::auth_server::AuthServer;
+use bitdemon::auth::key_store::InMemoryKeyStore;
+use bitdemon::lobby::LobbyServer;
+use bitdemon::networking::bd_socket::BdSocket;
+use std::error::Error;
+use std::sync::Arc;
+
+const AUTH_PORT: u16 = 3075;
+const LOBBY_PORT: u16 = 3074;
+
+/// Creates both servers sharing the keys that auth proofs are encrypted with.
+fn create_servers() -> (Arc<AuthServer>, Arc<LobbyServer>) {
+    let key_store = Arc::new(InMemoryKeyStore::new());
+
+    let auth_server = Arc::new(AuthServer::new());
+    auth_server.add_handler(
+        AuthMessageType::SteamForMmpRequest,
+        Arc::new(SteamAuthHandler::new(key_store.clone())),
+    );
+    let lobby_server = Arc::new(LobbyServer::new(key_store));
+
+    (auth_server, lobby_server)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let (auth_server, lobby_server) = create_servers();
+
+    let mut auth_socket = BdSocket::new(AUTH_PORT)?;
+    let mut lobby_socket = BdSocket::new(LOBBY_PORT)?;
+
+    let auth_join = auth_socket.run_async(auth_server);
+    let lobby_join = lobby_socket.run_async(lobby_server);
+
+    auth_join.join().unwrap()?;
+    lobby_join.join().unwrap()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitdemon::lobby::LobbyServiceId;
+
+    #[test]
+    fn lobby_server_only_offers_lobby_service() {
+        let (_, lobby_server) = create_servers();
+
+        assert!(lobby_server.has_service(LobbyServiceId::LobbyService));
+        assert!(!lobby_server.has_service(LobbyServiceId::Storage));
+    }
+}
@@ -0,0 +1,82 @@
+//! Greets every client with a push message as soon as it connected to the lobby.
+//!
+//! Push messages can be sent from any thread through a detached handle of the session,
+//! e.g. when another user sends a mail.
+
+use bitdemon::auth::auth_handler::steam::SteamAuthHandler;
+use bitdemon::auth::auth_handler::AuthMessageType;
+use bitdemon::auth::auth_server::AuthServer;
+use bitdemon::auth::key_store::InMemoryKeyStore;
+use bitdemon::lobby::{LobbyServer, LobbyServiceId, PushMessage};
+use bitdemon::messaging::bd_response::ResponseCreator;
+use bitdemon::networking::bd_session::BdSession;
+use bitdemon::networking::bd_socket::BdSocket;
+use bitdemon::networking::session_manager::SessionManager;
+use log::warn;
+use std::error::Error;
+use std::sync::Arc;
+
+const AUTH_PORT: u16 = 3075;
+const LOBBY_PORT: u16 = 3074;
+
+/// Sends a message to the client of the session without it asking for one.
+fn greet(session: &BdSession) -> Result<(), Box<dyn Error>> {
+    let mut handle = session.detach()?;
+
+    PushMessage::new(LobbyServiceId::Messaging2, b"Welcome!".to_vec())
+        .to_response()?
+        .send(&mut handle)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let key_store = Arc::new(InMemoryKeyStore::new());
+    let auth_server = Arc::new(AuthServer::new());
+    auth_server.add_handler(
+        AuthMessageType::SteamForMmpRequest,
+        Arc::new(SteamAuthHandler::new(key_store.clone())),
+    );
+    let lobby_server = Arc::new(LobbyServer::new(key_store));
+
+    let session_manager = Arc::new(SessionManager::new());
+    session_manager.on_session_authenticated(|session| {
+        if let Err(e) = greet(session) {
+            warn!("Failed to greet session {}: {e}", session.id);
+        }
+    });
+
+    let auth_join = BdSocket::new(AUTH_PORT)?.run_async(auth_server);
+    let lobby_join =
+        BdSocket::new_with_session_manager(LOBBY_PORT, session_manager)?.run_async(lobby_server);
+
+    auth_join.join().unwrap()?;
+    lobby_join.join().unwrap()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn client_receives_push_message() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        greet(&BdSession::new(stream)).unwrap();
+
+        let mut length = [0u8; 4];
+        client.read_exact(&mut length).unwrap();
+        let mut frame = vec![0u8; u32::from_le_bytes(length) as usize];
+        client.read_exact(&mut frame).unwrap();
+
+        // Sessions that did not authenticate yet receive unencrypted messages
+        assert_eq!(frame[0], 0);
+        let message = PushMessage::read_frame(frame.split_off(1)).unwrap();
+        assert_eq!(message.service_id, LobbyServiceId::Messaging2);
+        assert_eq!(message.payload, b"Welcome!");
+    }
+}
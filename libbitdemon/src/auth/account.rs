@@ -0,0 +1,113 @@
+use crate::auth::identity::PlatformIdentity;
+
+/// The key that users of bitdemon accounts authenticate with.
+/// Clients derive it from the password, so the password itself never reaches the server.
+pub type UserKey = [u8; 24];
+
+/// The longest username an account can have.
+pub const MAX_USERNAME_LEN: usize = 63;
+
+/// A bitdemon account as it is persisted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BdAccount {
+    pub user_id: u64,
+    /// The username with the casing it was created with.
+    pub username: String,
+    pub user_key: UserKey,
+}
+
+/// Errors that may occur when managing bitdemon accounts.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum AccountStoreError {
+    /// There is no account with the specified username.
+    AccountNotFoundError,
+    /// Another account already uses the username.
+    UsernameExistsError,
+    /// The username is empty, too long or contains characters that are not allowed.
+    UsernameIllegalError,
+    /// The user key does not match the one of the account.
+    IncorrectUserKeyError,
+    /// The license code does not match the one the account was created with.
+    IncorrectLicenseCodeError,
+}
+
+pub type ThreadSafeAccountStore = dyn AccountStore + Sync + Send;
+
+/// Persists bitdemon accounts, which users log in to with a username and user key
+/// instead of a ticket of an external platform.
+pub trait AccountStore {
+    /// Creates a new account and returns its user id.
+    /// The license code may be empty, but accounts without one cannot be reset.
+    fn create_account(
+        &self,
+        username: &str,
+        user_key: &UserKey,
+        license_code: &str,
+    ) -> Result<u64, AccountStoreError>;
+
+    /// Replaces the user key of an account after checking its current one.
+    fn change_user_key(
+        &self,
+        username: &str,
+        user_key: &UserKey,
+        new_user_key: &UserKey,
+    ) -> Result<(), AccountStoreError>;
+
+    /// Replaces the user key of an account whose user lost it,
+    /// proven by the license code the account was created with.
+    fn reset_account(
+        &self,
+        username: &str,
+        license_code: &str,
+        new_user_key: &UserKey,
+    ) -> Result<(), AccountStoreError>;
+
+    /// Deletes an account after checking its user key.
+    fn delete_account(&self, username: &str, user_key: &UserKey) -> Result<(), AccountStoreError>;
+
+    /// Moves an account to an identity of an external platform after checking its user key.
+    /// Afterward, the identity authenticates as the account, while its user key becomes invalid.
+    fn migrate_account(
+        &self,
+        username: &str,
+        user_key: &UserKey,
+        identity: &PlatformIdentity,
+    ) -> Result<(), AccountStoreError>;
+
+    /// Retrieves the account with the specified username.
+    fn find_account(&self, username: &str) -> Result<BdAccount, AccountStoreError>;
+
+    /// Retrieves the account that is mapped to a Call of Duty online id.
+    /// Ids that were not seen before get a new account with the specified username
+    /// and a random user key, since they never log in with a key.
+    /// A different username is picked when the specified one is already taken.
+    fn resolve_codo_account(
+        &self,
+        codo_id: u64,
+        username: &str,
+    ) -> Result<BdAccount, AccountStoreError>;
+}
+
+/// Checks whether a username can be used for a new account.
+/// Only printable ASCII characters without leading or trailing whitespace are allowed.
+pub fn is_legal_username(username: &str) -> bool {
+    !username.is_empty()
+        && username.len() <= MAX_USERNAME_LEN
+        && username.trim() == username
+        && username.chars().all(|c| c.is_ascii_graphic() || c == ' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usernames_must_be_printable_and_trimmed() {
+        assert!(is_legal_username("Player One"));
+        assert!(!is_legal_username(""));
+        assert!(!is_legal_username(" Player"));
+        assert!(!is_legal_username("Play\ter"));
+        assert!(!is_legal_username("Plâyer"));
+        assert!(!is_legal_username(&"a".repeat(MAX_USERNAME_LEN + 1)));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::auth::auth_handler::authentication_request::{
+    AuthenticationRequest, SteamAuthenticationRequest,
+};
+use crate::auth::auth_handler::ticket::{measure_clock_skew, TicketAuthResponse, TicketHolder};
+use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
+use crate::auth::ban::{NoBanStore, ThreadSafeBanStore};
+use crate::auth::identity::{
+    IdentityPlatform, PlatformAccountResolver, PlatformIdentity, ThreadSafeAccountResolver,
+};
+use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_serialization::BdDeserialize;
+use crate::messaging::{BdErrorCode, StreamMode};
+use crate::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::{info, warn};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Authenticates users with their AB account.
+///
+/// Patched clients send the custom ticket format with the id of the AB account in place of
+/// the Steam id, followed by the app id of the client.
+pub struct AbaccountsAuthHandler {
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    account_resolver: Arc<ThreadSafeAccountResolver>,
+    app_id: Option<u32>,
+    ban_store: Arc<ThreadSafeBanStore>,
+}
+
+impl AbaccountsAuthHandler {
+    pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>) -> Self {
+        AbaccountsAuthHandler {
+            key_store,
+            account_resolver: Arc::new(PlatformAccountResolver::new()),
+            app_id: None,
+            ban_store: Arc::new(NoBanStore::new()),
+        }
+    }
+
+    /// Determines which account AB account users are authenticated as.
+    /// By default, the id of the AB account is used as account id.
+    pub fn with_account_resolver(
+        mut self,
+        account_resolver: Arc<ThreadSafeAccountResolver>,
+    ) -> Self {
+        self.account_resolver = account_resolver;
+
+        self
+    }
+
+    /// Only accepts clients presenting the specified app id.
+    /// By default, any app id is accepted.
+    pub fn with_app_id(mut self, app_id: u32) -> Self {
+        self.app_id = Some(app_id);
+
+        self
+    }
+
+    /// Refuses users that are banned.
+    /// By default, nobody is banned.
+    pub fn with_ban_store(mut self, ban_store: Arc<ThreadSafeBanStore>) -> Self {
+        self.ban_store = ban_store;
+
+        self
+    }
+}
+
+impl AuthHandler for AbaccountsAuthHandler {
+    fn handle_message(
+        &self,
+        _session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>> {
+        message.reader.set_mode(StreamMode::BitMode);
+        message.reader.read_type_checked_bit()?;
+
+        let authentication_request = AuthenticationRequest::deserialize(&mut message.reader)?;
+        let app_id = message.reader.read_u32()?;
+        let request_data = match authentication_request.request_data {
+            SteamAuthenticationRequest::Custom { request_data: t } => t,
+        };
+
+        if self.app_id.is_some_and(|expected| expected != app_id) {
+            warn!(
+                "Refused AB account of username={} with app_id={app_id}",
+                &request_data.username
+            );
+            return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                AuthMessageType::AbaccountsForMmpReply,
+                BdErrorCode::AuthAbaccountsAppIdMismatch,
+            )));
+        }
+
+        info!(
+            "Trying to auth with AB account iv_seed={:x} title={:?} app_id={app_id} username={}",
+            authentication_request.iv_seed, authentication_request.title, &request_data.username
+        );
+
+        let identity = PlatformIdentity {
+            platform: IdentityPlatform::Abaccounts,
+            platform_user_id: request_data.platform_user_id,
+        };
+        let user_id = self.account_resolver.resolve_account(&identity)?;
+
+        if self.ban_store.is_banned(user_id, Some(&identity)) {
+            warn!(
+                "Refused banned user_id={user_id} username={}",
+                &request_data.username
+            );
+            return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                AuthMessageType::AbaccountsForMmpReply,
+                BdErrorCode::UserIdBanned,
+            )));
+        }
+
+        let clock_skew = measure_clock_skew(request_data.client_time, Utc::now().timestamp());
+        let session_key = request_data.session_key;
+        let holder = TicketHolder {
+            title: authentication_request.title,
+            user_id,
+            username: request_data.username,
+            session_key: session_key.clone(),
+            clock_skew,
+            anonymous: false,
+        };
+
+        Ok(Box::new(TicketAuthResponse::issue(
+            AuthMessageType::AbaccountsForMmpReply,
+            holder,
+            session_key,
+            self.key_store.as_ref(),
+        )))
+    }
+}
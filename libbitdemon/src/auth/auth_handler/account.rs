@@ -0,0 +1,143 @@
+use crate::auth::account::{is_legal_username, AccountStoreError, ThreadSafeAccountStore};
+use crate::auth::auth_handler::account_request::AccountRequest;
+use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
+use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::{BdErrorCode, StreamMode};
+use crate::networking::bd_session::BdSession;
+use log::info;
+use std::error::Error;
+use std::sync::Arc;
+
+/// The ways in which users can manage their bitdemon accounts.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum AccountOperation {
+    CreateAccount,
+    ChangeUserKey,
+    ResetAccount,
+    DeleteAccount,
+    /// Moves the account to an identity of an external platform.
+    MigrateAccounts,
+}
+
+impl AccountOperation {
+    pub const ALL: [AccountOperation; 5] = [
+        AccountOperation::CreateAccount,
+        AccountOperation::ChangeUserKey,
+        AccountOperation::ResetAccount,
+        AccountOperation::DeleteAccount,
+        AccountOperation::MigrateAccounts,
+    ];
+
+    /// The auth message type clients request this operation with.
+    pub fn request_type(&self) -> AuthMessageType {
+        match self {
+            AccountOperation::CreateAccount => AuthMessageType::CreateAccountRequest,
+            AccountOperation::ChangeUserKey => AuthMessageType::ChangeUserKeyRequest,
+            AccountOperation::ResetAccount => AuthMessageType::ResetAccountRequest,
+            AccountOperation::DeleteAccount => AuthMessageType::DeleteAccountRequest,
+            AccountOperation::MigrateAccounts => AuthMessageType::MigrateAccountsRequest,
+        }
+    }
+}
+
+/// Handles one of the requests managing bitdemon accounts by forwarding it to an account store.
+/// The reply only consists of the error code.
+pub struct AccountHandler {
+    account_store: Arc<ThreadSafeAccountStore>,
+    operation: AccountOperation,
+}
+
+impl AccountHandler {
+    pub fn new(account_store: Arc<ThreadSafeAccountStore>, operation: AccountOperation) -> Self {
+        AccountHandler {
+            account_store,
+            operation,
+        }
+    }
+
+    fn execute(&self, request: &mut AccountRequest) -> Result<BdErrorCode, Box<dyn Error>> {
+        let username = request.read_username()?;
+
+        let result = match self.operation {
+            AccountOperation::CreateAccount => {
+                if !is_legal_username(&username) {
+                    return Ok(BdErrorCode::AuthCreateUsernameIllegal);
+                }
+
+                let user_key = request.read_user_key()?;
+                let license_code = request.read_license_code()?;
+
+                self.account_store
+                    .create_account(&username, &user_key, &license_code)
+                    .map(|user_id| {
+                        info!("Created account user_id={user_id} username={username}");
+                    })
+            }
+            AccountOperation::ChangeUserKey => {
+                let user_key = request.read_user_key()?;
+                let new_user_key = request.read_user_key()?;
+
+                self.account_store
+                    .change_user_key(&username, &user_key, &new_user_key)
+            }
+            AccountOperation::ResetAccount => {
+                let license_code = request.read_license_code()?;
+                let new_user_key = request.read_user_key()?;
+
+                self.account_store
+                    .reset_account(&username, &license_code, &new_user_key)
+            }
+            AccountOperation::DeleteAccount => {
+                let user_key = request.read_user_key()?;
+
+                self.account_store.delete_account(&username, &user_key)
+            }
+            AccountOperation::MigrateAccounts => {
+                let user_key = request.read_user_key()?;
+                let Some(identity) = request.read_platform_identity()? else {
+                    return Ok(BdErrorCode::AuthMigrateNotSupported);
+                };
+
+                self.account_store
+                    .migrate_account(&username, &user_key, &identity)
+            }
+        };
+
+        Ok(match result {
+            Ok(()) => BdErrorCode::AuthNoError,
+            Err(AccountStoreError::AccountNotFoundError) => BdErrorCode::AuthBadAccount,
+            Err(AccountStoreError::UsernameExistsError) => BdErrorCode::AuthCreateUsernameExists,
+            Err(AccountStoreError::UsernameIllegalError) => BdErrorCode::AuthCreateUsernameIllegal,
+            Err(AccountStoreError::IncorrectUserKeyError) => BdErrorCode::AuthIncorrectPassword,
+            Err(AccountStoreError::IncorrectLicenseCodeError) => {
+                BdErrorCode::AuthIncorrectLicenseCode
+            }
+        })
+    }
+}
+
+impl AuthHandler for AccountHandler {
+    fn handle_message(
+        &self,
+        _session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>> {
+        message.reader.set_mode(StreamMode::BitMode);
+        message.reader.read_type_checked_bit()?;
+
+        let mut request = AccountRequest::deserialize(&mut message.reader)?;
+
+        info!(
+            "Handling {:?} iv_seed={:x} title={:?}",
+            self.operation, request.iv_seed, request.title
+        );
+
+        let error_code = self.execute(&mut request)?;
+
+        Ok(Box::new(AuthResponseWithOnlyCode::new(
+            self.operation.request_type().reply_code(),
+            error_code,
+        )))
+    }
+}
@@ -0,0 +1,116 @@
+use crate::auth::account::{AccountStoreError, ThreadSafeAccountStore};
+use crate::auth::auth_handler::account_request::AccountRequest;
+use crate::auth::auth_handler::ticket::{TicketAuthResponse, TicketHolder};
+use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
+use crate::auth::ban::{NoBanStore, ThreadSafeBanStore};
+use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
+use crate::auth::result::auth_ticket::BdAuthTicketType;
+use crate::crypto::SessionKey;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::{BdErrorCode, StreamMode};
+use crate::networking::bd_session::BdSession;
+use log::{info, warn};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Lets hosts of listen servers look up the accounts of clients connecting to them.
+///
+/// The request contains the username of the host followed by the username of the client.
+/// The reply is a ticket of the client account that is encrypted with the user key of the host,
+/// so only the actual host can read it.
+/// It does not contain a proof, so the host cannot use it to access the lobby server.
+pub struct AccountForHostHandler {
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    account_store: Arc<ThreadSafeAccountStore>,
+    ban_store: Arc<ThreadSafeBanStore>,
+}
+
+impl AccountForHostHandler {
+    pub fn new(
+        key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+        account_store: Arc<ThreadSafeAccountStore>,
+    ) -> Self {
+        AccountForHostHandler {
+            key_store,
+            account_store,
+            ban_store: Arc::new(NoBanStore::new()),
+        }
+    }
+
+    /// Refuses lookups by or of users that are banned.
+    /// By default, nobody is banned.
+    pub fn with_ban_store(mut self, ban_store: Arc<ThreadSafeBanStore>) -> Self {
+        self.ban_store = ban_store;
+
+        self
+    }
+}
+
+impl AuthHandler for AccountForHostHandler {
+    fn handle_message(
+        &self,
+        _session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>> {
+        message.reader.set_mode(StreamMode::BitMode);
+        message.reader.read_type_checked_bit()?;
+
+        let mut request = AccountRequest::deserialize(&mut message.reader)?;
+        let host_username = request.read_username()?;
+        let client_username = request.read_username()?;
+
+        info!(
+            "Looking up account for host iv_seed={:x} title={:?} host={host_username} client={client_username}",
+            request.iv_seed, request.title
+        );
+
+        let accounts = self
+            .account_store
+            .find_account(&host_username)
+            .and_then(|host| Ok((host, self.account_store.find_account(&client_username)?)));
+        let (host, client) = match accounts {
+            Ok(accounts) => accounts,
+            Err(AccountStoreError::AccountNotFoundError) => {
+                return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                    AuthMessageType::AccountForHostReply,
+                    BdErrorCode::AuthBadAccount,
+                )));
+            }
+            Err(err) => return Err(format!("Failed to find account: {err:?}").into()),
+        };
+
+        if let Some(banned) = [&host, &client]
+            .into_iter()
+            .find(|account| self.ban_store.is_user_banned(account.user_id))
+        {
+            warn!(
+                "Refused host lookup of banned user_id={} username={}",
+                banned.user_id, banned.username
+            );
+            return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                AuthMessageType::AccountForHostReply,
+                BdErrorCode::UserIdBanned,
+            )));
+        }
+
+        let session_key = SessionKey::generate();
+
+        let holder = TicketHolder {
+            title: request.title,
+            user_id: client.user_id,
+            username: client.username,
+            session_key,
+            clock_skew: 0,
+            anonymous: false,
+        };
+
+        Ok(Box::new(TicketAuthResponse::issue_with_type(
+            BdAuthTicketType::UserToHost,
+            AuthMessageType::AccountForHostReply,
+            holder,
+            host.user_key.into(),
+            self.key_store.as_ref(),
+        )))
+    }
+}
@@ -0,0 +1,131 @@
+use crate::auth::account::{AccountStoreError, ThreadSafeAccountStore};
+use crate::auth::auth_handler::account_request::AccountRequest;
+use crate::auth::auth_handler::ticket::{TicketAuthResponse, TicketHolder};
+use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
+use crate::auth::ban::{NoBanStore, ThreadSafeBanStore};
+use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
+use crate::auth::result::auth_ticket::BdAuthTicketType;
+use crate::crypto::SessionKey;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::{BdErrorCode, StreamMode};
+use crate::networking::bd_session::BdSession;
+use log::{info, warn};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Authenticates users with their bitdemon account.
+///
+/// The request only contains the username. The issued ticket is encrypted with the user key
+/// of the account, so only users knowing the key can read the session key inside it
+/// and make use of it.
+///
+/// Hosts of listen servers authenticate the same way, but receive a host ticket.
+pub struct AccountForMmpHandler {
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    account_store: Arc<ThreadSafeAccountStore>,
+    ticket_type: BdAuthTicketType,
+    ban_store: Arc<ThreadSafeBanStore>,
+}
+
+impl AccountForMmpHandler {
+    pub fn new(
+        key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+        account_store: Arc<ThreadSafeAccountStore>,
+    ) -> Self {
+        AccountForMmpHandler {
+            key_store,
+            account_store,
+            ticket_type: BdAuthTicketType::UserToService,
+            ban_store: Arc::new(NoBanStore::new()),
+        }
+    }
+
+    /// Handles `HostForMmp` requests of listen server hosts instead.
+    pub fn for_host(
+        key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+        account_store: Arc<ThreadSafeAccountStore>,
+    ) -> Self {
+        AccountForMmpHandler {
+            key_store,
+            account_store,
+            ticket_type: BdAuthTicketType::HostToService,
+            ban_store: Arc::new(NoBanStore::new()),
+        }
+    }
+
+    /// Refuses users that are banned.
+    /// By default, nobody is banned.
+    pub fn with_ban_store(mut self, ban_store: Arc<ThreadSafeBanStore>) -> Self {
+        self.ban_store = ban_store;
+
+        self
+    }
+
+    fn reply_type(&self) -> AuthMessageType {
+        match self.ticket_type {
+            BdAuthTicketType::HostToService => AuthMessageType::HostForMmpReply,
+            _ => AuthMessageType::AccountForMmpReply,
+        }
+    }
+}
+
+impl AuthHandler for AccountForMmpHandler {
+    fn handle_message(
+        &self,
+        _session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>> {
+        message.reader.set_mode(StreamMode::BitMode);
+        message.reader.read_type_checked_bit()?;
+
+        let mut request = AccountRequest::deserialize(&mut message.reader)?;
+        let username = request.read_username()?;
+
+        info!(
+            "Trying to auth with account ticket_type={:?} iv_seed={:x} title={:?} username={username}",
+            self.ticket_type, request.iv_seed, request.title
+        );
+
+        let account = match self.account_store.find_account(&username) {
+            Ok(account) => account,
+            Err(AccountStoreError::AccountNotFoundError) => {
+                return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                    self.reply_type(),
+                    BdErrorCode::AuthBadAccount,
+                )));
+            }
+            Err(err) => return Err(format!("Failed to find account: {err:?}").into()),
+        };
+
+        if self.ban_store.is_user_banned(account.user_id) {
+            warn!(
+                "Refused banned user_id={} username={}",
+                account.user_id, account.username
+            );
+            return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                self.reply_type(),
+                BdErrorCode::UserIdBanned,
+            )));
+        }
+
+        let session_key = SessionKey::generate();
+
+        let holder = TicketHolder {
+            title: request.title,
+            user_id: account.user_id,
+            username: account.username,
+            session_key,
+            clock_skew: 0,
+            anonymous: false,
+        };
+
+        Ok(Box::new(TicketAuthResponse::issue_with_type(
+            self.ticket_type,
+            self.reply_type(),
+            holder,
+            account.user_key.into(),
+            self.key_store.as_ref(),
+        )))
+    }
+}
@@ -0,0 +1,84 @@
+﻿use crate::auth::account::UserKey;
+use crate::auth::identity::{IdentityPlatform, PlatformIdentity};
+use crate::domain::title::Title;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::StreamMode;
+use num_traits::FromPrimitive;
+use snafu::{ensure, OptionExt, Snafu};
+use std::error::Error;
+
+/// The envelope of requests concerning bitdemon accounts.
+/// The account data itself is only known from observing clients and may not match
+/// the official format exactly: A null terminated username followed by the fields
+/// of the respective request.
+pub struct AccountRequest {
+    pub iv_seed: u32,
+    pub title: Title,
+    data: BdReader<'static>,
+}
+
+#[derive(Debug, Snafu)]
+enum AccountRequestDeserializationError {
+    #[snafu(display("The title id is unknown (value={title_id})"))]
+    UnknownTitleError { title_id: u32 },
+    #[snafu(display("The request data is too long (len={data_len} max={MAX_DATA_LEN})"))]
+    RequestDataTooLongError { data_len: usize },
+}
+
+const MAX_DATA_LEN: usize = 256usize;
+
+impl AccountRequest {
+    pub fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        let iv_seed = reader.read_u32()?;
+        let title_id = reader.read_u32()?;
+        let title = Title::from_u32(title_id).with_context(|| UnknownTitleSnafu { title_id })?;
+
+        let data_len = reader.read_u32()? as usize;
+        ensure!(
+            data_len <= MAX_DATA_LEN,
+            RequestDataTooLongSnafu { data_len }
+        );
+
+        let mut data_buf = vec![0u8; data_len];
+        reader.read_bytes(data_buf.as_mut_slice())?;
+
+        let mut data = BdReader::new(data_buf);
+        data.set_mode(StreamMode::ByteMode);
+        data.set_type_checked(false);
+
+        Ok(AccountRequest {
+            iv_seed,
+            title,
+            data,
+        })
+    }
+
+    pub fn read_username(&mut self) -> Result<String, Box<dyn Error>> {
+        self.data.read_str()
+    }
+
+    pub fn read_user_key(&mut self) -> Result<UserKey, Box<dyn Error>> {
+        let mut user_key: UserKey = [0; 24];
+        self.data.read_bytes(&mut user_key)?;
+
+        Ok(user_key)
+    }
+
+    pub fn read_license_code(&mut self) -> Result<String, Box<dyn Error>> {
+        self.data.read_str()
+    }
+
+    /// Reads the platform number followed by the id of the user on that platform.
+    /// Returns `None` for platforms that are not known.
+    pub fn read_platform_identity(&mut self) -> Result<Option<PlatformIdentity>, Box<dyn Error>> {
+        let platform_num = self.data.read_u8()?;
+        let platform_user_id = self.data.read_u64()?;
+
+        Ok(
+            IdentityPlatform::from_u8(platform_num).map(|platform| PlatformIdentity {
+                platform,
+                platform_user_id,
+            }),
+        )
+    }
+}
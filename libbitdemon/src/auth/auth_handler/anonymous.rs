@@ -0,0 +1,78 @@
+use crate::auth::account::is_legal_username;
+use crate::auth::auth_handler::account_request::AccountRequest;
+use crate::auth::auth_handler::ticket::{TicketAuthResponse, TicketHolder};
+use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
+use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::auth::response::AuthResponse;
+use crate::crypto::SessionKey;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::StreamMode;
+use crate::networking::bd_session::BdSession;
+use log::info;
+use rand::RngExt;
+use std::error::Error;
+use std::sync::Arc;
+
+/// Guests get random user ids with the highest bit set,
+/// which keeps them apart from accounts and platform ids.
+const ANONYMOUS_USER_ID_FLAG: u64 = 1 << 63;
+
+/// Authenticates users as guests that do not have any account.
+///
+/// The request contains the name the guest wants to be shown with, followed by a key
+/// chosen by the client that the issued ticket is encrypted with.
+/// Guests get a new user id every time they authenticate, so they cannot keep any data.
+pub struct AnonymousAuthHandler {
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+}
+
+impl AnonymousAuthHandler {
+    pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>) -> Self {
+        AnonymousAuthHandler { key_store }
+    }
+}
+
+impl AuthHandler for AnonymousAuthHandler {
+    fn handle_message(
+        &self,
+        _session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>> {
+        message.reader.set_mode(StreamMode::BitMode);
+        message.reader.read_type_checked_bit()?;
+
+        let mut request = AccountRequest::deserialize(&mut message.reader)?;
+        let requested_username = request.read_username()?;
+        let guest_key = request.read_user_key()?;
+
+        let user_id = rand::rng().random::<u64>() | ANONYMOUS_USER_ID_FLAG;
+        let username = if is_legal_username(&requested_username) {
+            requested_username
+        } else {
+            format!("Guest{}", user_id % 100_000)
+        };
+
+        info!(
+            "Authenticating guest iv_seed={:x} title={:?} user_id={user_id} username={username}",
+            request.iv_seed, request.title
+        );
+
+        let session_key = SessionKey::generate();
+
+        let holder = TicketHolder {
+            title: request.title,
+            user_id,
+            username,
+            session_key,
+            clock_skew: 0,
+            anonymous: true,
+        };
+
+        Ok(Box::new(TicketAuthResponse::issue(
+            AuthMessageType::AnonymousForMmpReply,
+            holder,
+            guest_key.into(),
+            self.key_store.as_ref(),
+        )))
+    }
+}
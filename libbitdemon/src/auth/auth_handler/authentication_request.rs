@@ -1,4 +1,5 @@
-﻿use crate::domain::title::Title;
+﻿use crate::crypto::SessionKey;
+use crate::domain::title::Title;
 use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_serialization::BdDeserialize;
 use crate::messaging::StreamMode;
@@ -20,32 +21,37 @@ enum AuthenticationRequestDeserializationError {
     RequestDataTooLongError { data_len: usize },
 }
 
-const MAX_DATA_LEN: usize = 128usize;
+// Leaves room for Steam session tickets appended to the custom format
+const MAX_DATA_LEN: usize = 1024usize;
 
 impl BdDeserialize for AuthenticationRequest {
     fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>>
     where
         Self: Sized,
     {
-        let iv_seed = reader.read_u32()?;
-        let title_id = reader.read_u32()?;
-        let title = Title::from_u32(title_id).with_context(|| UnknownTitleSnafu { title_id })?;
+        let (iv_seed, title, mut ticket_reader) = read_ticket_data(reader)?;
 
-        let data_len = reader.read_u32()? as usize;
-        ensure!(
-            data_len <= MAX_DATA_LEN,
-            RequestDataTooLongSnafu { data_len }
-        );
-
-        let mut data_buf = vec![0; data_len];
-        data_buf.resize(data_len, 0u8);
+        let request_data = SteamAuthenticationRequest::Custom {
+            request_data: CustomSteamAuthenticationRequest::deserialize(&mut ticket_reader)?,
+        };
 
-        reader.read_bytes(data_buf.as_mut_slice())?;
+        Ok(AuthenticationRequest {
+            iv_seed,
+            title,
+            request_data,
+        })
+    }
+}
 
-        let mut ticket_reader = BdReader::new(data_buf);
+impl AuthenticationRequest {
+    /// Reads a request whose ticket is in the reduced custom format of 3DS clients.
+    pub fn deserialize_reduced(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        let (iv_seed, title, mut ticket_reader) = read_ticket_data(reader)?;
 
         let request_data = SteamAuthenticationRequest::Custom {
-            request_data: CustomSteamAuthenticationRequest::deserialize(&mut ticket_reader)?,
+            request_data: CustomSteamAuthenticationRequest::deserialize_reduced(
+                &mut ticket_reader,
+            )?,
         };
 
         Ok(AuthenticationRequest {
@@ -56,16 +62,46 @@ impl BdDeserialize for AuthenticationRequest {
     }
 }
 
+fn read_ticket_data(
+    reader: &mut BdReader,
+) -> Result<(u32, Title, BdReader<'static>), Box<dyn Error>> {
+    let iv_seed = reader.read_u32()?;
+    let title_id = reader.read_u32()?;
+    let title = Title::from_u32(title_id).with_context(|| UnknownTitleSnafu { title_id })?;
+
+    let data_len = reader.read_u32()? as usize;
+    ensure!(
+        data_len <= MAX_DATA_LEN,
+        RequestDataTooLongSnafu { data_len }
+    );
+
+    let mut data_buf = vec![0; data_len];
+    data_buf.resize(data_len, 0u8);
+
+    reader.read_bytes(data_buf.as_mut_slice())?;
+
+    Ok((iv_seed, title, BdReader::new(data_buf)))
+}
+
 pub enum SteamAuthenticationRequest {
     Custom {
         request_data: CustomSteamAuthenticationRequest,
     },
 }
 
+/// A ticket in the custom format of patched clients.
+/// Nintendo builds send the same format in place of their console tokens.
 pub struct CustomSteamAuthenticationRequest {
-    pub steam_id: u64,
-    pub session_key: [u8; 24],
+    /// The Steam id or the id of the console user.
+    pub platform_user_id: u64,
+    pub session_key: SessionKey,
     pub username: String,
+    /// The unix timestamp of the client when it created the ticket.
+    /// Optional, since it was added to the custom format later on.
+    pub client_time: Option<i64>,
+    /// The session ticket the client got from Steam, so that it can be validated with Steam.
+    /// Optional, since it was added to the custom format after the client time.
+    pub steam_ticket: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Snafu)]
@@ -101,7 +137,7 @@ impl BdDeserialize for CustomSteamAuthenticationRequest {
             }
         );
 
-        let steam_id = reader.read_u64()?;
+        let platform_user_id = reader.read_u64()?;
 
         let secret_data_size = reader.read_u32()? as usize;
         ensure!(
@@ -112,22 +148,120 @@ impl BdDeserialize for CustomSteamAuthenticationRequest {
             }
         );
 
-        let mut session_key: [u8; 24] = [0; 24];
-        reader.read_bytes(&mut session_key)?;
+        let mut session_key = SessionKey::new([0; 24]);
+        reader.read_bytes(&mut *session_key)?;
+
+        let username = read_username(reader)?;
+
+        let client_time = if reader.remaining_bytes()? >= size_of::<i64>() {
+            Some(reader.read_i64()?)
+        } else {
+            None
+        };
+
+        let steam_ticket = if reader.remaining_bytes()? >= size_of::<u32>() {
+            let ticket_len = reader.read_u32()? as usize;
+            let mut steam_ticket = vec![0u8; ticket_len.min(reader.remaining_bytes()?)];
+            reader.read_bytes(&mut steam_ticket)?;
+            Some(steam_ticket)
+        } else {
+            None
+        };
+
+        Ok(CustomSteamAuthenticationRequest {
+            platform_user_id,
+            session_key,
+            username,
+            client_time,
+            steam_ticket,
+        })
+    }
+}
+
+impl CustomSteamAuthenticationRequest {
+    /// Reads a ticket in the reduced format, which lacks the secret data size and client time,
+    /// since 3DS clients only have little space for their ticket.
+    pub fn deserialize_reduced(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        reader.set_mode(StreamMode::ByteMode);
+        reader.set_type_checked(false);
+
+        let signature = reader.read_u32()?;
 
-        let username = reader.read_str()?;
         ensure!(
-            username.len() < 64usize,
-            UsernameTooLongSnafu {
-                actual: username.len(),
-                expected: 64usize
+            signature == CUSTOM_TICKET_SIGNATURE,
+            SignatureMismatchSnafu {
+                actual: signature,
+                expected: CUSTOM_TICKET_SIGNATURE
             }
         );
 
+        let platform_user_id = reader.read_u64()?;
+
+        let mut session_key = SessionKey::new([0; 24]);
+        reader.read_bytes(&mut *session_key)?;
+
+        let username = read_username(reader)?;
+
         Ok(CustomSteamAuthenticationRequest {
-            steam_id,
+            platform_user_id,
             session_key,
             username,
+            client_time: None,
+            steam_ticket: None,
         })
     }
 }
+
+fn read_username(reader: &mut BdReader) -> Result<String, Box<dyn Error>> {
+    let username = reader.read_str()?;
+    ensure!(
+        username.len() < 64usize,
+        UsernameTooLongSnafu {
+            actual: username.len(),
+            expected: 64usize
+        }
+    );
+
+    Ok(username)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steam_ticket_follows_client_time() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&CUSTOM_TICKET_SIGNATURE.to_le_bytes());
+        buf.extend_from_slice(&42u64.to_le_bytes());
+        buf.extend_from_slice(&(EXPECTED_SECRET_DATA_SIZE as u32).to_le_bytes());
+        buf.extend_from_slice(&[7; 24]);
+        buf.extend_from_slice(b"Player\0");
+        buf.extend_from_slice(&1234i64.to_le_bytes());
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        buf.extend_from_slice(&[1, 2, 3]);
+
+        let request =
+            CustomSteamAuthenticationRequest::deserialize(&mut BdReader::new(buf)).unwrap();
+
+        assert_eq!(request.client_time, Some(1234));
+        assert_eq!(request.steam_ticket, Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn reduced_ticket_has_no_client_time() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&CUSTOM_TICKET_SIGNATURE.to_le_bytes());
+        buf.extend_from_slice(&42u64.to_le_bytes());
+        buf.extend_from_slice(&[7; 24]);
+        buf.extend_from_slice(b"Player\0");
+
+        let request =
+            CustomSteamAuthenticationRequest::deserialize_reduced(&mut BdReader::new(buf)).unwrap();
+
+        assert_eq!(request.platform_user_id, 42);
+        assert_eq!(*request.session_key, [7; 24]);
+        assert_eq!(request.username, "Player");
+        assert_eq!(request.client_time, None);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::auth::account::{is_legal_username, AccountStoreError, ThreadSafeAccountStore};
+use crate::auth::auth_handler::authentication_request::{
+    AuthenticationRequest, SteamAuthenticationRequest,
+};
+use crate::auth::auth_handler::ticket::{measure_clock_skew, TicketAuthResponse, TicketHolder};
+use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
+use crate::auth::ban::{NoBanStore, ThreadSafeBanStore};
+use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_serialization::BdDeserialize;
+use crate::messaging::{BdErrorCode, StreamMode};
+use crate::networking::bd_session::BdSession;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use log::{info, warn};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Authenticates users with their Call of Duty online (CODO) id.
+///
+/// Patched clients send the custom ticket format with the CODO id in place of the Steam id
+/// and the username encoded as base64.
+/// Each CODO id is mapped to an account of the account store.
+pub struct CodoAuthHandler {
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    account_store: Arc<ThreadSafeAccountStore>,
+    ban_store: Arc<ThreadSafeBanStore>,
+}
+
+impl CodoAuthHandler {
+    pub fn new(
+        key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+        account_store: Arc<ThreadSafeAccountStore>,
+    ) -> Self {
+        CodoAuthHandler {
+            key_store,
+            account_store,
+            ban_store: Arc::new(NoBanStore::new()),
+        }
+    }
+
+    /// Refuses users that are banned.
+    /// By default, nobody is banned.
+    pub fn with_ban_store(mut self, ban_store: Arc<ThreadSafeBanStore>) -> Self {
+        self.ban_store = ban_store;
+
+        self
+    }
+
+    fn decode_username(encoded_username: &str) -> Result<String, BdErrorCode> {
+        if encoded_username.is_empty() {
+            return Err(BdErrorCode::AuthCodoUsernameNotSet);
+        }
+
+        let username_bytes = BASE64_STANDARD
+            .decode(encoded_username)
+            .map_err(|_| BdErrorCode::AuthCodoUsernameNotBase64)?;
+
+        String::from_utf8(username_bytes).map_err(|_| BdErrorCode::AuthCodoUsernameNotUtf8)
+    }
+
+    fn reply_with_code(error_code: BdErrorCode) -> Box<dyn AuthResponse> {
+        Box::new(AuthResponseWithOnlyCode::new(
+            AuthMessageType::CodoForMmpReply,
+            error_code,
+        ))
+    }
+}
+
+impl AuthHandler for CodoAuthHandler {
+    fn handle_message(
+        &self,
+        _session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>> {
+        message.reader.set_mode(StreamMode::BitMode);
+        message.reader.read_type_checked_bit()?;
+
+        let authentication_request = AuthenticationRequest::deserialize(&mut message.reader)?;
+        let request_data = match authentication_request.request_data {
+            SteamAuthenticationRequest::Custom { request_data: t } => t,
+        };
+
+        let username = match Self::decode_username(&request_data.username) {
+            Ok(username) => username,
+            Err(error_code) => return Ok(Self::reply_with_code(error_code)),
+        };
+        if !is_legal_username(&username) {
+            return Ok(Self::reply_with_code(
+                BdErrorCode::AuthCreateUsernameIllegal,
+            ));
+        }
+
+        info!(
+            "Trying to auth with CODO iv_seed={:x} title={:?} codo_id={} username={username}",
+            authentication_request.iv_seed,
+            authentication_request.title,
+            request_data.platform_user_id
+        );
+
+        let account = match self
+            .account_store
+            .resolve_codo_account(request_data.platform_user_id, &username)
+        {
+            Ok(account) => account,
+            Err(AccountStoreError::UsernameExistsError) => {
+                return Ok(Self::reply_with_code(BdErrorCode::AuthCreateUsernameExists));
+            }
+            Err(err) => return Err(format!("Failed to resolve CODO account: {err:?}").into()),
+        };
+
+        if self.ban_store.is_user_banned(account.user_id) {
+            warn!(
+                "Refused banned user_id={} username={}",
+                account.user_id, account.username
+            );
+            return Ok(Self::reply_with_code(BdErrorCode::UserIdBanned));
+        }
+
+        let clock_skew = measure_clock_skew(request_data.client_time, Utc::now().timestamp());
+        let session_key = request_data.session_key;
+        let holder = TicketHolder {
+            title: authentication_request.title,
+            user_id: account.user_id,
+            username: account.username,
+            session_key: session_key.clone(),
+            clock_skew,
+            anonymous: false,
+        };
+
+        Ok(Box::new(TicketAuthResponse::issue(
+            AuthMessageType::CodoForMmpReply,
+            holder,
+            session_key,
+            self.key_store.as_ref(),
+        )))
+    }
+}
@@ -73,5 +73,14 @@ pub trait AuthHandler {
     ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>>;
 }
 
+pub mod abaccounts;
+pub mod account;
+pub mod account_for_host;
+pub mod account_for_mmp;
+mod account_request;
+pub mod anonymous;
 mod authentication_request;
+pub mod codo;
+pub mod nintendo;
 pub mod steam;
+mod ticket;
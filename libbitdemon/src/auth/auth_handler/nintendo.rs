@@ -0,0 +1,187 @@
+use crate::auth::auth_handler::authentication_request::{
+    AuthenticationRequest, SteamAuthenticationRequest,
+};
+use crate::auth::auth_handler::ticket::{measure_clock_skew, TicketAuthResponse, TicketHolder};
+use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
+use crate::auth::ban::{NoBanStore, ThreadSafeBanStore};
+use crate::auth::identity::{
+    IdentityPlatform, PlatformAccountResolver, PlatformIdentity, ThreadSafeAccountResolver,
+};
+use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_serialization::BdDeserialize;
+use crate::messaging::{BdErrorCode, StreamMode};
+use crate::networking::bd_session::BdSession;
+use chrono::Utc;
+use log::{info, warn};
+use std::error::Error;
+use std::sync::Arc;
+
+/// The requests Nintendo console builds authenticate with.
+/// Each of them is registered as its own handler of the auth server.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum NintendoAuthKind {
+    Wii,
+    WiiU,
+    WiiU2,
+    /// Additional users playing on the same WiiU console.
+    WiiUSecondary,
+    /// Sends its ticket in the reduced custom format.
+    N3ds,
+}
+
+impl NintendoAuthKind {
+    pub const ALL: [NintendoAuthKind; 5] = [
+        NintendoAuthKind::Wii,
+        NintendoAuthKind::WiiU,
+        NintendoAuthKind::WiiU2,
+        NintendoAuthKind::WiiUSecondary,
+        NintendoAuthKind::N3ds,
+    ];
+
+    /// The auth message type clients authenticate with.
+    pub fn request_type(&self) -> AuthMessageType {
+        match self {
+            NintendoAuthKind::Wii => AuthMessageType::WiiForMmpRequest,
+            NintendoAuthKind::WiiU => AuthMessageType::WiiUForMmpRequest,
+            NintendoAuthKind::WiiU2 => AuthMessageType::WiiUForMmpRequest2,
+            NintendoAuthKind::WiiUSecondary => AuthMessageType::WiiUSecondaryForMmpRequest,
+            NintendoAuthKind::N3ds => AuthMessageType::N3dsForMmpRequest,
+        }
+    }
+
+    fn platform(&self) -> IdentityPlatform {
+        match self {
+            NintendoAuthKind::Wii => IdentityPlatform::Wii,
+            NintendoAuthKind::N3ds => IdentityPlatform::N3ds,
+            _ => IdentityPlatform::WiiU,
+        }
+    }
+
+    fn verification_failed_code(&self) -> BdErrorCode {
+        match self {
+            NintendoAuthKind::Wii => BdErrorCode::AuthWiiTokenVerificationFailed,
+            NintendoAuthKind::N3ds => BdErrorCode::Auth3dsTokenVerificationFailed,
+            _ => BdErrorCode::AuthWiiuTokenVerificationFailed,
+        }
+    }
+}
+
+/// Authenticates users of Nintendo consoles.
+///
+/// Console tokens cannot be verified since the Nintendo servers are offline.
+/// Like with Steam, only patched clients sending the custom ticket format can authenticate,
+/// with the id of the console user in place of the Steam id.
+/// 3DS clients send the reduced variant of the format.
+/// Tickets that cannot be read are answered with the token verification error of the console.
+pub struct NintendoAuthHandler {
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    account_resolver: Arc<ThreadSafeAccountResolver>,
+    kind: NintendoAuthKind,
+    ban_store: Arc<ThreadSafeBanStore>,
+}
+
+impl NintendoAuthHandler {
+    pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>, kind: NintendoAuthKind) -> Self {
+        NintendoAuthHandler {
+            key_store,
+            account_resolver: Arc::new(PlatformAccountResolver::new()),
+            kind,
+            ban_store: Arc::new(NoBanStore::new()),
+        }
+    }
+
+    /// Determines which account console users are authenticated as.
+    /// By default, the id of the console user is used as account id.
+    pub fn with_account_resolver(
+        mut self,
+        account_resolver: Arc<ThreadSafeAccountResolver>,
+    ) -> Self {
+        self.account_resolver = account_resolver;
+
+        self
+    }
+
+    /// Refuses users that are banned.
+    /// By default, nobody is banned.
+    pub fn with_ban_store(mut self, ban_store: Arc<ThreadSafeBanStore>) -> Self {
+        self.ban_store = ban_store;
+
+        self
+    }
+}
+
+impl AuthHandler for NintendoAuthHandler {
+    fn handle_message(
+        &self,
+        _session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<Box<dyn AuthResponse>, Box<dyn Error>> {
+        message.reader.set_mode(StreamMode::BitMode);
+        message.reader.read_type_checked_bit()?;
+
+        let authentication_request = match self.kind {
+            NintendoAuthKind::N3ds => {
+                AuthenticationRequest::deserialize_reduced(&mut message.reader)
+            }
+            _ => AuthenticationRequest::deserialize(&mut message.reader),
+        };
+        let authentication_request = match authentication_request {
+            Ok(authentication_request) => authentication_request,
+            Err(err) => {
+                warn!("Could not read {:?} ticket: {err}", self.kind);
+                return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                    self.kind.request_type().reply_code(),
+                    self.kind.verification_failed_code(),
+                )));
+            }
+        };
+        let request_data = match authentication_request.request_data {
+            SteamAuthenticationRequest::Custom { request_data: t } => t,
+        };
+
+        info!(
+            "Trying to auth with {:?} iv_seed={:x} title={:?} username={}",
+            self.kind,
+            authentication_request.iv_seed,
+            authentication_request.title,
+            &request_data.username
+        );
+
+        let identity = PlatformIdentity {
+            platform: self.kind.platform(),
+            platform_user_id: request_data.platform_user_id,
+        };
+        let user_id = self.account_resolver.resolve_account(&identity)?;
+
+        if self.ban_store.is_banned(user_id, Some(&identity)) {
+            warn!(
+                "Refused banned user_id={user_id} username={}",
+                &request_data.username
+            );
+            return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                self.kind.request_type().reply_code(),
+                BdErrorCode::UserIdBanned,
+            )));
+        }
+
+        let clock_skew = measure_clock_skew(request_data.client_time, Utc::now().timestamp());
+        let session_key = request_data.session_key;
+        let holder = TicketHolder {
+            title: authentication_request.title,
+            user_id,
+            username: request_data.username,
+            session_key: session_key.clone(),
+            clock_skew,
+            anonymous: false,
+        };
+
+        Ok(Box::new(TicketAuthResponse::issue(
+            self.kind.request_type().reply_code(),
+            holder,
+            session_key,
+            self.key_store.as_ref(),
+        )))
+    }
+}
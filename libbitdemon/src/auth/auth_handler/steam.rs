@@ -1,72 +1,132 @@
 ﻿use crate::auth::auth_handler::authentication_request::{
-    AuthenticationRequest, SteamAuthenticationRequest,
+    AuthenticationRequest, CustomSteamAuthenticationRequest, SteamAuthenticationRequest,
 };
+use crate::auth::auth_handler::ticket::{measure_clock_skew, TicketAuthResponse, TicketHolder};
 use crate::auth::auth_handler::{AuthHandler, AuthMessageType};
-use crate::auth::auth_proof::ClientOpaqueAuthProof;
+use crate::auth::ban::{NoBanStore, ThreadSafeBanStore};
+use crate::auth::identity::{
+    IdentityPlatform, PlatformAccountResolver, PlatformIdentity, ThreadSafeAccountResolver,
+};
 use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
-use crate::auth::response::AuthResponse;
-use crate::auth::result::auth_ticket::{AuthTicket, BdAuthTicketType};
-use crate::crypto::{encrypt_buffer_in_place, generate_iv_from_seed, generate_iv_seed};
+use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
+use crate::domain::title::Title;
 use crate::messaging::bd_message::BdMessage;
-use crate::messaging::bd_serialization::{BdDeserialize, BdSerialize};
-use crate::messaging::bd_writer::BdWriter;
+use crate::messaging::bd_serialization::BdDeserialize;
 use crate::messaging::{BdErrorCode, StreamMode};
 use crate::networking::bd_session::BdSession;
 use chrono::Utc;
-use des::cipher::BlockSizeUser;
-use log::info;
+use log::{info, warn};
 use std::error::Error;
 use std::sync::Arc;
 
 pub struct SteamAuthHandler {
     key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    account_resolver: Arc<ThreadSafeAccountResolver>,
+    ticket_validator: Option<Arc<ThreadSafeSteamTicketValidator>>,
+    ban_store: Arc<ThreadSafeBanStore>,
+}
+
+/// The outcome of checking a Steam session ticket with Steam.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SteamTicketValidation {
+    /// The ticket was issued by Steam for the specified user.
+    Valid { steam_id: u64 },
+    /// The ticket was not issued by Steam or is expired.
+    Invalid,
+    /// The ticket was issued for another app than the one of the title.
+    AppIdMismatch,
 }
 
-const TICKET_ISSUE_LENGTH: i64 = 5 * 60 * 1000;
+pub type ThreadSafeSteamTicketValidator = dyn SteamTicketValidator + Sync + Send;
 
-struct SteamAuthResponse {
-    ticket: AuthTicket,
-    serialized_proof_data: [u8; 128],
+/// Checks whether the Steam session tickets presented by clients were actually issued by Steam.
+pub trait SteamTicketValidator {
+    fn validate_ticket(
+        &self,
+        title: Title,
+        ticket: &[u8],
+    ) -> Result<SteamTicketValidation, Box<dyn Error>>;
 }
 
-impl AuthResponse for SteamAuthResponse {
-    fn message_type(&self) -> AuthMessageType {
-        AuthMessageType::SteamForMmpReply
+/// Clock skews of clients that are larger than this amount of seconds are logged.
+const LARGE_CLOCK_SKEW: i64 = 60;
+
+impl SteamAuthHandler {
+    pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>) -> Self {
+        SteamAuthHandler {
+            key_store,
+            account_resolver: Arc::new(PlatformAccountResolver::new()),
+            ticket_validator: None,
+            ban_store: Arc::new(NoBanStore::new()),
+        }
     }
 
-    fn error_code(&self) -> BdErrorCode {
-        BdErrorCode::AuthNoError
+    /// Validates the Steam session tickets that clients append to their custom ticket.
+    /// By default, the Steam id of the custom ticket is accepted without asking Steam.
+    pub fn with_ticket_validator(
+        mut self,
+        ticket_validator: Arc<ThreadSafeSteamTicketValidator>,
+    ) -> Self {
+        self.ticket_validator = Some(ticket_validator);
+
+        self
     }
 
-    fn write_auth_data(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
-        let seed = generate_iv_seed();
-        writer.write_u32(seed)?;
+    /// Checks the Steam ticket of the request if a validator is set
+    /// and returns the error code to reply with when it is not accepted.
+    fn validate_steam_ticket(
+        &self,
+        title: Title,
+        request_data: &CustomSteamAuthenticationRequest,
+    ) -> Result<Option<BdErrorCode>, Box<dyn Error>> {
+        let Some(ticket_validator) = &self.ticket_validator else {
+            return Ok(None);
+        };
+        let Some(steam_ticket) = &request_data.steam_ticket else {
+            warn!(
+                "Refused Steam user {} without Steam ticket",
+                request_data.platform_user_id
+            );
+            return Ok(Some(BdErrorCode::AuthBadAccount));
+        };
 
-        let mut ticket_buf = Vec::new();
-        {
-            let mut ticket_writer = BdWriter::new(&mut ticket_buf);
-            self.ticket.serialize(&mut ticket_writer)?;
-        }
+        let error_code = match ticket_validator.validate_ticket(title, steam_ticket)? {
+            SteamTicketValidation::Valid { steam_id }
+                if steam_id == request_data.platform_user_id =>
+            {
+                return Ok(None);
+            }
+            SteamTicketValidation::Valid { .. } | SteamTicketValidation::Invalid => {
+                BdErrorCode::AuthBadAccount
+            }
+            SteamTicketValidation::AppIdMismatch => BdErrorCode::AuthSteamAppIdMismatch,
+        };
 
-        let iv = generate_iv_from_seed(seed);
-        let ticket_buf_len = ticket_buf.len();
-        ticket_buf.resize(
-            ticket_buf_len.next_multiple_of(des::TdesEde3::block_size()),
-            0,
+        warn!(
+            "Refused Steam ticket of Steam user {}: {error_code:?}",
+            request_data.platform_user_id
         );
 
-        encrypt_buffer_in_place(&mut ticket_buf, &self.ticket.session_key, &iv);
-        writer.write_bytes(ticket_buf.as_slice())?;
+        Ok(Some(error_code))
+    }
 
-        writer.write_bytes(&self.serialized_proof_data)?;
+    /// Determines which account Steam users are authenticated as.
+    /// By default, the Steam id is used as account id.
+    pub fn with_account_resolver(
+        mut self,
+        account_resolver: Arc<ThreadSafeAccountResolver>,
+    ) -> Self {
+        self.account_resolver = account_resolver;
 
-        Ok(())
+        self
     }
-}
 
-impl SteamAuthHandler {
-    pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>) -> Self {
-        SteamAuthHandler { key_store }
+    /// Refuses users that are banned.
+    /// By default, nobody is banned.
+    pub fn with_ban_store(mut self, ban_store: Arc<ThreadSafeBanStore>) -> Self {
+        self.ban_store = ban_store;
+
+        self
     }
 }
 
@@ -89,35 +149,56 @@ impl AuthHandler for SteamAuthHandler {
             authentication_request.iv_seed, authentication_request.title, &request_data.username
         );
 
+        if let Some(error_code) =
+            self.validate_steam_ticket(authentication_request.title, &request_data)?
+        {
+            return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                AuthMessageType::SteamForMmpReply,
+                error_code,
+            )));
+        }
+
+        let identity = PlatformIdentity {
+            platform: IdentityPlatform::Steam,
+            platform_user_id: request_data.platform_user_id,
+        };
+        let user_id = self.account_resolver.resolve_account(&identity)?;
+
+        if self.ban_store.is_banned(user_id, Some(&identity)) {
+            warn!(
+                "Refused banned user_id={user_id} username={}",
+                &request_data.username
+            );
+            return Ok(Box::new(AuthResponseWithOnlyCode::new(
+                AuthMessageType::SteamForMmpReply,
+                BdErrorCode::UserIdBanned,
+            )));
+        }
+
         let now = Utc::now();
-        let issued = (now.timestamp() % (u32::MAX as i64)) as u32;
-        let expires_i64 = now.timestamp() + TICKET_ISSUE_LENGTH;
-        let expires = ((expires_i64) % (u32::MAX as i64)) as u32;
+        let clock_skew = measure_clock_skew(request_data.client_time, now.timestamp());
+        if clock_skew.abs() > LARGE_CLOCK_SKEW {
+            warn!(
+                "Clock of user_id={user_id} username={} is off by {clock_skew}s",
+                &request_data.username
+            );
+        }
 
-        let ticket = AuthTicket {
-            ticket_type: BdAuthTicketType::UserToService,
+        let session_key = request_data.session_key;
+        let holder = TicketHolder {
             title: authentication_request.title,
-            time_issued: issued,
-            time_expires: expires,
-            license_id: 1234u64,
-            user_id: request_data.steam_id,
+            user_id,
             username: request_data.username,
-            session_key: request_data.session_key,
-        };
-
-        let proof = ClientOpaqueAuthProof {
-            title: ticket.title,
-            time_expires: expires_i64,
-            license_id: ticket.license_id,
-            user_id: ticket.user_id,
-            session_key: ticket.session_key,
-            username: String::from(&ticket.username),
+            session_key: session_key.clone(),
+            clock_skew,
+            anonymous: false,
         };
-        let serialized_proof_data = proof.serialize(self.key_store.as_ref());
 
-        Ok(Box::new(SteamAuthResponse {
-            ticket,
-            serialized_proof_data,
-        }))
+        Ok(Box::new(TicketAuthResponse::issue(
+            AuthMessageType::SteamForMmpReply,
+            holder,
+            session_key,
+            self.key_store.as_ref(),
+        )))
     }
 }
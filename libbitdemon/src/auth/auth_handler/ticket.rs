@@ -0,0 +1,148 @@
+use crate::auth::auth_handler::AuthMessageType;
+use crate::auth::auth_proof::ClientOpaqueAuthProof;
+use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::auth::response::AuthResponse;
+use crate::auth::result::auth_ticket::{AuthTicket, BdAuthTicketType};
+use crate::crypto::{encrypt_buffer_in_place, generate_iv_from_seed, generate_iv_seed, SessionKey};
+use crate::domain::title::Title;
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use crate::messaging::BdErrorCode;
+use chrono::Utc;
+use des::cipher::BlockSizeUser;
+use std::error::Error;
+
+const TICKET_ISSUE_LENGTH: i64 = 5 * 60 * 1000;
+
+/// The amount of seconds the client is ahead of the server or zero if the client did not
+/// send its time.
+pub fn measure_clock_skew(client_time: Option<i64>, now: i64) -> i64 {
+    client_time.map_or(0, |client_time| client_time.saturating_sub(now))
+}
+
+/// The user that a ticket is issued for.
+pub struct TicketHolder {
+    pub title: Title,
+    pub user_id: u64,
+    pub username: String,
+    /// The key the user and lobby services share for the session.
+    pub session_key: SessionKey,
+    /// The amount of seconds the clock of the client is ahead of the server.
+    pub clock_skew: i64,
+    /// Whether the user is a guest without any account.
+    pub anonymous: bool,
+}
+
+/// Grants the user access to the lobby server.
+/// Consists of a ticket that only the user can decrypt, proving it is the user that the ticket
+/// was issued for, and a proof that only the lobby server can decrypt.
+///
+/// Tickets for hosts that users connect to come without a proof,
+/// since they must not grant the host access to the lobby server in the name of the user.
+pub struct TicketAuthResponse {
+    message_type: AuthMessageType,
+    ticket: AuthTicket,
+    ticket_key: SessionKey,
+    serialized_proof_data: Option<[u8; 128]>,
+}
+
+impl TicketAuthResponse {
+    /// Issues a ticket for the holder to access the lobby server
+    /// that is encrypted with the specified key.
+    pub fn issue(
+        message_type: AuthMessageType,
+        holder: TicketHolder,
+        ticket_key: SessionKey,
+        key_store: &ThreadSafeBackendPrivateKeyStorage,
+    ) -> Self {
+        Self::issue_with_type(
+            BdAuthTicketType::UserToService,
+            message_type,
+            holder,
+            ticket_key,
+            key_store,
+        )
+    }
+
+    /// Issues a ticket of the specified type that is encrypted with the specified key.
+    pub fn issue_with_type(
+        ticket_type: BdAuthTicketType,
+        message_type: AuthMessageType,
+        holder: TicketHolder,
+        ticket_key: SessionKey,
+        key_store: &ThreadSafeBackendPrivateKeyStorage,
+    ) -> Self {
+        let now = Utc::now();
+        let issued = (now.timestamp() % (u32::MAX as i64)) as u32;
+        let expires_i64 = now.timestamp() + TICKET_ISSUE_LENGTH;
+        let expires = ((expires_i64) % (u32::MAX as i64)) as u32;
+
+        let ticket = AuthTicket {
+            ticket_type,
+            title: holder.title,
+            time_issued: issued,
+            time_expires: expires,
+            license_id: 1234u64,
+            user_id: holder.user_id,
+            username: holder.username,
+            session_key: holder.session_key,
+        };
+
+        let proof = ClientOpaqueAuthProof {
+            title: ticket.title,
+            time_expires: expires_i64,
+            license_id: ticket.license_id,
+            user_id: ticket.user_id,
+            session_key: ticket.session_key.clone(),
+            username: String::from(&ticket.username),
+            clock_skew: holder.clock_skew.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+            anonymous: holder.anonymous,
+        };
+        let serialized_proof_data =
+            (ticket_type != BdAuthTicketType::UserToHost).then(|| proof.serialize(key_store));
+
+        TicketAuthResponse {
+            message_type,
+            ticket,
+            ticket_key,
+            serialized_proof_data,
+        }
+    }
+}
+
+impl AuthResponse for TicketAuthResponse {
+    fn message_type(&self) -> AuthMessageType {
+        self.message_type
+    }
+
+    fn error_code(&self) -> BdErrorCode {
+        BdErrorCode::AuthNoError
+    }
+
+    fn write_auth_data(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        let seed = generate_iv_seed();
+        writer.write_u32(seed)?;
+
+        let mut ticket_buf = Vec::new();
+        {
+            let mut ticket_writer = BdWriter::new(&mut ticket_buf);
+            self.ticket.serialize(&mut ticket_writer)?;
+        }
+
+        let iv = generate_iv_from_seed(seed);
+        let ticket_buf_len = ticket_buf.len();
+        ticket_buf.resize(
+            ticket_buf_len.next_multiple_of(des::TdesEde3::block_size()),
+            0,
+        );
+
+        encrypt_buffer_in_place(&mut ticket_buf, &self.ticket_key, &iv);
+        writer.write_bytes(ticket_buf.as_slice())?;
+
+        if let Some(serialized_proof_data) = &self.serialized_proof_data {
+            writer.write_bytes(serialized_proof_data)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
 ﻿use crate::auth::key_store::BackendPrivateKeyStorage;
+use crate::crypto::SessionKey;
 use crate::domain::title::Title;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use num_traits::{FromPrimitive, ToPrimitive};
 use snafu::{ensure, Snafu};
 use std::error::Error;
 use std::io::{Cursor, Read, Write};
+use zeroize::Zeroize;
 
 /// This represents data that is opaque data that is given to the client that it can use to
 /// authenticate to the lobby server.
@@ -16,12 +18,22 @@ pub struct ClientOpaqueAuthProof {
     pub time_expires: i64,
     pub license_id: u64,
     pub user_id: u64,
-    pub session_key: [u8; 24],
+    pub session_key: SessionKey,
     pub username: String,
+    /// Seconds the client clock was ahead of the server when authenticating.
+    /// Zero when it was not measured.
+    pub clock_skew: i32,
+    /// Whether the user authenticated as guest without any account.
+    pub anonymous: bool,
 }
 
 const MAGIC: u64 = 0xC0FFEEFFEEAA1337;
 
+/// Usernames are shorter than their 64 byte buffer, so its last byte used to always be zero.
+/// It now holds flags instead, which keeps proofs issued before readable.
+const USERNAME_LEN: usize = 63;
+const FLAG_ANONYMOUS: u8 = 0x1;
+
 #[derive(Debug, Snafu)]
 enum AuthProofError {
     #[snafu(display("The title id is unknown (value={title_id})"))]
@@ -43,16 +55,23 @@ impl ClientOpaqueAuthProof {
         cursor.write_i64::<LittleEndian>(self.time_expires).unwrap();
         cursor.write_u64::<LittleEndian>(self.license_id).unwrap();
         cursor.write_u64::<LittleEndian>(self.user_id).unwrap();
-        cursor.write_all(&self.session_key).unwrap();
+        cursor.write_all(self.session_key.as_slice()).unwrap();
 
         let username_bytes = self.username.as_bytes();
+        let username_bytes = &username_bytes[..username_bytes.len().min(USERNAME_LEN)];
         cursor.write_all(username_bytes).unwrap();
-        for _ in username_bytes.len()..64 {
+        for _ in username_bytes.len()..USERNAME_LEN {
             cursor.write_u8(0).unwrap();
         }
 
-        // Pad
-        cursor.write_u32::<LittleEndian>(0).unwrap();
+        let mut flags = 0u8;
+        if self.anonymous {
+            flags |= FLAG_ANONYMOUS;
+        }
+        cursor.write_u8(flags).unwrap();
+
+        // Used to be padding, so proofs issued before always have zero here
+        cursor.write_i32::<LittleEndian>(self.clock_skew).unwrap();
 
         debug_assert_eq!(vec.len(), 128usize);
 
@@ -81,7 +100,7 @@ impl ClientOpaqueAuthProof {
 
         ensure!(decryption_successful, UnknownKeySnafu {});
 
-        let mut cursor = Cursor::new(last_buf);
+        let mut cursor = Cursor::new(&last_buf[..]);
 
         // Skip magic
         cursor.set_position(8);
@@ -93,17 +112,24 @@ impl ClientOpaqueAuthProof {
         let license_id = cursor.read_u64::<LittleEndian>()?;
         let user_id = cursor.read_u64::<LittleEndian>()?;
 
-        let mut session_key: [u8; 24] = [0; 24];
-        cursor.read_exact(&mut session_key)?;
+        let mut session_key = SessionKey::new([0; 24]);
+        cursor.read_exact(&mut *session_key)?;
 
-        let mut username_buffer: [u8; 64] = [0; 64];
+        let mut username_buffer: [u8; USERNAME_LEN] = [0; USERNAME_LEN];
         cursor.read_exact(&mut username_buffer)?;
-        let username_end = username_buffer.iter().position(|&v| v == 0).unwrap_or(64);
+        let username_end = username_buffer
+            .iter()
+            .position(|&v| v == 0)
+            .unwrap_or(USERNAME_LEN);
 
         let username = String::from_utf8(Vec::from(&username_buffer[0..username_end]))?;
 
-        // Pad
-        cursor.read_u32::<LittleEndian>()?;
+        let flags = cursor.read_u8()?;
+
+        let clock_skew = cursor.read_i32::<LittleEndian>()?;
+
+        // The decrypted proof contains the session key
+        last_buf.zeroize();
 
         Ok(ClientOpaqueAuthProof {
             title,
@@ -112,6 +138,37 @@ impl ClientOpaqueAuthProof {
             user_id,
             session_key,
             username,
+            clock_skew,
+            anonymous: flags & FLAG_ANONYMOUS != 0,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::key_store::InMemoryKeyStore;
+
+    #[test]
+    fn anonymous_flag_does_not_affect_username() {
+        let key_store = InMemoryKeyStore::new();
+        let proof = ClientOpaqueAuthProof {
+            title: Title::T6Pc,
+            time_expires: 1234,
+            license_id: 1,
+            user_id: 2,
+            session_key: [3; 24].into(),
+            username: "a".repeat(USERNAME_LEN),
+            clock_skew: -5,
+            anonymous: true,
+        };
+
+        let mut serialized = proof.serialize(&key_store);
+        let deserialized = ClientOpaqueAuthProof::deserialize(&mut serialized, &key_store).unwrap();
+
+        assert_eq!(deserialized.session_key, proof.session_key);
+        assert_eq!(deserialized.username, proof.username);
+        assert_eq!(deserialized.clock_skew, -5);
+        assert!(deserialized.anonymous);
+    }
+}
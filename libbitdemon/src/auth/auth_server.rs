@@ -1,7 +1,5 @@
-use crate::auth::auth_handler::steam::SteamAuthHandler;
 use crate::auth::auth_handler::AuthMessageType;
 use crate::auth::auth_handler::ThreadSafeAuthHandler;
-use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
 use crate::auth::response::{AuthResponse, AuthResponseWithOnlyCode};
 use crate::messaging::bd_message::BdMessage;
 use crate::messaging::bd_response::ResponseCreator;
@@ -19,18 +17,19 @@ pub struct AuthServer {
     auth_handlers: RwLock<HashMap<AuthMessageType, Arc<ThreadSafeAuthHandler>>>,
 }
 
+impl Default for AuthServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AuthServer {
-    pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>) -> Self {
-        let auth_server = AuthServer {
+    /// Creates an auth server without any handlers.
+    /// Requests of message types without a handler are answered with an illegal operation error.
+    pub fn new() -> Self {
+        AuthServer {
             auth_handlers: RwLock::new(HashMap::new()),
-        };
-
-        auth_server.add_handler(
-            AuthMessageType::SteamForMmpRequest,
-            Arc::new(SteamAuthHandler::new(key_store)),
-        );
-
-        auth_server
+        }
     }
 
     pub fn add_handler(&self, message_type: AuthMessageType, handler: Arc<ThreadSafeAuthHandler>) {
@@ -40,6 +39,13 @@ impl AuthServer {
             .unwrap()
             .insert(message_type, handler);
     }
+
+    pub fn has_handler(&self, message_type: AuthMessageType) -> bool {
+        self.auth_handlers
+            .read()
+            .unwrap()
+            .contains_key(&message_type)
+    }
 }
 
 #[derive(Debug, Snafu)]
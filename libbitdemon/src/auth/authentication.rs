@@ -1,8 +1,15 @@
+use crate::crypto::SessionKey;
 use crate::domain::title::Title;
 
+#[derive(Clone)]
 pub struct SessionAuthentication {
     pub user_id: u64,
     pub username: String,
-    pub session_key: [u8; 24],
+    pub session_key: SessionKey,
     pub title: Title,
+    /// How many seconds the clock of the client is ahead of the server.
+    /// Only known when the client sent its time when authenticating.
+    pub clock_skew: Option<i32>,
+    /// Whether the user authenticated as guest without any account.
+    pub anonymous: bool,
 }
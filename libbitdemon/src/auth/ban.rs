@@ -0,0 +1,43 @@
+use crate::auth::identity::PlatformIdentity;
+
+pub type ThreadSafeBanStore = dyn BanStore + Sync + Send;
+
+/// Decides which users are refused when authenticating.
+///
+/// Users are checked before a ticket is issued to them,
+/// so banned users cannot reach the lobby server at all.
+pub trait BanStore {
+    /// Checks whether the account is banned.
+    fn is_user_banned(&self, user_id: u64) -> bool;
+
+    /// Checks whether the account of the user on an external platform is banned,
+    /// regardless of which account it belongs to.
+    fn is_platform_user_banned(&self, identity: &PlatformIdentity) -> bool;
+
+    /// Checks whether a user authenticating as the account is banned,
+    /// either by account or by the platform identity it authenticated with.
+    fn is_banned(&self, user_id: u64, identity: Option<&PlatformIdentity>) -> bool {
+        identity.is_some_and(|identity| self.is_platform_user_banned(identity))
+            || self.is_user_banned(user_id)
+    }
+}
+
+/// Bans nobody.
+#[derive(Default)]
+pub struct NoBanStore {}
+
+impl BanStore for NoBanStore {
+    fn is_user_banned(&self, _user_id: u64) -> bool {
+        false
+    }
+
+    fn is_platform_user_banned(&self, _identity: &PlatformIdentity) -> bool {
+        false
+    }
+}
+
+impl NoBanStore {
+    pub fn new() -> NoBanStore {
+        NoBanStore {}
+    }
+}
@@ -0,0 +1,50 @@
+use num_derive::{FromPrimitive, ToPrimitive};
+use std::error::Error;
+
+/// The platform a user authenticated with.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum IdentityPlatform {
+    Steam = 1,
+    Wii = 2,
+    WiiU = 3,
+    N3ds = 4,
+    Abaccounts = 5,
+}
+
+/// The account of a user on an external platform.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub struct PlatformIdentity {
+    /// The platform the account belongs to.
+    pub platform: IdentityPlatform,
+    /// The id of the user on that platform, like a Steam id.
+    pub platform_user_id: u64,
+}
+
+pub type ThreadSafeAccountResolver = dyn AccountResolver + Sync + Send;
+
+/// Maps identities of external platforms to the internal accounts that lobby services work with.
+///
+/// Multiple identities may belong to the same account,
+/// which allows users to share their data between platforms.
+pub trait AccountResolver {
+    /// Retrieves the id of the account an identity belongs to.
+    /// Identities that were not seen before are expected to get an account of their own.
+    fn resolve_account(&self, identity: &PlatformIdentity) -> Result<u64, Box<dyn Error>>;
+}
+
+/// Uses the id of the user on the platform as account id without any mapping.
+#[derive(Default)]
+pub struct PlatformAccountResolver {}
+
+impl AccountResolver for PlatformAccountResolver {
+    fn resolve_account(&self, identity: &PlatformIdentity) -> Result<u64, Box<dyn Error>> {
+        Ok(identity.platform_user_id)
+    }
+}
+
+impl PlatformAccountResolver {
+    pub fn new() -> PlatformAccountResolver {
+        PlatformAccountResolver {}
+    }
+}
@@ -6,15 +6,19 @@ use rand::Rng;
 use snafu::Snafu;
 use std::error::Error;
 use std::sync::RwLock;
+use zeroize::ZeroizeOnDrop;
 
 pub type AesKey = [u8; 32];
 pub type AesIv = [u8; 16];
 type Aes256CbcEnc = cbc::Encryptor<Aes256>;
 type Aes256CbcDec = cbc::Decryptor<Aes256>;
 
+/// Wiped from memory when dropped, since anyone knowing it can forge auth proofs.
+#[derive(ZeroizeOnDrop)]
 pub struct BackendPrivateKey {
     aes_key: AesKey,
     aes_iv: AesIv,
+    valid_until: i64,
 }
 
 #[derive(Debug, Snafu)]
@@ -22,6 +26,19 @@ pub struct BackendPrivateKey {
 struct BufferSizeError {}
 
 impl BackendPrivateKey {
+    pub fn new(aes_key: AesKey, aes_iv: AesIv, valid_until: i64) -> BackendPrivateKey {
+        BackendPrivateKey {
+            aes_key,
+            aes_iv,
+            valid_until,
+        }
+    }
+
+    /// The seconds timestamp after which the key no longer decrypts proofs.
+    pub fn valid_until(&self) -> i64 {
+        self.valid_until
+    }
+
     pub fn encrypt_data(&self, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
         let cipher = Aes256CbcEnc::new(&self.aes_key.into(), &self.aes_iv.into());
         cipher
@@ -45,6 +62,14 @@ impl BackendPrivateKey {
 pub trait BackendPrivateKeyStorage {
     fn get_current_key(&self) -> BackendPrivateKey;
     fn get_valid_keys(&self) -> Vec<BackendPrivateKey>;
+
+    /// Replaces the current key with a new one, regardless of its remaining lifetime.
+    /// Proofs encrypted with the previous key stay valid until it expires.
+    fn rotate_key(&self) -> BackendPrivateKey;
+
+    /// Removes the material of all keys that expired.
+    /// Returns the amount of removed keys.
+    fn evict_expired_keys(&self) -> usize;
 }
 
 pub type ThreadSafeBackendPrivateKeyStorage = dyn BackendPrivateKeyStorage + Sync + Send;
@@ -99,7 +124,52 @@ impl BackendPrivateKeyStorage for InMemoryKeyStore {
 
         info!("Current key reached end of lifetime, creating a new one");
 
-        state.key_index = (state.key_index + 1) % IN_MEMORY_KEY_STORAGE_COUNT;
+        state.next_key(now)
+    }
+
+    fn get_valid_keys(&self) -> Vec<BackendPrivateKey> {
+        let now = chrono::Utc::now().timestamp();
+        let state = self.state.read().unwrap();
+
+        state
+            .keys
+            .iter()
+            .filter(|key| key.valid_until >= now)
+            .map(|key| key.export())
+            .collect()
+    }
+
+    fn rotate_key(&self) -> BackendPrivateKey {
+        let now = chrono::Utc::now().timestamp();
+
+        info!("Rotating current key");
+
+        self.state.write().unwrap().next_key(now)
+    }
+
+    fn evict_expired_keys(&self) -> usize {
+        let now = chrono::Utc::now().timestamp();
+        let mut state = self.state.write().unwrap();
+
+        let expired_keys = state
+            .keys
+            .iter_mut()
+            .filter(|key| key.valid_until != 0 && key.valid_until < now);
+
+        let mut evicted_count = 0;
+        for key in expired_keys {
+            *key = InMemoryKey::empty();
+            evicted_count += 1;
+        }
+
+        evicted_count
+    }
+}
+
+impl InMemoryKeyState {
+    /// Replaces the oldest key with a new one and makes it the current key.
+    fn next_key(&mut self, now: i64) -> BackendPrivateKey {
+        self.key_index = (self.key_index + 1) % IN_MEMORY_KEY_STORAGE_COUNT;
 
         let mut aes_key = [0u8; 32];
         let mut aes_iv = [0u8; 16];
@@ -111,23 +181,10 @@ impl BackendPrivateKeyStorage for InMemoryKeyStore {
             valid_until: now + IN_MEMORY_KEY_LIFESPAN,
         };
 
-        let key_index = state.key_index;
-        state.keys[key_index] = next_key;
+        self.keys[self.key_index] = next_key;
 
         next_key.export()
     }
-
-    fn get_valid_keys(&self) -> Vec<BackendPrivateKey> {
-        let now = chrono::Utc::now().timestamp();
-        let state = self.state.read().unwrap();
-
-        state
-            .keys
-            .iter()
-            .filter(|key| key.valid_until >= now)
-            .map(|key| key.export())
-            .collect()
-    }
 }
 
 #[derive(Copy, Clone)]
@@ -150,6 +207,28 @@ impl InMemoryKey {
         BackendPrivateKey {
             aes_key: self.aes_key,
             aes_iv: self.aes_iv,
+            valid_until: self.valid_until,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotated_key_becomes_current_and_expired_keys_are_evicted() {
+        let key_store = InMemoryKeyStore::new();
+
+        let first_key = key_store.get_current_key();
+        let rotated_key = key_store.rotate_key();
+        assert_ne!(first_key.aes_key, rotated_key.aes_key);
+        assert_eq!(key_store.get_current_key().aes_key, rotated_key.aes_key);
+        assert_eq!(key_store.get_valid_keys().len(), 2);
+
+        key_store.state.write().unwrap().keys[1].valid_until = 1;
+        assert_eq!(key_store.evict_expired_keys(), 1);
+        assert_eq!(key_store.evict_expired_keys(), 0);
+        assert_eq!(key_store.get_valid_keys().len(), 1);
+    }
+}
@@ -1,7 +1,10 @@
-﻿pub mod auth_handler;
+﻿pub mod account;
+pub mod auth_handler;
 pub mod auth_proof;
 pub mod auth_server;
 pub mod authentication;
+pub mod ban;
+pub mod identity;
 pub mod key_store;
 pub mod response;
 mod result;
@@ -1,4 +1,5 @@
-﻿use crate::domain::title::Title;
+﻿use crate::crypto::SessionKey;
+use crate::domain::title::Title;
 use crate::messaging::bd_serialization::BdSerialize;
 use crate::messaging::bd_writer::BdWriter;
 use crate::messaging::StreamMode;
@@ -23,7 +24,7 @@ pub struct AuthTicket {
     pub license_id: u64,
     pub user_id: u64,
     pub username: String,
-    pub session_key: [u8; 24],
+    pub session_key: SessionKey,
 }
 
 const MAGIC_NUMBER: u32 = 0xEFBDADDE;
@@ -0,0 +1,257 @@
+//! Helpers to check captured traffic against the ciphers of this crate.
+//!
+//! When adding a new title, a frame captured from the original client or server
+//! can be decrypted with the key and seed that the key derivation of the title is expected to produce.
+//! If the result matches the known plaintext, the derivation is correct.
+
+use crate::crypto::{calculate_hmac, verify_hmac, SessionCipher};
+use snafu::{ensure, Snafu};
+
+/// Length of the length field preceding every frame.
+const FRAME_LENGTH_LEN: usize = 4;
+/// Length of the encryption flag and the iv seed preceding the encrypted data.
+const ENCRYPTION_HEADER_LEN: usize = 5;
+/// Length of the hmac or signature at the start of the encrypted data.
+const CHECKSUM_LEN: usize = 4;
+const RESPONSE_SIGNATURE: u32 = 0xDEADBEEF;
+
+/// Which side sent a captured frame.
+/// Clients prefix their encrypted data with a hmac, servers with a fixed signature.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum FrameDirection {
+    ClientToServer,
+    ServerToClient,
+}
+
+#[derive(Debug, Snafu, Eq, PartialEq)]
+pub enum InteropError {
+    #[snafu(display("Frame is too short to contain any data (len={len})"))]
+    TruncatedFrame { len: usize },
+    #[snafu(display(
+        "Frame length field does not match the frame (declared={declared} actual={actual})"
+    ))]
+    LengthMismatch { declared: usize, actual: usize },
+    #[snafu(display("Encrypted data is not aligned to the cipher block size (len={len} block_size={block_size})"))]
+    MisalignedFrame { len: usize, block_size: usize },
+    #[snafu(display(
+        "Frame was encrypted with another seed (expected={expected:#010x} actual={actual:#010x})"
+    ))]
+    SeedMismatch { expected: u32, actual: u32 },
+    #[snafu(display("Frame hmac mismatch, expected={expected} actual={actual}"))]
+    InvalidHmac { expected: u32, actual: u32 },
+    #[snafu(display("Frame signature mismatch (signature={signature:#010x})"))]
+    InvalidSignature { signature: u32 },
+    #[snafu(display("Decrypted data differs from the expected plaintext at offset {offset}"))]
+    PlaintextMismatch { offset: usize },
+}
+
+/// A frame as it was captured on the wire, including its length field.
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    direction: FrameDirection,
+    cipher: Option<SessionCipher>,
+    seed: Option<u32>,
+    data: Vec<u8>,
+}
+
+impl CapturedFrame {
+    pub fn parse(frame: &[u8], direction: FrameDirection) -> Result<Self, InteropError> {
+        ensure!(
+            frame.len() > FRAME_LENGTH_LEN,
+            TruncatedFrameSnafu { len: frame.len() }
+        );
+
+        let declared = u32::from_le_bytes(frame[..FRAME_LENGTH_LEN].try_into().unwrap()) as usize;
+        let body = &frame[FRAME_LENGTH_LEN..];
+        ensure!(
+            declared == body.len(),
+            LengthMismatchSnafu {
+                declared,
+                actual: body.len()
+            }
+        );
+
+        let Some(cipher) = SessionCipher::from_frame_flag(body[0]) else {
+            return Ok(CapturedFrame {
+                direction,
+                cipher: None,
+                seed: None,
+                data: body[1..].to_vec(),
+            });
+        };
+
+        ensure!(
+            body.len() >= ENCRYPTION_HEADER_LEN + cipher.block_size(),
+            TruncatedFrameSnafu { len: frame.len() }
+        );
+
+        let data = &body[ENCRYPTION_HEADER_LEN..];
+        ensure!(
+            data.len().is_multiple_of(cipher.block_size()),
+            MisalignedFrameSnafu {
+                len: data.len(),
+                block_size: cipher.block_size()
+            }
+        );
+
+        Ok(CapturedFrame {
+            direction,
+            cipher: Some(cipher),
+            seed: Some(u32::from_le_bytes(body[1..5].try_into().unwrap())),
+            data: data.to_vec(),
+        })
+    }
+
+    /// The cipher announced by the frame, or `None` if it was sent without encryption.
+    pub fn cipher(&self) -> Option<SessionCipher> {
+        self.cipher
+    }
+
+    /// The iv seed the frame was encrypted with, or `None` if it was sent without encryption.
+    pub fn seed(&self) -> Option<u32> {
+        self.seed
+    }
+
+    /// Decrypts the frame and checks its hmac or signature.
+    /// Returns the data following it, including the zero padding added for the cipher.
+    pub fn decrypt(&self, key: &[u8; 24]) -> Result<Vec<u8>, InteropError> {
+        let (Some(cipher), Some(seed)) = (self.cipher, self.seed) else {
+            return Ok(self.data.clone());
+        };
+
+        let mut data = self.data.clone();
+        cipher.decrypt_in_place(&mut data, key, seed).map_err(|_| {
+            InteropError::MisalignedFrame {
+                len: data.len(),
+                block_size: cipher.block_size(),
+            }
+        })?;
+
+        let checksum = u32::from_le_bytes(data[..CHECKSUM_LEN].try_into().unwrap());
+        match self.direction {
+            FrameDirection::ClientToServer => {
+                // Hmac does not include the message type byte that follows
+                let hmac_data = &data[CHECKSUM_LEN + 1..];
+                ensure!(
+                    verify_hmac(hmac_data, key, checksum),
+                    InvalidHmacSnafu {
+                        expected: calculate_hmac(hmac_data, key),
+                        actual: checksum
+                    }
+                );
+            }
+            FrameDirection::ServerToClient => {
+                ensure!(
+                    checksum == RESPONSE_SIGNATURE,
+                    InvalidSignatureSnafu {
+                        signature: checksum
+                    }
+                );
+            }
+        }
+
+        Ok(data.split_off(CHECKSUM_LEN))
+    }
+
+    /// Decrypts the frame with the known `key` and `seed` and compares it to the known plaintext.
+    /// Zero padding that the cipher requires may follow the plaintext.
+    pub fn verify(
+        &self,
+        key: &[u8; 24],
+        seed: u32,
+        expected_plaintext: &[u8],
+    ) -> Result<(), InteropError> {
+        if let Some(actual) = self.seed {
+            ensure!(
+                actual == seed,
+                SeedMismatchSnafu {
+                    expected: seed,
+                    actual
+                }
+            );
+        }
+
+        let data = self.decrypt(key)?;
+        let block_size = self.cipher.map(|cipher| cipher.block_size()).unwrap_or(1);
+
+        if let Some(offset) = data
+            .iter()
+            .zip(expected_plaintext)
+            .position(|(actual, expected)| actual != expected)
+        {
+            return PlaintextMismatchSnafu { offset }.fail();
+        }
+
+        let padding = data.get(expected_plaintext.len()..).unwrap_or_default();
+        ensure!(
+            data.len() >= expected_plaintext.len()
+                && padding.len() < block_size
+                && padding.iter().all(|b| *b == 0),
+            PlaintextMismatchSnafu {
+                offset: data.len().min(expected_plaintext.len())
+            }
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 24] = [7; 24];
+    const SEED: u32 = 0x1234_5678;
+
+    fn server_frame(cipher: SessionCipher, plaintext: &[u8]) -> Vec<u8> {
+        let mut data = RESPONSE_SIGNATURE.to_le_bytes().to_vec();
+        data.extend_from_slice(plaintext);
+        data.resize(data.len().next_multiple_of(cipher.block_size()), 0);
+        cipher.encrypt_in_place(&mut data, &KEY, SEED).unwrap();
+
+        let mut frame = ((data.len() + ENCRYPTION_HEADER_LEN) as u32)
+            .to_le_bytes()
+            .to_vec();
+        frame.push(cipher.frame_flag());
+        frame.extend_from_slice(&SEED.to_le_bytes());
+        frame.extend_from_slice(&data);
+
+        frame
+    }
+
+    #[test]
+    fn verifies_frames_of_both_ciphers() {
+        for cipher in [SessionCipher::Tdes, SessionCipher::Aes] {
+            let frame = CapturedFrame::parse(
+                &server_frame(cipher, b"hello"),
+                FrameDirection::ServerToClient,
+            )
+            .unwrap();
+
+            assert_eq!(frame.cipher(), Some(cipher));
+            assert_eq!(frame.verify(&KEY, SEED, b"hello"), Ok(()));
+        }
+    }
+
+    #[test]
+    fn reports_wrong_derivations() {
+        let frame = CapturedFrame::parse(
+            &server_frame(SessionCipher::Tdes, b"hello"),
+            FrameDirection::ServerToClient,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            frame.verify(&[8; 24], SEED, b"hello"),
+            Err(InteropError::InvalidSignature { .. })
+        ));
+        assert!(matches!(
+            frame.verify(&KEY, SEED + 1, b"hello"),
+            Err(InteropError::SeedMismatch { .. })
+        ));
+        assert_eq!(
+            frame.verify(&KEY, SEED, b"help"),
+            Err(InteropError::PlaintextMismatch { offset: 3 })
+        );
+    }
+}
@@ -1,17 +1,87 @@
-use des::cipher::block_padding::ZeroPadding;
+pub mod interop;
+pub mod rsa_key;
+
+use des::cipher::block_padding::NoPadding;
 use des::cipher::KeyIvInit;
-use des::cipher::{BlockModeDecrypt, BlockModeEncrypt, BlockSizeUser};
+use des::cipher::{BlockModeDecrypt, BlockModeEncrypt};
 use hmac::{Hmac, KeyInit, Mac};
 use rand::Rng;
 use sha1::Digest as Sha1Digest;
 use sha1::Sha1;
-use snafu::Snafu;
+use snafu::{ensure, Snafu};
 use std::error::Error;
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use subtle::ConstantTimeEq;
 use tiger::Digest as TigerDigest;
 use tiger::Tiger;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 type TdesCbcEnc = cbc::Encryptor<des::TdesEde3>;
 type TdesCbcDec = cbc::Decryptor<des::TdesEde3>;
+type Aes192CbcEnc = cbc::Encryptor<aes::Aes192>;
+type Aes192CbcDec = cbc::Decryptor<aes::Aes192>;
+
+/// The key messages of a session are encrypted with.
+///
+/// It is wiped from memory when dropped and only compared in constant time,
+/// since sessions of a public server may live for a long time.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct SessionKey([u8; 24]);
+
+impl SessionKey {
+    pub fn new(key: [u8; 24]) -> SessionKey {
+        SessionKey(key)
+    }
+
+    /// Generates a random key without leaving a copy of it behind.
+    pub fn generate() -> SessionKey {
+        let mut key = SessionKey([0; 24]);
+        rand::rng().fill_bytes(&mut key.0);
+
+        key
+    }
+}
+
+impl From<[u8; 24]> for SessionKey {
+    fn from(key: [u8; 24]) -> Self {
+        SessionKey::new(key)
+    }
+}
+
+impl Deref for SessionKey {
+    type Target = [u8; 24];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SessionKey {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl PartialEq for SessionKey {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SessionKey {}
+
+impl Debug for SessionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SessionKey({:08x})", key_fingerprint(&self.0))
+    }
+}
+
+/// Compares secrets without revealing through timing how many of their leading bytes match.
+/// Buffers of different lengths are never equal.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
 
 pub fn generate_iv_seed() -> u32 {
     rand::rng().next_u32()
@@ -27,42 +97,277 @@ pub fn generate_iv_from_seed(seed: u32) -> [u8; 8] {
     b
 }
 
+/// Derives the IV for AES the same way as for 3DES, but takes as many bytes as the AES block size.
+pub fn generate_aes_iv_from_seed(seed: u32) -> [u8; 16] {
+    let mut tiger = Tiger::new();
+    TigerDigest::update(&mut tiger, seed.to_le_bytes());
+    let a: [u8; 24] = tiger.finalize().into();
+    let mut b: [u8; 16] = [0; 16];
+    b.copy_from_slice(&a[0..16]);
+
+    b
+}
+
+/// The cipher the traffic of a session is encrypted with.
+///
+/// Clients announce the cipher with the encryption flag of each frame they send,
+/// and responses are encrypted with the cipher the client used last.
+/// The 24 byte session key is used as a 3DES key or as an AES-192 key respectively.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum SessionCipher {
+    /// 3DES-CBC, which is what most titles use.
+    #[default]
+    Tdes,
+    /// AES-192-CBC, which is used by some later titles.
+    Aes,
+}
+
+const AES_FRAME_FLAG: u8 = 2;
+
+impl SessionCipher {
+    /// Reads the encryption flag of a frame.
+    /// Returns `None` for frames that are not encrypted.
+    pub fn from_frame_flag(flag: u8) -> Option<SessionCipher> {
+        match flag {
+            0 => None,
+            AES_FRAME_FLAG => Some(SessionCipher::Aes),
+            // Older clients may set any other non-zero value
+            _ => Some(SessionCipher::Tdes),
+        }
+    }
+
+    pub fn frame_flag(&self) -> u8 {
+        match self {
+            SessionCipher::Tdes => 1,
+            SessionCipher::Aes => AES_FRAME_FLAG,
+        }
+    }
+
+    pub fn block_size(&self) -> usize {
+        match self {
+            SessionCipher::Tdes => CIPHER_BLOCK_SIZE,
+            SessionCipher::Aes => AES_BLOCK_SIZE,
+        }
+    }
+
+    /// Encrypts a buffer that is aligned to the block size with the IV derived from the seed.
+    pub fn encrypt_in_place(
+        &self,
+        buf: &mut [u8],
+        key: &[u8; 24],
+        seed: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            SessionCipher::Tdes => {
+                TdesCbcStream::new(key, &generate_iv_from_seed(seed)).encrypt_in_place(buf)
+            }
+            SessionCipher::Aes => {
+                let len = self.ensure_aligned(buf)?;
+                Aes192CbcEnc::new(key.into(), &generate_aes_iv_from_seed(seed).into())
+                    .encrypt_padded::<NoPadding>(buf, len)
+                    .map(|_| ())
+                    .map_err(|_| MisalignedBufferSnafu { len }.build().into())
+            }
+        }
+    }
+
+    /// Decrypts a buffer that is aligned to the block size with the IV derived from the seed.
+    pub fn decrypt_in_place(
+        &self,
+        buf: &mut [u8],
+        key: &[u8; 24],
+        seed: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            SessionCipher::Tdes => {
+                TdesCbcStream::new(key, &generate_iv_from_seed(seed)).decrypt_in_place(buf)
+            }
+            SessionCipher::Aes => {
+                let len = self.ensure_aligned(buf)?;
+                Aes192CbcDec::new(key.into(), &generate_aes_iv_from_seed(seed).into())
+                    .decrypt_padded::<NoPadding>(buf)
+                    .map(|_| ())
+                    .map_err(|_| MisalignedBufferSnafu { len }.build().into())
+            }
+        }
+    }
+
+    fn ensure_aligned(&self, buf: &[u8]) -> Result<usize, CipherError> {
+        let len = buf.len();
+        ensure!(
+            len.is_multiple_of(self.block_size()),
+            MisalignedBufferSnafu { len }
+        );
+
+        Ok(len)
+    }
+}
+
+/// The size of the blocks the session cipher works on.
+pub const CIPHER_BLOCK_SIZE: usize = 8;
+/// The size of the blocks of the AES session cipher.
+pub const AES_BLOCK_SIZE: usize = 16;
+
+/// Encrypts or decrypts data in 3DES-CBC across multiple calls, without taking ownership of it.
+///
+/// The last cipher block of each call is the IV of the next one,
+/// so data can be processed in pieces as long as each piece is a multiple of the block size.
+/// The result is the same as processing all pieces at once.
+/// Its copy of the key is wiped from memory when dropped.
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct TdesCbcStream {
+    key: [u8; 24],
+    iv: [u8; CIPHER_BLOCK_SIZE],
+}
+
+impl TdesCbcStream {
+    pub fn new(key: &[u8; 24], iv: &[u8; CIPHER_BLOCK_SIZE]) -> TdesCbcStream {
+        TdesCbcStream { key: *key, iv: *iv }
+    }
+
+    pub fn encrypt_in_place(&mut self, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
+        let len = Self::ensure_aligned(buf)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        TdesCbcEnc::new(&self.key.into(), &self.iv.into())
+            .encrypt_padded::<NoPadding>(buf, len)
+            .map_err(|_| MisalignedBufferSnafu { len }.build())?;
+        self.iv.copy_from_slice(&buf[len - CIPHER_BLOCK_SIZE..]);
+
+        Ok(())
+    }
+
+    pub fn decrypt_in_place(&mut self, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
+        let len = Self::ensure_aligned(buf)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        let mut next_iv = [0u8; CIPHER_BLOCK_SIZE];
+        next_iv.copy_from_slice(&buf[len - CIPHER_BLOCK_SIZE..]);
+
+        TdesCbcDec::new(&self.key.into(), &self.iv.into())
+            .decrypt_padded::<NoPadding>(buf)
+            .map_err(|_| MisalignedBufferSnafu { len }.build())?;
+        self.iv = next_iv;
+
+        Ok(())
+    }
+
+    fn ensure_aligned(buf: &[u8]) -> Result<usize, CipherError> {
+        let len = buf.len();
+        ensure!(
+            len.is_multiple_of(CIPHER_BLOCK_SIZE),
+            MisalignedBufferSnafu { len }
+        );
+
+        Ok(len)
+    }
+}
+
 pub fn encrypt_buffer_in_place(buf: &mut Vec<u8>, key: &[u8; 24], iv: &[u8; 8]) {
     let buf_len = buf.len();
-    buf.resize(buf_len.next_multiple_of(des::TdesEde3::block_size()), 0);
-
-    let encrypted = TdesCbcEnc::new(key.into(), iv.into())
-        .encrypt_padded::<ZeroPadding>(buf.as_mut_slice(), buf_len)
-        .unwrap();
+    buf.resize(buf_len.next_multiple_of(CIPHER_BLOCK_SIZE), 0);
 
-    debug_assert_eq!(encrypted.len(), buf.len());
+    TdesCbcStream::new(key, iv)
+        .encrypt_in_place(buf.as_mut_slice())
+        .expect("padded buffer to be aligned");
 }
 
 #[derive(Debug, Snafu)]
-#[snafu(display("Decryption failed due to wrong padding in the decryption buffer"))]
-struct DecryptionError {}
+enum CipherError {
+    #[snafu(display("The buffer length is not a multiple of the cipher block size (len={len})"))]
+    MisalignedBufferError { len: usize },
+    #[snafu(display(
+        "The data length does not match the padded buffer (data_len={data_len} buf_len={buf_len})"
+    ))]
+    DataLengthMismatchError { data_len: usize, buf_len: usize },
+}
 
+/// Decrypts the buffer without removing the padding,
+/// since the zero padding cannot be told apart from data ending with zeros.
 pub fn decrypt_buffer_in_place(
     buf: &mut [u8],
     key: &[u8; 24],
     iv: &[u8; 8],
 ) -> Result<(), Box<dyn Error>> {
-    TdesCbcDec::new(key.into(), iv.into())
-        .decrypt_padded::<ZeroPadding>(buf)
-        .map(|_| ())
-        .map_err(|_| DecryptionSnafu {}.build().into())
+    TdesCbcStream::new(key, iv).decrypt_in_place(buf)
+}
+
+/// Decrypts a buffer that was padded by [`encrypt_buffer_in_place`]
+/// and truncates it back to the length of the data, which must be known from the payload.
+pub fn decrypt_padded_buffer_in_place(
+    buf: &mut Vec<u8>,
+    data_len: usize,
+    key: &[u8; 24],
+    iv: &[u8; 8],
+) -> Result<(), Box<dyn Error>> {
+    let buf_len = buf.len();
+    ensure!(
+        data_len.next_multiple_of(CIPHER_BLOCK_SIZE) == buf_len,
+        DataLengthMismatchSnafu { data_len, buf_len }
+    );
+
+    decrypt_buffer_in_place(buf.as_mut_slice(), key, iv)?;
+    buf.truncate(data_len);
+
+    Ok(())
+}
+
+/// Derives an identifier from a session key that can be logged without revealing the key.
+/// Both sides of a connection end up with the same id if they use the same key.
+pub fn key_fingerprint(key: &[u8; 24]) -> u32 {
+    u32::from_be_bytes(key_digest(key)[0..4].try_into().unwrap())
+}
+
+/// Derives a digest from a session key that can be kept around without revealing the key.
+/// Unlike [`key_fingerprint`], it is long enough to tell keys apart reliably.
+pub fn key_digest(key: &[u8; 24]) -> [u8; 20] {
+    Sha1::digest(key).into()
 }
 
 type HmacSha1 = Hmac<Sha1>;
+type HmacTiger = Hmac<Tiger>;
+
+fn keyed_mac<M: Mac + KeyInit>(buf: &[u8], key: &[u8; 24]) -> M {
+    let mut mac = <M as KeyInit>::new_from_slice(key).expect("HMac accepts session key");
+    Mac::update(&mut mac, buf);
 
+    mac
+}
+
+/// The first four bytes of the HMAC-SHA1 of the buffer,
+/// which is what encrypted messages are authenticated with.
 pub fn calculate_hmac(buf: &[u8], key: &[u8; 24]) -> u32 {
-    let mut hmac = HmacSha1::new_from_slice(key).expect("HMac accepts session key");
-    Mac::update(&mut hmac, buf);
-    let result = HmacSha1::finalize(hmac);
+    let result = keyed_mac::<HmacSha1>(buf, key).finalize();
 
     u32::from_le_bytes((&result.into_bytes()[0..4]).try_into().unwrap())
 }
 
+/// Checks the result of [`calculate_hmac`] in constant time,
+/// so the comparison does not reveal how many bytes of a forged hmac were correct.
+pub fn verify_hmac(buf: &[u8], key: &[u8; 24], hmac: u32) -> bool {
+    keyed_mac::<HmacSha1>(buf, key)
+        .verify_truncated_left(&hmac.to_le_bytes())
+        .is_ok()
+}
+
+/// The first four bytes of the HMAC-Tiger of the buffer.
+pub fn calculate_tiger_hmac(buf: &[u8], key: &[u8; 24]) -> u32 {
+    let result = keyed_mac::<HmacTiger>(buf, key).finalize();
+
+    u32::from_le_bytes((&result.into_bytes()[0..4]).try_into().unwrap())
+}
+
+/// Checks the result of [`calculate_tiger_hmac`] in constant time.
+pub fn verify_tiger_hmac(buf: &[u8], key: &[u8; 24], hmac: u32) -> bool {
+    keyed_mac::<HmacTiger>(buf, key)
+        .verify_truncated_left(&hmac.to_le_bytes())
+        .is_ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,6 +382,16 @@ mod tests {
         assert_eq!(iv, EXPECTED_IV);
     }
 
+    #[test]
+    fn key_fingerprint_differs_between_keys() {
+        let key_a = [1u8; 24];
+        let mut key_b = key_a;
+        key_b[23] = 2;
+
+        assert_eq!(key_fingerprint(&key_a), key_fingerprint(&key_a));
+        assert_ne!(key_fingerprint(&key_a), key_fingerprint(&key_b));
+    }
+
     #[test]
     fn correctly_encrypts_buffer() {
         const KEY: [u8; 24] = [
@@ -98,4 +413,133 @@ mod tests {
 
         assert_eq!(buf.as_slice(), EXPECTED_OUTPUT);
     }
+
+    #[test]
+    fn session_ciphers_decrypt_what_they_encrypted() {
+        const KEY: [u8; 24] = [3; 24];
+        const SEED: u32 = 12345678u32;
+        let data: Vec<u8> = (0..32).collect();
+
+        let mut tdes_buf = data.clone();
+        SessionCipher::Tdes
+            .encrypt_in_place(&mut tdes_buf, &KEY, SEED)
+            .unwrap();
+        let mut expected_tdes_buf = data.clone();
+        encrypt_buffer_in_place(&mut expected_tdes_buf, &KEY, &generate_iv_from_seed(SEED));
+        assert_eq!(tdes_buf, expected_tdes_buf);
+
+        let mut aes_buf = data.clone();
+        SessionCipher::Aes
+            .encrypt_in_place(&mut aes_buf, &KEY, SEED)
+            .unwrap();
+        assert_ne!(aes_buf, tdes_buf);
+        SessionCipher::Aes
+            .decrypt_in_place(&mut aes_buf, &KEY, SEED)
+            .unwrap();
+        assert_eq!(aes_buf, data);
+
+        assert!(SessionCipher::Aes
+            .encrypt_in_place(&mut [0u8; 24], &KEY, SEED)
+            .is_err());
+    }
+
+    #[test]
+    fn reads_cipher_from_frame_flag() {
+        assert_eq!(SessionCipher::from_frame_flag(0), None);
+        for cipher in [SessionCipher::Tdes, SessionCipher::Aes] {
+            assert_eq!(
+                SessionCipher::from_frame_flag(cipher.frame_flag()),
+                Some(cipher)
+            );
+        }
+    }
+
+    #[test]
+    fn compares_secrets_by_content() {
+        assert!(constant_time_eq(&[1, 2, 3], &[1, 2, 3]));
+        assert!(!constant_time_eq(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!constant_time_eq(&[1, 2, 3], &[1, 2]));
+
+        assert_eq!(SessionKey::new([1; 24]), SessionKey::from([1; 24]));
+        assert_ne!(SessionKey::new([1; 24]), SessionKey::new([2; 24]));
+    }
+
+    #[test]
+    fn streaming_matches_encrypting_at_once() {
+        const KEY: [u8; 24] = [3; 24];
+        let iv = generate_iv_from_seed(12345678u32);
+        let data: Vec<u8> = (0..48).collect();
+
+        let mut at_once = data.clone();
+        encrypt_buffer_in_place(&mut at_once, &KEY, &iv);
+
+        let mut streamed = data.clone();
+        let mut stream = TdesCbcStream::new(&KEY, &iv);
+        let (first, rest) = streamed.split_at_mut(16);
+        stream.encrypt_in_place(first).unwrap();
+        stream.encrypt_in_place(&mut []).unwrap();
+        stream.encrypt_in_place(rest).unwrap();
+        assert_eq!(streamed, at_once);
+
+        let mut stream = TdesCbcStream::new(&KEY, &iv);
+        let (first, rest) = streamed.split_at_mut(40);
+        stream.decrypt_in_place(first).unwrap();
+        stream.decrypt_in_place(rest).unwrap();
+        assert_eq!(streamed, data);
+
+        assert!(stream.encrypt_in_place(&mut [0u8; 5]).is_err());
+    }
+
+    #[test]
+    fn verifies_only_matching_hmacs() {
+        const KEY: [u8; 24] = [5; 24];
+        let buf = [1u8, 2, 3, 4, 5];
+
+        let hmac = calculate_hmac(&buf, &KEY);
+        assert!(verify_hmac(&buf, &KEY, hmac));
+        assert!(!verify_hmac(&buf, &KEY, hmac ^ 1));
+        assert!(!verify_hmac(&buf[..4], &KEY, hmac));
+        assert!(!verify_hmac(&buf, &[6; 24], hmac));
+
+        let tiger_hmac = calculate_tiger_hmac(&buf, &KEY);
+        assert_ne!(tiger_hmac, hmac);
+        assert!(verify_tiger_hmac(&buf, &KEY, tiger_hmac));
+        assert!(!verify_tiger_hmac(&buf, &KEY, hmac));
+    }
+
+    #[test]
+    fn decrypts_what_was_encrypted() {
+        const KEY: [u8; 24] = [3; 24];
+        let iv = generate_iv_from_seed(12345678u32);
+        let data: Vec<u8> = (0..42).collect();
+
+        let mut buf = data.clone();
+        encrypt_buffer_in_place(&mut buf, &KEY, &iv);
+        assert_eq!(buf.len(), 48);
+
+        decrypt_padded_buffer_in_place(&mut buf, data.len(), &KEY, &iv).unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[test]
+    fn keeps_padding_when_decrypting_without_data_length() {
+        const KEY: [u8; 24] = [3; 24];
+        let iv = generate_iv_from_seed(12345678u32);
+
+        let mut buf = vec![1, 2, 3, 0];
+        encrypt_buffer_in_place(&mut buf, &KEY, &iv);
+        decrypt_buffer_in_place(&mut buf, &KEY, &iv).unwrap();
+
+        assert_eq!(buf, [1, 2, 3, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn refuses_buffers_of_wrong_length() {
+        const KEY: [u8; 24] = [3; 24];
+        let iv = generate_iv_from_seed(12345678u32);
+
+        assert!(decrypt_buffer_in_place(&mut [0u8; 12], &KEY, &iv).is_err());
+        assert!(decrypt_padded_buffer_in_place(&mut vec![0u8; 16], 4, &KEY, &iv).is_err());
+        assert!(decrypt_padded_buffer_in_place(&mut vec![0u8; 16], 17, &KEY, &iv).is_err());
+    }
 }
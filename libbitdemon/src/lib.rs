@@ -7,3 +7,6 @@ pub mod networking;
 
 #[macro_use]
 extern crate num_derive;
+
+// Lets derived implementations refer to this crate by name from within itself.
+extern crate self as bitdemon;
@@ -0,0 +1,73 @@
+use crate::auth::authentication::SessionAuthentication;
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+
+/// Reasons for refusing a session to authenticate on the lobby server.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum AdmissionRejection {
+    /// The user is banned.
+    UserBannedError,
+    /// The server is in maintenance and does not accept any sessions.
+    MaintenanceError,
+    /// The title only admits specific users and the user is not one of them.
+    NotAllowlistedError,
+    /// The server or the user already has the maximum amount of sessions.
+    SessionLimitReachedError,
+}
+
+pub type ThreadSafeSessionAdmissionPolicy = dyn SessionAdmissionPolicy + Sync + Send;
+
+/// Decides whether sessions may authenticate on the lobby server.
+///
+/// The policy is evaluated once when a session presents its auth proof,
+/// before any lobby service can be used.
+pub trait SessionAdmissionPolicy {
+    /// Checks whether the session may authenticate as the specified user.
+    /// The session itself is not yet authenticated when this is called.
+    fn admit(
+        &self,
+        session: &BdSession,
+        authentication: &SessionAuthentication,
+    ) -> Result<(), AdmissionRejection>;
+
+    /// A human-readable explanation of the rejection, preferably in the language of the user.
+    /// No message is sent when `None`, which is the default.
+    fn rejection_message(
+        &self,
+        _rejection: AdmissionRejection,
+        _authentication: &SessionAuthentication,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// Admits every session with a valid auth proof.
+#[derive(Default)]
+pub struct AdmitAllPolicy {}
+
+impl SessionAdmissionPolicy for AdmitAllPolicy {
+    fn admit(
+        &self,
+        _session: &BdSession,
+        _authentication: &SessionAuthentication,
+    ) -> Result<(), AdmissionRejection> {
+        Ok(())
+    }
+}
+
+impl AdmitAllPolicy {
+    pub fn new() -> AdmitAllPolicy {
+        AdmitAllPolicy {}
+    }
+}
+
+impl From<AdmissionRejection> for BdErrorCode {
+    fn from(value: AdmissionRejection) -> Self {
+        match value {
+            AdmissionRejection::UserBannedError => BdErrorCode::UserIdBanned,
+            AdmissionRejection::MaintenanceError => BdErrorCode::AuthTitleDisabled,
+            AdmissionRejection::NotAllowlistedError => BdErrorCode::AccessDenied,
+            AdmissionRejection::SessionLimitReachedError => BdErrorCode::ServiceNotAvailable,
+        }
+    }
+}
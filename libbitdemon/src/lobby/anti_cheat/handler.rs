@@ -0,0 +1,258 @@
+use crate::lobby::anti_cheat::result::ChallengesResult;
+use crate::lobby::anti_cheat::{ChallengeFailure, IssuedChallenge, ThreadSafeAntiCheatService};
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::{LobbyHandler, LobbyServiceId, PushMessage};
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::BdErrorCode::NoError;
+use crate::networking::bd_session::BdSession;
+use log::{debug, info, warn};
+use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const PENDING_CHALLENGES_KEY: &str = "anti_cheat_pending_challenges";
+/// How long clients have to answer a challenge.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub struct AntiCheatHandler {
+    pub anti_cheat_service: Arc<ThreadSafeAntiCheatService>,
+}
+
+/// A challenge that was issued to the session and was not answered yet.
+#[derive(Clone)]
+struct PendingChallenge {
+    issued: IssuedChallenge,
+    issued_at: Instant,
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum AntiCheatTaskId {
+    AnswerChallenges = 2,
+    ReportConsoleId = 3, // Index is a guess
+    ReportConsoleDetails = 4,
+}
+
+impl LobbyHandler for AntiCheatHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = AntiCheatTaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(NoError, task_id_value).to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            AntiCheatTaskId::ReportConsoleDetails => {
+                Self::report_console_details(session, &mut message.reader)
+            }
+            AntiCheatTaskId::AnswerChallenges => {
+                self.answer_challenges(session, &mut message.reader)
+            }
+            AntiCheatTaskId::ReportConsoleId => {
+                warn!("Client called unimplemented task {task_id:?}");
+                Ok(TaskReply::with_only_error_code(NoError, task_id).to_response()?)
+            }
+        }
+    }
+}
+
+impl AntiCheatHandler {
+    pub fn new(anti_cheat_service: Arc<ThreadSafeAntiCheatService>) -> AntiCheatHandler {
+        AntiCheatHandler { anti_cheat_service }
+    }
+
+    /// Pushes new challenges to the client of an authenticated session.
+    /// They are remembered for the session until they are answered or the session ends.
+    pub fn issue_challenges(&self, session: &BdSession) -> Result<(), Box<dyn Error>> {
+        let issued_challenges = self.anti_cheat_service.create_challenges(session);
+        if issued_challenges.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "Issuing {} anti cheat challenges to session {}",
+            issued_challenges.len(),
+            session.id
+        );
+
+        let content = ChallengesResult {
+            challenges: issued_challenges
+                .iter()
+                .map(|issued| issued.challenge.clone())
+                .collect(),
+        };
+        let mut pending_challenges = session
+            .scratch()
+            .take::<Vec<PendingChallenge>>(PENDING_CHALLENGES_KEY)
+            .unwrap_or_default();
+        let issued_at = Instant::now();
+        pending_challenges.extend(
+            issued_challenges
+                .into_iter()
+                .map(|issued| PendingChallenge { issued, issued_at }),
+        );
+        session
+            .scratch()
+            .insert(PENDING_CHALLENGES_KEY, pending_challenges);
+
+        let mut handle = session.detach()?;
+        PushMessage::with_content(LobbyServiceId::Anticheat, &content)?.send_to(&mut handle)
+    }
+
+    /// Records all challenges of the session that were not answered as failed.
+    /// Meant to be called when the session ends.
+    pub fn expire_challenges(&self, session: &BdSession) {
+        if session.authentication().is_none() {
+            return;
+        }
+
+        let pending_challenges = session
+            .scratch()
+            .take::<Vec<PendingChallenge>>(PENDING_CHALLENGES_KEY)
+            .unwrap_or_default();
+        for pending in pending_challenges {
+            self.anti_cheat_service.record_failure(
+                session,
+                pending.issued.challenge.challenge_id,
+                ChallengeFailure::Unanswered,
+            );
+        }
+    }
+
+    fn answer_challenges(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let mut pending_challenges = session
+            .scratch()
+            .take::<Vec<PendingChallenge>>(PENDING_CHALLENGES_KEY)
+            .unwrap_or_default();
+
+        let now = Instant::now();
+        while reader.next_is_u32().unwrap_or(false) {
+            let challenge_id = reader.read_u32()?;
+            let answer = reader.read_blob()?;
+
+            if let Some(failure) =
+                Self::judge_answer(&mut pending_challenges, challenge_id, &answer, now)
+            {
+                warn!(
+                    "Session {} failed challenge {challenge_id}: {failure:?}",
+                    session.id
+                );
+                self.anti_cheat_service
+                    .record_failure(session, challenge_id, failure);
+            }
+        }
+
+        if !pending_challenges.is_empty() {
+            session
+                .scratch()
+                .insert(PENDING_CHALLENGES_KEY, pending_challenges);
+        }
+
+        // Cheaters are not told whether their answers were accepted
+        TaskReply::with_only_error_code(NoError, AntiCheatTaskId::AnswerChallenges).to_response()
+    }
+
+    /// Removes the answered challenge from the pending ones and checks the answer.
+    fn judge_answer(
+        pending_challenges: &mut Vec<PendingChallenge>,
+        challenge_id: u32,
+        answer: &[u8],
+        now: Instant,
+    ) -> Option<ChallengeFailure> {
+        let Some(index) = pending_challenges
+            .iter()
+            .position(|pending| pending.issued.challenge.challenge_id == challenge_id)
+        else {
+            return Some(ChallengeFailure::UnknownChallenge);
+        };
+        let pending = pending_challenges.swap_remove(index);
+
+        if now.duration_since(pending.issued_at) > ANSWER_TIMEOUT {
+            Some(ChallengeFailure::LateAnswer)
+        } else if pending.issued.expected_answer != answer {
+            Some(ChallengeFailure::WrongAnswer)
+        } else {
+            None
+        }
+    }
+
+    fn report_console_details(
+        _session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let _blob1 = reader.read_blob()?; // Always blob with length 16 on PC with first 4 byte being 0x756B5B3
+        let _uint1 = reader.read_u32()?; // Always 2 on PC
+        let changelist = reader.read_u32()?; // Changelist of the game executable
+        let _ulong1 = reader.read_u64()?; // Always 0 on PC
+        let _ulong2 = reader.read_u64()?; // Always 0 on PC
+        let _ulong3 = reader.read_u64()?; // Always 0 on PC
+        let _blob2 = reader.read_blob()?; // Always nulled blob with length 6 on PC
+
+        debug!("Client reported console details changelist={changelist}");
+
+        TaskReply::with_only_error_code(NoError, AntiCheatTaskId::ReportConsoleDetails)
+            .to_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lobby::anti_cheat::Challenge;
+
+    fn pending(challenge_id: u32, issued_at: Instant) -> PendingChallenge {
+        PendingChallenge {
+            issued: IssuedChallenge {
+                challenge: Challenge {
+                    challenge_id,
+                    parameter: vec![1],
+                },
+                expected_answer: vec![2, 3],
+            },
+            issued_at,
+        }
+    }
+
+    #[test]
+    fn judges_answers_of_pending_challenges() {
+        let now = Instant::now();
+        let mut pending_challenges = vec![
+            pending(1, now),
+            pending(2, now),
+            pending(3, now - ANSWER_TIMEOUT * 2),
+        ];
+
+        let judge = |pending_challenges: &mut Vec<PendingChallenge>, id, answer: &[u8]| {
+            AntiCheatHandler::judge_answer(pending_challenges, id, answer, now)
+        };
+
+        assert_eq!(judge(&mut pending_challenges, 1, &[2, 3]), None);
+        assert_eq!(
+            judge(&mut pending_challenges, 1, &[2, 3]),
+            Some(ChallengeFailure::UnknownChallenge)
+        );
+        assert_eq!(
+            judge(&mut pending_challenges, 2, &[2]),
+            Some(ChallengeFailure::WrongAnswer)
+        );
+        assert_eq!(
+            judge(&mut pending_challenges, 3, &[2, 3]),
+            Some(ChallengeFailure::LateAnswer)
+        );
+        assert!(pending_challenges.is_empty());
+    }
+}
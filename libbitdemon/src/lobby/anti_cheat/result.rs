@@ -0,0 +1,29 @@
+﻿use crate::lobby::anti_cheat::Challenge;
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+/// The challenges pushed to a client.
+/// The layout is inferred from the answers, which are sent as pairs of id and data.
+pub struct ChallengesResult {
+    pub challenges: Vec<Challenge>,
+}
+
+impl BdSerialize for Challenge {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u32(self.challenge_id)?;
+        writer.write_blob(&self.parameter)?;
+
+        Ok(())
+    }
+}
+
+impl BdSerialize for ChallengesResult {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        for challenge in &self.challenges {
+            challenge.serialize(writer)?;
+        }
+
+        Ok(())
+    }
+}
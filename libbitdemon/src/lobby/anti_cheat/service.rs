@@ -0,0 +1,43 @@
+use crate::networking::bd_session::BdSession;
+
+/// A challenge a client has to answer, e.g. with the hash of a part of its memory.
+#[derive(Clone)]
+pub struct Challenge {
+    /// Identifies the challenge when the client answers it.
+    pub challenge_id: u32,
+    /// Tells the client what to compute.
+    pub parameter: Vec<u8>,
+}
+
+/// A challenge together with the answer an unmodified client gives.
+#[derive(Clone)]
+pub struct IssuedChallenge {
+    pub challenge: Challenge,
+    pub expected_answer: Vec<u8>,
+}
+
+/// The ways a client can fail a challenge.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ChallengeFailure {
+    /// The client answered a challenge that was not issued to its session.
+    UnknownChallenge,
+    /// The answer differs from the one of an unmodified client.
+    WrongAnswer,
+    /// The answer arrived after the challenge expired.
+    LateAnswer,
+    /// The session ended without the challenge being answered.
+    Unanswered,
+}
+
+pub type ThreadSafeAntiCheatService = dyn AntiCheatService + Sync + Send;
+
+/// Implements domain logic concerning the challenges clients have to answer
+/// to prove that they were not tampered with.
+pub trait AntiCheatService {
+    /// Creates the challenges that are issued to the current session.
+    /// Clients are not challenged when no challenges are returned.
+    fn create_challenges(&self, session: &BdSession) -> Vec<IssuedChallenge>;
+
+    /// Records that the current user failed a challenge.
+    fn record_failure(&self, session: &BdSession, challenge_id: u32, failure: ChallengeFailure);
+}
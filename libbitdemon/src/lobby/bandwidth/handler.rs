@@ -1,4 +1,6 @@
-﻿use crate::lobby::bandwidth::result::BandwidthTestRejected;
+﻿use crate::lobby::bandwidth::result::{
+    BandwidthPayload, BandwidthTestAccepted, BandwidthTestRejected, BandwidthTestResult,
+};
 use crate::lobby::response::lsg_reply::LsgResponseCreator;
 use crate::lobby::response::task_reply::TaskReply;
 use crate::lobby::LobbyHandler;
@@ -7,18 +9,28 @@ use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_response::{BdResponse, ResponseCreator};
 use crate::messaging::BdErrorCode;
 use crate::messaging::BdErrorCode::NoError;
-use crate::networking::bd_session::BdSession;
-use log::{debug, warn};
+use crate::networking::bd_session::{BandwidthStats, BdSession};
+use log::{debug, info, warn};
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 use std::error::Error;
+use std::time::{Duration, Instant};
+
+const RUNNING_TEST_KEY: &str = "bandwidth_running_test";
+/// How long a client may take to finish a test it started.
+const TEST_TIMEOUT: Duration = Duration::from_secs(30);
+const PACKET_SIZE: usize = 1024;
+const PACKET_COUNT: usize = 32;
 
 pub struct BandwidthHandler {}
 
+/// Only the id of starting a test is confirmed, the ids of the following packets are inferred.
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum BandwidthTaskId {
+pub enum BandwidthTaskId {
     BandwidthTask = 1,
+    UploadPayload = 2,
+    DownloadComplete = 3,
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
@@ -28,6 +40,59 @@ enum BandwidthTestType {
     UploadDownloadTest = 1,
 }
 
+/// The progress of a bandwidth test of a session.
+#[derive(Clone)]
+struct RunningTest {
+    test_type: BandwidthTestType,
+    started_at: Instant,
+    uploaded_packets: usize,
+    uploaded_bytes: usize,
+    upstream_bps: Option<u64>,
+    download_started_at: Option<Instant>,
+}
+
+impl RunningTest {
+    fn new(test_type: BandwidthTestType, started_at: Instant) -> RunningTest {
+        RunningTest {
+            test_type,
+            started_at,
+            uploaded_packets: 0,
+            uploaded_bytes: 0,
+            upstream_bps: None,
+            download_started_at: None,
+        }
+    }
+
+    /// Counts a payload packet of the client.
+    /// Measures the upstream throughput once all packets arrived.
+    fn record_upload(&mut self, len: usize, now: Instant) {
+        self.uploaded_packets += 1;
+        self.uploaded_bytes += len;
+
+        if self.uploaded_packets == PACKET_COUNT {
+            self.upstream_bps = Some(bits_per_second(
+                self.uploaded_bytes,
+                now.duration_since(self.started_at),
+            ));
+        }
+    }
+
+    fn downstream_bps(&self, now: Instant) -> Option<u64> {
+        self.download_started_at.map(|download_started_at| {
+            bits_per_second(
+                PACKET_SIZE * PACKET_COUNT,
+                now.duration_since(download_started_at),
+            )
+        })
+    }
+}
+
+fn bits_per_second(bytes: usize, elapsed: Duration) -> u64 {
+    let elapsed_micros = elapsed.as_micros().max(1);
+
+    (bytes as u128 * 8 * 1_000_000 / elapsed_micros).min(u64::MAX as u128) as u64
+}
+
 impl LobbyHandler for BandwidthHandler {
     fn handle_message(
         &self,
@@ -48,6 +113,8 @@ impl LobbyHandler for BandwidthHandler {
             BandwidthTaskId::BandwidthTask => {
                 Self::handle_bandwidth_task(session, &mut message.reader)
             }
+            BandwidthTaskId::UploadPayload => Self::upload_payload(session, &mut message.reader),
+            BandwidthTaskId::DownloadComplete => Self::download_complete(session),
         }
     }
 }
@@ -64,20 +131,128 @@ impl BandwidthHandler {
     }
 
     fn handle_bandwidth_task(
-        _session: &mut BdSession,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let test_type_value = reader.read_u8()?;
-        match BandwidthTestType::from_u8(test_type_value) {
-            Some(test_type) => {
-                debug!("Client requested bandwidth test type={test_type:?}");
-            }
-            None => {
-                warn!("Client requested unknown bandwidth test type={test_type_value}")
+        let Some(test_type) = BandwidthTestType::from_u8(test_type_value) else {
+            warn!("Client requested unknown bandwidth test type={test_type_value}");
+            return BandwidthTestRejected::with_reason(BdErrorCode::ServiceNotAvailable)
+                .to_response();
+        };
+
+        debug!("Client requested bandwidth test type={test_type:?}");
+
+        session.scratch().insert_with_ttl(
+            RUNNING_TEST_KEY,
+            RunningTest::new(test_type, Instant::now()),
+            TEST_TIMEOUT,
+        );
+
+        BandwidthTestAccepted {
+            packet_size: PACKET_SIZE as u32,
+            packet_count: PACKET_COUNT as u32,
+        }
+        .to_response()
+    }
+
+    fn upload_payload(
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let payload = reader.read_blob()?;
+
+        let Some(mut test) = session.scratch().take::<RunningTest>(RUNNING_TEST_KEY) else {
+            warn!("Client sent bandwidth payload without running test");
+            return Ok(BdResponse::no_reply());
+        };
+
+        test.record_upload(payload.len(), Instant::now());
+        let Some(upstream_bps) = test.upstream_bps else {
+            Self::keep_running(session, test);
+            return Ok(BdResponse::no_reply());
+        };
+
+        if test.test_type == BandwidthTestType::UploadTest {
+            return Self::finish(session, upstream_bps, None);
+        }
+
+        test.download_started_at = Some(Instant::now());
+        Self::keep_running(session, test);
+
+        for _ in 0..PACKET_COUNT {
+            BandwidthPayload {
+                payload: vec![0u8; PACKET_SIZE],
             }
+            .to_response()?
+            .send(session)?;
+        }
+
+        Ok(BdResponse::no_reply())
+    }
+
+    fn download_complete(session: &mut BdSession) -> Result<BdResponse, Box<dyn Error>> {
+        let now = Instant::now();
+        let maybe_result = session
+            .scratch()
+            .take::<RunningTest>(RUNNING_TEST_KEY)
+            .and_then(|test| Some((test.upstream_bps?, test.downstream_bps(now)?)));
+
+        let Some((upstream_bps, downstream_bps)) = maybe_result else {
+            warn!("Client completed download of bandwidth test that did not start it");
+            return BandwidthTestRejected::with_reason(BdErrorCode::ServiceNotAvailable)
+                .to_response();
+        };
+
+        Self::finish(session, upstream_bps, Some(downstream_bps))
+    }
+
+    /// Remembers the test with the time it was started at, so it expires as usual.
+    fn keep_running(session: &BdSession, test: RunningTest) {
+        let remaining = TEST_TIMEOUT.saturating_sub(test.started_at.elapsed());
+        session
+            .scratch()
+            .insert_with_ttl(RUNNING_TEST_KEY, test, remaining);
+    }
+
+    fn finish(
+        session: &mut BdSession,
+        upstream_bps: u64,
+        downstream_bps: Option<u64>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        info!("Measured bandwidth upstream={upstream_bps}bps downstream={downstream_bps:?}bps");
+
+        session.set_bandwidth_stats(BandwidthStats {
+            upstream_bps,
+            downstream_bps,
+        });
+
+        BandwidthTestResult {
+            upstream_bps: upstream_bps.min(u32::MAX as u64) as u32,
+            downstream_bps: downstream_bps.unwrap_or_default().min(u32::MAX as u64) as u32,
+        }
+        .to_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_upstream_once_all_packets_arrived() {
+        let started_at = Instant::now();
+        let mut test = RunningTest::new(BandwidthTestType::UploadTest, started_at);
+
+        for _ in 0..PACKET_COUNT - 1 {
+            test.record_upload(PACKET_SIZE, started_at);
         }
+        assert_eq!(test.upstream_bps, None);
 
-        // Bandwidth tests are not supported
-        BandwidthTestRejected::with_reason(BdErrorCode::ServiceNotAvailable).to_response()
+        test.record_upload(PACKET_SIZE, started_at + Duration::from_millis(500));
+        assert_eq!(
+            test.upstream_bps,
+            Some((PACKET_SIZE * PACKET_COUNT * 8 * 2) as u64)
+        );
     }
 }
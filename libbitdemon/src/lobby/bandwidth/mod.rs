@@ -1,4 +1,4 @@
 ﻿mod handler;
 mod result;
 
-pub use handler::BandwidthHandler;
+pub use handler::{BandwidthHandler, BandwidthTaskId};
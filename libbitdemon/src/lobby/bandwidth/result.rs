@@ -25,3 +25,48 @@ impl LsgServiceTaskReply for BandwidthTestRejected {
         Ok(())
     }
 }
+
+/// Tells the client to start sending payload packets.
+/// The layout is inferred, the client may expect further parameters.
+pub struct BandwidthTestAccepted {
+    pub packet_size: u32,
+    pub packet_count: u32,
+}
+
+impl LsgServiceTaskReply for BandwidthTestAccepted {
+    fn write_task_reply_data(&self, mut writer: BdWriter) -> Result<(), Box<dyn Error>> {
+        // Test not rejected
+        writer.write_bool(false)?;
+
+        writer.write_u32(self.packet_size)?;
+        writer.write_u32(self.packet_count)?;
+
+        Ok(())
+    }
+}
+
+/// A bulk packet of the downstream measurement.
+pub struct BandwidthPayload {
+    pub payload: Vec<u8>,
+}
+
+impl LsgServiceTaskReply for BandwidthPayload {
+    fn write_task_reply_data(&self, mut writer: BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_blob(&self.payload)
+    }
+}
+
+/// The measured throughput in bits per second.
+pub struct BandwidthTestResult {
+    pub upstream_bps: u32,
+    pub downstream_bps: u32,
+}
+
+impl LsgServiceTaskReply for BandwidthTestResult {
+    fn write_task_reply_data(&self, mut writer: BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u32(self.upstream_bps)?;
+        writer.write_u32(self.downstream_bps)?;
+
+        Ok(())
+    }
+}
@@ -27,7 +27,7 @@ pub struct ContentStreamingHandler {
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum ContentStreamingTaskId {
+pub enum ContentStreamingTaskId {
     // GetQuotaUsage
     // ReportContent
     // RemoveFile
@@ -400,12 +400,10 @@ impl ContentStreamingHandler {
         task_id: ContentStreamingTaskId,
         result: Result<ResultSlice<StreamInfo>, ContentStreamingServiceError>,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        match result {
-            Ok(info) => {
-                Ok(TaskReply::with_result_slice(task_id, info.serializable()).to_response()?)
-            }
-            Err(error) => Ok(TaskReply::with_only_error_code(error.into(), task_id).to_response()?),
-        }
+        TaskReply::builder(task_id)
+            .with_outcome(result)
+            .build()
+            .to_response()
     }
 
     fn answer_for_stream_url(
@@ -413,10 +411,10 @@ impl ContentStreamingHandler {
         task_id: ContentStreamingTaskId,
         result: Result<StreamUrl, ContentStreamingServiceError>,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        match result {
-            Ok(url) => Ok(TaskReply::with_results(task_id, vec![Box::from(url)]).to_response()?),
-            Err(error) => Ok(TaskReply::with_only_error_code(error.into(), task_id).to_response()?),
-        }
+        TaskReply::builder(task_id)
+            .with_outcome(result.map(|url| vec![url]))
+            .build()
+            .to_response()
     }
 }
 
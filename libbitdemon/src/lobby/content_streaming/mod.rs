@@ -2,5 +2,5 @@
 mod result;
 mod service;
 
-pub use handler::ContentStreamingHandler;
+pub use handler::{ContentStreamingHandler, ContentStreamingTaskId};
 pub use service::*;
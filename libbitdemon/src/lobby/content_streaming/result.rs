@@ -3,6 +3,7 @@ use crate::messaging::bd_serialization::BdSerialize;
 use crate::messaging::bd_writer::BdWriter;
 use std::error::Error;
 
+#[derive(BdSerialize)]
 pub struct FileIdResult {
     pub id: u64,
 }
@@ -42,9 +43,3 @@ impl BdSerialize for StreamUrl {
         writer.write_u64(self.stream_id)
     }
 }
-
-impl BdSerialize for FileIdResult {
-    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
-        writer.write_u64(self.id)
-    }
-}
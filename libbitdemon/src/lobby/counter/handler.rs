@@ -19,7 +19,7 @@ pub struct CounterHandler {
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum CounterTaskId {
+pub enum CounterTaskId {
     IncrementCounters = 1,
     GetCounterTotals = 2,
 }
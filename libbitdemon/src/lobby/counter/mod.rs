@@ -2,5 +2,5 @@
 mod result;
 mod service;
 
-pub use handler::CounterHandler;
+pub use handler::{CounterHandler, CounterTaskId};
 pub use service::*;
@@ -1,4 +1,4 @@
-﻿use crate::lobby::dml::result::{DmlHierarchicalInfoResult, DmlInfoResult};
+﻿use crate::lobby::dml::ThreadSafeDmlService;
 use crate::lobby::response::task_reply::TaskReply;
 use crate::lobby::LobbyHandler;
 use crate::messaging::bd_message::BdMessage;
@@ -9,12 +9,16 @@ use crate::networking::bd_session::BdSession;
 use log::{info, warn};
 use num_traits::FromPrimitive;
 use std::error::Error;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
 
-pub struct DmlHandler {}
+pub struct DmlHandler {
+    pub dml_service: Arc<ThreadSafeDmlService>,
+}
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum DmlTaskId {
+pub enum DmlTaskId {
     RecordIp = 1,
     GetUserData = 2,
     GetUserHierarchicalData = 3,
@@ -36,74 +40,54 @@ impl LobbyHandler for DmlHandler {
         let task_id = maybe_task_id.unwrap();
 
         match task_id {
-            DmlTaskId::RecordIp => Self::record_ip(session, &mut message.reader),
-            DmlTaskId::GetUserData => Self::get_user_data(session, &mut message.reader),
+            DmlTaskId::RecordIp => self.record_ip(session, &mut message.reader),
+            DmlTaskId::GetUserData => self.get_user_data(session, &mut message.reader),
             DmlTaskId::GetUserHierarchicalData => {
-                Self::get_user_hierarchical_data(session, &mut message.reader)
+                self.get_user_hierarchical_data(session, &mut message.reader)
             }
         }
     }
 }
 
-impl Default for DmlHandler {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl DmlHandler {
-    pub fn new() -> DmlHandler {
-        DmlHandler {}
+    pub fn new(dml_service: Arc<ThreadSafeDmlService>) -> DmlHandler {
+        DmlHandler { dml_service }
     }
 
     fn record_ip(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        let ip = reader.read_u32()?;
+        let ip = Ipv4Addr::from(reader.read_u32()?);
         info!("Recording IP: {ip}");
 
+        self.dml_service.record_ip(session, ip);
+
         TaskReply::with_only_error_code(BdErrorCode::NoError, DmlTaskId::RecordIp).to_response()
     }
 
     fn get_user_data(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        let dml_info = Self::create_mock_dml_info();
+        let dml_info = self.dml_service.get_user_data(session);
 
         TaskReply::with_results(DmlTaskId::GetUserData, vec![Box::from(dml_info)]).to_response()
     }
 
     fn get_user_hierarchical_data(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        let dml_hierarchical_info = DmlHierarchicalInfoResult {
-            base: Self::create_mock_dml_info(),
-            tier0: 0,
-            tier1: 0,
-            tier2: 0,
-            tier3: 0,
-        };
+        let dml_hierarchical_info = self.dml_service.get_user_hierarchical_data(session);
 
         TaskReply::with_results(
-            DmlTaskId::GetUserData,
+            DmlTaskId::GetUserHierarchicalData,
             vec![Box::from(dml_hierarchical_info)],
         )
         .to_response()
     }
 }
-
-impl DmlHandler {
-    fn create_mock_dml_info() -> DmlInfoResult {
-        DmlInfoResult {
-            country_code: String::from("US"),
-            country: String::from("United States"),
-            region: String::from("California"),
-            city: String::from("Los Angeles"),
-            latitude: 34.0453f32,
-            longitude: -118.2413f32,
-        }
-    }
-}
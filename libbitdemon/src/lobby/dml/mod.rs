@@ -1,4 +1,6 @@
 ﻿mod handler;
 mod result;
+mod service;
 
-pub use handler::DmlHandler;
+pub use handler::{DmlHandler, DmlTaskId};
+pub use service::*;
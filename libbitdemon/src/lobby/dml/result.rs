@@ -1,25 +1,9 @@
-﻿use crate::messaging::bd_serialization::BdSerialize;
+﻿use crate::lobby::dml::{DmlHierarchicalInfo, DmlInfo};
+use crate::messaging::bd_serialization::BdSerialize;
 use crate::messaging::bd_writer::BdWriter;
 use std::error::Error;
 
-pub struct DmlInfoResult {
-    pub country_code: String,
-    pub country: String,
-    pub region: String,
-    pub city: String,
-    pub latitude: f32,
-    pub longitude: f32,
-}
-
-pub struct DmlHierarchicalInfoResult {
-    pub base: DmlInfoResult,
-    pub tier0: u32,
-    pub tier1: u32,
-    pub tier2: u32,
-    pub tier3: u32,
-}
-
-impl BdSerialize for DmlInfoResult {
+impl BdSerialize for DmlInfo {
     fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
         writer.write_str(self.country_code.as_str())?;
         writer.write_str(self.country.as_str())?;
@@ -32,7 +16,7 @@ impl BdSerialize for DmlInfoResult {
     }
 }
 
-impl BdSerialize for DmlHierarchicalInfoResult {
+impl BdSerialize for DmlHierarchicalInfo {
     fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
         self.base.serialize(writer)?;
         writer.write_u32(self.tier0)?;
@@ -0,0 +1,39 @@
+use crate::networking::bd_session::BdSession;
+use std::net::Ipv4Addr;
+
+/// The location of a user as far as it can be derived from their ip address.
+#[derive(Clone)]
+pub struct DmlInfo {
+    pub country_code: String,
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+/// The location of a user together with the ids of the regions it is part of,
+/// ordered from the largest to the smallest region.
+#[derive(Clone)]
+pub struct DmlHierarchicalInfo {
+    pub base: DmlInfo,
+    pub tier0: u32,
+    pub tier1: u32,
+    pub tier2: u32,
+    pub tier3: u32,
+}
+
+pub type ThreadSafeDmlService = dyn DmlService + Sync + Send;
+
+/// Implements domain logic concerning the location of users,
+/// which titles use to select the matchmaking region.
+pub trait DmlService {
+    /// Remembers the ip address the current client reported for itself.
+    fn record_ip(&self, session: &BdSession, ip: Ipv4Addr);
+
+    /// Retrieves the location of the current user.
+    fn get_user_data(&self, session: &BdSession) -> DmlInfo;
+
+    /// Retrieves the location of the current user with the regions it is part of.
+    fn get_user_hierarchical_data(&self, session: &BdSession) -> DmlHierarchicalInfo;
+}
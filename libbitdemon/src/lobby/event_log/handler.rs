@@ -1,4 +1,4 @@
-﻿use crate::lobby::event_log::result::EventInfo;
+﻿use crate::lobby::event_log::{Event, EventPayload, ThreadSafeEventLogService};
 use crate::lobby::response::task_reply::TaskReply;
 use crate::lobby::LobbyHandler;
 use crate::messaging::bd_message::BdMessage;
@@ -7,16 +7,19 @@ use crate::messaging::bd_response::{BdResponse, ResponseCreator};
 use crate::messaging::bd_serialization::BdDeserialize;
 use crate::messaging::BdErrorCode;
 use crate::networking::bd_session::BdSession;
-use log::{info, warn};
+use log::{debug, warn};
 use num_traits::FromPrimitive;
 use std::error::Error;
+use std::sync::Arc;
 
-pub struct EventLogHandler {}
+pub struct EventLogHandler {
+    pub event_log_service: Arc<ThreadSafeEventLogService>,
+}
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
 #[allow(clippy::enum_variant_names)]
-enum EventLogTaskId {
+pub enum EventLogTaskId {
     RecordEvent = 1,
     RecordEventBin = 2,
     RecordEvents = 3,
@@ -39,95 +42,109 @@ impl LobbyHandler for EventLogHandler {
         let task_id = maybe_task_id.unwrap();
 
         match task_id {
-            EventLogTaskId::RecordEvent => Self::record_event(session, &mut message.reader),
-            EventLogTaskId::RecordEventBin => Self::record_event_bin(session, &mut message.reader),
-            EventLogTaskId::RecordEvents => Self::record_events(session, &mut message.reader),
+            EventLogTaskId::RecordEvent => self.record_event(session, &mut message.reader),
+            EventLogTaskId::RecordEventBin => self.record_event_bin(session, &mut message.reader),
+            EventLogTaskId::RecordEvents => self.record_events(session, &mut message.reader),
             EventLogTaskId::RecordEventsMixed => {
-                Self::record_events_mixed(session, &mut message.reader)
+                self.record_events_mixed(session, &mut message.reader)
             }
         }
     }
 }
 
-impl Default for EventLogHandler {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl EventLogHandler {
-    pub fn new() -> EventLogHandler {
-        EventLogHandler {}
+    pub fn new(event_log_service: Arc<ThreadSafeEventLogService>) -> EventLogHandler {
+        EventLogHandler { event_log_service }
     }
 
     fn record_event(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let string_event = reader.read_str()?;
         let category_id = reader.read_u32()?;
 
-        info!("Recording event category={category_id} event={string_event}");
+        debug!("Recording event category={category_id} event={string_event}");
+
+        self.event_log_service.record_events(
+            session,
+            vec![Event {
+                category_id,
+                payload: EventPayload::Text(string_event),
+            }],
+        )?;
 
         TaskReply::with_only_error_code(BdErrorCode::NoError, EventLogTaskId::RecordEvent)
             .to_response()
     }
 
     fn record_event_bin(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let binary_data = reader.read_blob()?;
         let category_id = reader.read_u32()?;
 
-        info!(
+        debug!(
             "Recording binary event category={category_id} data_len={}",
             binary_data.len()
         );
 
+        self.event_log_service.record_events(
+            session,
+            vec![Event {
+                category_id,
+                payload: EventPayload::Binary(binary_data),
+            }],
+        )?;
+
         TaskReply::with_only_error_code(BdErrorCode::NoError, EventLogTaskId::RecordEventBin)
             .to_response()
     }
 
     fn record_events(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let category_id = reader.read_u32()?;
         let event_count = reader.read_u32()?;
 
+        let mut events = Vec::new();
         for _ in 0..event_count {
-            let string_event = reader.read_str()?;
-            info!("Recording event category={category_id} event={string_event}");
+            events.push(Event {
+                category_id,
+                payload: EventPayload::Text(reader.read_str()?),
+            });
         }
 
+        debug!("Recording {event_count} events category={category_id}");
+
+        self.event_log_service.record_events(session, events)?;
+
         TaskReply::with_only_error_code(BdErrorCode::NoError, EventLogTaskId::RecordEvents)
             .to_response()
     }
 
     fn record_events_mixed(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let event_count = reader.read_u32()?;
 
+        let mut events = Vec::new();
         for _ in 0..event_count {
-            let event_info = EventInfo::deserialize(reader)?;
-            if let Some(binary_data) = event_info.binary_data {
-                info!(
-                    "Recording binary event category={} data_len={}",
-                    event_info.category_id,
-                    binary_data.len()
-                );
-            } else if let Some(string_data) = event_info.string_data {
-                info!(
-                    "Recording event category={} event={}",
-                    event_info.category_id, string_data
-                );
-            }
+            events.push(Event::deserialize(reader)?);
         }
 
-        TaskReply::with_only_error_code(BdErrorCode::NoError, EventLogTaskId::RecordEvents)
+        debug!("Recording {event_count} mixed events");
+
+        self.event_log_service.record_events(session, events)?;
+
+        TaskReply::with_only_error_code(BdErrorCode::NoError, EventLogTaskId::RecordEventsMixed)
             .to_response()
     }
 }
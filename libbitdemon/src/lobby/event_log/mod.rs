@@ -1,4 +1,6 @@
 ﻿mod handler;
 mod result;
+mod service;
 
-pub use handler::EventLogHandler;
+pub use handler::{EventLogHandler, EventLogTaskId};
+pub use service::*;
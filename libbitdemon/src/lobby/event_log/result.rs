@@ -1,14 +1,9 @@
-﻿use crate::messaging::bd_reader::BdReader;
+﻿use crate::lobby::event_log::{Event, EventPayload};
+use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_serialization::BdDeserialize;
 use std::error::Error;
 
-pub struct EventInfo {
-    pub category_id: u32,
-    pub binary_data: Option<Vec<u8>>,
-    pub string_data: Option<String>,
-}
-
-impl BdDeserialize for EventInfo {
+impl BdDeserialize for Event {
     fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>>
     where
         Self: Sized,
@@ -16,22 +11,15 @@ impl BdDeserialize for EventInfo {
         let category_id = reader.read_u32()?;
         let is_binary = reader.read_bool()?;
 
-        if is_binary {
-            let binary_data = reader.read_blob()?;
-
-            Ok(EventInfo {
-                category_id,
-                binary_data: Some(binary_data),
-                string_data: None,
-            })
+        let payload = if is_binary {
+            EventPayload::Binary(reader.read_blob()?)
         } else {
-            let string_data = reader.read_str()?;
+            EventPayload::Text(reader.read_str()?)
+        };
 
-            Ok(EventInfo {
-                category_id,
-                binary_data: None,
-                string_data: Some(string_data),
-            })
-        }
+        Ok(Event {
+            category_id,
+            payload,
+        })
     }
 }
@@ -0,0 +1,27 @@
+use crate::networking::bd_session::BdSession;
+use std::error::Error;
+
+/// The data of an event submitted by a client.
+pub enum EventPayload {
+    /// Events that are submitted as a string.
+    Text(String),
+    /// Events that are submitted as binary data.
+    Binary(Vec<u8>),
+}
+
+/// An event that a client submitted for telemetry purposes.
+pub struct Event {
+    /// The title-specific category of the event.
+    pub category_id: u32,
+    /// The data of the event.
+    pub payload: EventPayload,
+}
+
+pub type ThreadSafeEventLogService = dyn EventLogService + Sync + Send;
+
+/// Implements domain logic concerning telemetry events submitted by clients.
+pub trait EventLogService {
+    /// Records the specified events for the current authenticated user.
+    /// All events that were submitted with a single task are passed at once.
+    fn record_events(&self, session: &BdSession, events: Vec<Event>) -> Result<(), Box<dyn Error>>;
+}
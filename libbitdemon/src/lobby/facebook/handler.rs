@@ -0,0 +1,233 @@
+use crate::lobby::facebook::result::FacebookBoolResult;
+use crate::lobby::facebook::{FacebookServiceError, ThreadSafeFacebookService};
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::LobbyHandler;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use log::{info, warn};
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+
+pub struct FacebookHandler {
+    pub facebook_service: Arc<ThreadSafeFacebookService>,
+    /// The error code to answer with for tasks that require a registered account.
+    pub not_linked_error_code: BdErrorCode,
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum FacebookTaskId {
+    RegisterAccount = 1,
+    Post = 2,
+    UnregisterAccount = 3,
+    UploadPhoto = 4,
+    UploadVideo = 5,
+    IsRegistered = 6,
+    GetInfo = 7,
+    GetRegisteredAccounts = 8,
+}
+
+impl LobbyHandler for FacebookHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = FacebookTaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(BdErrorCode::NoError, task_id_value)
+                .to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            FacebookTaskId::RegisterAccount => self.register_account(session, &mut message.reader),
+            FacebookTaskId::UnregisterAccount => {
+                self.unregister_account(session, &mut message.reader)
+            }
+            FacebookTaskId::IsRegistered => self.is_registered(session, &mut message.reader),
+            FacebookTaskId::GetRegisteredAccounts => {
+                self.get_registered_accounts(session, &mut message.reader)
+            }
+            FacebookTaskId::Post => self.post(session, &mut message.reader),
+            FacebookTaskId::UploadPhoto => self.upload_photo(session, &mut message.reader),
+            FacebookTaskId::UploadVideo => self.upload_video(session, &mut message.reader),
+            FacebookTaskId::GetInfo => self.get_info(session, &mut message.reader),
+        }
+    }
+}
+
+impl FacebookHandler {
+    pub fn new(facebook_service: Arc<ThreadSafeFacebookService>) -> FacebookHandler {
+        FacebookHandler {
+            facebook_service,
+            not_linked_error_code: BdErrorCode::FacebookAuthTokenInvalid,
+        }
+    }
+
+    pub fn with_not_linked_error_code(mut self, error_code: BdErrorCode) -> Self {
+        self.not_linked_error_code = error_code;
+
+        self
+    }
+
+    fn register_account(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let access_token = reader.read_str()?;
+
+        info!("Trying to register account");
+
+        let result = self
+            .facebook_service
+            .register_account(session, access_token);
+
+        self.answer_without_result(FacebookTaskId::RegisterAccount, result)
+    }
+
+    fn unregister_account(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        info!("Trying to unregister account");
+
+        let result = self.facebook_service.unregister_account(session);
+
+        self.answer_without_result(FacebookTaskId::UnregisterAccount, result)
+    }
+
+    fn is_registered(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self
+            .facebook_service
+            .is_registered(session)
+            .map(|value| vec![Box::new(FacebookBoolResult { value }) as Box<dyn BdSerialize>]);
+
+        self.answer_with_results(FacebookTaskId::IsRegistered, result)
+    }
+
+    fn get_info(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self
+            .facebook_service
+            .get_info(session)
+            .map(|info| vec![Box::new(info) as Box<dyn BdSerialize>]);
+
+        self.answer_with_results(FacebookTaskId::GetInfo, result)
+    }
+
+    fn get_registered_accounts(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self
+            .facebook_service
+            .get_registered_accounts(session)
+            .map(|accounts| {
+                accounts
+                    .into_iter()
+                    .map(|account| Box::new(account) as Box<dyn BdSerialize>)
+                    .collect()
+            });
+
+        self.answer_with_results(FacebookTaskId::GetRegisteredAccounts, result)
+    }
+
+    fn post(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let message = reader.read_str()?;
+
+        info!("Trying to post {message}");
+
+        let result = self.facebook_service.post(session, message);
+
+        self.answer_without_result(FacebookTaskId::Post, result)
+    }
+
+    fn upload_photo(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let file_id = reader.read_u64()?;
+
+        info!("Trying to upload photo {file_id}");
+
+        let result = self.facebook_service.upload_photo(session, file_id);
+
+        self.answer_without_result(FacebookTaskId::UploadPhoto, result)
+    }
+
+    fn upload_video(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let file_id = reader.read_u64()?;
+
+        info!("Trying to upload video {file_id}");
+
+        let result = self.facebook_service.upload_video(session, file_id);
+
+        self.answer_without_result(FacebookTaskId::UploadVideo, result)
+    }
+
+    fn answer_with_results(
+        &self,
+        task_id: FacebookTaskId,
+        result: Result<Vec<Box<dyn BdSerialize>>, FacebookServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        match result {
+            Ok(results) => TaskReply::with_results(task_id, results).to_response(),
+            Err(error) => {
+                TaskReply::with_only_error_code(self.error_code_for(error), task_id).to_response()
+            }
+        }
+    }
+
+    fn answer_without_result(
+        &self,
+        task_id: FacebookTaskId,
+        result: Result<(), FacebookServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let error_code = match result {
+            Ok(()) => BdErrorCode::NoError,
+            Err(error) => self.error_code_for(error),
+        };
+
+        TaskReply::with_only_error_code(error_code, task_id).to_response()
+    }
+
+    fn error_code_for(&self, error: FacebookServiceError) -> BdErrorCode {
+        match error {
+            FacebookServiceError::AuthAttemptFailedError => BdErrorCode::FacebookAuthAttemptFailed,
+            FacebookServiceError::AccountAlreadyRegisteredError => {
+                BdErrorCode::FacebookAccountAlreadyRegistered
+            }
+            FacebookServiceError::NotRegisteredError => self.not_linked_error_code,
+            FacebookServiceError::PhotoDoesNotExistError => BdErrorCode::FacebookPhotoDoesNotExist,
+            FacebookServiceError::VideoDoesNotExistError => BdErrorCode::FacebookVideoDoesNotExist,
+            FacebookServiceError::UnavailableError => BdErrorCode::FacebookUnavailable,
+        }
+    }
+}
@@ -0,0 +1,6 @@
+mod handler;
+mod result;
+mod service;
+
+pub use handler::{FacebookHandler, FacebookTaskId};
+pub use service::*;
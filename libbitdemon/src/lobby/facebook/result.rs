@@ -0,0 +1,15 @@
+use crate::lobby::facebook::FacebookAccountInfo;
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+#[derive(BdSerialize)]
+pub struct FacebookBoolResult {
+    pub value: bool,
+}
+
+impl BdSerialize for FacebookAccountInfo {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_str(self.name.as_str())
+    }
+}
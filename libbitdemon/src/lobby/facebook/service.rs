@@ -0,0 +1,122 @@
+use crate::networking::bd_session::BdSession;
+
+/// Details about the Facebook account a user registered.
+pub struct FacebookAccountInfo {
+    /// The name that is shown for the account.
+    pub name: String,
+}
+
+/// Errors that may occur when handling Facebook calls.
+#[derive(Debug)]
+pub enum FacebookServiceError {
+    /// The access token of the Facebook account was rejected.
+    AuthAttemptFailedError,
+    /// The current authenticated user already registered a Facebook account.
+    AccountAlreadyRegisteredError,
+    /// The current authenticated user did not register a Facebook account.
+    NotRegisteredError,
+    /// The photo to upload does not exist.
+    PhotoDoesNotExistError,
+    /// The video to upload does not exist.
+    VideoDoesNotExistError,
+    /// The request could not be forwarded to Facebook.
+    UnavailableError,
+}
+
+pub type ThreadSafeFacebookService = dyn FacebookService + Sync + Send;
+
+/// Implements domain logic concerning registering Facebook accounts and sharing content to them.
+///
+/// Sharing content is optional.
+/// Implementations that do not support it answer with [`UnavailableError`][1].
+///
+/// [1]: FacebookServiceError::UnavailableError
+pub trait FacebookService {
+    /// Registers a Facebook account for the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`AuthAttemptFailedError`][1]: The access token was rejected.
+    /// * [`AccountAlreadyRegisteredError`][2]: The user already registered an account.
+    ///
+    /// [1]: FacebookServiceError::AuthAttemptFailedError
+    /// [2]: FacebookServiceError::AccountAlreadyRegisteredError
+    fn register_account(
+        &self,
+        session: &BdSession,
+        access_token: String,
+    ) -> Result<(), FacebookServiceError>;
+
+    /// Removes the Facebook account of the current authenticated user.
+    /// Does nothing if no account is registered.
+    fn unregister_account(&self, session: &BdSession) -> Result<(), FacebookServiceError>;
+
+    /// Checks whether the current authenticated user registered a Facebook account.
+    fn is_registered(&self, session: &BdSession) -> Result<bool, FacebookServiceError>;
+
+    /// Retrieves details about the Facebook account of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`NotRegisteredError`][1]: The user did not register an account.
+    ///
+    /// [1]: FacebookServiceError::NotRegisteredError
+    fn get_info(&self, session: &BdSession) -> Result<FacebookAccountInfo, FacebookServiceError>;
+
+    /// Retrieves all Facebook accounts the current authenticated user registered.
+    fn get_registered_accounts(
+        &self,
+        session: &BdSession,
+    ) -> Result<Vec<FacebookAccountInfo>, FacebookServiceError>;
+
+    /// Posts a message on the Facebook account of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`NotRegisteredError`][1]: The user did not register an account.
+    /// * [`UnavailableError`][2]: The post could not be delivered.
+    ///
+    /// [1]: FacebookServiceError::NotRegisteredError
+    /// [2]: FacebookServiceError::UnavailableError
+    fn post(&self, _session: &BdSession, _message: String) -> Result<(), FacebookServiceError> {
+        Err(FacebookServiceError::UnavailableError)
+    }
+
+    /// Uploads a user file as photo to the Facebook account of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`NotRegisteredError`][1]: The user did not register an account.
+    /// * [`PhotoDoesNotExistError`][2]: The file does not exist.
+    /// * [`UnavailableError`][3]: The photo could not be delivered.
+    ///
+    /// [1]: FacebookServiceError::NotRegisteredError
+    /// [2]: FacebookServiceError::PhotoDoesNotExistError
+    /// [3]: FacebookServiceError::UnavailableError
+    fn upload_photo(
+        &self,
+        _session: &BdSession,
+        _file_id: u64,
+    ) -> Result<(), FacebookServiceError> {
+        Err(FacebookServiceError::UnavailableError)
+    }
+
+    /// Uploads a user file as video to the Facebook account of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`NotRegisteredError`][1]: The user did not register an account.
+    /// * [`VideoDoesNotExistError`][2]: The file does not exist.
+    /// * [`UnavailableError`][3]: The video could not be delivered.
+    ///
+    /// [1]: FacebookServiceError::NotRegisteredError
+    /// [2]: FacebookServiceError::VideoDoesNotExistError
+    /// [3]: FacebookServiceError::UnavailableError
+    fn upload_video(
+        &self,
+        _session: &BdSession,
+        _file_id: u64,
+    ) -> Result<(), FacebookServiceError> {
+        Err(FacebookServiceError::UnavailableError)
+    }
+}
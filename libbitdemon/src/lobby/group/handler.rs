@@ -19,7 +19,7 @@ pub struct GroupHandler {
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum GroupTaskId {
+pub enum GroupTaskId {
     SetGroups = 1,
     SetGroupsForEntity = 2,
     GetEntityGroups = 3,
@@ -2,5 +2,5 @@
 mod result;
 mod service;
 
-pub use handler::GroupHandler;
+pub use handler::{GroupHandler, GroupTaskId};
 pub use service::*;
@@ -15,7 +15,7 @@ pub struct KeyArchiveHandler {}
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum KeyArchiveTaskId {
+pub enum KeyArchiveTaskId {
     Write = 1,
     Read = 2,
     ReadAll = 3,
@@ -1,4 +1,4 @@
 ﻿mod handler;
 mod result;
 
-pub use handler::KeyArchiveHandler;
+pub use handler::{KeyArchiveHandler, KeyArchiveTaskId};
@@ -1,15 +1,7 @@
 ﻿use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_serialization::BdDeserialize;
-use num_traits::FromPrimitive;
-use snafu::Snafu;
 use std::error::Error;
 
-#[derive(Debug, Snafu)]
-enum KeyArchiveResultError {
-    #[snafu(display("Value is not a valid update type (value={value})"))]
-    InvalidUpdateType { value: u8 },
-}
-
 #[derive(Debug, FromPrimitive, ToPrimitive)]
 pub enum KeyArchiveUpdateType {
     Replace = 0,
@@ -37,14 +29,7 @@ impl BdDeserialize for KeyValuePairWriteResult {
     {
         let index = reader.read_u16()?;
         let value = reader.read_i64()?;
-        let update_type_value = reader.read_u8()?;
-
-        let update_type = KeyArchiveUpdateType::from_u8(update_type_value).ok_or_else(|| {
-            InvalidUpdateTypeSnafu {
-                value: update_type_value,
-            }
-            .build()
-        })?;
+        let update_type = reader.read_enum_u8()?;
 
         Ok(KeyValuePairWriteResult {
             index,
@@ -0,0 +1,283 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::lobby::league::result::TeamIdResult;
+use crate::lobby::league::{LeagueServiceError, TeamOrderType, ThreadSafeLeagueService};
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::LobbyHandler;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use log::{info, warn};
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+
+pub struct LeagueHandler {
+    pub league_service: Arc<ThreadSafeLeagueService>,
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum LeagueTaskId {
+    // SetTeamIcon
+    // GetTeamLeaguesAndSubdivisions
+    // IncrementGamesPlayedCount
+    GetTeamId = 1,
+    GetTeamIDsForUser = 2,
+    GetTeamSubdivisions = 3,
+    SetTeamName = 4,
+
+    // ? = 5
+    GetTeamInfos = 6,
+    GetTeamMemberInfos = 8,
+    GetTeamSubdivisionInfos = 20,
+    GetTeamSubdivisionHistory = 21,
+}
+
+impl LobbyHandler for LeagueHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = LeagueTaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(BdErrorCode::NoError, task_id_value)
+                .to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            LeagueTaskId::GetTeamId => self.get_team_id(session, &mut message.reader),
+            LeagueTaskId::GetTeamIDsForUser => {
+                self.get_team_ids_for_user(session, &mut message.reader)
+            }
+            LeagueTaskId::GetTeamSubdivisions => {
+                self.get_team_subdivisions(session, &mut message.reader)
+            }
+            LeagueTaskId::SetTeamName => self.set_team_name(session, &mut message.reader),
+            LeagueTaskId::GetTeamInfos => self.get_team_infos(session, &mut message.reader),
+            LeagueTaskId::GetTeamMemberInfos => {
+                self.get_team_member_infos(session, &mut message.reader)
+            }
+            LeagueTaskId::GetTeamSubdivisionInfos => {
+                self.get_team_subdivision_infos(session, &mut message.reader)
+            }
+            LeagueTaskId::GetTeamSubdivisionHistory => {
+                self.get_team_subdivision_history(session, &mut message.reader)
+            }
+        }
+    }
+}
+
+impl LeagueHandler {
+    pub fn new(league_service: Arc<ThreadSafeLeagueService>) -> LeagueHandler {
+        LeagueHandler { league_service }
+    }
+
+    fn get_team_id(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let user_ids = reader.read_u64_array()?;
+
+        info!("Retrieving team id of users {user_ids:?}");
+
+        match self.league_service.get_team_id(session, user_ids) {
+            Ok(team_id) => TaskReply::with_results(
+                LeagueTaskId::GetTeamId,
+                vec![Box::new(TeamIdResult { team_id })],
+            )
+            .to_response(),
+            Err(error) => {
+                TaskReply::with_only_error_code(error.into(), LeagueTaskId::GetTeamId).to_response()
+            }
+        }
+    }
+
+    fn get_team_ids_for_user(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let user_id = reader.read_u64()?;
+        let order_type: TeamOrderType = reader.read_enum_u8()?;
+        let offset = reader.read_u32()?;
+        let max_results = reader.read_u32()?;
+
+        let result = self.league_service.get_team_ids_for_user(
+            session,
+            user_id,
+            order_type,
+            offset as usize,
+            max_results as usize,
+        );
+
+        match result {
+            Ok(team_ids) => {
+                let total_count = team_ids.total_count();
+                let offset = team_ids.offset();
+                let team_ids = team_ids
+                    .into_data()
+                    .into_iter()
+                    .map(|team_id| Box::new(TeamIdResult { team_id }) as Box<dyn BdSerialize>)
+                    .collect();
+
+                TaskReply::with_result_slice(
+                    LeagueTaskId::GetTeamIDsForUser,
+                    ResultSlice::with_total_count(team_ids, offset, total_count),
+                )
+                .to_response()
+            }
+            Err(error) => {
+                TaskReply::with_only_error_code(error.into(), LeagueTaskId::GetTeamIDsForUser)
+                    .to_response()
+            }
+        }
+    }
+
+    fn get_team_subdivisions(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let team_id = reader.read_u64()?;
+        let league_ids = reader.read_u64_array()?;
+
+        match self
+            .league_service
+            .get_team_subdivisions(session, team_id, league_ids)
+        {
+            Ok(subdivisions) => TaskReply::with_results(
+                LeagueTaskId::GetTeamSubdivisions,
+                subdivisions
+                    .into_iter()
+                    .map(|subdivision| Box::new(subdivision) as Box<dyn BdSerialize>)
+                    .collect(),
+            )
+            .to_response(),
+            Err(error) => {
+                TaskReply::with_only_error_code(error.into(), LeagueTaskId::GetTeamSubdivisions)
+                    .to_response()
+            }
+        }
+    }
+
+    fn set_team_name(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let team_id = reader.read_u64()?;
+        let name = reader.read_str()?;
+
+        info!("Renaming team {team_id} to {name}");
+
+        let error_code = match self.league_service.set_team_name(session, team_id, name) {
+            Ok(()) => BdErrorCode::NoError,
+            Err(error) => error.into(),
+        };
+
+        TaskReply::with_only_error_code(error_code, LeagueTaskId::SetTeamName).to_response()
+    }
+
+    fn get_team_infos(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let team_ids = reader.read_u64_array()?;
+
+        let team_infos = self.league_service.get_team_infos(session, team_ids);
+
+        TaskReply::with_results(
+            LeagueTaskId::GetTeamInfos,
+            team_infos
+                .into_iter()
+                .map(|team_info| Box::new(team_info) as Box<dyn BdSerialize>)
+                .collect(),
+        )
+        .to_response()
+    }
+
+    fn get_team_member_infos(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let team_ids = reader.read_u64_array()?;
+
+        let member_infos = self.league_service.get_team_member_infos(session, team_ids);
+
+        TaskReply::with_results(
+            LeagueTaskId::GetTeamMemberInfos,
+            member_infos
+                .into_iter()
+                .map(|member_info| Box::new(member_info) as Box<dyn BdSerialize>)
+                .collect(),
+        )
+        .to_response()
+    }
+
+    fn get_team_subdivision_infos(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let subdivision_ids = reader.read_u64_array()?;
+
+        let subdivision_infos = self
+            .league_service
+            .get_team_subdivision_infos(session, subdivision_ids);
+
+        TaskReply::with_results(
+            LeagueTaskId::GetTeamSubdivisionInfos,
+            subdivision_infos
+                .into_iter()
+                .map(|subdivision_info| Box::new(subdivision_info) as Box<dyn BdSerialize>)
+                .collect(),
+        )
+        .to_response()
+    }
+
+    fn get_team_subdivision_history(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let team_id = reader.read_u64()?;
+        let league_id = reader.read_u64()?;
+        let season_ids = reader.read_u64_array()?;
+
+        let history = self
+            .league_service
+            .get_team_subdivision_history(session, team_id, league_id, season_ids);
+
+        TaskReply::with_results(
+            LeagueTaskId::GetTeamSubdivisionHistory,
+            history
+                .into_iter()
+                .map(|entry| Box::new(entry) as Box<dyn BdSerialize>)
+                .collect(),
+        )
+        .to_response()
+    }
+}
+
+impl From<LeagueServiceError> for BdErrorCode {
+    fn from(value: LeagueServiceError) -> Self {
+        match value {
+            LeagueServiceError::InvalidTeamSizeError => BdErrorCode::LeagueInvalidTeamSize,
+            LeagueServiceError::InvalidTeamError => BdErrorCode::LeagueInvalidTeam,
+            LeagueServiceError::TeamNameTooLongError => BdErrorCode::LeagueTeamNameTooLong,
+            LeagueServiceError::TooManyResultsRequestedError => {
+                BdErrorCode::LeagueTooManyResultsRequested
+            }
+        }
+    }
+}
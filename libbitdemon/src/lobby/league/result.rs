@@ -0,0 +1,51 @@
+use crate::lobby::league::{
+    SubdivisionHistoryEntry, SubdivisionInfo, TeamInfo, TeamMemberInfo, TeamSubdivision,
+};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+#[derive(BdSerialize)]
+pub struct TeamIdResult {
+    pub team_id: u64,
+}
+
+impl BdSerialize for TeamInfo {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.team_id)?;
+        writer.write_str(&self.name)?;
+        writer.write_u64_array(&self.member_ids)?;
+        writer.write_i64(self.last_active_at)
+    }
+}
+
+impl BdSerialize for TeamMemberInfo {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.team_id)?;
+        writer.write_u64(self.user_id)?;
+        writer.write_str(&self.username)
+    }
+}
+
+impl BdSerialize for TeamSubdivision {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.league_id)?;
+        writer.write_u64(self.subdivision_id)
+    }
+}
+
+impl BdSerialize for SubdivisionInfo {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.subdivision_id)?;
+        writer.write_u64(self.league_id)?;
+        writer.write_str(&self.name)?;
+        writer.write_u32(self.team_count)
+    }
+}
+
+impl BdSerialize for SubdivisionHistoryEntry {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.season_id)?;
+        writer.write_u64(self.subdivision_id)
+    }
+}
@@ -0,0 +1,149 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::networking::bd_session::BdSession;
+use num_derive::{FromPrimitive, ToPrimitive};
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum TeamOrderType {
+    OrderByTeamId = 0x0,
+    OrderByRecentActivity = 0x1,
+}
+
+/// A fixed group of users competing in leagues together.
+/// Users playing on their own form a team with a single member.
+pub struct TeamInfo {
+    pub team_id: u64,
+    pub name: String,
+    pub member_ids: Vec<u64>,
+    /// The seconds timestamp of when the team last played or changed.
+    pub last_active_at: i64,
+}
+
+pub struct TeamMemberInfo {
+    pub team_id: u64,
+    pub user_id: u64,
+    pub username: String,
+}
+
+/// The subdivision of a league that a team competes in.
+pub struct TeamSubdivision {
+    pub league_id: u64,
+    pub subdivision_id: u64,
+}
+
+/// A group of teams within a league that are ranked against each other.
+pub struct SubdivisionInfo {
+    pub subdivision_id: u64,
+    pub league_id: u64,
+    pub name: String,
+    pub team_count: u32,
+}
+
+/// The subdivision a team competed in during a season.
+pub struct SubdivisionHistoryEntry {
+    pub season_id: u64,
+    pub subdivision_id: u64,
+}
+
+/// Errors that may occur when handling league calls.
+#[derive(Debug)]
+pub enum LeagueServiceError {
+    /// A team must have at least one and at most a limited amount of members.
+    InvalidTeamSizeError,
+    /// The team does not exist or the user is not a member of it.
+    InvalidTeamError,
+    /// The team name exceeds the maximum length.
+    TeamNameTooLongError,
+    /// More results were requested than allowed.
+    TooManyResultsRequestedError,
+}
+
+pub type ThreadSafeLeagueService = dyn LeagueService + Sync + Send;
+
+/// Implements domain logic concerning teams competing in the subdivisions of leagues.
+pub trait LeagueService {
+    /// Retrieves the id of the team consisting of exactly the specified users.
+    /// The team is created if it does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidTeamSizeError`][1] if there are no or too many users.
+    ///
+    /// [1]: LeagueServiceError::InvalidTeamSizeError
+    fn get_team_id(
+        &self,
+        session: &BdSession,
+        user_ids: Vec<u64>,
+    ) -> Result<u64, LeagueServiceError>;
+
+    /// Retrieves the ids of all teams the user is a member of.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TooManyResultsRequestedError`][1] if too many results were requested.
+    ///
+    /// [1]: LeagueServiceError::TooManyResultsRequestedError
+    fn get_team_ids_for_user(
+        &self,
+        session: &BdSession,
+        user_id: u64,
+        order_type: TeamOrderType,
+        item_offset: usize,
+        item_count: usize,
+    ) -> Result<ResultSlice<u64>, LeagueServiceError>;
+
+    /// Retrieves the subdivisions the team competes in for each of the leagues.
+    /// Teams are placed into a subdivision of a league when it is requested for the first time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidTeamError`][1] if the team does not exist.
+    ///
+    /// [1]: LeagueServiceError::InvalidTeamError
+    fn get_team_subdivisions(
+        &self,
+        session: &BdSession,
+        team_id: u64,
+        league_ids: Vec<u64>,
+    ) -> Result<Vec<TeamSubdivision>, LeagueServiceError>;
+
+    /// Renames a team of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvalidTeamError`][1] if the user is not a member of the team
+    /// and a [`TeamNameTooLongError`][2] if the name is too long.
+    ///
+    /// [1]: LeagueServiceError::InvalidTeamError
+    /// [2]: LeagueServiceError::TeamNameTooLongError
+    fn set_team_name(
+        &self,
+        session: &BdSession,
+        team_id: u64,
+        name: String,
+    ) -> Result<(), LeagueServiceError>;
+
+    /// Retrieves the teams with the specified ids. Unknown teams are omitted.
+    fn get_team_infos(&self, session: &BdSession, team_ids: Vec<u64>) -> Vec<TeamInfo>;
+
+    /// Retrieves the members of the teams with the specified ids.
+    fn get_team_member_infos(&self, session: &BdSession, team_ids: Vec<u64>)
+        -> Vec<TeamMemberInfo>;
+
+    /// Retrieves the subdivisions with the specified ids. Unknown subdivisions are omitted.
+    fn get_team_subdivision_infos(
+        &self,
+        session: &BdSession,
+        subdivision_ids: Vec<u64>,
+    ) -> Vec<SubdivisionInfo>;
+
+    /// Retrieves the subdivisions of the league the team competed in during the seasons.
+    /// Seasons the team did not compete in are omitted.
+    fn get_team_subdivision_history(
+        &self,
+        session: &BdSession,
+        team_id: u64,
+        league_id: u64,
+        season_ids: Vec<u64>,
+    ) -> Vec<SubdivisionHistoryEntry>;
+}
@@ -0,0 +1,160 @@
+use crate::lobby::link_code::{LinkCodeServiceError, ThreadSafeLinkCodeService};
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::LobbyHandler;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use log::warn;
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+
+pub struct LinkCodeHandler {
+    pub link_code_service: Arc<ThreadSafeLinkCodeService>,
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum LinkCodeTaskId {
+    GenerateCode = 1,
+    RedeemCode = 2,
+    GetLinkedUsers = 3,
+    MergeAccounts = 4,
+}
+
+impl LobbyHandler for LinkCodeHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = LinkCodeTaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(BdErrorCode::NoError, task_id_value)
+                .to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            LinkCodeTaskId::GenerateCode => self.generate_code(session, &mut message.reader),
+            LinkCodeTaskId::RedeemCode => self.redeem_code(session, &mut message.reader),
+            LinkCodeTaskId::GetLinkedUsers => self.get_linked_users(session, &mut message.reader),
+            LinkCodeTaskId::MergeAccounts => self.merge_accounts(session, &mut message.reader),
+        }
+    }
+}
+
+impl LinkCodeHandler {
+    pub fn new(link_code_service: Arc<ThreadSafeLinkCodeService>) -> LinkCodeHandler {
+        LinkCodeHandler { link_code_service }
+    }
+
+    fn generate_code(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self.link_code_service.generate_code(session);
+
+        match result {
+            Ok(code) => Ok(TaskReply::with_results(
+                LinkCodeTaskId::GenerateCode,
+                vec![Box::from(code)],
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                LinkCodeTaskId::GenerateCode,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn redeem_code(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let code = reader.read_str()?;
+
+        let result = self.link_code_service.redeem_code(session, code);
+
+        match result {
+            Ok(linked_user) => Ok(TaskReply::with_results(
+                LinkCodeTaskId::RedeemCode,
+                vec![Box::from(linked_user)],
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                LinkCodeTaskId::RedeemCode,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn merge_accounts(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let code = reader.read_str()?;
+
+        let result = self.link_code_service.merge_accounts(session, code);
+
+        match result {
+            Ok(linked_user) => Ok(TaskReply::with_results(
+                LinkCodeTaskId::MergeAccounts,
+                vec![Box::from(linked_user)],
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                LinkCodeTaskId::MergeAccounts,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn get_linked_users(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self.link_code_service.get_linked_users(session);
+
+        match result {
+            Ok(linked_users) => Ok(TaskReply::with_results(
+                LinkCodeTaskId::GetLinkedUsers,
+                linked_users
+                    .into_iter()
+                    .map(|linked_user| Box::from(linked_user) as Box<dyn BdSerialize>)
+                    .collect(),
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                LinkCodeTaskId::GetLinkedUsers,
+            )
+            .to_response()?),
+        }
+    }
+}
+
+impl From<LinkCodeServiceError> for BdErrorCode {
+    fn from(value: LinkCodeServiceError) -> Self {
+        match value {
+            LinkCodeServiceError::InvalidCodeError => BdErrorCode::UnlockKeyInvalid,
+            LinkCodeServiceError::CodeExpiredError => BdErrorCode::UnlockKeyInvalid,
+            LinkCodeServiceError::CodeAlreadyRedeemedError => BdErrorCode::UnlockKeyAlreadyUsedUp,
+            LinkCodeServiceError::SelfLinkNotAllowedError => BdErrorCode::UcdAccountLinkingError,
+            LinkCodeServiceError::AccountsAlreadyMergedError => BdErrorCode::UcdAccountLinkingError,
+            LinkCodeServiceError::IdentityConflictError => BdErrorCode::UcdAccountLinkingError,
+        }
+    }
+}
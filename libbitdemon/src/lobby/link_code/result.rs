@@ -0,0 +1,18 @@
+use crate::lobby::link_code::{LinkCode, LinkedUser};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+impl BdSerialize for LinkCode {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_str(self.code.as_str())?;
+        writer.write_u32((self.expires_at % (u32::MAX as i64)) as u32)
+    }
+}
+
+impl BdSerialize for LinkedUser {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.user_id)?;
+        writer.write_str(self.username.as_str())
+    }
+}
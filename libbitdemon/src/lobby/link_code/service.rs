@@ -0,0 +1,98 @@
+use crate::networking::bd_session::BdSession;
+
+/// A short code that can be redeemed by another user to link both accounts.
+pub struct LinkCode {
+    /// The code that is entered by the redeeming user.
+    pub code: String,
+    /// The seconds timestamp of when the code expires.
+    pub expires_at: i64,
+}
+
+/// A user that the current authenticated user is linked with.
+pub struct LinkedUser {
+    /// The id of the linked user.
+    pub user_id: u64,
+    /// The name of the linked user at the time of linking.
+    pub username: String,
+}
+
+/// Errors that may occur when handling link code calls.
+#[derive(Debug)]
+pub enum LinkCodeServiceError {
+    /// The code does not exist.
+    InvalidCodeError,
+    /// The code exists but is not valid anymore.
+    CodeExpiredError,
+    /// The code was already redeemed.
+    CodeAlreadyRedeemedError,
+    /// A user tried to redeem a code they generated themselves.
+    SelfLinkNotAllowedError,
+    /// Both users already belong to the same account.
+    AccountsAlreadyMergedError,
+    /// Both accounts have an identity on the same platform and cannot be merged.
+    IdentityConflictError,
+}
+
+pub type ThreadSafeLinkCodeService = dyn LinkCodeService + Sync + Send;
+
+/// Implements domain logic concerning linking accounts or devices using short codes.
+pub trait LinkCodeService {
+    /// Generates a new code for the current authenticated user.
+    /// Codes are single-use and expire after a period chosen by the implementation.
+    fn generate_code(&self, session: &BdSession) -> Result<LinkCode, LinkCodeServiceError>;
+
+    /// Redeems a code generated by another user and links the accounts of both users.
+    /// The service is expected to return the user that generated the code.
+    ///
+    /// # Errors
+    ///
+    /// * [`InvalidCodeError`][1]: The code does not exist.
+    /// * [`CodeExpiredError`][2]: The code expired.
+    /// * [`CodeAlreadyRedeemedError`][3]: The code was already redeemed.
+    /// * [`SelfLinkNotAllowedError`][4]: The code was generated by the redeeming user.
+    ///
+    /// [1]: LinkCodeServiceError::InvalidCodeError
+    /// [2]: LinkCodeServiceError::CodeExpiredError
+    /// [3]: LinkCodeServiceError::CodeAlreadyRedeemedError
+    /// [4]: LinkCodeServiceError::SelfLinkNotAllowedError
+    fn redeem_code(
+        &self,
+        session: &BdSession,
+        code: String,
+    ) -> Result<LinkedUser, LinkCodeServiceError>;
+
+    /// Redeems a code generated by another user and merges the account of the current authenticated user
+    /// into the account of the user that generated the code.
+    /// Afterward, all platform identities of both users authenticate as the same account.
+    /// The service is expected to return the user that generated the code.
+    ///
+    /// Data that belonged to the merged account is not carried over.
+    /// The current authenticated user needs to reauthenticate to use the merged account.
+    ///
+    /// # Errors
+    ///
+    /// * [`InvalidCodeError`][1]: The code does not exist.
+    /// * [`CodeExpiredError`][2]: The code expired.
+    /// * [`CodeAlreadyRedeemedError`][3]: The code was already redeemed.
+    /// * [`SelfLinkNotAllowedError`][4]: The code was generated by the redeeming user.
+    /// * [`AccountsAlreadyMergedError`][5]: Both users already share the same account.
+    /// * [`IdentityConflictError`][6]: Both accounts have an identity on the same platform.
+    ///
+    /// [1]: LinkCodeServiceError::InvalidCodeError
+    /// [2]: LinkCodeServiceError::CodeExpiredError
+    /// [3]: LinkCodeServiceError::CodeAlreadyRedeemedError
+    /// [4]: LinkCodeServiceError::SelfLinkNotAllowedError
+    /// [5]: LinkCodeServiceError::AccountsAlreadyMergedError
+    /// [6]: LinkCodeServiceError::IdentityConflictError
+    fn merge_accounts(
+        &self,
+        session: &BdSession,
+        code: String,
+    ) -> Result<LinkedUser, LinkCodeServiceError>;
+
+    /// Retrieves all users the current authenticated user is linked with.
+    fn get_linked_users(
+        &self,
+        session: &BdSession,
+    ) -> Result<Vec<LinkedUser>, LinkCodeServiceError>;
+}
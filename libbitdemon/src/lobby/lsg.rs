@@ -2,13 +2,16 @@
 use crate::auth::authentication::SessionAuthentication;
 use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
 use crate::domain::title::Title;
-use crate::lobby::response::lsg_reply::ConnectionIdResponse;
+use crate::lobby::admission::{AdmitAllPolicy, ThreadSafeSessionAdmissionPolicy};
+use crate::lobby::redeemed_proofs::RedeemedProofs;
+use crate::lobby::response::lsg_reply::{ConnectionIdResponse, LsgErrorResponse};
 use crate::lobby::LobbyHandler;
 use crate::messaging::bd_message::BdMessage;
 use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::BdErrorCode;
 use crate::messaging::StreamMode::BitMode;
 use crate::networking::bd_session::BdSession;
-use log::info;
+use log::{info, warn};
 use num_traits::FromPrimitive;
 use snafu::{ensure, OptionExt, Snafu};
 use std::error::Error;
@@ -16,11 +19,50 @@ use std::sync::Arc;
 
 pub struct LsgHandler {
     key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
+    admission_policy: Arc<ThreadSafeSessionAdmissionPolicy>,
+    redeemed_proofs: RedeemedProofs,
 }
 
 impl LsgHandler {
     pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>) -> LsgHandler {
-        LsgHandler { key_store }
+        LsgHandler {
+            key_store,
+            admission_policy: Arc::new(AdmitAllPolicy::new()),
+            redeemed_proofs: RedeemedProofs::new(),
+        }
+    }
+
+    pub fn with_admission_policy(
+        mut self,
+        admission_policy: Arc<ThreadSafeSessionAdmissionPolicy>,
+    ) -> Self {
+        self.admission_policy = admission_policy;
+
+        self
+    }
+
+    /// Marks the proof as redeemed by the session.
+    /// Returns `false` if another connection already redeemed it.
+    fn redeem_proof(
+        &self,
+        session: &BdSession,
+        auth_proof: &ClientOpaqueAuthProof,
+        now: i64,
+    ) -> bool {
+        let redeemed = self.redeemed_proofs.redeem(
+            &auth_proof.session_key,
+            auth_proof.time_expires,
+            session.id,
+            now,
+        );
+        if !redeemed {
+            warn!(
+                "Refused replayed auth proof of user_id={} username={}",
+                auth_proof.user_id, auth_proof.username
+            );
+        }
+
+        redeemed
     }
 }
 
@@ -35,6 +77,8 @@ enum LobbyServiceError {
     },
     #[snafu(display("The authentication expired (expires={expires} now={now})"))]
     AuthenticationExpired { expires: i64, now: i64 },
+    #[snafu(display("Tried to renegotiate the session key with an auth proof of another user or title (user_id={user_id} title={title:?})"))]
+    ForeignRenegotiation { user_id: u64, title: Title },
 }
 
 impl LobbyHandler for LsgHandler {
@@ -48,7 +92,7 @@ impl LobbyHandler for LsgHandler {
 
         let title_id = message.reader.read_u32()?;
         let title = Title::from_u32(title_id).with_context(|| UnknownTitleSnafu { title_id })?;
-        let _iv_seed = message.reader.read_u32()?;
+        let iv_seed = message.reader.read_u32()?;
 
         let mut auth_proof: [u8; 128] = [0; 128];
         message.reader.read_bytes(&mut auth_proof)?;
@@ -73,17 +117,69 @@ impl LobbyHandler for LsgHandler {
             }
         );
 
+        // Long-lived sessions may authenticate again to rotate their session key
+        if let Some(authentication) = session.authentication() {
+            ensure!(
+                authentication.user_id == auth_proof.user_id
+                    && authentication.title == auth_proof.title,
+                ForeignRenegotiationSnafu {
+                    user_id: auth_proof.user_id,
+                    title: auth_proof.title
+                }
+            );
+
+            if !self.redeem_proof(session, &auth_proof, now) {
+                return LsgErrorResponse::new(BdErrorCode::AuthIllegalOperation).to_response();
+            }
+
+            info!(
+                "Renegotiated session key of user_id={} username={} after {:?} (generation={})",
+                authentication.user_id,
+                authentication.username,
+                session.session_key_age().unwrap_or_default(),
+                session.session_key_generation() + 1
+            );
+            session.renegotiate_session_key(auth_proof.session_key, iv_seed);
+
+            return ConnectionIdResponse::new(session.id).to_response();
+        }
+
+        let authentication = SessionAuthentication {
+            user_id: auth_proof.user_id,
+            username: auth_proof.username.clone(),
+            session_key: auth_proof.session_key.clone(),
+            title: auth_proof.title,
+            clock_skew: (auth_proof.clock_skew != 0).then_some(auth_proof.clock_skew),
+            anonymous: auth_proof.anonymous,
+        };
+
+        if let Err(rejection) = self.admission_policy.admit(session, &authentication) {
+            warn!(
+                "Refused session of user_id={} username={}: {rejection:?}",
+                authentication.user_id, authentication.username
+            );
+            let mut response = LsgErrorResponse::new(rejection.into());
+            if let Some(message) = self
+                .admission_policy
+                .rejection_message(rejection, &authentication)
+            {
+                response = response.with_message(message);
+            }
+
+            return response.to_response();
+        }
+
+        // Only admitted sessions redeem the proof, so a rejected client may retry with it
+        if !self.redeem_proof(session, &auth_proof, now) {
+            return LsgErrorResponse::new(BdErrorCode::AuthIllegalOperation).to_response();
+        }
+
         info!(
             "Authenticated with opaque data user_id={} username={}",
-            auth_proof.user_id, auth_proof.username
+            authentication.user_id, authentication.username
         );
 
-        session.set_authentication(SessionAuthentication {
-            user_id: auth_proof.user_id,
-            username: auth_proof.username,
-            session_key: auth_proof.session_key,
-            title: auth_proof.title,
-        });
+        session.set_authentication(authentication);
 
         ConnectionIdResponse::new(session.id).to_response()
     }
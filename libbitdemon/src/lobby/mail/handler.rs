@@ -0,0 +1,159 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::lobby::mail::{MailHeader, MailServiceError, ThreadSafeMailService};
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::LobbyHandler;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use log::warn;
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+
+pub struct MailHandler {
+    pub mail_service: Arc<ThreadSafeMailService>,
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum MailTaskId {
+    SendMail = 1,
+    GetMailHeaders = 2,
+    GetMailBodies = 3,
+    DeleteMails = 4,
+}
+
+impl LobbyHandler for MailHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = MailTaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(BdErrorCode::NoError, task_id_value)
+                .to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            MailTaskId::SendMail => self.send_mail(session, &mut message.reader),
+            MailTaskId::GetMailHeaders => self.get_mail_headers(session, &mut message.reader),
+            MailTaskId::GetMailBodies => self.get_mail_bodies(session, &mut message.reader),
+            MailTaskId::DeleteMails => self.delete_mails(session, &mut message.reader),
+        }
+    }
+}
+
+impl MailHandler {
+    pub fn new(mail_service: Arc<ThreadSafeMailService>) -> MailHandler {
+        MailHandler { mail_service }
+    }
+
+    fn send_mail(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let recipient_ids = reader.read_u64_array()?;
+        let body = reader.read_blob()?;
+
+        let result = self
+            .mail_service
+            .send_mail(session, recipient_ids.as_slice(), body);
+
+        self.answer_for_no_return_value(MailTaskId::SendMail, result)
+    }
+
+    fn get_mail_headers(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let item_offset = reader.read_u32()?;
+        let item_count = reader.read_u32()?;
+
+        let result =
+            self.mail_service
+                .get_mail_headers(session, item_offset as usize, item_count as usize);
+
+        self.answer_for_header_slice(MailTaskId::GetMailHeaders, result)
+    }
+
+    fn get_mail_bodies(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let mail_ids = reader.read_u64_array()?;
+
+        let result = self
+            .mail_service
+            .get_mail_bodies(session, mail_ids.as_slice());
+
+        match result {
+            Ok(bodies) => Ok(TaskReply::with_results(
+                MailTaskId::GetMailBodies,
+                bodies
+                    .into_iter()
+                    .map(|body| Box::from(body) as Box<dyn BdSerialize>)
+                    .collect(),
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                MailTaskId::GetMailBodies,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn delete_mails(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let mail_ids = reader.read_u64_array()?;
+
+        let result = self.mail_service.delete_mails(session, mail_ids.as_slice());
+
+        self.answer_for_no_return_value(MailTaskId::DeleteMails, result)
+    }
+
+    fn answer_for_header_slice(
+        &self,
+        task_id: MailTaskId,
+        result: Result<ResultSlice<MailHeader>, MailServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        TaskReply::builder(task_id)
+            .with_outcome(result)
+            .build()
+            .to_response()
+    }
+
+    fn answer_for_no_return_value(
+        &self,
+        task_id: MailTaskId,
+        result: Result<(), MailServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        TaskReply::builder(task_id)
+            .with_outcome(result)
+            .build()
+            .to_response()
+    }
+}
+
+impl From<MailServiceError> for BdErrorCode {
+    fn from(value: MailServiceError) -> Self {
+        match value {
+            MailServiceError::InvalidRecipientError => BdErrorCode::InvalidUserId,
+            MailServiceError::TooManyRecipientsError => BdErrorCode::TooManyEntityIdsRequested,
+            MailServiceError::MailTooLargeError => BdErrorCode::GmsgPayloadTooBig,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::lobby::mail::{MailBody, MailHeader};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+impl BdSerialize for MailHeader {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.id)?;
+        writer.write_u64(self.sender_id)?;
+        writer.write_str(self.sender_name.as_str())?;
+        writer.write_u32((self.sent_at % (u32::MAX as i64)) as u32)?;
+        writer.write_u32(self.body_size)?;
+        writer.write_bool(self.read)
+    }
+}
+
+impl BdSerialize for MailBody {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.id)?;
+        writer.write_blob(self.body.as_slice())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::networking::bd_session::BdSession;
+
+/// The metadata of a mail that is shown when listing the inbox of a user.
+#[derive(Clone)]
+pub struct MailHeader {
+    /// The id of the mail.
+    /// Must be unique across all mails of the recipient.
+    pub id: u64,
+    /// The id of the user that sent the mail.
+    pub sender_id: u64,
+    /// The name of the user that sent the mail at the time of sending it.
+    pub sender_name: String,
+    /// The seconds timestamp of when the mail was sent.
+    pub sent_at: i64,
+    /// The size of the body of the mail in bytes.
+    pub body_size: u32,
+    /// Whether the recipient already read the body of the mail.
+    pub read: bool,
+}
+
+/// The content of a mail.
+#[derive(Clone)]
+pub struct MailBody {
+    /// The id of the mail.
+    pub id: u64,
+    /// The opaque data of the mail that was set by the sender.
+    pub body: Vec<u8>,
+}
+
+/// Errors that may occur when handling mail calls.
+#[derive(Debug)]
+pub enum MailServiceError {
+    /// At least one of the specified recipients is not a valid user.
+    InvalidRecipientError,
+    /// More recipients were specified than allowed.
+    TooManyRecipientsError,
+    /// The body of the mail is too large to process.
+    MailTooLargeError,
+}
+
+pub type ThreadSafeMailService = dyn MailService + Sync + Send;
+
+/// Implements domain logic concerning mails sent between users.
+pub trait MailService {
+    /// Sends a mail from the current authenticated user to all specified recipients.
+    ///
+    /// # Errors
+    ///
+    /// * [`InvalidRecipientError`][1]: At least one recipient is not a valid user.
+    /// * [`TooManyRecipientsError`][2]: More recipients were specified than allowed.
+    /// * [`MailTooLargeError`][3]: The body of the mail is larger than allowed.
+    ///
+    /// [1]: MailServiceError::InvalidRecipientError
+    /// [2]: MailServiceError::TooManyRecipientsError
+    /// [3]: MailServiceError::MailTooLargeError
+    fn send_mail(
+        &self,
+        session: &BdSession,
+        recipient_ids: &[u64],
+        body: Vec<u8>,
+    ) -> Result<(), MailServiceError>;
+
+    /// Lists the headers of mails in the inbox of the current authenticated user, most recent first.
+    /// The result is returned as a [`ResultSlice`].
+    ///
+    /// The `item_offset` parameter describes the amount of items to skip and **NOT** an index of a page.
+    /// The amount of returned items should be equal or less than the value of the `item_count` parameter.
+    fn get_mail_headers(
+        &self,
+        session: &BdSession,
+        item_offset: usize,
+        item_count: usize,
+    ) -> Result<ResultSlice<MailHeader>, MailServiceError>;
+
+    /// Retrieves the bodies of the specified mails of the current authenticated user
+    /// and marks them as read.
+    /// Ids of mails that do not exist or that the user did not receive are omitted from the result.
+    fn get_mail_bodies(
+        &self,
+        session: &BdSession,
+        mail_ids: &[u64],
+    ) -> Result<Vec<MailBody>, MailServiceError>;
+
+    /// Deletes the specified mails of the current authenticated user.
+    /// Ids of mails that do not exist or that the user did not receive are ignored.
+    fn delete_mails(&self, session: &BdSession, mail_ids: &[u64]) -> Result<(), MailServiceError>;
+}
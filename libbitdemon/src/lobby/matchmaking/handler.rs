@@ -0,0 +1,632 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::domain::title::Title;
+use crate::lobby::matchmaking::result::{
+    EntitySessionResult, MatchmakingInfoResult, PerformanceValueResult, SessionIdResult,
+    SessionInviteResult,
+};
+use crate::lobby::matchmaking::{
+    MatchmakingAttribute, MatchmakingInvite, MatchmakingSearchCriteria, MatchmakingServiceError,
+    MatchmakingSessionId, MatchmakingSessionInfo, PerformanceValue, ThreadSafeMatchmakingService,
+};
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::{LobbyHandler, LobbyServiceId, PushMessage};
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use crate::networking::session_manager::SessionManager;
+use log::warn;
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+
+pub struct MatchmakingHandler {
+    pub matchmaking_service: Arc<ThreadSafeMatchmakingService>,
+    /// Delivers invites to the sessions of invited users, if set.
+    session_manager: Option<Arc<SessionManager>>,
+}
+
+/// The task ids of the matchmaking service.
+/// Only the order of the tasks is known, so the values are inferred from it.
+/// `FindSessionsFromIds` is the least certain, since it is the last one.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum MatchmakingTaskId {
+    CreateSession = 1,
+    UpdateSession = 2,
+    DeleteSession = 3,
+    FindSessionFromId = 4,
+    FindSessions = 5,
+    NotifyJoin = 6,
+    NotifyLeave = 7,
+    InviteToSession = 8,
+    SubmitPerformance = 9,
+    GetPerformanceValues = 10,
+    GetSessionInvites = 11,
+    UpdateSessionPlayers = 12,
+    FindSessionsPaged = 13,
+    FindSessionsByEntityIds = 14,
+    FindSessionsFromIds = 15,
+}
+
+impl LobbyHandler for MatchmakingHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = MatchmakingTaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(BdErrorCode::NoError, task_id_value)
+                .to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            MatchmakingTaskId::CreateSession => self.create_session(session, &mut message.reader),
+            MatchmakingTaskId::UpdateSession => self.update_session(session, &mut message.reader),
+            MatchmakingTaskId::DeleteSession => self.delete_session(session, &mut message.reader),
+            MatchmakingTaskId::FindSessionFromId => {
+                self.find_session_from_id(session, &mut message.reader)
+            }
+            MatchmakingTaskId::NotifyJoin => {
+                self.update_players(session, &mut message.reader, MatchmakingTaskId::NotifyJoin)
+            }
+            MatchmakingTaskId::NotifyLeave => {
+                self.update_players(session, &mut message.reader, MatchmakingTaskId::NotifyLeave)
+            }
+            MatchmakingTaskId::UpdateSessionPlayers => self.update_players(
+                session,
+                &mut message.reader,
+                MatchmakingTaskId::UpdateSessionPlayers,
+            ),
+            MatchmakingTaskId::InviteToSession => {
+                self.invite_to_session(session, &mut message.reader)
+            }
+            MatchmakingTaskId::GetSessionInvites => self.get_session_invites(session),
+            MatchmakingTaskId::SubmitPerformance => {
+                self.submit_performance(session, &mut message.reader)
+            }
+            MatchmakingTaskId::GetPerformanceValues => {
+                self.get_performance_values(session, &mut message.reader)
+            }
+            MatchmakingTaskId::FindSessions => self.find_sessions(session, &mut message.reader),
+            MatchmakingTaskId::FindSessionsPaged => {
+                self.find_sessions_paged(session, &mut message.reader)
+            }
+            MatchmakingTaskId::FindSessionsByEntityIds => {
+                self.find_sessions_by_entity_ids(session, &mut message.reader)
+            }
+            MatchmakingTaskId::FindSessionsFromIds => {
+                self.find_sessions_from_ids(session, &mut message.reader)
+            }
+        }
+    }
+}
+
+impl MatchmakingHandler {
+    pub fn new(matchmaking_service: Arc<ThreadSafeMatchmakingService>) -> MatchmakingHandler {
+        MatchmakingHandler {
+            matchmaking_service,
+            session_manager: None,
+        }
+    }
+
+    /// Pushes invites to the authenticated sessions of invited users,
+    /// instead of them only seeing the invites when asking for them.
+    pub fn with_session_manager(mut self, session_manager: Arc<SessionManager>) -> Self {
+        self.session_manager = Some(session_manager);
+        self
+    }
+
+    fn create_session(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        // The session id of new sessions is empty and chosen by the server instead
+        let (_, info) = read_session_info(reader)?;
+
+        match self.matchmaking_service.create_session(session, info) {
+            Ok(session_id) => TaskReply::with_results(
+                MatchmakingTaskId::CreateSession,
+                vec![Box::from(SessionIdResult { session_id })],
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::CreateSession),
+        }
+    }
+
+    fn update_session(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let (session_id, info) = read_session_info(reader)?;
+
+        match self
+            .matchmaking_service
+            .update_session(session, session_id, info)
+        {
+            Ok(()) => TaskReply::with_only_error_code(
+                BdErrorCode::NoError,
+                MatchmakingTaskId::UpdateSession,
+            )
+            .to_response(),
+            Err(MatchmakingServiceError::SessionNotFoundError) => TaskReply::with_only_error_code(
+                BdErrorCode::NoEntryToUpdate,
+                MatchmakingTaskId::UpdateSession,
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::UpdateSession),
+        }
+    }
+
+    fn delete_session(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let session_id = read_session_id(reader)?;
+
+        match self.matchmaking_service.delete_session(session, session_id) {
+            Ok(()) => TaskReply::with_only_error_code(
+                BdErrorCode::NoError,
+                MatchmakingTaskId::DeleteSession,
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::DeleteSession),
+        }
+    }
+
+    fn find_session_from_id(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let session_id = read_session_id(reader)?;
+
+        match self.matchmaking_service.find_session(session, session_id) {
+            Ok(found_session) => TaskReply::with_results(
+                MatchmakingTaskId::FindSessionFromId,
+                vec![Box::from(MatchmakingInfoResult::from(found_session))],
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::FindSessionFromId),
+        }
+    }
+
+    fn update_players(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+        task_id: MatchmakingTaskId,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let session_id = read_session_id(reader)?;
+        let user_ids = reader.read_u64_array()?;
+
+        let result = match task_id {
+            MatchmakingTaskId::NotifyJoin => self
+                .matchmaking_service
+                .notify_join(session, session_id, user_ids),
+            MatchmakingTaskId::NotifyLeave => self
+                .matchmaking_service
+                .notify_leave(session, session_id, user_ids),
+            _ => self
+                .matchmaking_service
+                .update_session_players(session, session_id, user_ids),
+        };
+
+        match result {
+            Ok(()) => TaskReply::with_only_error_code(BdErrorCode::NoError, task_id).to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, task_id),
+        }
+    }
+
+    fn find_sessions_by_entity_ids(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let entity_ids = reader.read_u64_array()?;
+
+        match self
+            .matchmaking_service
+            .find_sessions_by_entity_ids(session, entity_ids)
+        {
+            Ok(entity_sessions) => TaskReply::with_results(
+                MatchmakingTaskId::FindSessionsByEntityIds,
+                entity_sessions
+                    .into_iter()
+                    .map(|entity_session| {
+                        Box::new(EntitySessionResult::from(entity_session)) as Box<dyn BdSerialize>
+                    })
+                    .collect(),
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::FindSessionsByEntityIds),
+        }
+    }
+
+    fn find_sessions_from_ids(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let mut session_ids = Vec::new();
+        while reader.next_is_blob().unwrap_or(false) {
+            session_ids.push(read_session_id(reader)?);
+        }
+
+        match self
+            .matchmaking_service
+            .find_sessions_from_ids(session, session_ids)
+        {
+            Ok(found_sessions) => TaskReply::with_results(
+                MatchmakingTaskId::FindSessionsFromIds,
+                found_sessions
+                    .into_iter()
+                    .map(|found_session| {
+                        Box::new(MatchmakingInfoResult::from(found_session)) as Box<dyn BdSerialize>
+                    })
+                    .collect(),
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::FindSessionsFromIds),
+        }
+    }
+
+    fn invite_to_session(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let session_id = read_session_id(reader)?;
+        let user_ids = reader.read_u64_array()?;
+        let attachment = reader.read_blob()?;
+
+        match self.matchmaking_service.invite_to_session(
+            session,
+            session_id,
+            user_ids,
+            attachment.clone(),
+        ) {
+            Ok(invited_user_ids) => {
+                let authentication = session.authentication().unwrap();
+                let invite = MatchmakingInvite {
+                    session_id,
+                    sender_user_id: authentication.user_id,
+                    sender_name: authentication.username.clone(),
+                    attachment,
+                };
+                self.push_invite(&invite, authentication.title, &invited_user_ids);
+
+                TaskReply::with_only_error_code(
+                    BdErrorCode::NoError,
+                    MatchmakingTaskId::InviteToSession,
+                )
+                .to_response()
+            }
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::InviteToSession),
+        }
+    }
+
+    /// Notifies all sessions of the invited users that play the same title.
+    /// Users that are not online only see the invite once they ask for their pending invites.
+    fn push_invite(&self, invite: &MatchmakingInvite, title: Title, invited_user_ids: &[u64]) {
+        let Some(session_manager) = &self.session_manager else {
+            return;
+        };
+
+        let content = SessionInviteResult::from(invite.clone());
+        for user_id in invited_user_ids {
+            for mut invited_session in session_manager.sessions_of_user(*user_id) {
+                if invited_session
+                    .authentication()
+                    .is_none_or(|authentication| authentication.title != title)
+                {
+                    continue;
+                }
+
+                let result = PushMessage::with_content(LobbyServiceId::Matchmaking, &content)
+                    .and_then(|message| {
+                        message
+                            .with_source_user(invite.sender_user_id)
+                            .send_to(&mut invited_session)
+                    });
+                if let Err(e) = result {
+                    warn!("Failed to push matchmaking invite to user {user_id}: {e}");
+                }
+            }
+        }
+    }
+
+    fn get_session_invites(&self, session: &mut BdSession) -> Result<BdResponse, Box<dyn Error>> {
+        match self.matchmaking_service.get_session_invites(session) {
+            Ok(invites) => TaskReply::with_results(
+                MatchmakingTaskId::GetSessionInvites,
+                invites
+                    .into_iter()
+                    .map(|invite| {
+                        Box::new(SessionInviteResult::from(invite)) as Box<dyn BdSerialize>
+                    })
+                    .collect(),
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::GetSessionInvites),
+        }
+    }
+
+    fn submit_performance(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let mut values = Vec::new();
+        while reader.next_is_u64().unwrap_or(false) {
+            values.push(PerformanceValue {
+                entity_id: reader.read_u64()?,
+                performance_value: reader.read_i64()?,
+            });
+        }
+
+        match self.matchmaking_service.submit_performance(session, values) {
+            Ok(()) => TaskReply::with_only_error_code(
+                BdErrorCode::NoError,
+                MatchmakingTaskId::SubmitPerformance,
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::SubmitPerformance),
+        }
+    }
+
+    fn get_performance_values(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let entity_ids = reader.read_u64_array()?;
+
+        match self
+            .matchmaking_service
+            .get_performance_values(session, entity_ids)
+        {
+            Ok(values) => TaskReply::with_results(
+                MatchmakingTaskId::GetPerformanceValues,
+                values
+                    .into_iter()
+                    .map(|value| {
+                        Box::new(PerformanceValueResult::from(value)) as Box<dyn BdSerialize>
+                    })
+                    .collect(),
+            )
+            .to_response(),
+            Err(e) => Self::handle_matchmaking_error(e, MatchmakingTaskId::GetPerformanceValues),
+        }
+    }
+
+    fn find_sessions(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let query_id = reader.read_u32()?;
+        let max_results = reader.read_u32()? as usize;
+        let criteria = read_search_criteria(query_id, reader)?;
+
+        self.reply_found_sessions(
+            session,
+            criteria,
+            0,
+            max_results,
+            MatchmakingTaskId::FindSessions,
+        )
+    }
+
+    fn find_sessions_paged(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let query_id = reader.read_u32()?;
+        let page_number = reader.read_u32()? as usize;
+        let results_per_page = reader.read_u32()? as usize;
+        let criteria = read_search_criteria(query_id, reader)?;
+
+        self.reply_found_sessions(
+            session,
+            criteria,
+            page_number * results_per_page,
+            results_per_page,
+            MatchmakingTaskId::FindSessionsPaged,
+        )
+    }
+
+    fn reply_found_sessions(
+        &self,
+        session: &mut BdSession,
+        criteria: MatchmakingSearchCriteria,
+        offset: usize,
+        max_results: usize,
+        task_id: MatchmakingTaskId,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        match self
+            .matchmaking_service
+            .find_sessions(session, criteria, offset, max_results)
+        {
+            Ok(sessions) => {
+                let total_count = sessions.total_count();
+                let offset = sessions.offset();
+                let results = sessions
+                    .into_data()
+                    .into_iter()
+                    .map(|found_session| {
+                        Box::new(MatchmakingInfoResult::from(found_session)) as Box<dyn BdSerialize>
+                    })
+                    .collect();
+
+                TaskReply::with_result_slice(
+                    task_id,
+                    ResultSlice::with_total_count(results, offset, total_count),
+                )
+                .to_response()
+            }
+            Err(e) => Self::handle_matchmaking_error(e, task_id),
+        }
+    }
+
+    fn handle_matchmaking_error(
+        error: MatchmakingServiceError,
+        task_id: MatchmakingTaskId,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        TaskReply::with_only_error_code(
+            match error {
+                MatchmakingServiceError::SessionNotFoundError => BdErrorCode::InvalidSessionId,
+                MatchmakingServiceError::PermissionDeniedError => BdErrorCode::PermissionDenied,
+                MatchmakingServiceError::InvalidQueryError => BdErrorCode::InvalidQueryId,
+                MatchmakingServiceError::InviteExistsError => BdErrorCode::SessionInviteExists,
+                MatchmakingServiceError::AttachmentTooLargeError => BdErrorCode::AttachmentTooLarge,
+            },
+            task_id,
+        )
+        .to_response()
+    }
+}
+
+/// Reads a session id that is sent as a blob of its little endian bytes.
+/// Empty blobs are read as an id of `0`, which no session has.
+pub(crate) fn read_session_id(
+    reader: &mut BdReader,
+) -> Result<MatchmakingSessionId, Box<dyn Error>> {
+    let mut bytes = [0u8; 8];
+    let blob = reader.read_blob()?;
+    let len = blob.len().min(bytes.len());
+    bytes[..len].copy_from_slice(&blob[..len]);
+
+    Ok(MatchmakingSessionId::from_le_bytes(bytes))
+}
+
+/// Reads the description of a session followed by its title specific attributes
+/// which make up the remainder of the message.
+fn read_session_info(
+    reader: &mut BdReader,
+) -> Result<(MatchmakingSessionId, MatchmakingSessionInfo), Box<dyn Error>> {
+    let session_id = read_session_id(reader)?;
+    let host_address = reader.read_blob()?;
+    let game_type = reader.read_u32()?;
+    let max_players = reader.read_u32()?;
+    let num_players = reader.read_u32()?;
+
+    let mut attributes = Vec::new();
+    while let Some(attribute) = read_attribute(reader)? {
+        attributes.push(attribute);
+    }
+
+    Ok((
+        session_id,
+        MatchmakingSessionInfo {
+            host_address,
+            game_type,
+            max_players,
+            num_players,
+            attributes,
+        },
+    ))
+}
+
+/// Reads the search parameters that are sent as a blob containing a type checked buffer.
+fn read_search_criteria(
+    query_id: u32,
+    reader: &mut BdReader,
+) -> Result<MatchmakingSearchCriteria, Box<dyn Error>> {
+    let mut criteria_reader = BdReader::new(reader.read_blob()?);
+    criteria_reader.set_type_checked(true);
+
+    let mut parameters = Vec::new();
+    while let Some(parameter) = read_attribute(&mut criteria_reader)? {
+        parameters.push(parameter);
+    }
+
+    Ok(MatchmakingSearchCriteria {
+        query_id,
+        parameters,
+    })
+}
+
+/// Reads the next attribute if the message has any more values.
+/// Only works on type checked messages since the type of attributes is not known beforehand.
+fn read_attribute(reader: &mut BdReader) -> Result<Option<MatchmakingAttribute>, Box<dyn Error>> {
+    let attribute = if reader.next_is_bool().unwrap_or(false) {
+        MatchmakingAttribute::Bool(reader.read_bool()?)
+    } else if reader.next_is_i32().unwrap_or(false) {
+        MatchmakingAttribute::I32(reader.read_i32()?)
+    } else if reader.next_is_u32().unwrap_or(false) {
+        MatchmakingAttribute::U32(reader.read_u32()?)
+    } else if reader.next_is_i64().unwrap_or(false) {
+        MatchmakingAttribute::I64(reader.read_i64()?)
+    } else if reader.next_is_u64().unwrap_or(false) {
+        MatchmakingAttribute::U64(reader.read_u64()?)
+    } else if reader.next_is_f32().unwrap_or(false) {
+        MatchmakingAttribute::F32(reader.read_f32()?)
+    } else if reader.next_is_f64().unwrap_or(false) {
+        MatchmakingAttribute::F64(reader.read_f64()?)
+    } else if reader.next_is_str().unwrap_or(false) {
+        MatchmakingAttribute::Str(reader.read_str()?)
+    } else if reader.next_is_blob().unwrap_or(false) {
+        MatchmakingAttribute::Blob(reader.read_blob()?)
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(attribute))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lobby::matchmaking::MatchmakingSession;
+    use crate::messaging::bd_serialization::BdSerialize;
+    use crate::messaging::bd_writer::BdWriter;
+
+    #[test]
+    fn session_info_is_sent_back_as_it_was_received() {
+        let session = MatchmakingSession {
+            session_id: 0x1122334455667788,
+            host_user_id: 1,
+            players: vec![1],
+            info: MatchmakingSessionInfo {
+                host_address: vec![1, 2, 3, 4],
+                game_type: 2,
+                max_players: 18,
+                num_players: 1,
+                attributes: vec![
+                    MatchmakingAttribute::U32(7),
+                    MatchmakingAttribute::I64(-5),
+                    MatchmakingAttribute::Str("mp_raid".to_string()),
+                    MatchmakingAttribute::Bool(true),
+                ],
+            },
+        };
+
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.set_type_checked(true);
+            MatchmakingInfoResult::from(session.clone())
+                .serialize(&mut writer)
+                .unwrap();
+            writer.flush().unwrap();
+        }
+
+        let mut reader = BdReader::new(buf);
+        reader.set_type_checked(true);
+        let (session_id, info) = read_session_info(&mut reader).unwrap();
+
+        assert_eq!(session_id, session.session_id);
+        assert_eq!(info, session.info);
+    }
+}
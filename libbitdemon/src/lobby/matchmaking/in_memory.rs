@@ -0,0 +1,762 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::domain::title::Title;
+use crate::lobby::matchmaking::{
+    EntitySession, MatchmakingInvite, MatchmakingQuery, MatchmakingSearchCriteria,
+    MatchmakingService, MatchmakingServiceError, MatchmakingSession, MatchmakingSessionId,
+    MatchmakingSessionInfo, PerformanceValue,
+};
+use crate::networking::bd_session::{BdSession, SessionId};
+use log::info;
+use rand::RngExt;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// The maximum size of data that can be attached to invites.
+const MAX_INVITE_ATTACHMENT_SIZE: usize = 1024;
+/// How long sessions are kept after their host last created or updated them.
+const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Keeps matchmaking sessions in memory, separated by title.
+/// Sessions are lost when the server restarts, which is fine since their hosts disconnect as well.
+///
+/// Titles without any queries find all of their sessions regardless of the query id,
+/// titles with queries can only search with those.
+///
+/// Sessions expire when their host did not update them within the session ttl,
+/// in case the host vanished without its connection being closed.
+pub struct InMemoryMatchmakingService {
+    sessions: RwLock<HashMap<Title, HashMap<MatchmakingSessionId, HostedSession>>>,
+    session_ttl: Duration,
+    queries: HashMap<Title, HashMap<u32, MatchmakingQuery>>,
+    /// Pending invites keyed by the invited user.
+    invites: RwLock<HashMap<Title, HashMap<u64, Vec<MatchmakingInvite>>>>,
+    performance_values: RwLock<HashMap<Title, HashMap<u64, i64>>>,
+}
+
+struct HostedSession {
+    session: MatchmakingSession,
+    /// The connection of the host, so that the session can be deleted when it closes.
+    host_session_id: SessionId,
+    expires_at: Instant,
+}
+
+impl HostedSession {
+    /// Counts the tracked players once there are any,
+    /// otherwise the count sent by the host is kept.
+    fn refresh_player_count(&mut self) {
+        if !self.session.players.is_empty() {
+            self.session.info.num_players = self.session.players.len() as u32;
+        }
+    }
+}
+
+impl MatchmakingService for InMemoryMatchmakingService {
+    fn create_session(
+        &self,
+        session: &BdSession,
+        info: MatchmakingSessionInfo,
+    ) -> Result<MatchmakingSessionId, MatchmakingServiceError> {
+        let authentication = session.authentication().unwrap();
+        self.expire_sessions(authentication.title);
+
+        let mut sessions = self.sessions.write().unwrap();
+        let title_sessions = sessions.entry(authentication.title).or_default();
+
+        let mut rng = rand::rng();
+        let session_id = loop {
+            let session_id: MatchmakingSessionId = rng.random();
+            if session_id != 0 && !title_sessions.contains_key(&session_id) {
+                break session_id;
+            }
+        };
+
+        info!(
+            "User {} created matchmaking session {session_id:x}",
+            authentication.user_id
+        );
+        title_sessions.insert(
+            session_id,
+            HostedSession {
+                session: MatchmakingSession {
+                    session_id,
+                    host_user_id: authentication.user_id,
+                    info,
+                    players: Vec::new(),
+                },
+                host_session_id: session.id,
+                expires_at: Instant::now() + self.session_ttl,
+            },
+        );
+
+        Ok(session_id)
+    }
+
+    fn update_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        info: MatchmakingSessionInfo,
+    ) -> Result<(), MatchmakingServiceError> {
+        let authentication = session.authentication().unwrap();
+        self.expire_sessions(authentication.title);
+
+        let mut sessions = self.sessions.write().unwrap();
+        let existing_session = sessions
+            .get_mut(&authentication.title)
+            .and_then(|title_sessions| title_sessions.get_mut(&session_id))
+            .ok_or(MatchmakingServiceError::SessionNotFoundError)?;
+
+        if existing_session.session.host_user_id != authentication.user_id {
+            return Err(MatchmakingServiceError::PermissionDeniedError);
+        }
+
+        existing_session.session.info = info;
+        existing_session.refresh_player_count();
+        existing_session.expires_at = Instant::now() + self.session_ttl;
+
+        Ok(())
+    }
+
+    fn delete_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+    ) -> Result<(), MatchmakingServiceError> {
+        let authentication = session.authentication().unwrap();
+        self.expire_sessions(authentication.title);
+
+        let mut sessions = self.sessions.write().unwrap();
+        let title_sessions = sessions
+            .get_mut(&authentication.title)
+            .ok_or(MatchmakingServiceError::SessionNotFoundError)?;
+        let existing_session = title_sessions
+            .get(&session_id)
+            .ok_or(MatchmakingServiceError::SessionNotFoundError)?;
+
+        if existing_session.session.host_user_id != authentication.user_id {
+            return Err(MatchmakingServiceError::PermissionDeniedError);
+        }
+
+        info!(
+            "User {} deleted matchmaking session {session_id:x}",
+            authentication.user_id
+        );
+        title_sessions.remove(&session_id);
+        self.forget_invites(authentication.title, &[session_id]);
+
+        Ok(())
+    }
+
+    fn delete_sessions_of_host(&self, session: &BdSession) {
+        let Some(authentication) = session.authentication() else {
+            return;
+        };
+
+        let mut sessions = self.sessions.write().unwrap();
+        let Some(title_sessions) = sessions.get_mut(&authentication.title) else {
+            return;
+        };
+
+        let hosted_session_ids: Vec<MatchmakingSessionId> = title_sessions
+            .values()
+            .filter(|hosted| hosted.host_session_id == session.id)
+            .map(|hosted| hosted.session.session_id)
+            .collect();
+        if hosted_session_ids.is_empty() {
+            return;
+        }
+
+        for session_id in &hosted_session_ids {
+            info!("Deleting matchmaking session {session_id:x} since its host disconnected");
+            title_sessions.remove(session_id);
+        }
+        self.forget_invites(authentication.title, &hosted_session_ids);
+    }
+
+    fn notify_join(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError> {
+        self.update_players(session, session_id, &user_ids, |players| {
+            for user_id in &user_ids {
+                if !players.contains(user_id) {
+                    players.push(*user_id);
+                }
+            }
+        })
+    }
+
+    fn notify_leave(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError> {
+        self.update_players(session, session_id, &user_ids, |players| {
+            players.retain(|player| !user_ids.contains(player));
+        })
+    }
+
+    fn update_session_players(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError> {
+        // Replacing all players is never only about the authenticated user, so only the host may do it
+        self.update_players(session, session_id, &[], |players| {
+            *players = user_ids.clone();
+        })
+    }
+
+    fn find_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+    ) -> Result<MatchmakingSession, MatchmakingServiceError> {
+        let title = session.authentication().unwrap().title;
+        self.expire_sessions(title);
+
+        self.sessions
+            .read()
+            .unwrap()
+            .get(&title)
+            .and_then(|title_sessions| title_sessions.get(&session_id))
+            .map(|hosted| hosted.session.clone())
+            .ok_or(MatchmakingServiceError::SessionNotFoundError)
+    }
+
+    fn find_sessions_by_entity_ids(
+        &self,
+        session: &BdSession,
+        entity_ids: Vec<u64>,
+    ) -> Result<Vec<EntitySession>, MatchmakingServiceError> {
+        let title = session.authentication().unwrap().title;
+        self.expire_sessions(title);
+
+        let sessions = self.sessions.read().unwrap();
+        let Some(title_sessions) = sessions.get(&title) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(entity_ids
+            .into_iter()
+            .filter_map(|entity_id| {
+                title_sessions
+                    .values()
+                    .find(|candidate| {
+                        candidate.session.host_user_id == entity_id
+                            || candidate.session.players.contains(&entity_id)
+                    })
+                    .map(|hosted| EntitySession {
+                        entity_id,
+                        session: hosted.session.clone(),
+                    })
+            })
+            .collect())
+    }
+
+    fn find_sessions_from_ids(
+        &self,
+        session: &BdSession,
+        session_ids: Vec<MatchmakingSessionId>,
+    ) -> Result<Vec<MatchmakingSession>, MatchmakingServiceError> {
+        let title = session.authentication().unwrap().title;
+        self.expire_sessions(title);
+
+        let sessions = self.sessions.read().unwrap();
+        let Some(title_sessions) = sessions.get(&title) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(session_ids
+            .into_iter()
+            .filter_map(|session_id| {
+                title_sessions
+                    .get(&session_id)
+                    .map(|hosted| hosted.session.clone())
+            })
+            .collect())
+    }
+
+    fn invite_to_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+        attachment: Vec<u8>,
+    ) -> Result<Vec<u64>, MatchmakingServiceError> {
+        if attachment.len() > MAX_INVITE_ATTACHMENT_SIZE {
+            return Err(MatchmakingServiceError::AttachmentTooLargeError);
+        }
+
+        let authentication = session.authentication().unwrap();
+        self.expire_sessions(authentication.title);
+
+        let sessions = self.sessions.read().unwrap();
+        if !sessions
+            .get(&authentication.title)
+            .is_some_and(|title_sessions| title_sessions.contains_key(&session_id))
+        {
+            return Err(MatchmakingServiceError::SessionNotFoundError);
+        }
+
+        let mut invites = self.invites.write().unwrap();
+        let title_invites = invites.entry(authentication.title).or_default();
+
+        let mut invited_user_ids = Vec::new();
+        for user_id in user_ids {
+            let user_invites = title_invites.entry(user_id).or_default();
+            if user_invites
+                .iter()
+                .any(|invite| invite.session_id == session_id)
+            {
+                continue;
+            }
+
+            user_invites.push(MatchmakingInvite {
+                session_id,
+                sender_user_id: authentication.user_id,
+                sender_name: authentication.username.clone(),
+                attachment: attachment.clone(),
+            });
+            invited_user_ids.push(user_id);
+        }
+
+        if invited_user_ids.is_empty() {
+            return Err(MatchmakingServiceError::InviteExistsError);
+        }
+
+        Ok(invited_user_ids)
+    }
+
+    fn get_session_invites(
+        &self,
+        session: &BdSession,
+    ) -> Result<Vec<MatchmakingInvite>, MatchmakingServiceError> {
+        let authentication = session.authentication().unwrap();
+
+        Ok(self
+            .invites
+            .read()
+            .unwrap()
+            .get(&authentication.title)
+            .and_then(|title_invites| title_invites.get(&authentication.user_id))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn submit_performance(
+        &self,
+        session: &BdSession,
+        values: Vec<PerformanceValue>,
+    ) -> Result<(), MatchmakingServiceError> {
+        let title = session.authentication().unwrap().title;
+
+        let mut performance_values = self.performance_values.write().unwrap();
+        let title_values = performance_values.entry(title).or_default();
+        for value in values {
+            title_values.insert(value.entity_id, value.performance_value);
+        }
+
+        Ok(())
+    }
+
+    fn get_performance_values(
+        &self,
+        session: &BdSession,
+        entity_ids: Vec<u64>,
+    ) -> Result<Vec<PerformanceValue>, MatchmakingServiceError> {
+        let title = session.authentication().unwrap().title;
+
+        let performance_values = self.performance_values.read().unwrap();
+        let Some(title_values) = performance_values.get(&title) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(entity_ids
+            .into_iter()
+            .filter_map(|entity_id| {
+                title_values
+                    .get(&entity_id)
+                    .map(|performance_value| PerformanceValue {
+                        entity_id,
+                        performance_value: *performance_value,
+                    })
+            })
+            .collect())
+    }
+
+    fn find_sessions(
+        &self,
+        session: &BdSession,
+        criteria: MatchmakingSearchCriteria,
+        offset: usize,
+        max_results: usize,
+    ) -> Result<ResultSlice<MatchmakingSession>, MatchmakingServiceError> {
+        let title = session.authentication().unwrap().title;
+
+        let match_all = MatchmakingQuery::default();
+        let query = match self.queries.get(&title) {
+            Some(title_queries) => title_queries
+                .get(&criteria.query_id)
+                .ok_or(MatchmakingServiceError::InvalidQueryError)?,
+            None => &match_all,
+        };
+
+        self.expire_sessions(title);
+        let sessions = self.sessions.read().unwrap();
+        let mut matching_sessions: Vec<&MatchmakingSession> = sessions
+            .get(&title)
+            .map(|title_sessions| {
+                title_sessions
+                    .values()
+                    .map(|hosted| &hosted.session)
+                    .filter(|candidate| candidate.info.num_players < candidate.info.max_players)
+                    .filter(|candidate| query.matches(&candidate.info, &criteria.parameters))
+                    .collect()
+            })
+            .unwrap_or_default();
+        matching_sessions.sort_by_key(|candidate| candidate.session_id);
+
+        let total_count = matching_sessions.len();
+        let found_sessions = matching_sessions
+            .into_iter()
+            .skip(offset)
+            .take(max_results)
+            .cloned()
+            .collect();
+
+        Ok(ResultSlice::with_total_count(
+            found_sessions,
+            offset,
+            total_count,
+        ))
+    }
+}
+
+impl Default for InMemoryMatchmakingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InMemoryMatchmakingService {
+    pub fn new() -> InMemoryMatchmakingService {
+        InMemoryMatchmakingService {
+            sessions: RwLock::new(HashMap::new()),
+            session_ttl: DEFAULT_SESSION_TTL,
+            queries: HashMap::new(),
+            invites: RwLock::new(HashMap::new()),
+            performance_values: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Changes how long sessions are kept after their host last created or updated them.
+    pub fn with_session_ttl(mut self, session_ttl: Duration) -> Self {
+        self.session_ttl = session_ttl;
+        self
+    }
+
+    /// Changes the players of a session, refreshing its player count.
+    /// Users other than the host may only change the players if the affected users are only themselves.
+    fn update_players(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        affected_user_ids: &[u64],
+        update: impl FnOnce(&mut Vec<u64>),
+    ) -> Result<(), MatchmakingServiceError> {
+        let authentication = session.authentication().unwrap();
+        self.expire_sessions(authentication.title);
+
+        let mut sessions = self.sessions.write().unwrap();
+        let hosted = sessions
+            .get_mut(&authentication.title)
+            .and_then(|title_sessions| title_sessions.get_mut(&session_id))
+            .ok_or(MatchmakingServiceError::SessionNotFoundError)?;
+
+        let is_host = hosted.session.host_user_id == authentication.user_id;
+        let only_self = !affected_user_ids.is_empty()
+            && affected_user_ids
+                .iter()
+                .all(|user_id| *user_id == authentication.user_id);
+        if !is_host && !only_self {
+            return Err(MatchmakingServiceError::PermissionDeniedError);
+        }
+
+        update(&mut hosted.session.players);
+        hosted.refresh_player_count();
+        hosted.expires_at = Instant::now() + self.session_ttl;
+
+        Ok(())
+    }
+
+    /// Removes the sessions of the title that were not updated within the session ttl.
+    fn expire_sessions(&self, title: Title) {
+        let now = Instant::now();
+
+        let mut sessions = self.sessions.write().unwrap();
+        let Some(title_sessions) = sessions.get_mut(&title) else {
+            return;
+        };
+
+        let expired_session_ids: Vec<MatchmakingSessionId> = title_sessions
+            .values()
+            .filter(|hosted| hosted.expires_at <= now)
+            .map(|hosted| hosted.session.session_id)
+            .collect();
+        if expired_session_ids.is_empty() {
+            return;
+        }
+
+        for session_id in &expired_session_ids {
+            info!("Matchmaking session {session_id:x} expired");
+            title_sessions.remove(session_id);
+        }
+        self.forget_invites(title, &expired_session_ids);
+    }
+
+    /// Removes all invites to the sessions, which do not exist anymore.
+    fn forget_invites(&self, title: Title, session_ids: &[MatchmakingSessionId]) {
+        if let Some(title_invites) = self.invites.write().unwrap().get_mut(&title) {
+            title_invites.retain(|_, user_invites| {
+                user_invites.retain(|invite| !session_ids.contains(&invite.session_id));
+                !user_invites.is_empty()
+            });
+        }
+    }
+
+    /// Allows clients of the title to search for sessions with the query.
+    pub fn with_query(mut self, title: Title, query_id: u32, query: MatchmakingQuery) -> Self {
+        self.queries
+            .entry(title)
+            .or_default()
+            .insert(query_id, query);
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authentication::SessionAuthentication;
+    use crate::lobby::matchmaking::{MatchmakingAttribute, MatchmakingFilter};
+    use std::net::{TcpListener, TcpStream};
+
+    fn session(user_id: u64, title: Title) -> BdSession {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut session = BdSession::new(stream);
+        session.set_authentication(SessionAuthentication {
+            user_id,
+            username: format!("user{user_id}"),
+            session_key: [0; 24].into(),
+            title,
+            clock_skew: None,
+            anonymous: false,
+        });
+
+        session
+    }
+
+    fn info(game_type: u32) -> MatchmakingSessionInfo {
+        MatchmakingSessionInfo {
+            host_address: vec![1, 2, 3, 4],
+            game_type,
+            max_players: 18,
+            num_players: 1,
+            attributes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn only_the_host_can_change_a_session() {
+        let service = InMemoryMatchmakingService::new();
+        let host = session(1, Title::T6Pc);
+        let other = session(2, Title::T6Pc);
+
+        let session_id = service.create_session(&host, info(1)).unwrap();
+
+        assert!(matches!(
+            service.update_session(&other, session_id, info(2)),
+            Err(MatchmakingServiceError::PermissionDeniedError)
+        ));
+        service.update_session(&host, session_id, info(3)).unwrap();
+        assert_eq!(
+            service
+                .find_session(&other, session_id)
+                .unwrap()
+                .info
+                .game_type,
+            3
+        );
+
+        assert!(matches!(
+            service.delete_session(&other, session_id),
+            Err(MatchmakingServiceError::PermissionDeniedError)
+        ));
+        service.delete_session(&host, session_id).unwrap();
+        assert!(matches!(
+            service.find_session(&other, session_id),
+            Err(MatchmakingServiceError::SessionNotFoundError)
+        ));
+    }
+
+    #[test]
+    fn titles_with_queries_only_find_sessions_with_them() {
+        let service = InMemoryMatchmakingService::new().with_query(
+            Title::T6Pc,
+            1,
+            MatchmakingQuery::new(vec![MatchmakingFilter::GameType { parameter: 0 }]),
+        );
+        let host = session(1, Title::T6Pc);
+        service.create_session(&host, info(1)).unwrap();
+        let session_id = service.create_session(&host, info(2)).unwrap();
+
+        let criteria = |query_id| MatchmakingSearchCriteria {
+            query_id,
+            parameters: vec![MatchmakingAttribute::U32(2)],
+        };
+
+        let found_sessions = service.find_sessions(&host, criteria(1), 0, 10).unwrap();
+        assert_eq!(found_sessions.total_count(), 1);
+        assert_eq!(found_sessions.data()[0].session_id, session_id);
+
+        assert!(matches!(
+            service.find_sessions(&host, criteria(2), 0, 10),
+            Err(MatchmakingServiceError::InvalidQueryError)
+        ));
+    }
+
+    #[test]
+    fn invites_are_pending_until_the_session_is_deleted() {
+        let service = InMemoryMatchmakingService::new();
+        let host = session(1, Title::T6Pc);
+        let invited = session(2, Title::T6Pc);
+        let session_id = service.create_session(&host, info(1)).unwrap();
+
+        assert_eq!(
+            service
+                .invite_to_session(&host, session_id, vec![2], vec![7])
+                .unwrap(),
+            vec![2]
+        );
+        assert!(matches!(
+            service.invite_to_session(&host, session_id, vec![2], vec![7]),
+            Err(MatchmakingServiceError::InviteExistsError)
+        ));
+
+        let invites = service.get_session_invites(&invited).unwrap();
+        assert_eq!(invites.len(), 1);
+        assert_eq!(invites[0].sender_user_id, 1);
+        assert_eq!(invites[0].attachment, vec![7]);
+
+        service.delete_session(&host, session_id).unwrap();
+        assert!(service.get_session_invites(&invited).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sessions_can_be_found_by_their_host() {
+        let service = InMemoryMatchmakingService::new();
+        let session_id = service
+            .create_session(&session(1, Title::T6Pc), info(1))
+            .unwrap();
+        let friend = session(2, Title::T6Pc);
+
+        let entity_sessions = service
+            .find_sessions_by_entity_ids(&friend, vec![3, 1])
+            .unwrap();
+        assert_eq!(entity_sessions.len(), 1);
+        assert_eq!(entity_sessions[0].entity_id, 1);
+        assert_eq!(entity_sessions[0].session.session_id, session_id);
+
+        let found_sessions = service
+            .find_sessions_from_ids(&friend, vec![session_id, session_id.wrapping_add(1)])
+            .unwrap();
+        assert_eq!(found_sessions.len(), 1);
+    }
+
+    #[test]
+    fn sessions_end_with_their_host() {
+        let service = InMemoryMatchmakingService::new();
+        let mut host = session(1, Title::T6Pc);
+        host.id = 7;
+        let other = session(2, Title::T6Pc);
+        let session_id = service.create_session(&host, info(1)).unwrap();
+
+        service.delete_sessions_of_host(&other);
+        assert!(service.find_session(&other, session_id).is_ok());
+
+        service.delete_sessions_of_host(&host);
+        assert!(matches!(
+            service.find_session(&other, session_id),
+            Err(MatchmakingServiceError::SessionNotFoundError)
+        ));
+    }
+
+    #[test]
+    fn sessions_expire_without_updates() {
+        let service = InMemoryMatchmakingService::new().with_session_ttl(Duration::ZERO);
+        let host = session(1, Title::T6Pc);
+        let session_id = service.create_session(&host, info(1)).unwrap();
+
+        assert!(matches!(
+            service.find_session(&host, session_id),
+            Err(MatchmakingServiceError::SessionNotFoundError)
+        ));
+    }
+
+    #[test]
+    fn full_sessions_are_not_found() {
+        let service = InMemoryMatchmakingService::new();
+        let host = session(1, Title::T6Pc);
+        let player = session(2, Title::T6Pc);
+        let mut two_player_info = info(1);
+        two_player_info.max_players = 2;
+        let session_id = service.create_session(&host, two_player_info).unwrap();
+        let criteria = || MatchmakingSearchCriteria {
+            query_id: 1,
+            parameters: Vec::new(),
+        };
+
+        assert!(matches!(
+            service.notify_join(&player, session_id, vec![3]),
+            Err(MatchmakingServiceError::PermissionDeniedError)
+        ));
+        service.notify_join(&host, session_id, vec![1]).unwrap();
+        service.notify_join(&player, session_id, vec![2]).unwrap();
+        assert_eq!(
+            service.find_session(&host, session_id).unwrap().players,
+            vec![1, 2]
+        );
+        assert_eq!(
+            service
+                .find_sessions(&player, criteria(), 0, 10)
+                .unwrap()
+                .total_count(),
+            0
+        );
+
+        service.notify_leave(&player, session_id, vec![2]).unwrap();
+        let found_sessions = service.find_sessions(&player, criteria(), 0, 10).unwrap();
+        assert_eq!(found_sessions.data()[0].info.num_players, 1);
+    }
+
+    #[test]
+    fn sessions_are_separated_by_title() {
+        let service = InMemoryMatchmakingService::new();
+        let session_id = service
+            .create_session(&session(1, Title::T6Pc), info(1))
+            .unwrap();
+
+        assert!(matches!(
+            service.find_session(&session(2, Title::Iw5), session_id),
+            Err(MatchmakingServiceError::SessionNotFoundError)
+        ));
+    }
+}
@@ -0,0 +1,10 @@
+﻿mod handler;
+mod in_memory;
+mod query;
+mod result;
+mod service;
+
+pub use handler::{MatchmakingHandler, MatchmakingTaskId};
+pub use in_memory::InMemoryMatchmakingService;
+pub use query::*;
+pub use service::*;
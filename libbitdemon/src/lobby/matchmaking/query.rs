@@ -0,0 +1,168 @@
+﻿use crate::lobby::matchmaking::{MatchmakingAttribute, MatchmakingSessionInfo};
+
+/// The parameters clients search for sessions with.
+/// What the parameters mean is defined by the query with the specified id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchmakingSearchCriteria {
+    pub query_id: u32,
+    pub parameters: Vec<MatchmakingAttribute>,
+}
+
+/// A single condition a session has to fulfill to be found by a query.
+/// Attributes and parameters are referenced by their index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchmakingFilter {
+    /// The game type of the session equals the parameter.
+    GameType { parameter: usize },
+    /// The attribute equals the parameter.
+    Equals { attribute: usize, parameter: usize },
+    /// The attribute lies between the two parameters, both inclusive.
+    Range {
+        attribute: usize,
+        min_parameter: usize,
+        max_parameter: usize,
+    },
+}
+
+/// A server side defined search for sessions.
+/// Sessions match if they fulfill all filters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchmakingQuery {
+    pub filters: Vec<MatchmakingFilter>,
+}
+
+impl MatchmakingQuery {
+    pub fn new(filters: Vec<MatchmakingFilter>) -> MatchmakingQuery {
+        MatchmakingQuery { filters }
+    }
+
+    /// Whether the session fulfills all filters with the specified parameters.
+    /// Filters referencing attributes or parameters that do not exist are not fulfilled.
+    pub fn matches(
+        &self,
+        info: &MatchmakingSessionInfo,
+        parameters: &[MatchmakingAttribute],
+    ) -> bool {
+        self.filters.iter().all(|filter| match filter {
+            MatchmakingFilter::GameType { parameter } => parameters
+                .get(*parameter)
+                .and_then(MatchmakingAttribute::as_f64)
+                .is_some_and(|game_type| game_type == info.game_type as f64),
+            MatchmakingFilter::Equals {
+                attribute,
+                parameter,
+            } => match (info.attributes.get(*attribute), parameters.get(*parameter)) {
+                (Some(attribute), Some(parameter)) => {
+                    match (attribute.as_f64(), parameter.as_f64()) {
+                        (Some(attribute), Some(parameter)) => attribute == parameter,
+                        _ => attribute == parameter,
+                    }
+                }
+                _ => false,
+            },
+            MatchmakingFilter::Range {
+                attribute,
+                min_parameter,
+                max_parameter,
+            } => {
+                let value = info
+                    .attributes
+                    .get(*attribute)
+                    .and_then(MatchmakingAttribute::as_f64);
+                let min = parameters
+                    .get(*min_parameter)
+                    .and_then(MatchmakingAttribute::as_f64);
+                let max = parameters
+                    .get(*max_parameter)
+                    .and_then(MatchmakingAttribute::as_f64);
+
+                match (value, min, max) {
+                    (Some(value), Some(min), Some(max)) => min <= value && value <= max,
+                    _ => false,
+                }
+            }
+        })
+    }
+}
+
+impl MatchmakingAttribute {
+    /// The value of numeric attributes, so that they can be compared regardless of their type.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MatchmakingAttribute::I32(value) => Some(*value as f64),
+            MatchmakingAttribute::U32(value) => Some(*value as f64),
+            MatchmakingAttribute::I64(value) => Some(*value as f64),
+            MatchmakingAttribute::U64(value) => Some(*value as f64),
+            MatchmakingAttribute::F32(value) => Some(*value as f64),
+            MatchmakingAttribute::F64(value) => Some(*value),
+            MatchmakingAttribute::Bool(_)
+            | MatchmakingAttribute::Str(_)
+            | MatchmakingAttribute::Blob(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(attributes: Vec<MatchmakingAttribute>) -> MatchmakingSessionInfo {
+        MatchmakingSessionInfo {
+            host_address: Vec::new(),
+            game_type: 3,
+            max_players: 18,
+            num_players: 1,
+            attributes,
+        }
+    }
+
+    #[test]
+    fn numeric_filters_compare_across_types() {
+        let query = MatchmakingQuery::new(vec![
+            MatchmakingFilter::GameType { parameter: 0 },
+            MatchmakingFilter::Equals {
+                attribute: 0,
+                parameter: 1,
+            },
+            MatchmakingFilter::Range {
+                attribute: 1,
+                min_parameter: 2,
+                max_parameter: 3,
+            },
+        ]);
+        let parameters = |map: u32| {
+            vec![
+                MatchmakingAttribute::U32(3),
+                MatchmakingAttribute::I64(map as i64),
+                MatchmakingAttribute::F32(10.0),
+                MatchmakingAttribute::F32(20.0),
+            ]
+        };
+        let session = info(vec![
+            MatchmakingAttribute::U32(5),
+            MatchmakingAttribute::I32(15),
+        ]);
+
+        assert!(query.matches(&session, &parameters(5)));
+        assert!(!query.matches(&session, &parameters(6)));
+        assert!(!query.matches(&session, &parameters(5)[..3]));
+    }
+
+    #[test]
+    fn non_numeric_attributes_must_be_identical() {
+        let query = MatchmakingQuery::new(vec![MatchmakingFilter::Equals {
+            attribute: 0,
+            parameter: 0,
+        }]);
+        let session = info(vec![MatchmakingAttribute::Str("mp_raid".to_string())]);
+
+        assert!(query.matches(
+            &session,
+            &[MatchmakingAttribute::Str("mp_raid".to_string())]
+        ));
+        assert!(!query.matches(
+            &session,
+            &[MatchmakingAttribute::Str("mp_slums".to_string())]
+        ));
+    }
+}
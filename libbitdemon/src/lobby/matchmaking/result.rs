@@ -0,0 +1,127 @@
+﻿use crate::lobby::matchmaking::{
+    EntitySession, MatchmakingAttribute, MatchmakingInvite, MatchmakingSession,
+    MatchmakingSessionId, PerformanceValue,
+};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+/// Session ids are sent as a blob, like other security ids.
+pub(crate) fn write_session_id(
+    writer: &mut BdWriter,
+    session_id: MatchmakingSessionId,
+) -> Result<(), Box<dyn Error>> {
+    writer.write_blob(&session_id.to_le_bytes())
+}
+
+pub struct SessionIdResult {
+    pub session_id: MatchmakingSessionId,
+}
+
+impl BdSerialize for SessionIdResult {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        write_session_id(writer, self.session_id)
+    }
+}
+
+pub struct MatchmakingInfoResult {
+    pub session: MatchmakingSession,
+}
+
+impl BdSerialize for MatchmakingInfoResult {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        let info = &self.session.info;
+
+        write_session_id(writer, self.session.session_id)?;
+        writer.write_blob(&info.host_address)?;
+        writer.write_u32(info.game_type)?;
+        writer.write_u32(info.max_players)?;
+        writer.write_u32(info.num_players)?;
+
+        for attribute in &info.attributes {
+            match attribute {
+                MatchmakingAttribute::Bool(value) => writer.write_bool(*value)?,
+                MatchmakingAttribute::I32(value) => writer.write_i32(*value)?,
+                MatchmakingAttribute::U32(value) => writer.write_u32(*value)?,
+                MatchmakingAttribute::I64(value) => writer.write_i64(*value)?,
+                MatchmakingAttribute::U64(value) => writer.write_u64(*value)?,
+                MatchmakingAttribute::F32(value) => writer.write_f32(*value)?,
+                MatchmakingAttribute::F64(value) => writer.write_f64(*value)?,
+                MatchmakingAttribute::Str(value) => writer.write_str(value)?,
+                MatchmakingAttribute::Blob(value) => writer.write_blob(value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct PerformanceValueResult {
+    pub value: PerformanceValue,
+}
+
+impl BdSerialize for PerformanceValueResult {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.value.entity_id)?;
+        writer.write_i64(self.value.performance_value)?;
+
+        Ok(())
+    }
+}
+
+impl From<PerformanceValue> for PerformanceValueResult {
+    fn from(value: PerformanceValue) -> Self {
+        PerformanceValueResult { value }
+    }
+}
+
+/// An invite as it is both pushed to the invited user and listed in their pending invites.
+pub struct SessionInviteResult {
+    pub invite: MatchmakingInvite,
+}
+
+impl BdSerialize for SessionInviteResult {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.invite.sender_user_id)?;
+        writer.write_str(&self.invite.sender_name)?;
+        write_session_id(writer, self.invite.session_id)?;
+        writer.write_blob(&self.invite.attachment)?;
+
+        Ok(())
+    }
+}
+
+impl From<MatchmakingInvite> for SessionInviteResult {
+    fn from(invite: MatchmakingInvite) -> Self {
+        SessionInviteResult { invite }
+    }
+}
+
+impl From<MatchmakingSession> for MatchmakingInfoResult {
+    fn from(session: MatchmakingSession) -> Self {
+        MatchmakingInfoResult { session }
+    }
+}
+
+/// A session preceded by the entity that is in it,
+/// so that clients can tell which of the requested entities they can join.
+pub struct EntitySessionResult {
+    pub entity_id: u64,
+    pub session: MatchmakingInfoResult,
+}
+
+impl BdSerialize for EntitySessionResult {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.entity_id)?;
+        self.session.serialize(writer)
+    }
+}
+
+impl From<EntitySession> for EntitySessionResult {
+    fn from(entity_session: EntitySession) -> Self {
+        EntitySessionResult {
+            entity_id: entity_session.entity_id,
+            session: MatchmakingInfoResult::from(entity_session.session),
+        }
+    }
+}
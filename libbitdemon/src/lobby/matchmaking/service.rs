@@ -0,0 +1,204 @@
+﻿use crate::domain::result_slice::ResultSlice;
+use crate::lobby::matchmaking::MatchmakingSearchCriteria;
+use crate::networking::bd_session::BdSession;
+
+/// Identifies a matchmaking session, chosen by the server when the session is created.
+pub type MatchmakingSessionId = u64;
+
+/// A single title specific attribute of a matchmaking session,
+/// e.g. the map or playlist that is being played.
+/// Attributes keep the type they were sent with so that they can be sent back the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchmakingAttribute {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Str(String),
+    Blob(Vec<u8>),
+}
+
+/// The description of a matchmaking session as it is sent by its host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchmakingSessionInfo {
+    /// The serialized address that clients connect to the host with.
+    pub host_address: Vec<u8>,
+    pub game_type: u32,
+    pub max_players: u32,
+    pub num_players: u32,
+    /// The title specific attributes, in the order they were sent.
+    pub attributes: Vec<MatchmakingAttribute>,
+}
+
+/// A matchmaking session that is hosted by a user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchmakingSession {
+    pub session_id: MatchmakingSessionId,
+    pub host_user_id: u64,
+    pub info: MatchmakingSessionInfo,
+    /// The users that joined the session, as notified by the host or the users themselves.
+    pub players: Vec<u64>,
+}
+
+/// The session an entity, e.g. a friend of the user, is currently in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySession {
+    pub entity_id: u64,
+    pub session: MatchmakingSession,
+}
+
+/// An invitation of a user to join a matchmaking session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchmakingInvite {
+    pub session_id: MatchmakingSessionId,
+    pub sender_user_id: u64,
+    pub sender_name: String,
+    /// Title specific data sent along with the invite.
+    pub attachment: Vec<u8>,
+}
+
+/// A measure of how well a user performs, used to match users of similar skill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerformanceValue {
+    pub entity_id: u64,
+    pub performance_value: i64,
+}
+
+/// Errors that may occur when handling matchmaking calls.
+#[derive(Debug)]
+pub enum MatchmakingServiceError {
+    /// There is no session with the specified id for the title of the user.
+    SessionNotFoundError,
+    /// The session is hosted by another user.
+    PermissionDeniedError,
+    /// The title has no query with the specified id.
+    InvalidQueryError,
+    /// All users were already invited to the session.
+    InviteExistsError,
+    /// The data attached to an invite is too large to process.
+    AttachmentTooLargeError,
+}
+
+pub type ThreadSafeMatchmakingService = dyn MatchmakingService + Sync + Send;
+
+/// Implements domain logic concerning matchmaking sessions.
+pub trait MatchmakingService {
+    /// Creates a new session that is hosted by the authenticated user.
+    fn create_session(
+        &self,
+        session: &BdSession,
+        info: MatchmakingSessionInfo,
+    ) -> Result<MatchmakingSessionId, MatchmakingServiceError>;
+
+    /// Replaces the description of a session that is hosted by the authenticated user.
+    fn update_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        info: MatchmakingSessionInfo,
+    ) -> Result<(), MatchmakingServiceError>;
+
+    /// Deletes a session that is hosted by the authenticated user.
+    fn delete_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+    ) -> Result<(), MatchmakingServiceError>;
+
+    /// Deletes all sessions that were hosted through the connection of the session.
+    /// Meant to be called when the connection closes, since nobody can join the sessions anymore.
+    fn delete_sessions_of_host(&self, session: &BdSession);
+
+    /// Adds users to the players of a session.
+    /// Only the host may add other users than the authenticated user.
+    fn notify_join(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError>;
+
+    /// Removes users from the players of a session.
+    /// Only the host may remove other users than the authenticated user.
+    fn notify_leave(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError>;
+
+    /// Replaces the players of a session that is hosted by the authenticated user.
+    fn update_session_players(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+    ) -> Result<(), MatchmakingServiceError>;
+
+    /// Retrieves a single session of the title of the authenticated user.
+    fn find_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+    ) -> Result<MatchmakingSession, MatchmakingServiceError>;
+
+    /// Retrieves the sessions of the title of the authenticated user that the specified entities are in.
+    /// Entities that are not in any session are left out.
+    fn find_sessions_by_entity_ids(
+        &self,
+        session: &BdSession,
+        entity_ids: Vec<u64>,
+    ) -> Result<Vec<EntitySession>, MatchmakingServiceError>;
+
+    /// Retrieves multiple sessions of the title of the authenticated user.
+    /// Sessions that do not exist are left out.
+    fn find_sessions_from_ids(
+        &self,
+        session: &BdSession,
+        session_ids: Vec<MatchmakingSessionId>,
+    ) -> Result<Vec<MatchmakingSession>, MatchmakingServiceError>;
+
+    /// Invites users of the same title to a session in the name of the authenticated user.
+    /// Returns the users that were invited, leaving out those that were invited before.
+    fn invite_to_session(
+        &self,
+        session: &BdSession,
+        session_id: MatchmakingSessionId,
+        user_ids: Vec<u64>,
+        attachment: Vec<u8>,
+    ) -> Result<Vec<u64>, MatchmakingServiceError>;
+
+    /// Retrieves the pending invites of the authenticated user to sessions that still exist.
+    fn get_session_invites(
+        &self,
+        session: &BdSession,
+    ) -> Result<Vec<MatchmakingInvite>, MatchmakingServiceError>;
+
+    /// Replaces the performance values of the specified entities for the title of the authenticated user.
+    fn submit_performance(
+        &self,
+        session: &BdSession,
+        values: Vec<PerformanceValue>,
+    ) -> Result<(), MatchmakingServiceError>;
+
+    /// Retrieves the performance values of the specified entities for the title of the authenticated user.
+    /// Entities without a performance value are left out.
+    fn get_performance_values(
+        &self,
+        session: &BdSession,
+        entity_ids: Vec<u64>,
+    ) -> Result<Vec<PerformanceValue>, MatchmakingServiceError>;
+
+    /// Searches the sessions of the title of the authenticated user that match the criteria.
+    /// Sessions that are full are left out.
+    fn find_sessions(
+        &self,
+        session: &BdSession,
+        criteria: MatchmakingSearchCriteria,
+        offset: usize,
+        max_results: usize,
+    ) -> Result<ResultSlice<MatchmakingSession>, MatchmakingServiceError>;
+}
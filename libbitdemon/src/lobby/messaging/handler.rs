@@ -0,0 +1,215 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::lobby::messaging::result::{MessageIdResult, UserIdResult};
+use crate::lobby::messaging::{InstantMessage, MessagingServiceError, ThreadSafeMessagingService};
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::LobbyHandler;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use log::warn;
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+
+/// Handles both generations of the messaging service.
+/// The legacy service is served with the task ids of the second generation until its own are known,
+/// which lets users of either generation read each other's messages.
+pub struct Messaging2Handler {
+    pub messaging_service: Arc<ThreadSafeMessagingService>,
+}
+
+/// The task ids of the second generation messaging service.
+/// They are not verified against a client yet.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum Messaging2TaskId {
+    SendInstantMessage = 1,
+    GetInstantMessages = 2,
+    DeleteInstantMessages = 3,
+    BlockUsers = 4,
+    UnblockUsers = 5,
+    GetBlockedUsers = 6,
+}
+
+impl LobbyHandler for Messaging2Handler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = Messaging2TaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(BdErrorCode::NoError, task_id_value)
+                .to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            Messaging2TaskId::SendInstantMessage => {
+                self.send_instant_message(session, &mut message.reader)
+            }
+            Messaging2TaskId::GetInstantMessages => {
+                self.get_instant_messages(session, &mut message.reader)
+            }
+            Messaging2TaskId::DeleteInstantMessages => {
+                self.delete_instant_messages(session, &mut message.reader)
+            }
+            Messaging2TaskId::BlockUsers => self.block_users(session, &mut message.reader),
+            Messaging2TaskId::UnblockUsers => self.unblock_users(session, &mut message.reader),
+            Messaging2TaskId::GetBlockedUsers => {
+                self.get_blocked_users(session, &mut message.reader)
+            }
+        }
+    }
+}
+
+impl Messaging2Handler {
+    pub fn new(messaging_service: Arc<ThreadSafeMessagingService>) -> Messaging2Handler {
+        Messaging2Handler { messaging_service }
+    }
+
+    fn send_instant_message(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let recipient_id = reader.read_u64()?;
+        let payload = reader.read_blob()?;
+
+        let result = self
+            .messaging_service
+            .send_message(session, recipient_id, payload);
+
+        match result {
+            Ok(id) => Ok(TaskReply::with_results(
+                Messaging2TaskId::SendInstantMessage,
+                vec![Box::from(MessageIdResult { id })],
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                Messaging2TaskId::SendInstantMessage,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn get_instant_messages(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let item_offset = reader.read_u32()?;
+        let item_count = reader.read_u32()?;
+
+        let result =
+            self.messaging_service
+                .get_messages(session, item_offset as usize, item_count as usize);
+
+        self.answer_for_message_slice(Messaging2TaskId::GetInstantMessages, result)
+    }
+
+    fn delete_instant_messages(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let message_ids = reader.read_u64_array()?;
+
+        let result = self
+            .messaging_service
+            .delete_messages(session, message_ids.as_slice());
+
+        self.answer_for_no_return_value(Messaging2TaskId::DeleteInstantMessages, result)
+    }
+
+    fn block_users(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let user_ids = reader.read_u64_array()?;
+
+        let result = self
+            .messaging_service
+            .block_users(session, user_ids.as_slice());
+
+        self.answer_for_no_return_value(Messaging2TaskId::BlockUsers, result)
+    }
+
+    fn unblock_users(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let user_ids = reader.read_u64_array()?;
+
+        let result = self
+            .messaging_service
+            .unblock_users(session, user_ids.as_slice());
+
+        self.answer_for_no_return_value(Messaging2TaskId::UnblockUsers, result)
+    }
+
+    fn get_blocked_users(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self.messaging_service.get_blocked_users(session);
+
+        match result {
+            Ok(user_ids) => Ok(TaskReply::with_results(
+                Messaging2TaskId::GetBlockedUsers,
+                user_ids
+                    .into_iter()
+                    .map(|user_id| Box::from(UserIdResult { user_id }) as Box<dyn BdSerialize>)
+                    .collect(),
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                Messaging2TaskId::GetBlockedUsers,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn answer_for_message_slice(
+        &self,
+        task_id: Messaging2TaskId,
+        result: Result<ResultSlice<InstantMessage>, MessagingServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        TaskReply::builder(task_id)
+            .with_outcome(result)
+            .build()
+            .to_response()
+    }
+
+    fn answer_for_no_return_value(
+        &self,
+        task_id: Messaging2TaskId,
+        result: Result<(), MessagingServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        TaskReply::builder(task_id)
+            .with_outcome(result)
+            .build()
+            .to_response()
+    }
+}
+
+impl From<MessagingServiceError> for BdErrorCode {
+    fn from(value: MessagingServiceError) -> Self {
+        match value {
+            MessagingServiceError::PermissionDeniedError => BdErrorCode::PermissionDenied,
+            MessagingServiceError::InvalidRecipientError => BdErrorCode::InvalidUserId,
+            MessagingServiceError::MessageTooLargeError => BdErrorCode::GmsgPayloadTooBig,
+            MessagingServiceError::SelfBlockNotAllowedError => BdErrorCode::SelfBlockNotAllowed,
+        }
+    }
+}
@@ -0,0 +1,6 @@
+mod handler;
+mod result;
+mod service;
+
+pub use handler::{Messaging2Handler, Messaging2TaskId};
+pub use service::*;
@@ -0,0 +1,24 @@
+use crate::lobby::messaging::InstantMessage;
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+impl BdSerialize for InstantMessage {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.id)?;
+        writer.write_u64(self.sender_id)?;
+        writer.write_str(self.sender_name.as_str())?;
+        writer.write_u32(self.sent_at.clamp(0, u32::MAX as i64) as u32)?;
+        writer.write_blob(self.payload.as_slice())
+    }
+}
+
+#[derive(BdSerialize)]
+pub struct MessageIdResult {
+    pub id: u64,
+}
+
+#[derive(BdSerialize)]
+pub struct UserIdResult {
+    pub user_id: u64,
+}
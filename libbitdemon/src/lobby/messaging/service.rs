@@ -0,0 +1,101 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::networking::bd_session::BdSession;
+
+/// A message that was sent from one user to another.
+#[derive(Clone)]
+pub struct InstantMessage {
+    /// The id of the message.
+    /// Must be unique across all messages of the recipient.
+    pub id: u64,
+    /// The id of the user that sent the message.
+    pub sender_id: u64,
+    /// The name of the user that sent the message at the time of sending it.
+    pub sender_name: String,
+    /// The seconds timestamp of when the message was sent.
+    pub sent_at: i64,
+    /// The opaque data of the message that was set by the sender.
+    pub payload: Vec<u8>,
+}
+
+/// Errors that may occur when handling messaging calls.
+#[derive(Debug)]
+pub enum MessagingServiceError {
+    /// The authenticated user does not have permission to perform the requested operation.
+    PermissionDeniedError,
+    /// The specified recipient is not a valid user.
+    InvalidRecipientError,
+    /// The data of the message is too large to process.
+    MessageTooLargeError,
+    /// A user tried to block themselves.
+    SelfBlockNotAllowedError,
+}
+
+pub type ThreadSafeMessagingService = dyn MessagingService + Sync + Send;
+
+/// Implements domain logic concerning messages sent between users.
+///
+/// The messaging services of different generations only differ in their wire format.
+/// They operate on the same data and can therefore share a single implementation.
+pub trait MessagingService {
+    /// Sends a message from the current authenticated user to the specified recipient.
+    /// The service is expected to return the id of the newly created message.
+    ///
+    /// If the recipient blocked the sender, the message should be silently discarded.
+    ///
+    /// # Errors
+    ///
+    /// * [`InvalidRecipientError`][1]: The recipient is not a valid user.
+    /// * [`MessageTooLargeError`][2]: The payload of the message is larger than allowed.
+    ///
+    /// [1]: MessagingServiceError::InvalidRecipientError
+    /// [2]: MessagingServiceError::MessageTooLargeError
+    fn send_message(
+        &self,
+        session: &BdSession,
+        recipient_id: u64,
+        payload: Vec<u8>,
+    ) -> Result<u64, MessagingServiceError>;
+
+    /// Lists messages received by the current authenticated user, most recent first.
+    /// The result is returned as a [`ResultSlice`].
+    ///
+    /// The `item_offset` parameter describes the amount of items to skip and **NOT** an index of a page.
+    /// The amount of returned items should be equal or less than the value of the `item_count` parameter.
+    fn get_messages(
+        &self,
+        session: &BdSession,
+        item_offset: usize,
+        item_count: usize,
+    ) -> Result<ResultSlice<InstantMessage>, MessagingServiceError>;
+
+    /// Deletes the specified messages of the current authenticated user.
+    /// Ids of messages that do not exist or that the user did not receive are ignored.
+    fn delete_messages(
+        &self,
+        session: &BdSession,
+        message_ids: &[u64],
+    ) -> Result<(), MessagingServiceError>;
+
+    /// Blocks the specified users from sending messages to the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`SelfBlockNotAllowedError`][1]: The user tried to block themselves.
+    ///
+    /// [1]: MessagingServiceError::SelfBlockNotAllowedError
+    fn block_users(
+        &self,
+        session: &BdSession,
+        user_ids: &[u64],
+    ) -> Result<(), MessagingServiceError>;
+
+    /// Removes the specified users from the block list of the current authenticated user.
+    fn unblock_users(
+        &self,
+        session: &BdSession,
+        user_ids: &[u64],
+    ) -> Result<(), MessagingServiceError>;
+
+    /// Retrieves the ids of all users that the current authenticated user blocked.
+    fn get_blocked_users(&self, session: &BdSession) -> Result<Vec<u64>, MessagingServiceError>;
+}
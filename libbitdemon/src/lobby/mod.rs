@@ -1,32 +1,50 @@
+pub mod admission;
 pub mod anti_cheat;
 pub mod bandwidth;
 pub mod content_streaming;
 pub mod counter;
 pub mod dml;
 pub mod event_log;
+pub mod facebook;
 pub mod group;
 pub mod key_archive;
 pub mod league;
+pub mod link_code;
 mod lsg;
+pub mod mail;
+pub mod matchmaking;
+pub mod messaging;
 pub mod profile;
+mod redeemed_proofs;
 mod response;
+pub mod response_cache;
 pub mod rich_presence;
 pub mod storage;
+pub mod task_scheduler;
 pub mod title_utilities;
 pub mod twitch;
+pub mod twitter;
 pub mod vote_rank;
 pub mod youtube;
 
+pub use response::push_message::PushMessage;
+pub use response::task_reply::TaskReply;
+pub use response::{with_string_encoding, with_type_checking, BdMessageType};
+
 use crate::auth::key_store::ThreadSafeBackendPrivateKeyStorage;
+use crate::lobby::admission::ThreadSafeSessionAdmissionPolicy;
 use crate::lobby::lsg::LsgHandler;
-use crate::lobby::response::task_reply::TaskReply;
 use crate::lobby::LobbyServiceId::LobbyService;
+use crate::messaging::bd_data_type::{BdDataType, BufferDataType};
 use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::string_encoding::StringEncoding;
 use crate::messaging::BdErrorCode::{AccessDenied, ServiceNotAvailable};
+use crate::messaging::StreamMode::BitMode;
 use crate::networking::bd_session::BdSession;
 use crate::networking::bd_socket::BdMessageHandler;
-use log::{info, warn};
+use log::{debug, info, warn};
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 use snafu::Snafu;
@@ -211,13 +229,17 @@ pub trait LobbyHandler {
 }
 
 pub struct LobbyServer {
+    key_store: Arc<ThreadSafeBackendPrivateKeyStorage>,
     lobby_handlers: RwLock<HashMap<LobbyServiceId, Arc<ThreadSafeLobbyHandler>>>,
+    string_encoding: StringEncoding,
 }
 
 impl LobbyServer {
     pub fn new(key_store: Arc<ThreadSafeBackendPrivateKeyStorage>) -> Self {
         let lobby_server = LobbyServer {
+            key_store: key_store.clone(),
             lobby_handlers: RwLock::new(HashMap::new()),
+            string_encoding: StringEncoding::default(),
         };
 
         lobby_server.add_service(LobbyService, Arc::new(LsgHandler::new(key_store)));
@@ -225,6 +247,29 @@ impl LobbyServer {
         lobby_server
     }
 
+    /// Determines which sessions may authenticate.
+    /// By default, every session with a valid auth proof is admitted.
+    pub fn with_admission_policy(
+        self,
+        admission_policy: Arc<ThreadSafeSessionAdmissionPolicy>,
+    ) -> Self {
+        self.add_service(
+            LobbyService,
+            Arc::new(
+                LsgHandler::new(self.key_store.clone()).with_admission_policy(admission_policy),
+            ),
+        );
+
+        self
+    }
+
+    /// Determines how strings of messages and their replies are encoded.
+    /// By default, strings must be valid UTF-8 and messages with other strings fail.
+    pub fn with_string_encoding(mut self, string_encoding: StringEncoding) -> Self {
+        self.string_encoding = string_encoding;
+        self
+    }
+
     pub fn add_service(&self, service_id: LobbyServiceId, handler: Arc<ThreadSafeLobbyHandler>) {
         info!("Adding {service_id:?} lobby handler");
         self.lobby_handlers
@@ -232,6 +277,13 @@ impl LobbyServer {
             .unwrap()
             .insert(service_id, handler);
     }
+
+    pub fn has_service(&self, service_id: LobbyServiceId) -> bool {
+        self.lobby_handlers
+            .read()
+            .unwrap()
+            .contains_key(&service_id)
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -247,11 +299,52 @@ impl BdMessageHandler for LobbyServer {
         mut message: BdMessage,
     ) -> Result<(), Box<dyn Error>> {
         message.reader.set_type_checked(false);
+        message.reader.set_string_encoding(self.string_encoding);
         let service_id_input = message.reader.read_u8()?;
 
         let service_id = LobbyServiceId::from_u8(service_id_input)
             .ok_or_else(|| IllegalServiceIdSnafu { service_id_input }.build())?;
 
+        if session.type_checked().is_none() {
+            let detected = message
+                .reader
+                .remaining_data()
+                .ok()
+                .and_then(|payload| detect_type_checked(service_id, payload));
+            if let Some(type_checked) = detected {
+                debug!("Client uses type checked buffers: {type_checked}");
+                session.set_type_checked(type_checked);
+            }
+        }
+        let type_checked = session.type_checked().unwrap_or(true);
+
+        with_string_encoding(self.string_encoding, || {
+            with_type_checking(type_checked, || {
+                self.dispatch(session, service_id, type_checked, message)
+            })
+        })
+    }
+
+    /// Tells the client to retry later, as the error is a temporary one.
+    fn reject_throttled(&self, session: &mut BdSession) -> Result<(), Box<dyn Error>> {
+        let type_checked = session.type_checked().unwrap_or(true);
+
+        with_type_checking(type_checked, || {
+            TaskReply::with_only_error_code(ServiceNotAvailable, 0)
+                .to_response()?
+                .send(session)
+        })
+    }
+}
+
+impl LobbyServer {
+    fn dispatch(
+        &self,
+        session: &mut BdSession,
+        service_id: LobbyServiceId,
+        type_checked: bool,
+        mut message: BdMessage,
+    ) -> Result<(), Box<dyn Error>> {
         let handlers = self.lobby_handlers.read().unwrap();
         let maybe_handler = handlers.get(&service_id);
 
@@ -263,9 +356,13 @@ impl BdMessageHandler for LobbyServer {
                         .to_response()?
                         .send(session)?;
                 } else {
-                    message.reader.set_type_checked(true);
+                    message.reader.set_type_checked(type_checked);
                     let mut response = handler.handle_message(session, message)?;
-                    response.send(session)?;
+                    if response.is_no_reply() {
+                        debug!("Service {service_id:?} does not reply to this message");
+                    } else {
+                        response.send(session)?;
+                    }
                 }
 
                 Ok(())
@@ -281,3 +378,65 @@ impl BdMessageHandler for LobbyServer {
         }
     }
 }
+
+/// Guesses from the payload of a frame whether the client sends buffers with data types.
+///
+/// The lobby service tells with a flag at the start of its bit stream.
+/// For all other services, a type checked payload starts with the data type of the task id,
+/// which is followed by the data type of the first argument if there is one.
+/// Such a payload might as well be task `3` of a client without data types,
+/// so only the absence of data types can be told for sure.
+/// Returns `None` when it cannot be told.
+fn detect_type_checked(service_id: LobbyServiceId, payload: &[u8]) -> Option<bool> {
+    if service_id == LobbyService {
+        let mut reader = BdReader::new(payload.to_vec());
+        reader.set_mode(BitMode);
+        reader.read_type_checked_bit().ok()?;
+
+        return Some(reader.type_checked());
+    }
+
+    let task_id_type = BdDataType::UnsignedChar8Type as u8;
+    match payload {
+        [] => None,
+        [first, ..] if *first != task_id_type => Some(false),
+        [_] => Some(false),
+        [_, _, next, ..] if !is_argument_type(*next) => Some(false),
+        _ => None,
+    }
+}
+
+fn is_argument_type(value: u8) -> bool {
+    BufferDataType::from_value(value)
+        .is_ok_and(|data_type| data_type.primitive_type != BdDataType::NoType)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_payloads_without_data_types() {
+        assert_eq!(
+            detect_type_checked(LobbyServiceId::Mail, &[3, 2, 8, 0, 0, 0, 0]),
+            None
+        );
+        assert_eq!(
+            detect_type_checked(LobbyServiceId::Mail, &[2, 0, 0, 0, 0]),
+            Some(false)
+        );
+        assert_eq!(detect_type_checked(LobbyServiceId::Mail, &[3]), Some(false));
+        assert_eq!(
+            detect_type_checked(LobbyServiceId::Mail, &[3, 1, 0, 0]),
+            Some(false)
+        );
+        assert_eq!(
+            detect_type_checked(LobbyServiceId::LobbyService, &[1]),
+            Some(true)
+        );
+        assert_eq!(
+            detect_type_checked(LobbyServiceId::LobbyService, &[0]),
+            Some(false)
+        );
+    }
+}
@@ -18,7 +18,7 @@ pub struct ProfileHandler {
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum ProfileTaskId {
+pub enum ProfileTaskId {
     GetPublicInfos = 1,
     GetPrivateInfo = 2,
     SetPublicInfo = 3,
@@ -2,5 +2,5 @@
 mod result;
 mod service;
 
-pub use handler::ProfileHandler;
+pub use handler::{ProfileHandler, ProfileTaskId};
 pub use service::*;
@@ -0,0 +1,100 @@
+use crate::crypto::{key_digest, SessionKey};
+use crate::networking::bd_session::SessionId;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Mutex;
+
+/// The digest of the random session key of an auth proof, which is unique for every issued proof.
+/// The key itself is not kept, since redemptions are remembered for as long as the proof is valid.
+type ProofNonce = [u8; 20];
+
+#[derive(Default)]
+struct Proofs {
+    /// The session that redeemed each proof.
+    by_nonce: HashMap<ProofNonce, SessionId>,
+    /// The nonces ordered by their expiry, so expired proofs are found without visiting all of them.
+    expiry_order: BinaryHeap<Reverse<(i64, ProofNonce)>>,
+}
+
+impl Proofs {
+    fn remove_expired(&mut self, now: i64) {
+        while let Some(Reverse((time_expires, nonce))) = self.expiry_order.peek().copied() {
+            if time_expires >= now {
+                break;
+            }
+
+            self.expiry_order.pop();
+            self.by_nonce.remove(&nonce);
+        }
+    }
+}
+
+/// Remembers which connection redeemed an auth proof until the proof expires,
+/// so that a proof cannot be replayed from a different connection.
+/// Redemptions are only kept in memory and are forgotten when the server restarts.
+#[derive(Default)]
+pub struct RedeemedProofs {
+    proofs: Mutex<Proofs>,
+}
+
+impl RedeemedProofs {
+    pub fn new() -> RedeemedProofs {
+        RedeemedProofs {
+            proofs: Mutex::new(Proofs::default()),
+        }
+    }
+
+    /// Marks the proof as redeemed by the session.
+    /// Returns `false` if the proof was already redeemed by another session.
+    pub fn redeem(
+        &self,
+        session_key: &SessionKey,
+        time_expires: i64,
+        session_id: SessionId,
+        now: i64,
+    ) -> bool {
+        let nonce = key_digest(session_key);
+        let mut proofs = self.proofs.lock().unwrap();
+        proofs.remove_expired(now);
+
+        if let Some(redeemed_by) = proofs.by_nonce.get(&nonce) {
+            return *redeemed_by == session_id;
+        }
+
+        proofs.by_nonce.insert(nonce, session_id);
+        proofs.expiry_order.push(Reverse((time_expires, nonce)));
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proof_can_only_be_redeemed_by_one_session_until_it_expires() {
+        let redeemed_proofs = RedeemedProofs::new();
+
+        assert!(redeemed_proofs.redeem(&SessionKey::new([1; 24]), 100, 1, 50));
+        assert!(redeemed_proofs.redeem(&SessionKey::new([1; 24]), 100, 1, 60));
+        assert!(!redeemed_proofs.redeem(&SessionKey::new([1; 24]), 100, 2, 70));
+        assert!(redeemed_proofs.redeem(&SessionKey::new([2; 24]), 100, 2, 70));
+
+        assert!(redeemed_proofs.redeem(&SessionKey::new([1; 24]), 100, 2, 101));
+    }
+
+    #[test]
+    fn expired_proofs_are_forgotten() {
+        let redeemed_proofs = RedeemedProofs::new();
+
+        assert!(redeemed_proofs.redeem(&SessionKey::new([1; 24]), 100, 1, 50));
+        assert!(redeemed_proofs.redeem(&SessionKey::new([2; 24]), 200, 1, 50));
+        assert!(redeemed_proofs.redeem(&SessionKey::new([3; 24]), 300, 1, 150));
+
+        let proofs = redeemed_proofs.proofs.lock().unwrap();
+        assert_eq!(proofs.by_nonce.len(), 2);
+        assert_eq!(proofs.expiry_order.len(), 2);
+        assert!(!proofs.by_nonce.contains_key(&key_digest(&[1; 24])));
+    }
+}
@@ -1,7 +1,8 @@
-﻿use crate::lobby::response::BdMessageType;
-use crate::lobby::response::BdMessageType::LsgServiceConnectionId;
+﻿use crate::lobby::response::BdMessageType::{LsgServiceConnectionId, LsgServiceError};
+use crate::lobby::response::{string_encoding, type_checked, BdMessageType};
 use crate::messaging::bd_response::{BdResponse, ResponseCreator};
 use crate::messaging::bd_writer::BdWriter;
+use crate::messaging::BdErrorCode;
 use crate::messaging::StreamMode::ByteMode;
 use num_traits::ToPrimitive;
 use std::error::Error;
@@ -27,6 +28,28 @@ impl ConnectionIdResponse {
     }
 }
 
+/// Tells the client that the lobby service refused its connection.
+pub struct LsgErrorResponse {
+    error_code: BdErrorCode,
+    message: Option<String>,
+}
+
+impl LsgErrorResponse {
+    pub fn new(error_code: BdErrorCode) -> LsgErrorResponse {
+        LsgErrorResponse {
+            error_code,
+            message: None,
+        }
+    }
+
+    /// Appends a human-readable message after the error code.
+    /// Clients that do not know about it stop reading after the error code.
+    pub fn with_message(mut self, message: String) -> LsgErrorResponse {
+        self.message = Some(message);
+        self
+    }
+}
+
 impl<T: LsgServiceTaskReply> LsgResponseCreator for T {
     fn to_response(&self) -> Result<BdResponse, Box<dyn Error>> {
         let mut data = Vec::new();
@@ -34,6 +57,7 @@ impl<T: LsgServiceTaskReply> LsgResponseCreator for T {
             let mut writer = BdWriter::new(&mut data);
             writer.set_type_checked(false);
             writer.set_mode(ByteMode);
+            writer.set_string_encoding(string_encoding());
 
             writer.write_u8(BdMessageType::LsgServiceTaskReply.to_u8().unwrap())?;
             writer.write_u64(self.transaction_id())?;
@@ -52,10 +76,11 @@ impl ResponseCreator for ConnectionIdResponse {
             let mut writer = BdWriter::new(&mut data);
             writer.set_type_checked(false);
             writer.set_mode(ByteMode);
+            writer.set_string_encoding(string_encoding());
 
             writer.write_u8(LsgServiceConnectionId.to_u8().unwrap())?;
 
-            writer.set_type_checked(true);
+            writer.set_type_checked(type_checked());
 
             writer.write_u64(self.connection_id)?;
         }
@@ -63,3 +88,27 @@ impl ResponseCreator for ConnectionIdResponse {
         Ok(BdResponse::encrypted_if_available(data))
     }
 }
+
+impl ResponseCreator for LsgErrorResponse {
+    fn to_response(&self) -> Result<BdResponse, Box<dyn Error>> {
+        let mut data = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut data);
+            writer.set_type_checked(false);
+            writer.set_mode(ByteMode);
+            writer.set_string_encoding(string_encoding());
+
+            writer.write_u8(LsgServiceError.to_u8().unwrap())?;
+
+            writer.set_type_checked(type_checked());
+
+            writer.write_u32(self.error_code.to_u32().unwrap())?;
+
+            if let Some(message) = &self.message {
+                writer.write_str(message)?;
+            }
+        }
+
+        Ok(BdResponse::encrypted_if_available(data))
+    }
+}
@@ -1,8 +1,16 @@
-﻿use num_derive::{FromPrimitive, ToPrimitive};
+﻿use crate::messaging::string_encoding::StringEncoding;
+use num_derive::{FromPrimitive, ToPrimitive};
+use std::cell::Cell;
 
 pub mod lsg_reply;
+pub mod push_message;
 pub mod task_reply;
 
+thread_local! {
+    static TYPE_CHECKED: Cell<bool> = const { Cell::new(true) };
+    static STRING_ENCODING: Cell<StringEncoding> = const { Cell::new(StringEncoding::Utf8) };
+}
+
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
 pub enum BdMessageType {
@@ -12,3 +20,34 @@ pub enum BdMessageType {
     LsgServiceConnectionId = 4,
     LsgServiceTaskReply = 5,
 }
+
+/// Creates responses within the closure with or without data types,
+/// matching what the client of the session the responses are for expects.
+/// Responses are type checked by default.
+pub fn with_type_checking<R>(type_checked: bool, create: impl FnOnce() -> R) -> R {
+    let previous = TYPE_CHECKED.replace(type_checked);
+    let result = create();
+    TYPE_CHECKED.set(previous);
+
+    result
+}
+
+/// Whether responses created now are expected to contain data types.
+pub(crate) fn type_checked() -> bool {
+    TYPE_CHECKED.get()
+}
+
+/// Creates responses within the closure with strings in the given encoding.
+/// Responses use UTF-8 by default.
+pub fn with_string_encoding<R>(string_encoding: StringEncoding, create: impl FnOnce() -> R) -> R {
+    let previous = STRING_ENCODING.replace(string_encoding);
+    let result = create();
+    STRING_ENCODING.set(previous);
+
+    result
+}
+
+/// The encoding of strings of responses created now.
+pub(crate) fn string_encoding() -> StringEncoding {
+    STRING_ENCODING.get()
+}
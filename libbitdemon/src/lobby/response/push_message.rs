@@ -0,0 +1,172 @@
+use crate::lobby::response::{string_encoding, type_checked, with_type_checking, BdMessageType};
+use crate::lobby::LobbyServiceId;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::{BdDeserialize, BdSerialize};
+use crate::messaging::bd_writer::BdWriter;
+use crate::messaging::StreamMode;
+use crate::networking::bd_session::BdSession;
+use num_traits::ToPrimitive;
+use std::error::Error;
+
+/// A message that is sent to a client without it asking for it,
+/// like a notification about a new mail.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PushMessage {
+    /// The user that caused the message or `0` if it originates from the server.
+    pub source_user_id: u64,
+    /// The service that handles the message on the client.
+    pub service_id: LobbyServiceId,
+    /// The service specific content of the message.
+    pub payload: Vec<u8>,
+}
+
+impl PushMessage {
+    /// Creates a message originating from the server.
+    pub fn new(service_id: LobbyServiceId, payload: Vec<u8>) -> PushMessage {
+        PushMessage {
+            source_user_id: 0,
+            service_id,
+            payload,
+        }
+    }
+
+    /// Creates a message with the serialized content as payload.
+    pub fn with_content(
+        service_id: LobbyServiceId,
+        content: &dyn BdSerialize,
+    ) -> Result<PushMessage, Box<dyn Error>> {
+        let mut payload = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut payload);
+            writer.set_string_encoding(string_encoding());
+            content.serialize(&mut writer)?;
+        }
+
+        Ok(PushMessage::new(service_id, payload))
+    }
+
+    /// Marks the message as caused by the specified user.
+    pub fn with_source_user(mut self, source_user_id: u64) -> PushMessage {
+        self.source_user_id = source_user_id;
+        self
+    }
+
+    /// Sends the message to the client of the session,
+    /// with or without data types depending on what the client expects.
+    pub fn send_to(&self, session: &mut BdSession) -> Result<(), Box<dyn Error>> {
+        with_type_checking(session.type_checked().unwrap_or(true), || {
+            self.to_response()?.send(session)
+        })
+    }
+
+    /// Reads a message including its message type, as received by a client.
+    pub fn read_frame(data: Vec<u8>) -> Result<PushMessage, Box<dyn Error>> {
+        let mut reader = BdReader::new(data);
+        reader.set_type_checked(false);
+        reader.set_mode(StreamMode::ByteMode);
+
+        let message_type: BdMessageType = reader.read_enum_u8()?;
+        if message_type != BdMessageType::LobbyServicePushMessage {
+            return Err(format!("Expected a push message but got {message_type:?}").into());
+        }
+
+        reader.set_type_checked(true);
+
+        PushMessage::deserialize(&mut reader)
+    }
+
+    fn write_frame(&self, data: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
+        let mut writer = BdWriter::new(data);
+        writer.set_type_checked(false);
+        writer.set_mode(StreamMode::ByteMode);
+        writer.set_string_encoding(string_encoding());
+
+        writer.write_u8(BdMessageType::LobbyServicePushMessage.to_u8().unwrap())?;
+
+        writer.set_type_checked(type_checked());
+
+        self.serialize(&mut writer)
+    }
+}
+
+impl BdSerialize for PushMessage {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.source_user_id)?;
+        writer.write_u8(self.service_id.to_u8().unwrap())?;
+        writer.write_blob(&self.payload)
+    }
+}
+
+impl BdDeserialize for PushMessage {
+    fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        let source_user_id = reader.read_u64()?;
+        let service_id = reader.read_enum_u8()?;
+        let payload = reader.read_blob()?;
+
+        Ok(PushMessage {
+            source_user_id,
+            service_id,
+            payload,
+        })
+    }
+}
+
+impl ResponseCreator for PushMessage {
+    fn to_response(&self) -> Result<BdResponse, Box<dyn Error>> {
+        let mut data = Vec::new();
+        self.write_frame(&mut data)?;
+
+        Ok(BdResponse::encrypted_if_available(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter {
+        value: u32,
+    }
+
+    impl BdSerialize for Counter {
+        fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+            writer.write_u32(self.value)
+        }
+    }
+
+    #[test]
+    fn frame_can_be_read_back() {
+        let message = PushMessage::new(LobbyServiceId::Mail, vec![1, 2, 3]).with_source_user(42);
+
+        let mut data = Vec::new();
+        message.write_frame(&mut data).unwrap();
+
+        assert_eq!(data[0], BdMessageType::LobbyServicePushMessage as u8);
+        assert_eq!(PushMessage::read_frame(data).unwrap(), message);
+    }
+
+    #[test]
+    fn content_is_serialized_into_payload() {
+        let message =
+            PushMessage::with_content(LobbyServiceId::Counter, &Counter { value: 7 }).unwrap();
+
+        let mut reader = BdReader::new(message.payload.clone());
+        assert_eq!(message.source_user_id, 0);
+        assert_eq!(reader.read_u32().unwrap(), 7);
+    }
+
+    #[test]
+    fn other_message_types_are_rejected() {
+        let mut data = Vec::new();
+        PushMessage::new(LobbyServiceId::Mail, Vec::new())
+            .write_frame(&mut data)
+            .unwrap();
+        data[0] = BdMessageType::LobbyServiceTaskReply as u8;
+
+        assert!(PushMessage::read_frame(data).is_err());
+    }
+}
@@ -1,9 +1,10 @@
 ﻿use crate::domain::result_slice::ResultSlice;
-use crate::lobby::response::BdMessageType;
+use crate::lobby::response::{string_encoding, type_checked, BdMessageType};
 use crate::messaging::bd_response::{BdResponse, ResponseCreator};
 use crate::messaging::bd_serialization::BdSerialize;
 use crate::messaging::bd_writer::BdWriter;
 use crate::messaging::{BdErrorCode, StreamMode};
+use log::debug;
 use num_traits::ToPrimitive;
 use std::cell::RefCell;
 use std::error::Error;
@@ -14,6 +15,29 @@ pub struct TaskReply {
     operation_id: u8,
     results: Vec<Box<dyn BdSerialize>>,
     total_num_results: Option<u32>,
+    max_frame_size: Option<usize>,
+}
+
+/// Size of the message type, transaction id, error code, operation id and result counts.
+const TASK_REPLY_HEADER_SIZE: usize = 22;
+/// Rough size of a single result, used to avoid reallocating while writing large replies.
+const ESTIMATED_RESULT_SIZE: usize = 64;
+
+/// Builds a [`TaskReply`] step by step.
+/// Without results or an error code, the reply reports success without results.
+pub struct TaskReplyBuilder {
+    transaction_id: Option<u64>,
+    error_code: BdErrorCode,
+    operation_id: u8,
+    results: Vec<Box<dyn BdSerialize>>,
+    total_num_results: Option<u32>,
+    max_frame_size: Option<usize>,
+}
+
+/// Values that can be the results of a successful task.
+/// Lets handlers turn the outcome of a service call into a reply with [`TaskReplyBuilder::with_outcome`].
+pub trait IntoTaskResults {
+    fn add_to(self, builder: TaskReplyBuilder) -> TaskReplyBuilder;
 }
 
 thread_local! {
@@ -21,30 +45,34 @@ thread_local! {
 }
 
 impl TaskReply {
-    pub fn with_only_error_code<T: ToPrimitive>(
-        error_code: BdErrorCode,
-        operation_id: T,
-    ) -> TaskReply {
-        TaskReply {
-            transaction_id: Self::next_transaction_id(),
-            error_code,
+    pub fn builder<T: ToPrimitive>(operation_id: T) -> TaskReplyBuilder {
+        TaskReplyBuilder {
+            transaction_id: None,
+            error_code: BdErrorCode::NoError,
             operation_id: operation_id.to_u8().unwrap(),
             results: Vec::new(),
             total_num_results: None,
+            max_frame_size: None,
         }
     }
 
+    pub fn with_only_error_code<T: ToPrimitive>(
+        error_code: BdErrorCode,
+        operation_id: T,
+    ) -> TaskReply {
+        Self::builder(operation_id)
+            .with_error_code(error_code)
+            .build()
+    }
+
     pub fn with_results<T: ToPrimitive>(
         operation_id: T,
         results: Vec<Box<dyn BdSerialize>>,
     ) -> TaskReply {
-        TaskReply {
-            transaction_id: Self::next_transaction_id(),
-            error_code: BdErrorCode::NoError,
-            operation_id: operation_id.to_u8().unwrap(),
-            results,
-            total_num_results: None,
-        }
+        let total_count = results.len();
+        Self::builder(operation_id)
+            .with_boxed_results(results, total_count)
+            .build()
     }
 
     pub fn with_result_slice<T: ToPrimitive>(
@@ -52,18 +80,9 @@ impl TaskReply {
         results: ResultSlice<Box<dyn BdSerialize>>,
     ) -> TaskReply {
         let total_count = results.total_count();
-        let total_num_results = if total_count != results.data().len() {
-            Some(total_count as u32)
-        } else {
-            None
-        };
-        TaskReply {
-            transaction_id: Self::next_transaction_id(),
-            error_code: BdErrorCode::NoError,
-            operation_id: operation_id.to_u8().unwrap(),
-            results: results.into_data(),
-            total_num_results,
-        }
+        Self::builder(operation_id)
+            .with_boxed_results(results.into_data(), total_count)
+            .build()
     }
 
     fn next_transaction_id() -> u64 {
@@ -75,34 +94,330 @@ impl TaskReply {
     }
 }
 
+impl TaskReplyBuilder {
+    /// Uses a known transaction id instead of the next one of the counter.
+    pub fn with_transaction_id(mut self, transaction_id: u64) -> Self {
+        self.transaction_id = Some(transaction_id);
+        self
+    }
+
+    pub fn with_error_code(mut self, error_code: impl Into<BdErrorCode>) -> Self {
+        self.error_code = error_code.into();
+        self
+    }
+
+    pub fn with_result<R: BdSerialize + 'static>(mut self, result: R) -> Self {
+        self.results.push(Box::new(result));
+        self
+    }
+
+    pub fn with_results<R: BdSerialize + 'static>(
+        mut self,
+        results: impl IntoIterator<Item = R>,
+    ) -> Self {
+        self.results.extend(
+            results
+                .into_iter()
+                .map(|result| Box::new(result) as Box<dyn BdSerialize>),
+        );
+        self
+    }
+
+    /// Adds the results of a slice and reports its total count if there are more results.
+    pub fn with_result_slice<R: BdSerialize + 'static>(self, results: ResultSlice<R>) -> Self {
+        let total_count = results.total_count();
+        let results = results
+            .into_data()
+            .into_iter()
+            .map(|result| Box::new(result) as Box<dyn BdSerialize>)
+            .collect();
+
+        self.with_boxed_results(results, total_count)
+    }
+
+    fn with_boxed_results(
+        mut self,
+        mut results: Vec<Box<dyn BdSerialize>>,
+        total_count: usize,
+    ) -> Self {
+        if total_count != results.len() {
+            self.total_num_results = Some(total_count as u32);
+        }
+
+        self.results.append(&mut results);
+        self
+    }
+
+    /// Adds at most `max_results` results starting at `offset` and reports the amount of all results.
+    pub fn with_page<R: BdSerialize + 'static>(
+        self,
+        results: Vec<R>,
+        offset: usize,
+        max_results: usize,
+    ) -> Self {
+        let total_count = results.len();
+        let page = results.into_iter().skip(offset).take(max_results).collect();
+
+        self.with_result_slice(ResultSlice::with_total_count(page, offset, total_count))
+    }
+
+    /// Overrides the amount of results reported besides the ones that are part of the reply.
+    pub fn with_total_num_results(mut self, total_num_results: u32) -> Self {
+        self.total_num_results = Some(total_num_results);
+        self
+    }
+
+    /// Splits the reply into multiple frames when it is larger than this.
+    /// Task replies have no field for the offset of their first result,
+    /// so this only works with clients that append the results of all replies of a transaction.
+    /// Replies are sent in a single frame by default.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = Some(max_frame_size);
+        self
+    }
+
+    /// Adds the results of a successful outcome or the error code of a failed one.
+    pub fn with_outcome<R: IntoTaskResults, E: Into<BdErrorCode>>(
+        self,
+        outcome: Result<R, E>,
+    ) -> Self {
+        match outcome {
+            Ok(results) => results.add_to(self),
+            Err(error) => self.with_error_code(error),
+        }
+    }
+
+    pub fn build(self) -> TaskReply {
+        TaskReply {
+            transaction_id: self
+                .transaction_id
+                .unwrap_or_else(TaskReply::next_transaction_id),
+            error_code: self.error_code,
+            operation_id: self.operation_id,
+            results: self.results,
+            total_num_results: self.total_num_results,
+            max_frame_size: self.max_frame_size,
+        }
+    }
+}
+
+impl IntoTaskResults for () {
+    fn add_to(self, builder: TaskReplyBuilder) -> TaskReplyBuilder {
+        builder
+    }
+}
+
+impl<R: BdSerialize + 'static> IntoTaskResults for Vec<R> {
+    fn add_to(self, builder: TaskReplyBuilder) -> TaskReplyBuilder {
+        builder.with_results(self)
+    }
+}
+
+impl<R: BdSerialize + 'static> IntoTaskResults for ResultSlice<R> {
+    fn add_to(self, builder: TaskReplyBuilder) -> TaskReplyBuilder {
+        builder.with_result_slice(self)
+    }
+}
+
+impl TaskReply {
+    fn write_header(
+        &self,
+        writer: &mut BdWriter,
+        num_results: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        writer.set_type_checked(false);
+        writer.set_mode(StreamMode::ByteMode);
+        writer.set_string_encoding(string_encoding());
+
+        writer.write_u8(BdMessageType::LobbyServiceTaskReply.to_u8().unwrap())?;
+
+        writer.set_type_checked(type_checked());
+
+        writer.write_u64(self.transaction_id)?;
+        writer.write_u32(self.error_code.to_u32().unwrap())?;
+        writer.write_u8(self.operation_id)?;
+
+        // numResults
+        writer.write_u32(num_results as u32)?;
+
+        // totalNumResults
+        writer.write_u32(self.total_num_results.unwrap_or(self.results.len() as u32))
+    }
+
+    /// Splits the results into multiple frames that each stay below the maximum frame size,
+    /// unless a single result already exceeds it.
+    /// Every frame is a complete task reply of the same transaction.
+    /// It reports the results it contains and the total of all frames.
+    fn to_chunks(&self, max_frame_size: usize) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let mut chunks = Vec::new();
+        let mut chunk_results: Vec<Vec<u8>> = Vec::new();
+        let mut chunk_size = TASK_REPLY_HEADER_SIZE;
+        let mut offset = 0usize;
+
+        for result in &self.results {
+            let mut writer = BdWriter::with_capacity(ESTIMATED_RESULT_SIZE);
+            writer.set_type_checked(type_checked());
+            writer.set_string_encoding(string_encoding());
+            result.serialize(&mut writer)?;
+            let result = writer.finish()?;
+
+            if !chunk_results.is_empty() && chunk_size + result.len() > max_frame_size {
+                chunks.push(self.write_chunk(offset, &chunk_results)?);
+                offset += chunk_results.len();
+                chunk_results.clear();
+                chunk_size = TASK_REPLY_HEADER_SIZE;
+            }
+
+            chunk_size += result.len();
+            chunk_results.push(result);
+        }
+
+        if !chunk_results.is_empty() || chunks.is_empty() {
+            chunks.push(self.write_chunk(offset, &chunk_results)?);
+        }
+
+        Ok(chunks)
+    }
+
+    fn write_chunk(&self, offset: usize, results: &[Vec<u8>]) -> Result<Vec<u8>, Box<dyn Error>> {
+        debug!(
+            "Sending results {offset}..{} of transaction {} in a separate frame",
+            offset + results.len(),
+            self.transaction_id
+        );
+
+        let mut writer = BdWriter::with_capacity(
+            TASK_REPLY_HEADER_SIZE + results.iter().map(Vec::len).sum::<usize>(),
+        );
+        self.write_header(&mut writer, results.len())?;
+        for result in results {
+            writer.write_bytes(result)?;
+        }
+
+        writer.finish()
+    }
+}
+
 impl ResponseCreator for TaskReply {
     fn to_response(&self) -> Result<BdResponse, Box<dyn Error>> {
-        let mut data = Vec::new();
+        if let Some(max_frame_size) = self.max_frame_size {
+            let mut chunks = self.to_chunks(max_frame_size)?;
+            if chunks.len() == 1 {
+                return Ok(BdResponse::encrypted_if_available(chunks.remove(0)));
+            }
+
+            return Ok(BdResponse::chunked_encrypted_if_available(chunks));
+        }
+
+        let mut writer = BdWriter::with_capacity(
+            TASK_REPLY_HEADER_SIZE + self.results.len() * ESTIMATED_RESULT_SIZE,
+        );
+        self.write_header(&mut writer, self.results.len())?;
+
+        for result in &self.results {
+            result.serialize(&mut writer)?;
+        }
 
-        {
-            let mut writer = BdWriter::new(&mut data);
-            writer.set_type_checked(false);
-            writer.set_mode(StreamMode::ByteMode);
+        Ok(BdResponse::encrypted_if_available(writer.finish()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::bd_reader::BdReader;
+
+    #[derive(Debug)]
+    enum ServiceError {
+        NotFound,
+    }
+
+    impl From<ServiceError> for BdErrorCode {
+        fn from(_: ServiceError) -> Self {
+            BdErrorCode::NoFile
+        }
+    }
+
+    #[test]
+    fn builder_reports_outcome_of_service_calls() {
+        let reply = TaskReply::builder(3u8)
+            .with_transaction_id(42)
+            .with_outcome(Ok::<_, ServiceError>(vec![1u32, 2]))
+            .build();
+        assert_eq!(reply.transaction_id, 42);
+        assert_eq!(reply.error_code, BdErrorCode::NoError);
+        assert_eq!(reply.results.len(), 2);
+        assert_eq!(reply.total_num_results, None);
 
-            writer.write_u8(BdMessageType::LobbyServiceTaskReply.to_u8().unwrap())?;
+        let reply = TaskReply::builder(3u8)
+            .with_outcome(Err::<(), _>(ServiceError::NotFound))
+            .build();
+        assert_eq!(reply.error_code, BdErrorCode::NoFile);
+        assert!(reply.results.is_empty());
+    }
 
-            writer.set_type_checked(true);
+    #[test]
+    fn builder_reports_total_of_pages() {
+        let reply = TaskReply::builder(3u8)
+            .with_page(vec![1u64, 2, 3, 4, 5], 3, 10)
+            .build();
+        assert_eq!(reply.results.len(), 2);
+        assert_eq!(reply.total_num_results, Some(5));
 
-            writer.write_u64(self.transaction_id)?;
-            writer.write_u32(self.error_code.to_u32().unwrap())?;
-            writer.write_u8(self.operation_id)?;
+        let reply = TaskReply::builder(3u8)
+            .with_result_slice(ResultSlice::new(vec![1u64, 2], 0))
+            .build();
+        assert_eq!(reply.total_num_results, None);
+    }
 
-            // numResults
-            writer.write_u32(self.results.len() as u32)?;
+    #[test]
+    fn large_replies_are_split_into_frames() {
+        let reply = TaskReply::builder(3u8)
+            .with_results((0..10u64).map(|id| id.to_string().repeat(10)))
+            .with_max_frame_size(TASK_REPLY_HEADER_SIZE + 40)
+            .build();
 
-            // totalNumResults
-            writer.write_u32(self.total_num_results.unwrap_or(self.results.len() as u32))?;
+        let response = reply.to_response().unwrap();
+        let payloads = response.payloads();
+        assert_eq!(payloads.len(), 4);
 
-            for result in &self.results {
-                result.serialize(&mut writer)?;
+        let mut strings = Vec::new();
+        for payload in payloads {
+            let mut reader = BdReader::borrowed(payload);
+            assert_eq!(reader.read_u8().unwrap(), 1);
+            reader.set_type_checked(true);
+            assert_eq!(reader.read_u64().unwrap(), reply.transaction_id);
+            assert_eq!(reader.read_u32().unwrap(), 0);
+            assert_eq!(reader.read_u8().unwrap(), 3);
+            let num_results = reader.read_u32().unwrap();
+            assert_eq!(reader.read_u32().unwrap(), 10);
+            for _ in 0..num_results {
+                strings.push(reader.read_str().unwrap());
             }
         }
 
-        Ok(BdResponse::encrypted_if_available(data))
+        let expected: Vec<_> = (0..10u64).map(|id| id.to_string().repeat(10)).collect();
+        assert_eq!(strings, expected);
+    }
+
+    #[test]
+    fn replies_are_only_split_when_enabled() {
+        let results = || (0..10u64).map(|id| id.to_string().repeat(10));
+        let single = TaskReply::builder(3u8)
+            .with_transaction_id(1)
+            .with_results(results())
+            .build()
+            .to_response()
+            .unwrap();
+        let fitting = TaskReply::builder(3u8)
+            .with_transaction_id(1)
+            .with_results(results())
+            .with_max_frame_size(0x10000)
+            .build()
+            .to_response()
+            .unwrap();
+
+        assert_eq!(single.payloads().len(), 1);
+        assert_eq!(single.payloads(), fitting.payloads());
     }
 }
@@ -0,0 +1,193 @@
+use crate::domain::title::Title;
+use crate::lobby::{LobbyHandler, ThreadSafeLobbyHandler};
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::BdResponse;
+use crate::networking::bd_session::{BdSession, SessionId};
+use log::debug;
+use num_traits::ToPrimitive;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_ENTRIES: usize = 4096;
+
+/// Determines which clients share cached responses.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ResponseCacheScope {
+    /// Responses are only reused for the session that caused them.
+    Session,
+    /// Responses are reused for all sessions authenticated for the same title.
+    Title,
+}
+
+#[derive(Eq, PartialEq, Hash)]
+enum ScopeKey {
+    Session(SessionId),
+    Title(Title),
+}
+
+#[derive(Eq, PartialEq, Hash)]
+struct CacheKey {
+    scope: ScopeKey,
+    request: Vec<u8>,
+}
+
+struct CachedResponse {
+    response: BdResponse,
+    expires_at: Instant,
+}
+
+/// Counts how well a [`CachingLobbyHandler`] performs.
+#[derive(Default)]
+pub struct ResponseCacheMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResponseCacheMetrics {
+    /// The amount of requests that were answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The amount of cacheable requests that had to be passed to the wrapped handler.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// Wraps a lobby handler and answers repeated idempotent requests with a previous response.
+///
+/// Clients tend to poll the same tasks repeatedly while showing menus.
+/// Requests are considered identical when their task id and parameters match byte for byte.
+/// Only tasks that were explicitly marked as cacheable are cached,
+/// all other tasks are always passed to the wrapped handler.
+pub struct CachingLobbyHandler {
+    handler: Arc<ThreadSafeLobbyHandler>,
+    ttl: RwLock<Duration>,
+    scope: ResponseCacheScope,
+    max_entries: usize,
+    cacheable_tasks: HashSet<u8>,
+    entries: Mutex<HashMap<CacheKey, CachedResponse>>,
+    metrics: Arc<ResponseCacheMetrics>,
+}
+
+impl LobbyHandler for CachingLobbyHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let maybe_key = self.cache_key(session, &message);
+        if maybe_key.is_none() {
+            return self.handler.handle_message(session, message);
+        }
+        let key = maybe_key.unwrap();
+
+        let now = Instant::now();
+        if let Some(cached) = self.entries.lock().unwrap().get(&key) {
+            if cached.expires_at > now {
+                self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+                debug!("Answering request from response cache");
+                return Ok(cached.response.clone());
+            }
+        }
+
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        let response = self.handler.handle_message(session, message)?;
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries {
+            entries.retain(|_, cached| cached.expires_at > now);
+        }
+        if entries.len() < self.max_entries {
+            entries.insert(
+                key,
+                CachedResponse {
+                    response: response.clone(),
+                    expires_at: now + *self.ttl.read().unwrap(),
+                },
+            );
+        }
+
+        Ok(response)
+    }
+
+    fn requires_authentication(&self) -> bool {
+        self.handler.requires_authentication()
+    }
+}
+
+impl CachingLobbyHandler {
+    pub fn new(handler: Arc<ThreadSafeLobbyHandler>, ttl: Duration) -> CachingLobbyHandler {
+        CachingLobbyHandler {
+            handler,
+            ttl: RwLock::new(ttl),
+            scope: ResponseCacheScope::Session,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            cacheable_tasks: HashSet::new(),
+            entries: Mutex::new(HashMap::new()),
+            metrics: Arc::new(ResponseCacheMetrics::default()),
+        }
+    }
+
+    pub fn with_scope(mut self, scope: ResponseCacheScope) -> Self {
+        self.scope = scope;
+
+        self
+    }
+
+    /// Limits the amount of responses that are kept at once.
+    /// Responses are not cached anymore when the limit is reached until older ones expired.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+
+        self
+    }
+
+    /// Marks a task as idempotent, meaning its responses can be cached.
+    pub fn with_cacheable_task(mut self, task_id: impl ToPrimitive) -> Self {
+        self.cacheable_tasks.insert(task_id.to_u8().unwrap());
+
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<ResponseCacheMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Changes how long responses are reused.
+    /// Only affects responses that are cached afterward.
+    pub fn set_ttl(&self, ttl: Duration) {
+        *self.ttl.write().unwrap() = ttl;
+    }
+
+    /// Discards all cached responses.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn cache_key(&self, session: &BdSession, message: &BdMessage) -> Option<CacheKey> {
+        let request = message.reader.remaining_data().ok()?;
+
+        let mut task_id_reader = BdReader::new(request.to_vec());
+        task_id_reader.set_type_checked(message.reader.type_checked());
+        let task_id = task_id_reader.read_u8().ok()?;
+        if !self.cacheable_tasks.contains(&task_id) {
+            return None;
+        }
+
+        let scope = match self.scope {
+            ResponseCacheScope::Session => ScopeKey::Session(session.id),
+            ResponseCacheScope::Title => ScopeKey::Title(session.authentication()?.title),
+        };
+
+        Some(CacheKey {
+            scope,
+            request: request.to_vec(),
+        })
+    }
+}
@@ -19,7 +19,7 @@ pub struct RichPresenceHandler {
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum RichPresenceTaskId {
+pub enum RichPresenceTaskId {
     SetInfo = 1,
     GetInfo = 2,
 }
@@ -2,5 +2,5 @@
 mod result;
 mod service;
 
-pub use handler::RichPresenceHandler;
+pub use handler::{RichPresenceHandler, RichPresenceTaskId};
 pub use service::*;
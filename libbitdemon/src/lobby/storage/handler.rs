@@ -21,9 +21,12 @@ pub struct StorageHandler {
     publisher_storage_service: Arc<ThreadSafePublisherStorageService>,
 }
 
+/// The maximum amount of bytes returned for a single chunked publisher file request.
+pub const MAX_PUBLISHER_FILE_CHUNK_SIZE: u32 = 0x100000;
+
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum StorageTaskId {
+pub enum StorageTaskId {
     // UploadFileAndDeleteMail
     // GetFilesByID
     UploadFile = 1,
@@ -247,6 +250,12 @@ impl StorageHandler {
         self.answer_for_file_info_slice(StorageTaskId::ListAllPublisherFiles, result)
     }
 
+    /// Retrieves a publisher file.
+    ///
+    /// The whole file is returned unless the client appends an offset and a length to the filename.
+    /// In that case only the requested range is returned,
+    /// limited to [`MAX_PUBLISHER_FILE_CHUNK_SIZE`] bytes per request.
+    /// Whole files are only limited by the size the service is willing to send at once.
     fn get_publisher_file(
         &self,
         session: &mut BdSession,
@@ -254,9 +263,16 @@ impl StorageHandler {
     ) -> Result<BdResponse, Box<dyn Error>> {
         let filename = reader.read_str()?;
 
-        let result = self
-            .publisher_storage_service
-            .get_publisher_file_data(session, filename.clone());
+        let result = if reader.next_is_u32().unwrap_or(false) {
+            let offset = reader.read_u32()?;
+            let length = reader.read_u32()?.min(MAX_PUBLISHER_FILE_CHUNK_SIZE);
+
+            self.publisher_storage_service
+                .get_publisher_file_data_range(session, filename, offset as u64, length as usize)
+        } else {
+            self.publisher_storage_service
+                .get_publisher_file_data(session, filename)
+        };
 
         self.answer_for_file_data(StorageTaskId::GetPublisherFile, result)
     }
@@ -284,14 +300,10 @@ impl StorageHandler {
         task_id: StorageTaskId,
         result: Result<Vec<u8>, StorageServiceError>,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        match result {
-            Ok(data) => Ok(TaskReply::with_results(
-                task_id,
-                vec![Box::from(FileDataResult { data })],
-            )
-            .to_response()?),
-            Err(error) => Ok(TaskReply::with_only_error_code(error.into(), task_id).to_response()?),
-        }
+        TaskReply::builder(task_id)
+            .with_outcome(result.map(|data| vec![FileDataResult { data }]))
+            .build()
+            .to_response()
     }
 
     fn answer_for_file_info_slice(
@@ -299,12 +311,10 @@ impl StorageHandler {
         task_id: StorageTaskId,
         result: Result<ResultSlice<StorageFileInfo>, StorageServiceError>,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        match result {
-            Ok(info) => {
-                Ok(TaskReply::with_result_slice(task_id, info.serializable()).to_response()?)
-            }
-            Err(error) => Ok(TaskReply::with_only_error_code(error.into(), task_id).to_response()?),
-        }
+        TaskReply::builder(task_id)
+            .with_outcome(result)
+            .build()
+            .to_response()
     }
 
     fn answer_for_no_return_value(
@@ -312,12 +322,10 @@ impl StorageHandler {
         task_id: StorageTaskId,
         result: Result<(), StorageServiceError>,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        match result {
-            Ok(_) => {
-                Ok(TaskReply::with_only_error_code(BdErrorCode::NoError, task_id).to_response()?)
-            }
-            Err(error) => Ok(TaskReply::with_only_error_code(error.into(), task_id).to_response()?),
-        }
+        TaskReply::builder(task_id)
+            .with_outcome(result)
+            .build()
+            .to_response()
     }
 }
 
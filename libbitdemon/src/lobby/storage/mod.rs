@@ -2,5 +2,5 @@
 mod result;
 mod service;
 
-pub use handler::StorageHandler;
+pub use handler::{StorageHandler, StorageTaskId, MAX_PUBLISHER_FILE_CHUNK_SIZE};
 pub use service::*;
@@ -1,5 +1,4 @@
 ﻿use crate::lobby::storage::service::{FileVisibility, StorageFileInfo};
-use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_serialization::{BdDeserialize, BdSerialize};
 use crate::messaging::bd_writer::BdWriter;
 use std::error::Error;
@@ -17,23 +16,8 @@ impl BdSerialize for StorageFileInfo {
     }
 }
 
+#[derive(BdSerialize, BdDeserialize)]
 pub struct FileDataResult {
+    #[bd(blob)]
     pub data: Vec<u8>,
 }
-
-impl BdDeserialize for FileDataResult {
-    fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>>
-    where
-        Self: Sized,
-    {
-        let data = reader.read_blob()?;
-
-        Ok(FileDataResult { data })
-    }
-}
-
-impl BdSerialize for FileDataResult {
-    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
-        writer.write_blob(self.data.as_slice())
-    }
-}
@@ -238,15 +238,50 @@ pub trait PublisherStorageService {
     ///
     /// * [`PermissionDeniedError`][1]: The requested operation is not allowed for the current user.
     /// * [`StorageFileNotFoundError`][2]: The requested file could not be found.
+    /// * [`StorageFileTooLargeError`][3]: The file is too large to be sent at once.
     ///
     /// [1]: StorageServiceError::PermissionDeniedError
     /// [2]: StorageServiceError::StorageFileNotFoundError
+    /// [3]: StorageServiceError::StorageFileTooLargeError
     fn get_publisher_file_data(
         &self,
         session: &BdSession,
         filename: String,
     ) -> Result<Vec<u8>, StorageServiceError>;
 
+    /// Gets a part of the data of a specified publisher file.
+    /// Clients that cannot use HTTP use this to retrieve large publisher files in multiple requests.
+    ///
+    /// The `offset` parameter describes the amount of bytes to skip.
+    /// The amount of returned bytes should be equal or less than the value of the `length` parameter.
+    /// Returning fewer bytes than requested signals that the end of the file was reached.
+    ///
+    /// The default implementation retrieves the whole file and discards any data outside the requested range.
+    ///
+    /// # Errors
+    ///
+    /// * [`PermissionDeniedError`][1]: The requested operation is not allowed for the current user.
+    /// * [`StorageFileNotFoundError`][2]: The requested file could not be found.
+    ///
+    /// [1]: StorageServiceError::PermissionDeniedError
+    /// [2]: StorageServiceError::StorageFileNotFoundError
+    fn get_publisher_file_data_range(
+        &self,
+        session: &BdSession,
+        filename: String,
+        offset: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, StorageServiceError> {
+        let data = self.get_publisher_file_data(session, filename)?;
+
+        let start = usize::try_from(offset)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let end = start.saturating_add(length).min(data.len());
+
+        Ok(data[start..end].to_vec())
+    }
+
     /// Lists details of the publisher files.
     /// The result is returned as a [`ResultSlice`].
     ///
@@ -0,0 +1,89 @@
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::response::with_type_checking;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use log::{debug, error, warn};
+use num_traits::ToPrimitive;
+use std::error::Error;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs long tasks of lobby handlers on a pool of background threads
+/// so that they do not stall the message loop of their session.
+///
+/// Tasks reply once they completed.
+/// This works for lobby tasks because clients match replies by their task and do not
+/// expect them in the order they sent the requests.
+pub struct TaskScheduler {
+    sender: SyncSender<Job>,
+}
+
+impl TaskScheduler {
+    /// Creates a scheduler with the specified amount of worker threads.
+    /// At most `queue_len` tasks can wait for a worker before new tasks are rejected.
+    pub fn new(workers: usize, queue_len: usize) -> TaskScheduler {
+        let (sender, receiver) = sync_channel::<Job>(queue_len);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            thread::spawn(move || Self::work(receiver));
+        }
+
+        TaskScheduler { sender }
+    }
+
+    /// Runs the task in the background and sends the response it produces to the session.
+    ///
+    /// When all workers are busy and the queue is full,
+    /// the client is asked to retry later with [`BdErrorCode::TooManyTasks`] instead.
+    /// The returned response must be sent by the caller either way.
+    pub fn defer<T, F>(
+        &self,
+        session: &BdSession,
+        task_id: T,
+        task: F,
+    ) -> Result<BdResponse, Box<dyn Error>>
+    where
+        T: ToPrimitive,
+        F: FnOnce(&mut BdSession) -> Result<BdResponse, Box<dyn Error>> + Send + 'static,
+    {
+        let mut detached_session = session.detach()?;
+        let type_checked = session.type_checked().unwrap_or(true);
+        let job: Job = Box::new(move || {
+            let result = with_type_checking(type_checked, || task(&mut detached_session))
+                .and_then(|mut response| response.send(&mut detached_session));
+            if let Err(e) = result {
+                error!("Deferred task failed: {e}");
+            }
+        });
+
+        match self.sender.try_send(job) {
+            Ok(()) => {
+                debug!("Deferred task to background worker");
+                Ok(BdResponse::no_reply())
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!("Too many deferred tasks, asking client to retry");
+                TaskReply::with_only_error_code(BdErrorCode::TooManyTasks, task_id).to_response()
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                panic!("Task scheduler workers are not running")
+            }
+        }
+    }
+
+    fn work(receiver: Arc<Mutex<Receiver<Job>>>) {
+        loop {
+            let job = receiver.lock().unwrap().recv();
+            match job {
+                Ok(job) => job(),
+                Err(_) => return,
+            }
+        }
+    }
+}
@@ -1,20 +1,28 @@
-﻿use crate::lobby::response::task_reply::TaskReply;
-use crate::lobby::title_utilities::result::TimestampResult;
+﻿use crate::lobby::event_log::EventPayload;
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::title_utilities::result::{TimestampResult, VerifyStringResult};
+use crate::lobby::title_utilities::ThreadSafeTitleUtilitiesService;
 use crate::lobby::LobbyHandler;
 use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::bd_serialization::BdSerialize;
 use crate::messaging::BdErrorCode::NoError;
 use crate::networking::bd_session::BdSession;
-use log::warn;
+use log::{debug, info, warn};
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 use std::error::Error;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
 
-pub struct TitleUtilitiesHandler {}
+pub struct TitleUtilitiesHandler {
+    pub title_utilities_service: Arc<ThreadSafeTitleUtilitiesService>,
+}
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum TitleUtilitiesTaskId {
+pub enum TitleUtilitiesTaskId {
     // SendOwnedContent
     // GetMAC
     // GetUserIDs
@@ -32,7 +40,7 @@ enum TitleUtilitiesTaskId {
 impl LobbyHandler for TitleUtilitiesHandler {
     fn handle_message(
         &self,
-        _session: &mut BdSession,
+        session: &mut BdSession,
         mut message: BdMessage,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let task_id_value = message.reader.read_u8()?;
@@ -44,38 +52,150 @@ impl LobbyHandler for TitleUtilitiesHandler {
         let task_id = maybe_task_id.unwrap();
 
         match task_id {
-            TitleUtilitiesTaskId::GetServerTime => Self::get_server_time(),
-            TitleUtilitiesTaskId::VerifyString
-            | TitleUtilitiesTaskId::GetTitleStats
-            | TitleUtilitiesTaskId::RecordEvent
-            | TitleUtilitiesTaskId::RecordIp
-            | TitleUtilitiesTaskId::RecordEventBin
-            | TitleUtilitiesTaskId::AreUsersOnline
-            | TitleUtilitiesTaskId::GetUserNames => {
-                warn!("Client called unimplemented task {task_id:?}");
-                Ok(TaskReply::with_only_error_code(NoError, task_id).to_response()?)
+            TitleUtilitiesTaskId::VerifyString => self.verify_string(session, &mut message.reader),
+            TitleUtilitiesTaskId::GetTitleStats => self.get_title_stats(session),
+            TitleUtilitiesTaskId::RecordEvent => self.record_event(session, &mut message.reader),
+            TitleUtilitiesTaskId::RecordIp => self.record_ip(session, &mut message.reader),
+            TitleUtilitiesTaskId::RecordEventBin => {
+                self.record_event_bin(session, &mut message.reader)
+            }
+            TitleUtilitiesTaskId::GetServerTime => self.get_server_time(session),
+            TitleUtilitiesTaskId::AreUsersOnline => {
+                self.are_users_online(session, &mut message.reader)
             }
+            TitleUtilitiesTaskId::GetUserNames => self.get_user_names(session, &mut message.reader),
         }
     }
 }
 
-impl Default for TitleUtilitiesHandler {
-    fn default() -> Self {
-        Self::new()
+impl TitleUtilitiesHandler {
+    pub fn new(
+        title_utilities_service: Arc<ThreadSafeTitleUtilitiesService>,
+    ) -> TitleUtilitiesHandler {
+        TitleUtilitiesHandler {
+            title_utilities_service,
+        }
     }
-}
 
-impl TitleUtilitiesHandler {
-    pub fn new() -> TitleUtilitiesHandler {
-        TitleUtilitiesHandler {}
+    fn verify_string(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let value = reader.read_str()?;
+
+        let valid = self.title_utilities_service.verify_string(session, value);
+
+        TaskReply::with_results(
+            TitleUtilitiesTaskId::VerifyString,
+            vec![Box::new(VerifyStringResult { valid })],
+        )
+        .to_response()
+    }
+
+    fn get_title_stats(&self, session: &mut BdSession) -> Result<BdResponse, Box<dyn Error>> {
+        let title_stats = self.title_utilities_service.get_title_stats(session);
+
+        TaskReply::with_results(
+            TitleUtilitiesTaskId::GetTitleStats,
+            vec![Box::new(title_stats)],
+        )
+        .to_response()
     }
 
-    fn get_server_time() -> Result<BdResponse, Box<dyn Error>> {
-        let now = chrono::Utc::now().timestamp();
+    fn record_event(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let event = reader.read_str()?;
+        debug!("Recording legacy event with len={}", event.len());
+
+        self.title_utilities_service
+            .record_event(session, EventPayload::Text(event));
+
+        TaskReply::with_only_error_code(NoError, TitleUtilitiesTaskId::RecordEvent).to_response()
+    }
+
+    fn record_event_bin(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let event = reader.read_blob()?;
+        debug!("Recording legacy binary event with len={}", event.len());
+
+        self.title_utilities_service
+            .record_event(session, EventPayload::Binary(event));
+
+        TaskReply::with_only_error_code(NoError, TitleUtilitiesTaskId::RecordEventBin).to_response()
+    }
+
+    fn record_ip(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let ip = Ipv4Addr::from(reader.read_u32()?);
+        info!("Recording IP: {ip}");
+
+        self.title_utilities_service.record_ip(session, ip);
+
+        TaskReply::with_only_error_code(NoError, TitleUtilitiesTaskId::RecordIp).to_response()
+    }
+
+    fn get_server_time(&self, session: &mut BdSession) -> Result<BdResponse, Box<dyn Error>> {
+        // The client only receives seconds, so round instead of truncating the milliseconds
+        let now_millis = self
+            .title_utilities_service
+            .get_server_time(session)
+            .timestamp_millis();
         let result = Box::from(TimestampResult {
-            value: (now % (u32::MAX as i64)) as u32,
+            value: ((now_millis + 500) / 1000) as u32,
         });
 
         TaskReply::with_results(TitleUtilitiesTaskId::GetServerTime, vec![result]).to_response()
     }
+
+    fn are_users_online(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let user_ids = reader.read_u64_array()?;
+
+        let statuses = self
+            .title_utilities_service
+            .are_users_online(session, user_ids);
+
+        TaskReply::with_results(
+            TitleUtilitiesTaskId::AreUsersOnline,
+            statuses
+                .into_iter()
+                .map(|status| Box::new(status) as Box<dyn BdSerialize>)
+                .collect(),
+        )
+        .to_response()
+    }
+
+    fn get_user_names(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let user_ids = reader.read_u64_array()?;
+
+        let user_names = self
+            .title_utilities_service
+            .get_user_names(session, user_ids);
+
+        TaskReply::with_results(
+            TitleUtilitiesTaskId::GetUserNames,
+            user_names
+                .into_iter()
+                .map(|user_name| Box::new(user_name) as Box<dyn BdSerialize>)
+                .collect(),
+        )
+        .to_response()
+    }
 }
@@ -1,4 +1,6 @@
 ﻿mod handler;
 mod result;
+mod service;
 
-pub use handler::TitleUtilitiesHandler;
+pub use handler::{TitleUtilitiesHandler, TitleUtilitiesTaskId};
+pub use service::*;
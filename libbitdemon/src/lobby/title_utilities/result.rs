@@ -1,13 +1,38 @@
-﻿use crate::messaging::bd_serialization::BdSerialize;
+﻿use crate::lobby::title_utilities::{TitleStats, UserName, UserOnlineStatus};
+use crate::messaging::bd_serialization::BdSerialize;
 use crate::messaging::bd_writer::BdWriter;
 use std::error::Error;
 
+#[derive(BdSerialize)]
 pub struct TimestampResult {
     pub value: u32,
 }
 
-impl BdSerialize for TimestampResult {
+#[derive(BdSerialize)]
+pub struct VerifyStringResult {
+    pub valid: bool,
+}
+
+impl BdSerialize for TitleStats {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u32(self.online_users)
+    }
+}
+
+impl BdSerialize for UserOnlineStatus {
     fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
-        writer.write_u32(self.value)
+        writer.write_u64(self.user_id)?;
+        writer.write_bool(self.online)?;
+
+        Ok(())
+    }
+}
+
+impl BdSerialize for UserName {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.user_id)?;
+        writer.write_str(&self.username)?;
+
+        Ok(())
     }
 }
@@ -0,0 +1,49 @@
+use crate::lobby::event_log::EventPayload;
+use crate::networking::bd_session::BdSession;
+use chrono::{DateTime, Utc};
+use std::net::Ipv4Addr;
+
+/// Statistics about the users of a title.
+/// The layout is inferred, the actual reply may contain further values.
+pub struct TitleStats {
+    /// The amount of users that are currently connected for the title.
+    pub online_users: u32,
+}
+
+/// Whether a user is currently connected for the same title.
+pub struct UserOnlineStatus {
+    pub user_id: u64,
+    pub online: bool,
+}
+
+pub struct UserName {
+    pub user_id: u64,
+    pub username: String,
+}
+
+pub type ThreadSafeTitleUtilitiesService = dyn TitleUtilitiesService + Sync + Send;
+
+/// Implements domain logic concerning the miscellaneous utilities every title can use.
+pub trait TitleUtilitiesService {
+    /// Checks whether the string does not contain any disallowed words.
+    fn verify_string(&self, session: &BdSession, value: String) -> bool;
+
+    /// Retrieves statistics about the users of the title of the current session.
+    fn get_title_stats(&self, session: &BdSession) -> TitleStats;
+
+    /// Records an event of the legacy event api that titles used before the event log service.
+    fn record_event(&self, session: &BdSession, payload: EventPayload);
+
+    /// Remembers the ip address the current client reported for itself.
+    fn record_ip(&self, session: &BdSession, ip: Ipv4Addr);
+
+    /// Retrieves the time the client should assume as the current time.
+    fn get_server_time(&self, session: &BdSession) -> DateTime<Utc>;
+
+    /// Retrieves which of the specified users are connected for the title of the current session.
+    fn are_users_online(&self, session: &BdSession, user_ids: Vec<u64>) -> Vec<UserOnlineStatus>;
+
+    /// Retrieves the names of the specified users.
+    /// Users whose name is not known are omitted.
+    fn get_user_names(&self, session: &BdSession, user_ids: Vec<u64>) -> Vec<UserName>;
+}
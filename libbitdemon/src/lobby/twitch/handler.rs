@@ -1,5 +1,6 @@
 ﻿use crate::lobby::response::task_reply::TaskReply;
 use crate::lobby::twitch::result::TwitchBoolResult;
+use crate::lobby::twitch::ThreadSafeTwitchService;
 use crate::lobby::LobbyHandler;
 use crate::messaging::bd_message::BdMessage;
 use crate::messaging::bd_reader::BdReader;
@@ -9,12 +10,15 @@ use crate::networking::bd_session::BdSession;
 use log::{info, warn};
 use num_traits::FromPrimitive;
 use std::error::Error;
+use std::sync::Arc;
 
-pub struct TwitchHandler {}
+pub struct TwitchHandler {
+    pub twitch_service: Arc<ThreadSafeTwitchService>,
+}
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum TwitchTaskId {
+pub enum TwitchTaskId {
     LinkAccount = 1,
     UnlinkAccount = 2,
     IsLinked = 3,
@@ -37,54 +41,57 @@ impl LobbyHandler for TwitchHandler {
         let task_id = maybe_task_id.unwrap();
 
         match task_id {
-            TwitchTaskId::LinkAccount => Self::link_account(session, &mut message.reader),
-            TwitchTaskId::UnlinkAccount => Self::unlink_account(session, &mut message.reader),
-            TwitchTaskId::IsLinked => Self::is_linked(session, &mut message.reader),
+            TwitchTaskId::LinkAccount => self.link_account(session, &mut message.reader),
+            TwitchTaskId::UnlinkAccount => self.unlink_account(session, &mut message.reader),
+            TwitchTaskId::IsLinked => self.is_linked(session, &mut message.reader),
             TwitchTaskId::GetUserInfo => Self::get_user_info(session, &mut message.reader),
         }
     }
 }
 
-impl Default for TwitchHandler {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl TwitchHandler {
-    pub fn new() -> TwitchHandler {
-        TwitchHandler {}
+    pub fn new(twitch_service: Arc<ThreadSafeTwitchService>) -> TwitchHandler {
+        TwitchHandler { twitch_service }
     }
 
     fn link_account(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let token = reader.read_str()?;
 
-        info!("Trying to link account token={token}");
+        info!("Trying to link account");
+
+        self.twitch_service.link_account(session, token)?;
 
         TaskReply::with_only_error_code(BdErrorCode::NoError, TwitchTaskId::LinkAccount)
             .to_response()
     }
 
     fn unlink_account(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         info!("Trying to unlink account");
 
-        TaskReply::with_only_error_code(BdErrorCode::NoError, TwitchTaskId::LinkAccount)
+        self.twitch_service.unlink_account(session)?;
+
+        TaskReply::with_only_error_code(BdErrorCode::NoError, TwitchTaskId::UnlinkAccount)
             .to_response()
     }
 
     fn is_linked(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
+        let is_linked = self.twitch_service.is_linked(session)?;
+
         TaskReply::with_results(
             TwitchTaskId::IsLinked,
-            vec![Box::new(TwitchBoolResult { value: false })],
+            vec![Box::new(TwitchBoolResult { value: is_linked })],
         )
         .to_response()
     }
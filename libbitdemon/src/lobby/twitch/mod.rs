@@ -1,4 +1,6 @@
 ﻿mod handler;
 mod result;
+mod service;
 
-pub use handler::TwitchHandler;
+pub use handler::{TwitchHandler, TwitchTaskId};
+pub use service::*;
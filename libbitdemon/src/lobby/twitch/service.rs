@@ -0,0 +1,18 @@
+use crate::networking::bd_session::BdSession;
+use std::error::Error;
+
+pub type ThreadSafeTwitchService = dyn TwitchService + Sync + Send;
+
+/// Implements domain logic concerning linking Twitch accounts.
+pub trait TwitchService {
+    /// Links a Twitch account to the current authenticated user.
+    /// Replaces any account that was linked previously.
+    fn link_account(&self, session: &BdSession, token: String) -> Result<(), Box<dyn Error>>;
+
+    /// Removes the link to the Twitch account of the current authenticated user.
+    /// Does nothing if no account is linked.
+    fn unlink_account(&self, session: &BdSession) -> Result<(), Box<dyn Error>>;
+
+    /// Checks whether the current authenticated user linked a Twitch account.
+    fn is_linked(&self, session: &BdSession) -> Result<bool, Box<dyn Error>>;
+}
@@ -0,0 +1,176 @@
+use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::twitter::result::TwitterBoolResult;
+use crate::lobby::twitter::{ThreadSafeTwitterService, TwitterServiceError};
+use crate::lobby::LobbyHandler;
+use crate::messaging::bd_message::BdMessage;
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::bd_response::{BdResponse, ResponseCreator};
+use crate::messaging::BdErrorCode;
+use crate::networking::bd_session::BdSession;
+use log::{info, warn};
+use num_traits::FromPrimitive;
+use std::error::Error;
+use std::sync::Arc;
+
+pub struct TwitterHandler {
+    pub twitter_service: Arc<ThreadSafeTwitterService>,
+    /// The error code to answer with for tasks that require a registered account.
+    pub not_linked_error_code: BdErrorCode,
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum TwitterTaskId {
+    RegisterAccount = 1,
+    Post = 2,
+    UnregisterAccount = 3,
+    IsRegistered = 4,
+    GetInfo = 5,
+}
+
+impl LobbyHandler for TwitterHandler {
+    fn handle_message(
+        &self,
+        session: &mut BdSession,
+        mut message: BdMessage,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let task_id_value = message.reader.read_u8()?;
+        let maybe_task_id = TwitterTaskId::from_u8(task_id_value);
+        if maybe_task_id.is_none() {
+            warn!("Client called unknown task {task_id_value}");
+            return TaskReply::with_only_error_code(BdErrorCode::NoError, task_id_value)
+                .to_response();
+        }
+        let task_id = maybe_task_id.unwrap();
+
+        match task_id {
+            TwitterTaskId::RegisterAccount => self.register_account(session, &mut message.reader),
+            TwitterTaskId::UnregisterAccount => {
+                self.unregister_account(session, &mut message.reader)
+            }
+            TwitterTaskId::IsRegistered => self.is_registered(session, &mut message.reader),
+            TwitterTaskId::Post => self.post(session, &mut message.reader),
+            TwitterTaskId::GetInfo => self.get_info(session, &mut message.reader),
+        }
+    }
+}
+
+impl TwitterHandler {
+    pub fn new(twitter_service: Arc<ThreadSafeTwitterService>) -> TwitterHandler {
+        TwitterHandler {
+            twitter_service,
+            not_linked_error_code: BdErrorCode::TwitterAuthTokenInvalid,
+        }
+    }
+
+    pub fn with_not_linked_error_code(mut self, error_code: BdErrorCode) -> Self {
+        self.not_linked_error_code = error_code;
+
+        self
+    }
+
+    fn register_account(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let handle = reader.read_str()?;
+        let password = reader.read_str()?;
+
+        info!("Trying to register account {handle}");
+
+        let result = self
+            .twitter_service
+            .register_account(session, handle, password);
+
+        self.answer_without_result(TwitterTaskId::RegisterAccount, result)
+    }
+
+    fn unregister_account(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        info!("Trying to unregister account");
+
+        let result = self.twitter_service.unregister_account(session);
+
+        self.answer_without_result(TwitterTaskId::UnregisterAccount, result)
+    }
+
+    fn is_registered(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self.twitter_service.is_registered(session);
+
+        match result {
+            Ok(value) => Ok(TaskReply::with_results(
+                TwitterTaskId::IsRegistered,
+                vec![Box::new(TwitterBoolResult { value })],
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                self.error_code_for(error),
+                TwitterTaskId::IsRegistered,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn post(
+        &self,
+        session: &mut BdSession,
+        reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let message = reader.read_str()?;
+
+        info!("Trying to post {message}");
+
+        let result = self.twitter_service.post(session, message);
+
+        self.answer_without_result(TwitterTaskId::Post, result)
+    }
+
+    fn get_info(
+        &self,
+        session: &mut BdSession,
+        _reader: &mut BdReader,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let result = self.twitter_service.get_info(session);
+
+        match result {
+            Ok(info) => Ok(
+                TaskReply::with_results(TwitterTaskId::GetInfo, vec![Box::new(info)])
+                    .to_response()?,
+            ),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                self.error_code_for(error),
+                TwitterTaskId::GetInfo,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn answer_without_result(
+        &self,
+        task_id: TwitterTaskId,
+        result: Result<(), TwitterServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let error_code = match result {
+            Ok(()) => BdErrorCode::NoError,
+            Err(error) => self.error_code_for(error),
+        };
+
+        TaskReply::with_only_error_code(error_code, task_id).to_response()
+    }
+
+    fn error_code_for(&self, error: TwitterServiceError) -> BdErrorCode {
+        match error {
+            TwitterServiceError::AuthAttemptFailedError => BdErrorCode::TwitterAuthAttemptFailed,
+            TwitterServiceError::NotRegisteredError => self.not_linked_error_code,
+            TwitterServiceError::UnavailableError => BdErrorCode::TwitterUnavailable,
+        }
+    }
+}
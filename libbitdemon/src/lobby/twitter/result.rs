@@ -0,0 +1,15 @@
+use crate::lobby::twitter::TwitterAccountInfo;
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use std::error::Error;
+
+#[derive(BdSerialize)]
+pub struct TwitterBoolResult {
+    pub value: bool,
+}
+
+impl BdSerialize for TwitterAccountInfo {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_str(self.handle.as_str())
+    }
+}
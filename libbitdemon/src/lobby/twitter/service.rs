@@ -0,0 +1,65 @@
+use crate::networking::bd_session::BdSession;
+
+/// Details about the Twitter account a user registered.
+pub struct TwitterAccountInfo {
+    /// The handle of the account without a leading `@`.
+    pub handle: String,
+}
+
+/// Errors that may occur when handling Twitter calls.
+#[derive(Debug)]
+pub enum TwitterServiceError {
+    /// The credentials of the Twitter account were rejected.
+    AuthAttemptFailedError,
+    /// The current authenticated user did not register a Twitter account.
+    NotRegisteredError,
+    /// The post could not be delivered.
+    UnavailableError,
+}
+
+pub type ThreadSafeTwitterService = dyn TwitterService + Sync + Send;
+
+/// Implements domain logic concerning registering Twitter accounts and posting to them.
+pub trait TwitterService {
+    /// Registers a Twitter account for the current authenticated user.
+    /// Replaces any account that was registered previously.
+    ///
+    /// # Errors
+    ///
+    /// * [`AuthAttemptFailedError`][1]: The credentials were rejected.
+    ///
+    /// [1]: TwitterServiceError::AuthAttemptFailedError
+    fn register_account(
+        &self,
+        session: &BdSession,
+        handle: String,
+        password: String,
+    ) -> Result<(), TwitterServiceError>;
+
+    /// Removes the Twitter account of the current authenticated user.
+    /// Does nothing if no account is registered.
+    fn unregister_account(&self, session: &BdSession) -> Result<(), TwitterServiceError>;
+
+    /// Checks whether the current authenticated user registered a Twitter account.
+    fn is_registered(&self, session: &BdSession) -> Result<bool, TwitterServiceError>;
+
+    /// Posts a message on the Twitter account of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`NotRegisteredError`][1]: The user did not register an account.
+    /// * [`UnavailableError`][2]: The post could not be delivered.
+    ///
+    /// [1]: TwitterServiceError::NotRegisteredError
+    /// [2]: TwitterServiceError::UnavailableError
+    fn post(&self, session: &BdSession, message: String) -> Result<(), TwitterServiceError>;
+
+    /// Retrieves details about the Twitter account of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`NotRegisteredError`][1]: The user did not register an account.
+    ///
+    /// [1]: TwitterServiceError::NotRegisteredError
+    fn get_info(&self, session: &BdSession) -> Result<TwitterAccountInfo, TwitterServiceError>;
+}
@@ -1,5 +1,7 @@
-﻿use crate::domain::result_slice::ResultSlice;
-use crate::lobby::response::task_reply::TaskReply;
+﻿use crate::lobby::response::task_reply::TaskReply;
+use crate::lobby::vote_rank::{
+    CategorizedRatingInfo, RatingInfo, ThreadSafeVoteRankService, VoteRankServiceError,
+};
 use crate::lobby::LobbyHandler;
 use crate::messaging::bd_message::BdMessage;
 use crate::messaging::bd_reader::BdReader;
@@ -9,26 +11,22 @@ use crate::messaging::BdErrorCode;
 use crate::networking::bd_session::BdSession;
 use log::{info, warn};
 use num_traits::FromPrimitive;
-use snafu::{OptionExt, Snafu};
 use std::error::Error;
+use std::sync::Arc;
 
-pub struct VoteRankHandler {}
+pub struct VoteRankHandler {
+    pub vote_rank_service: Arc<ThreadSafeVoteRankService>,
+}
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum VoteRankTaskId {
+pub enum VoteRankTaskId {
     // GetLikeDislikeRatioFromRating
     SubmitRating = 1,
     SubmitCategorizedRating = 2,
     GetVoteHistory = 3,
 }
 
-#[derive(Debug, Snafu)]
-enum VoteRankError {
-    #[snafu(display("There is no such vote entry for value={value}"))]
-    InvalidVote { value: u8 },
-}
-
 impl LobbyHandler for VoteRankHandler {
     fn handle_message(
         &self,
@@ -54,37 +52,38 @@ impl LobbyHandler for VoteRankHandler {
     }
 }
 
-impl Default for VoteRankHandler {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl VoteRankHandler {
-    pub fn new() -> VoteRankHandler {
-        VoteRankHandler {}
+    pub fn new(vote_rank_service: Arc<ThreadSafeVoteRankService>) -> VoteRankHandler {
+        VoteRankHandler { vote_rank_service }
     }
 
     fn submit_rating(
         &self,
-        _session: &mut BdSession,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let mut votes = Vec::new();
 
         while let Ok(rating_info) = RatingInfo::deserialize(reader) {
-            votes.push(rating_info);
+            votes.push(CategorizedRatingInfo {
+                rating_info,
+                category: 0,
+            });
         }
 
         info!("User submitted rating: {votes:?}");
 
-        TaskReply::with_only_error_code(BdErrorCode::NoError, VoteRankTaskId::SubmitRating)
-            .to_response()
+        let error_code = match self.vote_rank_service.submit_ratings(session, votes) {
+            Ok(()) => BdErrorCode::NoError,
+            Err(error) => error.into(),
+        };
+
+        TaskReply::with_only_error_code(error_code, VoteRankTaskId::SubmitRating).to_response()
     }
 
     fn submit_categorized_rating(
         &self,
-        _session: &mut BdSession,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let mut votes = Vec::new();
@@ -95,13 +94,18 @@ impl VoteRankHandler {
 
         info!("User submitted categorized rating: {votes:?}");
 
-        TaskReply::with_only_error_code(BdErrorCode::NoError, VoteRankTaskId::SubmitRating)
+        let error_code = match self.vote_rank_service.submit_ratings(session, votes) {
+            Ok(()) => BdErrorCode::NoError,
+            Err(error) => error.into(),
+        };
+
+        TaskReply::with_only_error_code(error_code, VoteRankTaskId::SubmitCategorizedRating)
             .to_response()
     }
 
     fn get_vote_history(
         &self,
-        _session: &mut BdSession,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let unknown = reader.read_u16()?;
@@ -110,33 +114,15 @@ impl VoteRankHandler {
 
         info!("Retrieving vote history unknown={unknown} item_offset={item_offset} item_count={item_count}");
 
-        // Returns result slice with CategorizedRatingInfo
-        TaskReply::with_result_slice(
-            VoteRankTaskId::GetVoteHistory,
-            ResultSlice::new(Vec::new(), 0),
-        )
-        .to_response()
-    }
-}
-
-#[derive(Debug, FromPrimitive, ToPrimitive)]
-enum Vote {
-    Dislike = 0x0,
-    Like = 0xA,
-}
-
-#[derive(Debug)]
-#[allow(dead_code)]
-struct RatingInfo {
-    entity_id: u64,
-    rating: Vote,
-}
+        let votes = self.vote_rank_service.get_vote_history(
+            session,
+            item_offset as usize,
+            item_count as usize,
+        );
 
-#[derive(Debug)]
-#[allow(dead_code)]
-struct CategorizedRatingInfo {
-    rating_info: RatingInfo,
-    category: u16,
+        TaskReply::with_result_slice(VoteRankTaskId::GetVoteHistory, votes.serializable())
+            .to_response()
+    }
 }
 
 impl BdDeserialize for RatingInfo {
@@ -145,10 +131,7 @@ impl BdDeserialize for RatingInfo {
         Self: Sized,
     {
         let entity_id = reader.read_u64()?;
-        let rating_value = reader.read_u8()?;
-        let rating = Vote::from_u8(rating_value).with_context(|| InvalidVoteSnafu {
-            value: rating_value,
-        })?;
+        let rating = reader.read_enum_u8()?;
 
         Ok(RatingInfo { entity_id, rating })
     }
@@ -168,3 +151,11 @@ impl BdDeserialize for CategorizedRatingInfo {
         })
     }
 }
+
+impl From<VoteRankServiceError> for BdErrorCode {
+    fn from(value: VoteRankServiceError) -> Self {
+        match value {
+            VoteRankServiceError::TooManyRatingsError => BdErrorCode::TooManyEntityIdsRequested,
+        }
+    }
+}
@@ -1,3 +1,6 @@
 ﻿mod handler;
+mod result;
+mod service;
 
-pub use handler::VoteRankHandler;
+pub use handler::{VoteRankHandler, VoteRankTaskId};
+pub use service::*;
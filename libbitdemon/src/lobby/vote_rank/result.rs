@@ -0,0 +1,13 @@
+use crate::lobby::vote_rank::CategorizedRatingInfo;
+use crate::messaging::bd_serialization::BdSerialize;
+use crate::messaging::bd_writer::BdWriter;
+use num_traits::ToPrimitive;
+use std::error::Error;
+
+impl BdSerialize for CategorizedRatingInfo {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_u64(self.rating_info.entity_id)?;
+        writer.write_u8(self.rating_info.rating.to_u8().unwrap())?;
+        writer.write_u16(self.category)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::domain::result_slice::ResultSlice;
+use crate::networking::bd_session::BdSession;
+use num_derive::{FromPrimitive, ToPrimitive};
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum Vote {
+    Dislike = 0x0,
+    Like = 0xA,
+}
+
+/// The vote of a user for an entity, like a map or a piece of user generated content.
+#[derive(Debug, Clone)]
+pub struct RatingInfo {
+    pub entity_id: u64,
+    pub rating: Vote,
+}
+
+/// A vote that only applies to a single category of the entity.
+/// Votes without a category are treated as votes for category `0`.
+#[derive(Debug, Clone)]
+pub struct CategorizedRatingInfo {
+    pub rating_info: RatingInfo,
+    pub category: u16,
+}
+
+/// All votes of all users for a single category of an entity.
+#[derive(Debug, Clone)]
+pub struct RatingAggregate {
+    pub entity_id: u64,
+    pub category: u16,
+    pub likes: u64,
+    pub dislikes: u64,
+}
+
+impl RatingAggregate {
+    /// The share of likes in all votes, `0.5` when nobody voted yet.
+    pub fn like_ratio(&self) -> f32 {
+        let total = self.likes + self.dislikes;
+        if total == 0 {
+            return 0.5;
+        }
+
+        self.likes as f32 / total as f32
+    }
+}
+
+#[derive(Debug)]
+pub enum VoteRankServiceError {
+    /// The client submitted more votes at once than allowed.
+    TooManyRatingsError,
+}
+
+pub type ThreadSafeVoteRankService = dyn VoteRankService + Sync + Send;
+
+/// Implements domain logic concerning users voting for entities and ranking them by votes.
+pub trait VoteRankService {
+    /// Records the votes of the current authenticated user.
+    /// A later vote for the same entity and category replaces the earlier one.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TooManyRatingsError`][1] when too many votes are submitted at once.
+    ///
+    /// [1]: VoteRankServiceError::TooManyRatingsError
+    fn submit_ratings(
+        &self,
+        session: &BdSession,
+        ratings: Vec<CategorizedRatingInfo>,
+    ) -> Result<(), VoteRankServiceError>;
+
+    /// Retrieves the votes of the current authenticated user, most recent first.
+    fn get_vote_history(
+        &self,
+        session: &BdSession,
+        item_offset: usize,
+        item_count: usize,
+    ) -> ResultSlice<CategorizedRatingInfo>;
+
+    /// Sums up the votes of all users for the specified entities per category.
+    fn get_rating_aggregates(
+        &self,
+        session: &BdSession,
+        entity_ids: &[u64],
+    ) -> Vec<RatingAggregate>;
+}
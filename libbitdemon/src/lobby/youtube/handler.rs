@@ -1,5 +1,6 @@
 ﻿use crate::lobby::response::task_reply::TaskReply;
-use crate::lobby::youtube::result::YoutubeBoolResult;
+use crate::lobby::youtube::result::{YoutubeBoolResult, YoutubeUserTokenResult};
+use crate::lobby::youtube::{ThreadSafeYoutubeService, YoutubeServiceError, YoutubeUpload};
 use crate::lobby::LobbyHandler;
 use crate::messaging::bd_message::BdMessage;
 use crate::messaging::bd_reader::BdReader;
@@ -9,12 +10,15 @@ use crate::networking::bd_session::BdSession;
 use log::{info, warn};
 use num_traits::FromPrimitive;
 use std::error::Error;
+use std::sync::Arc;
 
-pub struct YoutubeHandler {}
+pub struct YoutubeHandler {
+    pub youtube_service: Arc<ThreadSafeYoutubeService>,
+}
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u8)]
-enum YoutubeTaskId {
+pub enum YoutubeTaskId {
     // GetUploadStats
     StartAccountRegistration = 1,
     IsRegistered = 2,
@@ -40,63 +44,69 @@ impl LobbyHandler for YoutubeHandler {
 
         match task_id {
             YoutubeTaskId::StartAccountRegistration => {
-                Self::start_account_registration(session, &mut message.reader)
+                self.start_account_registration(session, &mut message.reader)
             }
-            YoutubeTaskId::IsRegistered => Self::is_registered(session, &mut message.reader),
-            YoutubeTaskId::Unregister => Self::unregister(session, &mut message.reader),
-            YoutubeTaskId::UploadVideo => Self::upload_video(session, &mut message.reader),
-            YoutubeTaskId::GetUserToken => Self::get_user_token(session, &mut message.reader),
+            YoutubeTaskId::IsRegistered => self.is_registered(session, &mut message.reader),
+            YoutubeTaskId::Unregister => self.unregister(session, &mut message.reader),
+            YoutubeTaskId::UploadVideo => self.upload_video(session, &mut message.reader),
+            YoutubeTaskId::GetUserToken => self.get_user_token(session, &mut message.reader),
         }
     }
 }
 
-impl Default for YoutubeHandler {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl YoutubeHandler {
-    pub fn new() -> YoutubeHandler {
-        YoutubeHandler {}
+    pub fn new(youtube_service: Arc<ThreadSafeYoutubeService>) -> YoutubeHandler {
+        YoutubeHandler { youtube_service }
     }
 
     fn start_account_registration(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         info!("Trying to start account registration");
 
-        TaskReply::with_only_error_code(
-            BdErrorCode::YoutubeServiceError,
-            YoutubeTaskId::StartAccountRegistration,
-        )
-        .to_response()
+        let result = self.youtube_service.start_account_registration(session);
+
+        Self::answer_without_result(YoutubeTaskId::StartAccountRegistration, result)
     }
 
     fn is_registered(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
-        TaskReply::with_results(
-            YoutubeTaskId::IsRegistered,
-            vec![Box::new(YoutubeBoolResult { value: false })],
-        )
-        .to_response()
+        let result = self.youtube_service.is_registered(session);
+
+        match result {
+            Ok(value) => Ok(TaskReply::with_results(
+                YoutubeTaskId::IsRegistered,
+                vec![Box::new(YoutubeBoolResult { value })],
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                YoutubeTaskId::IsRegistered,
+            )
+            .to_response()?),
+        }
     }
 
     fn unregister(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         info!("Trying to unregister");
 
-        TaskReply::with_only_error_code(BdErrorCode::NoError, YoutubeTaskId::Unregister)
-            .to_response()
+        let result = self.youtube_service.unregister(session);
+
+        Self::answer_without_result(YoutubeTaskId::Unregister, result)
     }
 
     fn upload_video(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         let file_id = reader.read_u64()?;
@@ -110,23 +120,68 @@ impl YoutubeHandler {
 
         info!("Trying to upload file {file_id} (private={is_private}; developerTags={developer_tags:?})");
 
-        TaskReply::with_only_error_code(
-            BdErrorCode::YoutubeServiceError,
-            YoutubeTaskId::UploadVideo,
-        )
-        .to_response()
+        let result = self.youtube_service.upload_video(
+            session,
+            YoutubeUpload {
+                file_id,
+                is_private,
+                developer_tags,
+            },
+        );
+
+        Self::answer_without_result(YoutubeTaskId::UploadVideo, result)
     }
 
     fn get_user_token(
-        _session: &mut BdSession,
+        &self,
+        session: &mut BdSession,
         _reader: &mut BdReader,
     ) -> Result<BdResponse, Box<dyn Error>> {
         info!("Trying to get user token");
 
-        TaskReply::with_only_error_code(
-            BdErrorCode::YoutubeServiceError,
-            YoutubeTaskId::GetUserToken,
-        )
-        .to_response()
+        let result = self.youtube_service.get_user_token(session);
+
+        match result {
+            Ok(token) => Ok(TaskReply::with_results(
+                YoutubeTaskId::GetUserToken,
+                vec![Box::new(YoutubeUserTokenResult { token })],
+            )
+            .to_response()?),
+            Err(error) => Ok(TaskReply::with_only_error_code(
+                error.into(),
+                YoutubeTaskId::GetUserToken,
+            )
+            .to_response()?),
+        }
+    }
+
+    fn answer_without_result(
+        task_id: YoutubeTaskId,
+        result: Result<(), YoutubeServiceError>,
+    ) -> Result<BdResponse, Box<dyn Error>> {
+        let error_code = match result {
+            Ok(()) => BdErrorCode::NoError,
+            Err(error) => error.into(),
+        };
+
+        TaskReply::with_only_error_code(error_code, task_id).to_response()
+    }
+}
+
+impl From<YoutubeServiceError> for BdErrorCode {
+    fn from(value: YoutubeServiceError) -> Self {
+        match value {
+            YoutubeServiceError::ServiceDisabledError => BdErrorCode::YoutubeServiceError,
+            YoutubeServiceError::AccountAlreadyRegisteredError => {
+                BdErrorCode::YoutubeAccountAlreadyRegistered
+            }
+            YoutubeServiceError::AccountNotRegisteredError => {
+                BdErrorCode::YoutubeAccountNotRegistered
+            }
+            YoutubeServiceError::UploadDoesNotExistError => BdErrorCode::YoutubeUploadDoesNotExist,
+            YoutubeServiceError::DeveloperTagsInvalidError => {
+                BdErrorCode::YoutubeDeveloperTagsInvalid
+            }
+        }
     }
 }
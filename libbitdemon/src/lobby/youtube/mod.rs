@@ -1,4 +1,6 @@
 ﻿mod handler;
 mod result;
+mod service;
 
-pub use handler::YoutubeHandler;
+pub use handler::{YoutubeHandler, YoutubeTaskId};
+pub use service::*;
@@ -0,0 +1,85 @@
+use crate::networking::bd_session::BdSession;
+
+/// A request to upload a video to the YouTube account of a user.
+pub struct YoutubeUpload {
+    /// The id of the user file containing the video.
+    pub file_id: u64,
+    /// Whether the video should only be visible to the owner of the account.
+    pub is_private: bool,
+    /// Tags attached to the video to identify the title that uploaded it.
+    pub developer_tags: Vec<String>,
+}
+
+/// Errors that may occur when handling YouTube calls.
+#[derive(Debug)]
+pub enum YoutubeServiceError {
+    /// The backend does not support interacting with YouTube.
+    ServiceDisabledError,
+    /// The current authenticated user already registered a YouTube account.
+    AccountAlreadyRegisteredError,
+    /// The current authenticated user did not register a YouTube account.
+    AccountNotRegisteredError,
+    /// The file to upload does not exist.
+    UploadDoesNotExistError,
+    /// The developer tags of an upload were rejected.
+    DeveloperTagsInvalidError,
+}
+
+pub type ThreadSafeYoutubeService = dyn YoutubeService + Sync + Send;
+
+/// Implements domain logic concerning linking YouTube accounts and uploading videos to them.
+///
+/// Implementations may forward requests to YouTube or only keep track of registered accounts
+/// and answer everything else with [`ServiceDisabledError`][1].
+///
+/// [1]: YoutubeServiceError::ServiceDisabledError
+pub trait YoutubeService {
+    /// Registers a YouTube account for the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`ServiceDisabledError`][1]: Registering accounts is not supported.
+    /// * [`AccountAlreadyRegisteredError`][2]: The user already registered an account.
+    ///
+    /// [1]: YoutubeServiceError::ServiceDisabledError
+    /// [2]: YoutubeServiceError::AccountAlreadyRegisteredError
+    fn start_account_registration(&self, session: &BdSession) -> Result<(), YoutubeServiceError>;
+
+    /// Checks whether the current authenticated user registered a YouTube account.
+    fn is_registered(&self, session: &BdSession) -> Result<bool, YoutubeServiceError>;
+
+    /// Removes the YouTube account of the current authenticated user.
+    /// Does nothing if no account is registered.
+    fn unregister(&self, session: &BdSession) -> Result<(), YoutubeServiceError>;
+
+    /// Uploads a user file as video to the YouTube account of the current authenticated user.
+    ///
+    /// # Errors
+    ///
+    /// * [`ServiceDisabledError`][1]: Uploading videos is not supported.
+    /// * [`AccountNotRegisteredError`][2]: The user did not register an account.
+    /// * [`UploadDoesNotExistError`][3]: The file to upload does not exist.
+    /// * [`DeveloperTagsInvalidError`][4]: The developer tags were rejected.
+    ///
+    /// [1]: YoutubeServiceError::ServiceDisabledError
+    /// [2]: YoutubeServiceError::AccountNotRegisteredError
+    /// [3]: YoutubeServiceError::UploadDoesNotExistError
+    /// [4]: YoutubeServiceError::DeveloperTagsInvalidError
+    fn upload_video(
+        &self,
+        session: &BdSession,
+        upload: YoutubeUpload,
+    ) -> Result<(), YoutubeServiceError>;
+
+    /// Retrieves a token the client can use to upload to the YouTube account
+    /// of the current authenticated user on its own.
+    ///
+    /// # Errors
+    ///
+    /// * [`ServiceDisabledError`][1]: Upload tokens are not supported.
+    /// * [`AccountNotRegisteredError`][2]: The user did not register an account.
+    ///
+    /// [1]: YoutubeServiceError::ServiceDisabledError
+    /// [2]: YoutubeServiceError::AccountNotRegisteredError
+    fn get_user_token(&self, session: &BdSession) -> Result<String, YoutubeServiceError>;
+}
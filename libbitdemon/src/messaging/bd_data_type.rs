@@ -1,6 +1,7 @@
 ﻿use num_traits::{FromPrimitive, ToPrimitive};
 use snafu::{OptionExt, Snafu};
 use std::error::Error;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, FromPrimitive, ToPrimitive)]
 #[repr(u32)]
@@ -27,9 +28,52 @@ pub enum BdDataType {
     BlobType = 0x13,
     NanType = 0x14,
     FullType = 0x15,
+    /// A fixed number of values of any type, including further structs and maps.
+    StructType = 0x16,
+    /// A number of key value pairs of any type.
+    MapType = 0x17,
     MaxType = 0x20,
 }
 
+impl BdDataType {
+    /// A short name of the type, used when dumping buffers.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BdDataType::NoType => "none",
+            BdDataType::BoolType => "bool",
+            BdDataType::SignedChar8Type => "i8",
+            BdDataType::UnsignedChar8Type => "u8",
+            BdDataType::WChar16Type => "wchar16",
+            BdDataType::SignedInteger16Type => "i16",
+            BdDataType::UnsignedInteger16Type => "u16",
+            BdDataType::SignedInteger32Type => "i32",
+            BdDataType::UnsignedInteger32Type => "u32",
+            BdDataType::SignedInteger64Type => "i64",
+            BdDataType::UnsignedInteger64Type => "u64",
+            BdDataType::RangedSignedInteger32Type => "ranged_i32",
+            BdDataType::RangedUnsignedInteger32Type => "ranged_u32",
+            BdDataType::Float32Type => "f32",
+            BdDataType::Float64Type => "f64",
+            BdDataType::RangedFloat32Type => "ranged_f32",
+            BdDataType::SignedChar8StringType => "string",
+            BdDataType::UnsignedChar8StringType => "ustring",
+            BdDataType::MbStringType => "mbstring",
+            BdDataType::BlobType => "blob",
+            BdDataType::NanType => "nan",
+            BdDataType::FullType => "full",
+            BdDataType::StructType => "struct",
+            BdDataType::MapType => "map",
+            BdDataType::MaxType => "max",
+        }
+    }
+}
+
+impl Display for BdDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct BufferDataType {
     pub primitive_type: BdDataType,
@@ -44,6 +88,11 @@ struct IllegalDataTypeError {
 
 const ARRAY_TYPE_OFFSET: u8 = 100;
 
+/// Array types do not fit into the 5 bits of a data type in bit mode.
+/// There they are written as this tag followed by the data type of their elements.
+/// The tag is not known to be understood by clients, so it is only used for data the server reads itself.
+pub(crate) const BIT_MODE_ARRAY_TAG: u8 = 0x1F;
+
 impl BufferDataType {
     pub fn no_array(primitive_type: BdDataType) -> BufferDataType {
         BufferDataType {
@@ -92,3 +141,13 @@ impl BufferDataType {
         }
     }
 }
+
+impl Display for BufferDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_array {
+            write!(f, "{}[]", self.primitive_type)
+        } else {
+            write!(f, "{}", self.primitive_type)
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Human-readable dumps of type checked buffers.
+//!
+//! Every value of a type checked buffer is preceded by its data type,
+//! so its structure can be shown without knowing what it contains.
+//! This helps when figuring out the payload of tasks that are not implemented yet.
+
+use crate::messaging::bd_data_type::{BdDataType, BufferDataType};
+use crate::messaging::bd_reader::BdReader;
+use crate::messaging::StreamMode;
+use std::error::Error;
+use std::fmt::Write;
+
+const INDENT: &str = "  ";
+
+/// Dumps a type checked buffer in the given mode.
+/// Messages in bit mode that announce type checking with a leading bit
+/// should be dumped with [`dump_reader`] after reading it.
+pub fn dump(data: &[u8], mode: StreamMode) -> String {
+    let mut reader = BdReader::borrowed(data);
+    reader.set_mode(mode);
+    reader.set_type_checked(true);
+
+    dump_reader(&mut reader)
+}
+
+/// Dumps all values that the reader did not read yet, one per line.
+/// Nested values of structs and maps are indented.
+/// Data that cannot be interpreted is appended as hex and ends the dump.
+pub fn dump_reader(reader: &mut BdReader) -> String {
+    let mut out = String::new();
+
+    if !reader.type_checked() {
+        write_unknown(reader, &mut out, 0, "buffer is not type checked");
+        return out;
+    }
+
+    loop {
+        match reader.peek_data_type() {
+            Ok(None) => break,
+            Ok(Some(_)) => {}
+            Err(error) => {
+                write_unknown(reader, &mut out, 0, &error.to_string());
+                break;
+            }
+        }
+
+        if let Err(error) = dump_value(reader, &mut out, 0, "") {
+            write_unknown(reader, &mut out, 0, &error.to_string());
+            break;
+        }
+    }
+
+    out
+}
+
+fn dump_value(
+    reader: &mut BdReader,
+    out: &mut String,
+    depth: usize,
+    label: &str,
+) -> Result<(), Box<dyn Error>> {
+    let data_type = reader
+        .peek_data_type()?
+        .ok_or("expected a value but the buffer ended")?;
+
+    let value = if data_type.is_array {
+        dump_array(reader, data_type)?
+    } else {
+        match data_type.primitive_type {
+            BdDataType::BoolType => reader.read_bool()?.to_string(),
+            BdDataType::SignedChar8Type => reader.read_i8()?.to_string(),
+            BdDataType::UnsignedChar8Type => reader.read_u8()?.to_string(),
+            BdDataType::SignedInteger16Type => reader.read_i16()?.to_string(),
+            BdDataType::UnsignedInteger16Type => reader.read_u16()?.to_string(),
+            BdDataType::SignedInteger32Type => reader.read_i32()?.to_string(),
+            BdDataType::UnsignedInteger32Type => reader.read_u32()?.to_string(),
+            BdDataType::SignedInteger64Type => reader.read_i64()?.to_string(),
+            BdDataType::UnsignedInteger64Type => reader.read_u64()?.to_string(),
+            BdDataType::Float32Type => reader.read_f32()?.to_string(),
+            BdDataType::Float64Type => reader.read_f64()?.to_string(),
+            BdDataType::WChar16Type => format!("{:?}", reader.read_wstr()?),
+            BdDataType::SignedChar8StringType => format!("{:?}", reader.read_str()?),
+            BdDataType::BlobType => {
+                let blob = reader.read_blob()?;
+                format!("({}) {}", blob.len(), hex(&blob))
+            }
+            BdDataType::StructType => {
+                let num_fields = reader.read_struct_header()?;
+                writeln!(
+                    out,
+                    "{}{label}{data_type} ({num_fields})",
+                    INDENT.repeat(depth)
+                )?;
+                for _ in 0..num_fields {
+                    dump_value(reader, out, depth + 1, "")?;
+                }
+
+                return Ok(());
+            }
+            BdDataType::MapType => {
+                let num_entries = reader.read_map_header()?;
+                writeln!(
+                    out,
+                    "{}{label}{data_type} ({num_entries})",
+                    INDENT.repeat(depth)
+                )?;
+                for _ in 0..num_entries {
+                    dump_value(reader, out, depth + 1, "key ")?;
+                    dump_value(reader, out, depth + 1, "value ")?;
+                }
+
+                return Ok(());
+            }
+            _ => return Err(format!("cannot dump values of type {data_type}").into()),
+        }
+    };
+
+    writeln!(out, "{}{label}{data_type} {value}", INDENT.repeat(depth))?;
+
+    Ok(())
+}
+
+fn dump_array(reader: &mut BdReader, data_type: BufferDataType) -> Result<String, Box<dyn Error>> {
+    Ok(match data_type.primitive_type {
+        BdDataType::SignedChar8Type => format!("{:?}", reader.read_i8_array()?),
+        BdDataType::UnsignedChar8Type => format!("{:?}", reader.read_u8_array()?),
+        BdDataType::SignedInteger16Type => format!("{:?}", reader.read_i16_array()?),
+        BdDataType::UnsignedInteger16Type => format!("{:?}", reader.read_u16_array()?),
+        BdDataType::SignedInteger32Type => format!("{:?}", reader.read_i32_array()?),
+        BdDataType::UnsignedInteger32Type => format!("{:?}", reader.read_u32_array()?),
+        BdDataType::SignedInteger64Type => format!("{:?}", reader.read_i64_array()?),
+        BdDataType::UnsignedInteger64Type => format!("{:?}", reader.read_u64_array()?),
+        BdDataType::Float32Type => format!("{:?}", reader.read_f32_array()?),
+        BdDataType::Float64Type => format!("{:?}", reader.read_f64_array()?),
+        BdDataType::WChar16Type => format!("{:?}", reader.read_wstr_array()?),
+        BdDataType::SignedChar8StringType => format!("{:?}", reader.read_str_array()?),
+        _ => return Err(format!("cannot dump arrays of type {data_type}").into()),
+    })
+}
+
+/// Appends the data that could not be interpreted as hex.
+fn write_unknown(reader: &mut BdReader, out: &mut String, depth: usize, reason: &str) {
+    let position = reader.position();
+    let mut rest = vec![0u8; reader.remaining()];
+    let rest = match reader.read_bytes(&mut rest) {
+        Ok(()) if rest.is_empty() => String::new(),
+        Ok(()) => format!(" {}", hex(&rest)),
+        Err(_) => String::from(" <unreadable>"),
+    };
+
+    let _ = writeln!(
+        out,
+        "{}<{reason} at position {position}>{rest}",
+        INDENT.repeat(depth)
+    );
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::bd_writer::BdWriter;
+
+    #[test]
+    fn dumps_nested_values() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.set_type_checked(true);
+            writer.write_u32(42).unwrap();
+            writer.write_struct(&[&1u8, &"name".to_string()]).unwrap();
+            writer.write_map([(&1u64, &true)].into_iter()).unwrap();
+            writer.write_u64_array(&[1, 2]).unwrap();
+            writer.write_blob(&[0xAB, 0xCD]).unwrap();
+        }
+        buf.push(0x0B);
+
+        // The unknown data type itself was already read when it is reported
+        assert_eq!(
+            dump(&buf, StreamMode::ByteMode),
+            format!(
+                "u32 42\n\
+             struct (2)\n  u8 1\n  string \"name\"\n\
+             map (1)\n  key u64 1\n  value bool true\n\
+             u64[] [1, 2]\n\
+             blob (2) ab cd\n\
+             <cannot dump values of type ranged_i32 at position {}>\n",
+                buf.len()
+            )
+        );
+    }
+}
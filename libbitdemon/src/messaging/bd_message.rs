@@ -1,59 +1,313 @@
-use crate::crypto::{calculate_hmac, decrypt_buffer_in_place, generate_iv_from_seed};
+use crate::crypto::{key_fingerprint, verify_hmac, SessionCipher};
 use crate::messaging::bd_reader::BdReader;
 use crate::networking::bd_session::BdSession;
 use snafu::{ensure, Snafu};
 use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-pub struct BdMessage {
-    pub reader: BdReader,
+/// Length of the encryption flag and the iv seed preceding the encrypted data.
+const ENCRYPTION_HEADER_LEN: usize = 5;
+pub struct BdMessage<'a> {
+    pub reader: BdReader<'a>,
+    cipher: Option<SessionCipher>,
 }
 
+/// Errors that may occur when reading an encrypted message.
+///
+/// Neither variant contains key material.
+/// Keys are referred to by their [`key_fingerprint`] instead.
 #[derive(Debug, Snafu)]
-enum BdMessageError {
+pub enum BdMessageError {
     #[snafu(display("Received encrypted message but no session key was set"))]
     NoSessionKeyError,
-    #[snafu(display("Message Hmac mismatch, expected={expected} actual={actual}"))]
-    InvalidHmacError { expected: u32, actual: u32 },
-}
-
-impl BdMessage {
-    pub fn new(session: &BdSession, mut buf: Vec<u8>) -> Result<Self, Box<dyn Error>> {
-        let encrypted = buf.first().unwrap();
-        if *encrypted > 0 {
-            ensure!(session.authentication().is_some(), NoSessionKeySnafu {});
-            let seed = u32::from_le_bytes(buf[1..5].try_into().unwrap());
-
-            let iv = generate_iv_from_seed(seed);
-            let buf_len = buf.len();
-            decrypt_buffer_in_place(
-                &mut buf[5..buf_len],
-                &session.authentication().unwrap().session_key,
-                &iv,
-            )?;
-
-            let hmac = u32::from_le_bytes(buf[5..9].try_into().unwrap());
-
-            // Hmac does not include the message type byte that follows so skip that.
-            let expected_hmac = calculate_hmac(
-                &buf[10..buf.len()],
-                &session.authentication().unwrap().session_key,
-            );
-
-            ensure!(
-                hmac == expected_hmac,
-                InvalidHmacSnafu {
-                    expected: expected_hmac,
-                    actual: hmac
+    #[snafu(display("Encrypted message is too short to contain any data (len={len})"))]
+    TruncatedMessageError { len: usize },
+    #[snafu(display(
+        "Encrypted message is not aligned to the cipher block size (len={len} seed={seed:#010x} key_id={key_id:#010x})"
+    ))]
+    MisalignedMessageError { len: usize, seed: u32, key_id: u32 },
+    #[snafu(display(
+        "Message Hmac mismatch, actual={actual} (seed={seed:#010x} key_id={key_id:#010x})"
+    ))]
+    InvalidHmacError { actual: u32, seed: u32, key_id: u32 },
+}
+
+/// The likely cause of a message that could not be decrypted.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum MessageCryptoFailure {
+    /// The client does not frame or encrypt messages the way the server expects.
+    ProtocolMismatch,
+    /// The message was damaged or cut off in transit.
+    CorruptedTraffic,
+    /// The message was encrypted with a different key than the one of the session.
+    WrongKey,
+}
+
+impl BdMessageError {
+    pub fn failure(&self) -> MessageCryptoFailure {
+        match self {
+            BdMessageError::NoSessionKeyError | BdMessageError::TruncatedMessageError { .. } => {
+                MessageCryptoFailure::ProtocolMismatch
+            }
+            BdMessageError::MisalignedMessageError { .. } => MessageCryptoFailure::CorruptedTraffic,
+            BdMessageError::InvalidHmacError { .. } => MessageCryptoFailure::WrongKey,
+        }
+    }
+}
+
+/// Counts messages that could not be decrypted, grouped by their likely cause.
+#[derive(Default)]
+pub struct MessageCryptoMetrics {
+    protocol_mismatches: AtomicU64,
+    corrupted_messages: AtomicU64,
+    wrong_keys: AtomicU64,
+}
+
+impl MessageCryptoMetrics {
+    pub fn record(&self, failure: MessageCryptoFailure) {
+        let counter = match failure {
+            MessageCryptoFailure::ProtocolMismatch => &self.protocol_mismatches,
+            MessageCryptoFailure::CorruptedTraffic => &self.corrupted_messages,
+            MessageCryptoFailure::WrongKey => &self.wrong_keys,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The amount of messages that were not framed or encrypted as expected.
+    pub fn protocol_mismatches(&self) -> u64 {
+        self.protocol_mismatches.load(Ordering::Relaxed)
+    }
+
+    /// The amount of messages that were damaged in transit.
+    pub fn corrupted_messages(&self) -> u64 {
+        self.corrupted_messages.load(Ordering::Relaxed)
+    }
+
+    /// The amount of messages that were encrypted with a different key.
+    pub fn wrong_keys(&self) -> u64 {
+        self.wrong_keys.load(Ordering::Relaxed)
+    }
+}
+
+impl<'a> BdMessage<'a> {
+    /// Reads a message from the frame it was received in.
+    /// Encrypted frames are decrypted in place, so the message does not need to be copied.
+    pub fn new(session: &BdSession, buf: &'a mut [u8]) -> Result<Self, Box<dyn Error>> {
+        let cipher = SessionCipher::from_frame_flag(*buf.first().unwrap());
+        if let Some(cipher) = cipher {
+            let Some(session_key) = session.session_key() else {
+                return NoSessionKeySnafu {}.fail().map_err(|e| e.into());
+            };
+
+            // Messages sent before a renegotiation reached the client use the previous key
+            match session.previous_session_key() {
+                Some(previous_key) => {
+                    // Decryption overwrites the frame, so keep it for the second attempt
+                    let encrypted = buf.to_vec();
+                    match Self::decrypt(buf, &session_key, cipher) {
+                        Err(BdMessageError::InvalidHmacError { .. }) => {
+                            buf.copy_from_slice(&encrypted);
+                            Self::decrypt(buf, &previous_key, cipher)
+                        }
+                        result => result,
+                    }
                 }
-            );
+                None => Self::decrypt(buf, &session_key, cipher),
+            }?;
 
+            let buf: &'a [u8] = buf;
             Ok(BdMessage {
-                reader: BdReader::new(Vec::from(&buf[9..buf.len()])),
+                reader: BdReader::borrowed(&buf[9..]),
+                cipher: Some(cipher),
             })
         } else {
+            let buf: &'a [u8] = buf;
             Ok(BdMessage {
-                reader: BdReader::new(Vec::from(&buf[1..buf.len()])),
+                reader: BdReader::borrowed(&buf[1..]),
+                cipher: None,
             })
         }
     }
+
+    /// The cipher the client encrypted the message with,
+    /// or `None` if it was sent without encryption.
+    pub fn cipher(&self) -> Option<SessionCipher> {
+        self.cipher
+    }
+
+    fn decrypt(
+        buf: &mut [u8],
+        session_key: &[u8; 24],
+        cipher: SessionCipher,
+    ) -> Result<(), BdMessageError> {
+        let buf_len = buf.len();
+        ensure!(
+            buf_len >= ENCRYPTION_HEADER_LEN + cipher.block_size(),
+            TruncatedMessageSnafu { len: buf_len }
+        );
+
+        let seed = u32::from_le_bytes(buf[1..5].try_into().unwrap());
+        let key_id = key_fingerprint(session_key);
+        ensure!(
+            (buf_len - ENCRYPTION_HEADER_LEN).is_multiple_of(cipher.block_size()),
+            MisalignedMessageSnafu {
+                len: buf_len,
+                seed,
+                key_id
+            }
+        );
+
+        cipher
+            .decrypt_in_place(&mut buf[ENCRYPTION_HEADER_LEN..buf_len], session_key, seed)
+            .map_err(|_| {
+                MisalignedMessageSnafu {
+                    len: buf_len,
+                    seed,
+                    key_id,
+                }
+                .build()
+            })?;
+
+        let hmac = u32::from_le_bytes(buf[5..9].try_into().unwrap());
+
+        // Hmac does not include the message type byte that follows so skip that.
+        let hmac_data = &buf[10..buf.len()];
+
+        ensure!(
+            verify_hmac(hmac_data, session_key, hmac),
+            InvalidHmacSnafu {
+                actual: hmac,
+                seed,
+                key_id,
+            }
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authentication::SessionAuthentication;
+    use crate::crypto::calculate_hmac;
+    use crate::domain::title::Title;
+    use std::net::{TcpListener, TcpStream};
+
+    const KEY: [u8; 24] = [7; 24];
+    const SEED: u32 = 12345678;
+
+    fn session(authenticated: bool) -> BdSession {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut session = BdSession::new(stream);
+        if authenticated {
+            session.set_authentication(SessionAuthentication {
+                user_id: 1,
+                username: "user".to_string(),
+                session_key: KEY.into(),
+                title: Title::T6Pc,
+                clock_skew: None,
+                anonymous: false,
+            });
+        }
+
+        session
+    }
+
+    fn encrypted_message(key: &[u8; 24], payload: &[u8]) -> Vec<u8> {
+        encrypted_message_with(SessionCipher::Tdes, key, payload)
+    }
+
+    fn encrypted_message_with(cipher: SessionCipher, key: &[u8; 24], payload: &[u8]) -> Vec<u8> {
+        // The hmac covers the zero padding that is added by the encryption
+        let mut payload = payload.to_vec();
+        payload.resize(
+            (payload.len() + 4).next_multiple_of(cipher.block_size()) - 4,
+            0,
+        );
+
+        let mut data = calculate_hmac(&payload[1..], key).to_le_bytes().to_vec();
+        data.extend_from_slice(&payload);
+        cipher.encrypt_in_place(&mut data, key, SEED).unwrap();
+
+        let mut buf = vec![cipher.frame_flag()];
+        buf.extend_from_slice(&SEED.to_le_bytes());
+        buf.extend_from_slice(&data);
+
+        buf
+    }
+
+    fn failure_of(session: &BdSession, mut buf: Vec<u8>) -> MessageCryptoFailure {
+        BdMessage::new(session, &mut buf)
+            .err()
+            .unwrap()
+            .downcast_ref::<BdMessageError>()
+            .unwrap()
+            .failure()
+    }
+
+    #[test]
+    fn decrypts_message_encrypted_with_session_key() {
+        let mut frame = encrypted_message(&KEY, &[3, 42]);
+        let mut message = BdMessage::new(&session(true), &mut frame).unwrap();
+
+        let mut payload = [0u8; 2];
+        message.reader.read_bytes(&mut payload).unwrap();
+
+        assert_eq!(payload, [3, 42]);
+        assert_eq!(message.cipher(), Some(SessionCipher::Tdes));
+    }
+
+    #[test]
+    fn decrypts_message_encrypted_with_aes() {
+        let mut frame = encrypted_message_with(SessionCipher::Aes, &KEY, &[3, 42]);
+        let mut message = BdMessage::new(&session(true), &mut frame).unwrap();
+
+        let mut payload = [0u8; 2];
+        message.reader.read_bytes(&mut payload).unwrap();
+
+        assert_eq!(payload, [3, 42]);
+        assert_eq!(message.cipher(), Some(SessionCipher::Aes));
+    }
+
+    #[test]
+    fn classifies_decryption_failures() {
+        let session = session(true);
+
+        let mut misaligned = encrypted_message(&KEY, &[3, 42, 1, 2, 3, 4, 5, 6, 7, 8]);
+        misaligned.pop();
+
+        assert_eq!(
+            failure_of(&session, encrypted_message(&[8; 24], &[3, 42])),
+            MessageCryptoFailure::WrongKey
+        );
+        assert_eq!(
+            failure_of(&session, misaligned),
+            MessageCryptoFailure::CorruptedTraffic
+        );
+        assert_eq!(
+            failure_of(&session, vec![1, 0, 0, 0, 0, 0]),
+            MessageCryptoFailure::ProtocolMismatch
+        );
+        assert_eq!(
+            failure_of(&self::session(false), encrypted_message(&KEY, &[3, 42])),
+            MessageCryptoFailure::ProtocolMismatch
+        );
+    }
+
+    #[test]
+    fn accepts_previous_key_after_renegotiation() {
+        let mut session = session(true);
+        let new_key = [9; 24];
+        session.renegotiate_session_key(new_key.into(), SEED);
+
+        assert!(BdMessage::new(&session, &mut encrypted_message(&new_key, &[3, 42])).is_ok());
+        assert!(BdMessage::new(&session, &mut encrypted_message(&KEY, &[3, 42])).is_ok());
+        assert_eq!(
+            failure_of(&session, encrypted_message(&[8; 24], &[3, 42])),
+            MessageCryptoFailure::WrongKey
+        );
+    }
 }
@@ -1,7 +1,11 @@
-﻿use crate::messaging::bd_data_type::{BdDataType, BufferDataType};
+﻿use crate::messaging::bd_data_type::{BdDataType, BufferDataType, BIT_MODE_ARRAY_TAG};
+use crate::messaging::bd_serialization::{BdArrayElement, BdDeserialize, WideString};
+use crate::messaging::string_encoding::StringEncoding;
 use crate::messaging::StreamMode;
 use byteorder::{LittleEndian, ReadBytesExt};
-use snafu::{ensure, Snafu};
+use num_traits::FromPrimitive;
+use snafu::{ensure, OptionExt, Snafu};
+use std::borrow::Cow;
 use std::cmp::min;
 use std::error::Error;
 use std::io::{BufRead, Cursor, Read};
@@ -22,20 +26,63 @@ enum BdReaderError {
     },
     #[snafu(display("The message terminated unexpectedly."))]
     UnexpectedEndOfMessage,
+    #[snafu(display("Value is not a valid {enum_name} (value={value})"))]
+    InvalidEnumValue { enum_name: &'static str, value: u8 },
+    #[snafu(display("The {what} exceeds the limit of the reader (size={size} max={max})"))]
+    LimitExceeded {
+        what: &'static str,
+        size: usize,
+        max: usize,
+    },
+}
+
+/// Upper bounds for lengths read from a buffer,
+/// so that clients cannot make the server allocate arbitrary amounts of memory.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BdReaderLimits {
+    pub max_blob_size: usize,
+    /// Also applies to the number of values of structs and the number of entries of maps.
+    pub max_array_elements: usize,
+    /// The maximum length of a string in bytes, excluding its terminator.
+    pub max_string_length: usize,
+}
+
+impl Default for BdReaderLimits {
+    fn default() -> Self {
+        BdReaderLimits {
+            max_blob_size: 0x1000000,
+            max_array_elements: 0x10000,
+            max_string_length: 0x10000,
+        }
+    }
 }
 
-pub struct BdReader {
-    cursor: Cursor<Vec<u8>>,
+/// Reads values from a buffer that is either owned by the reader
+/// or borrowed, e.g. from the frame a message was received in.
+pub struct BdReader<'a> {
+    cursor: Cursor<Cow<'a, [u8]>>,
     bit_offset: usize,
     last_byte: u8,
     has_data_type_cached: bool,
     cached_data_type: BufferDataType,
     mode: StreamMode,
     type_checked: bool,
+    string_encoding: StringEncoding,
+    limits: BdReaderLimits,
+    bit_mode_arrays: bool,
 }
 
-impl BdReader {
+impl<'a> BdReader<'a> {
     pub fn new(buf: Vec<u8>) -> Self {
+        Self::from_cow(Cow::Owned(buf))
+    }
+
+    /// Creates a reader over data it does not own, without copying it.
+    pub fn borrowed(buf: &'a [u8]) -> Self {
+        Self::from_cow(Cow::Borrowed(buf))
+    }
+
+    fn from_cow(buf: Cow<'a, [u8]>) -> Self {
         BdReader {
             cursor: Cursor::new(buf),
             bit_offset: 8,
@@ -44,9 +91,30 @@ impl BdReader {
             cached_data_type: BufferDataType::no_array(BdDataType::NoType),
             mode: StreamMode::ByteMode,
             type_checked: false,
+            string_encoding: StringEncoding::default(),
+            limits: BdReaderLimits::default(),
+            bit_mode_arrays: false,
         }
     }
 
+    pub fn limits(&self) -> BdReaderLimits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: BdReaderLimits) {
+        self.limits = limits;
+    }
+
+    fn ensure_within_limit(
+        what: &'static str,
+        size: usize,
+        max: usize,
+    ) -> Result<usize, Box<dyn Error>> {
+        ensure!(size <= max, LimitExceededSnafu { what, size, max });
+
+        Ok(size)
+    }
+
     pub fn mode(&self) -> StreamMode {
         self.mode
     }
@@ -63,6 +131,21 @@ impl BdReader {
         self.type_checked = type_checked;
     }
 
+    pub fn string_encoding(&self) -> StringEncoding {
+        self.string_encoding
+    }
+
+    /// Changes how strings are read, e.g. for old clients that do not use UTF-8.
+    pub fn set_string_encoding(&mut self, string_encoding: StringEncoding) {
+        self.string_encoding = string_encoding;
+    }
+
+    /// Allows reading arrays that were written in bit mode by a [`BdWriter`] of this server.
+    /// Clients are not known to send them, so only enable this for data the server wrote itself.
+    pub fn set_bit_mode_arrays(&mut self, bit_mode_arrays: bool) {
+        self.bit_mode_arrays = bit_mode_arrays;
+    }
+
     pub fn read_bits(&mut self, buf: &mut [u8], count: usize) -> Result<(), Box<dyn Error>> {
         debug_assert!(buf.len() * 8 >= count, "Buffer does not fit");
 
@@ -166,6 +249,13 @@ impl BdReader {
         let mut temp_buffer = [0u8];
         self.read_bits(&mut temp_buffer, 5)?;
 
+        if self.bit_mode_arrays && temp_buffer[0] == BIT_MODE_ARRAY_TAG {
+            self.read_bits(&mut temp_buffer, 5)?;
+            let element_type = BufferDataType::from_value(temp_buffer[0])?;
+
+            return Ok(BufferDataType::array(element_type.primitive_type));
+        }
+
         BufferDataType::from_value(temp_buffer[0])
     }
 
@@ -178,12 +268,72 @@ impl BdReader {
             return Ok(self.cached_data_type);
         }
 
+        let min_bits = if self.mode == StreamMode::BitMode {
+            5
+        } else {
+            8
+        };
+        if self.remaining_bits() < min_bits {
+            return Ok(BufferDataType::no_array(BdDataType::NoType));
+        }
+
         self.cached_data_type = self.read_data_type()?;
         self.has_data_type_cached = true;
 
         Ok(self.cached_data_type)
     }
 
+    /// The data type of the next value without reading it,
+    /// or `None` if the buffer is not type checked or no further value follows.
+    pub fn peek_data_type(&mut self) -> Result<Option<BufferDataType>, Box<dyn Error>> {
+        let data_type = self.next_data_type()?;
+
+        Ok((!data_type.eq_non_array(BdDataType::NoType)).then_some(data_type))
+    }
+
+    fn remaining_bits(&self) -> usize {
+        let unread_bytes = self.cursor.get_ref().len() - self.cursor.position() as usize;
+        let buffered_bits = match self.mode {
+            StreamMode::BitMode => 8 - self.bit_offset,
+            StreamMode::ByteMode => 0,
+        };
+
+        unread_bytes * 8 + buffered_bits
+    }
+
+    /// The amount of bytes that were read so far.
+    /// A peeked data type counts as read.
+    pub fn position(&self) -> usize {
+        self.cursor.position() as usize
+    }
+
+    /// The amount of whole bytes that were not read yet, in either mode.
+    pub fn remaining(&self) -> usize {
+        self.remaining_bits() / 8
+    }
+
+    /// Skips `count` bytes, e.g. of trailing fields that are not supported.
+    /// A peeked data type counts as read, so the skipped bytes start after it.
+    pub fn skip(&mut self, count: usize) -> Result<(), Box<dyn Error>> {
+        ensure!(self.remaining() >= count, UnexpectedEndOfMessageSnafu {});
+
+        self.has_data_type_cached = false;
+        if self.mode == StreamMode::ByteMode {
+            self.cursor.set_position((self.position() + count) as u64);
+            return Ok(());
+        }
+
+        let mut temp_buffer = [0u8; 64];
+        let mut left = count;
+        while left > 0 {
+            let chunk = left.min(temp_buffer.len());
+            self.read_bits(&mut temp_buffer[..chunk], chunk * 8)?;
+            left -= chunk;
+        }
+
+        Ok(())
+    }
+
     pub fn next_is_bool(&mut self) -> Result<bool, Box<dyn Error>> {
         Ok(self.next_data_type()?.eq_non_array(BdDataType::BoolType))
     }
@@ -254,6 +404,19 @@ impl BdReader {
         Ok(self.next_data_type()?.eq_non_array(BdDataType::BlobType))
     }
 
+    pub fn next_is_wstr(&mut self) -> Result<bool, Box<dyn Error>> {
+        Ok(self.next_data_type()?.eq_non_array(BdDataType::WChar16Type))
+    }
+
+    pub fn next_is_struct(&mut self) -> Result<bool, Box<dyn Error>> {
+        Ok(self.next_data_type()?.eq_non_array(BdDataType::StructType))
+    }
+
+    pub fn next_is_map(&mut self) -> Result<bool, Box<dyn Error>> {
+        Ok(self.next_data_type()?.eq_non_array(BdDataType::MapType))
+    }
+
+    /// Like [`remaining`](Self::remaining), but only allowed in byte mode.
     pub fn remaining_bytes(&self) -> Result<usize, Box<dyn Error>> {
         ensure!(
             self.mode == StreamMode::ByteMode,
@@ -263,7 +426,21 @@ impl BdReader {
             }
         );
 
-        Ok(self.cursor.get_ref().len() - self.cursor.position() as usize)
+        Ok(self.remaining())
+    }
+
+    /// The data that was not read yet.
+    /// Does not advance the reader.
+    pub fn remaining_data(&self) -> Result<&[u8], Box<dyn Error>> {
+        ensure!(
+            self.mode == StreamMode::ByteMode,
+            ModeSnafu {
+                actual_mode: self.mode,
+                expected_mode: StreamMode::ByteMode
+            }
+        );
+
+        Ok(&self.cursor.get_ref()[self.cursor.position() as usize..])
     }
 
     fn read_array_num_elements(&mut self) -> Result<usize, Box<dyn Error>> {
@@ -278,12 +455,75 @@ impl BdReader {
         );
 
         // Clients also just ignore this
-        let _total_size = self.cursor.read_u32::<LittleEndian>()?;
+        let _total_size = u32::from_le_bytes(self.read_array_element()?);
 
         // This however is never type checked
-        let num_elements = self.cursor.read_u32::<LittleEndian>()?;
+        let num_elements = u32::from_le_bytes(self.read_array_element()?);
+
+        Self::ensure_within_limit(
+            "array",
+            num_elements as usize,
+            self.limits.max_array_elements,
+        )
+    }
+
+    /// Reads the untyped bytes of a single array element in either mode.
+    pub(crate) fn read_array_element<const N: usize>(&mut self) -> Result<[u8; N], Box<dyn Error>> {
+        let mut buf = [0u8; N];
+        self.read_bytes(&mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Reads a zero terminated string of at most the configured maximum length.
+    pub(crate) fn read_terminated_string(&mut self) -> Result<String, Box<dyn Error>> {
+        let max = self.limits.max_string_length;
+
+        let mut buf = Vec::new();
+        if self.mode == StreamMode::BitMode {
+            while buf.len() <= max {
+                let [byte] = self.read_array_element()?;
+                buf.push(byte);
+                if byte == 0 {
+                    break;
+                }
+            }
+        } else {
+            (&mut self.cursor)
+                .take(max as u64 + 1)
+                .read_until(0u8, &mut buf)?;
+        }
+
+        if buf.last() == Some(&0) {
+            // Remove the 0 byte
+            buf.pop();
+        }
+        Self::ensure_within_limit("string", buf.len(), max)?;
 
-        Ok(num_elements as usize)
+        self.string_encoding.decode(buf)
+    }
+
+    /// Reads a UTF-16LE string terminated by a zero code unit.
+    /// The maximum string length applies to the number of code units.
+    /// Unpaired surrogates are only replaced with the lossy UTF-8 encoding.
+    pub(crate) fn read_terminated_wide_string(&mut self) -> Result<String, Box<dyn Error>> {
+        let max = self.limits.max_string_length;
+
+        let mut units = Vec::new();
+        while units.len() <= max {
+            let unit = u16::from_le_bytes(self.read_array_element()?);
+            if unit == 0 {
+                break;
+            }
+            units.push(unit);
+        }
+        Self::ensure_within_limit("string", units.len(), max)?;
+
+        if self.string_encoding == StringEncoding::Utf8Lossy {
+            Ok(String::from_utf16_lossy(&units))
+        } else {
+            Ok(String::from_utf16(&units)?)
+        }
     }
 
     pub fn read_bool(&mut self) -> Result<bool, Box<dyn Error>> {
@@ -352,6 +592,17 @@ impl BdReader {
         Ok(u8::from_le_bytes(temp_buffer))
     }
 
+    /// Reads an u8 and converts it to the specified enum.
+    /// Fails when the value does not correspond to any of the enum's variants.
+    pub fn read_enum_u8<T: FromPrimitive>(&mut self) -> Result<T, Box<dyn Error>> {
+        let value = self.read_u8()?;
+
+        Ok(T::from_u8(value).with_context(|| InvalidEnumValueSnafu {
+            enum_name: short_type_name::<T>(),
+            value,
+        })?)
+    }
+
     pub fn read_i16(&mut self) -> Result<i16, Box<dyn Error>> {
         if self.type_checked {
             let actual_type = self.read_data_type()?;
@@ -548,17 +799,11 @@ impl BdReader {
             );
         }
 
-        let mut buf = Vec::new();
-        self.cursor.read_until(0u8, &mut buf)?;
-        if !buf.is_empty() {
-            // Remove the 0 byte
-            buf.remove(buf.len() - 1);
-        }
-
-        Ok(String::from_utf8(buf)?)
+        self.read_terminated_string()
     }
 
-    pub fn read_i8_array(&mut self) -> Result<Vec<i8>, Box<dyn Error>> {
+    /// Reads a wide string, which some console titles use instead of byte strings.
+    pub fn read_wstr(&mut self) -> Result<String, Box<dyn Error>> {
         ensure!(
             self.mode == StreamMode::ByteMode,
             ModeSnafu {
@@ -567,42 +812,29 @@ impl BdReader {
             }
         );
 
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::SignedChar8Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::SignedChar8Type)
-            }
-        );
-
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
-
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_i8()?);
+        if self.type_checked {
+            let actual_type = self.read_data_type()?;
+            ensure!(
+                actual_type.eq_non_array(BdDataType::WChar16Type),
+                UnexpectedDataTypeSnafu {
+                    actual_type,
+                    expected_type: BufferDataType::no_array(BdDataType::WChar16Type)
+                }
+            );
         }
 
-        Ok(result)
+        self.read_terminated_wide_string()
     }
 
-    pub fn read_u8_array(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
+    /// Reads a typed array of any element type.
+    pub fn read_array<T: BdArrayElement>(&mut self) -> Result<Vec<T>, Box<dyn Error>> {
         // Arrays are always type checked
         let actual_type = self.read_data_type()?;
         ensure!(
-            actual_type.eq_array(BdDataType::UnsignedChar8Type),
+            actual_type.eq_array(T::DATA_TYPE),
             UnexpectedDataTypeSnafu {
                 actual_type,
-                expected_type: BufferDataType::array(BdDataType::UnsignedChar8Type)
+                expected_type: BufferDataType::array(T::DATA_TYPE)
             }
         );
 
@@ -610,187 +842,65 @@ impl BdReader {
         let mut result = Vec::with_capacity(num_elements);
 
         for _ in 0..num_elements {
-            result.push(self.cursor.read_u8()?);
+            result.push(T::read_element(self)?);
         }
 
         Ok(result)
     }
 
-    pub fn read_i16_array(&mut self) -> Result<Vec<i16>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::SignedInteger16Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::SignedInteger16Type)
-            }
-        );
-
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
+    pub fn read_i8_array(&mut self) -> Result<Vec<i8>, Box<dyn Error>> {
+        self.read_array()
+    }
 
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_i16::<LittleEndian>()?);
-        }
+    pub fn read_u8_array(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.read_array()
+    }
 
-        Ok(result)
+    pub fn read_i16_array(&mut self) -> Result<Vec<i16>, Box<dyn Error>> {
+        self.read_array()
     }
 
     pub fn read_u16_array(&mut self) -> Result<Vec<u16>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::UnsignedInteger16Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::UnsignedInteger16Type)
-            }
-        );
-
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
-
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_u16::<LittleEndian>()?);
-        }
-
-        Ok(result)
+        self.read_array()
     }
 
     pub fn read_i32_array(&mut self) -> Result<Vec<i32>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::SignedInteger32Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::SignedInteger32Type)
-            }
-        );
-
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
-
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_i32::<LittleEndian>()?);
-        }
-
-        Ok(result)
+        self.read_array()
     }
 
     pub fn read_u32_array(&mut self) -> Result<Vec<u32>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::UnsignedInteger32Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::UnsignedInteger32Type)
-            }
-        );
-
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
-
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_u32::<LittleEndian>()?);
-        }
-
-        Ok(result)
+        self.read_array()
     }
 
     pub fn read_i64_array(&mut self) -> Result<Vec<i64>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::SignedInteger64Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::SignedInteger64Type)
-            }
-        );
-
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
-
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_i64::<LittleEndian>()?);
-        }
-
-        Ok(result)
+        self.read_array()
     }
 
     pub fn read_u64_array(&mut self) -> Result<Vec<u64>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
+        self.read_array()
+    }
 
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::UnsignedInteger64Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::UnsignedInteger64Type)
-            }
-        );
+    pub fn read_f32_array(&mut self) -> Result<Vec<f32>, Box<dyn Error>> {
+        self.read_array()
+    }
 
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
+    pub fn read_f64_array(&mut self) -> Result<Vec<f64>, Box<dyn Error>> {
+        self.read_array()
+    }
 
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_u64::<LittleEndian>()?);
-        }
+    pub fn read_str_array(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        self.read_array()
+    }
 
-        Ok(result)
+    pub fn read_wstr_array(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self
+            .read_array::<WideString>()?
+            .into_iter()
+            .map(|value| value.0)
+            .collect())
     }
 
-    pub fn read_f32_array(&mut self) -> Result<Vec<f32>, Box<dyn Error>> {
+    pub fn read_blob(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
         ensure!(
             self.mode == StreamMode::ByteMode,
             ModeSnafu {
@@ -799,125 +909,95 @@ impl BdReader {
             }
         );
 
-        // Arrays are always type checked
-        let actual_type = self.read_data_type()?;
-        ensure!(
-            actual_type.eq_array(BdDataType::Float32Type),
-            UnexpectedDataTypeSnafu {
-                actual_type,
-                expected_type: BufferDataType::array(BdDataType::Float32Type)
-            }
-        );
-
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
-
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_f32::<LittleEndian>()?);
+        if self.type_checked {
+            let actual_type = self.read_data_type()?;
+            ensure!(
+                actual_type.eq_non_array(BdDataType::BlobType),
+                UnexpectedDataTypeSnafu {
+                    actual_type,
+                    expected_type: BufferDataType::no_array(BdDataType::BlobType)
+                }
+            );
         }
 
-        Ok(result)
-    }
-
-    pub fn read_f64_array(&mut self) -> Result<Vec<f64>, Box<dyn Error>> {
+        let blob_size = Self::ensure_within_limit(
+            "blob",
+            self.read_u32()? as usize,
+            self.limits.max_blob_size,
+        )?;
+        let mut blob = vec![0; blob_size];
         ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
+            self.cursor.read(&mut blob[0..blob_size])? == blob_size,
+            UnexpectedEndOfMessageSnafu {}
         );
 
-        // Arrays are always type checked
+        Ok(blob)
+    }
+
+    /// Reads the start of a struct and returns the number of values that follow.
+    /// The data type of a struct is always present, even if the buffer is not type checked.
+    pub fn read_struct_header(&mut self) -> Result<usize, Box<dyn Error>> {
         let actual_type = self.read_data_type()?;
         ensure!(
-            actual_type.eq_array(BdDataType::Float64Type),
+            actual_type.eq_non_array(BdDataType::StructType),
             UnexpectedDataTypeSnafu {
                 actual_type,
-                expected_type: BufferDataType::array(BdDataType::Float64Type)
+                expected_type: BufferDataType::no_array(BdDataType::StructType)
             }
         );
 
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
-
-        for _ in 0..num_elements {
-            result.push(self.cursor.read_f64::<LittleEndian>()?);
-        }
-
-        Ok(result)
+        Self::ensure_within_limit(
+            "struct",
+            self.read_u32()? as usize,
+            self.limits.max_array_elements,
+        )
     }
 
-    pub fn read_str_array(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
+    /// Reads the start of a map and returns the number of key value pairs that follow.
+    /// The data type of a map is always present, even if the buffer is not type checked.
+    pub fn read_map_header(&mut self) -> Result<usize, Box<dyn Error>> {
         let actual_type = self.read_data_type()?;
         ensure!(
-            actual_type.eq_array(BdDataType::SignedChar8StringType),
+            actual_type.eq_non_array(BdDataType::MapType),
             UnexpectedDataTypeSnafu {
                 actual_type,
-                expected_type: BufferDataType::array(BdDataType::SignedChar8StringType)
+                expected_type: BufferDataType::no_array(BdDataType::MapType)
             }
         );
 
-        let num_elements = self.read_array_num_elements()?;
-        let mut result = Vec::with_capacity(num_elements);
+        Self::ensure_within_limit(
+            "map",
+            self.read_u32()? as usize,
+            self.limits.max_array_elements,
+        )
+    }
 
-        for _ in 0..num_elements {
-            let mut buf = Vec::new();
-            self.cursor.read_until(0u8, &mut buf)?;
-            if !buf.is_empty() {
-                // Remove the 0 byte
-                buf.remove(buf.len() - 1);
-            }
+    pub fn read_map<K: BdDeserialize, V: BdDeserialize>(
+        &mut self,
+    ) -> Result<Vec<(K, V)>, Box<dyn Error>> {
+        let num_entries = self.read_map_header()?;
 
-            result.push(String::from_utf8(buf)?);
+        let mut entries = Vec::new();
+        for _ in 0..num_entries {
+            let key = K::deserialize(self)?;
+            let value = V::deserialize(self)?;
+            entries.push((key, value));
         }
 
-        Ok(result)
+        Ok(entries)
     }
+}
 
-    pub fn read_blob(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        if self.type_checked {
-            let actual_type = self.read_data_type()?;
-            ensure!(
-                actual_type.eq_non_array(BdDataType::BlobType),
-                UnexpectedDataTypeSnafu {
-                    actual_type,
-                    expected_type: BufferDataType::no_array(BdDataType::BlobType)
-                }
-            );
-        }
-
-        let blob_size = self.read_u32()? as usize;
-        let mut blob = vec![0; blob_size];
-        ensure!(
-            self.cursor.read(&mut blob[0..blob_size])? == blob_size,
-            UnexpectedEndOfMessageSnafu {}
-        );
+fn short_type_name<T>() -> &'static str {
+    let type_name = std::any::type_name::<T>();
 
-        Ok(blob)
-    }
+    type_name.rsplit("::").next().unwrap_or(type_name)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::messaging::bd_writer::BdWriter;
 
     #[test]
     fn ensure_can_read_bits() {
@@ -1153,4 +1233,237 @@ mod tests {
 
         assert!(reader.read_bool().is_err());
     }
+
+    #[derive(Debug, Eq, PartialEq, FromPrimitive)]
+    enum TestEnum {
+        First = 1,
+        Second = 2,
+    }
+
+    #[test]
+    fn ensure_can_read_enum_u8_with_type_check_in_byte_mode() {
+        let mut reader = BdReader::new(vec![0x03, 0x02, 0x03, 0x01]);
+        reader.set_mode(StreamMode::ByteMode);
+        reader.set_type_checked(true);
+
+        assert_eq!(TestEnum::Second, reader.read_enum_u8().unwrap());
+        assert_eq!(TestEnum::First, reader.read_enum_u8().unwrap());
+    }
+
+    #[test]
+    fn ensure_errors_when_reading_enum_u8_with_unknown_value() {
+        let mut reader = BdReader::new(vec![0x05]);
+        reader.set_mode(StreamMode::ByteMode);
+
+        let error = reader.read_enum_u8::<TestEnum>().unwrap_err();
+        assert_eq!("Value is not a valid TestEnum (value=5)", error.to_string());
+    }
+
+    fn write_nested(mode: StreamMode, type_checked: bool) -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.set_mode(mode);
+            writer.set_type_checked(type_checked);
+
+            writer.write_struct_header(3).unwrap();
+            writer.write_u32(42).unwrap();
+            writer.write_struct(&[&true, &7u64]).unwrap();
+            writer
+                .write_map([(&1u32, &-1i64), (&2u32, &-2i64)].into_iter())
+                .unwrap();
+            writer.write_u16(0xBEEF).unwrap();
+        }
+
+        buf
+    }
+
+    #[test]
+    fn reads_nested_structs_and_maps() {
+        for mode in [StreamMode::ByteMode, StreamMode::BitMode] {
+            for type_checked in [false, true] {
+                let mut reader = BdReader::new(write_nested(mode, type_checked));
+                reader.set_mode(mode);
+                reader.set_type_checked(type_checked);
+
+                assert_eq!(reader.next_is_struct().unwrap(), type_checked);
+                assert_eq!(reader.read_struct_header().unwrap(), 3);
+                assert_eq!(reader.read_u32().unwrap(), 42);
+                assert_eq!(reader.read_struct_header().unwrap(), 2);
+                assert!(reader.read_bool().unwrap());
+                assert_eq!(reader.read_u64().unwrap(), 7);
+                assert_eq!(
+                    reader.read_map::<u32, i64>().unwrap(),
+                    vec![(1, -1), (2, -2)]
+                );
+                assert_eq!(reader.read_u16().unwrap(), 0xBEEF);
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_to_read_other_types_as_struct() {
+        let mut buf = Vec::new();
+        BdWriter::new(&mut buf).write_map_header(0).unwrap();
+
+        let mut reader = BdReader::new(buf);
+        assert!(reader.read_struct_header().is_err());
+    }
+
+    #[test]
+    fn enforces_limits_on_lengths() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.write_blob(&[0; 16]).unwrap();
+            writer.write_str("too long").unwrap();
+            writer.write_u64_array(&[1, 2, 3]).unwrap();
+        }
+
+        let limits = BdReaderLimits {
+            max_blob_size: 8,
+            max_array_elements: 2,
+            max_string_length: 4,
+        };
+
+        let mut reader = BdReader::new(buf.clone());
+        reader.set_limits(limits);
+        assert!(reader.read_blob().is_err());
+
+        let mut reader = BdReader::new(buf[20..].to_vec());
+        reader.set_limits(limits);
+        assert!(reader.read_str().is_err());
+
+        let mut reader = BdReader::new(buf[29..].to_vec());
+        reader.set_limits(limits);
+        assert!(reader.read_u64_array().is_err());
+
+        let mut reader = BdReader::new(buf);
+        assert_eq!(reader.read_blob().unwrap(), vec![0; 16]);
+        assert_eq!(reader.read_str().unwrap(), "too long");
+        assert_eq!(reader.read_u64_array().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn reads_arrays_in_bit_mode() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.set_mode(StreamMode::BitMode);
+            writer.set_type_checked(true);
+            writer.set_bit_mode_arrays(true);
+
+            writer.write_bool(true).unwrap();
+            writer.write_u16_array(&[1, 0xFFFF]).unwrap();
+            writer.write_str_array(&["a", "bc"]).unwrap();
+            writer.write_i64_array(&[-5]).unwrap();
+        }
+
+        let mut reader = BdReader::borrowed(&buf);
+        reader.set_mode(StreamMode::BitMode);
+        reader.set_type_checked(true);
+        assert!(reader.read_bool().unwrap());
+        assert!(reader.read_u16_array().is_err());
+
+        let mut reader = BdReader::new(buf);
+        reader.set_mode(StreamMode::BitMode);
+        reader.set_type_checked(true);
+        reader.set_bit_mode_arrays(true);
+
+        assert!(reader.read_bool().unwrap());
+        assert_eq!(reader.read_u16_array().unwrap(), vec![1, 0xFFFF]);
+        assert_eq!(reader.read_str_array().unwrap(), vec!["a", "bc"]);
+        assert_eq!(reader.read_i64_array().unwrap(), vec![-5]);
+    }
+
+    #[test]
+    fn arrays_are_not_written_in_bit_mode_by_default() {
+        let mut buf = Vec::new();
+        let mut writer = BdWriter::new(&mut buf);
+        writer.set_mode(StreamMode::BitMode);
+        writer.set_type_checked(true);
+
+        assert!(writer.write_u16_array(&[1]).is_err());
+    }
+
+    #[test]
+    fn peeks_and_skips_optional_trailing_fields() {
+        for mode in [StreamMode::ByteMode, StreamMode::BitMode] {
+            let mut buf = Vec::new();
+            {
+                let mut writer = BdWriter::new(&mut buf);
+                writer.set_mode(mode);
+                writer.set_type_checked(true);
+                writer.write_u32(1).unwrap();
+                writer.write_u64(2).unwrap();
+            }
+
+            let mut reader = BdReader::new(buf);
+            reader.set_mode(mode);
+            reader.set_type_checked(true);
+
+            assert_eq!(reader.read_u32().unwrap(), 1);
+            let remaining = reader.remaining();
+            assert!(reader
+                .peek_data_type()
+                .unwrap()
+                .unwrap()
+                .eq_non_array(BdDataType::UnsignedInteger64Type));
+            assert!(reader.next_is_u64().unwrap());
+
+            reader.skip(8).unwrap();
+            assert!(reader.remaining() < remaining);
+            assert!(reader.peek_data_type().unwrap().is_none());
+            assert!(!reader.next_is_u32().unwrap());
+            assert!(reader.skip(1).is_err());
+        }
+    }
+
+    #[test]
+    fn reads_generic_arrays_of_any_element_type() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.write_array(&[1.5f32, -2.0]).unwrap();
+            writer
+                .write_array(&["a".to_string(), "bc".to_string()])
+                .unwrap();
+            writer.write_u32_array(&[7]).unwrap();
+        }
+
+        let mut reader = BdReader::new(buf);
+        assert_eq!(reader.read_f32_array().unwrap(), vec![1.5, -2.0]);
+        assert_eq!(reader.read_array::<String>().unwrap(), vec!["a", "bc"]);
+        assert!(reader.read_array::<u64>().is_err());
+    }
+
+    #[test]
+    fn reads_wide_strings_and_arrays() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.set_type_checked(true);
+            writer.write_wstr("Jörg 日本").unwrap();
+            writer.write_wstr_array(&["a", "😀"]).unwrap();
+        }
+
+        let mut reader = BdReader::new(buf);
+        reader.set_type_checked(true);
+        assert!(reader.next_is_wstr().unwrap());
+        assert_eq!(reader.read_wstr().unwrap(), "Jörg 日本");
+        assert_eq!(reader.read_wstr_array().unwrap(), vec!["a", "😀"]);
+        assert!(reader.read_wstr().is_err());
+    }
+
+    #[test]
+    fn invalid_wide_strings_only_fail_in_strict_mode() {
+        // An unpaired surrogate followed by the terminator
+        let buf = vec![0x00, 0xD8, 0x00, 0x00];
+
+        assert!(BdReader::new(buf.clone()).read_wstr().is_err());
+
+        let mut reader = BdReader::new(buf);
+        reader.set_string_encoding(StringEncoding::Utf8Lossy);
+        assert_eq!(reader.read_wstr().unwrap(), "\u{FFFD}");
+    }
 }
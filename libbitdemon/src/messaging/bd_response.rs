@@ -1,12 +1,14 @@
-﻿use crate::crypto::{encrypt_buffer_in_place, generate_iv_from_seed, generate_iv_seed};
+﻿use crate::crypto::generate_iv_seed;
 use crate::networking::bd_session::BdSession;
 use byteorder::{LittleEndian, WriteBytesExt};
 use std::error::Error;
-use std::io::Write;
 
+#[derive(Clone)]
 pub struct BdResponse {
     should_encrypt: bool,
-    data: Vec<u8>,
+    no_reply: bool,
+    /// The payloads of all frames of the response, sent in order.
+    payloads: Vec<Vec<u8>>,
 }
 
 pub trait ResponseCreator {
@@ -14,47 +16,100 @@ pub trait ResponseCreator {
 }
 
 const RESPONSE_SIGNATURE: u32 = 0xDEADBEEF;
+const RESPONSE_SIGNATURE_LEN: usize = 4;
 
 impl BdResponse {
     pub fn unencrypted(data: Vec<u8>) -> Self {
         BdResponse {
             should_encrypt: false,
-            data,
+            no_reply: false,
+            payloads: vec![data],
         }
     }
     pub fn encrypted_if_available(data: Vec<u8>) -> Self {
         BdResponse {
             should_encrypt: true,
-            data,
+            no_reply: false,
+            payloads: vec![data],
         }
     }
 
+    /// Creates a response that is sent as one frame per payload,
+    /// for answers that are too large for a single frame.
+    pub fn chunked_encrypted_if_available(payloads: Vec<Vec<u8>>) -> Self {
+        BdResponse {
+            should_encrypt: true,
+            no_reply: false,
+            payloads,
+        }
+    }
+
+    /// Creates a response for messages that the client does not expect an answer to.
+    /// Sending it does not write anything to the session.
+    pub fn no_reply() -> Self {
+        BdResponse {
+            should_encrypt: false,
+            no_reply: true,
+            payloads: Vec::new(),
+        }
+    }
+
+    pub fn is_no_reply(&self) -> bool {
+        self.no_reply
+    }
+
+    /// The payloads of the frames the response is sent as.
+    #[cfg(test)]
+    pub(crate) fn payloads(&self) -> &[Vec<u8>] {
+        &self.payloads
+    }
+
     pub fn send(&mut self, session: &mut BdSession) -> Result<(), Box<dyn Error>> {
-        if self.should_encrypt && session.authentication().is_some() {
+        if self.no_reply {
+            return Ok(());
+        }
+
+        for payload in &self.payloads {
+            self.send_frame(session, payload)?;
+        }
+
+        Ok(())
+    }
+
+    fn send_frame(&self, session: &mut BdSession, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let session_key = session.session_key().filter(|_| !session.plaintext());
+        if let Some(session_key) = session_key.filter(|_| self.should_encrypt) {
             let seed = generate_iv_seed();
-            let iv = generate_iv_from_seed(seed);
+            let cipher = session.session_cipher();
 
-            self.data
-                .splice(0..0, RESPONSE_SIGNATURE.to_le_bytes().iter().cloned());
-            encrypt_buffer_in_place(
-                &mut self.data,
-                &session.authentication().unwrap().session_key,
-                &iv,
-            );
+            let encrypted_len =
+                (RESPONSE_SIGNATURE_LEN + data.len()).next_multiple_of(cipher.block_size());
 
             // Written length minus length field itself
             // 1 byte (encrypted) + 4 byte (seed)
-            let message_length = self.data.len() + 5;
-            session.write_u32::<LittleEndian>(message_length as u32)?;
-            session.write_u8(1u8)?; // Encrypted
-            session.write_u32::<LittleEndian>(seed)?;
-            session.write_all(self.data.as_slice())?;
+            let message_length = encrypted_len + 5;
+            let mut frame = Vec::with_capacity(message_length + 4);
+            frame.write_u32::<LittleEndian>(message_length as u32)?;
+            frame.write_u8(cipher.frame_flag())?; // Encrypted
+            frame.write_u32::<LittleEndian>(seed)?;
+
+            // The data is encrypted inside the frame, so it does not need to be copied again
+            let encrypted_start = frame.len();
+            frame.write_u32::<LittleEndian>(RESPONSE_SIGNATURE)?;
+            frame.extend_from_slice(data);
+            frame.resize(encrypted_start + encrypted_len, 0);
+            cipher.encrypt_in_place(&mut frame[encrypted_start..], &session_key, seed)?;
+
+            session.write_frame(&frame)?;
         } else {
             // Written length minus length field itself
-            let message_length = self.data.len() + 1;
-            session.write_u32::<LittleEndian>(message_length as u32)?;
-            session.write_u8(0u8)?; // Encrypted
-            session.write_all(self.data.as_slice())?;
+            let message_length = data.len() + 1;
+            let mut frame = Vec::with_capacity(message_length + 4);
+            frame.write_u32::<LittleEndian>(message_length as u32)?;
+            frame.write_u8(0u8)?; // Encrypted
+            frame.extend_from_slice(data);
+
+            session.write_frame(&frame)?;
         }
 
         Ok(())
@@ -1,7 +1,10 @@
-﻿use crate::messaging::bd_reader::BdReader;
+﻿use crate::messaging::bd_data_type::BdDataType;
+use crate::messaging::bd_reader::BdReader;
 use crate::messaging::bd_writer::BdWriter;
 use std::error::Error;
 
+pub use bitdemon_derive::{BdDeserialize, BdSerialize};
+
 pub trait BdSerialize {
     fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>>;
 }
@@ -11,3 +14,224 @@ pub trait BdDeserialize {
     where
         Self: Sized;
 }
+
+/// Writes a collection as a typed array instead of element by element.
+/// Used by `#[bd(array)]` fields of derived implementations.
+pub trait BdArraySerialize {
+    fn serialize_array(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>>;
+}
+
+/// Reads a collection from a typed array.
+/// Used by `#[bd(array)]` fields of derived implementations.
+pub trait BdArrayDeserialize {
+    fn deserialize_array(reader: &mut BdReader) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized;
+}
+
+/// A type that can be an element of a typed array.
+/// The elements follow the array header without data types of their own.
+pub trait BdArrayElement: BdSerialize + BdDeserialize + Sized {
+    const DATA_TYPE: BdDataType;
+
+    fn write_element(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>>;
+
+    fn read_element(reader: &mut BdReader) -> Result<Self, Box<dyn Error>>;
+}
+
+macro_rules! impl_primitive {
+    ($ty:ty, $write:ident, $read:ident) => {
+        impl BdSerialize for $ty {
+            fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+                writer.$write(*self)
+            }
+        }
+
+        impl BdDeserialize for $ty {
+            fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+                reader.$read()
+            }
+        }
+    };
+}
+
+impl_primitive!(bool, write_bool, read_bool);
+impl_primitive!(i8, write_i8, read_i8);
+impl_primitive!(u8, write_u8, read_u8);
+impl_primitive!(i16, write_i16, read_i16);
+impl_primitive!(u16, write_u16, read_u16);
+impl_primitive!(i32, write_i32, read_i32);
+impl_primitive!(u32, write_u32, read_u32);
+impl_primitive!(i64, write_i64, read_i64);
+impl_primitive!(u64, write_u64, read_u64);
+impl_primitive!(f32, write_f32, read_f32);
+impl_primitive!(f64, write_f64, read_f64);
+
+impl BdSerialize for String {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_str(self)
+    }
+}
+
+impl BdDeserialize for String {
+    fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        reader.read_str()
+    }
+}
+
+macro_rules! impl_array_element {
+    ($ty:ty, $data_type:ident) => {
+        impl BdArrayElement for $ty {
+            const DATA_TYPE: BdDataType = BdDataType::$data_type;
+
+            fn write_element(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+                writer.write_bytes(&self.to_le_bytes())
+            }
+
+            fn read_element(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+                Ok(<$ty>::from_le_bytes(reader.read_array_element()?))
+            }
+        }
+    };
+}
+
+impl_array_element!(i8, SignedChar8Type);
+impl_array_element!(u8, UnsignedChar8Type);
+impl_array_element!(i16, SignedInteger16Type);
+impl_array_element!(u16, UnsignedInteger16Type);
+impl_array_element!(i32, SignedInteger32Type);
+impl_array_element!(u32, UnsignedInteger32Type);
+impl_array_element!(i64, SignedInteger64Type);
+impl_array_element!(u64, UnsignedInteger64Type);
+impl_array_element!(f32, Float32Type);
+impl_array_element!(f64, Float64Type);
+
+impl BdArrayElement for String {
+    const DATA_TYPE: BdDataType = BdDataType::SignedChar8StringType;
+
+    fn write_element(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_terminated_str(self)
+    }
+
+    fn read_element(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        reader.read_terminated_string()
+    }
+}
+
+/// A string that is transferred as UTF-16 instead of bytes.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct WideString(pub String);
+
+impl From<String> for WideString {
+    fn from(value: String) -> Self {
+        WideString(value)
+    }
+}
+
+impl From<WideString> for String {
+    fn from(value: WideString) -> Self {
+        value.0
+    }
+}
+
+impl BdSerialize for WideString {
+    fn serialize(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_wstr(&self.0)
+    }
+}
+
+impl BdDeserialize for WideString {
+    fn deserialize(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        Ok(WideString(reader.read_wstr()?))
+    }
+}
+
+impl BdArrayElement for WideString {
+    const DATA_TYPE: BdDataType = BdDataType::WChar16Type;
+
+    fn write_element(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_terminated_wide_str(&self.0)
+    }
+
+    fn read_element(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        Ok(WideString(reader.read_terminated_wide_string()?))
+    }
+}
+
+impl<T: BdArrayElement> BdArraySerialize for Vec<T> {
+    fn serialize_array(&self, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+        writer.write_array(self)
+    }
+}
+
+impl<T: BdArrayElement> BdArrayDeserialize for Vec<T> {
+    fn deserialize_array(reader: &mut BdReader) -> Result<Self, Box<dyn Error>> {
+        reader.read_array()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod seconds {
+        use super::*;
+
+        pub fn serialize(value: &i64, writer: &mut BdWriter) -> Result<(), Box<dyn Error>> {
+            writer.write_u32(*value as u32)
+        }
+
+        pub fn deserialize(reader: &mut BdReader) -> Result<i64, Box<dyn Error>> {
+            Ok(reader.read_u32()? as i64)
+        }
+    }
+
+    #[derive(Debug, PartialEq, BdSerialize, BdDeserialize)]
+    struct Derived {
+        id: u64,
+        name: String,
+        #[bd(blob)]
+        data: Vec<u8>,
+        #[bd(array)]
+        members: Vec<u64>,
+        #[bd(unchecked)]
+        flags: u16,
+        #[bd(with = "seconds")]
+        created_at: i64,
+        #[bd(skip)]
+        cached: Option<u32>,
+    }
+
+    #[test]
+    fn derived_implementations_read_what_they_wrote() {
+        let value = Derived {
+            id: 42,
+            name: "name".to_string(),
+            data: vec![1, 2, 3],
+            members: vec![4, 5],
+            flags: 0xBEEF,
+            created_at: 1234,
+            cached: Some(7),
+        };
+
+        let mut buf = Vec::new();
+        {
+            let mut writer = BdWriter::new(&mut buf);
+            writer.set_type_checked(true);
+            value.serialize(&mut writer).unwrap();
+            assert!(writer.type_checked());
+        }
+
+        let mut reader = BdReader::new(buf);
+        reader.set_type_checked(true);
+        let read = Derived::deserialize(&mut reader).unwrap();
+
+        assert_eq!(
+            read,
+            Derived {
+                cached: None,
+                ..value
+            }
+        );
+    }
+}
@@ -1,10 +1,12 @@
-use crate::messaging::bd_data_type::{BdDataType, BufferDataType};
+use crate::messaging::bd_data_type::{BdDataType, BufferDataType, BIT_MODE_ARRAY_TAG};
+use crate::messaging::bd_serialization::{BdArrayElement, BdSerialize};
+use crate::messaging::string_encoding::StringEncoding;
 use crate::messaging::StreamMode;
 use byteorder::{LittleEndian, WriteBytesExt};
 use snafu::{ensure, Snafu};
 use std::cmp::Ordering;
 use std::error::Error;
-use std::io::{Cursor, Write};
+use std::io::Write;
 
 #[derive(Debug, Snafu)]
 enum BdWriterError {
@@ -15,25 +17,93 @@ enum BdWriterError {
     },
 }
 
+/// The buffer a [`BdWriter`] appends to.
+/// Either borrowed from the caller or owned by the writer itself.
+enum WriterBuffer<'a> {
+    Borrowed(&'a mut Vec<u8>),
+    Owned(Vec<u8>),
+}
+
+impl WriterBuffer<'_> {
+    fn vec(&self) -> &Vec<u8> {
+        match self {
+            WriterBuffer::Borrowed(buf) => buf,
+            WriterBuffer::Owned(buf) => buf,
+        }
+    }
+
+    fn vec_mut(&mut self) -> &mut Vec<u8> {
+        match self {
+            WriterBuffer::Borrowed(buf) => buf,
+            WriterBuffer::Owned(buf) => buf,
+        }
+    }
+}
+
+impl Write for WriterBuffer<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.vec_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.vec_mut().extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 pub struct BdWriter<'a> {
-    cursor: Cursor<&'a mut Vec<u8>>,
+    buf: WriterBuffer<'a>,
     bit_offset: usize,
     last_byte: u8,
     mode: StreamMode,
     type_checked: bool,
+    string_encoding: StringEncoding,
+    bit_mode_arrays: bool,
 }
 
 impl<'a> BdWriter<'a> {
+    /// Creates a writer appending to `buf`.
+    /// The last partially written byte is flushed when the writer is dropped.
     pub fn new(buf: &'a mut Vec<u8>) -> Self {
+        Self::from_buffer(WriterBuffer::Borrowed(buf))
+    }
+
+    fn from_buffer(buf: WriterBuffer<'a>) -> Self {
         BdWriter {
-            cursor: Cursor::new(buf),
+            buf,
             bit_offset: 8,
             last_byte: 0,
             mode: StreamMode::ByteMode,
             type_checked: false,
+            string_encoding: StringEncoding::default(),
+            bit_mode_arrays: false,
         }
     }
 
+    /// Reserves space for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.buf.vec_mut().reserve(additional);
+    }
+
+    /// The amount of bytes the buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.vec().capacity()
+    }
+
+    /// The amount of bytes written so far, including a partially written byte in bit mode.
+    pub fn len(&self) -> usize {
+        self.buf.vec().len() + usize::from(self.bit_offset < 8)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn mode(&self) -> StreamMode {
         self.mode
     }
@@ -50,12 +120,27 @@ impl<'a> BdWriter<'a> {
         self.type_checked = type_checked;
     }
 
+    pub fn string_encoding(&self) -> StringEncoding {
+        self.string_encoding
+    }
+
+    /// Changes how strings are written, e.g. for old clients that do not use UTF-8.
+    pub fn set_string_encoding(&mut self, string_encoding: StringEncoding) {
+        self.string_encoding = string_encoding;
+    }
+
+    /// Allows writing arrays in bit mode with a type tag of this server.
+    /// Clients are not known to understand it, so only enable this for data the server reads back itself.
+    pub fn set_bit_mode_arrays(&mut self, bit_mode_arrays: bool) {
+        self.bit_mode_arrays = bit_mode_arrays;
+    }
+
     pub fn flush(&mut self) -> Result<(), Box<dyn Error>> {
         if self.bit_offset >= 8 {
             return Ok(());
         }
 
-        self.cursor.write_u8(self.last_byte)?;
+        self.buf.write_u8(self.last_byte)?;
         self.bit_offset = 8;
 
         Ok(())
@@ -94,12 +179,12 @@ impl<'a> BdWriter<'a> {
                 match (self.bit_offset + in_bits).cmp(&8) {
                     Ordering::Greater => {
                         let used_bits = 8 - self.bit_offset;
-                        self.cursor.write_u8(self.last_byte)?;
+                        self.buf.write_u8(self.last_byte)?;
                         self.bit_offset = (self.bit_offset as i64 + (in_bits as i64 - 8)) as usize;
                         self.last_byte = in_byte >> used_bits;
                     }
                     Ordering::Equal => {
-                        self.cursor.write_u8(self.last_byte)?;
+                        self.buf.write_u8(self.last_byte)?;
                         self.last_byte = 0;
                         self.bit_offset = 8;
                     }
@@ -108,7 +193,7 @@ impl<'a> BdWriter<'a> {
                     }
                 }
             } else if in_bits == 8 {
-                self.cursor.write_u8(in_byte)?;
+                self.buf.write_u8(in_byte)?;
             } else {
                 self.last_byte = in_byte;
                 self.bit_offset = in_bits;
@@ -124,7 +209,7 @@ impl<'a> BdWriter<'a> {
         if self.mode == StreamMode::BitMode {
             self.write_bits(buffer, buffer.len() * 8)
         } else {
-            self.cursor.write_all(buffer)?;
+            self.buf.write_all(buffer)?;
             Ok(())
         }
     }
@@ -144,12 +229,24 @@ impl<'a> BdWriter<'a> {
     }
 
     fn write_data_type(&mut self, buffer_data_type: BufferDataType) -> Result<(), Box<dyn Error>> {
-        let value = buffer_data_type.to_value();
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_u8(value)?;
+            self.buf.write_u8(buffer_data_type.to_value())?;
             Ok(())
+        } else if buffer_data_type.is_array {
+            ensure!(
+                self.bit_mode_arrays,
+                ModeSnafu {
+                    actual_mode: self.mode,
+                    expected_mode: StreamMode::ByteMode
+                }
+            );
+            self.write_bits(&[BIT_MODE_ARRAY_TAG], 5)?;
+            self.write_bits(
+                &[BufferDataType::no_array(buffer_data_type.primitive_type).to_value()],
+                5,
+            )
         } else {
-            self.write_bits(&[value], 5)
+            self.write_bits(&[buffer_data_type.to_value()], 5)
         }
     }
 
@@ -158,10 +255,10 @@ impl<'a> BdWriter<'a> {
         self.write_data_type(BufferDataType::no_array(BdDataType::UnsignedInteger32Type))?;
 
         // TotalSize: Clients just ignore this
-        self.cursor.write_u32::<LittleEndian>(0)?;
+        self.write_bytes(&0u32.to_le_bytes())?;
 
         // This however is never type checked
-        self.cursor.write_u32::<LittleEndian>(num_elements as u32)?;
+        self.write_bytes(&(num_elements as u32).to_le_bytes())?;
 
         Ok(())
     }
@@ -172,7 +269,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_u8(if value { 1 } else { 0 })?;
+            self.buf.write_u8(if value { 1 } else { 0 })?;
             Ok(())
         } else {
             self.write_bits(if value { &[0x01] } else { &[0x00] }, 1)
@@ -185,7 +282,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_i8(value)?;
+            self.buf.write_i8(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), i8::BITS as usize)
@@ -198,7 +295,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_u8(value)?;
+            self.buf.write_u8(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), u8::BITS as usize)
@@ -211,7 +308,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_i16::<LittleEndian>(value)?;
+            self.buf.write_i16::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), i16::BITS as usize)
@@ -224,7 +321,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_u16::<LittleEndian>(value)?;
+            self.buf.write_u16::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), u16::BITS as usize)
@@ -237,7 +334,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_i32::<LittleEndian>(value)?;
+            self.buf.write_i32::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), i32::BITS as usize)
@@ -250,7 +347,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_u32::<LittleEndian>(value)?;
+            self.buf.write_u32::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), u32::BITS as usize)
@@ -263,7 +360,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_i64::<LittleEndian>(value)?;
+            self.buf.write_i64::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), i64::BITS as usize)
@@ -276,7 +373,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_u64::<LittleEndian>(value)?;
+            self.buf.write_u64::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), u64::BITS as usize)
@@ -289,7 +386,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_f32::<LittleEndian>(value)?;
+            self.buf.write_f32::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), 32)
@@ -302,7 +399,7 @@ impl<'a> BdWriter<'a> {
         }
 
         if self.mode == StreamMode::ByteMode {
-            self.cursor.write_f64::<LittleEndian>(value)?;
+            self.buf.write_f64::<LittleEndian>(value)?;
             Ok(())
         } else {
             self.write_bits(&value.to_le_bytes(), 64)
@@ -322,13 +419,11 @@ impl<'a> BdWriter<'a> {
             self.write_data_type(BufferDataType::no_array(BdDataType::SignedChar8StringType))?;
         }
 
-        self.cursor.write_all(value.as_bytes())?;
-        self.cursor.write_u8(0)?;
-
-        Ok(())
+        self.write_terminated_str(value)
     }
 
-    pub fn write_i8_array(&mut self, value: &[i8]) -> Result<(), Box<dyn Error>> {
+    /// Writes a wide string, which some console titles use instead of byte strings.
+    pub fn write_wstr(&mut self, value: &str) -> Result<(), Box<dyn Error>> {
         ensure!(
             self.mode == StreamMode::ByteMode,
             ModeSnafu {
@@ -337,124 +432,112 @@ impl<'a> BdWriter<'a> {
             }
         );
 
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::SignedChar8Type))?;
+        if self.type_checked {
+            self.write_data_type(BufferDataType::no_array(BdDataType::WChar16Type))?;
+        }
 
-        self.write_array_num_elements(value.len())?;
+        self.write_terminated_wide_str(value)
+    }
 
-        for el in value {
-            self.cursor.write_i8(*el)?;
+    /// Writes the string as UTF-16LE followed by a zero code unit, in either mode.
+    pub(crate) fn write_terminated_wide_str(&mut self, value: &str) -> Result<(), Box<dyn Error>> {
+        for unit in value.encode_utf16().chain([0]) {
+            self.write_bytes(&unit.to_le_bytes())?;
         }
 
         Ok(())
     }
 
-    pub fn write_u8_array(&mut self, value: &[u8]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::UnsignedChar8Type))?;
+    /// Writes the encoded string followed by its terminator, in either mode.
+    pub(crate) fn write_terminated_str(&mut self, value: &str) -> Result<(), Box<dyn Error>> {
+        let encoding = self.string_encoding;
+        self.write_bytes(&encoding.encode(value))?;
+        self.write_bytes(&[0])
+    }
 
-        self.write_array_num_elements(value.len())?;
+    /// Writes a typed array of any element type.
+    pub fn write_array<T: BdArrayElement>(&mut self, value: &[T]) -> Result<(), Box<dyn Error>> {
+        self.write_array_header(T::DATA_TYPE, value.len())?;
 
         for el in value {
-            self.cursor.write_u8(*el)?;
+            el.write_element(self)?;
         }
 
         Ok(())
     }
 
-    pub fn write_i16_array(&mut self, value: &[i16]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
+    fn write_array_header(
+        &mut self,
+        data_type: BdDataType,
+        num_elements: usize,
+    ) -> Result<(), Box<dyn Error>> {
         // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::SignedInteger16Type))?;
+        self.write_data_type(BufferDataType::array(data_type))?;
 
-        self.write_array_num_elements(value.len())?;
+        self.write_array_num_elements(num_elements)
+    }
 
-        for el in value {
-            self.cursor.write_i16::<LittleEndian>(*el)?;
-        }
+    pub fn write_i8_array(&mut self, value: &[i8]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
+    }
 
-        Ok(())
+    pub fn write_u8_array(&mut self, value: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
+    }
+
+    pub fn write_i16_array(&mut self, value: &[i16]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
     }
 
     pub fn write_u16_array(&mut self, value: &[u16]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
+        self.write_array(value)
+    }
 
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::UnsignedInteger16Type))?;
+    pub fn write_i32_array(&mut self, value: &[i32]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
+    }
 
-        self.write_array_num_elements(value.len())?;
+    pub fn write_u32_array(&mut self, value: &[u32]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
+    }
 
-        for el in value {
-            self.cursor.write_u16::<LittleEndian>(*el)?;
-        }
+    pub fn write_i64_array(&mut self, value: &[i64]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
+    }
 
-        Ok(())
+    pub fn write_u64_array(&mut self, value: &[u64]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
     }
 
-    pub fn write_i32_array(&mut self, value: &[i32]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
+    pub fn write_f32_array(&mut self, value: &[f32]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
+    }
 
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::SignedInteger32Type))?;
+    pub fn write_f64_array(&mut self, value: &[f64]) -> Result<(), Box<dyn Error>> {
+        self.write_array(value)
+    }
 
-        self.write_array_num_elements(value.len())?;
+    pub fn write_str_array(&mut self, value: &[&str]) -> Result<(), Box<dyn Error>> {
+        self.write_array_header(BdDataType::SignedChar8StringType, value.len())?;
 
         for el in value {
-            self.cursor.write_i32::<LittleEndian>(*el)?;
+            self.write_terminated_str(el)?;
         }
 
         Ok(())
     }
 
-    pub fn write_u32_array(&mut self, value: &[u32]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::UnsignedInteger32Type))?;
-
-        self.write_array_num_elements(value.len())?;
+    pub fn write_wstr_array(&mut self, value: &[&str]) -> Result<(), Box<dyn Error>> {
+        self.write_array_header(BdDataType::WChar16Type, value.len())?;
 
         for el in value {
-            self.cursor.write_u32::<LittleEndian>(*el)?;
+            self.write_terminated_wide_str(el)?;
         }
 
         Ok(())
     }
 
-    pub fn write_i64_array(&mut self, value: &[i64]) -> Result<(), Box<dyn Error>> {
+    pub fn write_blob(&mut self, value: &[u8]) -> Result<(), Box<dyn Error>> {
         ensure!(
             self.mode == StreamMode::ByteMode,
             ModeSnafu {
@@ -463,78 +546,75 @@ impl<'a> BdWriter<'a> {
             }
         );
 
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::SignedInteger64Type))?;
-
-        self.write_array_num_elements(value.len())?;
-
-        for el in value {
-            self.cursor.write_i64::<LittleEndian>(*el)?;
+        if self.type_checked {
+            self.write_data_type(BufferDataType::no_array(BdDataType::BlobType))?;
         }
 
+        self.write_u32(value.len() as u32)?;
+        self.buf.write_all(value)?;
+
         Ok(())
     }
 
-    pub fn write_u64_array(&mut self, value: &[u64]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::UnsignedInteger64Type))?;
+    /// Starts a struct of `num_fields` values that are written by the following calls.
+    /// The data type of a struct is always written, so that readers know where it ends.
+    pub fn write_struct_header(&mut self, num_fields: usize) -> Result<(), Box<dyn Error>> {
+        self.write_data_type(BufferDataType::no_array(BdDataType::StructType))?;
+        self.write_u32(num_fields as u32)
+    }
 
-        self.write_array_num_elements(value.len())?;
+    pub fn write_struct(&mut self, fields: &[&dyn BdSerialize]) -> Result<(), Box<dyn Error>> {
+        self.write_struct_header(fields.len())?;
 
-        for el in value {
-            self.cursor.write_u64::<LittleEndian>(*el)?;
+        for field in fields {
+            field.serialize(self)?;
         }
 
         Ok(())
     }
 
-    pub fn write_str_array(&mut self, value: &[&str]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        // Arrays are always type checked
-        self.write_data_type(BufferDataType::array(BdDataType::SignedChar8StringType))?;
+    /// Starts a map of `num_entries` key value pairs that are written by the following calls.
+    /// The data type of a map is always written, so that readers know where it ends.
+    pub fn write_map_header(&mut self, num_entries: usize) -> Result<(), Box<dyn Error>> {
+        self.write_data_type(BufferDataType::no_array(BdDataType::MapType))?;
+        self.write_u32(num_entries as u32)
+    }
 
-        self.write_array_num_elements(value.len())?;
+    pub fn write_map<'v, K, V>(
+        &mut self,
+        entries: impl ExactSizeIterator<Item = (&'v K, &'v V)>,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        K: BdSerialize + 'v,
+        V: BdSerialize + 'v,
+    {
+        self.write_map_header(entries.len())?;
 
-        for el in value {
-            self.cursor.write_all(el.as_bytes())?;
-            self.cursor.write_u8(0)?;
+        for (key, value) in entries {
+            key.serialize(self)?;
+            value.serialize(self)?;
         }
 
         Ok(())
     }
+}
 
-    pub fn write_blob(&mut self, value: &[u8]) -> Result<(), Box<dyn Error>> {
-        ensure!(
-            self.mode == StreamMode::ByteMode,
-            ModeSnafu {
-                actual_mode: self.mode,
-                expected_mode: StreamMode::ByteMode
-            }
-        );
-
-        if self.type_checked {
-            self.write_data_type(BufferDataType::no_array(BdDataType::BlobType))?;
-        }
+impl BdWriter<'static> {
+    /// Creates a writer with its own buffer that can hold `capacity` bytes without reallocating.
+    /// The written data is retrieved with [`BdWriter::finish`].
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_buffer(WriterBuffer::Owned(Vec::with_capacity(capacity)))
+    }
 
-        self.write_u32(value.len() as u32)?;
-        self.cursor.write_all(value)?;
+    /// Flushes the last partially written byte and returns the written data.
+    /// Data written to a borrowed buffer stays there, so nothing is returned for it.
+    pub fn finish(mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.flush()?;
 
-        Ok(())
+        match &mut self.buf {
+            WriterBuffer::Owned(buf) => Ok(std::mem::take(buf)),
+            WriterBuffer::Borrowed(_) => Ok(Vec::new()),
+        }
     }
 }
 
@@ -677,4 +757,35 @@ mod tests {
         assert_eq!(out[3], 0);
         assert_eq!(out[4], 0);
     }
+
+    #[test]
+    fn owned_writer_returns_written_data() {
+        let mut writer = BdWriter::with_capacity(64);
+        assert!(writer.is_empty());
+        assert!(writer.capacity() >= 64);
+
+        writer.write_u32(0x12345678).unwrap();
+        assert_eq!(writer.len(), 4);
+
+        writer.set_mode(StreamMode::BitMode);
+        writer.write_bits(&[0b101], 3).unwrap();
+        assert_eq!(writer.len(), 5);
+
+        assert_eq!(writer.finish().unwrap(), [0x78, 0x56, 0x34, 0x12, 0b101]);
+    }
+
+    #[test]
+    fn borrowed_writer_leaves_data_in_buffer() {
+        let mut out = vec![0xAA];
+
+        {
+            let mut writer = BdWriter::new(&mut out);
+            writer.write_u16(0xBEEF).unwrap();
+            writer.set_mode(StreamMode::BitMode);
+            writer.write_bits(&[0b1], 1).unwrap();
+            assert_eq!(writer.len(), 4);
+        }
+
+        assert_eq!(out, [0xAA, 0xEF, 0xBE, 0b1]);
+    }
 }
@@ -1,11 +1,14 @@
 ﻿use num_derive::{FromPrimitive, ToPrimitive};
+use std::io;
 
 pub mod bd_data_type;
+pub mod bd_dump;
 pub mod bd_message;
 pub mod bd_reader;
 pub mod bd_response;
 pub mod bd_serialization;
 pub mod bd_writer;
+pub mod string_encoding;
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum StreamMode {
@@ -338,3 +341,201 @@ pub enum BdErrorCode {
     GmsgGroupPostRateExceeded = 10209,
     MaxErrorCode,
 }
+
+/// The area an error code belongs to, derived from the range of its value.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub enum BdErrorCategory {
+    General,
+    Lobby,
+    Teams,
+    Stats,
+    Title,
+    Messaging,
+    Auth,
+    Profiles,
+    Friends,
+    Storage,
+    Channels,
+    Events,
+    ContentUnlock,
+    KeyArchive,
+    Bandwidth,
+    Matchmaking,
+    ContentStreaming,
+    VoteRank,
+    Tags,
+    Groups,
+    LinkedAccounts,
+    Whitelist,
+    UserGroups,
+    RichPresence,
+    Subscriptions,
+    Telemetry,
+    Marketplace,
+    League,
+    Commerce,
+    GlobalMessaging,
+}
+
+impl BdErrorCode {
+    pub fn code(&self) -> u32 {
+        *self as u32
+    }
+
+    /// Whether the code reports success.
+    /// Besides `NoError`, some services use a success code of their own.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self,
+            BdErrorCode::NoError | BdErrorCode::AuthNoError | BdErrorCode::StreamingComplete
+        )
+    }
+
+    pub fn category(&self) -> BdErrorCategory {
+        match self.code() {
+            0..100 => BdErrorCategory::General,
+            100..300 => BdErrorCategory::Lobby,
+            300..400 => BdErrorCategory::Teams,
+            400..500 => BdErrorCategory::Stats,
+            500..600 => BdErrorCategory::Title,
+            600..700 => BdErrorCategory::Messaging,
+            700..800 => BdErrorCategory::Auth,
+            800..900 => BdErrorCategory::Profiles,
+            900..1000 => BdErrorCategory::Friends,
+            1000..1100 => BdErrorCategory::Storage,
+            1100..1200 => BdErrorCategory::Channels,
+            1200..1300 => BdErrorCategory::Events,
+            1300..1400 => BdErrorCategory::ContentUnlock,
+            1500..1600 => BdErrorCategory::KeyArchive,
+            1800..1900 => BdErrorCategory::Bandwidth,
+            1900..2000 | 2100..2200 => BdErrorCategory::Matchmaking,
+            2000..2030 => BdErrorCategory::ContentStreaming,
+            2030..2090 => BdErrorCategory::VoteRank,
+            2090..2100 => BdErrorCategory::Tags,
+            2800..2900 => BdErrorCategory::Groups,
+            3200..3800 | 3900..4000 => BdErrorCategory::LinkedAccounts,
+            3800..3900 => BdErrorCategory::Whitelist,
+            6000..6100 => BdErrorCategory::UserGroups,
+            6800..6900 => BdErrorCategory::RichPresence,
+            7000..7100 => BdErrorCategory::Subscriptions,
+            7100..7200 => BdErrorCategory::Telemetry,
+            8000..8100 => BdErrorCategory::Marketplace,
+            8100..8300 => BdErrorCategory::League,
+            8300..8400 => BdErrorCategory::Commerce,
+            10200..10300 => BdErrorCategory::GlobalMessaging,
+            _ => BdErrorCategory::General,
+        }
+    }
+
+    /// Whether the code is a failure of the auth service.
+    pub fn is_auth_error(&self) -> bool {
+        self.category() == BdErrorCategory::Auth && !self.is_success()
+    }
+
+    /// Whether the request might succeed when the client tries again later,
+    /// like when a service is overloaded or a connection dropped.
+    pub fn is_temporary(&self) -> bool {
+        matches!(
+            self,
+            BdErrorCode::TooManyTasks
+                | BdErrorCode::NotConnected
+                | BdErrorCode::SendFailed
+                | BdErrorCode::ServiceNotAvailable
+                | BdErrorCode::ConnectionReset
+                | BdErrorCode::LobbyInternalFailure
+                | BdErrorCode::ExceptionInDb
+                | BdErrorCode::BandwidthTestTryAgain
+                | BdErrorCode::BandwidthTestStillInProgress
+                | BdErrorCode::ContentStreamingUploadBandwidthExceeded
+                | BdErrorCode::ContentStreamingDownloadBandwidthExceeded
+                | BdErrorCode::ContentStreamingHttpError
+                | BdErrorCode::ContentStreamingFailedToStartHttp
+                | BdErrorCode::YoutubeServiceCommunicationError
+                | BdErrorCode::FacebookLiteUnavailable
+                | BdErrorCode::FacebookLiteTimedOut
+                | BdErrorCode::TwitterUnavailable
+                | BdErrorCode::TwitterTimedOut
+                | BdErrorCode::TwitterUpdateLimitReached
+                | BdErrorCode::FacebookUnavailable
+                | BdErrorCode::FacebookTimedOut
+                | BdErrorCode::GmsgOverloaded
+                | BdErrorCode::GmsgUserPerCategoryPostRateExceeded
+                | BdErrorCode::GmsgUserGlobalPostRateExceeded
+                | BdErrorCode::GmsgGroupPostRateExceeded
+        )
+    }
+}
+
+impl From<&io::Error> for BdErrorCode {
+    fn from(value: &io::Error) -> Self {
+        match value.kind() {
+            io::ErrorKind::NotFound => BdErrorCode::NoFile,
+            io::ErrorKind::PermissionDenied => BdErrorCode::PermissionDenied,
+            io::ErrorKind::NotConnected => BdErrorCode::NotConnected,
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => BdErrorCode::ConnectionReset,
+            io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::ConnectionRefused => BdErrorCode::ServiceNotAvailable,
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => {
+                BdErrorCode::ParamParseError
+            }
+            io::ErrorKind::UnexpectedEof => BdErrorCode::MalformedTaskHeader,
+            io::ErrorKind::StorageFull
+            | io::ErrorKind::FileTooLarge
+            | io::ErrorKind::QuotaExceeded => BdErrorCode::FileSizeLimitExceeded,
+            io::ErrorKind::InvalidFilename => BdErrorCode::FilenameMaxLengthExceeded,
+            _ => BdErrorCode::LobbyInternalFailure,
+        }
+    }
+}
+
+impl From<io::Error> for BdErrorCode {
+    fn from(value: io::Error) -> Self {
+        BdErrorCode::from(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_are_categorized_by_range() {
+        assert_eq!(
+            BdErrorCode::AuthBadAccount.category(),
+            BdErrorCategory::Auth
+        );
+        assert!(BdErrorCode::AuthBadAccount.is_auth_error());
+        assert!(!BdErrorCode::AuthNoError.is_auth_error());
+        assert!(BdErrorCode::AuthNoError.is_success());
+        assert_eq!(
+            BdErrorCode::CommerceReceiptUsed.category(),
+            BdErrorCategory::Commerce
+        );
+        assert_eq!(
+            BdErrorCode::InvalidSessionId.category(),
+            BdErrorCategory::Matchmaking
+        );
+        assert!(BdErrorCode::GmsgOverloaded.is_temporary());
+        assert!(!BdErrorCode::PermissionDenied.is_temporary());
+    }
+
+    #[test]
+    fn io_errors_map_to_precise_codes() {
+        let code = |kind| BdErrorCode::from(io::Error::from(kind));
+
+        assert_eq!(code(io::ErrorKind::NotFound), BdErrorCode::NoFile);
+        assert_eq!(
+            code(io::ErrorKind::BrokenPipe),
+            BdErrorCode::ConnectionReset
+        );
+        assert!(code(io::ErrorKind::TimedOut).is_temporary());
+        assert_eq!(
+            code(io::ErrorKind::Other),
+            BdErrorCode::LobbyInternalFailure
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use snafu::Snafu;
+use std::borrow::Cow;
+use std::error::Error;
+use std::str::FromStr;
+
+/// How strings of buffers are converted from and to their bytes.
+/// Current clients use UTF-8, while old clients send names in their Windows code page.
+#[derive(Debug, Default, Eq, PartialEq, Hash, Copy, Clone)]
+pub enum StringEncoding {
+    /// Fails on strings that are not valid UTF-8.
+    #[default]
+    Utf8,
+    /// Replaces invalid UTF-8 sequences with the replacement character.
+    Utf8Lossy,
+    /// Reads and writes strings as Windows-1252, a superset of Latin-1.
+    /// Characters that it cannot represent are written as `?`.
+    Windows1252,
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display("Unknown string encoding {value}, expected utf8, utf8_lossy or windows1252"))]
+pub struct UnknownStringEncodingError {
+    value: String,
+}
+
+/// The characters of Windows-1252 that differ from Latin-1.
+/// Bytes that are not assigned keep their Latin-1 control character.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+impl StringEncoding {
+    pub fn decode(&self, bytes: Vec<u8>) -> Result<String, Box<dyn Error>> {
+        match self {
+            StringEncoding::Utf8 => Ok(String::from_utf8(bytes)?),
+            StringEncoding::Utf8Lossy => Ok(match String::from_utf8(bytes) {
+                Ok(value) => value,
+                Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            }),
+            StringEncoding::Windows1252 => Ok(bytes
+                .into_iter()
+                .map(|byte| match byte {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+                    _ => char::from(byte),
+                })
+                .collect()),
+        }
+    }
+
+    pub fn encode<'a>(&self, value: &'a str) -> Cow<'a, [u8]> {
+        match self {
+            StringEncoding::Utf8 | StringEncoding::Utf8Lossy => Cow::Borrowed(value.as_bytes()),
+            StringEncoding::Windows1252 if value.is_ascii() => Cow::Borrowed(value.as_bytes()),
+            StringEncoding::Windows1252 => Cow::Owned(
+                value
+                    .chars()
+                    .map(|c| match c as u32 {
+                        0..0x80 | 0xA0..0x100 => c as u8,
+                        _ => WINDOWS_1252_HIGH
+                            .iter()
+                            .position(|high| *high == c)
+                            .map(|index| 0x80 + index as u8)
+                            .unwrap_or(b'?'),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl FromStr for StringEncoding {
+    type Err = UnknownStringEncodingError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "utf8" => Ok(StringEncoding::Utf8),
+            "utf8_lossy" => Ok(StringEncoding::Utf8Lossy),
+            "windows1252" => Ok(StringEncoding::Windows1252),
+            _ => UnknownStringEncodingSnafu { value }.fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_1252_round_trips() {
+        let bytes = vec![b'J', 0xF6, b'r', b'g', 0x80, 0x81];
+        let value = StringEncoding::Windows1252.decode(bytes.clone()).unwrap();
+
+        assert_eq!(value, "Jörg€\u{81}");
+        assert_eq!(StringEncoding::Windows1252.encode(&value), bytes);
+        assert_eq!(StringEncoding::Windows1252.encode("日"), b"?".as_slice());
+    }
+
+    #[test]
+    fn invalid_utf8_only_fails_in_strict_mode() {
+        let bytes = vec![b'J', 0xF6, b'r', b'g'];
+
+        assert!(StringEncoding::Utf8.decode(bytes.clone()).is_err());
+        assert_eq!(
+            StringEncoding::Utf8Lossy.decode(bytes).unwrap(),
+            "J\u{FFFD}rg"
+        );
+    }
+}
@@ -1,14 +1,60 @@
 use crate::auth::authentication::SessionAuthentication;
+use crate::crypto::{SessionCipher, SessionKey};
+use crate::networking::scratch_store::ScratchStore;
 use std::io;
-use std::io::BufReader;
-use std::net::{SocketAddr, TcpStream};
+use std::io::{BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 pub type SessionId = u64;
 
+/// Takes the frames of a session whose connection is written asynchronously.
+/// Frames are passed in the order they were written.
+pub(crate) type FrameSink = Arc<dyn Fn(Vec<u8>) -> io::Result<()> + Send + Sync>;
+
+/// How long messages encrypted with a replaced session key are still accepted,
+/// since the client may have sent them before it received the reply to the renegotiation.
+const PREVIOUS_KEY_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// The throughput measured by the latest bandwidth test of a session.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BandwidthStats {
+    /// Bits per second from the client to the server.
+    pub upstream_bps: u64,
+    /// Bits per second from the server to the client, if the test measured them.
+    pub downstream_bps: Option<u64>,
+}
+
+#[derive(Default)]
+struct SessionKeys {
+    current: Option<SessionKey>,
+    previous: Option<(SessionKey, Instant)>,
+    cipher: SessionCipher,
+    /// How often the key was renegotiated since the session authenticated.
+    generation: u32,
+    /// When the current key was established.
+    established_at: Option<Instant>,
+    /// The IV seed the client announced together with the current key.
+    iv_seed: Option<u32>,
+}
+
 pub struct BdSession {
     pub id: SessionId,
     authentication: Option<SessionAuthentication>,
+    scratch: ScratchStore,
+    plaintext: bool,
+    type_checked: Option<bool>,
+    bandwidth_stats: Option<BandwidthStats>,
+    /// Shared with detached handles so that they encrypt with the current key after renegotiation.
+    keys: Arc<RwLock<SessionKeys>>,
     stream: BufReader<TcpStream>,
+    /// Shared with detached handles so that frames of different threads do not interleave.
+    write_lock: Arc<Mutex<()>>,
+    /// Replaces writing to the stream when the connection is driven asynchronously.
+    frame_sink: Option<FrameSink>,
+    /// When the client last sent a frame, shared with detached handles.
+    last_activity: Arc<Mutex<Instant>>,
 }
 
 impl io::Read for BdSession {
@@ -19,10 +65,19 @@ impl io::Read for BdSession {
 
 impl io::Write for BdSession {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(frame_sink) = &self.frame_sink {
+            frame_sink(buf.to_vec())?;
+            return Ok(buf.len());
+        }
+
         self.stream.get_mut().write(buf)
     }
 
     fn flush(&mut self) -> io::Result<()> {
+        if self.frame_sink.is_some() {
+            return Ok(());
+        }
+
         self.stream.get_mut().flush()
     }
 }
@@ -34,20 +89,245 @@ impl BdSession {
         BdSession {
             id: 0,
             authentication: None,
+            scratch: ScratchStore::new(),
+            plaintext: false,
+            type_checked: None,
+            bandwidth_stats: None,
+            keys: Arc::new(RwLock::new(SessionKeys::default())),
             stream: reader,
+            write_lock: Arc::new(Mutex::new(())),
+            frame_sink: None,
+            last_activity: Arc::new(Mutex::new(Instant::now())),
         }
     }
 
+    /// Creates a session whose frames are passed to the sink instead of being written to the stream.
+    /// The stream is only used for its address and to close the connection.
+    /// The session must not be used for reading.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn with_frame_sink(stream: TcpStream, frame_sink: FrameSink) -> Self {
+        BdSession {
+            frame_sink: Some(frame_sink),
+            ..Self::new(stream)
+        }
+    }
+
+    /// Creates another handle to this session that can be moved to a different thread,
+    /// e.g. to reply to a task in the background.
+    /// The handle shares the connection and the authentication but has its own scratch store.
+    /// It must not be used for reading.
+    pub fn detach(&self) -> io::Result<BdSession> {
+        Ok(BdSession {
+            id: self.id,
+            authentication: self.authentication.clone(),
+            scratch: ScratchStore::new(),
+            plaintext: self.plaintext,
+            type_checked: self.type_checked,
+            bandwidth_stats: self.bandwidth_stats,
+            keys: self.keys.clone(),
+            stream: BufReader::new(self.try_clone_stream()?),
+            write_lock: self.write_lock.clone(),
+            frame_sink: self.frame_sink.clone(),
+            last_activity: self.last_activity.clone(),
+        })
+    }
+
+    /// Writes a complete frame without interleaving with frames written by detached handles.
+    pub fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+
+        if let Some(frame_sink) = &self.frame_sink {
+            return frame_sink(frame.to_vec());
+        }
+
+        self.stream.get_mut().write_all(frame)
+    }
+
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         self.stream.get_ref().peer_addr()
     }
 
+    /// Creates another handle to the connection of this session.
+    /// Can be used to close the connection from a different thread.
+    pub fn try_clone_stream(&self) -> io::Result<TcpStream> {
+        self.stream.get_ref().try_clone()
+    }
+
+    /// Closes the connection of this session and all detached handles,
+    /// which ends the session once it handled its current message.
+    pub fn close(&self) -> io::Result<()> {
+        self.stream.get_ref().shutdown(Shutdown::Both)
+    }
+
+    /// Notes that the client is still there, e.g. because it sent a frame.
+    pub fn record_activity(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// How long the client did not send anything.
+    pub fn idle_time(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+
     pub fn authentication(&self) -> Option<&SessionAuthentication> {
         self.authentication.as_ref()
     }
 
+    /// Temporary state that is bound to the lifetime of this session.
+    pub fn scratch(&self) -> &ScratchStore {
+        &self.scratch
+    }
+
+    /// Whether responses to this session are sent without encryption even when authenticated.
+    pub fn plaintext(&self) -> bool {
+        self.plaintext
+    }
+
+    pub fn set_plaintext(&mut self, plaintext: bool) {
+        self.plaintext = plaintext;
+    }
+
+    /// Whether the client sends and expects buffers with data types,
+    /// or `None` as long as it was not detected from its frames yet.
+    pub fn type_checked(&self) -> Option<bool> {
+        self.type_checked
+    }
+
+    pub fn set_type_checked(&mut self, type_checked: bool) {
+        self.type_checked = Some(type_checked);
+    }
+
+    /// The result of the latest bandwidth test of the client, if it ran one.
+    pub fn bandwidth_stats(&self) -> Option<BandwidthStats> {
+        self.bandwidth_stats
+    }
+
+    pub fn set_bandwidth_stats(&mut self, bandwidth_stats: BandwidthStats) {
+        self.bandwidth_stats = Some(bandwidth_stats);
+    }
+
     pub fn set_authentication(&mut self, authentication: SessionAuthentication) {
         debug_assert!(self.authentication.is_none());
+        {
+            let mut keys = self.keys.write().unwrap();
+            keys.current = Some(authentication.session_key.clone());
+            keys.established_at = Some(Instant::now());
+        }
         self.authentication = Some(authentication);
     }
+
+    /// The key messages of this session are currently encrypted with,
+    /// or `None` as long as the session is not authenticated.
+    pub fn session_key(&self) -> Option<SessionKey> {
+        self.keys.read().unwrap().current.clone()
+    }
+
+    /// The key that was replaced by the latest renegotiation,
+    /// as long as messages of the client encrypted with it may still be in flight.
+    pub fn previous_session_key(&self) -> Option<SessionKey> {
+        self.keys
+            .read()
+            .unwrap()
+            .previous
+            .as_ref()
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(key, _)| key.clone())
+    }
+
+    /// How often the session key was renegotiated since the session authenticated.
+    pub fn session_key_generation(&self) -> u32 {
+        self.keys.read().unwrap().generation
+    }
+
+    /// How long the current session key is in use,
+    /// or `None` as long as the session is not authenticated.
+    pub fn session_key_age(&self) -> Option<Duration> {
+        self.keys
+            .read()
+            .unwrap()
+            .established_at
+            .map(|established_at| established_at.elapsed())
+    }
+
+    /// The IV seed the client announced when it last renegotiated the session key,
+    /// or `None` if it never renegotiated.
+    pub fn renegotiated_iv_seed(&self) -> Option<u32> {
+        self.keys.read().unwrap().iv_seed
+    }
+
+    /// The cipher responses to this session are encrypted with.
+    pub fn session_cipher(&self) -> SessionCipher {
+        self.keys.read().unwrap().cipher
+    }
+
+    /// Switches the cipher of this and all detached handles at once,
+    /// e.g. after the client sent a message encrypted with a different cipher.
+    pub fn set_session_cipher(&mut self, cipher: SessionCipher) {
+        self.keys.write().unwrap().cipher = cipher;
+    }
+
+    /// Replaces the key of an authenticated session for this and all detached handles at once.
+    /// Messages encrypted with the replaced key are still accepted for a short grace period.
+    /// Responses always carry their own IV seed, so the announced `iv_seed` is only tracked.
+    pub fn renegotiate_session_key(&mut self, session_key: SessionKey, iv_seed: u32) {
+        debug_assert!(self.authentication.is_some());
+
+        {
+            let mut keys = self.keys.write().unwrap();
+            keys.previous = keys
+                .current
+                .take()
+                .map(|key| (key, Instant::now() + PREVIOUS_KEY_GRACE_PERIOD));
+            keys.current = Some(session_key.clone());
+            keys.generation += 1;
+            keys.established_at = Some(Instant::now());
+            keys.iv_seed = Some(iv_seed);
+        }
+
+        if let Some(authentication) = self.authentication.as_mut() {
+            authentication.session_key = session_key;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::title::Title;
+    use std::net::TcpListener;
+
+    fn authenticated_session(session_key: SessionKey) -> BdSession {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut session = BdSession::new(stream);
+        session.set_authentication(SessionAuthentication {
+            user_id: 1,
+            username: "user".to_string(),
+            session_key,
+            title: Title::T6Pc,
+            clock_skew: None,
+            anonymous: false,
+        });
+
+        session
+    }
+
+    #[test]
+    fn renegotiation_rotates_key_state() {
+        let mut session = authenticated_session([1; 24].into());
+        assert_eq!(session.session_key_generation(), 0);
+        assert_eq!(session.renegotiated_iv_seed(), None);
+
+        let detached = session.detach().unwrap();
+        session.renegotiate_session_key([2; 24].into(), 0xCAFE);
+
+        assert_eq!(session.session_key_generation(), 1);
+        assert_eq!(session.renegotiated_iv_seed(), Some(0xCAFE));
+        assert_eq!(detached.session_key(), Some([2; 24].into()));
+        assert_eq!(detached.previous_session_key(), Some([1; 24].into()));
+        assert_eq!(
+            session.authentication().unwrap().session_key,
+            [2; 24].into()
+        );
+    }
 }
@@ -1,24 +1,46 @@
-use crate::messaging::bd_message::BdMessage;
+use crate::crypto::key_fingerprint;
+use crate::messaging::bd_message::{BdMessage, BdMessageError, MessageCryptoMetrics};
 use crate::networking::bd_session::BdSession;
+use crate::networking::connection_limits::{
+    ConnectionLimiter, ConnectionLimits, IpConnectionGuard, MessageRateLimiter, RateDecision,
+};
 use crate::networking::session_manager::SessionManager;
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use log::{debug, error, info};
+use crate::networking::socket_shutdown::{ConnectionGuard, ShutdownHandle};
+use byteorder::{LittleEndian, ReadBytesExt};
+use log::{debug, error, info, warn};
 use snafu::{ensure, Snafu};
 use std::error::Error;
 use std::io::{ErrorKind, Read};
-use std::net::TcpListener;
+use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::{io, thread};
+#[cfg(feature = "tokio")]
+use {
+    std::future::Future,
+    std::net::Shutdown,
+    std::pin::Pin,
+    tokio::io::{AsyncReadExt, AsyncWriteExt},
+};
 
 const MAX_MESSAGE_SIZE: u32 = 0x4000000;
+/// Capacity of the frame buffer that is kept between messages of a connection.
+/// Buffers grown beyond it by large messages are shrunk again afterwards.
+const RETAINED_FRAME_BUFFER_SIZE: usize = 0x10000;
+/// How many frames of a connection driven by tokio may wait to be written.
+/// Connections of clients that do not read their responses are closed when exceeding it.
+#[cfg(feature = "tokio")]
+const MAX_QUEUED_FRAMES: usize = 1024;
 
 #[derive(Debug, Snafu)]
+#[allow(clippy::enum_variant_names)]
 enum BdSocketError {
     #[snafu(display("Message was too large (size={msg_size}, max={MAX_MESSAGE_SIZE})"))]
     MessageTooLargeError { msg_size: u32 },
     #[snafu(display("The client sent an incomplete message header"))]
     IncompleteMessageHeaderError {},
+    #[snafu(display("The client was throttled too often (throttled={throttled_count})"))]
+    ThrottledTooOftenError { throttled_count: u32 },
 }
 
 pub trait BdMessageHandler {
@@ -27,11 +49,72 @@ pub trait BdMessageHandler {
         session: &mut BdSession,
         message: BdMessage,
     ) -> Result<(), Box<dyn Error>>;
+
+    /// Answers a message that is not handled because the client sent too many messages.
+    /// The message is dropped without an answer by default.
+    fn reject_throttled(&self, _session: &mut BdSession) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// The future of an [`AsyncBdMessageHandler`] handling a single message.
+#[cfg(feature = "tokio")]
+pub type BdMessageHandlerFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<(), Box<dyn Error>>> + Send + 'a>>;
+
+/// A message handler that can await other work, e.g. database queries or http requests,
+/// without blocking a thread while doing so.
+/// Used with [`BdSocket::run_tokio`].
+#[cfg(feature = "tokio")]
+pub trait AsyncBdMessageHandler {
+    fn handle_message<'a>(
+        &'a self,
+        session: &'a mut BdSession,
+        message: BdMessage<'a>,
+    ) -> BdMessageHandlerFuture<'a>;
+
+    /// Answers a message that is not handled because the client sent too many messages.
+    /// The message is dropped without an answer by default.
+    fn reject_throttled<'a>(&'a self, _session: &'a mut BdSession) -> BdMessageHandlerFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// How the messages of connections driven by tokio are handled.
+#[cfg(feature = "tokio")]
+#[derive(Clone)]
+enum TokioMessageHandler {
+    Async(Arc<dyn AsyncBdMessageHandler + Send + Sync>),
+    /// Runs on the blocking threads of tokio, so that handlers do not stall other connections.
+    Blocking(Arc<dyn BdMessageHandler + Send + Sync>),
+}
+
+/// What was read from a connection driven by tokio.
+#[cfg(feature = "tokio")]
+enum ReadFrame {
+    Closed,
+    /// Pings, buffer sizes and throttled messages, which are answered right away.
+    Handled,
+    Message,
+}
+
+/// The parts of a connection that is handled as a tokio task.
+#[cfg(feature = "tokio")]
+struct AsyncConnection {
+    session: BdSession,
+    read_half: tokio::net::tcp::OwnedReadHalf,
+    /// Queues the frames of the session, or `None` to write all queued frames and shut down writing.
+    frame_sender: tokio::sync::mpsc::Sender<Option<Vec<u8>>>,
+    writer: tokio::task::JoinHandle<()>,
 }
 
 pub struct BdSocket {
     session_manager: Arc<SessionManager>,
+    crypto_metrics: Arc<MessageCryptoMetrics>,
+    plaintext: bool,
     listener: Option<TcpListener>,
+    shutdown: ShutdownHandle,
+    limiter: Arc<ConnectionLimiter>,
 }
 
 impl BdSocket {
@@ -50,25 +133,86 @@ impl BdSocket {
         info!("Opened bitdemon socket on port {port}");
 
         Ok(BdSocket {
+            shutdown: ShutdownHandle::new(listener.local_addr()?),
             listener: Some(listener),
             session_manager,
+            crypto_metrics: Arc::new(MessageCryptoMetrics::default()),
+            plaintext: false,
+            limiter: Arc::new(ConnectionLimiter::new(ConnectionLimits::default())),
         })
     }
 
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener
+            .as_ref()
+            .ok_or_else(|| io::Error::from(ErrorKind::NotConnected))?
+            .local_addr()
+    }
+
+    /// Allows shutting down the socket gracefully once it runs.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Counts messages received on this socket that could not be decrypted.
+    pub fn crypto_metrics(&self) -> Arc<MessageCryptoMetrics> {
+        self.crypto_metrics.clone()
+    }
+
+    /// Sends all responses of this socket without encryption.
+    /// Clients must be patched to understand them.
+    ///
+    /// Unencrypted messages of clients are always accepted.
+    /// This is insecure and only meant for protocol research, e.g. with packet captures.
+    pub fn set_plaintext(&mut self, plaintext: bool) {
+        self.plaintext = plaintext;
+    }
+
+    /// Protects the socket from peers opening too many connections or sending too many messages.
+    /// Only applies to connections accepted afterwards.
+    pub fn set_connection_limits(&mut self, limits: ConnectionLimits) {
+        self.limiter = Arc::new(ConnectionLimiter::new(limits));
+    }
+
     fn listen(
         listener: &TcpListener,
         session_manager: &Arc<SessionManager>,
+        crypto_metrics: &Arc<MessageCryptoMetrics>,
+        plaintext: bool,
+        shutdown: &ShutdownHandle,
+        limiter: &Arc<ConnectionLimiter>,
         message_handler: Arc<dyn BdMessageHandler + Send + Sync>,
     ) -> Result<(), io::Error> {
         for stream in listener.incoming() {
             let stream = stream?;
+            if shutdown.is_shutdown() {
+                info!("Stopped accepting connections");
+                break;
+            }
+            let Some(ip_connection) = Self::admit_connection(limiter, stream.peer_addr()) else {
+                continue;
+            };
 
             let session_manager = Arc::clone(session_manager);
+            let crypto_metrics = Arc::clone(crypto_metrics);
             let message_handler = Arc::clone(&message_handler);
+            let shutdown = shutdown.clone();
+            let limiter = Arc::clone(limiter);
             thread::spawn(move || {
+                let _ip_connection = ip_connection;
+                let Some(_connection) = Self::track_connection(&shutdown, &stream) else {
+                    return;
+                };
                 let mut session = BdSession::new(stream);
+                session.set_plaintext(plaintext);
                 session_manager.register_session(&mut session);
-                BdSocket::handle_connection(&mut session, message_handler.as_ref());
+                BdSocket::handle_connection(
+                    &mut session,
+                    message_handler.as_ref(),
+                    session_manager.as_ref(),
+                    crypto_metrics.as_ref(),
+                    limiter.as_ref(),
+                );
                 session_manager.unregister_session(&session);
             });
         }
@@ -83,10 +227,45 @@ impl BdSocket {
         Self::listen(
             self.listener.as_ref().unwrap(),
             &self.session_manager,
+            &self.crypto_metrics,
+            self.plaintext,
+            &self.shutdown,
+            &self.limiter,
             message_handler,
         )
     }
 
+    /// Counts the connection towards the limit of its IP address,
+    /// or returns `None` if it is refused.
+    fn admit_connection(
+        limiter: &Arc<ConnectionLimiter>,
+        peer_addr: io::Result<SocketAddr>,
+    ) -> Option<IpConnectionGuard> {
+        let peer_addr = peer_addr
+            .inspect_err(|e| warn!("Failed to set up connection: {e}"))
+            .ok()?;
+
+        let ip_connection = limiter.try_connect(peer_addr.ip());
+        if ip_connection.is_none() {
+            warn!(
+                "Refused connection of {} that has too many connections open",
+                peer_addr.ip()
+            );
+        }
+
+        ip_connection
+    }
+
+    fn track_connection(shutdown: &ShutdownHandle, stream: &TcpStream) -> Option<ConnectionGuard> {
+        match stream.try_clone() {
+            Ok(stream) => Some(shutdown.track(stream)),
+            Err(e) => {
+                warn!("Failed to set up connection: {e}");
+                None
+            }
+        }
+    }
+
     pub fn run_async(
         &mut self,
         message_handler: Arc<dyn BdMessageHandler + Send + Sync>,
@@ -94,18 +273,338 @@ impl BdSocket {
         let message_handler = Arc::clone(&message_handler);
         let listener = self.listener.take();
         let session_manager = self.session_manager.clone();
+        let crypto_metrics = self.crypto_metrics.clone();
+        let plaintext = self.plaintext;
+        let shutdown = self.shutdown.clone();
+        let limiter = self.limiter.clone();
         thread::spawn(move || -> Result<(), io::Error> {
             let session_manager = session_manager;
             Self::listen(
                 listener.as_ref().unwrap(),
                 &session_manager,
+                &crypto_metrics,
+                plaintext,
+                &shutdown,
+                &limiter,
                 message_handler,
             )
         })
     }
 
-    fn handle_connection(session: &mut BdSession, message_handler: &dyn BdMessageHandler) {
+    /// Handles all connections as tasks of the current tokio runtime instead of separate threads.
+    /// Idle connections do not occupy a thread and handlers can await while handling messages.
+    ///
+    /// Responses are queued and written in the background,
+    /// so handlers and detached session handles never block on slow clients.
+    /// Connections are closed when too many of their responses are queued.
+    #[cfg(feature = "tokio")]
+    pub fn run_tokio(
+        &mut self,
+        message_handler: Arc<dyn AsyncBdMessageHandler + Send + Sync>,
+    ) -> tokio::task::JoinHandle<Result<(), io::Error>> {
+        self.spawn_tokio(TokioMessageHandler::Async(message_handler))
+    }
+
+    /// Like [`BdSocket::run_tokio`], but for handlers that block while handling messages.
+    /// Messages are handled on the blocking threads of tokio,
+    /// while idle connections still do not occupy a thread.
+    /// Throttled messages are rejected on the runtime, so rejecting them must not block.
+    #[cfg(feature = "tokio")]
+    pub fn run_tokio_blocking(
+        &mut self,
+        message_handler: Arc<dyn BdMessageHandler + Send + Sync>,
+    ) -> tokio::task::JoinHandle<Result<(), io::Error>> {
+        self.spawn_tokio(TokioMessageHandler::Blocking(message_handler))
+    }
+
+    #[cfg(feature = "tokio")]
+    fn spawn_tokio(
+        &mut self,
+        message_handler: TokioMessageHandler,
+    ) -> tokio::task::JoinHandle<Result<(), io::Error>> {
+        let listener = self.listener.take();
+        let session_manager = self.session_manager.clone();
+        let crypto_metrics = self.crypto_metrics.clone();
+        let plaintext = self.plaintext;
+        let shutdown = self.shutdown.clone();
+        let limiter = self.limiter.clone();
+        tokio::spawn(async move {
+            let listener = listener.unwrap();
+            listener.set_nonblocking(true)?;
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+
+            loop {
+                let (stream, peer_addr) = listener.accept().await?;
+                if shutdown.is_shutdown() {
+                    info!("Stopped accepting connections");
+                    return Ok(());
+                }
+                let Some(ip_connection) = Self::admit_connection(&limiter, Ok(peer_addr)) else {
+                    continue;
+                };
+
+                let session_manager = Arc::clone(&session_manager);
+                let crypto_metrics = Arc::clone(&crypto_metrics);
+                let message_handler = message_handler.clone();
+                let shutdown = shutdown.clone();
+                let limiter = Arc::clone(&limiter);
+                tokio::spawn(async move {
+                    let _ip_connection = ip_connection;
+                    let AsyncConnection {
+                        mut session,
+                        read_half,
+                        frame_sender,
+                        writer,
+                    } = match Self::split_async_stream(stream) {
+                        Ok(connection) => connection,
+                        Err(e) => {
+                            warn!("Failed to set up connection: {e}");
+                            return;
+                        }
+                    };
+                    let Some(_connection) = session
+                        .try_clone_stream()
+                        .inspect_err(|e| warn!("Failed to set up connection: {e}"))
+                        .ok()
+                        .map(|stream| shutdown.track(stream))
+                    else {
+                        return;
+                    };
+                    session.set_plaintext(plaintext);
+                    session_manager.register_session(&mut session);
+                    let (session, connection_result) = Self::handle_connection_async(
+                        session,
+                        &mut tokio::io::BufReader::new(read_half),
+                        &message_handler,
+                        session_manager.as_ref(),
+                        &crypto_metrics,
+                        limiter.as_ref(),
+                    )
+                    .await;
+                    Self::log_connection_result(connection_result);
+                    session_manager.unregister_session(&session);
+
+                    // Write all queued responses before closing the connection
+                    let _ = frame_sender.send(None).await;
+                    let _ = writer.await;
+                });
+            }
+        })
+    }
+
+    /// Creates the session of an asynchronous connection and the half it is read from.
+    /// The frames of the session are written by a separate task in the order they were sent.
+    #[cfg(feature = "tokio")]
+    fn split_async_stream(stream: tokio::net::TcpStream) -> Result<AsyncConnection, io::Error> {
+        // The session keeps its own handle to be able to close the connection
+        let stream = stream.into_std()?;
+        let session_stream = stream.try_clone()?;
+        let sink_stream = stream.try_clone()?;
+        let (read_half, mut write_half) = tokio::net::TcpStream::from_std(stream)?.into_split();
+
+        let (frame_sender, mut frames) =
+            tokio::sync::mpsc::channel::<Option<Vec<u8>>>(MAX_QUEUED_FRAMES);
+        let writer = tokio::spawn(async move {
+            while let Some(Some(frame)) = frames.recv().await {
+                if let Err(e) = write_half.write_all(&frame).await {
+                    debug!("Stopped writing frames: {e}");
+                    return;
+                }
+            }
+
+            let _ = write_half.shutdown().await;
+        });
+
+        let session_frame_sender = frame_sender.clone();
+        let session = BdSession::with_frame_sink(
+            session_stream,
+            Arc::new(
+                move |frame| match session_frame_sender.try_send(Some(frame)) {
+                    Ok(()) => Ok(()),
+                    Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                        warn!("Closing connection that does not read its responses");
+                        let _ = sink_stream.shutdown(Shutdown::Both);
+                        Err(io::Error::other(
+                            "Too many responses are waiting to be sent",
+                        ))
+                    }
+                    Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                        Err(io::Error::from(ErrorKind::BrokenPipe))
+                    }
+                },
+            ),
+        );
+
+        Ok(AsyncConnection {
+            session,
+            read_half,
+            frame_sender,
+            writer,
+        })
+    }
+
+    /// Handles the messages of a connection until it ends.
+    /// The session is passed along to blocking handlers, so it is returned once the connection ended.
+    #[cfg(feature = "tokio")]
+    async fn handle_connection_async(
+        mut session: BdSession,
+        stream: &mut tokio::io::BufReader<tokio::net::tcp::OwnedReadHalf>,
+        message_handler: &TokioMessageHandler,
+        session_manager: &SessionManager,
+        crypto_metrics: &Arc<MessageCryptoMetrics>,
+        limiter: &ConnectionLimiter,
+    ) -> (BdSession, Result<(), Box<dyn Error>>) {
+        // Messages are read from and decrypted in this buffer without further copies
+        let mut frame = Vec::new();
+        let mut rate_limiter = limiter.message_rate_limiter();
+
+        loop {
+            match Self::read_frame_async(
+                &mut session,
+                stream,
+                &mut frame,
+                message_handler,
+                &mut rate_limiter,
+            )
+            .await
+            {
+                Ok(ReadFrame::Closed) => return (session, Ok(())),
+                Ok(ReadFrame::Handled) => continue,
+                Ok(ReadFrame::Message) => {}
+                Err(e) => return (session, Err(e)),
+            }
+
+            let was_authenticated = session.authentication().is_some();
+            let handled;
+            (session, frame, handled) =
+                Self::dispatch_message(session, frame, message_handler, crypto_metrics).await;
+            if let Err(e) = handled {
+                return (session, Err(e));
+            }
+
+            if !was_authenticated && session.authentication().is_some() {
+                session_manager.authenticate_session(&session);
+            }
+        }
+    }
+
+    /// Reads the next frame of a connection and answers it unless it is a message to handle.
+    #[cfg(feature = "tokio")]
+    async fn read_frame_async(
+        session: &mut BdSession,
+        stream: &mut tokio::io::BufReader<tokio::net::tcp::OwnedReadHalf>,
+        frame: &mut Vec<u8>,
+        message_handler: &TokioMessageHandler,
+        rate_limiter: &mut MessageRateLimiter,
+    ) -> Result<ReadFrame, Box<dyn Error>> {
+        let mut b: [u8; 4] = [0; 4];
+        let len = stream.read(&mut b).await?;
+        if len == 0 {
+            return Ok(ReadFrame::Closed);
+        }
+
+        ensure!(len == 4, IncompleteMessageHeaderSnafu {});
+        session.record_activity();
+        let header = u32::from_le_bytes(b);
+
+        match header {
+            0 => {
+                debug!("Ping");
+                session.write_frame(&0u32.to_le_bytes())?;
+            }
+            200 => {
+                let available_buffer_size = stream.read_u32_le().await?;
+                debug!("Buffer available: {available_buffer_size}");
+            }
+            _ => {
+                ensure!(
+                    header <= MAX_MESSAGE_SIZE,
+                    MessageTooLargeSnafu { msg_size: header }
+                );
+
+                debug!("Message with size {header}");
+                frame.clear();
+                frame.shrink_to(RETAINED_FRAME_BUFFER_SIZE);
+                frame.resize(header as usize, 0);
+                stream.read_exact(frame.as_mut_slice()).await?;
+                if Self::check_message_rate(session, rate_limiter)? {
+                    return Ok(ReadFrame::Message);
+                }
+
+                match message_handler {
+                    TokioMessageHandler::Async(handler) => {
+                        handler.reject_throttled(session).await?
+                    }
+                    TokioMessageHandler::Blocking(handler) => handler.reject_throttled(session)?,
+                }
+            }
+        }
+
+        Ok(ReadFrame::Handled)
+    }
+
+    /// Handles the message in the frame.
+    /// The session and frame are returned to be used for the next messages.
+    #[cfg(feature = "tokio")]
+    async fn dispatch_message(
+        mut session: BdSession,
+        mut frame: Vec<u8>,
+        message_handler: &TokioMessageHandler,
+        crypto_metrics: &Arc<MessageCryptoMetrics>,
+    ) -> (BdSession, Vec<u8>, Result<(), Box<dyn Error>>) {
+        match message_handler {
+            TokioMessageHandler::Async(handler) => {
+                let message = match Self::read_message(&mut session, &mut frame, crypto_metrics) {
+                    Ok(message) => message,
+                    Err(e) => return (session, frame, Err(e)),
+                };
+                let handled = handler.handle_message(&mut session, message).await;
+
+                (session, frame, handled)
+            }
+            TokioMessageHandler::Blocking(handler) => {
+                let handler = Arc::clone(handler);
+                let crypto_metrics = Arc::clone(crypto_metrics);
+                let blocking = tokio::task::spawn_blocking(move || {
+                    let handled = Self::read_message(&mut session, &mut frame, &crypto_metrics)
+                        .and_then(|message| handler.handle_message(&mut session, message))
+                        .map_err(Self::into_sendable_error);
+
+                    (session, frame, handled)
+                });
+
+                match blocking.await {
+                    Ok((session, frame, handled)) => {
+                        (session, frame, handled.map_err(|e| e as Box<dyn Error>))
+                    }
+                    Err(e) => std::panic::resume_unwind(e.into_panic()),
+                }
+            }
+        }
+    }
+
+    /// Keeps io errors intact to be logged like errors of the connection itself,
+    /// other errors of handlers are only kept as their message.
+    #[cfg(feature = "tokio")]
+    fn into_sendable_error(error: Box<dyn Error>) -> Box<dyn Error + Send + Sync> {
+        match error.downcast::<io::Error>() {
+            Ok(io_error) => io_error,
+            Err(error) => error.to_string().into(),
+        }
+    }
+
+    fn handle_connection(
+        session: &mut BdSession,
+        message_handler: &dyn BdMessageHandler,
+        session_manager: &SessionManager,
+        crypto_metrics: &MessageCryptoMetrics,
+        limiter: &ConnectionLimiter,
+    ) {
         let connection_loop = |session: &mut BdSession| -> Result<(), Box<dyn Error>> {
+            // Messages are read from and decrypted in this buffer without further copies
+            let mut frame = Vec::new();
+            let mut rate_limiter = limiter.message_rate_limiter();
+
             loop {
                 let mut b: [u8; 4] = [0; 4];
                 let len = session.read(&mut b)?;
@@ -114,12 +613,13 @@ impl BdSocket {
                 }
 
                 ensure!(len == 4, IncompleteMessageHeaderSnafu {});
+                session.record_activity();
                 let header = u32::from_le_bytes(b);
 
                 match header {
                     0 => {
                         debug!("Ping");
-                        session.write_u32::<LittleEndian>(0)?;
+                        session.write_frame(&0u32.to_le_bytes())?;
                     }
                     200 => {
                         let available_buffer_size = session.read_u32::<LittleEndian>()?;
@@ -132,16 +632,71 @@ impl BdSocket {
                         );
 
                         debug!("Message with size {header}");
-                        let mut msg = vec![0; header as usize];
-                        session.read_exact(msg.as_mut_slice())?;
-                        let message = BdMessage::new(session, msg)?;
+                        frame.clear();
+                        frame.shrink_to(RETAINED_FRAME_BUFFER_SIZE);
+                        frame.resize(header as usize, 0);
+                        session.read_exact(frame.as_mut_slice())?;
+                        if !Self::check_message_rate(session, &mut rate_limiter)? {
+                            message_handler.reject_throttled(session)?;
+                            continue;
+                        }
+                        let message = Self::read_message(session, &mut frame, crypto_metrics)?;
+                        let was_authenticated = session.authentication().is_some();
                         message_handler.handle_message(session, message)?;
+
+                        if !was_authenticated && session.authentication().is_some() {
+                            session_manager.authenticate_session(session);
+                        }
                     }
                 }
             }
         };
 
-        let connection_result = connection_loop(session);
+        Self::log_connection_result(connection_loop(session));
+    }
+
+    /// Returns whether the next message of the session may be handled,
+    /// or fails if the client was throttled so often that it should be dropped.
+    fn check_message_rate(
+        session: &BdSession,
+        rate_limiter: &mut MessageRateLimiter,
+    ) -> Result<bool, BdSocketError> {
+        match rate_limiter.acquire() {
+            RateDecision::Allowed => Ok(true),
+            RateDecision::Throttled => {
+                if rate_limiter.throttled_count() == 1 {
+                    warn!("Throttling messages of session {}", session.id);
+                } else {
+                    debug!("Throttled message of session {}", session.id);
+                }
+
+                Ok(false)
+            }
+            RateDecision::Exceeded { throttled_count } => {
+                ThrottledTooOftenSnafu { throttled_count }.fail()
+            }
+        }
+    }
+
+    /// Reads a message from its frame and switches to the cipher the client used for it.
+    fn read_message<'a>(
+        session: &mut BdSession,
+        frame: &'a mut [u8],
+        crypto_metrics: &MessageCryptoMetrics,
+    ) -> Result<BdMessage<'a>, Box<dyn Error>> {
+        let message = BdMessage::new(session, frame)
+            .inspect_err(|e| Self::record_crypto_failure(session, crypto_metrics, e.as_ref()))?;
+        if let Some(cipher) = message.cipher() {
+            if cipher != session.session_cipher() {
+                debug!("Switching session cipher to {cipher:?}");
+                session.set_session_cipher(cipher);
+            }
+        }
+
+        Ok(message)
+    }
+
+    fn log_connection_result(connection_result: Result<(), Box<dyn Error>>) {
         if let Err(e) = connection_result {
             if let Some(e0) = e.downcast_ref::<io::Error>() {
                 match e0.kind() {
@@ -153,4 +708,151 @@ impl BdSocket {
             }
         }
     }
+
+    fn record_crypto_failure(
+        session: &BdSession,
+        crypto_metrics: &MessageCryptoMetrics,
+        error: &(dyn Error + 'static),
+    ) {
+        let Some(message_error) = error.downcast_ref::<BdMessageError>() else {
+            return;
+        };
+
+        let failure = message_error.failure();
+        crypto_metrics.record(failure);
+
+        match session.authentication().zip(session.session_key()) {
+            Some((authentication, session_key)) => warn!(
+                "Failed to read message ({failure:?}) of user {} (key_id={:#010x}): {message_error}",
+                authentication.user_id,
+                key_fingerprint(&session_key)
+            ),
+            None => warn!("Failed to read message ({failure:?}) of unauthenticated session: {message_error}"),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    /// Echoes the payload of every message after yielding to the runtime once.
+    struct EchoHandler;
+
+    impl AsyncBdMessageHandler for EchoHandler {
+        fn handle_message<'a>(
+            &'a self,
+            session: &'a mut BdSession,
+            message: BdMessage<'a>,
+        ) -> BdMessageHandlerFuture<'a> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                let payload = message.reader.remaining_data()?;
+                let mut frame = (payload.len() as u32).to_le_bytes().to_vec();
+                frame.extend_from_slice(payload);
+                session.write_frame(&frame)?;
+
+                Ok(())
+            })
+        }
+    }
+
+    /// Echoes the payload of every message while blocking the thread it runs on.
+    struct BlockingEchoHandler;
+
+    impl BdMessageHandler for BlockingEchoHandler {
+        fn handle_message(
+            &self,
+            session: &mut BdSession,
+            message: BdMessage,
+        ) -> Result<(), Box<dyn Error>> {
+            thread::sleep(std::time::Duration::from_millis(10));
+            let payload = message.reader.remaining_data()?;
+            let mut frame = (payload.len() as u32).to_le_bytes().to_vec();
+            frame.extend_from_slice(payload);
+            session.write_frame(&frame)?;
+
+            Ok(())
+        }
+    }
+
+    fn read_frame(stream: &mut TcpStream) -> Vec<u8> {
+        let len = stream.read_u32::<LittleEndian>().unwrap();
+        let mut frame = vec![0u8; len as usize];
+        stream.read_exact(&mut frame).unwrap();
+
+        frame
+    }
+
+    #[test]
+    fn handles_connections_as_tokio_tasks() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        let mut socket = BdSocket::new(0).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let _guard = runtime.enter();
+        socket.run_tokio(Arc::new(EchoHandler));
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(&0u32.to_le_bytes()).unwrap();
+        assert_eq!(stream.read_u32::<LittleEndian>().unwrap(), 0);
+
+        stream.write_all(&4u32.to_le_bytes()).unwrap();
+        stream.write_all(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(read_frame(&mut stream), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn blocking_handlers_run_on_current_thread_runtime() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        let mut socket = BdSocket::new(0).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let join = {
+            let _guard = runtime.enter();
+            socket.run_tokio_blocking(Arc::new(BlockingEchoHandler))
+        };
+        let client = runtime.spawn_blocking(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            for i in 0..3u8 {
+                stream.write_all(&4u32.to_le_bytes()).unwrap();
+                stream.write_all(&[0, i, 2, 3]).unwrap();
+                assert_eq!(read_frame(&mut stream), vec![i, 2, 3]);
+            }
+        });
+
+        runtime.block_on(client).unwrap();
+        join.abort();
+    }
+
+    #[test]
+    fn shutdown_closes_tokio_connections() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        let mut socket = BdSocket::new(0).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let shutdown = socket.shutdown_handle();
+        let join = {
+            let _guard = runtime.enter();
+            socket.run_tokio(Arc::new(EchoHandler))
+        };
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(&4u32.to_le_bytes()).unwrap();
+        stream.write_all(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(read_frame(&mut stream), vec![1, 2, 3]);
+
+        shutdown.shutdown();
+        assert!(shutdown.wait_timeout(std::time::Duration::from_secs(5)));
+        assert_eq!(stream.read(&mut [0u8; 1]).unwrap(), 0);
+        assert!(runtime.block_on(join).unwrap().is_ok());
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Limits that protect a [`BdSocket`](crate::networking::bd_socket::BdSocket)
+/// from peers trying to exhaust its resources.
+/// Nothing is limited by default.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct ConnectionLimits {
+    max_connections_per_ip: Option<usize>,
+    max_messages_per_second: Option<u32>,
+    message_burst: Option<u32>,
+    max_throttled_messages: Option<u32>,
+}
+
+impl ConnectionLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuses further connections of an IP address while it has this many connections open.
+    pub fn with_max_connections_per_ip(mut self, max_connections: usize) -> Self {
+        self.max_connections_per_ip = Some(max_connections);
+        self
+    }
+
+    /// Throttles connections sending more messages per second on average.
+    /// Up to `burst` messages can be sent at once after being quiet for a while.
+    /// Pings are not limited.
+    pub fn with_message_rate(mut self, max_messages_per_second: u32, burst: u32) -> Self {
+        self.max_messages_per_second = Some(max_messages_per_second);
+        self.message_burst = Some(burst.max(1));
+        self
+    }
+
+    /// Drops connections once this many of their messages were throttled.
+    pub fn with_max_throttled_messages(mut self, max_throttled_messages: u32) -> Self {
+        self.max_throttled_messages = Some(max_throttled_messages);
+        self
+    }
+
+    pub fn max_connections_per_ip(&self) -> Option<usize> {
+        self.max_connections_per_ip
+    }
+
+    pub fn max_messages_per_second(&self) -> Option<u32> {
+        self.max_messages_per_second
+    }
+
+    pub fn max_throttled_messages(&self) -> Option<u32> {
+        self.max_throttled_messages
+    }
+}
+
+/// Applies the [`ConnectionLimits`] to the connections of a socket.
+pub(crate) struct ConnectionLimiter {
+    limits: ConnectionLimits,
+    connections_per_ip: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl ConnectionLimiter {
+    pub(crate) fn new(limits: ConnectionLimits) -> Self {
+        ConnectionLimiter {
+            limits,
+            connections_per_ip: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a new connection of the IP address until the returned guard is dropped,
+    /// or returns `None` if the address already has too many connections.
+    pub(crate) fn try_connect(self: &Arc<Self>, ip: IpAddr) -> Option<IpConnectionGuard> {
+        let mut connections_per_ip = self.connections_per_ip.lock().unwrap();
+        let connections = connections_per_ip.entry(ip).or_default();
+        if self
+            .limits
+            .max_connections_per_ip
+            .is_some_and(|max_connections| *connections >= max_connections)
+        {
+            return None;
+        }
+
+        *connections += 1;
+
+        Some(IpConnectionGuard {
+            limiter: self.clone(),
+            ip,
+        })
+    }
+
+    /// Creates the rate limiter for the messages of a new connection.
+    pub(crate) fn message_rate_limiter(&self) -> MessageRateLimiter {
+        let burst = self.limits.message_burst.unwrap_or(0);
+
+        MessageRateLimiter {
+            messages_per_second: self.limits.max_messages_per_second,
+            burst: burst as f64,
+            tokens: burst as f64,
+            refilled_at: Instant::now(),
+            throttled_count: 0,
+            max_throttled_messages: self.limits.max_throttled_messages,
+        }
+    }
+}
+
+/// Frees the slot of a connection of an IP address when dropped.
+pub(crate) struct IpConnectionGuard {
+    limiter: Arc<ConnectionLimiter>,
+    ip: IpAddr,
+}
+
+impl Drop for IpConnectionGuard {
+    fn drop(&mut self) {
+        let mut connections_per_ip = self.limiter.connections_per_ip.lock().unwrap();
+        if let Some(connections) = connections_per_ip.get_mut(&self.ip) {
+            *connections -= 1;
+            if *connections == 0 {
+                connections_per_ip.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Whether a message of a connection may be handled.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum RateDecision {
+    Allowed,
+    Throttled,
+    /// The connection was throttled too often and should be dropped.
+    Exceeded {
+        throttled_count: u32,
+    },
+}
+
+/// A token bucket limiting the messages of a single connection.
+pub(crate) struct MessageRateLimiter {
+    messages_per_second: Option<u32>,
+    burst: f64,
+    tokens: f64,
+    refilled_at: Instant,
+    throttled_count: u32,
+    max_throttled_messages: Option<u32>,
+}
+
+impl MessageRateLimiter {
+    pub(crate) fn acquire(&mut self) -> RateDecision {
+        self.acquire_at(Instant::now())
+    }
+
+    fn acquire_at(&mut self, now: Instant) -> RateDecision {
+        let Some(messages_per_second) = self.messages_per_second else {
+            return RateDecision::Allowed;
+        };
+
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * messages_per_second as f64).min(self.burst);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return RateDecision::Allowed;
+        }
+
+        self.throttled_count += 1;
+        match self.max_throttled_messages {
+            Some(max_throttled) if self.throttled_count > max_throttled => RateDecision::Exceeded {
+                throttled_count: self.throttled_count,
+            },
+            _ => RateDecision::Throttled,
+        }
+    }
+
+    pub(crate) fn throttled_count(&self) -> u32 {
+        self.throttled_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    #[test]
+    fn connections_per_ip_are_limited_until_closed() {
+        let limiter = Arc::new(ConnectionLimiter::new(
+            ConnectionLimits::new().with_max_connections_per_ip(2),
+        ));
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let other_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+        let first = limiter.try_connect(ip).unwrap();
+        let _second = limiter.try_connect(ip).unwrap();
+        assert!(limiter.try_connect(ip).is_none());
+        assert!(limiter.try_connect(other_ip).is_some());
+
+        drop(first);
+        assert!(limiter.try_connect(ip).is_some());
+    }
+
+    #[test]
+    fn messages_are_throttled_and_connections_dropped_eventually() {
+        let limiter = ConnectionLimiter::new(
+            ConnectionLimits::new()
+                .with_message_rate(10, 2)
+                .with_max_throttled_messages(1),
+        );
+        let mut rate_limiter = limiter.message_rate_limiter();
+        let start = rate_limiter.refilled_at;
+
+        assert_eq!(rate_limiter.acquire_at(start), RateDecision::Allowed);
+        assert_eq!(rate_limiter.acquire_at(start), RateDecision::Allowed);
+        assert_eq!(rate_limiter.acquire_at(start), RateDecision::Throttled);
+
+        // A message becomes available every 100ms
+        let later = start + Duration::from_millis(100);
+        assert_eq!(rate_limiter.acquire_at(later), RateDecision::Allowed);
+        assert_eq!(
+            rate_limiter.acquire_at(later),
+            RateDecision::Exceeded { throttled_count: 2 }
+        );
+    }
+
+    #[test]
+    fn nothing_is_limited_by_default() {
+        let limiter = Arc::new(ConnectionLimiter::new(ConnectionLimits::default()));
+        let mut rate_limiter = limiter.message_rate_limiter();
+
+        for _ in 0..1000 {
+            assert_eq!(rate_limiter.acquire(), RateDecision::Allowed);
+        }
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let _guards: Vec<_> = (0..1000)
+            .map(|_| limiter.try_connect(ip).unwrap())
+            .collect();
+    }
+}
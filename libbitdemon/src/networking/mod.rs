@@ -1,4 +1,7 @@
 pub mod bd_server;
 pub mod bd_session;
 pub mod bd_socket;
+pub mod connection_limits;
+pub mod scratch_store;
 pub mod session_manager;
+pub mod socket_shutdown;
@@ -0,0 +1,138 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+struct ScratchEntry {
+    value: Box<dyn Any + Send>,
+    expires_at: Option<Instant>,
+}
+
+impl ScratchEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Short-lived state that handlers and services can attach to a session in between tasks,
+/// like pending challenges or invite nonces.
+///
+/// Entries can optionally expire after a certain duration.
+/// All entries are dropped together with the session they belong to.
+/// Callbacks registered for unregistering sessions can still access them.
+#[derive(Default)]
+pub struct ScratchStore {
+    entries: RefCell<HashMap<String, ScratchEntry>>,
+}
+
+impl ScratchStore {
+    pub fn new() -> ScratchStore {
+        ScratchStore {
+            entries: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Stores a value that stays available until it is removed or the session ends.
+    pub fn insert<T: Any + Send>(&self, key: impl Into<String>, value: T) {
+        self.insert_entry(key.into(), Box::new(value), None);
+    }
+
+    /// Stores a value that is discarded after the specified duration.
+    pub fn insert_with_ttl<T: Any + Send>(&self, key: impl Into<String>, value: T, ttl: Duration) {
+        self.insert_entry(key.into(), Box::new(value), Some(Instant::now() + ttl));
+    }
+
+    /// Retrieves a copy of the value stored for the key.
+    /// Returns `None` if there is no such value, it expired or it is of a different type.
+    pub fn get<T: Any + Send + Clone>(&self, key: &str) -> Option<T> {
+        self.evict_expired();
+
+        self.entries
+            .borrow()
+            .get(key)
+            .and_then(|entry| entry.value.downcast_ref::<T>())
+            .cloned()
+    }
+
+    /// Removes the value stored for the key and returns it.
+    /// Returns `None` if there is no such value, it expired or it is of a different type.
+    /// A value of a different type is left untouched.
+    pub fn take<T: Any + Send>(&self, key: &str) -> Option<T> {
+        self.evict_expired();
+
+        let mut entries = self.entries.borrow_mut();
+        if !entries.get(key)?.value.is::<T>() {
+            return None;
+        }
+
+        entries
+            .remove(key)
+            .and_then(|entry| entry.value.downcast::<T>().ok())
+            .map(|value| *value)
+    }
+
+    /// Removes the value stored for the key regardless of its type.
+    /// Returns whether a value was removed.
+    pub fn remove(&self, key: &str) -> bool {
+        self.entries.borrow_mut().remove(key).is_some()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.evict_expired();
+
+        self.entries.borrow().contains_key(key)
+    }
+
+    fn insert_entry(&self, key: String, value: Box<dyn Any + Send>, expires_at: Option<Instant>) {
+        self.entries
+            .borrow_mut()
+            .insert(key, ScratchEntry { value, expires_at });
+    }
+
+    fn evict_expired(&self) {
+        let now = Instant::now();
+
+        self.entries
+            .borrow_mut()
+            .retain(|_, entry| !entry.is_expired(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_stored_values_of_matching_type() {
+        let store = ScratchStore::new();
+        store.insert("nonce", 1337u64);
+
+        assert_eq!(store.get::<u64>("nonce"), Some(1337u64));
+        assert_eq!(store.get::<u32>("nonce"), None);
+        assert_eq!(store.take::<u32>("nonce"), None);
+        assert_eq!(store.take::<u64>("nonce"), Some(1337u64));
+        assert!(!store.contains_key("nonce"));
+    }
+
+    #[test]
+    fn discards_expired_values() {
+        let store = ScratchStore::new();
+        store.insert_with_ttl("challenge", String::from("abc"), Duration::ZERO);
+        store.insert_with_ttl("invite", String::from("def"), Duration::from_secs(60));
+
+        assert_eq!(store.get::<String>("challenge"), None);
+        assert_eq!(store.get::<String>("invite"), Some(String::from("def")));
+    }
+
+    #[test]
+    fn replaces_values_with_same_key() {
+        let store = ScratchStore::new();
+        store.insert("key", 1u8);
+        store.insert("key", String::from("value"));
+
+        assert_eq!(store.get::<u8>("key"), None);
+        assert_eq!(store.get::<String>("key"), Some(String::from("value")));
+        assert!(store.remove("key"));
+        assert!(!store.remove("key"));
+    }
+}
@@ -1,13 +1,20 @@
 use crate::networking::bd_session::{BdSession, SessionId};
-use log::info;
+use log::{info, warn};
+use std::collections::HashMap;
 use std::sync::Mutex;
+use std::time::Duration;
 
 type OnSessionCallback = dyn FnMut(&BdSession) + Sync + Send;
 
 pub struct SessionManager {
     session_id_counter: Mutex<SessionId>,
     register_cb: Mutex<Vec<Box<OnSessionCallback>>>,
+    authenticated_cb: Mutex<Vec<Box<OnSessionCallback>>>,
     unregister_cb: Mutex<Vec<Box<OnSessionCallback>>>,
+    /// Detached handles of all authenticated sessions, so that they can be sent messages.
+    authenticated_sessions: Mutex<HashMap<SessionId, BdSession>>,
+    /// Detached handles of all registered sessions, so that idle ones can be closed.
+    registered_sessions: Mutex<HashMap<SessionId, BdSession>>,
 }
 
 impl Default for SessionManager {
@@ -21,7 +28,10 @@ impl SessionManager {
         SessionManager {
             session_id_counter: Mutex::new(0),
             register_cb: Mutex::new(vec![]),
+            authenticated_cb: Mutex::new(vec![]),
             unregister_cb: Mutex::new(vec![]),
+            authenticated_sessions: Mutex::new(HashMap::new()),
+            registered_sessions: Mutex::new(HashMap::new()),
         }
     }
 
@@ -38,6 +48,16 @@ impl SessionManager {
             peer_addr.port()
         );
 
+        match session.detach() {
+            Ok(handle) => {
+                self.registered_sessions
+                    .lock()
+                    .unwrap()
+                    .insert(session.id, handle);
+            }
+            Err(e) => warn!("Failed to keep handle of session {}: {e}", session.id),
+        }
+
         self.register_cb
             .lock()
             .unwrap()
@@ -45,9 +65,34 @@ impl SessionManager {
             .for_each(|cb| cb(session));
     }
 
+    /// Notifies about a session that just got authenticated.
+    pub fn authenticate_session(&self, session: &BdSession) {
+        match session.detach() {
+            Ok(handle) => {
+                self.authenticated_sessions
+                    .lock()
+                    .unwrap()
+                    .insert(session.id, handle);
+            }
+            Err(e) => warn!("Failed to keep handle of session {}: {e}", session.id),
+        }
+
+        self.authenticated_cb
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .for_each(|cb| cb(session));
+    }
+
     pub fn unregister_session(&self, session: &BdSession) {
         info!("Session ended");
 
+        self.authenticated_sessions
+            .lock()
+            .unwrap()
+            .remove(&session.id);
+        self.registered_sessions.lock().unwrap().remove(&session.id);
+
         self.unregister_cb
             .lock()
             .unwrap()
@@ -55,6 +100,54 @@ impl SessionManager {
             .for_each(|cb| cb(session));
     }
 
+    /// Creates detached handles to all authenticated sessions of the user,
+    /// e.g. to notify them with push messages.
+    pub fn sessions_of_user(&self, user_id: u64) -> Vec<BdSession> {
+        self.authenticated_sessions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|session| {
+                session
+                    .authentication()
+                    .is_some_and(|authentication| authentication.user_id == user_id)
+            })
+            .filter_map(|session| session.detach().ok())
+            .collect()
+    }
+
+    /// Closes all sessions whose clients did not send anything for at least the idle timeout,
+    /// e.g. because they crashed without closing their connection.
+    /// The sessions are unregistered once their connections noticed being closed,
+    /// but are not considered by later calls anymore.
+    /// Returns how many sessions were closed by this call.
+    pub fn evict_idle_sessions(&self, idle_timeout: Duration) -> usize {
+        let mut registered_sessions = self.registered_sessions.lock().unwrap();
+
+        let mut closed_count = 0;
+        registered_sessions.retain(|_, session| {
+            let idle_time = session.idle_time();
+            if idle_time < idle_timeout {
+                return true;
+            }
+
+            match session.close() {
+                Ok(()) => {
+                    info!(
+                        "Closing session {} after being idle for {idle_time:?}",
+                        session.id
+                    );
+                    closed_count += 1;
+                }
+                Err(e) => warn!("Failed to close idle session {}: {e}", session.id),
+            }
+
+            false
+        });
+
+        closed_count
+    }
+
     pub fn on_session_registered<F>(&self, cb: F)
     where
         F: FnMut(&BdSession) + Sync + Send + 'static,
@@ -62,6 +155,13 @@ impl SessionManager {
         self.register_cb.lock().unwrap().push(Box::from(cb));
     }
 
+    pub fn on_session_authenticated<F>(&self, cb: F)
+    where
+        F: FnMut(&BdSession) + Sync + Send + 'static,
+    {
+        self.authenticated_cb.lock().unwrap().push(Box::from(cb));
+    }
+
     pub fn on_session_unregistered<F>(&self, cb: F)
     where
         F: FnMut(&BdSession) + Sync + Send + 'static,
@@ -69,3 +169,58 @@ impl SessionManager {
         self.unregister_cb.lock().unwrap().push(Box::from(cb));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authentication::SessionAuthentication;
+    use crate::domain::title::Title;
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn authenticated_sessions_can_be_found_until_unregistered() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut session = BdSession::new(stream);
+        let session_manager = SessionManager::new();
+
+        session_manager.register_session(&mut session);
+        session.set_authentication(SessionAuthentication {
+            user_id: 5,
+            username: "user".to_string(),
+            session_key: [0; 24].into(),
+            title: Title::T6Pc,
+            clock_skew: None,
+            anonymous: false,
+        });
+        session_manager.authenticate_session(&session);
+
+        assert_eq!(session_manager.sessions_of_user(5).len(), 1);
+        assert!(session_manager.sessions_of_user(6).is_empty());
+
+        session_manager.unregister_session(&session);
+        assert!(session_manager.sessions_of_user(5).is_empty());
+    }
+
+    #[test]
+    fn idle_sessions_are_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut client, _) = listener.accept().unwrap();
+        let mut session = BdSession::new(stream);
+        let session_manager = SessionManager::new();
+        session_manager.register_session(&mut session);
+
+        assert_eq!(
+            session_manager.evict_idle_sessions(Duration::from_secs(60)),
+            0
+        );
+        assert_eq!(session_manager.evict_idle_sessions(Duration::ZERO), 1);
+        assert_eq!(client.read(&mut [0u8; 1]).unwrap(), 0);
+        assert_eq!(session_manager.evict_idle_sessions(Duration::ZERO), 0);
+
+        session_manager.unregister_session(&session);
+        assert_eq!(session_manager.evict_idle_sessions(Duration::ZERO), 0);
+    }
+}
@@ -0,0 +1,263 @@
+use log::{debug, info};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct Connections {
+    next_id: u64,
+    /// Handles to the open connections, to stop reading further messages from them.
+    streams: Vec<(u64, TcpStream)>,
+}
+
+struct ShutdownState {
+    requested: AtomicBool,
+    connections: Mutex<Connections>,
+    drained: Condvar,
+    /// Address the socket accepts connections on, to wake it up when shutting down.
+    local_addr: SocketAddr,
+}
+
+/// Shuts down a [`BdSocket`](crate::networking::bd_socket::BdSocket) gracefully.
+///
+/// The socket stops accepting connections and every connection ends after its current message was handled.
+/// Responses are written completely before the connections are closed.
+/// The handle can be cloned and used from any thread.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    state: Arc<ShutdownState>,
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new(local_addr: SocketAddr) -> Self {
+        ShutdownHandle {
+            state: Arc::new(ShutdownState {
+                requested: AtomicBool::new(false),
+                connections: Mutex::new(Connections::default()),
+                drained: Condvar::new(),
+                local_addr,
+            }),
+        }
+    }
+
+    /// Requests the shutdown without waiting for it to complete.
+    pub fn shutdown(&self) {
+        if self.state.requested.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let connections = self.state.connections.lock().unwrap();
+        info!(
+            "Shutting down socket on port {} with {} connections",
+            self.state.local_addr.port(),
+            connections.streams.len()
+        );
+        for (_, stream) in &connections.streams {
+            Self::stop_reading(stream);
+        }
+        drop(connections);
+
+        // Accepting is blocking, so it is woken up by a connection of its own
+        let wake_addr = SocketAddr::from(([127, 0, 0, 1], self.state.local_addr.port()));
+        if let Err(e) = TcpStream::connect(wake_addr) {
+            debug!("Failed to wake up socket for shutdown: {e}");
+        }
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.state.requested.load(Ordering::SeqCst)
+    }
+
+    /// The number of connections that did not end yet.
+    pub fn num_connections(&self) -> usize {
+        self.state.connections.lock().unwrap().streams.len()
+    }
+
+    /// Blocks until all connections ended.
+    pub fn wait(&self) {
+        let connections = self.state.connections.lock().unwrap();
+        let _connections = self
+            .state
+            .drained
+            .wait_while(connections, |connections| !connections.streams.is_empty())
+            .unwrap();
+    }
+
+    /// Blocks until all connections ended or the timeout elapsed.
+    /// Returns whether all connections ended.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut connections = self.state.connections.lock().unwrap();
+        while !connections.streams.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+
+            connections = self
+                .state
+                .drained
+                .wait_timeout(connections, remaining)
+                .unwrap()
+                .0;
+        }
+
+        true
+    }
+
+    /// Closes all connections that did not end yet, e.g. after waiting for them timed out.
+    /// Messages that are still being handled can no longer be answered.
+    pub fn close_connections(&self) {
+        let connections = self.state.connections.lock().unwrap();
+        if !connections.streams.is_empty() {
+            info!(
+                "Closing {} remaining connections on port {}",
+                connections.streams.len(),
+                self.state.local_addr.port()
+            );
+        }
+        for (_, stream) in &connections.streams {
+            // The connection may already be closed by the client
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Keeps track of a connection until the returned guard is dropped.
+    /// Connections that start while shutting down do not read any messages.
+    pub(crate) fn track(&self, stream: TcpStream) -> ConnectionGuard {
+        let mut connections = self.state.connections.lock().unwrap();
+        if self.is_shutdown() {
+            Self::stop_reading(&stream);
+        }
+
+        let id = connections.next_id;
+        connections.next_id += 1;
+        connections.streams.push((id, stream));
+
+        ConnectionGuard {
+            state: self.state.clone(),
+            id,
+        }
+    }
+
+    fn stop_reading(stream: &TcpStream) {
+        // Pending reads return as if the client closed the connection
+        if let Err(e) = stream.shutdown(Shutdown::Read) {
+            debug!("Failed to stop reading from connection: {e}");
+        }
+    }
+}
+
+/// Closes the connection and marks it as ended when dropped,
+/// even if detached handles of its session still exist.
+pub(crate) struct ConnectionGuard {
+    state: Arc<ShutdownState>,
+    id: u64,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut connections = self.state.connections.lock().unwrap();
+        if let Some(index) = connections
+            .streams
+            .iter()
+            .position(|(id, _)| *id == self.id)
+        {
+            let (_, stream) = connections.streams.swap_remove(index);
+            // The connection may already be closed by the client
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        if connections.streams.is_empty() {
+            self.state.drained.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messaging::bd_message::BdMessage;
+    use crate::networking::bd_session::BdSession;
+    use crate::networking::bd_socket::{BdMessageHandler, BdSocket};
+    use std::error::Error;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Replies with a single byte once it is allowed to.
+    struct SlowHandler {
+        proceed: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl BdMessageHandler for SlowHandler {
+        fn handle_message(
+            &self,
+            session: &mut BdSession,
+            _message: BdMessage,
+        ) -> Result<(), Box<dyn Error>> {
+            self.proceed.lock().unwrap().recv()?;
+            session.write_frame(&[1, 0, 0, 0, 42])?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shutdown_waits_for_handlers_and_closes_connections() {
+        let (proceed, receiver) = mpsc::channel();
+        let mut socket = BdSocket::new(0).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let shutdown = socket.shutdown_handle();
+        let join = socket.run_async(Arc::new(SlowHandler {
+            proceed: Mutex::new(receiver),
+        }));
+
+        let mut idle = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let mut busy = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        busy.write_all(&[1, 0, 0, 0, 0]).unwrap();
+        while shutdown.num_connections() < 2 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        shutdown.shutdown();
+        assert!(!shutdown.wait_timeout(Duration::from_millis(50)));
+        assert_eq!(idle.read(&mut [0u8; 1]).unwrap(), 0);
+
+        proceed.send(()).unwrap();
+        assert!(shutdown.wait_timeout(Duration::from_secs(5)));
+        let mut reply = Vec::new();
+        busy.read_to_end(&mut reply).unwrap();
+        assert_eq!(reply, vec![1, 0, 0, 0, 42]);
+        assert!(join.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn remaining_connections_can_be_closed() {
+        let (proceed, receiver) = mpsc::channel();
+        let mut socket = BdSocket::new(0).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let shutdown = socket.shutdown_handle();
+        let join = socket.run_async(Arc::new(SlowHandler {
+            proceed: Mutex::new(receiver),
+        }));
+
+        let mut busy = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        busy.write_all(&[1, 0, 0, 0, 0]).unwrap();
+        while shutdown.num_connections() < 1 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        shutdown.shutdown();
+        assert!(!shutdown.wait_timeout(Duration::from_millis(50)));
+        shutdown.close_connections();
+        let mut reply = Vec::new();
+        let _ = busy.read_to_end(&mut reply);
+        assert!(reply.is_empty());
+
+        proceed.send(()).unwrap();
+        assert!(shutdown.wait_timeout(Duration::from_secs(5)));
+        assert!(join.join().unwrap().is_ok());
+    }
+}